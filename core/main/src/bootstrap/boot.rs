@@ -33,6 +33,7 @@ use super::{
     start_app_manager_step::StartAppManagerStep,
     start_communication_broker::{StartCommunicationBroker, StartOtherBrokers},
     start_fbgateway_step::FireboltGatewayStep,
+    start_service_launcher_step::StartServiceLauncherStep,
     start_ws_step::StartWsStep,
 };
 /// Starts up Ripple uses `PlatformState` to manage State
@@ -51,6 +52,7 @@ use super::{
 /// 6. [StartAppManagerStep] - Starts the App Manager and other supporting services
 /// 7. [StartOtherBrokers] - Start Other brokers if they are setup in endpoints for rules
 /// 8. [LoadDistributorValuesStep] - Loads the values from distributor like Session
+/// 9. [StartServiceLauncherStep] - Launches and supervises companion services from the device manifest
 /// 10. [StartWsStep] - Starts the Websocket to accept external and internal connections
 /// 11. [FireboltGatewayStep] - Starts the firebolt gateway and blocks the thread to keep it alive till interruption.
 
@@ -83,6 +85,8 @@ pub async fn boot(state: BootstrapState) -> RippleResponse {
     log_memory_usage("After-StartOtherBrokers");
     execute_step(LoadDistributorValuesStep, &bootstrap).await?;
     log_memory_usage("After-LoadDistributorValuesStep");
+    execute_step(StartServiceLauncherStep, &bootstrap).await?;
+    log_memory_usage("After-StartServiceLauncherStep");
     execute_step(FireboltGatewayStep, &bootstrap).await?;
     log_memory_usage("After-FireboltGatewayStep");
     Ok(())
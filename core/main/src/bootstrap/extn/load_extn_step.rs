@@ -18,15 +18,15 @@
 use std::thread;
 
 use ripple_sdk::{
-    api::manifest::extn_manifest::ExtnManifestEntry,
+    api::{firebolt::fb_telemetry::BootMilestoneType, manifest::extn_manifest::ExtnManifestEntry},
     async_trait::async_trait,
     extn::ffi::ffi_channel::load_channel_builder,
     framework::bootstrap::Bootstep,
-    log::{debug, info, warn},
+    log::{debug, error, info, warn},
     utils::error::RippleError,
 };
 
-use crate::state::bootstrap_state::BootstrapState;
+use crate::{service::telemetry_builder::TelemetryBuilder, state::bootstrap_state::BootstrapState};
 use ripple_sdk::libloading::Library;
 use std::ffi::OsStr;
 
@@ -70,6 +70,19 @@ impl LoadExtensionsStep {
         let extn_paths: Vec<(String, ExtnManifestEntry)> = manifest
             .extns
             .into_iter()
+            .filter(|f| {
+                let refused = f
+                    .symbols
+                    .iter()
+                    .any(|s| state.platform_state.extn_status_state.is_refused(&s.id));
+                if refused {
+                    error!(
+                        "Skipping extension {} with incompatible contract versions",
+                        f.path
+                    );
+                }
+                !refused
+            })
             .map(|f| {
                 (f.get_path(&default_path, &default_extn), f)
                 // TODO Add Resolution checks later on
@@ -128,6 +141,10 @@ impl Bootstep<BootstrapState> for LoadExtensionsStep {
                 }
             }
         }
+        TelemetryBuilder::send_boot_milestone(
+            &state.platform_state,
+            BootMilestoneType::ExtensionsUp,
+        );
 
         Ok(())
     }
@@ -18,7 +18,10 @@
 use ripple_sdk::framework::bootstrap::Bootstep;
 use ripple_sdk::{async_trait::async_trait, framework::RippleResponse};
 
+use crate::processor::entitlements_sync_processor::EntitlementsSyncProcessor;
 use crate::processor::main_context_processor::MainContextProcessor;
+use crate::service::activation_orchestrator::ActivationOrchestrator;
+use crate::service::data_governance::DataGovernance;
 use crate::state::bootstrap_state::BootstrapState;
 
 pub struct LoadDistributorValuesStep;
@@ -31,6 +34,7 @@ impl Bootstep<BootstrapState> for LoadDistributorValuesStep {
 
     async fn setup(&self, s: BootstrapState) -> RippleResponse {
         MainContextProcessor::remove_expired_and_inactive_entries(&s.platform_state);
+        DataGovernance::load_metrics_consent(&s.platform_state).await;
 
         if !s.platform_state.supports_session() {
             return Ok(());
@@ -40,6 +44,18 @@ impl Bootstep<BootstrapState> for LoadDistributorValuesStep {
             .get_client()
             .add_event_processor(MainContextProcessor::new(s.platform_state.clone()));
 
+        if s.platform_state
+            .get_device_manifest()
+            .get_entitlements_sync_configuration()
+            .enabled
+        {
+            s.platform_state
+                .get_client()
+                .add_event_processor(EntitlementsSyncProcessor::new(s.platform_state.clone()));
+            EntitlementsSyncProcessor::start_schedule(s.platform_state.clone());
+        }
+        ActivationOrchestrator::start(s.platform_state.clone());
+
         Ok(())
     }
 }
@@ -16,21 +16,27 @@
 //
 
 use ripple_sdk::{
-    async_trait::async_trait, framework::bootstrap::Bootstep, utils::error::RippleError,
+    async_trait::async_trait,
+    framework::bootstrap::Bootstep,
+    log::{error, info},
+    utils::error::RippleError,
 };
 
 use crate::processor::metrics_processor::OpMetricsProcessor;
+use crate::processor::remote_key_processor::RemoteKeyProcessor;
 use crate::processor::settings_processor::SettingsProcessor;
 use crate::processor::{
     store_privacy_settings_processor::StorePrivacySettingsProcessor,
     store_user_grants_processor::StoreUserGrantsProcessor,
+    update_status_processor::UpdateStatusProcessor, user_activity_processor::UserActivityProcessor,
 };
 use crate::{
     processor::{
         app_events_processor::AppEventsProcessor,
         authorized_info_processor::AuthorizedInfoProcessor,
-        config_processor::ConfigRequestProcessor, keyboard_processor::KeyboardProcessor,
-        pin_processor::PinProcessor, storage::storage_manager_processor::StorageManagerProcessor,
+        config_processor::ConfigRequestProcessor, extn_method_processor::ExtnMethodProcessor,
+        keyboard_processor::KeyboardProcessor, pin_processor::PinProcessor,
+        storage::storage_manager_processor::StorageManagerProcessor,
     },
     state::bootstrap_state::BootstrapState,
 };
@@ -58,6 +64,42 @@ impl Bootstep<BootstrapState> for SetupExtnClientStep {
         client.add_request_processor(AuthorizedInfoProcessor::new(state.platform_state.clone()));
         client.add_request_processor(SettingsProcessor::new(state.platform_state.clone()));
         client.add_request_processor(OpMetricsProcessor::new(state.platform_state.clone()));
+        client.add_request_processor(ExtnMethodProcessor::new(state.platform_state.clone()));
+        client.add_event_processor(UpdateStatusProcessor::new(state.platform_state.clone()));
+        client.add_event_processor(UserActivityProcessor::new(state.platform_state.clone()));
+        client.add_event_processor(RemoteKeyProcessor::new(state.platform_state.clone()));
+        let extn_manifest = state.platform_state.get_manifest();
+        let extn_client = client.get_extn_client();
+        extn_client.set_backlog_warning_threshold(extn_manifest.get_backlog_warning_threshold());
+        extn_client.set_extn_routes_enforced(extn_manifest.is_extn_routes_enforced());
+        extn_client.set_extn_routes(extn_manifest.extn_routes);
+        Self::negotiate_contract_versions(&state);
         Ok(())
     }
 }
+
+impl SetupExtnClientStep {
+    /// Negotiates the contract versions declared for the extensions in the extn manifest with
+    /// the versions supported by Ripple Main. Extensions with an incompatible contract are
+    /// refused when they load, services negotiate again with the versions declared in their
+    /// handshake when they connect.
+    fn negotiate_contract_versions(state: &BootstrapState) {
+        let matrix = state
+            .platform_state
+            .get_manifest()
+            .negotiate_contract_versions();
+        for status in matrix.iter() {
+            match status.get_error() {
+                Some(e) => error!("Refusing extension: {}", e),
+                None => info!(
+                    "Extension {} negotiated contract {} version={:?}",
+                    status.extn_id, status.contract, status.negotiated_version
+                ),
+            }
+        }
+        state
+            .platform_state
+            .extn_status_state
+            .set_contract_matrix(matrix);
+    }
+}
@@ -20,8 +20,18 @@ use ripple_sdk::{framework::bootstrap::Bootstep, tokio, utils::error::RippleErro
 
 use crate::processor::lifecycle_management_processor::LifecycleManagementProcessor;
 use crate::{
-    service::apps::delegated_launcher_handler::DelegatedLauncherHandler,
-    state::bootstrap_state::BootstrapState,
+    service::apps::{
+        app_library_refresh::AppLibraryRefresh,
+        delegated_launcher_handler::DelegatedLauncherHandler,
+    },
+    service::{
+        grant_reaper::GrantReaper, heartbeat::Heartbeat, inactivity_monitor::InactivityMonitor,
+        notification_policy::NotificationPolicy, pending_request_monitor::PendingRequestMonitor,
+    },
+    state::{
+        bootstrap_state::BootstrapState, method_override_state::MethodOverrideState,
+        region_state::RegionState, request_journal_state::RequestJournalState,
+    },
 };
 
 /// Starts the App Manager and other supporting services
@@ -40,6 +50,16 @@ impl Bootstep<BootstrapState> for StartAppManagerStep {
             .add_request_processor(LifecycleManagementProcessor::new(
                 state.platform_state.get_client(),
             ));
+        AppLibraryRefresh::start(state.platform_state.clone());
+        state.platform_state.watch_history_state.start();
+        Heartbeat::start(state.platform_state.clone());
+        MethodOverrideState::start(state.platform_state.clone());
+        RegionState::start(state.platform_state.clone());
+        RequestJournalState::start(state.platform_state.clone());
+        GrantReaper::start(state.platform_state.clone());
+        InactivityMonitor::start(state.platform_state.clone());
+        NotificationPolicy::start(state.platform_state.clone());
+        PendingRequestMonitor::start(state.platform_state.clone());
         let mut app_manager =
             DelegatedLauncherHandler::new(state.channels_state, state.platform_state);
         tokio::spawn(async move {
@@ -42,6 +42,7 @@ impl Bootstep<BootstrapState> for StartCommunicationBroker {
 
         // Start the Broker Reciever
         if let Ok(rx) = state.channels_state.get_broker_receiver() {
+            let _guard = ps.runtime_topology.enter_transform();
             BrokerOutputForwarder::start_forwarder(ps.clone(), rx)
         }
         // Setup the endpoints from the manifests
@@ -63,6 +64,7 @@ impl Bootstep<BootstrapState> for StartOtherBrokers {
         let ps = state.platform_state.clone();
         // Start the Broker Reciever
         if let Ok(rx) = state.channels_state.get_broker_receiver() {
+            let _guard = ps.runtime_topology.enter_transform();
             BrokerOutputForwarder::start_forwarder(ps.clone(), rx)
         }
         // Setup the endpoints from the manifests
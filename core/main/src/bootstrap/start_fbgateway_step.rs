@@ -21,20 +21,30 @@ use crate::{
     firebolt::{
         firebolt_gateway::FireboltGateway,
         handlers::{
-            accessory_rpc::AccessoryRippleProvider, advertising_rpc::AdvertisingRPCProvider,
-            audio_description_rpc::AudioDescriptionRPCProvider, capabilities_rpc::CapRPCProvider,
-            closed_captions_rpc::ClosedcaptionsRPCProvider, device_rpc::DeviceRPCProvider,
-            discovery_rpc::DiscoveryRPCProvider, internal_rpc::InternalProvider,
+            accessibility_rpc::AccessibilityRPCProvider, accessory_rpc::AccessoryRippleProvider,
+            admin_rpc::AdminRPCProvider, advertising_rpc::AdvertisingRPCProvider,
+            audio_description_rpc::AudioDescriptionRPCProvider,
+            audio_focus_rpc::AudioFocusRPCProvider, capabilities_rpc::CapRPCProvider,
+            closed_captions_rpc::ClosedcaptionsRPCProvider,
+            compatibility_rpc::CompatibilityRPCProvider, device_rpc::DeviceRPCProvider,
+            diagnostics_rpc::DiagnosticsRPCProvider, discovery_rpc::DiscoveryRPCProvider,
+            drm_rpc::DrmRPCProvider, input_rpc::InputRPCProvider,
+            inspector_rpc::InspectorRPCProvider, internal_rpc::InternalProvider,
             keyboard_rpc::KeyboardRPCProvider, lcm_rpc::LifecycleManagementProvider,
             lifecycle_rpc::LifecycleRippleProvider, localization_rpc::LocalizationRPCProvider,
-            parameters_rpc::ParametersRPCProvider, privacy_rpc::PrivacyProvider,
-            profile_rpc::ProfileRPCProvider, provider_registrar::ProviderRegistrar,
+            media_session_rpc::MediaSessionRPCProvider,
+            notifications_rpc::NotificationsRPCProvider, parameters_rpc::ParametersRPCProvider,
+            privacy_rpc::PrivacyProvider, profile_rpc::ProfileRPCProvider,
+            provider_registrar::ProviderRegistrar, provisioning_rpc::ProvisioningRPCProvider,
             second_screen_rpc::SecondScreenRPCProvider, user_grants_rpc::UserGrantsRPCProvider,
             wifi_rpc::WifiRPCProvider,
         },
         rpc::RippleRPCProvider,
     },
-    service::telemetry_builder::TelemetryBuilder,
+    service::{
+        graceful_shutdown::GracefulShutdown, soft_restart::SoftRestart,
+        telemetry_builder::TelemetryBuilder,
+    },
     state::{bootstrap_state::BootstrapState, platform_state::PlatformState},
 };
 use jsonrpsee::core::{async_trait, server::rpc_module::Methods};
@@ -43,7 +53,8 @@ use ripple_sdk::{framework::bootstrap::Bootstep, utils::error::RippleError};
 pub struct FireboltGatewayStep;
 
 impl FireboltGatewayStep {
-    async fn init_handlers(&self, state: PlatformState) -> Methods {
+    /// Builds the Firebolt methods served by the router, called again on a soft restart
+    pub async fn init_handlers(state: PlatformState) -> Methods {
         let mut methods = Methods::new();
 
         // TODO: Ultimately this may be able to register all providers below, for now just does
@@ -71,7 +82,20 @@ impl FireboltGatewayStep {
         let _ = methods.merge(AudioDescriptionRPCProvider::provide_with_alias(
             state.clone(),
         ));
+        let _ = methods.merge(AccessibilityRPCProvider::provide_with_alias(state.clone()));
+        let _ = methods.merge(DiagnosticsRPCProvider::provide_with_alias(state.clone()));
         let _ = methods.merge(InternalProvider::provide_with_alias(state.clone()));
+        let _ = methods.merge(ProvisioningRPCProvider::provide_with_alias(state.clone()));
+        let _ = methods.merge(InputRPCProvider::provide_with_alias(state.clone()));
+        let _ = methods.merge(MediaSessionRPCProvider::provide_with_alias(state.clone()));
+        let _ = methods.merge(AudioFocusRPCProvider::provide_with_alias(state.clone()));
+        let _ = methods.merge(DrmRPCProvider::provide_with_alias(state.clone()));
+        let _ = methods.merge(NotificationsRPCProvider::provide_with_alias(state.clone()));
+        let _ = methods.merge(CompatibilityRPCProvider::provide_with_alias(state.clone()));
+        let _ = methods.merge(InspectorRPCProvider::provide_with_alias(state.clone()));
+        if state.admin_state.is_enabled() {
+            let _ = methods.merge(AdminRPCProvider::provide_with_alias(state.clone()));
+        }
 
         // LCM Api(s) not required for internal launcher
         if !state.has_internal_launcher() {
@@ -88,7 +112,7 @@ impl Bootstep<BootstrapState> for FireboltGatewayStep {
     }
 
     async fn setup(&self, state: BootstrapState) -> Result<(), RippleError> {
-        let methods = self.init_handlers(state.platform_state.clone()).await;
+        let methods = Self::init_handlers(state.platform_state.clone()).await;
         let gateway = FireboltGateway::new(state.clone(), methods);
         debug!("Handlers initialized");
         #[cfg(feature = "sysd")]
@@ -102,8 +126,13 @@ impl Bootstep<BootstrapState> for FireboltGatewayStep {
             "Ripple Total Bootstrap time: {}",
             Instant::now().duration_since(state.start_time).as_millis()
         );
+        GracefulShutdown::listen(state.platform_state.clone());
+        SoftRestart::listen(state.platform_state.clone());
         gateway.start().await;
 
+        if state.platform_state.shutdown_state.is_shutting_down() {
+            return Ok(());
+        }
         Err(RippleError::ServiceError)
     }
 }
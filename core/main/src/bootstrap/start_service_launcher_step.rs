@@ -0,0 +1,59 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::{
+    async_trait::async_trait, framework::bootstrap::Bootstep, log::info, utils::error::RippleError,
+};
+
+use crate::{
+    service::ripple_service::{
+        service_controller_state::ServiceControllerState, service_launcher::ServiceLauncher,
+    },
+    state::bootstrap_state::BootstrapState,
+};
+
+/// Launches the companion services listed in the device manifest and supervises them,
+/// along with the liveness of every connected service
+pub struct StartServiceLauncherStep;
+
+#[async_trait]
+impl Bootstep<BootstrapState> for StartServiceLauncherStep {
+    fn get_name(&self) -> String {
+        "StartServiceLauncherStep".into()
+    }
+
+    async fn setup(&self, state: BootstrapState) -> Result<(), RippleError> {
+        ServiceControllerState::start_liveness_monitor(state.platform_state.clone());
+        let config = state
+            .platform_state
+            .get_device_manifest()
+            .get_service_launcher_configuration();
+        if !config.enabled {
+            return Ok(());
+        }
+        let launcher_state = state
+            .platform_state
+            .service_controller_state
+            .launcher_state
+            .clone();
+        for service in config.services {
+            info!("Starting companion service {}", service.service_id);
+            ServiceLauncher::launch(launcher_state.clone(), service);
+        }
+        Ok(())
+    }
+}
@@ -16,15 +16,92 @@
 //
 
 use ripple_sdk::{
-    async_trait::async_trait, framework::bootstrap::Bootstep, tokio, utils::error::RippleError,
+    api::manifest::device_manifest::WsConfiguration,
+    async_trait::async_trait,
+    framework::bootstrap::Bootstep,
+    log::{error, warn},
+    tokio::{self, net::TcpListener},
+    utils::{error::RippleError, tls_utils::ServerTlsAcceptor},
 };
 
-use crate::state::bootstrap_state::BootstrapState;
+use crate::state::{bootstrap_state::BootstrapState, platform_state::PlatformState};
+use crate::utils::bind_utils::take_inherited_listener;
 
-use crate::firebolt::firebolt_ws::FireboltWs;
+use crate::firebolt::{firebolt_http::FireboltHttp, firebolt_ws::FireboltWs};
+use crate::service::ripple_service::service_http::ServiceHttp;
 
 pub struct StartWsStep;
 
+impl StartWsStep {
+    /// Fails when the TLS configuration cannot be used or the gateway cannot be bound,
+    /// additional gateways which cannot be bound are skipped
+    async fn start_ws(
+        config: &WsConfiguration,
+        state: &PlatformState,
+        secure: bool,
+        internal_app_id: Option<String>,
+    ) -> Result<(), RippleError> {
+        let tls_acceptor = ServerTlsAcceptor::new(config.tls.as_ref()).map_err(|e| {
+            error!("Failed to set up TLS for {}: {:?}", config.gateway, e);
+            RippleError::BootstrapError
+        })?;
+        if let Some(name) = &config.socket_activation_name {
+            match take_inherited_listener(name).map(TcpListener::from_std) {
+                Some(Ok(listener)) => {
+                    let name = name.clone();
+                    let state_for_ws = state.clone();
+                    tokio::spawn(async move {
+                        FireboltWs::serve(
+                            listener,
+                            &name,
+                            state_for_ws,
+                            secure,
+                            internal_app_id,
+                            tls_acceptor,
+                        )
+                        .await;
+                    });
+                    return Ok(());
+                }
+                Some(Err(e)) => error!("Unable to use inherited socket {}: {:?}", name, e),
+                None => warn!("No inherited socket {}, binding the gateway", name),
+            }
+        }
+        let listener = FireboltWs::bind(&config.gateway).await?;
+        let gateway = config.gateway.clone();
+        let state_for_ws = state.clone();
+        let iai_c = internal_app_id.clone();
+        let tls_acceptor_c = tls_acceptor.clone();
+        tokio::spawn(async move {
+            FireboltWs::serve(
+                listener,
+                &gateway,
+                state_for_ws,
+                secure,
+                iai_c,
+                tls_acceptor_c,
+            )
+            .await;
+        });
+        for ws_addr in config.additional_gateways.clone() {
+            let state_for_ws = state.clone();
+            let iai_c = internal_app_id.clone();
+            let tls_acceptor_c = tls_acceptor.clone();
+            tokio::spawn(async move {
+                FireboltWs::start(
+                    ws_addr.as_str(),
+                    state_for_ws,
+                    secure,
+                    iai_c,
+                    tls_acceptor_c,
+                )
+                .await;
+            });
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Bootstep<BootstrapState> for StartWsStep {
     fn get_name(&self) -> String {
@@ -36,23 +113,68 @@ impl Bootstep<BootstrapState> for StartWsStep {
         let iai = manifest.get_internal_app_id();
         let ws_enabled = manifest.get_web_socket_enabled();
         let internal_ws_enabled = manifest.get_internal_ws_enabled();
-        let iai_c = iai.clone();
         if ws_enabled {
-            let ws_addr = manifest.get_ws_gateway_host();
-            let state_for_ws = state.platform_state.clone();
+            Self::start_ws(
+                &manifest.configuration.ws_configuration,
+                &state.platform_state,
+                true,
+                iai.clone(),
+            )
+            .await?;
+        }
+
+        let http_bridge_config = manifest.get_http_bridge_configuration();
+        if http_bridge_config.enabled {
+            for http_addr in http_bridge_config.get_gateways() {
+                let state_for_http = state.platform_state.clone();
+                tokio::spawn(async move {
+                    FireboltHttp::start(http_addr.as_str(), state_for_http).await;
+                });
+            }
+        }
+
+        if manifest.get_service_http_configuration().enabled {
+            let state_for_service_http = state.platform_state.clone();
             tokio::spawn(async move {
-                FireboltWs::start(ws_addr.as_str(), state_for_ws, true, iai.clone()).await;
+                ServiceHttp::start(state_for_service_http).await;
             });
         }
 
         if internal_ws_enabled {
-            let ws_addr = manifest.get_internal_gateway_host();
-            let state_for_ws = state.platform_state;
-            tokio::spawn(async move {
-                FireboltWs::start(ws_addr.as_str(), state_for_ws, false, iai_c).await;
-            });
+            Self::start_ws(
+                &manifest.configuration.internal_ws_configuration,
+                &state.platform_state,
+                false,
+                iai,
+            )
+            .await?;
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::manifest::device_manifest::WsTlsConfiguration;
+    use ripple_tdk::utils::test_utils::Mockable;
+
+    #[tokio::test]
+    async fn test_start_ws_fails_bootstrap_on_unusable_tls() {
+        let state = PlatformState::mock();
+        let config = WsConfiguration {
+            gateway: "127.0.0.1:0".into(),
+            tls: Some(WsTlsConfiguration {
+                cert_path: "/nonexistent/cert.pem".into(),
+                key_path: "/nonexistent/key.pem".into(),
+                client_ca_path: None,
+            }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            StartWsStep::start_ws(&config, &state, true, None).await,
+            Err(RippleError::BootstrapError)
+        ));
+    }
+}
@@ -17,13 +17,12 @@
 
 use ripple_sdk::{
     api::{
-        firebolt::fb_capabilities::{
-            FireboltPermission, CAPABILITY_NOT_AVAILABLE, JSON_RPC_STANDARD_ERROR_INVALID_PARAMS,
-        },
+        firebolt::fb_capabilities::{FireboltPermission, CAPABILITY_NOT_AVAILABLE},
         gateway::rpc_gateway_api::{
             ApiMessage, ApiProtocol, CallContext, JsonRpcApiRequest, JsonRpcApiResponse,
             RpcRequest, RPC_V2,
         },
+        manifest::device_manifest::RouteTarget,
         observability::log_signal::LogSignal,
         session::AccountSession,
     },
@@ -31,34 +30,37 @@ use ripple_sdk::{
     framework::RippleResponse,
     log::{debug, error, info, trace},
     service::service_message::{
-        Id as ServiceMessageId, JsonRpcMessage as ServiceJsonRpcMessage,
-        JsonRpcSuccess as ServiceJsonRpcSuccess, ServiceMessage,
+        JsonRpcMessage as ServiceJsonRpcMessage, JsonRpcSuccess as ServiceJsonRpcSuccess,
+        ServiceMessage,
     },
     tokio::{
         self,
         sync::mpsc::{self, Receiver, Sender},
     },
     tokio_tungstenite::tungstenite::Message,
-    utils::error::RippleError,
+    utils::{error::RippleError, expiring_map::ExpiringMap, id_allocator::next_id},
 };
 use serde_json::{json, Value};
 use std::{
     collections::HashMap,
-    sync::{
-        atomic::{AtomicU64, Ordering},
-        Arc, RwLock,
-    },
+    sync::{Arc, OnceLock, RwLock},
+    time::{Duration, Instant},
 };
 
 use crate::{
-    broker::broker_utils::BrokerUtils,
-    firebolt::firebolt_gateway::JsonRpcError,
+    broker::{
+        broker_utils::BrokerUtils,
+        event_debouncer::{DebounceAction, EventDebouncer},
+    },
     service::extn::ripple_client::RippleClient,
     state::{
         ops_metrics_state::OpMetricState, platform_state::PlatformState, session_state::Session,
     },
-    utils::router_utils::{
-        add_telemetry_status_code, capture_stage, get_rpc_header, return_extn_response,
+    utils::{
+        router_utils::{
+            add_telemetry_status_code, capture_stage, get_rpc_header, return_extn_response,
+        },
+        runtime_topology::RuntimeTopology,
     },
 };
 
@@ -67,9 +69,12 @@ use super::{
     extn_broker::ExtnBroker,
     http_broker::HttpBroker,
     provider_broker_state::{ProvideBrokerState, ProviderResult},
+    request_deadlines::RequestDeadlines,
+    rule_explain::RuleExplainState,
     rules::rules_engine::{
-        jq_compile, EventHandler, Rule, RuleEndpoint, RuleEndpointProtocol, RuleEngine,
-        RuleRetrievalError, RuleRetrieved, RuleType,
+        jq_compile, jq_compile_rule, EventHandler, RoutePrecedence, Rule, RuleEndpoint,
+        RuleEndpointProtocol, RuleEngine, RuleProvenance, RuleRetrievalError, RuleRetrieved,
+        RuleType,
     },
     service_broker::ServiceBroker,
     thunder_broker::ThunderBroker,
@@ -102,6 +107,9 @@ impl BrokerCleaner {
 
 // Default Broker mpsc channel buffer size
 pub const BROKER_CHANNEL_BUFFER_SIZE: usize = 32;
+// Brokered requests without a response are dropped after this long
+pub const BROKER_REQUEST_TTL: Duration = Duration::from_secs(300);
+pub const BROKER_REQUEST_CAPACITY: usize = 4096;
 
 #[derive(Clone, Debug, Default)]
 pub struct BrokerRequest {
@@ -265,8 +273,6 @@ impl Default for BrokerCallback {
     }
 }
 
-pub(crate) static ATOMIC_ID: AtomicU64 = AtomicU64::new(0);
-
 impl BrokerCallback {
     pub async fn send_json_rpc_api_response(&self, response: JsonRpcApiResponse) {
         let output = BrokerOutput::new(response);
@@ -276,20 +282,7 @@ impl BrokerCallback {
     }
     /// Default method used for sending errors via the BrokerCallback
     pub async fn send_error(&self, request: BrokerRequest, error: RippleError) {
-        let value = serde_json::to_value(JsonRpcError {
-            code: JSON_RPC_STANDARD_ERROR_INVALID_PARAMS,
-            message: format!("Error with {:?}", error),
-            data: None,
-        })
-        .unwrap();
-        let data = JsonRpcApiResponse {
-            jsonrpc: "2.0".to_owned(),
-            id: Some(request.rpc.ctx.call_id),
-            error: Some(value),
-            result: None,
-            method: None,
-            params: None,
-        };
+        let data = JsonRpcApiResponse::builder(request.rpc.ctx.call_id).ripple_error(&error);
         self.send_json_rpc_api_response(data).await;
     }
 }
@@ -302,11 +295,16 @@ pub struct BrokerContext {
 #[derive(Debug, Clone, Default)]
 pub struct BrokerOutput {
     pub data: JsonRpcApiResponse,
+    /// Set on events held back by the [EventDebouncer] once their interval elapses
+    pub debounce_flush: bool,
 }
 
 impl BrokerOutput {
     pub fn new(data: JsonRpcApiResponse) -> Self {
-        Self { data }
+        Self {
+            data,
+            debounce_flush: false,
+        }
     }
     pub fn with_jsonrpc_response(&mut self, data: JsonRpcApiResponse) -> &mut Self {
         self.data = data;
@@ -373,13 +371,33 @@ impl BrokerSender {
 pub struct EndpointBrokerState {
     endpoint_map: Arc<RwLock<HashMap<String, BrokerSender>>>,
     callback: BrokerCallback,
-    request_map: Arc<RwLock<HashMap<u64, BrokerRequest>>>,
-    extension_request_map: Arc<RwLock<HashMap<u64, ExtnMessage>>>,
+    request_map: Arc<RwLock<ExpiringMap<u64, BrokerRequest>>>,
+    extension_request_map: Arc<RwLock<ExpiringMap<u64, ExtnMessage>>>,
     rule_engine: Arc<RwLock<RuleEngine>>,
-    cleaner_list: Arc<RwLock<Vec<BrokerCleaner>>>,
+    /// Cleaners of the running brokers, keyed by endpoint
+    cleaner_list: Arc<RwLock<HashMap<String, BrokerCleaner>>>,
     reconnect_tx: Sender<BrokerConnectRequest>,
     provider_broker_state: ProvideBrokerState,
     metrics_state: OpMetricState,
+    explain_state: RuleExplainState,
+    /// Order in which the rules of services, extensions and the device are tried, shared
+    /// between the clones so a rules reload refreshes it for every clone
+    route_precedence: Arc<RwLock<Arc<RoutePrecedence>>>,
+    /// Rules of services taken over by another service under the method conflict policy,
+    /// keyed by the ServiceId of the winner and restored when it gives the method up
+    displaced_rules: Arc<RwLock<HashMap<String, Vec<DisplacedRule>>>>,
+    /// Started with the first request which carries a deadline
+    request_deadlines: Arc<OnceLock<RequestDeadlines>>,
+    /// Brokers are started in the dedicated broker runtime when one is configured
+    runtime_topology: RuntimeTopology,
+}
+
+/// Rule of a service taken over by the method of another service
+#[derive(Debug, Clone)]
+struct DisplacedRule {
+    rule_name: String,
+    rule: Rule,
+    displaced_by: String,
 }
 
 #[derive(Debug)]
@@ -396,9 +414,6 @@ impl From<RuleRetrievalError> for HandleBrokerageError {
             RuleRetrievalError::RuleNotFoundAsWildcard => {
                 HandleBrokerageError::RuleNotFound("Rule Not found as wildcard".to_string())
             }
-            RuleRetrievalError::TooManyWildcardMatches => {
-                HandleBrokerageError::RuleNotFound("Too many wildcard matches".to_string())
-            }
         }
     }
 }
@@ -464,13 +479,21 @@ impl Default for EndpointBrokerState {
         Self {
             endpoint_map: Arc::new(RwLock::new(HashMap::new())),
             callback: BrokerCallback::default(),
-            request_map: Arc::new(RwLock::new(HashMap::new())),
-            extension_request_map: Arc::new(RwLock::new(HashMap::new())),
+            request_map: Arc::new(RwLock::new(Self::get_request_map())),
+            extension_request_map: Arc::new(RwLock::new(ExpiringMap::new(
+                BROKER_REQUEST_TTL,
+                BROKER_REQUEST_CAPACITY,
+            ))),
             rule_engine: Arc::new(RwLock::new(RuleEngine::default())),
-            cleaner_list: Arc::new(RwLock::new(Vec::new())),
+            cleaner_list: Arc::new(RwLock::new(HashMap::new())),
             reconnect_tx: mpsc::channel(2).0,
             provider_broker_state: ProvideBrokerState::default(),
             metrics_state: OpMetricState::default(),
+            explain_state: RuleExplainState::default(),
+            route_precedence: Arc::new(RwLock::new(Arc::new(RoutePrecedence::default()))),
+            displaced_rules: Arc::new(RwLock::new(HashMap::new())),
+            request_deadlines: Arc::new(OnceLock::new()),
+            runtime_topology: RuntimeTopology::default(),
         }
     }
 }
@@ -486,13 +509,21 @@ impl EndpointBrokerState {
         let state = Self {
             endpoint_map: Arc::new(RwLock::new(HashMap::new())),
             callback: BrokerCallback { sender: tx },
-            request_map: Arc::new(RwLock::new(HashMap::new())),
-            extension_request_map: Arc::new(RwLock::new(HashMap::new())),
+            request_map: Arc::new(RwLock::new(Self::get_request_map())),
+            extension_request_map: Arc::new(RwLock::new(ExpiringMap::new(
+                BROKER_REQUEST_TTL,
+                BROKER_REQUEST_CAPACITY,
+            ))),
             rule_engine: Arc::new(RwLock::new(rule_engine)),
-            cleaner_list: Arc::new(RwLock::new(Vec::new())),
+            cleaner_list: Arc::new(RwLock::new(HashMap::new())),
             reconnect_tx,
             provider_broker_state: ProvideBrokerState::default(),
             metrics_state,
+            explain_state: RuleExplainState::default(),
+            route_precedence: Arc::new(RwLock::new(Arc::new(RoutePrecedence::default()))),
+            displaced_rules: Arc::new(RwLock::new(HashMap::new())),
+            request_deadlines: Arc::new(OnceLock::new()),
+            runtime_topology: RuntimeTopology::default(),
         };
         /*bobra: configuring this out for unit tests */
         #[cfg(not(test))]
@@ -503,6 +534,41 @@ impl EndpointBrokerState {
         self.rule_engine = rule_engine;
         self
     }
+    pub fn with_route_precedence(self, precedence: Vec<RouteTarget>) -> Self {
+        self.set_route_precedence(precedence);
+        self
+    }
+    pub fn with_runtime_topology(mut self, runtime_topology: RuntimeTopology) -> Self {
+        self.runtime_topology = runtime_topology;
+        self
+    }
+    fn set_route_precedence(&self, precedence: Vec<RouteTarget>) {
+        *self.route_precedence.write().unwrap() = Arc::new(RoutePrecedence::new(precedence));
+    }
+    pub fn get_route_precedence(&self) -> Arc<RoutePrecedence> {
+        self.route_precedence.read().unwrap().clone()
+    }
+    /// Checks if a rule serves the method ahead of the handlers, in a single pass over the
+    /// rules
+    pub fn has_rule_before_handler(&self, method: &str) -> bool {
+        let route_precedence = self.get_route_precedence();
+        let handler_rank = route_precedence.rank(RouteTarget::Handler);
+        self.rule_engine
+            .read()
+            .unwrap()
+            .find_best_rule(method, &route_precedence)
+            .is_some_and(|rule| {
+                route_precedence.rank(Rule::from(rule).provenance.route_target()) < handler_rank
+            })
+    }
+    /// Finds the rule of the target kind serving the method, see [RuleEngine::find_rule]
+    pub fn find_rule(&self, method: &str, target: RouteTarget) -> Option<Rule> {
+        self.rule_engine
+            .read()
+            .unwrap()
+            .find_rule(method, target)
+            .map(Rule::from)
+    }
     pub fn add_rule(self, rule: Rule) -> Self {
         self.rule_engine.write().unwrap().add_rule(rule);
         self
@@ -510,12 +576,170 @@ impl EndpointBrokerState {
     pub fn has_rule(&self, rule: &str) -> bool {
         self.rule_engine.read().unwrap().has_rule(rule)
     }
+    pub fn get_service_methods(&self) -> HashMap<String, Vec<String>> {
+        self.rule_engine.read().unwrap().get_service_methods()
+    }
+    pub fn is_routed_to_service(&self, service_id: &str, method: &str) -> bool {
+        self.rule_engine
+            .read()
+            .unwrap()
+            .is_routed_to_service(service_id, method)
+    }
+    pub fn is_service_rule(&self, rule: &Rule) -> bool {
+        self.rule_engine.read().unwrap().is_service_rule(rule)
+    }
+    pub fn get_rule_provenance(&self) -> HashMap<String, RuleProvenance> {
+        self.rule_engine.read().unwrap().get_rule_provenance()
+    }
+    pub fn get_rules(&self) -> HashMap<String, Rule> {
+        self.rule_engine.read().unwrap().rules.rules.clone()
+    }
+    pub fn get_rule_endpoints(&self) -> HashMap<String, RuleEndpoint> {
+        self.rule_engine.read().unwrap().rules.endpoints.clone()
+    }
+    pub fn get_rule_by_method(&self, method: &str) -> Option<Rule> {
+        self.rule_engine.read().unwrap().get_rule_by_method(method)
+    }
+    /// Returns the rules serving at least one method in common with the method, which may
+    /// be a pattern, see [RuleEngine::find_overlapping_rules]
+    pub fn find_overlapping_rules(&self, method: &str) -> Vec<(String, Rule)> {
+        self.rule_engine
+            .read()
+            .unwrap()
+            .find_overlapping_rules(method)
+    }
+    pub fn add_service_rules(&self, service_id: &str, methods: &[String]) {
+        self.resolve_service_rules(service_id, |_| (methods.to_vec(), Vec::new(), ()));
+    }
+    /// Resolves the methods of the service against the rules and routes the ones it gets to
+    /// the service, under one write lock so no other registration lands between the check
+    /// and the insert. The resolution returns every method the service serves, the rules of
+    /// other services it takes over along with the method taking each over, and its result.
+    /// Rules taken over are kept aside and restored once the winner stops serving the method.
+    pub fn resolve_service_rules<T>(
+        &self,
+        service_id: &str,
+        resolve: impl FnOnce(&RuleEngine) -> (Vec<String>, Vec<(String, String)>, T),
+    ) -> T {
+        let mut rule_engine = self.rule_engine.write().unwrap();
+        let mut displaced_rules = self.displaced_rules.write().unwrap();
+        let (methods, taken_over, result) = resolve(&rule_engine);
+        for (rule_name, method) in taken_over {
+            if let Some(rule) = rule_engine.rules.rules.remove(&rule_name) {
+                displaced_rules
+                    .entry(service_id.to_owned())
+                    .or_default()
+                    .push(DisplacedRule {
+                        rule_name,
+                        rule,
+                        displaced_by: method.to_lowercase(),
+                    });
+            }
+        }
+        let endpoint = rule_engine.ensure_service_endpoint();
+        rule_engine.add_service_rules(service_id, &methods, &endpoint);
+        Self::restore_displaced_rules(&mut rule_engine, &mut displaced_rules, service_id);
+        result
+    }
+    pub fn get_service_registered_methods(&self, service_id: &str) -> Vec<String> {
+        self.rule_engine
+            .read()
+            .unwrap()
+            .get_registered_methods(&RuleProvenance::Service {
+                service_id: service_id.to_owned(),
+            })
+    }
+    /// Removes the rules of the service and gives the methods it took over back to their
+    /// previous owners
+    pub fn remove_service_rules(&self, service_id: &str) {
+        let mut rule_engine = self.rule_engine.write().unwrap();
+        let mut displaced_rules = self.displaced_rules.write().unwrap();
+        rule_engine.remove_service_rules(service_id);
+        Self::forget_displaced_rules(&mut displaced_rules, service_id);
+        Self::restore_displaced_rules(&mut rule_engine, &mut displaced_rules, service_id);
+    }
+    /// Drops the rules of the service which were taken over, a new registration of the
+    /// service supersedes them
+    pub fn forget_displaced_service_rules(&self, service_id: &str) {
+        Self::forget_displaced_rules(&mut self.displaced_rules.write().unwrap(), service_id);
+    }
+    fn forget_displaced_rules(
+        displaced_rules: &mut HashMap<String, Vec<DisplacedRule>>,
+        service_id: &str,
+    ) {
+        let provenance = RuleProvenance::Service {
+            service_id: service_id.to_owned(),
+        };
+        for rules in displaced_rules.values_mut() {
+            rules.retain(|displaced| displaced.rule.provenance.ne(&provenance));
+        }
+        displaced_rules.retain(|_, rules| !rules.is_empty());
+    }
+    /// Restores the rules the service took over with methods it no longer serves, unless
+    /// someone else serves them meanwhile
+    fn restore_displaced_rules(
+        rule_engine: &mut RuleEngine,
+        displaced_rules: &mut HashMap<String, Vec<DisplacedRule>>,
+        service_id: &str,
+    ) {
+        let Some(rules) = displaced_rules.remove(service_id) else {
+            return;
+        };
+        let provenance = RuleProvenance::Service {
+            service_id: service_id.to_owned(),
+        };
+        let (kept, released): (Vec<DisplacedRule>, Vec<DisplacedRule>) =
+            rules.into_iter().partition(|displaced| {
+                rule_engine
+                    .get_rule_by_method(&displaced.displaced_by)
+                    .is_some_and(|rule| rule.provenance.eq(&provenance))
+            });
+        for displaced in released {
+            if rule_engine
+                .find_overlapping_rules(&displaced.rule_name)
+                .is_empty()
+            {
+                rule_engine
+                    .rules
+                    .rules
+                    .insert(displaced.rule_name, displaced.rule);
+            }
+        }
+        rule_engine.rules.index_patterns();
+        if !kept.is_empty() {
+            displaced_rules.insert(service_id.to_owned(), kept);
+        }
+    }
+    /// Routes the methods to the extension, the rule alias is the ExtnId used by [ExtnBroker]
+    pub fn add_extn_rules(&self, extn_id: &str, methods: &[String]) {
+        let mut rule_engine = self.rule_engine.write().unwrap();
+        let endpoint = rule_engine.ensure_extn_endpoint();
+        let provenance = RuleProvenance::Extn {
+            extn_id: extn_id.to_owned(),
+        };
+        rule_engine.add_registered_rules(provenance, extn_id, methods, &endpoint);
+    }
+    pub fn remove_extn_rules(&self, extn_id: &str) {
+        self.rule_engine
+            .write()
+            .unwrap()
+            .remove_registered_rules(&RuleProvenance::Extn {
+                extn_id: extn_id.to_owned(),
+            });
+    }
+    /// Adds the rules to the engine, existing rules with the same name are replaced
+    pub fn restore_rules(&self, rules: HashMap<String, Rule>) {
+        let mut rule_engine = self.rule_engine.write().unwrap();
+        rule_engine.rules.rules.extend(rules);
+        rule_engine.rules.index_patterns();
+    }
     #[cfg(not(test))]
     fn reconnect_thread(&self, mut rx: Receiver<BrokerConnectRequest>, client: RippleClient) {
         use crate::firebolt::firebolt_gateway::FireboltGatewayCommand;
         let mut state = self.clone();
         tokio::spawn(async move {
             while let Some(v) = rx.recv().await {
+                state.metrics_state.record_reconnect();
                 if matches!(v.endpoint.protocol, RuleEndpointProtocol::Thunder) {
                     if client
                         .send_gateway_command(FireboltGatewayCommand::StopServer)
@@ -544,14 +768,74 @@ impl EndpointBrokerState {
         Ok(result)
     }
 
+    /// Fails the non subscription broker requests of a gateway request with an internal error
+    /// and returns their ids. The error goes through the broker output, so the entries of the
+    /// endpoint broker are freed on the way back to the caller.
+    pub async fn fail_requests(&self, request_id: &str, message: &str) -> Vec<u64> {
+        let requests: Vec<BrokerRequest> = self
+            .request_map
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, r)| r.rpc.ctx.request_id.eq(request_id) && !r.rpc.is_subscription())
+            .map(|(id, r)| {
+                let mut request = r.clone();
+                request.rpc.ctx.call_id = *id;
+                request
+            })
+            .collect();
+        for request in requests.iter() {
+            let response = JsonRpcApiResponse::builder(request.rpc.ctx.call_id)
+                .internal_error(message.to_owned());
+            self.callback.send_json_rpc_api_response(response).await;
+        }
+        requests.iter().map(|r| r.rpc.ctx.call_id).collect()
+    }
+
+    /// Fails the request through the broker output once the deadline of the caller passed
+    /// without a response of the broker, a late response then finds no request and is dropped.
+    fn watch_deadline(&self, id: u64, deadline: Instant) {
+        let request_map = self.request_map.clone();
+        let callback = self.callback.clone();
+        self.request_deadlines
+            .get_or_init(|| {
+                RequestDeadlines::start(move |id| {
+                    let Some(method) = request_map
+                        .read()
+                        .unwrap()
+                        .get(&id)
+                        .map(|request| request.rpc.method.clone())
+                    else {
+                        return;
+                    };
+                    let response = JsonRpcApiResponse::builder(id)
+                        .deadline_exceeded(format!("{} deadline exceeded", method));
+                    if let Err(e) = callback.sender.try_send(BrokerOutput::new(response)) {
+                        error!("Unable to fail expired request {} {:?}", id, e);
+                    }
+                })
+            })
+            .add(id, deadline);
+    }
+
     fn update_unsubscribe_request(&self, id: u64) {
         let mut result = self.request_map.write().unwrap();
-        if let Some(mut value) = result.remove(&id) {
+        if let Some(value) = result.get_mut(&id) {
             value.subscription_processed = Some(true);
-            let _ = result.insert(id, value);
         }
     }
 
+    fn get_request_map() -> ExpiringMap<u64, BrokerRequest> {
+        ExpiringMap::new(BROKER_REQUEST_TTL, BROKER_REQUEST_CAPACITY).with_eviction_callback(
+            Arc::new(|id, request: BrokerRequest, reason| {
+                error!(
+                    "Broker request {} for {} evicted without a response {:?}",
+                    id, request.rpc.method, reason
+                )
+            }),
+        )
+    }
+
     fn get_extn_message(&self, id: u64, is_event: bool) -> Result<ExtnMessage, RippleError> {
         if is_event {
             let v = { self.extension_request_map.read().unwrap().get(&id).cloned() };
@@ -569,18 +853,13 @@ impl EndpointBrokerState {
         }
     }
 
+    /// Broker request ids come from the process wide allocator, so they never collide with the
+    /// ids of the service path. The original JSON-RPC id is kept in the request map.
     pub fn get_next_id() -> u64 {
-        //https://en.cppreference.com/w/cpp/atomic/memory_order#Sequentially-consistent_ordering
-        /*
-        Switching to SeqCst for now, as the there could be consistency issues using Relaxed
-        note that fetch add returns the previous value after the add . This is
-        fine because the requirement is to have a unique id for each request
-        */
-        ATOMIC_ID.fetch_add(1, Ordering::SeqCst)
+        next_id()
     }
-    /// Generic method which takes the given parameters from RPC request and adds rules using rule engine
-    //TODO: decide fate of this function
-    #[allow(dead_code)]
+    /// Generic method which takes the given parameters from RPC request and adds rules using rule engine.
+    /// Brokers apply their own request rule, this is used to explain the request transform.
     fn apply_request_rule(rpc_request: &BrokerRequest) -> Result<Value, RippleError> {
         if let Ok(mut params) = serde_json::from_str::<Vec<Value>>(&rpc_request.rpc.params_json) {
             let last = params.pop().unwrap_or(Value::Null);
@@ -590,10 +869,11 @@ impl EndpointBrokerState {
                 .transform
                 .get_transform_data(super::rules::rules_engine::RuleTransformType::Request)
             {
-                let transformed_request_res = jq_compile(
+                let transformed_request_res = jq_compile_rule(
                     last,
                     &filter,
                     format!("{}_request", rpc_request.rpc.ctx.method),
+                    &rpc_request.rule.provenance,
                 );
 
                 LogSignal::new(
@@ -603,6 +883,10 @@ impl EndpointBrokerState {
                 )
                 .with_diagnostic_context_item("success", "true")
                 .with_diagnostic_context_item("result", &format!("{:?}", transformed_request_res))
+                .with_diagnostic_context_item(
+                    "provenance",
+                    &rpc_request.rule.provenance.to_string(),
+                )
                 .emit_debug();
 
                 return transformed_request_res;
@@ -636,23 +920,31 @@ impl EndpointBrokerState {
     ) -> BrokerRequest {
         let id = Self::get_next_id();
         let mut rpc_request_c = rpc_request.clone();
+        let is_subscription = rpc_request.is_subscription();
         {
             let mut request_map = self.request_map.write().unwrap();
-            let _ = request_map.insert(
-                id,
-                BrokerRequest {
-                    rpc: rpc_request.clone(),
-                    rule: rule.clone(),
-                    subscription_processed: None,
-                    workflow_callback: workflow_callback.clone(),
-                    telemetry_response_listeners: telemetry_response_listeners.clone(),
-                },
-            );
+            let request = BrokerRequest {
+                rpc: rpc_request.clone(),
+                rule: rule.clone(),
+                subscription_processed: None,
+                workflow_callback: workflow_callback.clone(),
+                telemetry_response_listeners: telemetry_response_listeners.clone(),
+            };
+            // subscriptions stay until the app unsubscribes or disconnects
+            let _ = if is_subscription {
+                request_map.insert_persistent(id, request)
+            } else {
+                request_map.insert(id, request)
+            };
         }
 
-        if extn_message.is_some() {
+        if let Some(extn_message) = extn_message {
             let mut extn_map = self.extension_request_map.write().unwrap();
-            let _ = extn_map.insert(id, extn_message.unwrap());
+            let _ = if is_subscription {
+                extn_map.insert_persistent(id, extn_message)
+            } else {
+                extn_map.insert(id, extn_message)
+            };
         }
 
         rpc_request_c.ctx.call_id = id;
@@ -685,7 +977,13 @@ impl EndpointBrokerState {
     }
 
     pub fn build_other_endpoints(&mut self, ps: PlatformState, session: Option<AccountSession>) {
-        let endpoints = self.rule_engine.read().unwrap().rules.endpoints.clone();
+        let endpoints = {
+            let mut rule_engine = self.rule_engine.write().unwrap();
+            // services and extensions can register their methods at runtime
+            rule_engine.ensure_service_endpoint();
+            rule_engine.ensure_extn_endpoint();
+            rule_engine.rules.endpoints.clone()
+        };
         for (key, endpoint) in endpoints {
             // skip thunder endpoint as it is already built using build_thunder_endpoint
             if let RuleEndpointProtocol::Thunder = endpoint.protocol {
@@ -701,6 +999,65 @@ impl EndpointBrokerState {
         }
     }
 
+    /// Reloads the rules from the extn manifest and the route precedence from the device
+    /// configuration. Rules registered by services or through the admin API are kept along
+    /// with the existing endpoints. Brokers are started for the endpoints added by the
+    /// reloaded rules and restarted for the endpoints whose configuration changed, the
+    /// Thunder broker is left running.
+    pub fn reload_rules(&mut self, ps: PlatformState) -> usize {
+        self.set_route_precedence(ps.get_device_configuration().routing.get_precedence());
+        let mut reloaded = RuleEngine::build(&ps.extn_manifest);
+        let (previous, endpoints) = {
+            let mut rule_engine = self.rule_engine.write().unwrap();
+            for (key, endpoint) in rule_engine.rules.endpoints.iter() {
+                reloaded
+                    .rules
+                    .endpoints
+                    .entry(key.clone())
+                    .or_insert_with(|| endpoint.clone());
+            }
+            for (method, rule) in rule_engine.rules.rules.iter() {
+                if matches!(
+                    rule.provenance,
+                    RuleProvenance::Service { .. }
+                        | RuleProvenance::Extn { .. }
+                        | RuleProvenance::Admin
+                ) {
+                    reloaded.rules.rules.insert(method.clone(), rule.clone());
+                }
+            }
+            reloaded.rules.index_patterns();
+            reloaded.ensure_service_endpoint();
+            reloaded.ensure_extn_endpoint();
+            let previous = std::mem::replace(&mut *rule_engine, reloaded)
+                .rules
+                .endpoints;
+            (previous, rule_engine.rules.endpoints.clone())
+        };
+        let session = ps.session_state.get_account_session();
+        for (key, endpoint) in endpoints {
+            if matches!(endpoint.protocol, RuleEndpointProtocol::Thunder) {
+                continue;
+            }
+            if self.endpoint_map.read().unwrap().contains_key(&key) {
+                if previous.get(&key) == Some(&endpoint) {
+                    continue;
+                }
+                // the broker running with the previous configuration stops once its sender
+                // is replaced
+                info!("Restarting the broker of endpoint {}", key);
+            }
+            let request = BrokerConnectRequest::new_with_sesssion(
+                key,
+                endpoint,
+                self.reconnect_tx.clone(),
+                session.clone(),
+            );
+            self.build_endpoint(Some(ps.clone()), request);
+        }
+        self.rule_engine.read().unwrap().rules.rules.len()
+    }
+
     fn add_endpoint(&mut self, key: String, endpoint: BrokerSender) -> &mut Self {
         {
             let mut endpoint_map = self.endpoint_map.write().unwrap();
@@ -712,7 +1069,20 @@ impl EndpointBrokerState {
         self.endpoint_map.read().unwrap().clone()
     }
 
+    /// Connection state of every endpoint, an endpoint is healthy while its broker is running
+    pub fn get_endpoint_health(&self) -> HashMap<String, bool> {
+        self.endpoint_map
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(key, endpoint)| (key.clone(), !endpoint.sender.is_closed()))
+            .collect()
+    }
+
     fn build_endpoint(&mut self, ps: Option<PlatformState>, request: BrokerConnectRequest) {
+        // brokers started at bootstrap, on reconnect and on rules reload share the runtime
+        let runtime_topology = self.runtime_topology.clone();
+        let _guard = runtime_topology.enter_broker();
         let endpoint = request.endpoint.clone();
         let key = request.key.clone();
         let (broker, cleaner) = match endpoint.protocol {
@@ -741,17 +1111,22 @@ impl EndpointBrokerState {
                 ExtnBroker::get_broker(ps, request, self.callback.clone(), self).get_sender(),
                 None,
             ),
-            RuleEndpointProtocol::Service => (
-                ServiceBroker::get_broker(ps, request, self.callback.clone(), self).get_sender(),
-                None,
-            ),
+            RuleEndpointProtocol::Service => {
+                let service_broker =
+                    ServiceBroker::get_broker(ps, request, self.callback.clone(), self);
+                (
+                    service_broker.get_sender(),
+                    Some(service_broker.get_cleaner()),
+                )
+            }
         };
-        self.add_endpoint(key, broker);
+        self.add_endpoint(key.clone(), broker);
 
-        if let Some(cleaner) = cleaner {
-            let mut cleaner_list = self.cleaner_list.write().unwrap();
-            cleaner_list.push(cleaner);
-        }
+        let mut cleaner_list = self.cleaner_list.write().unwrap();
+        match cleaner {
+            Some(cleaner) => cleaner_list.insert(key, cleaner),
+            None => cleaner_list.remove(&key),
+        };
     }
 
     fn handle_static_request(&self, rpc_request: RpcRequest) -> JsonRpcApiResponse {
@@ -827,7 +1202,10 @@ impl EndpointBrokerState {
         &self,
         rpc_request: &RpcRequest,
     ) -> Result<RuleRetrieved, RuleRetrievalError> {
-        self.rule_engine.read().unwrap().get_rule(rpc_request)
+        self.rule_engine
+            .read()
+            .unwrap()
+            .get_rule(rpc_request, &self.get_route_precedence())
     }
     /// Main handler method which checks for brokerage and then sends the request for
     /// asynchronous processing
@@ -957,14 +1335,21 @@ impl EndpointBrokerState {
         telemetry_response_listeners: Vec<Sender<BrokerOutput>>,
     ) -> Result<RenderedRequest, HandleBrokerageError> {
         /*if rule not found, "unhandled https://github.com/rdkcentral/Ripple/blob/ae3fcd78b055cf70022959bf827de9ed569762aa/core/main/src/broker/endpoint_broker.rs#L719" */
-        let rule: Rule = match self.get_broker_rule(&rpc_request)? {
-            RuleRetrieved::ExactMatch(rule) | RuleRetrieved::WildcardMatch(rule) => rule,
+        let (rule, wildcard_match) = match self.get_broker_rule(&rpc_request)? {
+            RuleRetrieved::ExactMatch(rule) => (rule, false),
+            RuleRetrieved::WildcardMatch(rule) => (rule, true),
         };
         /*
          attempt to get the endpoint from the rule
         https://github.com/rdkcentral/Ripple/blob/ae3fcd78b055cf70022959bf827de9ed569762aa/core/main/src/broker/endpoint_broker.rs#L722
         */
         let endpoint = self.get_endpoint(&rule, self.callback.clone())?;
+        self.explain_state.start(
+            &rpc_request.ctx,
+            &rule,
+            wildcard_match,
+            &Self::get_endpoint_name(&rule),
+        );
         LogSignal::new(
             "handle_brokerage_workflow".to_string(),
             "starting brokerage workflow".to_string(),
@@ -979,19 +1364,21 @@ impl EndpointBrokerState {
         broker_callback is used to send the response back to the caller
         */
         let broker_callback = self.callback.clone();
-
-        match self.render_brokered_request(
+        let broker_request = self.update_request(
+            &rpc_request,
             &rule,
-            &self.update_request(
-                &rpc_request,
-                &rule,
-                extn_message,
-                workflow_callback,
-                telemetry_response_listeners,
-            ),
-            permissions,
-            session.clone(),
-        ) {
+            extn_message,
+            workflow_callback,
+            telemetry_response_listeners,
+        );
+        if self
+            .explain_state
+            .is_explaining(&rpc_request.ctx.request_id)
+        {
+            self.explain_request(&broker_request);
+        }
+
+        match self.render_brokered_request(&rule, &broker_request, permissions, session.clone()) {
             Ok(response) => match response.clone() {
                 RenderedRequest::JsonRpc(data) => {
                     tokio::spawn(async move {
@@ -1014,7 +1401,14 @@ impl EndpointBrokerState {
                 RenderedRequest::Unlisten(data) => {
                     info!("Sending unlisten json rpc response to endpoint {:?}", data);
 
-                    if let Some(thunder) = self.get_sender("thunder") {
+                    // the service acknowledges the unlisten through the subscription response
+                    if self.is_service_rule(&rule) {
+                        tokio::spawn(async move {
+                            if let Err(e) = endpoint.send_request(data.clone()).await {
+                                broker_callback.send_error(data, e).await
+                            }
+                        });
+                    } else if let Some(thunder) = self.get_sender("thunder") {
                         tokio::spawn(async move {
                             match thunder.send(data.clone()).await {
                                 Ok(_) => {
@@ -1042,8 +1436,28 @@ impl EndpointBrokerState {
                         method: Some(request.rpc.method.clone()),
                         params: request.rpc.get_params(),
                     };
+                    if let (Some(deadline), false) =
+                        (request.rpc.ctx.deadline, request.rpc.is_subscription())
+                    {
+                        self.watch_deadline(request.rpc.ctx.call_id, deadline);
+                    }
                     let request_for_spawn = request.clone();
-                    tokio::spawn(async move { endpoint.send_request(request_for_spawn).await });
+                    tokio::spawn(async move {
+                        // no downstream work for a caller which stopped waiting
+                        if request_for_spawn.rpc.ctx.is_expired() {
+                            broker_callback
+                                .send_json_rpc_api_response(
+                                    JsonRpcApiResponse::builder(request_for_spawn.rpc.ctx.call_id)
+                                        .deadline_exceeded(format!(
+                                            "{} deadline exceeded",
+                                            request_for_spawn.rpc.method
+                                        )),
+                                )
+                                .await;
+                            return Ok(());
+                        }
+                        endpoint.send_request(request_for_spawn).await
+                    });
 
                     Ok(RenderedRequest::ProviderJsonRpc(data))
                 }
@@ -1075,15 +1489,55 @@ impl EndpointBrokerState {
         }
     }
 
+    fn get_endpoint_name(rule: &Rule) -> String {
+        match (&rule.endpoint, rule.rule_type()) {
+            (Some(endpoint), _) => endpoint.clone(),
+            (None, RuleType::Provider) => "provider".into(),
+            (None, _) => "thunder".into(),
+        }
+    }
+
+    fn explain_request(&self, broker_request: &BrokerRequest) {
+        let input = serde_json::from_str::<Vec<Value>>(&broker_request.rpc.params_json)
+            .ok()
+            .and_then(|mut params| params.pop())
+            .unwrap_or(Value::Null);
+        let output = match Self::apply_request_rule(broker_request) {
+            Ok(output) => output,
+            Err(e) => json!({ "error": format!("{:?}", e) }),
+        };
+        self.explain_state.add_stage(
+            &broker_request.rpc.ctx.request_id,
+            "request",
+            broker_request
+                .rule
+                .transform
+                .get_transform_data(super::rules::rules_engine::RuleTransformType::Request),
+            input,
+            output,
+        );
+    }
+
+    pub fn get_explain_state(&self) -> &RuleExplainState {
+        &self.explain_state
+    }
+
     pub fn handle_broker_response(&self, data: JsonRpcApiResponse) {
-        if let Err(e) = self.callback.sender.try_send(BrokerOutput { data }) {
+        if let Err(e) = self.callback.sender.try_send(BrokerOutput::new(data)) {
             error!("Cannot forward broker response {:?}", e)
         }
     }
 
     // Method to cleanup all subscription on App termination
     pub async fn cleanup_for_app(&self, app_id: &str) {
-        let cleaners = { self.cleaner_list.read().unwrap().clone() };
+        let cleaners: Vec<BrokerCleaner> = {
+            self.cleaner_list
+                .read()
+                .unwrap()
+                .values()
+                .cloned()
+                .collect()
+        };
 
         for cleaner in cleaners {
             /*
@@ -1148,10 +1602,11 @@ pub trait EndpointBroker {
                 .transform
                 .get_transform_data(super::rules::rules_engine::RuleTransformType::Request)
             {
-                let transformed_request_res = jq_compile(
+                let transformed_request_res = jq_compile_rule(
                     last,
                     &filter,
                     format!("{}_request", rpc_request.rpc.ctx.method),
+                    &rpc_request.rule.provenance,
                 );
 
                 LogSignal::new(
@@ -1161,6 +1616,10 @@ pub trait EndpointBroker {
                 )
                 .with_diagnostic_context_item("success", "true")
                 .with_diagnostic_context_item("result", &format!("{:?}", transformed_request_res))
+                .with_diagnostic_context_item(
+                    "provenance",
+                    &rpc_request.rule.provenance.to_string(),
+                )
                 .emit_debug();
 
                 return transformed_request_res;
@@ -1225,6 +1684,7 @@ impl BrokerOutputForwarder {
         let event_utility = Arc::new(EventManagementUtility::new());
         event_utility.register_custom_functions();
         let event_utility_clone = event_utility.clone();
+        let event_debouncer = EventDebouncer::default();
 
         tokio::spawn(async move {
             while let Some(output) = rx.recv().await {
@@ -1247,6 +1707,28 @@ impl BrokerOutputForwarder {
                         )
                         .emit_debug();
 
+                        if let Some(debounce) = broker_request.rule.event_debounce.as_ref() {
+                            if is_event
+                                && event_debouncer.check(
+                                    id,
+                                    debounce,
+                                    &output_c,
+                                    &platform_state.endpoint_state.callback.sender,
+                                ) != DebounceAction::Emit
+                            {
+                                continue;
+                            }
+                        }
+
+                        // the gateway answers and emits the region fields of other sources
+                        if is_event
+                            && platform_state
+                                .region_state
+                                .is_overridden_event(&broker_request.rpc.method)
+                        {
+                            continue;
+                        }
+
                         let rule_context_name = broker_request.rpc.method.clone();
                         let workflow_callback = broker_request.workflow_callback.clone();
                         let telemetry_response_listeners =
@@ -1301,12 +1783,27 @@ impl BrokerOutputForwarder {
                         };
 
                         if apply_response_needed {
+                            let explain_state = platform_state.endpoint_state.get_explain_state();
+                            let explain_input = explain_state
+                                .is_explaining(&rpc_request.ctx.request_id)
+                                .then(|| json!(response));
                             Self::apply_response_transform(
                                 &broker_request,
                                 &output_c,
                                 &mut response,
                                 &rule_context_name,
                             );
+                            if let Some(input) = explain_input {
+                                explain_state.add_stage(
+                                    &rpc_request.ctx.request_id,
+                                    "response",
+                                    broker_request.rule.transform.get_transform_data(
+                                        super::rules::rules_engine::RuleTransformType::Response,
+                                    ),
+                                    input,
+                                    json!(response),
+                                );
+                            }
                         }
 
                         response.id = Some(rpc_request.ctx.call_id);
@@ -1580,10 +2077,11 @@ impl BrokerOutputForwarder {
                     if key == "response" {
                         if let Some(filter) = value.as_str() {
                             apply_response_using_main_req_needed = false;
-                            apply_response(
+                            apply_rule_response(
                                 filter.to_string(),
                                 &broker_request.rpc.ctx.method,
                                 response,
+                                Some(&broker_request.rule.provenance),
                             );
                         }
                     }
@@ -1596,7 +2094,12 @@ impl BrokerOutputForwarder {
                 .transform
                 .get_transform_data(super::rules::rules_engine::RuleTransformType::Response)
             {
-                apply_response(filter, rule_context_name, response);
+                apply_rule_response(
+                    filter,
+                    rule_context_name,
+                    response,
+                    Some(&broker_request.rule.provenance),
+                );
             } else if response.result.is_none() && response.error.is_none() {
                 response.result = Some(Value::Null);
             }
@@ -1631,7 +2134,10 @@ impl BrokerOutputForwarder {
                     .unwrap_or_default(),
             )
             .unwrap();
-            let id = ServiceMessageId::String(message.request_id.clone());
+            let id = platform_state
+                .service_controller_state
+                .take_original_id(&message.request_id)
+                .await;
 
             let service_message = ServiceMessage {
                 message: ServiceJsonRpcMessage::Success(ServiceJsonRpcSuccess {
@@ -1823,14 +2329,27 @@ pub fn apply_response(
     result_response_filter: String,
     method: &str,
     response: &mut JsonRpcApiResponse,
+) {
+    apply_rule_response(result_response_filter, method, response, None)
+}
+
+/// Applies the response filter of a rule, transform failures carry the provenance of the rule
+pub fn apply_rule_response(
+    result_response_filter: String,
+    method: &str,
+    response: &mut JsonRpcApiResponse,
+    provenance: Option<&RuleProvenance>,
 ) {
     match serde_json::to_value(response.clone()) {
         Ok(input) => {
-            match jq_compile(
-                input,
-                &result_response_filter,
-                format!("{}_response", method),
-            ) {
+            let reference = format!("{}_response", method);
+            let output = match provenance {
+                Some(provenance) => {
+                    jq_compile_rule(input, &result_response_filter, reference, provenance)
+                }
+                None => jq_compile(input, &result_response_filter, reference),
+            };
+            match output {
                 Ok(jq_out) => {
                     trace!(
                         "jq rendered output {:?} original input {:?} for filter {}",
@@ -1849,10 +2368,15 @@ pub fn apply_response(
                     trace!("mutated response {:?}", response);
                 }
                 Err(e) => {
-                    response.error = Some(json!(e.to_string()));
+                    response.error = Some(match provenance {
+                        Some(provenance) => {
+                            json!(format!("{} (rule from {})", e, provenance.get_source()))
+                        }
+                        None => json!(e.to_string()),
+                    });
                     error!(
-                        "jq compile error: e={:?}, filter={}, response={:?}",
-                        e, result_response_filter, response
+                        "jq compile error: e={:?}, filter={}, provenance={:?}, response={:?}",
+                        e, result_response_filter, provenance, response
                     );
                 }
             }
@@ -1874,10 +2398,11 @@ pub fn apply_rule_for_event(
     filter: &str,
     response: &mut JsonRpcApiResponse,
 ) {
-    if let Ok(r) = jq_compile(
+    if let Ok(r) = jq_compile_rule(
         result.clone(),
         filter,
         format!("{}_event", rpc_request.ctx.method),
+        &broker_request.rule.provenance,
     ) {
         LogSignal::new(
             "apply_rule_for_event".to_string(),
@@ -1895,16 +2420,18 @@ pub fn apply_rule_for_event(
             broker_request.clone(),
         )
         .with_diagnostic_context_item("success", "false")
+        .with_diagnostic_context_item("provenance", &broker_request.rule.provenance.to_string())
         .emit_debug();
     }
 }
 
 fn apply_filter(broker_request: &BrokerRequest, result: &Value, rpc_request: &RpcRequest) -> bool {
     if let Some(filter) = broker_request.rule.filter.clone() {
-        if let Ok(r) = jq_compile(
+        if let Ok(r) = jq_compile_rule(
             result.clone(),
             &filter,
             format!("{}_event filter", rpc_request.ctx.method),
+            &broker_request.rule.provenance,
         ) {
             if r.is_null() {
                 return false;
@@ -1939,6 +2466,8 @@ mod endpoint_broker_tests {
                         filter: None,
                         event_handler: None,
                         sources: None,
+                        event_debounce: None,
+                        provenance: Default::default(),
                     },
                     subscription_processed: None,
                     workflow_callback: None,
@@ -1948,7 +2477,12 @@ mod endpoint_broker_tests {
             )
             .await;
         let value = tr.recv().await.unwrap();
-        assert!(value.data.error.is_some())
+        assert_eq!(
+            value.data.error.unwrap()["code"],
+            serde_json::json!(
+                ripple_sdk::api::firebolt::fb_capabilities::JSON_RPC_STANDARD_ERROR_INVALID_PARAMS
+            )
+        );
     }
 
     mod broker_output {
@@ -1989,10 +2523,10 @@ mod endpoint_broker_tests {
 
         use super::EndpointBrokerState;
         use crate::broker::endpoint_broker::BrokerConnectRequest;
-        use crate::broker::endpoint_broker::ATOMIC_ID;
         use crate::broker::rules::rules_engine::RuleEndpoint;
         use crate::broker::rules::rules_engine::RuleEndpointProtocol;
         use ripple_sdk::api::session::AccountSession;
+        use ripple_sdk::utils::id_allocator::NEXT_ID as ATOMIC_ID;
         use std::{collections::HashMap, sync::atomic::Ordering};
 
         fn reset_counter(value: u64) {
@@ -2415,6 +2949,31 @@ mod endpoint_broker_tests {
         //     // assert!(state.get_request(2).is_ok());
         //     // assert!(state.get_request(1).is_ok());
         // }
+
+        #[test]
+        fn test_restore_displaced_rules() {
+            let state = EndpointBrokerState::default();
+            state.add_service_rules("service1", &["player.play".into(), "player.stop".into()]);
+            state.resolve_service_rules("service2", |rules| {
+                let taken_over = rules
+                    .find_overlapping_rules("player.pl*")
+                    .into_iter()
+                    .map(|(rule_name, _)| (rule_name, "player.pl*".to_owned()))
+                    .collect();
+                (vec!["player.pl*".into()], taken_over, ())
+            });
+            assert_eq!(
+                state.get_service_registered_methods("service1"),
+                vec!["player.stop".to_string()]
+            );
+
+            // the method goes back to its owner once the winner leaves
+            state.remove_service_rules("service2");
+            assert_eq!(
+                state.get_service_registered_methods("service1"),
+                vec!["player.play".to_string(), "player.stop".to_string()]
+            );
+        }
     }
 
     #[tokio::test]
@@ -2644,6 +3203,8 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             };
             state.update_request(&rpc_request, &rule, None, None, vec![]);
             apply_response(filter, &rpc_request.ctx.method, &mut output.data);
@@ -2689,6 +3250,8 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             };
             state.update_request(&rpc_request, &rule, None, None, vec![]);
             apply_response(filter, &rpc_request.ctx.method, &mut output.data);
@@ -2736,6 +3299,8 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             };
             state.update_request(&rpc_request, &rule, None, None, vec![]);
             apply_response(filter, &rpc_request.ctx.method, &mut output.data);
@@ -2782,6 +3347,8 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             };
             state.update_request(&rpc_request, &rule, None, None, vec![]);
             apply_response(filter, &rpc_request.ctx.method, &mut output.data);
@@ -2829,6 +3396,8 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             };
             state.update_request(&rpc_request, &rule, None, None, vec![]);
             apply_response(filter, &rpc_request.ctx.method, &mut output.data);
@@ -2867,6 +3436,8 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             };
             engine.add_rule(r);
 
@@ -3011,6 +3582,8 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             };
             engine.add_rule(rule);
             let mut under_test =
@@ -3064,6 +3637,8 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             };
             engine.add_rule(rule);
             let under_test = EndpointBrokerState::new(OpMetricState::default(), tx, engine, client);
@@ -3116,6 +3691,8 @@ mod endpoint_broker_tests {
                     filter: None,
                     event_handler: None,
                     sources: None,
+                    event_debounce: None,
+                    provenance: Default::default(),
                 };
 
                 let broker_request = state.update_request(&rpc_request, &rule, None, None, vec![]);
@@ -3123,6 +3700,39 @@ mod endpoint_broker_tests {
                 assert_eq!(broker_request.rule.alias, rule.alias);
             }
 
+            #[tokio::test]
+            async fn test_fail_requests() {
+                let (tx, mut rx) = channel(2);
+                let client = RippleClient::new(ChannelsState::new());
+                let state = EndpointBrokerState::new(
+                    OpMetricState::default(),
+                    tx,
+                    RuleEngine {
+                        rules: RuleSet::default(),
+                        functions: HashMap::default(),
+                    },
+                    client,
+                );
+                let rpc_request = RpcRequest::mock();
+                let rule = Rule {
+                    alias: "test.method".to_owned(),
+                    ..Default::default()
+                };
+                let broker_request = state.update_request(&rpc_request, &rule, None, None, vec![]);
+
+                let failed = state
+                    .fail_requests(&rpc_request.ctx.request_id, "stuck")
+                    .await;
+                assert_eq!(failed, vec![broker_request.rpc.ctx.call_id]);
+                let output = rx.recv().await.unwrap();
+                assert_eq!(
+                    output.data.error.unwrap()["code"],
+                    serde_json::json!(
+                        ripple_sdk::api::gateway::rpc_response::JSON_RPC_STANDARD_ERROR_INTERNAL
+                    )
+                );
+            }
+
             #[tokio::test]
             async fn test_update_request_with_extn_message() {
                 let (tx, _) = channel(2);
@@ -3145,6 +3755,8 @@ mod endpoint_broker_tests {
                     filter: None,
                     event_handler: None,
                     sources: None,
+                    event_debounce: None,
+                    provenance: Default::default(),
                 };
                 let extn_message = Some(ExtnMessage::default());
 
@@ -3176,6 +3788,8 @@ mod endpoint_broker_tests {
                     filter: None,
                     event_handler: None,
                     sources: None,
+                    event_debounce: None,
+                    provenance: Default::default(),
                 };
                 let workflow_callback = Some(BrokerCallback::default());
 
@@ -3213,6 +3827,8 @@ mod endpoint_broker_tests {
                     filter: None,
                     event_handler: None,
                     sources: None,
+                    event_debounce: None,
+                    provenance: Default::default(),
                 };
                 let telemetry_response_listeners = vec![channel(2).0];
 
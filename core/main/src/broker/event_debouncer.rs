@@ -0,0 +1,187 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use ripple_sdk::{
+    log::error,
+    tokio::{self, sync::mpsc::Sender},
+};
+
+use super::{endpoint_broker::BrokerOutput, rules::rules_engine::EventDebounce};
+
+#[derive(Debug, PartialEq)]
+pub enum DebounceAction {
+    Emit,
+    Drop,
+    Hold,
+}
+
+#[derive(Debug)]
+struct DebounceWindow {
+    last_emit: Instant,
+    interval: Duration,
+    pending: Option<BrokerOutput>,
+    flushing: bool,
+}
+
+/// Debounces the events of brokered subscriptions which have an `event_debounce` in their
+/// rule. Held events are sent back to the broker output channel once the interval
+/// elapses, so they go through the same forwarding path as the original event.
+#[derive(Debug, Clone, Default)]
+pub struct EventDebouncer {
+    windows: Arc<Mutex<HashMap<u64, DebounceWindow>>>,
+}
+
+impl EventDebouncer {
+    pub fn check(
+        &self,
+        id: u64,
+        debounce: &EventDebounce,
+        output: &BrokerOutput,
+        sender: &Sender<BrokerOutput>,
+    ) -> DebounceAction {
+        let now = Instant::now();
+        let interval = Duration::from_millis(debounce.interval_ms);
+        let mut windows = self.windows.lock().unwrap();
+        // Forget windows of subscriptions which went quiet
+        windows.retain(|_, w| {
+            w.flushing || w.pending.is_some() || now.duration_since(w.last_emit) < w.interval
+        });
+
+        let window = match windows.get_mut(&id) {
+            Some(window) => window,
+            None => {
+                windows.insert(
+                    id,
+                    DebounceWindow {
+                        last_emit: now,
+                        interval,
+                        pending: None,
+                        flushing: false,
+                    },
+                );
+                return DebounceAction::Emit;
+            }
+        };
+
+        if output.debounce_flush {
+            window.flushing = false;
+            window.last_emit = now;
+            if window.pending.is_some() {
+                self.schedule_flush(id, interval, sender.clone());
+            }
+            return DebounceAction::Emit;
+        }
+
+        let elapsed = now.duration_since(window.last_emit);
+        // Newer events wait for a flush in flight so they are never delivered before it
+        if elapsed >= interval && !window.flushing && window.pending.is_none() {
+            window.last_emit = now;
+            return DebounceAction::Emit;
+        }
+        if !debounce.coalesce {
+            return DebounceAction::Drop;
+        }
+        if window.pending.replace(output.clone()).is_none() && !window.flushing {
+            self.schedule_flush(id, interval.saturating_sub(elapsed), sender.clone());
+        }
+        DebounceAction::Hold
+    }
+
+    fn schedule_flush(&self, id: u64, delay: Duration, sender: Sender<BrokerOutput>) {
+        let windows = self.windows.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let pending = {
+                let mut windows = windows.lock().unwrap();
+                windows.get_mut(&id).and_then(|w| {
+                    w.flushing = w.pending.is_some();
+                    w.pending.take()
+                })
+            };
+            if let Some(mut output) = pending {
+                output.debounce_flush = true;
+                if let Err(e) = sender.try_send(output) {
+                    error!("Unable to flush debounced event {}: {:?}", id, e);
+                    if let Some(w) = windows.lock().unwrap().get_mut(&id) {
+                        w.flushing = false;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_event_debounce() {
+        let debouncer = EventDebouncer::default();
+        let (tx, mut rx) = mpsc::channel(2);
+        let drop = EventDebounce {
+            interval_ms: 50,
+            coalesce: false,
+        };
+        let output = BrokerOutput::default();
+        assert_eq!(
+            debouncer.check(1, &drop, &output, &tx),
+            DebounceAction::Emit
+        );
+        assert_eq!(
+            debouncer.check(1, &drop, &output, &tx),
+            DebounceAction::Drop
+        );
+
+        let coalesce = EventDebounce {
+            interval_ms: 50,
+            coalesce: true,
+        };
+        assert_eq!(
+            debouncer.check(2, &coalesce, &output, &tx),
+            DebounceAction::Emit
+        );
+        assert_eq!(
+            debouncer.check(2, &coalesce, &output, &tx),
+            DebounceAction::Hold
+        );
+        assert_eq!(
+            debouncer.check(2, &coalesce, &output, &tx),
+            DebounceAction::Hold
+        );
+        // only the latest held event is flushed
+        let flushed = rx.recv().await.unwrap();
+        assert!(flushed.debounce_flush);
+        // events arriving before the flush is forwarded wait for it
+        assert_eq!(
+            debouncer.check(2, &coalesce, &output, &tx),
+            DebounceAction::Hold
+        );
+        assert_eq!(
+            debouncer.check(2, &coalesce, &flushed, &tx),
+            DebounceAction::Emit
+        );
+        assert!(rx.recv().await.unwrap().debounce_flush);
+    }
+}
@@ -19,6 +19,7 @@ use super::endpoint_broker::{
     EndpointBroker, EndpointBrokerState, BROKER_CHANNEL_BUFFER_SIZE,
 };
 use crate::state::platform_state::PlatformState;
+use ripple_sdk::api::firebolt::fb_capabilities::JSON_RPC_STANDARD_ERROR_INVALID_PARAMS;
 use ripple_sdk::api::gateway::rpc_gateway_api::JsonRpcApiError;
 use ripple_sdk::extn::extn_client_message::ExtnResponse;
 use ripple_sdk::extn::extn_id::ExtnProviderRequest;
@@ -63,17 +64,34 @@ impl ExtnBroker {
                     }
                 };
 
-                let request = ExtnProviderRequest {
-                    value: serde_json::to_value(rpc_request.clone()).unwrap(),
-                    id: id.clone(),
-                };
-
                 let client = if let Some(platform_state) = &ps {
+                    if let Err(e) = platform_state
+                        .extn_method_state
+                        .validate_params(&rpc_request.method, rpc_request.get_params().as_ref())
+                    {
+                        Self::log_error_and_send_broker_failure_response(
+                            broker_request.clone(),
+                            &callback,
+                            JsonRpcApiError::default()
+                                .with_code(JSON_RPC_STANDARD_ERROR_INVALID_PARAMS)
+                                .with_message(format!(
+                                    "Invalid params for api {}: {}",
+                                    broker_request.rpc.method, e
+                                ))
+                                .with_id(broker_request.rpc.ctx.call_id),
+                        );
+                        continue;
+                    }
                     platform_state.get_client()
                 } else {
                     return;
                 };
 
+                let request = ExtnProviderRequest {
+                    value: serde_json::to_value(rpc_request.clone()).unwrap(),
+                    id: id.clone(),
+                };
+
                 match client.send_extn_request(request.clone()).await {
                     Ok(response) => {
                         if let Some(ExtnResponse::String(v)) = response.payload.extract() {
@@ -19,7 +19,7 @@ use std::vec;
 
 use hyper::{client::HttpConnector, Body, Client, Method, Request, Response, Uri};
 use ripple_sdk::{
-    api::{gateway::rpc_gateway_api::JsonRpcApiError, observability::log_signal::LogSignal},
+    api::{gateway::rpc_gateway_api::JsonRpcApiResponse, observability::log_signal::LogSignal},
     log::{debug, error},
     tokio::{self, sync::mpsc},
     utils::error::RippleError,
@@ -32,7 +32,7 @@ use super::endpoint_broker::{
 };
 
 use crate::{
-    broker::rules::rules_engine::{jq_compile, RuleTransformType},
+    broker::rules::rules_engine::{jq_compile_rule, RuleTransformType},
     state::platform_state::PlatformState,
 };
 
@@ -73,10 +73,11 @@ async fn send_http_request(
                 }
             };
 
-        let body_val = jq_compile(
+        let body_val = jq_compile_rule(
             transform_params,
             &request_transform,
             format!("{}_http_post", broker_request.rpc.ctx.method),
+            &broker_request.rule.provenance,
         )?;
 
         body = Body::from(body_val.to_string());
@@ -94,7 +95,14 @@ async fn send_http_request(
         .body(body)
         .map_err(|e| RippleError::BrokerError(e.to_string()))?;
 
-    match client.request(http_request).await {
+    // the downstream timeout shrinks to what is left of the caller deadline
+    let response = match broker_request.rpc.ctx.get_remaining_budget() {
+        Some(budget) => tokio::time::timeout(budget, client.request(http_request))
+            .await
+            .map_err(|_| RippleError::TimeoutError)?,
+        None => client.request(http_request).await,
+    };
+    match response {
         Ok(v) => Ok(v),
         Err(e) => {
             error!("Error in server");
@@ -178,9 +186,8 @@ impl EndpointBroker for HttpBroker {
                                     .with_diagnostic_context_item("error", &msg)
                                     .emit_error();
                                 Self::send_broker_failure_response(&callback,
-                                    JsonRpcApiError::default()
-                                    .with_id(request.rpc.ctx.call_id)
-                                    .with_message(msg.to_string()).into());
+                                    JsonRpcApiResponse::builder(request.rpc.ctx.call_id)
+                                    .internal_error(msg));
                             }
                         } else {
                             send_broker_response(&callback, &request, &body).await.ok();
@@ -233,7 +240,10 @@ mod tests {
     use super::*;
 
     use ripple_sdk::{
-        api::gateway::rpc_gateway_api::{JsonRpcApiResponse, RpcRequest},
+        api::gateway::{
+            rpc_gateway_api::{JsonRpcApiResponse, RpcRequest},
+            rpc_response::JSON_RPC_STANDARD_ERROR_INTERNAL,
+        },
         tokio::{runtime::Runtime, task::JoinHandle, time::timeout},
         Mockable,
     };
@@ -455,6 +465,10 @@ mod tests {
                 &output.data,
                 "Error in http broker parsing response from http service at"
             ));
+            assert_eq!(
+                output.data.error.unwrap()["code"],
+                json!(JSON_RPC_STANDARD_ERROR_INTERNAL)
+            );
         } else {
             panic!("Timeout or channel closed without receiving data");
         }
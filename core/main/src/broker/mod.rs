@@ -16,10 +16,13 @@
 //
 pub mod broker_utils;
 pub mod endpoint_broker;
+pub mod event_debouncer;
 pub mod event_management_utility;
 pub mod extn_broker;
 pub mod http_broker;
 pub mod provider_broker_state;
+pub mod request_deadlines;
+pub mod rule_explain;
 pub mod rules;
 pub mod service_broker;
 #[cfg(test)]
@@ -0,0 +1,94 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{cmp::Reverse, collections::BinaryHeap, time::Instant};
+
+use ripple_sdk::tokio::{self, sync::mpsc};
+
+/// Deadlines of brokered requests, served by a single task which waits for the earliest one.
+/// Most requests are answered well before their deadline, `on_expired` gets the id of every
+/// request once its deadline passed and decides whether it still needs to be failed.
+#[derive(Debug, Clone)]
+pub struct RequestDeadlines {
+    sender: mpsc::UnboundedSender<(Instant, u64)>,
+}
+
+impl RequestDeadlines {
+    pub fn start<F>(on_expired: F) -> Self
+    where
+        F: Fn(u64) + Send + 'static,
+    {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<(Instant, u64)>();
+        tokio::spawn(async move {
+            let mut deadlines = BinaryHeap::new();
+            loop {
+                let next = deadlines
+                    .peek()
+                    .map(|Reverse((deadline, _)): &Reverse<(Instant, u64)>| *deadline);
+                tokio::select! {
+                    received = receiver.recv() => match received {
+                        Some(deadline) => deadlines.push(Reverse(deadline)),
+                        None => break,
+                    },
+                    _ = Self::sleep_until(next), if next.is_some() => {
+                        let now = Instant::now();
+                        while let Some(Reverse((deadline, id))) = deadlines.peek().copied() {
+                            if deadline > now {
+                                break;
+                            }
+                            deadlines.pop();
+                            on_expired(id);
+                        }
+                    }
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    async fn sleep_until(deadline: Option<Instant>) {
+        if let Some(deadline) = deadline {
+            tokio::time::sleep_until(deadline.into()).await;
+        }
+    }
+
+    pub fn add(&self, id: u64, deadline: Instant) {
+        let _ = self.sender.send((deadline, id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn test_request_deadlines() {
+        let expired = Arc::new(Mutex::new(Vec::new()));
+        let expired_c = expired.clone();
+        let deadlines = RequestDeadlines::start(move |id| expired_c.lock().unwrap().push(id));
+        let now = Instant::now();
+        deadlines.add(1, now + Duration::from_millis(60));
+        deadlines.add(2, now + Duration::from_millis(20));
+        deadlines.add(3, now + Duration::from_secs(60));
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(*expired.lock().unwrap(), vec![2, 1]);
+    }
+}
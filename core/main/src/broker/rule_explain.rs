@@ -0,0 +1,218 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use ripple_sdk::{
+    api::gateway::rpc_gateway_api::{ApiMessage, CallContext},
+    log::info,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::rules::rules_engine::{Rule, RuleType};
+
+/// Explanations which were never attached to a response are dropped after this long
+const EXPLANATION_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetRuleExplainParams {
+    pub app_id: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainStage {
+    pub stage: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    pub input: Value,
+    pub output: Value,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleExplanation {
+    pub method: String,
+    pub rule: Value,
+    pub rule_type: String,
+    pub wildcard_match: bool,
+    pub endpoint: String,
+    pub stages: Vec<ExplainStage>,
+    pub total_ms: u64,
+    #[serde(skip)]
+    started: Instant,
+}
+
+/// Explain mode records how a brokered request was handled: the rule which matched, the
+/// endpoint chosen and the request and response transforms with their input, output and
+/// timing. The explanation is added to the response as an `explain` field, so it also
+/// shows up in the inspector frames of the app.
+///
+/// Explain mode is toggled per app through the `ripple.setRuleExplain` admin method.
+#[derive(Debug, Clone, Default)]
+pub struct RuleExplainState {
+    apps: Arc<RwLock<HashSet<String>>>,
+    explanations: Arc<RwLock<HashMap<String, RuleExplanation>>>,
+}
+
+impl RuleExplainState {
+    pub fn set_enabled(&self, app_id: &str, enabled: bool) {
+        info!("Rule explain mode for {} enabled={}", app_id, enabled);
+        let mut apps = self.apps.write().unwrap();
+        if enabled {
+            apps.insert(app_id.to_owned());
+        } else {
+            apps.remove(app_id);
+            self.explanations
+                .write()
+                .unwrap()
+                .retain(|_, e| e.started.elapsed() < EXPLANATION_TTL);
+        }
+    }
+
+    pub fn is_enabled(&self, app_id: &str) -> bool {
+        self.apps.read().unwrap().contains(app_id)
+    }
+
+    pub fn is_explaining(&self, request_id: &str) -> bool {
+        self.explanations.read().unwrap().contains_key(request_id)
+    }
+
+    /// Starts the explanation of a request if explain mode is enabled for the calling app
+    pub fn start(&self, ctx: &CallContext, rule: &Rule, wildcard_match: bool, endpoint: &str) {
+        if !self.is_enabled(&ctx.app_id) {
+            return;
+        }
+        let rule_type = match rule.rule_type() {
+            RuleType::Static => "static",
+            RuleType::Provider => "provider",
+            RuleType::Endpoint => "endpoint",
+        };
+        let explanation = RuleExplanation {
+            method: ctx.method.clone(),
+            rule: serde_json::to_value(rule).unwrap_or_default(),
+            rule_type: rule_type.into(),
+            wildcard_match,
+            endpoint: endpoint.to_owned(),
+            stages: Vec::new(),
+            total_ms: 0,
+            started: Instant::now(),
+        };
+        let mut explanations = self.explanations.write().unwrap();
+        explanations.retain(|_, e| e.started.elapsed() < EXPLANATION_TTL);
+        explanations.insert(ctx.request_id.clone(), explanation);
+    }
+
+    pub fn add_stage(
+        &self,
+        request_id: &str,
+        stage: &str,
+        filter: Option<String>,
+        input: Value,
+        output: Value,
+    ) {
+        if let Some(explanation) = self.explanations.write().unwrap().get_mut(request_id) {
+            explanation.stages.push(ExplainStage {
+                stage: stage.to_owned(),
+                filter,
+                input,
+                output,
+                elapsed_ms: explanation.started.elapsed().as_millis() as u64,
+            });
+        }
+    }
+
+    pub fn take(&self, request_id: &str) -> Option<RuleExplanation> {
+        let mut explanation = self.explanations.write().unwrap().remove(request_id)?;
+        explanation.total_ms = explanation.started.elapsed().as_millis() as u64;
+        Some(explanation)
+    }
+
+    /// Adds the explanation of the request to the response message
+    pub fn attach(&self, api_message: &mut ApiMessage) {
+        let explanation = match self.take(&api_message.request_id) {
+            Some(explanation) => explanation,
+            None => return,
+        };
+        if let Ok(Value::Object(mut response)) =
+            serde_json::from_str::<Value>(&api_message.jsonrpc_msg)
+        {
+            if let Ok(explain) = serde_json::to_value(explanation) {
+                response.insert("explain".into(), explain);
+            }
+            if let Ok(msg) = serde_json::to_string(&response) {
+                api_message.jsonrpc_msg = msg;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{api::gateway::rpc_gateway_api::ApiProtocol, Mockable};
+    use serde_json::json;
+
+    #[test]
+    fn test_rule_explanation() {
+        let state = RuleExplainState::default();
+        let mut ctx = CallContext::mock();
+        ctx.method = "device.name".into();
+        let rule = Rule {
+            alias: "org.rdk.System.getFriendlyName".into(),
+            ..Default::default()
+        };
+
+        // not enabled for the app
+        state.start(&ctx, &rule, false, "thunder");
+        assert!(!state.is_explaining(&ctx.request_id));
+
+        state.set_enabled(&ctx.app_id, true);
+        state.start(&ctx, &rule, false, "thunder");
+        state.add_stage(
+            &ctx.request_id,
+            "response",
+            Some(".result.friendlyName".into()),
+            json!({"result": {"friendlyName": "Living Room"}}),
+            json!("Living Room"),
+        );
+
+        let mut api_message = ApiMessage::new(
+            ApiProtocol::JsonRpc,
+            r#"{"jsonrpc":"2.0","id":1,"result":"Living Room"}"#.into(),
+            ctx.request_id.clone(),
+        );
+        state.attach(&mut api_message);
+        let response: Value = serde_json::from_str(&api_message.jsonrpc_msg).unwrap();
+        assert_eq!(response["result"], "Living Room");
+        assert_eq!(response["explain"]["method"], "device.name");
+        assert_eq!(response["explain"]["endpoint"], "thunder");
+        assert_eq!(response["explain"]["stages"][0]["output"], "Living Room");
+        assert!(!state.is_explaining(&ctx.request_id));
+
+        state.set_enabled(&ctx.app_id, false);
+        assert!(!state.is_enabled(&ctx.app_id));
+    }
+}
@@ -0,0 +1,618 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex, OnceLock, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use ripple_sdk::{
+    api::manifest::device_manifest::{JqLimits, JqSandboxConfiguration},
+    log::{error, warn},
+    serde_json::Value,
+    tokio::runtime::{Handle, RuntimeFlavor},
+    utils::error::RippleError,
+};
+use serde::{Deserialize, Serialize};
+
+static JQ_SANDBOX: OnceLock<JqSandbox> = OnceLock::new();
+
+/// Evaluations waiting for a worker, beyond these the sandbox fails right away
+const JQ_QUEUE_SIZE: usize = 16;
+
+/// jaq parses and evaluates filters recursively on the stack of the worker, the filter depth
+/// limit keeps the parsing well within it
+const JQ_WORKER_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetJqSandboxParams {
+    pub enabled: Option<bool>,
+    /// Applies the limits to this rule only, instead of the default limits
+    pub rule: Option<String>,
+    pub limits: Option<JqLimits>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseJqRuleParams {
+    pub rule: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JqSandboxStatus {
+    pub config: JqSandboxConfiguration,
+    pub quarantined: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JqViolation {
+    Runtime,
+    OutputSize,
+    Nesting,
+    FilterDepth,
+}
+
+/// Runs on a worker, returns false when the worker was replaced while the job was running
+type JqJob = Box<dyn FnOnce() -> bool + Send + 'static>;
+
+enum JqProgress {
+    Started,
+    Done(Result<Value, RippleError>),
+}
+
+const JQ_JOB_RUNNING: u8 = 0;
+const JQ_JOB_DONE: u8 = 1;
+const JQ_JOB_ABANDONED: u8 = 2;
+const JQ_JOB_TIMED_OUT: u8 = 3;
+
+/// Fixed pool of threads evaluating the filters. A worker stuck in a filter running past its
+/// runtime is replaced right away and exits once the filter finishes, so runaway filters
+/// neither exhaust the pool nor keep the threads they were running on. At most as many
+/// workers as the pool has are replaced at a time, past that a stuck worker stays in the pool
+/// and serves again once its filter finishes, so filters of rules which are never quarantined
+/// cannot pile up threads.
+#[derive(Debug)]
+struct JqWorkers {
+    jobs: mpsc::SyncSender<JqJob>,
+    rx: Arc<Mutex<mpsc::Receiver<JqJob>>>,
+    next_id: AtomicUsize,
+    count: usize,
+    stuck: Arc<AtomicUsize>,
+}
+
+impl JqWorkers {
+    fn start(count: usize) -> JqWorkers {
+        let (jobs, rx) = mpsc::sync_channel::<JqJob>(JQ_QUEUE_SIZE);
+        let workers = JqWorkers {
+            jobs,
+            rx: Arc::new(Mutex::new(rx)),
+            next_id: AtomicUsize::new(0),
+            count: count.max(1),
+            stuck: Arc::new(AtomicUsize::new(0)),
+        };
+        for _ in 0..workers.count {
+            workers.spawn();
+        }
+        workers
+    }
+
+    fn spawn(&self) {
+        let rx = self.rx.clone();
+        let stuck = self.stuck.clone();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        if let Err(e) = thread::Builder::new()
+            .name(format!("ripple-jq-{}", id))
+            .stack_size(JQ_WORKER_STACK_SIZE)
+            .spawn(move || loop {
+                let job = match rx.lock().unwrap().recv() {
+                    Ok(job) => job,
+                    Err(_) => break,
+                };
+                if !job() {
+                    stuck.fetch_sub(1, Ordering::AcqRel);
+                    break;
+                }
+            })
+        {
+            error!("Unable to start the jq worker {:?}", e);
+        }
+    }
+
+    /// Gives up on the job running past its runtime, returns whether its worker was replaced
+    fn abandon(&self, state: &AtomicU8) -> bool {
+        let replace = self
+            .stuck
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |stuck| {
+                (stuck < self.count).then_some(stuck + 1)
+            })
+            .is_ok();
+        let abandoned = if replace {
+            JQ_JOB_ABANDONED
+        } else {
+            JQ_JOB_TIMED_OUT
+        };
+        if state
+            .compare_exchange(
+                JQ_JOB_RUNNING,
+                abandoned,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_err()
+        {
+            // the job finished meanwhile, its worker keeps serving
+            if replace {
+                self.stuck.fetch_sub(1, Ordering::AcqRel);
+            }
+            return false;
+        }
+        if replace {
+            self.spawn();
+        }
+        replace
+    }
+}
+
+/// Runs the jq filters of the rules supplied at runtime within the limits from the device
+/// manifest.
+///
+/// The filter is evaluated on the worker pool so a slow filter can be abandoned once it
+/// exceeds its runtime. Rules are identified by the method part of the jq reference, so the
+/// request, response and event filters of a rule share their violations.
+#[derive(Debug, Default)]
+pub struct JqSandbox {
+    config: RwLock<JqSandboxConfiguration>,
+    violations: RwLock<HashMap<String, u32>>,
+    quarantined: RwLock<HashSet<String>>,
+    workers: OnceLock<JqWorkers>,
+}
+
+impl JqSandbox {
+    pub fn get() -> &'static JqSandbox {
+        JQ_SANDBOX.get_or_init(JqSandbox::default)
+    }
+
+    pub fn configure(&self, config: JqSandboxConfiguration) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// Updates the sandbox at runtime, the next evaluation picks up the new limits
+    pub fn update(&self, params: SetJqSandboxParams) {
+        let mut config = self.config.write().unwrap();
+        if let Some(enabled) = params.enabled {
+            config.enabled = enabled;
+        }
+        if let Some(limits) = params.limits {
+            match params.rule {
+                Some(rule) => {
+                    config.rule_limits.insert(rule.to_lowercase(), limits);
+                }
+                None => config.limits = limits,
+            }
+        }
+    }
+
+    pub fn get_status(&self) -> JqSandboxStatus {
+        JqSandboxStatus {
+            config: self.config.read().unwrap().clone(),
+            quarantined: self.get_quarantined(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().unwrap().enabled
+    }
+
+    pub fn get_quarantined(&self) -> Vec<String> {
+        let mut rules: Vec<String> = self.quarantined.read().unwrap().iter().cloned().collect();
+        rules.sort();
+        rules
+    }
+
+    /// Releases the rule from the quarantine and resets its violations
+    pub fn release(&self, rule: &str) -> bool {
+        let rule = rule.to_lowercase();
+        self.violations.write().unwrap().remove(&rule);
+        self.quarantined.write().unwrap().remove(&rule)
+    }
+
+    pub fn is_quarantined(&self, rule: &str) -> bool {
+        self.quarantined.read().unwrap().contains(rule)
+    }
+
+    fn get_rule(reference: &str) -> String {
+        reference
+            .split('_')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase()
+    }
+
+    fn record_violation(&self, rule: &str, violation: JqViolation) {
+        let threshold = self.config.read().unwrap().quarantine_threshold;
+        let count = {
+            let mut violations = self.violations.write().unwrap();
+            let count = violations.entry(rule.to_owned()).or_insert(0);
+            *count += 1;
+            *count
+        };
+        warn!(
+            "jq rule {} exceeded its {:?} limit, violations={}",
+            rule, violation, count
+        );
+        if threshold > 0
+            && count >= threshold
+            && self.quarantined.write().unwrap().insert(rule.to_owned())
+        {
+            error!("jq rule {} quarantined after {} violations", rule, count);
+        }
+    }
+
+    fn check_filter(limits: &JqLimits, filter: &str) -> Option<JqViolation> {
+        if get_filter_depth(filter) > limits.max_filter_depth {
+            return Some(JqViolation::FilterDepth);
+        }
+        None
+    }
+
+    fn check(limits: &JqLimits, value: &Value) -> Option<JqViolation> {
+        if get_nesting(value) > limits.max_nesting {
+            return Some(JqViolation::Nesting);
+        }
+        None
+    }
+
+    /// Waits for the worker, moving the other tasks of a multi threaded runtime off this
+    /// thread meanwhile
+    fn wait(
+        rx: &mpsc::Receiver<JqProgress>,
+        timeout: Duration,
+    ) -> Result<JqProgress, mpsc::RecvTimeoutError> {
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                ripple_sdk::tokio::task::block_in_place(|| rx.recv_timeout(timeout))
+            }
+            _ => rx.recv_timeout(timeout),
+        }
+    }
+
+    /// Evaluates the filter within the limits of the rule, quarantined rules fail right away
+    pub fn evaluate<F>(
+        &self,
+        reference: &str,
+        filter: &str,
+        input: Value,
+        eval: F,
+    ) -> Result<Value, RippleError>
+    where
+        F: FnOnce(Value) -> Result<Value, RippleError> + Send + 'static,
+    {
+        let rule = Self::get_rule(reference);
+        if self.is_quarantined(&rule) {
+            warn!("jq rule {} is quarantined", rule);
+            return Err(RippleError::RuleError);
+        }
+        let limits = self.config.read().unwrap().get_limits(&rule);
+        if let Some(violation) =
+            Self::check_filter(&limits, filter).or_else(|| Self::check(&limits, &input))
+        {
+            self.record_violation(&rule, violation);
+            return Err(RippleError::RuleError);
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let state = Arc::new(AtomicU8::new(JQ_JOB_RUNNING));
+        let job_state = state.clone();
+        let job: JqJob = Box::new(move || {
+            // evaluations abandoned while queued are skipped
+            if tx.send(JqProgress::Started).is_err() {
+                return true;
+            }
+            let output = eval(input);
+            if job_state
+                .compare_exchange(
+                    JQ_JOB_RUNNING,
+                    JQ_JOB_DONE,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_err()
+            {
+                return job_state.load(Ordering::Acquire) != JQ_JOB_ABANDONED;
+            }
+            let _ = tx.send(JqProgress::Done(output));
+            true
+        });
+        let max_workers = self.config.read().unwrap().max_workers;
+        let workers = self.workers.get_or_init(|| JqWorkers::start(max_workers));
+        if workers.jobs.try_send(job).is_err() {
+            warn!("jq workers are busy, unable to evaluate rule {}", rule);
+            return Err(RippleError::RuleError);
+        }
+        let runtime = Duration::from_millis(limits.max_runtime_ms);
+        // the time spent waiting for a worker is not held against the rule
+        if !matches!(Self::wait(&rx, runtime), Ok(JqProgress::Started)) {
+            warn!("jq workers are busy, rule {} timed out in the queue", rule);
+            return Err(RippleError::TimeoutError);
+        }
+        let output = match Self::wait(&rx, runtime) {
+            Ok(JqProgress::Done(output)) => output?,
+            _ => {
+                if workers.abandon(&state) {
+                    warn!("jq rule {} is stuck, replacing its worker", rule);
+                } else {
+                    warn!(
+                        "jq rule {} is stuck, too many workers replaced already",
+                        rule
+                    );
+                }
+                self.record_violation(&rule, JqViolation::Runtime);
+                return Err(RippleError::TimeoutError);
+            }
+        };
+
+        if let Some(violation) = Self::check(&limits, &output) {
+            self.record_violation(&rule, violation);
+            return Err(RippleError::RuleError);
+        }
+        let size = ripple_sdk::serde_json::to_vec(&output)
+            .map(|v| v.len())
+            .unwrap_or_default();
+        if size > limits.max_output_bytes {
+            self.record_violation(&rule, JqViolation::OutputSize);
+            return Err(RippleError::RuleError);
+        }
+        Ok(output)
+    }
+}
+
+/// Deepest nesting of brackets, conditionals and definitions in the filter, strings and
+/// comments aside
+fn get_filter_depth(filter: &str) -> usize {
+    let mut chars = filter.chars().peekable();
+    // open brackets and keywords, an interpolation within a string is closed by `)`
+    let mut open: Vec<char> = Vec::new();
+    let mut in_string = false;
+    let mut depth = 0;
+    let mut prev = ' ';
+    while let Some(c) = chars.next() {
+        if in_string {
+            match c {
+                '"' => in_string = false,
+                '\\' if chars.peek() == Some(&'(') => {
+                    chars.next();
+                    in_string = false;
+                    open.push('"');
+                }
+                '\\' => {
+                    chars.next();
+                }
+                _ => {}
+            }
+            depth = depth.max(open.len());
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '#' => while chars.next_if(|c| *c != '\n').is_some() {},
+            '(' | '[' | '{' => open.push(c),
+            ')' | ']' | '}' => {
+                while let Some(o) = open.pop() {
+                    if matches!(o, '(' | '[' | '{') {
+                        break;
+                    }
+                    if o == '"' {
+                        in_string = true;
+                        break;
+                    }
+                }
+            }
+            ';' if open.last() == Some(&'d') => {
+                open.pop();
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut word = String::from(c);
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    word.push(c);
+                }
+                // fields and variables named like keywords
+                if prev != '.' && prev != '$' {
+                    match word.as_str() {
+                        "if" => open.push('i'),
+                        "def" => open.push('d'),
+                        "end" if open.last() == Some(&'i') => {
+                            open.pop();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        depth = depth.max(open.len());
+        prev = c;
+    }
+    depth
+}
+
+fn get_nesting(value: &Value) -> usize {
+    match value {
+        Value::Array(values) => 1 + values.iter().map(get_nesting).max().unwrap_or_default(),
+        Value::Object(values) => 1 + values.values().map(get_nesting).max().unwrap_or_default(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::serde_json::json;
+
+    fn get_sandbox() -> JqSandbox {
+        let sandbox = JqSandbox::default();
+        sandbox.configure(JqSandboxConfiguration {
+            enabled: true,
+            limits: JqLimits {
+                max_runtime_ms: 50,
+                max_output_bytes: 32,
+                max_nesting: 2,
+                max_filter_depth: 2,
+            },
+            rule_limits: HashMap::from([(
+                "device.info".to_owned(),
+                JqLimits {
+                    max_output_bytes: 1024,
+                    ..Default::default()
+                },
+            )]),
+            quarantine_threshold: 2,
+            max_workers: 1,
+        });
+        sandbox
+    }
+
+    #[test]
+    fn test_jq_sandbox_limits() {
+        let sandbox = get_sandbox();
+        let large = json!({ "value": "x".repeat(64) });
+
+        assert_eq!(
+            sandbox.evaluate("device.name_response", ".", json!({}), Ok),
+            Ok(json!({}))
+        );
+        assert_eq!(
+            sandbox.evaluate("device.name_response", ".", json!([[[1]]]), Ok),
+            Err(RippleError::RuleError)
+        );
+        let output = large.clone();
+        assert_eq!(
+            sandbox.evaluate("device.info_response", ".", json!({}), move |_| Ok(output)),
+            Ok(large.clone())
+        );
+        assert_eq!(
+            sandbox.evaluate("device.model_response", ".", json!({}), |_| {
+                thread::sleep(Duration::from_millis(200));
+                Ok(Value::Null)
+            }),
+            Err(RippleError::TimeoutError)
+        );
+        // the stuck worker was replaced, the next rule does not wait for it
+        assert_eq!(
+            sandbox.evaluate("device.make_response", ".", json!({}), Ok),
+            Ok(json!({}))
+        );
+        assert!(!sandbox
+            .violations
+            .read()
+            .unwrap()
+            .contains_key("device.make"));
+    }
+
+    #[test]
+    fn test_jq_sandbox_quarantine() {
+        let sandbox = get_sandbox();
+        let large = json!({ "value": "x".repeat(64) });
+        for _ in 0..2 {
+            let output = large.clone();
+            assert!(sandbox
+                .evaluate("device.name_event", ".", json!({}), move |_| Ok(output))
+                .is_err());
+        }
+        assert_eq!(sandbox.get_quarantined(), vec!["device.name".to_owned()]);
+        // quarantined rules fail without being evaluated
+        assert_eq!(
+            sandbox.evaluate("device.name_request", ".", json!({}), Ok),
+            Err(RippleError::RuleError)
+        );
+        assert!(sandbox.release("Device.Name"));
+        assert!(sandbox
+            .evaluate("device.name_request", ".", json!({}), Ok)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_get_filter_depth() {
+        assert_eq!(get_filter_depth("."), 0);
+        assert_eq!(get_filter_depth(".[0] | {a: .b}"), 1);
+        assert_eq!(get_filter_depth("if .a then [.b] else .c end | .d"), 2);
+        assert_eq!(get_filter_depth("def f: def g: 1; g; [f]"), 2);
+        assert_eq!(get_filter_depth("reduce .[] as $x (0; . + $x)"), 1);
+        // keywords as fields and brackets in strings and comments do not count
+        assert_eq!(get_filter_depth(".if | .end"), 0);
+        assert_eq!(get_filter_depth(r#""((([" # [[["#), 0);
+        assert_eq!(get_filter_depth(r#""a\(.b | [.c])""#), 2);
+        assert_eq!(
+            get_filter_depth(&format!("{}.{}", "[".repeat(64), "]".repeat(64))),
+            64
+        );
+    }
+
+    #[test]
+    fn test_jq_sandbox_filter_depth() {
+        let sandbox = get_sandbox();
+        assert!(sandbox
+            .evaluate("device.name_response", "[[.]]", json!({}), Ok)
+            .is_ok());
+        // rejected before it runs
+        assert_eq!(
+            sandbox.evaluate("device.name_response", "[[[.]]]", json!({}), |_| {
+                panic!("the filter is too deep to run")
+            }),
+            Err(RippleError::RuleError)
+        );
+        assert_eq!(
+            sandbox.violations.read().unwrap().get("device.name"),
+            Some(&1)
+        );
+    }
+
+    #[test]
+    fn test_jq_sandbox_replaced_workers() {
+        let sandbox = get_sandbox();
+        sandbox.config.write().unwrap().quarantine_threshold = 0;
+        let stuck = |_| {
+            thread::sleep(Duration::from_millis(300));
+            Ok(Value::Null)
+        };
+        // the first stuck worker is replaced, the replacement stays in the pool when it gets
+        // stuck as well, and the next evaluation times out waiting for a worker
+        for _ in 0..3 {
+            assert_eq!(
+                sandbox.evaluate("device.model_response", ".", json!({}), stuck),
+                Err(RippleError::TimeoutError)
+            );
+        }
+        let workers = sandbox.workers.get().unwrap();
+        assert_eq!(workers.next_id.load(Ordering::Relaxed), 2);
+        assert!(sandbox.get_quarantined().is_empty());
+
+        thread::sleep(Duration::from_millis(400));
+        assert_eq!(workers.stuck.load(Ordering::Acquire), 0);
+        assert_eq!(
+            sandbox.evaluate("device.make_response", ".", json!({}), Ok),
+            Ok(json!({}))
+        );
+        assert_eq!(workers.next_id.load(Ordering::Relaxed), 2);
+    }
+}
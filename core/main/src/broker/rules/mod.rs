@@ -15,5 +15,6 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+pub mod jq_sandbox;
 pub mod rules_engine;
 pub mod rules_functions;
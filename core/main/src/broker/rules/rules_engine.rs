@@ -16,7 +16,12 @@
 //
 use jaq_interpret::{Ctx, FilterT, ParseCtx, RcIter, Val};
 use ripple_sdk::api::{
-    gateway::rpc_gateway_api::RpcRequest, manifest::extn_manifest::ExtnManifest,
+    gateway::rpc_gateway_api::RpcRequest,
+    manifest::{
+        device_manifest::{RouteTarget, RoutingConfiguration},
+        extn_manifest::ExtnManifest,
+        manifest_source::ManifestSource,
+    },
 };
 
 use ripple_sdk::{
@@ -28,10 +33,18 @@ use ripple_sdk::{
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use std::sync::{Mutex, MutexGuard, Once};
+use std::sync::{Arc, Mutex, MutexGuard, Once};
 use std::{fs, path::Path};
 
-use super::rules_functions::{apply_functions, RulesFunction, RulesImport};
+use super::{
+    jq_sandbox::JqSandbox,
+    rules_functions::{apply_functions, RulesFunction, RulesImport},
+};
+
+/// Endpoint used for methods registered by services when the rules do not define one
+pub const DEFAULT_SERVICE_ENDPOINT: &str = "ripple_services";
+/// Endpoint used for methods registered by extensions when the rules do not define one
+pub const DEFAULT_EXTN_ENDPOINT: &str = "ripple_extns";
 
 static BASE_PARSE_CTX_INIT: Once = Once::new();
 static mut BASE_PARSE_CTX_PTR: Option<Mutex<ParseCtx>> = None;
@@ -42,6 +55,9 @@ pub struct RuleSet {
     pub imports: Vec<String>,
     pub endpoints: HashMap<String, RuleEndpoint>,
     pub rules: HashMap<String, Rule>,
+    /// Compiled patterns of the rule names which are not exact, see [RuleSet::index_patterns]
+    #[serde(skip)]
+    patterns: HashMap<String, Arc<MethodPattern>>,
 }
 
 impl RuleSet {
@@ -62,12 +78,40 @@ impl RuleSet {
             })
             .collect();
         self.rules.extend(rules);
+        self.index_patterns();
     }
+
+    /// Compiles the patterns of the rule names added since the last call and drops those of
+    /// the removed rules, so requests are matched without compiling patterns
+    pub fn index_patterns(&mut self) {
+        let rules = &self.rules;
+        self.patterns.retain(|name, _| rules.contains_key(name));
+        for name in self.rules.keys() {
+            if RuleMatchKind::of(name) != RuleMatchKind::Exact && !self.patterns.contains_key(name)
+            {
+                self.patterns
+                    .insert(name.clone(), Arc::new(MethodPattern::compile(name)));
+            }
+        }
+    }
+
+    fn pattern(&self, rule_name: &str) -> Arc<MethodPattern> {
+        self.patterns
+            .get(rule_name)
+            .cloned()
+            .unwrap_or_else(|| Arc::new(MethodPattern::compile(rule_name)))
+    }
+
     pub fn get(&self, key: &str) -> Option<&Rule> {
         self.rules.get(key)
     }
+    pub fn set_provenance(&mut self, provenance: RuleProvenance) {
+        for rule in self.rules.values_mut() {
+            rule.provenance = provenance.clone();
+        }
+    }
 }
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
 pub struct RuleEndpoint {
     pub protocol: RuleEndpointProtocol,
     pub url: String,
@@ -92,9 +136,8 @@ fn default_autostart() -> bool {
     true
 }
 
-#[derive(Deserialize, Debug, Clone, Default)]
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
 #[serde(rename_all = "lowercase")]
-#[cfg_attr(test, derive(PartialEq))]
 pub enum RuleEndpointProtocol {
     #[default]
     Websocket,
@@ -119,6 +162,95 @@ pub struct EventHandler {
     pub params: Option<String>,
 }
 
+/// Limits how often events brokered by a rule are forwarded to the app.
+///
+/// Events arriving within `interval_ms` of the last forwarded event are dropped, or
+/// with `coalesce` the latest of them is forwarded once the interval elapses.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventDebounce {
+    pub interval_ms: u64,
+    #[serde(default)]
+    pub coalesce: bool,
+}
+
+/// Who supplied a rule, reported in transform failures and admin dumps so a bad transform
+/// can be traced back to the firmware config or the service which registered it. Apps only
+/// see the [RuleProvenance::get_source], the paths and ids stay in the logs.
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+#[serde(tag = "source", rename_all = "camelCase")]
+pub enum RuleProvenance {
+    /// Rules added by Ripple itself
+    #[default]
+    Builtin,
+    Manifest {
+        path: String,
+    },
+    Service {
+        #[serde(rename = "serviceId")]
+        service_id: String,
+    },
+    Extn {
+        #[serde(rename = "extnId")]
+        extn_id: String,
+    },
+    Admin,
+    Snapshot {
+        path: String,
+    },
+}
+
+impl RuleProvenance {
+    /// Checks if the rule was registered by the service
+    pub fn is_service(&self, service_id: &str) -> bool {
+        matches!(self, RuleProvenance::Service { service_id: id } if id.eq(service_id))
+    }
+
+    /// Rules supplied at runtime by services, the admin api or a restored snapshot are not
+    /// part of the device image, their jq filters run in the sandbox
+    pub fn is_sandboxed(&self) -> bool {
+        matches!(
+            self,
+            RuleProvenance::Service { .. }
+                | RuleProvenance::Admin
+                | RuleProvenance::Snapshot { .. }
+        )
+    }
+
+    /// Kind of supplier of the rule, without the file paths or ids
+    pub fn get_source(&self) -> &'static str {
+        match self {
+            RuleProvenance::Builtin => "builtin",
+            RuleProvenance::Manifest { .. } => "manifest",
+            RuleProvenance::Service { .. } => "service",
+            RuleProvenance::Extn { .. } => "extn",
+            RuleProvenance::Admin => "admin",
+            RuleProvenance::Snapshot { .. } => "snapshot",
+        }
+    }
+
+    /// Kind of target serving the methods of rules with this provenance
+    pub fn route_target(&self) -> RouteTarget {
+        match self {
+            RuleProvenance::Service { .. } => RouteTarget::Service,
+            RuleProvenance::Extn { .. } => RouteTarget::Extension,
+            _ => RouteTarget::Broker,
+        }
+    }
+}
+
+impl std::fmt::Display for RuleProvenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleProvenance::Builtin => write!(f, "builtin"),
+            RuleProvenance::Manifest { path } => write!(f, "manifest {}", path),
+            RuleProvenance::Service { service_id } => write!(f, "service {}", service_id),
+            RuleProvenance::Extn { extn_id } => write!(f, "extension {}", extn_id),
+            RuleProvenance::Admin => write!(f, "admin api"),
+            RuleProvenance::Snapshot { path } => write!(f, "snapshot {}", path),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Rule {
     pub alias: String,
@@ -133,6 +265,10 @@ pub struct Rule {
     pub endpoint: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sources: Option<Vec<JsonDataSource>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_debounce: Option<EventDebounce>,
+    #[serde(default, skip_deserializing)]
+    pub provenance: RuleProvenance,
 }
 impl std::fmt::Display for Rule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -175,10 +311,18 @@ impl Rule {
         self.event_handler = Some(event_handler);
         self
     }
+    pub fn with_event_debounce(&mut self, event_debounce: EventDebounce) -> &mut Self {
+        self.event_debounce = Some(event_debounce);
+        self
+    }
     pub fn with_endpoint(&mut self, endpoint: String) -> &mut Self {
         self.endpoint = Some(endpoint);
         self
     }
+    pub fn with_provenance(&mut self, provenance: RuleProvenance) -> &mut Self {
+        self.provenance = provenance;
+        self
+    }
     pub fn with_sources(&mut self, sources: Vec<JsonDataSource>) -> &mut Self {
         self.sources = Some(sources);
         self
@@ -302,12 +446,11 @@ impl RuleEngine {
     }
 
     pub fn load(path: &str) -> Result<RuleEngine, RippleError> {
-        let path = Path::new(path);
-        if path.exists() {
-            let contents = fs::read_to_string(path).unwrap();
+        if Path::new(path).exists() {
+            let contents = ManifestSource::load_to_string(path)?;
             Self::load_from_string_literal(contents)
         } else {
-            warn!("path for the rule is invalid {}", path.display());
+            warn!("path for the rule is invalid {}", path);
             Err(RippleError::InvalidInput)
         }
     }
@@ -325,11 +468,15 @@ impl RuleEngine {
             let path_for_rule = Self::build_path(path, &extn_manifest.default_path);
             debug!("loading rules file {}", path_for_rule);
             if let Some(p) = Path::new(&path_for_rule).to_str() {
-                if let Ok(contents) = fs::read_to_string(p) {
+                // json, yaml and toml rule sets are normalized along with their includes
+                if let Ok(contents) = ManifestSource::load_to_string(p) {
                     info!("Rules content {}", contents);
                     info!("loading rules from path {}", path);
                     info!("loading rule {}", path_for_rule);
-                    if let Ok((_, rule_set)) = Self::load_from_content(contents) {
+                    if let Ok((_, mut rule_set)) = Self::load_from_content(contents) {
+                        rule_set.set_provenance(RuleProvenance::Manifest {
+                            path: path_for_rule.clone(),
+                        });
                         engine.add_rules(rule_set, &extn_manifest.default_path);
                     } else {
                         warn!("invalid rule found in path {}", path)
@@ -390,31 +537,212 @@ impl RuleEngine {
     }
     pub fn add_rule(&mut self, rule: Rule) {
         self.rules.rules.insert(rule.alias.clone(), rule);
+        self.rules.index_patterns();
     }
 
     pub fn has_rule(&self, request: &str) -> bool {
         self.rules.rules.contains_key(&request.to_lowercase())
     }
-    fn wildcard_match(rule_name: &str, method: &str) -> bool {
-        rule_name.ends_with(".*") && method.starts_with(&rule_name[..rule_name.len() - 1])
+
+    /// Returns the provenance of every active rule keyed by the rule name
+    pub fn get_rule_provenance(&self) -> HashMap<String, RuleProvenance> {
+        self.rules
+            .rules
+            .iter()
+            .map(|(name, rule)| (name.clone(), rule.provenance.clone()))
+            .collect()
+    }
+
+    /// Checks if the rule is routed to a service endpoint
+    pub fn is_service_rule(&self, rule: &Rule) -> bool {
+        rule.endpoint
+            .as_ref()
+            .and_then(|endpoint| self.rules.endpoints.get(endpoint))
+            .map(|endpoint| matches!(endpoint.protocol, RuleEndpointProtocol::Service))
+            .unwrap_or(false)
     }
-    fn find_wildcard_rule(
-        rules: &HashMap<String, Rule>,
-        method: &str,
-    ) -> Result<RuleRetrieved, RuleRetrievalError> {
-        let filtered_rules: Vec<&Rule> = rules
+
+    /// Returns the methods routed to each service, keyed by the ServiceId in the rule alias
+    pub fn get_service_methods(&self) -> HashMap<String, Vec<String>> {
+        let mut service_methods: HashMap<String, Vec<String>> = HashMap::new();
+        for (method, rule) in self.rules.rules.iter() {
+            if self.is_service_rule(rule) {
+                service_methods
+                    .entry(rule.alias.clone())
+                    .or_default()
+                    .push(method.clone());
+            }
+        }
+        for methods in service_methods.values_mut() {
+            methods.sort();
+        }
+        service_methods
+    }
+
+    /// Returns true when the method, or a pattern serving it, is routed to the service
+    pub fn is_routed_to_service(&self, service_id: &str, method: &str) -> bool {
+        let method = method.to_lowercase();
+        self.rules.rules.iter().any(|(rule_name, rule)| {
+            rule.alias.eq(service_id)
+                && self.is_service_rule(rule)
+                && (rule_name.eq(&method) || self.pattern_match(rule_name, &method))
+        })
+    }
+
+    /// Returns the first endpoint of the protocol, adding one under the default key when the
+    /// rules do not define any
+    fn ensure_endpoint(&mut self, protocol: RuleEndpointProtocol, default_key: &str) -> String {
+        let mut keys: Vec<&String> = self
+            .rules
+            .endpoints
+            .iter()
+            .filter(|(_, endpoint)| {
+                std::mem::discriminant(&endpoint.protocol) == std::mem::discriminant(&protocol)
+            })
+            .map(|(key, _)| key)
+            .collect();
+        keys.sort();
+        if let Some(key) = keys.first() {
+            return (*key).clone();
+        }
+        self.rules.endpoints.insert(
+            default_key.to_owned(),
+            RuleEndpoint {
+                protocol,
+                ..Default::default()
+            },
+        );
+        default_key.to_owned()
+    }
+
+    /// Returns the first service endpoint, adding [DEFAULT_SERVICE_ENDPOINT] when the rules
+    /// do not define any
+    pub fn ensure_service_endpoint(&mut self) -> String {
+        self.ensure_endpoint(RuleEndpointProtocol::Service, DEFAULT_SERVICE_ENDPOINT)
+    }
+
+    /// Returns the first extension endpoint, adding [DEFAULT_EXTN_ENDPOINT] when the rules
+    /// do not define any
+    pub fn ensure_extn_endpoint(&mut self) -> String {
+        self.ensure_endpoint(RuleEndpointProtocol::Extn, DEFAULT_EXTN_ENDPOINT)
+    }
+
+    /// Routes the methods to the alias through the endpoint, replacing the rules registered
+    /// before with the same provenance
+    pub fn add_registered_rules(
+        &mut self,
+        provenance: RuleProvenance,
+        alias: &str,
+        methods: &[String],
+        endpoint: &str,
+    ) {
+        self.remove_registered_rules(&provenance);
+        for method in methods {
+            self.rules.rules.insert(
+                method.to_lowercase(),
+                Rule {
+                    alias: alias.to_owned(),
+                    endpoint: Some(endpoint.to_owned()),
+                    provenance: provenance.clone(),
+                    ..Default::default()
+                },
+            );
+        }
+        self.rules.index_patterns();
+    }
+
+    /// Returns the methods registered at runtime with the provenance, sorted
+    pub fn get_registered_methods(&self, provenance: &RuleProvenance) -> Vec<String> {
+        let mut methods: Vec<String> = self
+            .rules
+            .rules
             .iter()
-            .filter(|(rule_name, _)| Self::wildcard_match(rule_name, method))
-            .map(|(_, rule)| rule)
+            .filter(|(_, rule)| rule.provenance.eq(provenance))
+            .map(|(method, _)| method.clone())
             .collect();
+        methods.sort();
+        methods
+    }
+
+    pub fn remove_registered_rules(&mut self, provenance: &RuleProvenance) {
+        self.rules
+            .rules
+            .retain(|_, rule| rule.provenance.ne(provenance));
+        self.rules.index_patterns();
+    }
 
-        match filtered_rules.len() {
-            1 => Ok(RuleRetrieved::WildcardMatch(filtered_rules[0].clone())),
-            0 => Err(RuleRetrievalError::RuleNotFoundAsWildcard),
-            _ => Err(RuleRetrievalError::TooManyWildcardMatches),
+    /// Routes the methods to the service through the endpoint, replacing the methods it
+    /// registered before
+    pub fn add_service_rules(&mut self, service_id: &str, methods: &[String], endpoint: &str) {
+        let provenance = RuleProvenance::Service {
+            service_id: service_id.to_owned(),
+        };
+        self.add_registered_rules(provenance, service_id, methods, endpoint);
+    }
+
+    /// Removes the rules registered by the service, rules from the manifest are kept
+    pub fn remove_service_rules(&mut self, service_id: &str) {
+        self.remove_registered_rules(&RuleProvenance::Service {
+            service_id: service_id.to_owned(),
+        });
+    }
+
+    fn pattern_match(&self, rule_name: &str, method: &str) -> bool {
+        match RuleMatchKind::of(rule_name) {
+            RuleMatchKind::Exact => false,
+            _ => self.rules.pattern(rule_name).matches(method),
         }
     }
 
+    /// Finds the rule of the most specific pattern matching the method. Prefix patterns like
+    /// `player.*` take precedence over wildcard patterns like `module.method[12]`, then the
+    /// longer pattern wins and equally long wildcard patterns are taken in name order.
+    fn find_wildcard_rule<'a>(
+        &self,
+        rules: impl Iterator<Item = (&'a String, &'a Rule)>,
+        method: &str,
+    ) -> Result<RuleRetrieved, RuleRetrievalError> {
+        rules
+            .filter(|(rule_name, _)| self.pattern_match(rule_name, method))
+            .min_by(|(a, _), (b, _)| {
+                RuleMatchKind::of(a)
+                    .cmp(&RuleMatchKind::of(b))
+                    .then_with(|| b.len().cmp(&a.len()))
+                    .then_with(|| a.cmp(b))
+            })
+            .map(|(_, rule)| RuleRetrieved::WildcardMatch(rule.clone()))
+            .ok_or(RuleRetrievalError::RuleNotFoundAsWildcard)
+    }
+
+    /// Returns the rules which serve at least one method in common with the method, which
+    /// may be a pattern. Exact names are compared as is, patterns are checked for overlap.
+    pub fn find_overlapping_rules(&self, method: &str) -> Vec<(String, Rule)> {
+        let method = method.to_lowercase();
+        let pattern = match RuleMatchKind::of(&method) {
+            RuleMatchKind::Exact => None,
+            _ => Some(MethodPattern::compile(&method)),
+        };
+        let mut overlapping: Vec<(String, Rule)> = self
+            .rules
+            .rules
+            .iter()
+            .filter(|(rule_name, _)| {
+                match (
+                    &pattern,
+                    RuleMatchKind::of(rule_name) == RuleMatchKind::Exact,
+                ) {
+                    (None, true) => **rule_name == method,
+                    (None, false) => self.pattern_match(rule_name, &method),
+                    (Some(pattern), true) => pattern.matches(rule_name),
+                    (Some(pattern), false) => pattern.overlaps(&self.rules.pattern(rule_name)),
+                }
+            })
+            .map(|(rule_name, rule)| (rule_name.clone(), rule.clone()))
+            .collect();
+        overlapping.sort_by(|(a, _), (b, _)| a.cmp(b));
+        overlapping
+    }
+
     fn apply_functions(&self, rule: &mut Rule) {
         rule.transform.apply_functions(&self.functions);
     }
@@ -423,22 +751,76 @@ impl RuleEngine {
         rule.transform.apply_variables(rpc_request);
     }
 
-    pub fn get_rule(&self, rpc_request: &RpcRequest) -> Result<RuleRetrieved, RuleRetrievalError> {
-        let method = rpc_request.method.to_lowercase();
+    /// Finds the rule of the target kind first in the precedence, in a single pass over the
+    /// rules. Within a target kind exact names match before patterns, target kinds missing
+    /// from the precedence are never matched.
+    pub fn find_best_rule(
+        &self,
+        method: &str,
+        precedence: &RoutePrecedence,
+    ) -> Option<RuleRetrieved> {
+        let method = method.to_lowercase();
+        let rank = |rule: &Rule| precedence.rank(rule.provenance.route_target());
+        let exact = self
+            .rules
+            .get(&method)
+            .filter(|rule| rank(rule) != RoutePrecedence::UNRANKED);
+        let exact_rank = exact.map_or(RoutePrecedence::UNRANKED, rank);
+        let pattern = self
+            .rules
+            .rules
+            .iter()
+            .filter(|(rule_name, rule)| {
+                rank(rule) < exact_rank && self.pattern_match(rule_name, &method)
+            })
+            .min_by(|(a, rule_a), (b, rule_b)| {
+                rank(rule_a)
+                    .cmp(&rank(rule_b))
+                    .then_with(|| RuleMatchKind::of(a).cmp(&RuleMatchKind::of(b)))
+                    .then_with(|| b.len().cmp(&a.len()))
+                    .then_with(|| a.cmp(b))
+            });
+        match (pattern, exact) {
+            (Some((_, rule)), _) => Some(RuleRetrieved::WildcardMatch(rule.clone())),
+            (None, Some(rule)) => Some(RuleRetrieved::ExactMatch(rule.clone())),
+            (None, None) => None,
+        }
+    }
 
-        /*
-        match directly from method name
-         */
+    /// Finds the rule of the target kind for the method, exact names match before patterns
+    pub fn find_rule(&self, method: &str, target: RouteTarget) -> Option<RuleRetrieved> {
+        let method = method.to_lowercase();
+        match self.rules.get(&method) {
+            Some(rule) if rule.provenance.route_target() == target => {
+                Some(RuleRetrieved::ExactMatch(rule.clone()))
+            }
+            _ => self
+                .find_wildcard_rule(
+                    self.rules
+                        .rules
+                        .iter()
+                        .filter(|(_, rule)| rule.provenance.route_target() == target),
+                    &method,
+                )
+                .ok(),
+        }
+    }
 
-        if let Some(mut rule) = self.rules.get(&method).cloned() {
-            self.apply_functions(&mut rule);
-            self.apply_variables(&mut rule, rpc_request);
-            Ok(RuleRetrieved::ExactMatch(rule.to_owned()))
-        } else {
-            /*
-             * match, for example api.v1.* as rule name and api.v1.get as method name
-             */
-            Self::find_wildcard_rule(&self.rules.rules, &method)
+    /// Returns the rule for the request from the first target kind in the precedence which
+    /// has one, see [RuleEngine::find_best_rule]
+    pub fn get_rule(
+        &self,
+        rpc_request: &RpcRequest,
+        precedence: &RoutePrecedence,
+    ) -> Result<RuleRetrieved, RuleRetrievalError> {
+        match self.find_best_rule(&rpc_request.method, precedence) {
+            Some(RuleRetrieved::ExactMatch(mut rule)) => {
+                self.apply_functions(&mut rule);
+                self.apply_variables(&mut rule, rpc_request);
+                Ok(RuleRetrieved::ExactMatch(rule))
+            }
+            Some(retrieved) => Ok(retrieved),
+            None => Err(RuleRetrievalError::RuleNotFoundAsWildcard),
         }
     }
 
@@ -446,6 +828,42 @@ impl RuleEngine {
         self.rules.rules.get(&method.to_lowercase()).cloned()
     }
 }
+/// Routing precedence of the device resolved once into the rank of every target kind, so
+/// requests are matched without walking the precedence
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutePrecedence {
+    targets: Vec<RouteTarget>,
+    ranks: [usize; 4],
+}
+
+impl RoutePrecedence {
+    /// Rank of the target kinds missing from the precedence
+    pub const UNRANKED: usize = usize::MAX;
+
+    pub fn new(targets: Vec<RouteTarget>) -> RoutePrecedence {
+        let mut ranks = [Self::UNRANKED; 4];
+        for (rank, target) in targets.iter().enumerate() {
+            ranks[*target as usize] = ranks[*target as usize].min(rank);
+        }
+        RoutePrecedence { targets, ranks }
+    }
+
+    pub fn targets(&self) -> &[RouteTarget] {
+        &self.targets
+    }
+
+    /// Position of the target kind in the precedence, lower ranks are served first
+    pub fn rank(&self, target: RouteTarget) -> usize {
+        self.ranks[target as usize]
+    }
+}
+
+impl Default for RoutePrecedence {
+    fn default() -> Self {
+        Self::new(RoutingConfiguration::default().get_precedence())
+    }
+}
+
 #[derive(Debug)]
 pub enum RuleRetrieved {
     ExactMatch(Rule),
@@ -464,7 +882,187 @@ impl From<RuleRetrieved> for Rule {
 pub enum RuleRetrievalError {
     RuleNotFound(String),
     RuleNotFoundAsWildcard,
-    TooManyWildcardMatches,
+}
+
+/// How a rule name matches method names, in order of precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RuleMatchKind {
+    Exact,
+    /// Name ending with a single `*`, like `player.*`
+    Prefix,
+    /// Name with `*`, `?` or `[...]` elsewhere, like `module.method[12]`
+    Wildcard,
+}
+
+impl RuleMatchKind {
+    pub fn of(rule_name: &str) -> Self {
+        match rule_name.find(['*', '?', '[']) {
+            None => RuleMatchKind::Exact,
+            Some(i) if i + 1 == rule_name.len() && rule_name.ends_with('*') => {
+                RuleMatchKind::Prefix
+            }
+            Some(_) => RuleMatchKind::Wildcard,
+        }
+    }
+
+    /// Checks that every `[` of the pattern is closed by a `]` after at least one character
+    /// and that the module, before the first `.`, is literal. Patterns like `*` or
+    /// `*.play` would take over the methods of every module.
+    pub fn is_valid_pattern(rule_name: &str) -> bool {
+        if let Some(i) = rule_name.find(['*', '?', '[']) {
+            if !rule_name[..i].contains('.') {
+                return false;
+            }
+        }
+        let mut chars = rule_name.chars();
+        while let Some(c) = chars.next() {
+            if c == '[' && !matches!(chars.position(|c| c == ']'), Some(n) if n > 0) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PatternToken {
+    Char(char),
+    /// `?`
+    AnyChar,
+    /// `[...]`, as inclusive ranges
+    Class(Vec<(char, char)>),
+    /// `*`
+    AnyString,
+}
+
+impl PatternToken {
+    fn accepts(&self, c: char) -> bool {
+        match self {
+            PatternToken::Char(p) => *p == c,
+            PatternToken::AnyChar => true,
+            PatternToken::Class(ranges) => ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c)),
+            PatternToken::AnyString => false,
+        }
+    }
+
+    /// Checks that a single character is accepted by both tokens
+    fn intersects(&self, other: &PatternToken) -> bool {
+        match (self, other) {
+            (PatternToken::AnyChar, _) | (_, PatternToken::AnyChar) => true,
+            (PatternToken::Char(c), token) | (token, PatternToken::Char(c)) => token.accepts(*c),
+            (PatternToken::Class(a), PatternToken::Class(b)) => a
+                .iter()
+                .any(|(lo_a, hi_a)| b.iter().any(|(lo_b, hi_b)| lo_a <= hi_b && lo_b <= hi_a)),
+            _ => false,
+        }
+    }
+}
+
+/// Method name pattern with `*`, `?` and character classes like `[12]` or `[a-z]`, compiled
+/// once so methods are matched without allocating
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodPattern {
+    tokens: Vec<PatternToken>,
+}
+
+impl MethodPattern {
+    pub fn compile(pattern: &str) -> MethodPattern {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::with_capacity(chars.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let token = match chars[i] {
+                '*' => PatternToken::AnyString,
+                '?' => PatternToken::AnyChar,
+                '[' => match chars.iter().skip(i + 2).position(|c| *c == ']') {
+                    Some(n) => {
+                        let class = &chars[i + 1..i + 2 + n];
+                        let mut ranges = Vec::new();
+                        let mut j = 0;
+                        while j < class.len() {
+                            if j + 2 < class.len() && class[j + 1] == '-' {
+                                ranges.push((class[j], class[j + 2]));
+                                j += 3;
+                            } else {
+                                ranges.push((class[j], class[j]));
+                                j += 1;
+                            }
+                        }
+                        i += n + 2;
+                        PatternToken::Class(ranges)
+                    }
+                    None => PatternToken::Char('['),
+                },
+                c => PatternToken::Char(c),
+            };
+            tokens.push(token);
+            i += 1;
+        }
+        MethodPattern { tokens }
+    }
+
+    pub fn matches(&self, method: &str) -> bool {
+        let (mut t, mut m) = (0, 0);
+        let mut backtrack: Option<(usize, usize)> = None;
+        while let Some(c) = method[m..].chars().next() {
+            match self.tokens.get(t) {
+                Some(PatternToken::AnyString) => {
+                    backtrack = Some((t + 1, m));
+                    t += 1;
+                    continue;
+                }
+                Some(token) if token.accepts(c) => {
+                    t += 1;
+                    m += c.len_utf8();
+                    continue;
+                }
+                _ => {}
+            }
+            match backtrack {
+                Some((star_t, star_m)) => {
+                    let skipped = method[star_m..].chars().next().map_or(1, char::len_utf8);
+                    backtrack = Some((star_t, star_m + skipped));
+                    t = star_t;
+                    m = star_m + skipped;
+                }
+                None => return false,
+            }
+        }
+        self.tokens[t..]
+            .iter()
+            .all(|token| *token == PatternToken::AnyString)
+    }
+
+    /// Checks that at least one method name matches both patterns
+    pub fn overlaps(&self, other: &MethodPattern) -> bool {
+        let (a, b) = (&self.tokens, &other.tokens);
+        let width = b.len() + 1;
+        let mut visited = vec![false; (a.len() + 1) * width];
+        let mut pending = vec![(0, 0)];
+        while let Some((i, j)) = pending.pop() {
+            if std::mem::replace(&mut visited[i * width + j], true) {
+                continue;
+            }
+            match (a.get(i), b.get(j)) {
+                (None, None) => return true,
+                (Some(PatternToken::AnyString), _) => {
+                    pending.push((i + 1, j));
+                    if j < b.len() {
+                        pending.push((i, j + 1));
+                    }
+                }
+                (_, Some(PatternToken::AnyString)) => {
+                    pending.push((i, j + 1));
+                    if i < a.len() {
+                        pending.push((i + 1, j));
+                    }
+                }
+                (Some(x), Some(y)) if x.intersects(y) => pending.push((i + 1, j + 1)),
+                _ => {}
+            }
+        }
+        false
+    }
 }
 
 /// Compiles and executes a JQ filter on a given JSON input value.
@@ -522,6 +1120,25 @@ fn get_parse_ctx() -> MutexGuard<'static, ParseCtx> {
     }
 }
 
+/// Evaluates the filter of a rule, the filters of rules supplied at runtime are evaluated in
+/// the jq sandbox while the filters shipped with the device run inline
+pub fn jq_compile_rule(
+    input: Value,
+    filter: &str,
+    reference: String,
+    provenance: &RuleProvenance,
+) -> Result<Value, RippleError> {
+    let sandbox = JqSandbox::get();
+    if !provenance.is_sandboxed() || !sandbox.is_enabled() {
+        return jq_compile(input, filter, reference);
+    }
+    let sandbox_filter = filter.to_owned();
+    let sandbox_reference = reference.clone();
+    sandbox.evaluate(&sandbox_reference, filter, input, move |input| {
+        jq_compile(input, &sandbox_filter, reference)
+    })
+}
+
 pub fn jq_compile(input: Value, filter: &str, reference: String) -> Result<Value, RippleError> {
     info!(
         "Jq rule {}  input {:?}, reference {}",
@@ -550,6 +1167,8 @@ pub fn jq_compile(input: Value, filter: &str, reference: String) -> Result<Value
         defs.errs.clear(); // Clear errors before returning
         return Err(RippleError::RuleError);
     }
+    // a runaway filter must not hold the shared ParseCtx
+    drop(defs);
     let inputs = RcIter::new(core::iter::empty());
     // iterator over the output values
     let mut out = f.run((Ctx::new([], &inputs), Val::from(input)));
@@ -711,6 +1330,7 @@ mod tests {
             rules: rule_set,
             functions: HashMap::default(),
         };
+        let precedence = RoutePrecedence::default();
 
         let rpc_request = RpcRequest {
             method: "test.method".to_string(),
@@ -722,7 +1342,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = rule_engine.get_rule(&rpc_request);
+        let result = rule_engine.get_rule(&rpc_request, &precedence);
         match result {
             Ok(RuleRetrieved::ExactMatch(retrieved_rule)) => {
                 assert_eq!(retrieved_rule.alias, rule.alias);
@@ -744,6 +1364,7 @@ mod tests {
             rules: rule_set,
             functions: HashMap::default(),
         };
+        let precedence = RoutePrecedence::default();
 
         let rpc_request = RpcRequest {
             method: "api.v1.get".to_string(),
@@ -755,7 +1376,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = rule_engine.get_rule(&rpc_request);
+        let result = rule_engine.get_rule(&rpc_request, &precedence);
         match result {
             Ok(RuleRetrieved::WildcardMatch(retrieved_rule)) => {
                 assert_eq!(retrieved_rule.alias, rule.alias);
@@ -764,6 +1385,201 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_rule_pattern_precedence() {
+        let mut rule_set = RuleSet::default();
+        for (name, alias) in [
+            ("player.*", "prefix"),
+            ("player.media.*", "longer_prefix"),
+            ("player.play[12]", "wildcard"),
+            ("player.?top", "wildcard_stop"),
+            ("player.play1", "exact"),
+            ("module.method[12]", "module_wildcard"),
+        ] {
+            rule_set.rules.insert(
+                name.to_string(),
+                Rule {
+                    alias: alias.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        let rule_engine = RuleEngine {
+            rules: rule_set,
+            functions: HashMap::default(),
+        };
+        let precedence = RoutePrecedence::default();
+        let alias_of = |method: &str| {
+            let rpc_request = RpcRequest {
+                method: method.to_string(),
+                ..Default::default()
+            };
+            rule_engine
+                .get_rule(&rpc_request, &precedence)
+                .ok()
+                .map(|rule| Rule::from(rule).alias)
+        };
+
+        assert_eq!(alias_of("player.play1"), Some("exact".to_string()));
+        assert_eq!(alias_of("player.play2"), Some("prefix".to_string()));
+        assert_eq!(
+            alias_of("player.media.seek"),
+            Some("longer_prefix".to_string())
+        );
+        assert_eq!(
+            alias_of("module.method2"),
+            Some("module_wildcard".to_string())
+        );
+        assert_eq!(alias_of("module.method3"), None);
+        assert_eq!(alias_of("device.name"), None);
+
+        assert_eq!(RuleMatchKind::of("player.play1"), RuleMatchKind::Exact);
+        assert_eq!(RuleMatchKind::of("player.*"), RuleMatchKind::Prefix);
+        assert_eq!(RuleMatchKind::of("player.?top"), RuleMatchKind::Wildcard);
+        assert!(RuleMatchKind::is_valid_pattern("player.play[12]"));
+        assert!(!RuleMatchKind::is_valid_pattern("player.play[]"));
+        assert!(!RuleMatchKind::is_valid_pattern("player.play[12"));
+
+        assert!(!RuleMatchKind::is_valid_pattern("*"));
+        assert!(!RuleMatchKind::is_valid_pattern("*.play"));
+
+        let pattern = MethodPattern::compile("player.play[1-3]*");
+        for (method, expected) in [
+            ("player.play2", true),
+            ("player.play3x", true),
+            ("player.play4", false),
+        ] {
+            assert_eq!(pattern.matches(method), expected);
+        }
+    }
+
+    #[test]
+    fn test_pattern_overlap() {
+        let overlaps = |a: &str, b: &str| {
+            let (a, b) = (MethodPattern::compile(a), MethodPattern::compile(b));
+            assert_eq!(a.overlaps(&b), b.overlaps(&a));
+            a.overlaps(&b)
+        };
+        assert!(overlaps("player.*", "player.play[12]"));
+        assert!(overlaps("player.*", "*.play"));
+        assert!(overlaps("player.?top", "player.[r-t]*"));
+        assert!(overlaps("player.play[1-3]", "player.play[3-5]"));
+        assert!(!overlaps("player.play[12]", "player.play[34]"));
+        assert!(!overlaps("player.*", "device.*"));
+        assert!(!overlaps("player.?top", "player.stop*x"));
+
+        let mut rule_set = RuleSet::default();
+        for name in ["player.play", "player.stop*", "device.name"] {
+            rule_set.rules.insert(name.to_owned(), Rule::default());
+        }
+        rule_set.index_patterns();
+        let engine = RuleEngine {
+            rules: rule_set,
+            functions: HashMap::new(),
+        };
+        let names = |method: &str| -> Vec<String> {
+            engine
+                .find_overlapping_rules(method)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect()
+        };
+        assert_eq!(names("player.*"), vec!["player.play", "player.stop*"]);
+        assert_eq!(names("player.stopall"), vec!["player.stop*"]);
+        assert_eq!(names("Device.Name"), vec!["device.name"]);
+        assert!(names("device.model").is_empty());
+    }
+
+    #[test]
+    fn test_get_service_methods() {
+        let mut rule_set = RuleSet::default();
+        rule_set.endpoints.insert(
+            "service".to_string(),
+            RuleEndpoint {
+                protocol: RuleEndpointProtocol::Service,
+                ..Default::default()
+            },
+        );
+        for method in ["svc.b", "svc.a"] {
+            rule_set.rules.insert(
+                method.to_string(),
+                Rule {
+                    alias: "ripple:channel:test:svc".to_string(),
+                    endpoint: Some("service".to_string()),
+                    ..Default::default()
+                },
+            );
+        }
+        rule_set.rules.insert(
+            "device.name".to_string(),
+            Rule {
+                alias: "org.rdk.System.getFriendlyName".to_string(),
+                ..Default::default()
+            },
+        );
+        let rule_engine = RuleEngine {
+            rules: rule_set,
+            functions: HashMap::default(),
+        };
+
+        let service_methods = rule_engine.get_service_methods();
+        assert_eq!(service_methods.len(), 1);
+        assert_eq!(
+            service_methods.get("ripple:channel:test:svc").unwrap(),
+            &vec!["svc.a".to_string(), "svc.b".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_get_rule_target_precedence() {
+        let mut rule_set = RuleSet::default();
+        rule_set.rules.insert(
+            "player.play".to_string(),
+            Rule {
+                alias: "device".to_string(),
+                ..Default::default()
+            },
+        );
+        rule_set.rules.insert(
+            "player.*".to_string(),
+            Rule {
+                alias: "media".to_string(),
+                provenance: RuleProvenance::Service {
+                    service_id: "media".to_string(),
+                },
+                ..Default::default()
+            },
+        );
+        let rule_engine = RuleEngine {
+            rules: rule_set,
+            functions: HashMap::default(),
+        };
+        let rpc_request = RpcRequest {
+            method: "Player.play".to_string(),
+            ..Default::default()
+        };
+        let alias_of = |precedence: Vec<RouteTarget>| {
+            rule_engine
+                .get_rule(&rpc_request, &RoutePrecedence::new(precedence))
+                .ok()
+                .map(|rule| Rule::from(rule).alias)
+        };
+
+        // the rules of the device come before the methods registered at runtime
+        assert_eq!(
+            alias_of(RoutingConfiguration::default().get_precedence()),
+            Some("device".to_string())
+        );
+        assert_eq!(
+            alias_of(vec![RouteTarget::Service, RouteTarget::Broker]),
+            Some("media".to_string())
+        );
+        assert_eq!(alias_of(vec![RouteTarget::Extension]), None);
+        assert!(rule_engine
+            .find_rule("player.stop", RouteTarget::Broker)
+            .is_none());
+    }
+
     #[test]
     fn test_get_rule_no_match() {
         let rule_set = RuleSet::default();
@@ -771,6 +1587,7 @@ mod tests {
             rules: rule_set,
             functions: HashMap::default(),
         };
+        let precedence = RoutePrecedence::default();
 
         let rpc_request = RpcRequest {
             method: "nonexistent.method".to_string(),
@@ -782,10 +1599,88 @@ mod tests {
             ..Default::default()
         };
 
-        let result = rule_engine.get_rule(&rpc_request);
+        let result = rule_engine.get_rule(&rpc_request, &precedence);
         assert!(matches!(
             result,
             Err(RuleRetrievalError::RuleNotFoundAsWildcard)
         ));
     }
+
+    #[test]
+    fn test_rule_provenance() {
+        let (_, mut rule_set) = RuleEngine::load_from_content(
+            json!({
+                "endpoints": {},
+                "rules": {
+                    "Device.Name": { "alias": "org.rdk.System.getFriendlyName" }
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        rule_set.set_provenance(RuleProvenance::Manifest {
+            path: "/etc/ripple/rules/device.json".into(),
+        });
+        let mut rule_engine = RuleEngine::default();
+        rule_engine.add_rules(rule_set, "/etc/ripple/rules/");
+        rule_engine.add_rule(
+            Rule::default()
+                .with_alias("svc".into())
+                .with_provenance(RuleProvenance::Service {
+                    service_id: "ripple:channel:test:svc".into(),
+                })
+                .clone(),
+        );
+
+        let provenance = rule_engine.get_rule_provenance();
+        assert_eq!(
+            provenance.get("device.name").unwrap().to_string(),
+            "manifest /etc/ripple/rules/device.json"
+        );
+        assert_eq!(
+            provenance.get("device.name").unwrap().get_source(),
+            "manifest"
+        );
+        assert_eq!(
+            json!(provenance.get("svc").unwrap()),
+            json!({ "source": "service", "serviceId": "ripple:channel:test:svc" })
+        );
+        // only the rules supplied at runtime go through the jq sandbox
+        assert!(!provenance.get("device.name").unwrap().is_sandboxed());
+        assert!(provenance.get("svc").unwrap().is_sandboxed());
+        assert!(!RuleProvenance::Builtin.is_sandboxed());
+    }
+
+    #[test]
+    fn test_service_rules() {
+        let mut rule_engine = RuleEngine::default();
+        let endpoint = rule_engine.ensure_service_endpoint();
+        assert_eq!(endpoint, DEFAULT_SERVICE_ENDPOINT);
+        assert_eq!(rule_engine.ensure_service_endpoint(), endpoint);
+
+        rule_engine.add_rule(
+            Rule::default()
+                .with_alias("device.name".into())
+                .with_provenance(RuleProvenance::Manifest {
+                    path: "/etc/ripple/rules/device.json".into(),
+                })
+                .clone(),
+        );
+        let svc = "ripple:channel:test:svc".to_string();
+        rule_engine.add_service_rules(&svc, &["Svc.A".into(), "svc.b".into()], &endpoint);
+        rule_engine.add_service_rules(&svc, &["svc.a".into(), "svc.on*".into()], &endpoint);
+        assert_eq!(
+            rule_engine.get_service_methods().get(&svc).unwrap(),
+            &vec!["svc.a".to_string(), "svc.on*".to_string()]
+        );
+        assert!(rule_engine.is_routed_to_service(&svc, "Svc.A"));
+        assert!(rule_engine.is_routed_to_service(&svc, "svc.onChanged"));
+        assert!(!rule_engine.is_routed_to_service(&svc, "svc.b"));
+        assert!(!rule_engine.is_routed_to_service("ripple:channel:test:other", "svc.a"));
+        assert!(!rule_engine.is_routed_to_service(&svc, "device.name"));
+
+        rule_engine.remove_service_rules(&svc);
+        assert!(rule_engine.get_service_methods().is_empty());
+        assert!(rule_engine.has_rule("device.name"));
+    }
 }
@@ -14,23 +14,62 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 //
-use super::endpoint_broker::{
-    BrokerCallback, BrokerCleaner, BrokerConnectRequest, BrokerRequest, BrokerSender,
-    EndpointBroker, EndpointBrokerState, BROKER_CHANNEL_BUFFER_SIZE,
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::{
+    endpoint_broker::{
+        BrokerCallback, BrokerCleaner, BrokerConnectRequest, BrokerRequest, BrokerSender,
+        EndpointBroker, EndpointBrokerState, BROKER_CHANNEL_BUFFER_SIZE,
+    },
+    request_deadlines::RequestDeadlines,
 };
 use crate::state::platform_state::PlatformState;
 use ripple_sdk::{
-    api::{gateway::rpc_gateway_api::JsonRpcApiError, observability::log_signal::LogSignal},
-    log::{error, info},
+    api::{gateway::rpc_gateway_api::JsonRpcApiResponse, observability::log_signal::LogSignal},
+    log::{debug, error, info},
     service::service_message::{Id, ServiceMessage},
     tokio::{self, sync::mpsc},
     tokio_tungstenite::tungstenite::Message,
     utils::error::RippleError,
 };
 
+/// Service request waiting on a response until its timeout
+struct PendingRequest {
+    service_id: String,
+    method: String,
+    timeout: Duration,
+}
+
+/// Timeouts of the requests sent to services, the deadlines are served by a single task
+/// instead of a sleeping task per request
+#[derive(Clone)]
+struct RequestTimeouts {
+    deadlines: RequestDeadlines,
+    pending: Arc<Mutex<HashMap<u64, PendingRequest>>>,
+}
+
+impl RequestTimeouts {
+    fn add(&self, service_id: String, method: String, request_id: u64, timeout: Duration) {
+        self.pending.lock().unwrap().insert(
+            request_id,
+            PendingRequest {
+                service_id,
+                method,
+                timeout,
+            },
+        );
+        self.deadlines.add(request_id, Instant::now() + timeout);
+    }
+}
+
 #[derive(Clone)]
 pub struct ServiceBroker {
     sender: BrokerSender,
+    cleaner: BrokerCleaner,
 }
 
 impl ServiceBroker {
@@ -51,6 +90,11 @@ impl ServiceBroker {
             };
         };
 
+        let timeouts = ps_c
+            .get_device_manifest()
+            .get_service_request_timeout_configuration();
+        let request_timeouts = Self::start_request_timeouts(ps_c.clone());
+
         tokio::spawn(async move {
             while let Some(broker_request) = broker_request_rx.recv().await {
                 LogSignal::new(
@@ -62,22 +106,38 @@ impl ServiceBroker {
 
                 let service_id = broker_request.rule.alias.clone();
 
+                // services of a tenant only serve the method namespaces given to the tenant
+                if !ps_c
+                    .tenant_state
+                    .is_method_allowed(&service_id, &broker_request.rpc.method)
+                {
+                    Self::log_error_and_send_broker_failure_response(
+                        broker_request.clone(),
+                        &callback,
+                        JsonRpcApiResponse::builder(broker_request.rpc.ctx.call_id)
+                            .method_not_found(format!(
+                                "{} is not available from service {}",
+                                broker_request.rpc.method, service_id
+                            )),
+                    );
+                    continue;
+                }
+
                 // get the ws sender for the service from service_controller_state
                 let service_sender =
                     match ps_c.service_controller_state.get_sender(&service_id).await {
                         Some(sender) => sender,
                         None => {
                             error!("Service sender not found for service id: {}", service_id);
+                            ps_c.tenant_state.record_request(&service_id, false);
                             Self::log_error_and_send_broker_failure_response(
                                 broker_request.clone(),
                                 &callback,
-                                JsonRpcApiError::default()
-                                    .with_code(-32001)
-                                    .with_message(format!(
+                                JsonRpcApiResponse::builder(broker_request.rpc.ctx.call_id)
+                                    .service_unavailable(format!(
                                         "Service sender not found for service id: {}",
                                         service_id
-                                    ))
-                                    .with_id(broker_request.rpc.ctx.call_id),
+                                    )),
                             );
                             continue;
                         }
@@ -90,10 +150,8 @@ impl ServiceBroker {
                         Self::log_error_and_send_broker_failure_response(
                             broker_request.clone(),
                             &callback,
-                            JsonRpcApiError::default()
-                                .with_code(-32001)
-                                .with_message(format!("Failed to update request: {}", e))
-                                .with_id(broker_request.rpc.ctx.call_id),
+                            JsonRpcApiResponse::builder(broker_request.rpc.ctx.call_id)
+                                .invalid_params(format!("Failed to update request: {}", e)),
                         );
                         continue;
                     }
@@ -111,15 +169,33 @@ impl ServiceBroker {
                 if let Some(workflow_callback) = broker_request.workflow_callback.clone() {
                     let _ = ps_c
                         .service_controller_state
-                        .set_broker_callback(&service_id, request_id, workflow_callback)
+                        .set_broker_callback(
+                            &service_id,
+                            request_id,
+                            workflow_callback,
+                            Some(request.clone()),
+                        )
                         .await;
                 } else {
                     let _ = ps_c
                         .service_controller_state
-                        .set_broker_callback(&service_id, request_id, callback.clone())
+                        .set_broker_callback(
+                            &service_id,
+                            request_id,
+                            callback.clone(),
+                            Some(request.clone()),
+                        )
                         .await;
                 }
 
+                // the service is notified of listen and unlisten requests, the gateway keeps the
+                // listeners to fan out the events pushed by the service
+                if broker_request.rpc.is_unlisten() {
+                    ps_c.service_controller_state
+                        .subscriptions
+                        .unsubscribe(&service_id, &broker_request);
+                }
+
                 let message = Message::Text(request.clone());
                 info!("Sending request to service {}: {:#?}", service_id, message);
 
@@ -128,16 +204,15 @@ impl ServiceBroker {
                         "Failed to send request to service {}: {:?}",
                         service_id, err
                     );
+                    ps_c.tenant_state.record_request(&service_id, false);
                     Self::log_error_and_send_broker_failure_response(
                         broker_request.clone(),
                         &callback,
-                        JsonRpcApiError::default()
-                            .with_code(-32001)
-                            .with_message(format!(
+                        JsonRpcApiResponse::builder(broker_request.rpc.ctx.call_id)
+                            .service_unavailable(format!(
                                 "Failed to send request to service {}: {:?}",
                                 service_id, err
-                            ))
-                            .with_id(broker_request.rpc.ctx.call_id),
+                            )),
                     );
                 } else {
                     LogSignal::new(
@@ -146,6 +221,22 @@ impl ServiceBroker {
                         broker_request.rpc.ctx.clone(),
                     )
                     .emit_debug();
+                    if broker_request.rpc.is_subscription() && broker_request.rpc.is_listening() {
+                        ps_c.service_controller_state
+                            .subscriptions
+                            .subscribe(&service_id, &broker_request);
+                    }
+                    // a tighter caller deadline wins over the configured service timeout
+                    let mut timeout = timeouts.get_timeout(&service_id, &broker_request.rpc.method);
+                    if let Some(budget) = broker_request.rpc.ctx.get_remaining_budget() {
+                        timeout = timeout.min(budget);
+                    }
+                    request_timeouts.add(
+                        service_id,
+                        broker_request.rpc.method.clone(),
+                        request_id,
+                        timeout,
+                    );
                 }
             }
         });
@@ -155,10 +246,84 @@ impl ServiceBroker {
         }
     }
 
+    /// Fails the requests with a deadline exceeded error if the service has not responded once
+    /// their timeout elapsed. Taking the broker callback cancels the pending entry, so a late
+    /// response from the service is dropped.
+    fn start_request_timeouts(ps: PlatformState) -> RequestTimeouts {
+        let pending: Arc<Mutex<HashMap<u64, PendingRequest>>> = Arc::default();
+        let (expired_tx, mut expired_rx) = mpsc::unbounded_channel::<(u64, PendingRequest)>();
+        let pending_c = pending.clone();
+        let deadlines = RequestDeadlines::start(move |request_id| {
+            if let Some(request) = pending_c.lock().unwrap().remove(&request_id) {
+                let _ = expired_tx.send((request_id, request));
+            }
+        });
+        tokio::spawn(async move {
+            while let Some((request_id, request)) = expired_rx.recv().await {
+                let PendingRequest {
+                    service_id,
+                    method,
+                    timeout,
+                } = request;
+                if let Ok(Some(callback)) = ps
+                    .service_controller_state
+                    .extract_broker_callback(&service_id, request_id)
+                    .await
+                {
+                    error!(
+                        "Request {} to service {} timed out after {}ms",
+                        request_id,
+                        service_id,
+                        timeout.as_millis()
+                    );
+                    ps.tenant_state.record_request(&service_id, false);
+                    Self::send_broker_failure_response(
+                        &callback,
+                        JsonRpcApiResponse::builder(request_id).deadline_exceeded(format!(
+                            "{} timed out waiting for service {}",
+                            method, service_id
+                        )),
+                    );
+                }
+            }
+        });
+        RequestTimeouts { deadlines, pending }
+    }
+
+    /// Unlistens the service events of a session once the app is gone, the unlisten requests
+    /// go through the broker so the services are notified.
+    fn start_cleaner(ps: Option<PlatformState>, sender: BrokerSender) -> BrokerCleaner {
+        let Some(ps) = ps else {
+            return BrokerCleaner::default();
+        };
+        let (cleaner_tx, mut cleaner_rx) = mpsc::channel::<String>(2);
+        tokio::spawn(async move {
+            while let Some(session_id) = cleaner_rx.recv().await {
+                let listeners = ps
+                    .service_controller_state
+                    .subscriptions
+                    .remove_session(&session_id);
+                for (service_id, mut listener) in listeners {
+                    debug!(
+                        "Unlistening {} from service {} for session {}",
+                        listener.rpc.ctx.method, service_id, session_id
+                    );
+                    listener.rpc = listener.rpc.get_unsubscribe();
+                    if sender.sender.send(listener).await.is_err() {
+                        error!("Cleanup Error for {}", session_id);
+                    }
+                }
+            }
+        });
+        BrokerCleaner {
+            cleaner: Some(cleaner_tx),
+        }
+    }
+
     fn log_error_and_send_broker_failure_response(
         request: BrokerRequest,
         callback: &BrokerCallback,
-        error: JsonRpcApiError,
+        error: JsonRpcApiResponse,
     ) {
         LogSignal::new(
             "service_broker".to_string(),
@@ -166,7 +331,7 @@ impl ServiceBroker {
             request.rpc.ctx.clone(),
         )
         .emit_error();
-        Self::send_broker_failure_response(callback, error.into());
+        Self::send_broker_failure_response(callback, error);
     }
 
     fn update_service_request(broker_request: &BrokerRequest) -> Result<String, RippleError> {
@@ -193,8 +358,10 @@ impl EndpointBroker for ServiceBroker {
         callback: BrokerCallback,
         broker_state: &mut EndpointBrokerState,
     ) -> Self {
+        let sender = Self::start(ps.clone(), callback, broker_state.clone());
         Self {
-            sender: Self::start(ps, callback, broker_state.clone()),
+            cleaner: Self::start_cleaner(ps, sender.clone()),
+            sender,
         }
     }
 
@@ -203,7 +370,7 @@ impl EndpointBroker for ServiceBroker {
     }
 
     fn get_cleaner(&self) -> super::endpoint_broker::BrokerCleaner {
-        BrokerCleaner::default()
+        self.cleaner.clone()
     }
 }
 
@@ -241,10 +408,8 @@ mod tests {
             telemetry_response_listeners: vec![],
         };
 
-        let error = JsonRpcApiError::default()
-            .with_code(-32001)
-            .with_message("Test error message".to_string())
-            .with_id(147);
+        let error =
+            JsonRpcApiResponse::builder(147).service_unavailable("Test error message".into());
 
         ServiceBroker::log_error_and_send_broker_failure_response(
             broker_request.clone(),
@@ -287,7 +452,10 @@ mod tests {
 
         let (tx, _rx) = mpsc::channel::<BrokerRequest>(10);
         let sender = BrokerSender { sender: tx.clone() };
-        let broker = ServiceBroker { sender };
+        let broker = ServiceBroker {
+            sender,
+            cleaner: BrokerCleaner::default(),
+        };
 
         assert!(broker.get_sender().sender.same_channel(&tx));
     }
@@ -298,7 +466,10 @@ mod tests {
 
         let (tx, _rx) = mpsc::channel::<BrokerRequest>(10);
         let sender = BrokerSender { sender: tx };
-        let broker = ServiceBroker { sender };
+        let broker = ServiceBroker {
+            sender,
+            cleaner: BrokerCleaner::default(),
+        };
 
         let cleaner = broker.get_cleaner();
         assert!(cleaner.cleaner.is_none());
@@ -349,4 +520,64 @@ mod tests {
             eprintln!("Timeout or channel closed without receiving data, skipping test");
         }
     }
+
+    #[tokio::test]
+    pub async fn test_start_request_timeouts() {
+        use crate::service::ripple_service::service_controller_state::ServiceInfo;
+        use ripple_sdk::api::manifest::device_manifest::ServiceTakeoverPolicy;
+        use tokio::time::timeout;
+
+        let platform_state = PlatformState::new(
+            ExtnManifest::default(),
+            DeviceManifest::default(),
+            RippleClient::new(ChannelsState::default()),
+            Vec::new(),
+            None,
+        );
+        let service_id = "ripple:channel:test:svc".to_string();
+        let (tx, _rx) = mpsc::channel::<Message>(4);
+        platform_state
+            .service_controller_state
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn1".into(), tx, false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await
+            .unwrap();
+        let (callback_tx, mut callback_rx) = mpsc::channel::<BrokerOutput>(1);
+        platform_state
+            .service_controller_state
+            .set_broker_callback(
+                &service_id,
+                21,
+                BrokerCallback {
+                    sender: callback_tx,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let request_timeouts = ServiceBroker::start_request_timeouts(platform_state.clone());
+        request_timeouts.add(
+            service_id.clone(),
+            "svc.slow".into(),
+            21,
+            Duration::from_millis(10),
+        );
+
+        let output = timeout(Duration::from_secs(5), callback_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(output.data.is_error());
+        assert!(platform_state
+            .service_controller_state
+            .extract_broker_callback(&service_id, 21)
+            .await
+            .unwrap()
+            .is_none());
+    }
 }
@@ -725,6 +725,8 @@ mod tests {
                 alias: "TestPlugin".to_string(),
                 transform: RuleTransform::default(),
                 endpoint: None,
+                event_debounce: None,
+                provenance: Default::default(),
             },
             subscription_processed: None,
         };
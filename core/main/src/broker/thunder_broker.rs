@@ -18,7 +18,7 @@ use super::{
     endpoint_broker::{
         BrokerCallback, BrokerCleaner, BrokerConnectRequest, BrokerOutput, BrokerRequest,
         BrokerSender, BrokerSubMap, EndpointBroker, EndpointBrokerState,
-        BROKER_CHANNEL_BUFFER_SIZE,
+        BROKER_CHANNEL_BUFFER_SIZE, BROKER_REQUEST_CAPACITY, BROKER_REQUEST_TTL,
     },
     thunder::thunder_plugins_status_mgr::StatusManager,
     thunder::user_data_migrator::UserDataMigrator,
@@ -37,13 +37,12 @@ use ripple_sdk::{
         time,
     },
     tokio_tungstenite::tungstenite::Message,
-    utils::{error::RippleError, ws_utils::WebSocketUtils},
+    utils::{error::RippleError, expiring_map::ExpiringMap, ws_utils::WebSocketUtils},
 };
 use serde_json::json;
 use serde_json::Value;
 use std::time::SystemTime;
 use std::{
-    collections::HashMap,
     sync::{Arc, RwLock},
     time::Duration,
     vec,
@@ -59,8 +58,8 @@ pub struct ThunderBroker {
     status_manager: StatusManager,
     default_callback: BrokerCallback,
     data_migrator: Option<UserDataMigrator>,
-    custom_callback_list: Arc<Mutex<HashMap<u64, BrokerCallback>>>,
-    composite_request_list: Arc<Mutex<HashMap<u64, CompositeRequest>>>,
+    custom_callback_list: Arc<Mutex<ExpiringMap<u64, BrokerCallback>>>,
+    composite_request_list: Arc<Mutex<ExpiringMap<u64, CompositeRequest>>>,
     composite_request_purge_started: Arc<Mutex<bool>>,
 }
 
@@ -93,8 +92,14 @@ impl ThunderBroker {
             status_manager: StatusManager::new(),
             default_callback,
             data_migrator: None,
-            custom_callback_list: Arc::new(Mutex::new(HashMap::new())),
-            composite_request_list: Arc::new(Mutex::new(HashMap::new())),
+            custom_callback_list: Arc::new(Mutex::new(ExpiringMap::new(
+                BROKER_REQUEST_TTL,
+                BROKER_REQUEST_CAPACITY,
+            ))),
+            composite_request_list: Arc::new(Mutex::new(ExpiringMap::new(
+                Duration::from_secs(COMPOSITE_REQUEST_TIME_OUT),
+                BROKER_REQUEST_CAPACITY,
+            ))),
             composite_request_purge_started: Arc::new(Mutex::new(false)),
         }
     }
@@ -164,23 +169,9 @@ impl ThunderBroker {
             loop {
                 interval.tick().await;
                 let mut composite_request_list = composite_request_list.lock().await;
-                let mut keys_to_remove = Vec::new();
-                for (key, value) in composite_request_list.iter() {
-                    match value.time_stamp.elapsed() {
-                        Ok(elapsed) => {
-                            if elapsed.as_secs() > COMPOSITE_REQUEST_TIME_OUT {
-                                keys_to_remove.push(*key);
-                            }
-                        }
-                        Err(e) => {
-                            error!("Error while calculating elapsed time {:?}", e);
-                        }
-                    }
-                }
-                // remove request from the list
-                for key in keys_to_remove {
-                    composite_request_list.remove(&key);
-                    debug!("Removed composite request with id {}", key);
+                let purged = composite_request_list.purge_expired();
+                if purged > 0 {
+                    debug!("Removed {} expired composite requests", purged);
                 }
                 if composite_request_list.is_empty() {
                     *purge_thread_started.lock().await = false;
@@ -840,6 +831,8 @@ mod tests {
                 filter: event_filter,
                 event_handler,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             },
             subscription_processed: None,
             workflow_callback: None,
@@ -1067,6 +1060,8 @@ mod tests {
                     filter: None,
                     event_handler: None,
                     sources: None,
+                    event_debounce: None,
+                    provenance: Default::default(),
                 },
                 subscription_processed: Some(false),
                 workflow_callback: None,
@@ -1124,6 +1119,8 @@ mod tests {
                     filter: None,
                     event_handler: None,
                     sources: None,
+                    event_debounce: None,
+                    provenance: Default::default(),
                 },
                 subscription_processed: Some(true),
                 workflow_callback: None,
@@ -82,7 +82,11 @@ impl WebsocketBroker {
                             }
 
                         },
-                        Some(request) = tr.recv() => {
+                        request = tr.recv() => {
+                            // the sender is dropped when the endpoint is rebuilt
+                            let Some(request) = request else {
+                                break true
+                            };
                             LogSignal::new(
                                 "websocket_broker".to_string(),
                                 format!("Got request from receiver for broker: {:?}", request),
@@ -304,6 +308,8 @@ mod tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             },
             workflow_callback: None,
             subscription_processed: None,
@@ -351,6 +357,8 @@ mod tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             },
             workflow_callback: None,
             subscription_processed: None,
@@ -382,6 +390,8 @@ mod tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             },
             workflow_callback: None,
             subscription_processed: None,
@@ -426,6 +436,8 @@ mod tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             },
             workflow_callback: None,
             subscription_processed: None,
@@ -505,6 +517,8 @@ mod tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                event_debounce: None,
+                provenance: Default::default(),
             },
             workflow_callback: None,
             subscription_processed: None,
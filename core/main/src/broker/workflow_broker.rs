@@ -555,7 +555,7 @@ pub mod tests {
             params: None,
         };
 
-        let broker_output = BrokerOutput { data: response };
+        let broker_output = BrokerOutput::new(response);
 
         callback.sender.send(broker_output).await.unwrap();
 
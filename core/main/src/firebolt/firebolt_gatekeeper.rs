@@ -20,10 +20,12 @@ use std::collections::HashMap;
 use ripple_sdk::api::firebolt::fb_capabilities::{
     DenyReason, DenyReasonWithCap, FireboltPermission,
 };
+use ripple_sdk::api::gatekeeper_policy::{GatekeeperPolicyDecision, GatekeeperPolicyRequest};
 use ripple_sdk::api::gateway::rpc_gateway_api::RpcRequest;
-use ripple_sdk::log::trace;
+use ripple_sdk::api::manifest::device_manifest::MethodOverrideAction;
+use ripple_sdk::log::{trace, warn};
 
-use crate::service::user_grants::GrantState;
+use crate::service::{telemetry_builder::TelemetryBuilder, user_grants::GrantState};
 //use crate::state::openrpc_state::ApiSurface;
 use crate::state::{cap::permitted_state::PermissionHandler, platform_state::PlatformState};
 
@@ -59,31 +61,70 @@ impl FireboltGatekeeper {
             method,
             secure,
         );
-        None
+        // methods registered by extensions at runtime carry the capability they were
+        // registered with
+        platform_state.extn_method_state.get_permissions(method)
     }
     // TODO return Deny Reason into ripple error
     pub async fn gate(
         state: PlatformState,
         request: RpcRequest,
     ) -> Result<Vec<FireboltPermission>, DenyReasonWithCap> {
-        let caps =
-            Self::get_resolved_caps_for_method(&state, &request.method, request.ctx.gateway_secure)
-                .ok_or(DenyReasonWithCap {
-                    reason: DenyReason::NotFound,
-                    caps: Vec::new(),
-                })?;
-
-        if caps.is_empty() {
-            // Couldnt find any capabilities for the method
-            trace!(
-                "Unable to find any caps for the method ({})",
-                request.method
+        let method_override = state
+            .method_override_state
+            .get_override(&request.ctx.app_id, &request.method);
+        if let Some(MethodOverrideAction::Deny) = method_override.as_ref().map(|o| o.action) {
+            warn!(
+                "Method {} blocked by policy for app {}",
+                request.method, request.ctx.app_id
             );
             return Err(DenyReasonWithCap {
-                reason: DenyReason::Unsupported,
+                reason: DenyReason::BlockedByPolicy,
                 caps: Vec::new(),
             });
         }
+        let policy_allowed = match Self::get_policy_decision(&state, &request).await {
+            Some(Err(e)) => return Err(e),
+            Some(Ok(())) => true,
+            None => false,
+        };
+        Self::check_capabilities(state, request, method_override.is_some(), policy_allowed).await
+    }
+
+    /// Runs the capability checks for the method. An allow override or a policy allow only
+    /// skips the availability checks, permissions and grants still apply.
+    async fn check_capabilities(
+        state: PlatformState,
+        request: RpcRequest,
+        overridden: bool,
+        policy_allowed: bool,
+    ) -> Result<Vec<FireboltPermission>, DenyReasonWithCap> {
+        let caps = match Self::get_resolved_caps_for_method(
+            &state,
+            &request.method,
+            request.ctx.gateway_secure,
+        ) {
+            Some(caps) if !caps.is_empty() => caps,
+            // the policy vouches for methods which need no capability
+            _ if policy_allowed => return Ok(Vec::new()),
+            None => {
+                return Err(DenyReasonWithCap {
+                    reason: DenyReason::NotFound,
+                    caps: Vec::new(),
+                })
+            }
+            Some(_) => {
+                // Couldnt find any capabilities for the method
+                trace!(
+                    "Unable to find any caps for the method ({})",
+                    request.method
+                );
+                return Err(DenyReasonWithCap {
+                    reason: DenyReason::Unsupported,
+                    caps: Vec::new(),
+                });
+            }
+        };
         let filtered_perm_list = state
             .clone()
             .cap_state
@@ -99,7 +140,12 @@ impl FireboltGatekeeper {
             request.method,
             filtered_perm_list
         );
-        if let Err(e) = state
+        if overridden || policy_allowed {
+            trace!(
+                "Availability checks skipped by policy for {}",
+                request.method
+            );
+        } else if let Err(e) = state
             .clone()
             .cap_state
             .generic
@@ -109,10 +155,80 @@ impl FireboltGatekeeper {
             return Err(e);
         }
         // permission checks
-        Self::permissions_check(state, request, filtered_perm_list).await?;
+        if let Err(e) =
+            Self::permissions_check(state.clone(), request.clone(), filtered_perm_list).await
+        {
+            // developer mode lets ungranted calls through and flags them in the response
+            if !state.developer_mode.relax(&request.ctx.request_id, &e) {
+                return Err(e);
+            }
+            warn!(
+                "Developer mode allowed {} for app {} reason={}",
+                request.method, request.ctx.app_id, e.reason
+            );
+            TelemetryBuilder::send_relaxed_capability_call(&state, &request, &e);
+        }
         Ok(caps)
     }
 
+    /// Asks the gatekeeper policy extension for a decision on the methods configured in the
+    /// device manifest. `None` lets the call fall through to the capability and permission
+    /// checks, `Some(Ok(()))` only skips the availability checks.
+    async fn get_policy_decision(
+        state: &PlatformState,
+        request: &RpcRequest,
+    ) -> Option<Result<(), DenyReasonWithCap>> {
+        let config = &state.get_device_configuration().gatekeeper_policy;
+        if !config.is_policy_method(&request.method) {
+            return None;
+        }
+        let policy_request = GatekeeperPolicyRequest {
+            app_id: request.ctx.app_id.clone(),
+            method: request.method.clone(),
+            session_id: request.ctx.session_id.clone(),
+        };
+        let decision = match state
+            .get_client()
+            .get_extn_client()
+            .request_with_timeout::<GatekeeperPolicyDecision>(
+                policy_request,
+                config.timeout_ms,
+                None,
+            )
+            .await
+        {
+            Ok(decision) => decision,
+            Err(e) => {
+                warn!(
+                    "Gatekeeper policy unavailable for {} fail_closed={} error={:?}",
+                    request.method, config.fail_closed, e
+                );
+                if config.fail_closed {
+                    GatekeeperPolicyDecision::Deny { reason: None }
+                } else {
+                    GatekeeperPolicyDecision::Fallthrough
+                }
+            }
+        };
+        match decision {
+            GatekeeperPolicyDecision::Allow => {
+                trace!("Gatekeeper policy allowed {}", request.method);
+                Some(Ok(()))
+            }
+            GatekeeperPolicyDecision::Deny { reason } => {
+                warn!(
+                    "Method {} denied by gatekeeper policy for app {} reason={:?}",
+                    request.method, request.ctx.app_id, reason
+                );
+                Some(Err(DenyReasonWithCap {
+                    reason: DenyReason::BlockedByPolicy,
+                    caps: Vec::new(),
+                }))
+            }
+            GatekeeperPolicyDecision::Fallthrough => None,
+        }
+    }
+
     async fn permissions_check(
         state: PlatformState,
         request: RpcRequest,
@@ -157,3 +273,69 @@ impl FireboltGatekeeper {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::extn_method_state::ExtnMethod;
+    use ripple_sdk::api::firebolt::fb_capabilities::FireboltCap;
+    use ripple_sdk::{tokio, Mockable as _};
+    use ripple_tdk::utils::test_utils::Mockable;
+
+    #[tokio::test]
+    async fn test_policy_gate() {
+        let mut state = PlatformState::mock();
+        let mut manifest = state.get_device_manifest();
+        manifest.configuration.gatekeeper_policy.methods = vec!["player.*".into()];
+        manifest.configuration.gatekeeper_policy.timeout_ms = 10;
+        manifest.configuration.gatekeeper_policy.fail_closed = true;
+        state.set_device_manifest(manifest);
+        state.extn_method_state.set_methods(
+            "ripple:extn:jsonrpsee:player",
+            vec![(
+                "player.seek".into(),
+                ExtnMethod::new(
+                    "ripple:extn:jsonrpsee:player",
+                    "player.seek",
+                    FireboltCap::Full("xrn:firebolt:capability:player:base".into()),
+                    None,
+                )
+                .unwrap(),
+            )],
+        );
+
+        // an unavailable policy extension fails closed
+        let mut request = RpcRequest::mock();
+        request.method = "player.seek".into();
+        let e = FireboltGatekeeper::gate(state.clone(), request.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(e.reason, DenyReason::BlockedByPolicy);
+
+        // a policy allow still needs the permissions for the capability
+        assert!(FireboltGatekeeper::check_capabilities(
+            state.clone(),
+            request.clone(),
+            false,
+            true
+        )
+        .await
+        .is_err());
+
+        // and vouches for methods without a capability
+        request.method = "player.unknown".into();
+        assert!(FireboltGatekeeper::check_capabilities(
+            state.clone(),
+            request.clone(),
+            false,
+            true
+        )
+        .await
+        .unwrap()
+        .is_empty());
+        let e = FireboltGatekeeper::check_capabilities(state, request, false, false)
+            .await
+            .unwrap_err();
+        assert_eq!(e.reason, DenyReason::NotFound);
+    }
+}
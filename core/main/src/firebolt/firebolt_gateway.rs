@@ -19,7 +19,7 @@ use jsonrpsee::{core::server::rpc_module::Methods, types::TwoPointZero};
 use ripple_sdk::{
     api::{
         firebolt::{
-            fb_capabilities::JSON_RPC_STANDARD_ERROR_INVALID_PARAMS,
+            fb_capabilities::{DenyReason, JSON_RPC_STANDARD_ERROR_INVALID_PARAMS},
             fb_openrpc::FireboltOpenRpcMethod,
         },
         gateway::{
@@ -27,6 +27,9 @@ use ripple_sdk::{
             rpc_gateway_api::{
                 ApiMessage, ApiProtocol, CallContext, JsonRpcApiResponse, RpcRequest,
             },
+            rpc_response::{
+                JSON_RPC_SERVER_ERROR_DEADLINE_EXCEEDED, JSON_RPC_SERVER_ERROR_THROTTLED,
+            },
         },
         observability::{log_signal::LogSignal, metrics_util::ApiStats},
     },
@@ -36,6 +39,7 @@ use ripple_sdk::{
     serde_json::{self, Value},
     service::service_message::{JsonRpcMessage as JsonRpcServiceMessage, ServiceMessage},
     tokio::{self, runtime::Handle, sync::mpsc::Sender},
+    utils::id_allocator::next_id,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -43,18 +47,19 @@ use std::collections::HashMap;
 use crate::{
     broker::endpoint_broker::BrokerOutput,
     firebolt::firebolt_gatekeeper::FireboltGatekeeper,
+    processor::metrics_processor::MetricsEnrichment,
     service::{
         apps::{app_events::AppEvents, provider_broker::ProviderBroker},
         telemetry_builder::TelemetryBuilder,
     },
     state::{
-        bootstrap_state::BootstrapState,
+        bootstrap_state::BootstrapState, error_budget_state::RpcOutcome,
         platform_state::PlatformState, session_state::Session,
     },
     utils::router_utils::{capture_stage, get_rpc_header_with_status},
 };
 
-use super::rpc_router::RpcRouter;
+use super::rpc_router::{RoutingTable, RpcRouter};
 
 pub struct FireboltGateway {
     state: BootstrapState,
@@ -106,7 +111,9 @@ impl FireboltGateway {
         for method in methods.method_names() {
             info!("Adding RPC method {}", method);
         }
-        state.platform_state.router_state.update_methods(methods);
+        if let Err(e) = state.platform_state.router_state.update_methods(methods) {
+            error!("Unable to register the Firebolt methods {:?}", e);
+        }
         FireboltGateway { state }
     }
 
@@ -160,9 +167,22 @@ impl FireboltGateway {
                 }
                 HandleRpcForService { msg } => {
                     if let JsonRpcServiceMessage::Request(json_rpc_request) = msg.message {
-                        let ctx = msg.context.as_ref().map_or_else(CallContext::default, |v| {
-                            serde_json::from_value(v.clone()).unwrap_or_default()
-                        });
+                        let mut ctx: CallContext =
+                            msg.context.as_ref().map_or_else(CallContext::default, |v| {
+                                serde_json::from_value(v.clone()).unwrap_or_default()
+                            });
+                        let service_id = ctx
+                            .context
+                            .get(1)
+                            .cloned()
+                            .unwrap_or_else(|| ctx.session_id.clone());
+                        ctx.request_id = self
+                            .state
+                            .platform_state
+                            .service_controller_state
+                            .allocate_request_id(&service_id, json_rpc_request.id.clone())
+                            .await;
+                        ctx.call_id = next_id();
                         let request: RpcRequest = RpcRequest {
                             ctx: ctx.clone(),
                             method: json_rpc_request.method,
@@ -305,9 +325,32 @@ impl FireboltGateway {
         let mut request_c = request.clone();
         request_c.method = FireboltOpenRpcMethod::name_with_lowercase_module(&request.method);
 
+        if matches!(request_c.ctx.protocol, ApiProtocol::JsonRpc) {
+            if let Some(deadline_ms) = platform_state
+                .get_device_configuration()
+                .deadlines
+                .get_deadline_ms(&request_c.method)
+            {
+                request_c.ctx.set_deadline_budget(deadline_ms);
+            }
+        }
+
         platform_state
             .metrics
             .add_api_stats(&request_c.ctx.request_id, &request_c.method);
+        // app requests are counted until answered so the shutdown can drain them, stuck
+        // requests are only failed when configured
+        if matches!(request_c.ctx.protocol, ApiProtocol::JsonRpc) {
+            let pending_requests = &platform_state.get_device_configuration().pending_requests;
+            let threshold = if pending_requests.enabled {
+                pending_requests.get_stuck_threshold(&request_c.method)
+            } else {
+                None
+            };
+            platform_state
+                .pending_request_state
+                .add(&request_c, threshold);
+        }
 
         let fail_open = matches!(
             platform_state
@@ -317,14 +360,45 @@ impl FireboltGateway {
             ripple_sdk::api::manifest::device_manifest::IntentValidation::FailOpen
         );
 
-
         tokio::spawn(async move {
             capture_stage(&platform_state.metrics, &request_c, "context_ready");
 
             capture_stage(&platform_state.metrics, &request_c, "openrpc_val");
+            // apps over their error budget are turned away until the throttle expires
+            if matches!(request_c.ctx.protocol, ApiProtocol::JsonRpc) {
+                if let Some(remaining_ms) = platform_state
+                    .error_budget_state
+                    .get_throttle_remaining(&request_c.ctx.app_id)
+                {
+                    send_json_rpc_error(
+                        &mut platform_state,
+                        &request,
+                        JsonRpcError {
+                            code: JSON_RPC_SERVER_ERROR_THROTTLED,
+                            message: format!(
+                                "Error budget exceeded, throttled for {}ms",
+                                remaining_ms
+                            ),
+                            data: None,
+                        },
+                    )
+                    .await;
+                    return;
+                }
+            }
+
             let result = if extn_request || service_request {
                 // extn protocol means its an internal Ripple request skip permissions.
                 Ok(Vec::new())
+            } else if platform_state
+                .admin_state
+                .is_admin_method(&request_c.method)
+            {
+                // admin methods are authorized by the admin role instead of capabilities
+                platform_state
+                    .admin_state
+                    .check(&request_c.ctx, &request_c.method)
+                    .map(|_| Vec::new())
             } else {
                 FireboltGatekeeper::gate(platform_state.clone(), request_c.clone()).await
             };
@@ -333,6 +407,24 @@ impl FireboltGateway {
 
             match result {
                 Ok(p) => {
+                    platform_state
+                        .cap_state
+                        .record_usage(&request_c.ctx.app_id, &p);
+
+                    // the app stopped waiting while the request was gated
+                    if request_c.ctx.is_expired() {
+                        send_json_rpc_error(
+                            &mut platform_state,
+                            &request,
+                            JsonRpcError {
+                                code: JSON_RPC_SERVER_ERROR_DEADLINE_EXCEEDED,
+                                message: format!("{} deadline exceeded", request_c.method),
+                                data: None,
+                            },
+                        )
+                        .await;
+                        return;
+                    }
                     if let Some(overridden_method) = platform_state
                         .get_manifest()
                         .has_rpc_override_method(&request_c.method)
@@ -349,17 +441,49 @@ impl FireboltGateway {
                         None
                     };
 
+                    // context overridden for the app session through ripple.setContextOverride
+                    if let Some(value) = session
+                        .as_ref()
+                        .and_then(|_| {
+                            platform_state
+                                .session_state
+                                .get_context_override(&request_c.ctx)
+                        })
+                        .and_then(|context| context.get_value(&request_c.method))
+                    {
+                        send_json_rpc_result(&platform_state, &request, Value::String(value)).await;
+                        return;
+                    }
+
+                    // region values from the manifest or remote configuration
+                    if let Some(value) = session
+                        .as_ref()
+                        .and_then(|_| platform_state.region_state.get_value(&request_c.method))
+                    {
+                        send_json_rpc_result(&platform_state, &request, Value::String(value)).await;
+                        return;
+                    }
+
+                    // app reported metrics events get the server side context
+                    if session.is_some() {
+                        MetricsEnrichment::enrich(&platform_state, &mut request_c);
+                    }
+
                     let requestor_callback_tx =
                         Self::handle_broker_callback(platform_state.clone(), request_c.clone());
 
-                    let handled = platform_state.endpoint_state.handle_brokerage(
-                        request_c.clone(),
-                        extn_msg.clone(),
-                        None,
-                        p,
-                        session.clone(),
-                        vec![requestor_callback_tx],
-                    );
+                    // handlers taking precedence over the rules of the method skip brokerage
+                    let handler_first =
+                        RoutingTable::is_handler_first(&platform_state, &request_c.method);
+                    let handled = !handler_first
+                        && platform_state.endpoint_state.handle_brokerage(
+                            request_c.clone(),
+                            extn_msg.clone(),
+                            None,
+                            p,
+                            session.clone(),
+                            vec![requestor_callback_tx],
+                        );
                     //.is_ok();
 
                     if !handled {
@@ -416,11 +540,33 @@ impl FireboltGateway {
                     );
 
                     let caps: Vec<String> = e.caps.iter().map(|x| x.as_str()).collect();
+                    if matches!(request_c.ctx.protocol, ApiProtocol::JsonRpc) {
+                        TelemetryBuilder::record_app_outcome(
+                            &platform_state,
+                            &request_c.ctx.app_id,
+                            RpcOutcome::ClientError,
+                        );
+                    }
+
+                    // policy blocks carry the operator supplied reason
+                    let data = if deny_reason == DenyReason::BlockedByPolicy {
+                        platform_state
+                            .method_override_state
+                            .get_override(&request_c.ctx.app_id, &request_c.method)
+                            .map(|o| {
+                                serde_json::json!({
+                                    "reason": deny_reason,
+                                    "details": o.reason,
+                                })
+                            })
+                    } else {
+                        None
+                    };
 
                     let json_rpc_error = JsonRpcError {
                         code: deny_reason.get_rpc_error_code(),
                         message: deny_reason.get_rpc_error_message(caps.clone()),
-                        data: None,
+                        data,
                     };
                     let caps_diag = caps.join(",");
                     let mut diagnostic_context = HashMap::new();
@@ -441,7 +587,33 @@ impl FireboltGateway {
     }
 }
 
-async fn send_json_rpc_error(
+async fn send_json_rpc_result(platform_state: &PlatformState, request: &RpcRequest, result: Value) {
+    if let Some(session) = platform_state.session_state.get_session(&request.ctx) {
+        let response = JsonRpcApiResponse {
+            id: Some(request.ctx.call_id),
+            result: Some(result),
+            ..Default::default()
+        };
+        if let Ok(response) = serde_json::to_string(&response) {
+            let mut api_message = ApiMessage::new(
+                request.ctx.protocol.clone(),
+                response,
+                request.ctx.request_id.clone(),
+            );
+            api_message.stats = platform_state
+                .metrics
+                .get_api_stats(&request.ctx.request_id);
+            if let Err(e) = session.send_json_rpc(api_message).await {
+                error!(
+                    "send_json_rpc_result: Error sending websocket message: e={:?}",
+                    e
+                )
+            }
+        }
+    }
+}
+
+pub async fn send_json_rpc_error(
     platform_state: &mut PlatformState,
     request: &RpcRequest,
     json_rpc_error: JsonRpcError,
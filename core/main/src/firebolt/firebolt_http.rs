@@ -0,0 +1,279 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{convert::Infallible, time::Duration};
+
+use hyper::{
+    body::HttpBody,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use jsonrpsee::types::{error::INVALID_REQUEST_CODE, ErrorObject, ErrorResponse, Id};
+use ripple_sdk::{
+    api::{
+        gateway::rpc_gateway_api::RpcRequest,
+        manifest::device_manifest::{AdminRole, HttpBridgeConfiguration},
+    },
+    log::{error, info},
+    tokio::{self, sync::mpsc},
+    uuid::Uuid,
+};
+
+use super::firebolt_gateway::FireboltGatewayCommand;
+use crate::{
+    state::{platform_state::PlatformState, session_state::Session},
+    utils::bind_utils::resolve_bind_address,
+};
+
+/// HTTP bridge to the Firebolt gateway for tooling which cant use websockets.
+///
+/// Every POST carries a single JSON-RPC request which goes through the same gatekeeper and
+/// router as the websocket gateway, using a short lived session for the request. The app is
+/// given by the `appId` query parameter and requests are authorized with an admin token in
+/// the `Authorization: Bearer <token>` header. Events are not available over HTTP.
+pub struct FireboltHttp;
+
+impl FireboltHttp {
+    pub async fn start(server_addr: &str, state: PlatformState) {
+        let config = state.get_device_manifest().get_http_bridge_configuration();
+        let addr = match resolve_bind_address(server_addr) {
+            Ok(addr) => addr,
+            Err(e) => {
+                error!("Invalid HTTP bridge address {}: {:?}", server_addr, e);
+                return;
+            }
+        };
+        let listener = server_addr.to_owned();
+        let make_service = make_service_fn(move |_| {
+            let state = state.clone();
+            let config = config.clone();
+            state.metrics.record_connection(&listener);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    Self::handle(state.clone(), config.clone(), req)
+                }))
+            }
+        });
+        let server = match Server::try_bind(&addr) {
+            Ok(builder) => builder.serve(make_service),
+            Err(e) => {
+                error!("Failed to bind HTTP bridge {}: {:?}", addr, e);
+                return;
+            }
+        };
+        info!("HTTP bridge listening on: {}", addr);
+        if let Err(e) = server.await {
+            error!("HTTP bridge error {:?}", e);
+        }
+    }
+
+    async fn handle(
+        state: PlatformState,
+        config: HttpBridgeConfiguration,
+        req: Request<Body>,
+    ) -> Result<Response<Body>, Infallible> {
+        if req.method() != Method::POST {
+            return Ok(Self::get_status_response(StatusCode::METHOD_NOT_ALLOWED));
+        }
+        let role = match Self::get_bearer_token(&req)
+            .and_then(|token| state.admin_state.resolve_token(&token))
+        {
+            Some(role) if role >= config.min_role => role,
+            Some(_) => return Ok(Self::get_status_response(StatusCode::FORBIDDEN)),
+            None => return Ok(Self::get_status_response(StatusCode::UNAUTHORIZED)),
+        };
+        let app_id = match req.uri().query().and_then(|qs| {
+            querystring::querify(qs)
+                .iter()
+                .find(|q| q.0 == "appId")
+                .map(|q| q.1.to_owned())
+        }) {
+            Some(app_id) => app_id,
+            None => return Ok(Self::get_status_response(StatusCode::BAD_REQUEST)),
+        };
+        let body = match Self::read_body(req.into_body(), config.max_body_bytes).await {
+            Ok(body) => body,
+            Err(status) => return Ok(Self::get_status_response(status)),
+        };
+
+        let response = Self::handle_rpc(&state, &config, app_id, role, body).await;
+        Ok(match response {
+            Some(jsonrpc_msg) => Response::builder()
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(jsonrpc_msg))
+                .unwrap_or_default(),
+            None => Self::get_status_response(StatusCode::GATEWAY_TIMEOUT),
+        })
+    }
+
+    /// Reads the body up to the limit, larger requests are refused before they are buffered
+    async fn read_body(mut body: Body, limit: usize) -> Result<String, StatusCode> {
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(|e| {
+                error!("Unable to read HTTP bridge request {:?}", e);
+                StatusCode::BAD_REQUEST
+            })?;
+            if bytes.len() + chunk.len() > limit {
+                error!("HTTP bridge request exceeds {} bytes", limit);
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+
+    async fn handle_rpc(
+        state: &PlatformState,
+        config: &HttpBridgeConfiguration,
+        app_id: String,
+        role: AdminRole,
+        body: String,
+    ) -> Option<String> {
+        let connection_id = Uuid::new_v4().to_string();
+        let session_id = Uuid::new_v4().to_string();
+        let request = match RpcRequest::parse(
+            body,
+            app_id.clone(),
+            session_id.clone(),
+            Uuid::new_v4().to_string(),
+            Some(connection_id.clone()),
+            false,
+            Vec::new(),
+        ) {
+            Ok(request) => request,
+            Err(_) => return Some(Self::get_error_message(Id::Null, "invalid request")),
+        };
+        if request.is_subscription() {
+            return Some(Self::get_error_message(
+                Id::Number(request.ctx.call_id),
+                "Events are not available over the HTTP bridge, use the websocket gateway",
+            ));
+        }
+
+        let request_id = request.ctx.request_id.clone();
+        let client = state.get_client();
+        let (session_tx, mut resp_rx) = mpsc::channel(1);
+        let session = Session::new(app_id, Some(session_tx));
+        if let Err(e) = client.send_gateway_command(FireboltGatewayCommand::RegisterSession {
+            session_id: connection_id.clone(),
+            session,
+        }) {
+            error!("Error registering the HTTP bridge session: {:?}", e);
+            return None;
+        }
+        state
+            .admin_state
+            .add_connection(connection_id.clone(), role);
+
+        let response = match client
+            .send_gateway_command(FireboltGatewayCommand::HandleRpc { request })
+        {
+            Ok(_) => tokio::time::timeout(Duration::from_millis(config.timeout_ms), resp_rx.recv())
+                .await
+                .ok()
+                .flatten()
+                .map(|mut api_message| {
+                    state.developer_mode.attach_warning(&mut api_message);
+                    state
+                        .endpoint_state
+                        .get_explain_state()
+                        .attach(&mut api_message);
+                    api_message.jsonrpc_msg
+                }),
+            Err(e) => {
+                error!("failed to send request {:?}", e);
+                None
+            }
+        };
+
+        state.pending_request_state.complete(&request_id);
+        state.admin_state.remove_connection(&connection_id);
+        if let Err(e) = client.send_gateway_command(FireboltGatewayCommand::UnregisterSession {
+            session_id,
+            cid: connection_id,
+        }) {
+            error!("Error Unregistering {:?}", e);
+        }
+        response
+    }
+
+    fn get_bearer_token(req: &Request<Body>) -> Option<String> {
+        req.headers()
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_owned())
+    }
+
+    fn get_error_message(id: Id<'_>, message: &str) -> String {
+        let err = ErrorResponse::owned(
+            ErrorObject::owned::<()>(INVALID_REQUEST_CODE, message.to_owned(), None),
+            id.into_owned(),
+        );
+        serde_json::to_string(&err).unwrap()
+    }
+
+    fn get_status_response(status: StatusCode) -> Response<Body> {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = status;
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_bearer_token() {
+        let req = Request::builder()
+            .header(AUTHORIZATION, "Bearer dev-token")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            FireboltHttp::get_bearer_token(&req),
+            Some("dev-token".to_owned())
+        );
+        let req = Request::builder()
+            .header(AUTHORIZATION, "Basic abc")
+            .body(Body::empty())
+            .unwrap();
+        assert!(FireboltHttp::get_bearer_token(&req).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_body() {
+        assert_eq!(
+            FireboltHttp::read_body(Body::from("{}"), 2).await,
+            Ok("{}".to_owned())
+        );
+        assert_eq!(
+            FireboltHttp::read_body(Body::from("{\"id\":1}"), 2).await,
+            Err(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+    }
+
+    #[test]
+    fn test_get_error_message() {
+        let msg = FireboltHttp::get_error_message(Id::Number(1), "no events");
+        let value: serde_json::Value = serde_json::from_str(&msg).unwrap();
+        assert_eq!(value["id"], 1);
+        assert_eq!(value["error"]["code"], INVALID_REQUEST_CODE);
+    }
+}
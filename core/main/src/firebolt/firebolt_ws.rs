@@ -18,24 +18,42 @@
 use std::{
     net::SocketAddr,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 use super::firebolt_gateway::FireboltGatewayCommand;
 use crate::{
     service::apps::delegated_launcher_handler::{AppManagerState, AppManagerState2_0},
-    service::ripple_service::service_controller_state::ServiceControllerState,
+    service::ripple_service::{
+        service_auth::{PresentedCredentials, ServiceAuth, SERVICE_UNAUTHORIZED_BODY},
+        service_controller_state::ServiceControllerState,
+    },
+    service::{inspector::Inspector, telemetry_builder::TelemetryBuilder},
     state::{
-        cap::permitted_state::PermissionHandler, platform_state::PlatformState,
-        session_state::Session,
+        cap::permitted_state::PermissionHandler,
+        platform_state::PlatformState,
+        session_state::{ConnectionEviction, ConnectionLimit, Session},
+        shutdown_state::ShutdownPhase,
     },
+    utils::bind_utils::resolve_bind_address,
 };
 use futures::SinkExt;
 use futures::StreamExt;
 use jsonrpsee::types::{error::INVALID_REQUEST_CODE, ErrorObject, ErrorResponse, Id};
 use ripple_sdk::{
-    api::manifest::extn_manifest::ExtnSymbol,
+    api::{
+        firebolt::fb_telemetry::BootMilestoneType,
+        manifest::{
+            device_manifest::{WsConfiguration, WsKeepaliveConfiguration},
+            extn_manifest::{ExtnSymbol, CONTRACT_VERSIONS_QUERY},
+        },
+    },
     tokio_tungstenite::{
-        tungstenite::{self, Message},
+        tungstenite::{
+            self,
+            protocol::{frame::coding::CloseCode, CloseFrame},
+            Message,
+        },
         WebSocketStream,
     },
 };
@@ -46,18 +64,65 @@ use ripple_sdk::{
         },
         observability::log_signal::LogSignal,
     },
-    log::{error, info, trace},
+    log::{error, info, trace, warn},
     tokio::{
-        net::{TcpListener, TcpStream},
+        net::TcpListener,
         sync::{mpsc, oneshot},
+        time::Instant,
+    },
+    utils::{
+        channel_utils::oneshot_send_and_log,
+        error::RippleError,
+        tls_utils::{ServerStream, ServerTlsAcceptor},
     },
-    utils::channel_utils::oneshot_send_and_log,
     uuid::Uuid,
 };
 use ripple_sdk::{log::debug, tokio};
 #[allow(dead_code)]
 pub struct FireboltWs {}
 
+/// Notification sent to an app right before its session is closed to admit a critical app
+pub const SESSION_PREEMPTED_EVENT: &str = "ripple.onSessionPreempted";
+
+/// Time a client has to complete the TLS handshake of a new connection
+const TLS_HANDSHAKE_TIMEOUT_MS: u64 = 10_000;
+
+/// Reason for closing an app connection which failed admission, using the standard
+/// websocket close codes so browser apps can tell the failures apart.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsRejection {
+    /// None of the requested subprotocols are accepted, closed with 1002
+    UnsupportedSubprotocol(String),
+    /// The origin is not allowed by the origin policies, closed with 1008
+    OriginNotAllowed(String),
+    /// The session or app connection cap is reached, closed with 1013 so the app retries
+    /// later
+    ConnectionLimit(ConnectionLimit),
+}
+
+impl WsRejection {
+    fn get_close_frame(&self) -> CloseFrame<'static> {
+        match self {
+            WsRejection::UnsupportedSubprotocol(requested) => CloseFrame {
+                code: CloseCode::Protocol,
+                reason: format!("Unsupported subprotocol {}", requested).into(),
+            },
+            WsRejection::OriginNotAllowed(origin) => CloseFrame {
+                code: CloseCode::Policy,
+                reason: format!("Origin {} is not allowed", origin).into(),
+            },
+            WsRejection::ConnectionLimit(ConnectionLimit::Sessions(limit)) => CloseFrame {
+                code: CloseCode::Again,
+                reason: format!("Session limit {} reached", limit).into(),
+            },
+            WsRejection::ConnectionLimit(ConnectionLimit::App(limit)) => CloseFrame {
+                code: CloseCode::Again,
+                reason: format!("App connection limit {} reached", limit).into(),
+            },
+        }
+    }
+}
+
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct ClientIdentity {
@@ -65,6 +130,72 @@ pub struct ClientIdentity {
     pub app_id: String,
     pub rpc_v2: bool,
     pub service_info: Option<ExtnSymbol>,
+    /// Service connection asked to take over an already connected ServiceId
+    pub force_takeover: bool,
+    /// Token presented for the admin API
+    pub admin_token: Option<String>,
+    /// Firebolt SDK version declared by the app with the `sdkVersion` query parameter
+    pub sdk_version: Option<String>,
+    /// Set when the app connection failed admission, the connection is closed right after
+    /// the handshake
+    pub rejection: Option<WsRejection>,
+}
+
+#[derive(Debug, PartialEq)]
+enum KeepaliveAction {
+    Ping,
+    Close(&'static str),
+}
+
+/// Tracks the liveness of an app connection. Every frame from the app answers the
+/// outstanding pings, only Firebolt requests count against the idle timeout.
+#[derive(Debug)]
+struct AppKeepalive {
+    max_missed_pongs: u32,
+    idle_timeout: Option<Duration>,
+    missed_pongs: u32,
+    last_request: Instant,
+}
+
+impl AppKeepalive {
+    fn new(config: &WsKeepaliveConfiguration, app_id: &str, now: Instant) -> Self {
+        Self {
+            max_missed_pongs: config.max_missed_pongs,
+            idle_timeout: config
+                .get_idle_timeout_ms(app_id)
+                .map(Duration::from_millis),
+            missed_pongs: 0,
+            last_request: now,
+        }
+    }
+
+    fn on_frame(&mut self) {
+        self.missed_pongs = 0;
+    }
+
+    fn on_request(&mut self, now: Instant) {
+        self.last_request = now;
+    }
+
+    fn on_tick(&mut self, now: Instant) -> KeepaliveAction {
+        if let Some(idle_timeout) = self.idle_timeout {
+            if now.duration_since(self.last_request) >= idle_timeout {
+                return KeepaliveAction::Close("Idle timeout");
+            }
+        }
+        if self.missed_pongs >= self.max_missed_pongs {
+            return KeepaliveAction::Close("Keepalive timeout");
+        }
+        self.missed_pongs += 1;
+        KeepaliveAction::Ping
+    }
+}
+
+/// Controls the lifetime of an app connection besides the socket itself
+struct AppConnectionControl {
+    keepalive: WsKeepaliveConfiguration,
+    /// Receives why Ripple closes the connection
+    evict_rx: oneshot::Receiver<ConnectionEviction>,
 }
 
 struct ConnectionCallbackConfig {
@@ -75,6 +206,10 @@ struct ConnectionCallbackConfig {
     pub secure: bool,
     pub internal_app_id: Option<String>,
     extns: Vec<ExtnSymbol>,
+    ws_config: WsConfiguration,
+    service_auth: ServiceAuth,
+    /// Fingerprint of the TLS client certificate of the connection
+    cert_fingerprint: Option<String>,
 }
 
 impl ConnectionCallbackConfig {
@@ -125,6 +260,23 @@ fn get_query(
     Ok(found_q.map(|q| String::from(q.1)))
 }
 
+fn get_authorization(req: &tungstenite::handshake::server::Request) -> Option<&str> {
+    req.headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+}
+
+/// Admin token from the `Authorization` header. Query parameters end up in access logs and
+/// proxies, so a token in the URL is ignored.
+fn get_admin_token(req: &tungstenite::handshake::server::Request) -> Option<String> {
+    if matches!(get_query(req, "adminToken", false), Ok(Some(_))) {
+        warn!("Ignoring the adminToken query parameter, use the Authorization header");
+    }
+    get_authorization(req)
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_owned())
+}
+
 impl tungstenite::handshake::server::Callback for ConnectionCallback {
     fn on_request(
         self,
@@ -141,13 +293,54 @@ impl tungstenite::handshake::server::Callback for ConnectionCallback {
         if !cfg.secure {
             if let Ok(Some(extn_id)) = get_query(request, "service_handshake", false) {
                 info!("Service handshake for extn_id={}", extn_id);
-                let cid = if let Some(c) = cfg.get_extn(&extn_id) {
+                let credentials = PresentedCredentials::default()
+                    .with_authorization(get_authorization(request))
+                    .with_cert_fingerprint(cfg.cert_fingerprint.clone());
+                if let Err(e) = cfg.service_auth.authenticate(&extn_id, &credentials) {
+                    error!("Service connection refused extn_id={} {}", extn_id, e);
+                    let err = tungstenite::http::response::Builder::new()
+                        .status(401)
+                        .header("Content-Type", "application/json")
+                        .body(Some(SERVICE_UNAUTHORIZED_BODY.to_owned()))
+                        .unwrap();
+                    return Err(err);
+                }
+                let force_takeover = matches!(
+                    get_query(request, "force", false),
+                    Ok(Some(force)) if force == "true"
+                );
+                let contract_versions = match get_query(request, CONTRACT_VERSIONS_QUERY, false)?
+                    .as_deref()
+                    .map(ExtnSymbol::decode_contract_versions)
+                    .transpose()
+                {
+                    Ok(contract_versions) => contract_versions,
+                    Err(_) => {
+                        let err_msg =
+                            format!("Invalid {} query parameter", CONTRACT_VERSIONS_QUERY);
+                        error!("Service connection refused extn_id={} {}", extn_id, err_msg);
+                        let err = tungstenite::http::response::Builder::new()
+                            .status(400)
+                            .body(Some(err_msg))
+                            .unwrap();
+                        return Err(err);
+                    }
+                };
+                let cid = if let Some(mut c) = cfg.get_extn(&extn_id) {
+                    // The versions declared in the handshake take precedence over the manifest
+                    if let Some(contract_versions) = contract_versions {
+                        c.contract_versions = contract_versions;
+                    }
                     // valid extn_id
                     ClientIdentity {
                         session_id: Uuid::new_v4().to_string(),
                         app_id: extn_id.clone(),
                         rpc_v2: true,
                         service_info: Some(c),
+                        force_takeover,
+                        admin_token: None,
+                        sdk_version: None,
+                        rejection: None,
                     }
                 } else {
                     // extn_id without any symbol in the manifest
@@ -155,6 +348,7 @@ impl tungstenite::handshake::server::Callback for ConnectionCallback {
                     // Accept the connection, the service will be registered later.
                     let extn_symbol = ExtnSymbol {
                         id: extn_id.clone(),
+                        contract_versions: contract_versions.unwrap_or_default(),
                         ..Default::default()
                     };
                     ClientIdentity {
@@ -162,6 +356,10 @@ impl tungstenite::handshake::server::Callback for ConnectionCallback {
                         app_id: extn_id.clone(),
                         rpc_v2: true,
                         service_info: Some(extn_symbol),
+                        force_takeover,
+                        admin_token: None,
+                        sdk_version: None,
+                        rejection: None,
                     }
                 };
                 info!("New Service connection {:?}", extn_id);
@@ -227,16 +425,40 @@ impl tungstenite::handshake::server::Callback for ConnectionCallback {
         /*
         add Sec-WebSocket-Protocol header to the response to indicate we suport jsonrpc
         this was breaking FCA as it tried to use standard websocket protocol and do the upgrade,
-        but ripple was not sending the header
+        but ripple was not sending the header. The accepted subprotocols are configured in
+        the device manifest, jsonrpc by default.
         */
-        if request.headers().get("Sec-WebSocket-Protocol").is_some() {
-            /*
-            jsonrpc is the only answer...
-            */
-            response.headers_mut().insert(
-                "Sec-WebSocket-Protocol",
-                tungstenite::http::header::HeaderValue::from_str("jsonrpc").unwrap(),
-            );
+        let mut rejection = None;
+        if let Some(requested) = request
+            .headers()
+            .get("Sec-WebSocket-Protocol")
+            .and_then(|h| h.to_str().ok())
+        {
+            match cfg.ws_config.negotiate_subprotocol(requested) {
+                Some(subprotocol) => {
+                    if let Ok(value) =
+                        tungstenite::http::header::HeaderValue::from_str(&subprotocol)
+                    {
+                        response
+                            .headers_mut()
+                            .insert("Sec-WebSocket-Protocol", value);
+                    }
+                }
+                None => {
+                    error!("Unsupported subprotocol {} app_id={}", requested, app_id);
+                    rejection = Some(WsRejection::UnsupportedSubprotocol(requested.to_owned()));
+                }
+            }
+        }
+
+        let origin = request
+            .headers()
+            .get("Origin")
+            .and_then(|h| h.to_str().ok());
+        if !cfg.ws_config.is_origin_allowed(origin) {
+            let origin = origin.unwrap_or_default().to_owned();
+            error!("Origin {} is not allowed app_id={}", origin, app_id);
+            rejection = Some(WsRejection::OriginNotAllowed(origin));
         }
 
         info!("{:?} {} is_rpc_v2={}", query, app_id, rpc_v2);
@@ -246,6 +468,10 @@ impl tungstenite::handshake::server::Callback for ConnectionCallback {
             app_id,
             rpc_v2,
             service_info: None,
+            force_takeover: false,
+            admin_token: get_admin_token(request),
+            sdk_version: get_query(request, "sdkVersion", false).ok().flatten(),
+            rejection,
         };
         oneshot_send_and_log(cfg.next, cid, "ResolveClientIdentity");
 
@@ -254,68 +480,164 @@ impl tungstenite::handshake::server::Callback for ConnectionCallback {
 }
 
 impl FireboltWs {
+    /// Creates the TCP listener we'll accept connections on
+    pub async fn bind(server_addr: &str) -> Result<TcpListener, RippleError> {
+        let bind_addr = resolve_bind_address(server_addr).map_err(|e| {
+            error!("Invalid gateway address {}: {:?}", server_addr, e);
+            RippleError::BootstrapError
+        })?;
+        TcpListener::bind(bind_addr).await.map_err(|e| {
+            error!("Failed to bind {}: {:?}", server_addr, e);
+            RippleError::BootstrapError
+        })
+    }
+
+    /// Binds and serves an additional gateway, which is skipped when it cannot be bound
     pub async fn start(
         server_addr: &str,
         state: PlatformState,
         secure: bool,
         internal_app_id: Option<String>,
+        tls_acceptor: ServerTlsAcceptor,
+    ) {
+        if let Ok(listener) = Self::bind(server_addr).await {
+            Self::serve(
+                listener,
+                server_addr,
+                state,
+                secure,
+                internal_app_id,
+                tls_acceptor,
+            )
+            .await;
+        }
+    }
+
+    /// Accepts connections on a listener which is already bound, like a socket inherited
+    /// through systemd socket activation. The TLS acceptor is built by the caller, so a bad
+    /// TLS configuration fails the bootstrap instead of the listener task.
+    pub async fn serve(
+        listener: TcpListener,
+        listener_name: &str,
+        state: PlatformState,
+        secure: bool,
+        internal_app_id: Option<String>,
+        tls_acceptor: ServerTlsAcceptor,
     ) {
-        // Create the event loop and TCP listener we'll accept connections on.
-        let try_socket = TcpListener::bind(&server_addr).await; //create the server on the address
-        let listener = try_socket.unwrap_or_else(|_| panic!("Failed to bind {:?}", server_addr));
-        info!("Listening on: {} secure={}", server_addr, secure);
         let state_for_connection = state.clone();
         let extns = state.extn_manifest.get_all_extns();
+        let manifest = state.get_device_manifest();
+        let ws_config = if secure {
+            manifest.configuration.ws_configuration
+        } else {
+            manifest.configuration.internal_ws_configuration
+        };
+        info!(
+            "Listening on: {} secure={} tls={}",
+            listener_name,
+            secure,
+            ws_config.tls.is_some()
+        );
+        TelemetryBuilder::send_boot_milestone(&state, BootMilestoneType::WsListening);
         let app_state = state.app_manager_state.clone();
         let app_state2_0 = state.lifecycle2_app_state.clone();
         let app_lifecycle_2_enabled = std::env::var("RIPPLE_LIFECYCLE_2_ENABLED")
             .ok()
             .and_then(|s| s.parse::<bool>().ok())
             .unwrap_or(false);
+        let mut shutdown = state.shutdown_state.subscribe();
         // Let's spawn the handling of each connection in a separate task.
-        while let Ok((stream, client_addr)) = listener.accept().await {
-            let (connect_tx, connect_rx) = oneshot::channel::<ClientIdentity>();
-            let cfg = ConnectionCallbackConfig {
-                next: connect_tx,
-                app_state: app_state.clone(),
-                app_state2_0: app_state2_0.clone(),
-                app_lifecycle_2_enabled,
-                secure,
-                internal_app_id: internal_app_id.clone(),
-                extns: extns.clone(),
-            };
-            match ripple_sdk::tokio_tungstenite::accept_hdr_async(stream, ConnectionCallback(cfg))
-                .await
-            {
-                Err(e) => {
-                    error!("websocket connection error {:?}", e);
+        loop {
+            let (stream, client_addr) = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(_) => break,
+                },
+                reason = shutdown.wait_for(ShutdownPhase::Draining) => {
+                    info!("Stopped listening on {} reason={}", listener_name, reason);
+                    break;
                 }
-                Ok(ws_stream) => {
-                    trace!("websocket connection success");
-                    let state_for_connection_c = state_for_connection.clone();
-                    tokio::spawn(async move {
-                        FireboltWs::handle_connection(
-                            client_addr,
-                            ws_stream,
-                            connect_rx,
-                            state_for_connection_c.clone(),
-                            secure,
-                        )
-                        .await;
-                    });
-                }
-            }
+            };
+            state.metrics.record_connection(listener_name);
+            let tls_acceptor = tls_acceptor.clone();
+            let app_state = app_state.clone();
+            let app_state2_0 = app_state2_0.clone();
+            let internal_app_id = internal_app_id.clone();
+            let extns = extns.clone();
+            let ws_config = ws_config.clone();
+            let service_auth = state.service_auth.clone();
+            let state_for_connection_c = state_for_connection.clone();
+            // the handshakes run in the connection task so a stalled client does not hold up
+            // the listener
+            state_for_connection
+                .runtime_topology
+                .spawn_websocket(async move {
+                    let stream = match tokio::time::timeout(
+                        Duration::from_millis(TLS_HANDSHAKE_TIMEOUT_MS),
+                        tls_acceptor.accept(stream),
+                    )
+                    .await
+                    {
+                        Ok(Ok(stream)) => stream,
+                        Ok(Err(_)) => return,
+                        Err(_) => {
+                            error!("TLS handshake timed out for {}", client_addr);
+                            return;
+                        }
+                    };
+                    let (connect_tx, connect_rx) = oneshot::channel::<ClientIdentity>();
+                    let keepalive = ws_config.keepalive.clone();
+                    let cfg = ConnectionCallbackConfig {
+                        next: connect_tx,
+                        app_state,
+                        app_state2_0,
+                        app_lifecycle_2_enabled,
+                        secure,
+                        internal_app_id,
+                        extns,
+                        ws_config,
+                        service_auth,
+                        cert_fingerprint: stream.get_peer_cert_fingerprint(),
+                    };
+                    match ripple_sdk::tokio_tungstenite::accept_hdr_async(
+                        stream,
+                        ConnectionCallback(cfg),
+                    )
+                    .await
+                    {
+                        Err(e) => {
+                            error!("websocket connection error {:?}", e);
+                        }
+                        Ok(ws_stream) => {
+                            trace!("websocket connection success");
+                            FireboltWs::handle_connection(
+                                client_addr,
+                                ws_stream,
+                                connect_rx,
+                                state_for_connection_c,
+                                secure,
+                                keepalive,
+                            )
+                            .await;
+                        }
+                    }
+                });
         }
     }
 
     async fn handle_app_connection(
         _client_addr: SocketAddr,
-        ws_stream: WebSocketStream<TcpStream>,
+        ws_stream: WebSocketStream<ServerStream>,
         state: PlatformState,
         identity: ClientIdentity,
         connection_id: String,
         gateway_secure: bool,
+        control: AppConnectionControl,
     ) {
+        let AppConnectionControl {
+            keepalive: keepalive_config,
+            mut evict_rx,
+        } = control;
         info!(
             "Creating new app connection_id={} app_id={} session_id={}, gateway_secure={}, port={}",
             connection_id,
@@ -333,7 +655,8 @@ impl FireboltWs {
             app_id: app_id.clone(),
             gateway_secure,
         };
-        let session = Session::new(identity.app_id.clone(), Some(session_tx.clone()));
+        let session = Session::new(identity.app_id.clone(), Some(session_tx.clone()))
+            .with_sdk_version(identity.sdk_version.clone());
         let app_id_c = app_id.clone();
         let session_id_c = identity.session_id.clone();
         let connection_id_c = connection_id.clone();
@@ -346,6 +669,22 @@ impl FireboltWs {
             error!("Error registering the app connection: {:?}", e);
             return;
         }
+        TelemetryBuilder::send_boot_milestone(&state, BootMilestoneType::FirstAppConnected);
+
+        if let Some(token) = &identity.admin_token {
+            match state.admin_state.resolve_token(token) {
+                Some(role) => {
+                    info!(
+                        "Admin role {:?} bound to connection_id={}",
+                        role, connection_id
+                    );
+                    state
+                        .admin_state
+                        .add_connection(connection_id.clone(), role);
+                }
+                None => error!("Invalid admin token for connection_id={}", connection_id),
+            }
+        }
 
         if !gateway_secure
             && PermissionHandler::fetch_and_store(&state, &identity.app_id, false)
@@ -364,9 +703,56 @@ impl FireboltWs {
         let (mut sender, mut receiver) = ws_stream.split();
         let mut platform_state = state.clone();
         let context_clone = ctx.clone();
+        // keepalive pings and closes for dead connections
+        let (control_tx, mut control_rx) = mpsc::channel::<Message>(4);
+        let mut shutdown = state.shutdown_state.subscribe();
 
         tokio::spawn(async move {
-            while let Some(api_message) = resp_rx.recv().await {
+            loop {
+                let mut api_message = tokio::select! {
+                    api_message = resp_rx.recv() => match api_message {
+                        Some(api_message) => api_message,
+                        None => break,
+                    },
+                    Some(control) = control_rx.recv() => {
+                        let is_close = control.is_close();
+                        if let Err(e) = sender.send(control).await {
+                            error!("Error sending control frame {:?}", e);
+                        }
+                        if is_close {
+                            break;
+                        }
+                        continue;
+                    },
+                    reason = shutdown.wait_for(ShutdownPhase::Closing) => {
+                        let close = Message::Close(Some(CloseFrame {
+                            code: CloseCode::Away,
+                            reason: reason.into(),
+                        }));
+                        if let Err(e) = sender.send(close).await {
+                            error!("Error closing connection on shutdown {:?}", e);
+                        }
+                        break;
+                    }
+                };
+                platform_state
+                    .developer_mode
+                    .attach_warning(&mut api_message);
+                platform_state
+                    .endpoint_state
+                    .get_explain_state()
+                    .attach(&mut api_message);
+                platform_state
+                    .pending_request_state
+                    .complete(&api_message.request_id);
+                Inspector::mirror(
+                    &platform_state,
+                    &session_id_c,
+                    &app_id_c,
+                    false,
+                    &api_message.jsonrpc_msg,
+                )
+                .await;
                 let send_result = sender
                     .send(Message::Text(api_message.jsonrpc_msg.clone()))
                     .await;
@@ -421,10 +807,76 @@ impl FireboltWs {
         });
         let session_id_c = identity.session_id.clone();
         let app_id_c = identity.app_id.clone();
-        while let Some(msg) = receiver.next().await {
+        let mut keepalive = AppKeepalive::new(&keepalive_config, &app_id_c, Instant::now());
+        let ping_interval = Duration::from_millis(keepalive_config.ping_interval_ms.max(1));
+        let mut keepalive_interval =
+            tokio::time::interval_at(Instant::now() + ping_interval, ping_interval);
+        loop {
+            let msg = tokio::select! {
+                msg = receiver.next() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                _ = keepalive_interval.tick(), if keepalive_config.enabled => {
+                    match keepalive.on_tick(Instant::now()) {
+                        KeepaliveAction::Ping => {
+                            if let Err(e) = control_tx.try_send(Message::Ping(Vec::new())) {
+                                trace!("Failed to ping cid={} {:?}", connection_id, e);
+                            }
+                        }
+                        KeepaliveAction::Close(reason) => {
+                            info!(
+                                "Closing dead app connection_id={} app_id={} reason={}",
+                                connection_id, app_id_c, reason
+                            );
+                            let _ = control_tx.try_send(Message::Close(Some(CloseFrame {
+                                code: CloseCode::Away,
+                                reason: reason.into(),
+                            })));
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                eviction = &mut evict_rx => {
+                    let preempted_by = match eviction {
+                        Ok(ConnectionEviction::Restart(reason)) => {
+                            info!(
+                                "Closing app connection_id={} app_id={} for gateway restart reason={}",
+                                connection_id, app_id_c, reason
+                            );
+                            let _ = control_tx.try_send(Message::Close(Some(CloseFrame {
+                                code: CloseCode::Restart,
+                                reason: reason.into(),
+                            })));
+                            break;
+                        }
+                        Ok(ConnectionEviction::Preempted(preempted_by)) => preempted_by,
+                        Err(_) => String::default(),
+                    };
+                    info!(
+                        "Session preempted connection_id={} app_id={} preempted_by={}",
+                        connection_id, app_id_c, preempted_by
+                    );
+                    let event = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": SESSION_PREEMPTED_EVENT,
+                        "params": { "preemptedBy": preempted_by },
+                    });
+                    let _ = control_tx.try_send(Message::Text(event.to_string()));
+                    let _ = control_tx.try_send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::Away,
+                        reason: "Session preempted".into(),
+                    })));
+                    break;
+                }
+            };
+            keepalive.on_frame();
             match msg {
                 Ok(msg) => {
                     if msg.is_text() && !msg.is_empty() {
+                        keepalive.on_request(Instant::now());
+                        state.session_state.touch_connection(&connection_id);
                         debug!("Received JsonRpc Request {}", msg);
                         let req_id = Uuid::new_v4().to_string();
                         let req_text = String::from(msg.to_text().unwrap());
@@ -439,6 +891,8 @@ impl FireboltWs {
                             context,
                         ) {
                             info!("Received Firebolt request {}", request.params_json);
+                            Inspector::mirror(&state, &session_id_c, &app_id_c, true, &req_text)
+                                .await;
                             let msg = FireboltGatewayCommand::HandleRpc { request };
                             if let Err(e) = client.clone().send_gateway_command(msg) {
                                 error!("failed to send request {:?}", e);
@@ -461,6 +915,11 @@ impl FireboltWs {
             }
         }
         debug!("SESSION DEBUG Unregistering {}", connection_id);
+        state.admin_state.remove_connection(&connection_id);
+        state.inspector_state.remove_session(&identity.session_id);
+        state
+            .pending_request_state
+            .remove_session(&identity.session_id);
         let msg = FireboltGatewayCommand::UnregisterSession {
             session_id: identity.session_id.clone(),
             cid: connection_id,
@@ -472,12 +931,19 @@ impl FireboltWs {
 
     async fn handle_connection(
         _client_addr: SocketAddr,
-        ws_stream: WebSocketStream<TcpStream>,
+        mut ws_stream: WebSocketStream<ServerStream>,
         connect_rx: oneshot::Receiver<ClientIdentity>,
         state: PlatformState,
         gateway_secure: bool,
+        keepalive: WsKeepaliveConfiguration,
     ) {
-        let identity = connect_rx.await.unwrap();
+        let mut identity = connect_rx.await.unwrap();
+        if let Some(rejection) = identity.rejection.take() {
+            if let Err(e) = ws_stream.close(Some(rejection.get_close_frame())).await {
+                error!("Error closing rejected connection {:?}", e);
+            }
+            return;
+        }
 
         // Generate a unique connection ID
         let connection_id = Uuid::new_v4().to_string();
@@ -494,14 +960,49 @@ impl FireboltWs {
             )
             .await;
         } else {
+            let app_id = identity.app_id.clone();
+            let limits = state.get_device_manifest().get_connection_limits();
+            let priority = state
+                .app_library_state
+                .read()
+                .unwrap()
+                .get_session_priority(&app_id);
+            let (evict_tx, evict_rx) = oneshot::channel::<ConnectionEviction>();
+            // The admission slot is released when the guard drops, even if the task unwinds
+            let _admission = match state.session_state.admit_connection(
+                &connection_id,
+                &app_id,
+                priority,
+                &limits,
+                evict_tx,
+            ) {
+                Ok(admission) => admission,
+                Err(limit) => {
+                    let close_frame = WsRejection::ConnectionLimit(limit).get_close_frame();
+                    error!("Rejecting app_id={} reason={}", app_id, close_frame.reason);
+                    TelemetryBuilder::send_connection_rejected(
+                        &state,
+                        &app_id,
+                        close_frame.reason.to_string(),
+                    );
+                    if let Err(e) = ws_stream.close(Some(close_frame)).await {
+                        error!("Error closing rejected connection {:?}", e);
+                    }
+                    return;
+                }
+            };
             // Handle app connection
             Self::handle_app_connection(
                 _client_addr,
                 ws_stream,
-                state,
+                state.clone(),
                 identity,
                 connection_id,
                 gateway_secure,
+                AppConnectionControl {
+                    keepalive,
+                    evict_rx,
+                },
             )
             .await;
         }
@@ -554,3 +1055,73 @@ async fn return_invalid_format_error_message(
         let _ = session.send_json_rpc(api_msg).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{api::manifest::device_manifest::WsKeepaliveAppClass, tokio};
+
+    #[test]
+    fn test_admin_token() {
+        let request = tungstenite::handshake::server::Request::builder()
+            .uri("ws://127.0.0.1:3473/?appId=refui&session=s1")
+            .header("Authorization", "Bearer token1")
+            .body(())
+            .unwrap();
+        assert_eq!(get_admin_token(&request), Some("token1".to_owned()));
+
+        let request = tungstenite::handshake::server::Request::builder()
+            .uri("ws://127.0.0.1:3473/?appId=refui&session=s1&adminToken=token1")
+            .body(())
+            .unwrap();
+        assert_eq!(get_admin_token(&request), None);
+    }
+
+    #[tokio::test]
+    async fn test_bind_failure() {
+        let listener = FireboltWs::bind("127.0.0.1:0").await.unwrap();
+        let bound = listener.local_addr().unwrap().to_string();
+        assert_eq!(
+            FireboltWs::bind(&bound).await.unwrap_err(),
+            RippleError::BootstrapError
+        );
+        assert!(FireboltWs::bind("not an address").await.is_err());
+    }
+
+    #[test]
+    fn test_app_keepalive() {
+        let config = WsKeepaliveConfiguration {
+            enabled: true,
+            max_missed_pongs: 2,
+            idle_timeout_ms: Some(1000),
+            app_classes: vec![WsKeepaliveAppClass {
+                name: "player".into(),
+                app_ids: vec!["player1".into()],
+                idle_timeout_ms: None,
+            }],
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let mut keepalive = AppKeepalive::new(&config, "player1", start);
+        assert_eq!(keepalive.on_tick(start), KeepaliveAction::Ping);
+        assert_eq!(keepalive.on_tick(start), KeepaliveAction::Ping);
+        keepalive.on_frame();
+        assert_eq!(keepalive.on_tick(start), KeepaliveAction::Ping);
+        assert_eq!(keepalive.on_tick(start), KeepaliveAction::Ping);
+        assert_eq!(
+            keepalive.on_tick(start),
+            KeepaliveAction::Close("Keepalive timeout")
+        );
+
+        let mut keepalive = AppKeepalive::new(&config, "app1", start);
+        keepalive.on_request(start + Duration::from_millis(500));
+        assert_eq!(
+            keepalive.on_tick(start + Duration::from_millis(1000)),
+            KeepaliveAction::Ping
+        );
+        assert_eq!(
+            keepalive.on_tick(start + Duration::from_millis(1500)),
+            KeepaliveAction::Close("Idle timeout")
+        );
+    }
+}
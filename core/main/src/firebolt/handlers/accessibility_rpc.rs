@@ -0,0 +1,257 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::{
+        device::{
+            device_accessibility_data::{
+                HighContrastSettings, MagnificationSettings, MagnificationSettingsSet,
+                MAGNIFICATION_SCALE_DEFAULT,
+            },
+            device_peristence::SetBoolProperty,
+        },
+        firebolt::fb_general::{ListenRequest, ListenerResponse},
+        gateway::rpc_gateway_api::CallContext,
+        storage_property::{
+            StorageProperty, EVENT_HIGH_CONTRAST_SETTINGS_CHANGED,
+            EVENT_MAGNIFICATION_SETTINGS_CHANGED,
+        },
+    },
+    log::error,
+};
+use serde_json::Value;
+
+use crate::{
+    broker::broker_utils::BrokerUtils,
+    firebolt::rpc::RippleRPCProvider,
+    processor::storage::storage_manager::StorageManager,
+    service::apps::app_events::{AppEventDecorationError, AppEventDecorator},
+    state::platform_state::PlatformState,
+    utils::rpc_utils::rpc_add_event_listener_with_decorator,
+};
+
+/// Broker rules which apply the settings on the device, they are optional and only invoked
+/// when the rule is configured.
+const DEVICE_SET_HIGH_CONTRAST: &str = "sts.accessibility.setHighContrast";
+const DEVICE_SET_MAGNIFICATION: &str = "sts.accessibility.setMagnification";
+
+#[derive(Clone)]
+struct AccessibilityEventDecorator {}
+
+#[async_trait]
+impl AppEventDecorator for AccessibilityEventDecorator {
+    async fn decorate(
+        &self,
+        ps: &PlatformState,
+        _ctx: &CallContext,
+        event_name: &str,
+        _val_in: &Value,
+    ) -> Result<Value, AppEventDecorationError> {
+        let settings = if event_name.eq(EVENT_MAGNIFICATION_SETTINGS_CHANGED) {
+            serde_json::to_value(AccessibilityImpl::get_magnification_settings(ps).await)
+        } else {
+            serde_json::to_value(AccessibilityImpl::get_high_contrast_settings(ps).await)
+        };
+        Ok(settings.unwrap_or_default())
+    }
+
+    fn dec_clone(&self) -> Box<dyn AppEventDecorator + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+#[rpc(server)]
+pub trait Accessibility {
+    #[method(name = "accessibility.highContrastSettings")]
+    async fn high_contrast_settings(&self, ctx: CallContext) -> RpcResult<HighContrastSettings>;
+    #[method(name = "accessibility.setHighContrast")]
+    async fn high_contrast_set(&self, ctx: CallContext, request: SetBoolProperty) -> RpcResult<()>;
+    #[method(name = "accessibility.onHighContrastSettingsChanged")]
+    async fn on_high_contrast_settings_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+    #[method(name = "accessibility.magnificationSettings")]
+    async fn magnification_settings(&self, ctx: CallContext) -> RpcResult<MagnificationSettings>;
+    #[method(name = "accessibility.setMagnification")]
+    async fn magnification_set(
+        &self,
+        ctx: CallContext,
+        request: MagnificationSettingsSet,
+    ) -> RpcResult<()>;
+    #[method(name = "accessibility.onMagnificationSettingsChanged")]
+    async fn on_magnification_settings_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+}
+
+#[derive(Debug)]
+pub struct AccessibilityImpl {
+    pub state: PlatformState,
+}
+
+impl AccessibilityImpl {
+    pub async fn get_high_contrast_settings(state: &PlatformState) -> HighContrastSettings {
+        HighContrastSettings {
+            enabled: StorageManager::get_bool(state, StorageProperty::HighContrastEnabled)
+                .await
+                .unwrap_or(false),
+        }
+    }
+
+    pub async fn get_magnification_settings(state: &PlatformState) -> MagnificationSettings {
+        MagnificationSettings {
+            enabled: StorageManager::get_bool(state, StorageProperty::MagnificationEnabled)
+                .await
+                .unwrap_or(false),
+            scale: StorageManager::get_number_as_f32(state, StorageProperty::MagnificationScale)
+                .await
+                .unwrap_or(MAGNIFICATION_SCALE_DEFAULT),
+        }
+    }
+
+    /// Passes the updated settings to the device, failures are logged as the settings are
+    /// already persisted and will be applied by the device on the next read.
+    async fn apply_on_device(state: &PlatformState, method: &str, settings: Value) {
+        if !state.endpoint_state.has_rule(method) {
+            return;
+        }
+        if let Err(e) =
+            BrokerUtils::process_internal_main_request(state, method, Some(settings)).await
+        {
+            error!("Failed to apply {} on device: {:?}", method, e);
+        }
+    }
+}
+
+#[async_trait]
+impl AccessibilityServer for AccessibilityImpl {
+    async fn high_contrast_settings(&self, _ctx: CallContext) -> RpcResult<HighContrastSettings> {
+        Ok(Self::get_high_contrast_settings(&self.state).await)
+    }
+
+    async fn high_contrast_set(
+        &self,
+        _ctx: CallContext,
+        request: SetBoolProperty,
+    ) -> RpcResult<()> {
+        StorageManager::set_bool(
+            &self.state,
+            StorageProperty::HighContrastEnabled,
+            request.value,
+            None,
+        )
+        .await?;
+        let settings = HighContrastSettings {
+            enabled: request.value,
+        };
+        Self::apply_on_device(
+            &self.state,
+            DEVICE_SET_HIGH_CONTRAST,
+            serde_json::to_value(settings).unwrap_or_default(),
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn on_high_contrast_settings_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        rpc_add_event_listener_with_decorator(
+            &self.state,
+            ctx,
+            request,
+            EVENT_HIGH_CONTRAST_SETTINGS_CHANGED,
+            Some(Box::new(AccessibilityEventDecorator {})),
+        )
+        .await
+    }
+
+    async fn magnification_settings(&self, _ctx: CallContext) -> RpcResult<MagnificationSettings> {
+        Ok(Self::get_magnification_settings(&self.state).await)
+    }
+
+    async fn magnification_set(
+        &self,
+        _ctx: CallContext,
+        request: MagnificationSettingsSet,
+    ) -> RpcResult<()> {
+        if !request.is_valid() {
+            return Err(jsonrpsee::core::error::Error::Custom(
+                "Invalid value for magnification scale".to_owned(),
+            ));
+        }
+        if let Some(enabled) = request.enabled {
+            StorageManager::set_bool(
+                &self.state,
+                StorageProperty::MagnificationEnabled,
+                enabled,
+                None,
+            )
+            .await?;
+        }
+        if let Some(scale) = request.scale {
+            StorageManager::set_number_as_f32(
+                &self.state,
+                StorageProperty::MagnificationScale,
+                scale,
+                None,
+            )
+            .await?;
+        }
+        let settings = Self::get_magnification_settings(&self.state).await;
+        Self::apply_on_device(
+            &self.state,
+            DEVICE_SET_MAGNIFICATION,
+            serde_json::to_value(settings).unwrap_or_default(),
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn on_magnification_settings_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        rpc_add_event_listener_with_decorator(
+            &self.state,
+            ctx,
+            request,
+            EVENT_MAGNIFICATION_SETTINGS_CHANGED,
+            Some(Box::new(AccessibilityEventDecorator {})),
+        )
+        .await
+    }
+}
+
+pub struct AccessibilityRPCProvider;
+impl RippleRPCProvider<AccessibilityImpl> for AccessibilityRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<AccessibilityImpl> {
+        (AccessibilityImpl { state }).into_rpc()
+    }
+}
@@ -0,0 +1,419 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::HashMap;
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, RpcModule};
+use ripple_sdk::{
+    api::{
+        firebolt::{
+            fb_capabilities::{CapabilityUsage, CapabilityUsageRequest},
+            fb_localization::{
+                ClearContextOverrideParams, RegionInfo, SetContextOverrideParams,
+                SetRegionSourcesParams,
+            },
+            fb_telemetry::AppErrorStats,
+        },
+        gateway::rpc_gateway_api::CallContext,
+        manifest::{compliance::ComplianceReport, device_manifest::AdminRole},
+    },
+    async_trait::async_trait,
+    extn::client::extn_usage::ExtnUsage,
+    log::info,
+};
+
+use crate::{
+    broker::{
+        rule_explain::SetRuleExplainParams,
+        rules::{
+            jq_sandbox::{JqSandbox, JqSandboxStatus, ReleaseJqRuleParams, SetJqSandboxParams},
+            rules_engine::RuleProvenance,
+        },
+    },
+    firebolt::{
+        rpc::RippleRPCProvider,
+        rpc_router::{RouteEntry, RoutingTable, RoutingTableParams},
+    },
+    service::{
+        activation_orchestrator::ActivationOrchestrator,
+        apps::{
+            app_events::{AppEvents, InjectEventParams},
+            provider_broker::{ProviderBroker, SetSyntheticProviderParams},
+        },
+        graceful_shutdown::{GracefulShutdown, ShutdownParams},
+        ripple_info::RippleInfo,
+        soft_restart::{SoftRestart, SoftRestartParams, SoftRestartResult},
+        state_snapshot::{StateSnapshot, StateSnapshotParams},
+    },
+    state::{
+        admin_state::AdminSchema, platform_state::PlatformState, region_state::RegionState,
+        tenant_state::TenantStats,
+    },
+    utils::rpc_utils::rpc_err,
+};
+
+/// Admin API methods. Every method here has to be listed in
+/// [crate::state::admin_state::ADMIN_METHOD_ROLES] with the role it requires.
+#[rpc(server)]
+pub trait Admin {
+    #[method(name = "ripple.getAdminRole")]
+    async fn get_admin_role(&self, ctx: CallContext) -> RpcResult<AdminRole>;
+    #[method(name = "ripple.setContextOverride")]
+    async fn set_context_override(
+        &self,
+        ctx: CallContext,
+        request: SetContextOverrideParams,
+    ) -> RpcResult<()>;
+    #[method(name = "ripple.clearContextOverride")]
+    async fn clear_context_override(
+        &self,
+        ctx: CallContext,
+        request: ClearContextOverrideParams,
+    ) -> RpcResult<()>;
+    #[method(name = "ripple.getCapabilityUsage")]
+    async fn get_capability_usage(
+        &self,
+        ctx: CallContext,
+        request: CapabilityUsageRequest,
+    ) -> RpcResult<HashMap<String, Vec<CapabilityUsage>>>;
+    #[method(name = "ripple.getExtnUsage")]
+    async fn get_extn_usage(&self, ctx: CallContext) -> RpcResult<Vec<ExtnUsage>>;
+    #[method(name = "ripple.getComplianceReport")]
+    async fn get_compliance_report(&self, ctx: CallContext) -> RpcResult<ComplianceReport>;
+    #[method(name = "ripple.setRuleExplain")]
+    async fn set_rule_explain(
+        &self,
+        ctx: CallContext,
+        request: SetRuleExplainParams,
+    ) -> RpcResult<()>;
+    #[method(name = "ripple.shutdown")]
+    async fn shutdown(&self, ctx: CallContext, request: ShutdownParams) -> RpcResult<()>;
+    #[method(name = "ripple.getRuleProvenance")]
+    async fn get_rule_provenance(
+        &self,
+        ctx: CallContext,
+    ) -> RpcResult<HashMap<String, RuleProvenance>>;
+    #[method(name = "ripple.getJqSandbox")]
+    async fn get_jq_sandbox(&self, ctx: CallContext) -> RpcResult<JqSandboxStatus>;
+    #[method(name = "ripple.setJqSandbox")]
+    async fn set_jq_sandbox(&self, ctx: CallContext, request: SetJqSandboxParams) -> RpcResult<()>;
+    #[method(name = "ripple.injectEvent")]
+    async fn inject_event(&self, ctx: CallContext, request: InjectEventParams) -> RpcResult<u32>;
+    #[method(name = "ripple.setSyntheticProvider")]
+    async fn set_synthetic_provider(
+        &self,
+        ctx: CallContext,
+        request: SetSyntheticProviderParams,
+    ) -> RpcResult<()>;
+    #[method(name = "ripple.dumpState")]
+    async fn dump_state(&self, ctx: CallContext, request: StateSnapshotParams)
+        -> RpcResult<String>;
+    #[method(name = "ripple.loadState")]
+    async fn load_state(&self, ctx: CallContext, request: StateSnapshotParams) -> RpcResult<()>;
+    #[method(name = "ripple.releaseJqRule")]
+    async fn release_jq_rule(
+        &self,
+        ctx: CallContext,
+        request: ReleaseJqRuleParams,
+    ) -> RpcResult<bool>;
+    #[method(name = "ripple.getErrorBudgets")]
+    async fn get_error_budgets(&self, ctx: CallContext) -> RpcResult<Vec<AppErrorStats>>;
+    #[method(name = "ripple.getTenantStats")]
+    async fn get_tenant_stats(&self, ctx: CallContext) -> RpcResult<Vec<TenantStats>>;
+    #[method(name = "ripple.getRegion")]
+    async fn get_region(&self, ctx: CallContext) -> RpcResult<RegionInfo>;
+    #[method(name = "ripple.setRegionSources")]
+    async fn set_region_sources(
+        &self,
+        ctx: CallContext,
+        request: SetRegionSourcesParams,
+    ) -> RpcResult<RegionInfo>;
+    #[method(name = "ripple.getAdminSchema")]
+    async fn get_admin_schema(&self, ctx: CallContext) -> RpcResult<AdminSchema>;
+    #[method(name = "ripple.info")]
+    async fn info(&self, ctx: CallContext) -> RpcResult<RippleInfo>;
+    #[method(name = "ripple.softRestart")]
+    async fn soft_restart(
+        &self,
+        ctx: CallContext,
+        request: SoftRestartParams,
+    ) -> RpcResult<SoftRestartResult>;
+    #[method(name = "ripple.getRoutingTable")]
+    async fn get_routing_table(
+        &self,
+        ctx: CallContext,
+        request: RoutingTableParams,
+    ) -> RpcResult<Vec<RouteEntry>>;
+    #[method(name = "ripple.resetActivation")]
+    async fn reset_activation(&self, ctx: CallContext) -> RpcResult<()>;
+}
+
+#[derive(Debug)]
+pub struct AdminImpl {
+    pub state: PlatformState,
+}
+
+#[async_trait]
+impl AdminServer for AdminImpl {
+    async fn get_admin_role(&self, ctx: CallContext) -> RpcResult<AdminRole> {
+        self.state
+            .admin_state
+            .get_role(&ctx)
+            .ok_or_else(|| rpc_err("No admin role for the connection"))
+    }
+
+    async fn set_context_override(
+        &self,
+        _ctx: CallContext,
+        request: SetContextOverrideParams,
+    ) -> RpcResult<()> {
+        let updated = self
+            .state
+            .session_state
+            .set_context_override(&request.app_id, request.context);
+        if updated == 0 {
+            return Err(rpc_err(format!(
+                "No active session for app {}",
+                request.app_id
+            )));
+        }
+        info!(
+            "Context override set for app {} sessions={}",
+            request.app_id, updated
+        );
+        Ok(())
+    }
+
+    async fn clear_context_override(
+        &self,
+        _ctx: CallContext,
+        request: ClearContextOverrideParams,
+    ) -> RpcResult<()> {
+        self.state
+            .session_state
+            .clear_context_override(&request.app_id);
+        Ok(())
+    }
+
+    async fn get_capability_usage(
+        &self,
+        _ctx: CallContext,
+        request: CapabilityUsageRequest,
+    ) -> RpcResult<HashMap<String, Vec<CapabilityUsage>>> {
+        Ok(self
+            .state
+            .cap_state
+            .get_usage_report(request.app_id.as_deref()))
+    }
+
+    async fn get_extn_usage(&self, _ctx: CallContext) -> RpcResult<Vec<ExtnUsage>> {
+        Ok(self.state.get_client().get_extn_client().get_extn_usage())
+    }
+
+    async fn get_compliance_report(&self, _ctx: CallContext) -> RpcResult<ComplianceReport> {
+        Ok(self
+            .state
+            .get_device_manifest()
+            .configuration
+            .get_compliance_report())
+    }
+
+    async fn set_rule_explain(
+        &self,
+        _ctx: CallContext,
+        request: SetRuleExplainParams,
+    ) -> RpcResult<()> {
+        self.state
+            .endpoint_state
+            .get_explain_state()
+            .set_enabled(&request.app_id, request.enabled);
+        Ok(())
+    }
+
+    async fn shutdown(&self, ctx: CallContext, request: ShutdownParams) -> RpcResult<()> {
+        let reason = request.reason.unwrap_or_else(|| "ripple.shutdown".into());
+        info!("Shutdown requested by {} reason={}", ctx.app_id, reason);
+        if !GracefulShutdown::start(self.state.clone(), &reason) {
+            return Err(rpc_err("Shutdown already in progress"));
+        }
+        Ok(())
+    }
+
+    async fn get_rule_provenance(
+        &self,
+        _ctx: CallContext,
+    ) -> RpcResult<HashMap<String, RuleProvenance>> {
+        Ok(self.state.endpoint_state.get_rule_provenance())
+    }
+
+    async fn get_jq_sandbox(&self, _ctx: CallContext) -> RpcResult<JqSandboxStatus> {
+        Ok(JqSandbox::get().get_status())
+    }
+
+    async fn set_jq_sandbox(&self, ctx: CallContext, request: SetJqSandboxParams) -> RpcResult<()> {
+        info!("jq sandbox updated by {} {:?}", ctx.app_id, request);
+        JqSandbox::get().update(request);
+        Ok(())
+    }
+
+    async fn inject_event(&self, ctx: CallContext, request: InjectEventParams) -> RpcResult<u32> {
+        info!(
+            "Injecting event {} requested by {} apps={:?} sessions={:?}",
+            request.event, ctx.app_id, request.app_ids, request.session_ids
+        );
+        AppEvents::inject(&self.state, &request)
+            .await
+            .map(|notified| notified as u32)
+            .map_err(rpc_err)
+    }
+
+    async fn set_synthetic_provider(
+        &self,
+        _ctx: CallContext,
+        request: SetSyntheticProviderParams,
+    ) -> RpcResult<()> {
+        // canned provider answers bypass the user, which is only meant for dev instances
+        if !self.state.developer_mode.is_enabled() {
+            return Err(rpc_err("Synthetic providers need developer mode"));
+        }
+        ProviderBroker::set_synthetic_response(&self.state, request);
+        Ok(())
+    }
+
+    async fn dump_state(
+        &self,
+        _ctx: CallContext,
+        request: StateSnapshotParams,
+    ) -> RpcResult<String> {
+        let path = StateSnapshot::get_path(&self.state, request.path)
+            .map_err(|_| rpc_err("The state snapshot must be in the saved dir"))?;
+        StateSnapshot::dump(&self.state, &path)
+            .await
+            .map_err(|e| rpc_err(format!("Unable to write the state snapshot {}", e)))?;
+        Ok(path)
+    }
+
+    async fn load_state(&self, ctx: CallContext, request: StateSnapshotParams) -> RpcResult<()> {
+        // loading overwrites caps and rules, which is only meant for dev instances
+        if !self.state.developer_mode.is_enabled() {
+            return Err(rpc_err("Loading a state snapshot needs developer mode"));
+        }
+        let path = StateSnapshot::get_path(&self.state, request.path)
+            .map_err(|_| rpc_err("The state snapshot must be in the saved dir"))?;
+        info!(
+            "Loading state snapshot {} requested by {}",
+            path, ctx.app_id
+        );
+        StateSnapshot::load(&self.state, &path)
+            .await
+            .map_err(|e| rpc_err(format!("Unable to load the state snapshot {}", e)))
+    }
+
+    async fn release_jq_rule(
+        &self,
+        _ctx: CallContext,
+        request: ReleaseJqRuleParams,
+    ) -> RpcResult<bool> {
+        Ok(JqSandbox::get().release(&request.rule))
+    }
+
+    async fn get_error_budgets(&self, _ctx: CallContext) -> RpcResult<Vec<AppErrorStats>> {
+        Ok(self.state.error_budget_state.get_stats())
+    }
+
+    async fn get_tenant_stats(&self, _ctx: CallContext) -> RpcResult<Vec<TenantStats>> {
+        Ok(self.state.tenant_state.get_stats())
+    }
+
+    async fn get_region(&self, _ctx: CallContext) -> RpcResult<RegionInfo> {
+        Ok(self.state.region_state.get_info())
+    }
+
+    async fn set_region_sources(
+        &self,
+        _ctx: CallContext,
+        request: SetRegionSourcesParams,
+    ) -> RpcResult<RegionInfo> {
+        if request.sources.is_empty() {
+            return Err(rpc_err("At least one region source is needed"));
+        }
+        self.state.region_state.set_sources(request.sources);
+        RegionState::reload(&self.state).await;
+        RegionState::start(self.state.clone());
+        Ok(self.state.region_state.get_info())
+    }
+
+    async fn get_admin_schema(&self, ctx: CallContext) -> RpcResult<AdminSchema> {
+        let version = self
+            .state
+            .version
+            .clone()
+            .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
+        Ok(self.state.admin_state.get_schema(
+            &ctx,
+            version,
+            self.state.router_state.get_method_names(),
+        ))
+    }
+
+    async fn info(&self, _ctx: CallContext) -> RpcResult<RippleInfo> {
+        Ok(RippleInfo::collect(&self.state))
+    }
+
+    async fn soft_restart(
+        &self,
+        ctx: CallContext,
+        request: SoftRestartParams,
+    ) -> RpcResult<SoftRestartResult> {
+        let reason = request
+            .reason
+            .unwrap_or_else(|| "ripple.softRestart".into());
+        info!("Soft restart requested by {} reason={}", ctx.app_id, reason);
+        SoftRestart::run(&self.state, &reason, request.close_app_connections)
+            .await
+            .map_err(|_| rpc_err("Shutdown in progress"))
+    }
+
+    async fn get_routing_table(
+        &self,
+        _ctx: CallContext,
+        request: RoutingTableParams,
+    ) -> RpcResult<Vec<RouteEntry>> {
+        match request.method {
+            Some(method) => Ok(RoutingTable::resolve(&self.state, &method)
+                .into_iter()
+                .collect()),
+            None => Ok(RoutingTable::dump(&self.state)),
+        }
+    }
+
+    async fn reset_activation(&self, ctx: CallContext) -> RpcResult<()> {
+        if !self.state.get_device_configuration().activation.enabled {
+            return Err(rpc_err("Activation is not enabled"));
+        }
+        info!("Activation reset requested by {}", ctx.app_id);
+        ActivationOrchestrator::reset(self.state.clone());
+        Ok(())
+    }
+}
+
+pub struct AdminRPCProvider;
+impl RippleRPCProvider<AdminImpl> for AdminRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<AdminImpl> {
+        (AdminImpl { state }).into_rpc()
+    }
+}
@@ -14,19 +14,31 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 //
-use super::privacy_rpc::{self};
+use std::collections::HashMap;
+
+use super::privacy_rpc::{self, LMT_KEY};
 use crate::{
     firebolt::rpc::RippleRPCProvider,
     processor::storage::storage_manager::StorageManager,
     service::apps::app_events::{AppEventDecorationError, AppEventDecorator},
-    state::platform_state::PlatformState,
+    state::{ad_config_state::AdConfigState, platform_state::PlatformState},
+    utils::rpc_utils::rpc_err,
 };
 use jsonrpsee::{
     core::{async_trait, RpcResult},
     proc_macros::rpc,
     RpcModule,
 };
-use ripple_sdk::api::{gateway::rpc_gateway_api::CallContext, storage_property::StorageProperty};
+use ripple_sdk::{
+    api::{
+        firebolt::fb_advertising::{
+            AdConfigRequestParams, AdvertisingFrameworkConfig, GetAdConfig,
+        },
+        gateway::rpc_gateway_api::CallContext,
+        storage_property::StorageProperty,
+    },
+    log::error,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -74,6 +86,12 @@ impl ScopeType {
 pub trait Advertising {
     #[method(name = "advertising.policy")]
     async fn policy(&self, ctx: CallContext) -> RpcResult<AdvertisingPolicy>;
+    #[method(name = "advertising.config")]
+    async fn config(
+        &self,
+        ctx: CallContext,
+        config: GetAdConfig,
+    ) -> RpcResult<AdvertisingFrameworkConfig>;
 }
 const NONE: &str = "none";
 async fn get_advertisting_policy(platform_state: &PlatformState) -> AdvertisingPolicy {
@@ -121,6 +139,61 @@ impl AdvertisingServer for AdvertisingImpl {
     async fn policy(&self, _ctx: CallContext) -> RpcResult<AdvertisingPolicy> {
         Ok(get_advertisting_policy(&self.state).await)
     }
+
+    /// The ad server details come from the distributor through the advertising contract,
+    /// the privacy fields are always taken from the current privacy settings.
+    async fn config(
+        &self,
+        ctx: CallContext,
+        config: GetAdConfig,
+    ) -> RpcResult<AdvertisingFrameworkConfig> {
+        let mut platform_state = self.state.clone();
+        let dist_session = platform_state
+            .session_state
+            .get_account_session()
+            .ok_or_else(|| rpc_err("Account session is not available"))?;
+        let privacy_data = privacy_rpc::get_allow_app_content_ad_targeting_settings(
+            &mut platform_state,
+            None,
+            &ctx.app_id,
+            &ctx,
+        )
+        .await;
+        let params = AdConfigRequestParams {
+            privacy_data: privacy_data.clone(),
+            durable_app_id: ctx.app_id.clone(),
+            dist_session,
+            environment: config.options.environment.to_string(),
+            scope: HashMap::new(),
+        };
+        let ad_config = AdConfigState::get_ad_config(&self.state, params)
+            .await
+            .map_err(|e| {
+                error!("Ad config request failed: {:?}", e);
+                rpc_err("Could not get the ad config from the distributor")
+            })?;
+
+        Ok(AdvertisingFrameworkConfig {
+            ad_server_url: ad_config.ad_server_url,
+            ad_server_url_template: ad_config.ad_server_url_template,
+            ad_network_id: ad_config.ad_network_id,
+            ad_profile_id: ad_config.ad_profile_id,
+            ad_site_section_id: ad_config.ad_site_section_id,
+            ad_opt_out: privacy_data
+                .get(LMT_KEY)
+                .map(|lmt| lmt == "1")
+                .unwrap_or(true),
+            privacy_data: serde_json::to_string(&privacy_data).unwrap_or_default(),
+            ifa_value: ad_config.ifa_value,
+            ifa: ad_config.ifa,
+            app_name: ctx.app_id.clone(),
+            app_bundle_id: ad_config.app_bundle_id,
+            distributor_app_id: ctx.app_id,
+            device_ad_attributes: String::default(),
+            coppa: config.options.coppa.unwrap_or(false) as u32,
+            authentication_entity: config.options.authentication_entity.unwrap_or_default(),
+        })
+    }
 }
 
 pub struct AdvertisingRPCProvider;
@@ -0,0 +1,147 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::{
+        firebolt::{
+            fb_audio_focus::{
+                AudioFocusRequest, AudioFocusResponse, AUDIO_FOCUS_EVENT_ON_FOCUS_CHANGED,
+            },
+            fb_general::{ListenRequest, ListenerResponse},
+            fb_lifecycle::LifecycleState,
+        },
+        gateway::rpc_gateway_api::CallContext,
+    },
+    log::debug,
+};
+use serde_json::json;
+
+use crate::{
+    firebolt::rpc::RippleRPCProvider,
+    service::apps::app_events::AppEvents,
+    state::{audio_focus_state::FocusChanges, platform_state::PlatformState},
+    utils::rpc_utils::rpc_add_event_listener,
+};
+
+#[rpc(server)]
+pub trait AudioFocus {
+    #[method(name = "audiofocus.request")]
+    async fn request(
+        &self,
+        ctx: CallContext,
+        request: AudioFocusRequest,
+    ) -> RpcResult<AudioFocusResponse>;
+    #[method(name = "audiofocus.abandon")]
+    async fn abandon(&self, ctx: CallContext) -> RpcResult<()>;
+    #[method(name = "audiofocus.onFocusChanged")]
+    async fn on_focus_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+}
+
+pub struct AudioFocusImpl {
+    pub state: PlatformState,
+}
+
+impl AudioFocusImpl {
+    async fn notify(state: &PlatformState, changes: FocusChanges) {
+        for (app_id, change) in changes {
+            debug!("Audio focus of {} changed to {:?}", app_id, change.status);
+            AppEvents::emit_to_app(
+                state,
+                app_id,
+                AUDIO_FOCUS_EVENT_ON_FOCUS_CHANGED,
+                &json!(change),
+            )
+            .await;
+        }
+    }
+
+    /// Releases the focus of an app which is no longer running
+    pub async fn abandon_for_app(state: &PlatformState, app_id: &str) {
+        let changes = state.audio_focus_state.abandon(app_id);
+        Self::notify(state, changes).await;
+    }
+
+    fn is_foreground(&self, app_id: &str) -> bool {
+        self.state
+            .app_manager_state
+            .get(app_id)
+            .map(|app| app.state == LifecycleState::Foreground)
+            .unwrap_or(false)
+    }
+}
+
+#[async_trait]
+impl AudioFocusServer for AudioFocusImpl {
+    async fn request(
+        &self,
+        ctx: CallContext,
+        request: AudioFocusRequest,
+    ) -> RpcResult<AudioFocusResponse> {
+        let config = self
+            .state
+            .get_device_manifest()
+            .get_audio_focus_configuration();
+        let priority = config.is_priority_app(&ctx.app_id);
+        if !priority && config.require_foreground && !self.is_foreground(&ctx.app_id) {
+            return Ok(AudioFocusResponse { granted: false });
+        }
+        let changes =
+            self.state
+                .audio_focus_state
+                .request(&ctx.app_id, request.focus_type, priority);
+        let granted = changes.is_some();
+        if let Some(changes) = changes {
+            Self::notify(&self.state, changes).await;
+        }
+        Ok(AudioFocusResponse { granted })
+    }
+
+    async fn abandon(&self, ctx: CallContext) -> RpcResult<()> {
+        Self::abandon_for_app(&self.state, &ctx.app_id).await;
+        Ok(())
+    }
+
+    async fn on_focus_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        rpc_add_event_listener(
+            &self.state,
+            ctx,
+            request,
+            AUDIO_FOCUS_EVENT_ON_FOCUS_CHANGED,
+        )
+        .await
+    }
+}
+
+pub struct AudioFocusRPCProvider;
+impl RippleRPCProvider<AudioFocusImpl> for AudioFocusRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<AudioFocusImpl> {
+        (AudioFocusImpl { state }).into_rpc()
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{collections::HashMap, sync::OnceLock};
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::{
+        firebolt::{fb_capabilities::FireboltPermission, fb_openrpc::FireboltInfo},
+        gateway::rpc_gateway_api::CallContext,
+    },
+    log::error,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{firebolt::rpc::RippleRPCProvider, state::platform_state::PlatformState};
+
+/// Ripple's own OpenRPC document bundled with this build. It is not the Firebolt
+/// specification, so only its own title and version are reported.
+const RIPPLE_OPENRPC: &str = include_str!("../../state/ripple-rpc.json");
+
+/// Version of the Ripple OpenRPC document bundled with this build
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RippleRpcVersion {
+    /// Version of OpenRPC the document is written in
+    pub openrpc: String,
+    pub title: String,
+    pub version: String,
+}
+
+#[derive(Deserialize)]
+struct OpenRpcDocument {
+    openrpc: String,
+    info: FireboltInfo,
+}
+
+impl RippleRpcVersion {
+    fn get() -> RippleRpcVersion {
+        static RPC_VERSION: OnceLock<RippleRpcVersion> = OnceLock::new();
+        RPC_VERSION
+            .get_or_init(
+                || match serde_json::from_str::<OpenRpcDocument>(RIPPLE_OPENRPC) {
+                    Ok(document) => RippleRpcVersion {
+                        openrpc: document.openrpc,
+                        title: document.info.title,
+                        version: document.info.version,
+                    },
+                    Err(e) => {
+                        error!("Unable to read the Ripple OpenRPC version {:?}", e);
+                        RippleRpcVersion::default()
+                    }
+                },
+            )
+            .clone()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionCompatibility {
+    pub id: String,
+    /// Negotiated version of every contract used or fulfilled by the extension
+    pub contracts: HashMap<String, Option<u32>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceCompatibility {
+    pub id: String,
+    pub connected: bool,
+    pub methods: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatibilityReport {
+    pub ripple: String,
+    pub ripple_rpc: RippleRpcVersion,
+    pub firebolt_methods: Vec<String>,
+    pub supported_capabilities: Vec<FireboltPermission>,
+    pub unavailable_capabilities: Vec<String>,
+    pub extensions: Vec<ExtensionCompatibility>,
+    pub services: Vec<ServiceCompatibility>,
+}
+
+#[rpc(server)]
+pub trait Compatibility {
+    #[method(name = "ripple.compatibility")]
+    async fn compatibility(&self, ctx: CallContext) -> RpcResult<CompatibilityReport>;
+}
+
+/// Reports what this build supports, so app developers and certification tooling can check
+/// the Ripple RPC document version and the exact method, capability, extension and
+/// service set of a device.
+pub struct CompatibilityImpl {
+    pub state: PlatformState,
+}
+
+impl CompatibilityImpl {
+    fn get_extensions(&self) -> Vec<ExtensionCompatibility> {
+        let contract_matrix = self.state.extn_status_state.get_contract_matrix();
+        let mut extensions: Vec<ExtensionCompatibility> = self
+            .state
+            .get_client()
+            .get_extn_client()
+            .get_connected_extn_ids()
+            .into_iter()
+            .map(|id| ExtensionCompatibility {
+                contracts: contract_matrix
+                    .iter()
+                    .filter(|c| c.extn_id.eq(&id))
+                    .map(|c| (c.contract.clone(), c.negotiated_version))
+                    .collect(),
+                id,
+            })
+            .collect();
+        extensions.sort_by(|a, b| a.id.cmp(&b.id));
+        extensions
+    }
+
+    async fn get_services(&self) -> Vec<ServiceCompatibility> {
+        let connected = self
+            .state
+            .service_controller_state
+            .get_connected_service_ids()
+            .await;
+        let mut service_methods = self.state.endpoint_state.get_service_methods();
+        // connected services without any rule are reported too
+        for id in connected.iter() {
+            service_methods.entry(id.clone()).or_default();
+        }
+        let mut services: Vec<ServiceCompatibility> = service_methods
+            .into_iter()
+            .map(|(id, methods)| ServiceCompatibility {
+                connected: connected.contains(&id),
+                id,
+                methods,
+            })
+            .collect();
+        services.sort_by(|a, b| a.id.cmp(&b.id));
+        services
+    }
+}
+
+#[async_trait]
+impl CompatibilityServer for CompatibilityImpl {
+    async fn compatibility(&self, _ctx: CallContext) -> RpcResult<CompatibilityReport> {
+        let mut firebolt_methods = self.state.router_state.get_method_names();
+        firebolt_methods.sort();
+        let mut supported_capabilities = self.state.cap_state.generic.get_supported();
+        supported_capabilities.sort_by_key(|p| p.cap.as_str());
+        let mut unavailable_capabilities = self.state.cap_state.generic.get_not_available();
+        unavailable_capabilities.sort();
+        Ok(CompatibilityReport {
+            ripple: self
+                .state
+                .version
+                .clone()
+                .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string()),
+            ripple_rpc: RippleRpcVersion::get(),
+            firebolt_methods,
+            supported_capabilities,
+            unavailable_capabilities,
+            extensions: self.get_extensions(),
+            services: self.get_services().await,
+        })
+    }
+}
+
+pub struct CompatibilityRPCProvider;
+impl RippleRPCProvider<CompatibilityImpl> for CompatibilityRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<CompatibilityImpl> {
+        (CompatibilityImpl { state }).into_rpc()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ripple_rpc_version() {
+        let version = RippleRpcVersion::get();
+        assert_eq!(version.openrpc, "1.2.4");
+        assert_eq!(version.title, "Ripple");
+        assert!(!version.version.is_empty());
+    }
+}
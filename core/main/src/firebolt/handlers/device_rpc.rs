@@ -34,11 +34,16 @@ use ripple_sdk::{
         device::{
             device_events::{
                 DeviceEvent, DeviceEventCallback, DeviceEventRequest, AUDIO_CHANGED_EVENT,
-                HDCP_CHANGED_EVENT,
+                HDCP_CHANGED_EVENT, INACTIVITY_CHANGED_EVENT,
             },
             device_info_request::{DeviceInfoRequest, DeviceResponse, FirmwareInfo},
             device_request::{AudioProfile, DeviceVersionResponse, HdcpProfile},
+            device_update::{
+                SystemUpdateRequest, UpdateStatus, REBOOT_PENDING_EVENT,
+                UPDATE_STATUS_CHANGED_EVENT,
+            },
         },
+        distributor::distributor_activation::{ActivationProgress, ACTIVATION_EVENT_ON_PROGRESS},
         firebolt::fb_general::{ListenRequest, ListenerResponse},
         gateway::rpc_gateway_api::CallContext,
         storage_property::{EVENT_DEVICE_DEVICE_NAME_CHANGED, EVENT_DEVICE_NAME_CHANGED},
@@ -91,6 +96,34 @@ pub trait Device {
     async fn platform(&self, ctx: CallContext) -> RpcResult<String>;
     #[method(name = "device.version")]
     async fn version(&self, ctx: CallContext) -> RpcResult<DeviceVersionResponse>;
+    #[method(name = "device.activationProgress")]
+    async fn activation_progress(&self, ctx: CallContext) -> RpcResult<ActivationProgress>;
+    #[method(name = "device.onActivationProgress")]
+    async fn on_activation_progress(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+    #[method(name = "device.updateStatus")]
+    async fn update_status(&self, ctx: CallContext) -> RpcResult<UpdateStatus>;
+    #[method(name = "device.onUpdateStatusChanged")]
+    async fn on_update_status_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+    #[method(name = "device.onRebootPending")]
+    async fn on_reboot_pending(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+    #[method(name = "device.onInactivityChanged")]
+    async fn on_inactivity_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
 }
 
 #[derive(Debug)]
@@ -296,6 +329,64 @@ impl DeviceServer for DeviceImpl {
             event: AUDIO_CHANGED_EVENT.to_string(),
         })
     }
+
+    async fn activation_progress(&self, _ctx: CallContext) -> RpcResult<ActivationProgress> {
+        Ok(self.state.activation_state.get_progress())
+    }
+
+    async fn on_activation_progress(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        rpc_add_event_listener(&self.state, ctx, request, ACTIVATION_EVENT_ON_PROGRESS).await
+    }
+
+    async fn update_status(&self, _ctx: CallContext) -> RpcResult<UpdateStatus> {
+        if let Some(status) = self.state.update_status_state.get_status() {
+            return Ok(status);
+        }
+        // no status event received yet, ask the updater
+        match self
+            .state
+            .get_client()
+            .send_extn_request(SystemUpdateRequest::GetStatus)
+            .await
+        {
+            Ok(response) => match response.payload.extract::<UpdateStatus>() {
+                Some(status) => {
+                    self.state.update_status_state.update(status.clone());
+                    Ok(status)
+                }
+                None => Err(rpc_err("device.updateStatus error")),
+            },
+            Err(_) => Err(rpc_err("device.updateStatus error")),
+        }
+    }
+
+    async fn on_update_status_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        rpc_add_event_listener(&self.state, ctx, request, UPDATE_STATUS_CHANGED_EVENT).await
+    }
+
+    async fn on_reboot_pending(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        rpc_add_event_listener(&self.state, ctx, request, REBOOT_PENDING_EVENT).await
+    }
+
+    async fn on_inactivity_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        rpc_add_event_listener(&self.state, ctx, request, INACTIVITY_CHANGED_EVENT).await
+    }
 }
 
 pub struct DeviceRPCProvider;
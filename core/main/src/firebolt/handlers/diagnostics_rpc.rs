@@ -0,0 +1,227 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::{
+        distributor::distributor_privacy::DataEventType,
+        firebolt::{
+            fb_capabilities::{CapabilityRole, FireboltCap, RoleInfo, CAPABILITY_NOT_PERMITTED},
+            fb_diagnostics::{DiagnosticsLogParams, UploadDiagnosticsParams},
+        },
+        gateway::rpc_gateway_api::CallContext,
+        storage_property::StorageProperty,
+    },
+    log::{debug, error, info},
+    utils::rpc_utils::rpc_error_with_code_result,
+};
+
+use crate::{
+    firebolt::{handlers::capabilities_rpc::is_permitted, rpc::RippleRPCProvider},
+    processor::storage::storage_manager::StorageManager,
+    service::{
+        data_governance::{DataGovernance, DataGovernanceDecision},
+        diagnostics_bundle::DiagnosticsBundle,
+        telemetry_builder::TelemetryBuilder,
+    },
+    state::platform_state::PlatformState,
+    utils::{
+        http_utils::{get_http_client, HttpClient},
+        rpc_utils::rpc_err,
+    },
+};
+
+const DIAGNOSTICS_LOG_CAPABILITY: &str = "diagnostics:log";
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+#[rpc(server)]
+pub trait Diagnostics {
+    #[method(name = "diagnostics.log")]
+    async fn log(&self, ctx: CallContext, params: DiagnosticsLogParams) -> RpcResult<bool>;
+    /// Privileged method listed in [crate::state::admin_state::ADMIN_METHOD_ROLES], called
+    /// by the distributor backend to start a remote diagnostics session.
+    #[method(name = "ripple.uploadDiagnostics")]
+    async fn upload_diagnostics(
+        &self,
+        ctx: CallContext,
+        params: UploadDiagnosticsParams,
+    ) -> RpcResult<usize>;
+}
+
+/// Fixed window rate limiter for the diagnostic records of each app
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticsRateLimiter {
+    windows: Arc<RwLock<HashMap<String, (Instant, u32)>>>,
+}
+
+impl DiagnosticsRateLimiter {
+    pub fn try_acquire(&self, app_id: &str, max_records: u32, now: Instant) -> bool {
+        let mut windows = self.windows.write().unwrap();
+        let (window_start, count) = windows.entry(app_id.to_owned()).or_insert((now, 0));
+        if now.duration_since(*window_start) >= RATE_LIMIT_WINDOW {
+            *window_start = now;
+            *count = 0;
+        }
+        if *count >= max_records {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+pub struct DiagnosticsImpl {
+    pub state: PlatformState,
+    pub rate_limiter: DiagnosticsRateLimiter,
+    pub client: HttpClient,
+}
+
+impl DiagnosticsImpl {
+    fn truncate_message(message: &mut String, max_length: usize) {
+        if message.len() > max_length {
+            let mut end = max_length;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+        }
+    }
+}
+
+#[async_trait]
+impl DiagnosticsServer for DiagnosticsImpl {
+    async fn log(&self, ctx: CallContext, mut params: DiagnosticsLogParams) -> RpcResult<bool> {
+        let cap = RoleInfo {
+            capability: FireboltCap::short(DIAGNOSTICS_LOG_CAPABILITY),
+            role: Some(CapabilityRole::Use),
+        };
+        if !is_permitted(&self.state, &ctx, &cap).await? {
+            return rpc_error_with_code_result(
+                format!("{} is not permitted", cap.capability.as_str()),
+                CAPABILITY_NOT_PERMITTED,
+            );
+        }
+
+        let config = self
+            .state
+            .get_device_manifest()
+            .get_diagnostics_configuration();
+        if !self.rate_limiter.try_acquire(
+            &ctx.app_id,
+            config.max_records_per_minute,
+            Instant::now(),
+        ) {
+            debug!(
+                "Diagnostic record from {} dropped by rate limit",
+                ctx.app_id
+            );
+            return Ok(false);
+        }
+
+        Self::truncate_message(&mut params.message, config.max_message_length);
+        if let Err(e) = TelemetryBuilder::send_app_diagnostic(&self.state, &ctx, params) {
+            error!("send_app_diagnostic={:?}", e);
+            return Err(rpc_err("Unable to forward diagnostic record"));
+        }
+        Ok(true)
+    }
+
+    async fn upload_diagnostics(
+        &self,
+        ctx: CallContext,
+        params: UploadDiagnosticsParams,
+    ) -> RpcResult<usize> {
+        // The bundle is only uploaded when the user allowed remote diagnostics, a setting
+        // which can not be read is treated as not allowed
+        let allowed =
+            StorageManager::get_bool(&self.state, StorageProperty::AllowRemoteDiagnostics)
+                .await
+                .unwrap_or(false);
+        if !allowed
+            || DataGovernance::get_decision(
+                &self.state,
+                &ctx.app_id,
+                DataEventType::RemoteDiagnostics,
+            )
+            .await
+                == DataGovernanceDecision::Drop
+        {
+            return Err(rpc_err("Remote diagnostics is not consented"));
+        }
+        let bundle = DiagnosticsBundle::collect(&self.state);
+        match bundle.upload(&self.client, &params.upload_url).await {
+            Ok(size) => {
+                info!("Uploaded diagnostics bundle size={}", size);
+                Ok(size)
+            }
+            Err(e) => {
+                error!("Diagnostics bundle upload failed: {:?}", e);
+                Err(rpc_err("Unable to upload diagnostics bundle"))
+            }
+        }
+    }
+}
+
+pub struct DiagnosticsRPCProvider;
+impl RippleRPCProvider<DiagnosticsImpl> for DiagnosticsRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<DiagnosticsImpl> {
+        (DiagnosticsImpl {
+            state,
+            rate_limiter: DiagnosticsRateLimiter::default(),
+            client: get_http_client(),
+        })
+        .into_rpc()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let rate_limiter = DiagnosticsRateLimiter::default();
+        let now = Instant::now();
+        assert!(rate_limiter.try_acquire("app1", 2, now));
+        assert!(rate_limiter.try_acquire("app1", 2, now));
+        assert!(!rate_limiter.try_acquire("app1", 2, now));
+        // limits are tracked per app
+        assert!(rate_limiter.try_acquire("app2", 2, now));
+        // a new window resets the count
+        assert!(rate_limiter.try_acquire("app1", 2, now + RATE_LIMIT_WINDOW));
+    }
+
+    #[test]
+    fn test_truncate_message() {
+        let mut message = String::from("héllo");
+        DiagnosticsImpl::truncate_message(&mut message, 2);
+        assert_eq!(message, "h");
+        let mut message = String::from("hello");
+        DiagnosticsImpl::truncate_message(&mut message, 10);
+        assert_eq!(message, "hello");
+    }
+}
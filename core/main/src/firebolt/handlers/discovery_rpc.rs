@@ -24,6 +24,10 @@ use crate::{
         app_events::{AppEventDecorationError, AppEventDecorator, AppEvents},
         provider_broker::{self, ProviderBroker},
     },
+    service::{
+        data_governance::{DataGovernance, DataGovernanceDecision},
+        watch_history::WatchHistoryItem,
+    },
     utils::rpc_utils::{rpc_await_oneshot, rpc_err, rpc_navigate_reserved_app_err},
 };
 use jsonrpsee::{
@@ -35,17 +39,23 @@ use jsonrpsee::{
 use ripple_sdk::{
     api::{
         apps::{AppError, AppManagerResponse, AppMethod, AppRequest, AppResponse},
+        distributor::distributor_privacy::DataEventType,
         firebolt::{
             fb_capabilities::FireboltCap,
             fb_discovery::{
-                LaunchRequest, DISCOVERY_EVENT_ON_NAVIGATE_TO, ENTITY_INFO_CAPABILITY,
-                ENTITY_INFO_EVENT, EVENT_DISCOVERY_POLICY_CHANGED, PURCHASED_CONTENT_CAPABILITY,
-                PURCHASED_CONTENT_EVENT,
+                ClearContentSetParams, ContentAccessInfo, ContentAccessListSetParams,
+                ContentAccessRequest, DiscoveryRequest, EntitlementsInfo, InterestEvent,
+                InterestResult, LaunchRequest, SessionParams, UserInterest, UserInterestRequest,
+                WatchNextInfo, WatchedInfo, CONTENT_EVENT_ON_USER_INTEREST,
+                DISCOVERY_EVENT_ON_ENTITLEMENTS_CHANGED, DISCOVERY_EVENT_ON_NAVIGATE_TO,
+                ENTITY_INFO_CAPABILITY, ENTITY_INFO_EVENT, EVENT_DISCOVERY_POLICY_CHANGED,
+                PURCHASED_CONTENT_CAPABILITY, PURCHASED_CONTENT_EVENT, USER_INTEREST_CAPABILITY,
+                USER_INTEREST_EVENT,
             },
             provider::{ProviderRequestPayload, ProviderResponse, ProviderResponsePayload},
         },
     },
-    log::{error, info},
+    log::{debug, error, info},
     tokio::{sync::oneshot, time::timeout},
 };
 use ripple_sdk::{
@@ -118,6 +128,68 @@ pub trait Discovery {
     #[method(name = "discovery.policy")]
     async fn get_content_policy_rpc(&self, ctx: CallContext) -> RpcResult<ContentPolicy>;
 
+    #[method(name = "discovery.entitlements")]
+    async fn entitlements(
+        &self,
+        ctx: CallContext,
+        entitlements_info: EntitlementsInfo,
+    ) -> RpcResult<bool>;
+
+    #[method(name = "discovery.onEntitlementsChanged")]
+    async fn on_entitlements_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+
+    #[method(name = "discovery.watched")]
+    async fn watched(&self, ctx: CallContext, watched_info: WatchedInfo) -> RpcResult<bool>;
+
+    #[method(name = "discovery.watchNext")]
+    async fn watch_next(&self, ctx: CallContext, watch_next_info: WatchNextInfo)
+        -> RpcResult<bool>;
+
+    #[method(name = "discovery.userInterest")]
+    async fn user_interest(&self, ctx: CallContext, interest: UserInterest) -> RpcResult<()>;
+
+    #[method(name = "discovery.onRequestUserInterest")]
+    async fn on_request_user_interest(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+
+    #[method(name = "discovery.userInterestResponse")]
+    async fn handle_user_interest_response(
+        &self,
+        ctx: CallContext,
+        response: ExternalProviderResponse<InterestResult>,
+    ) -> RpcResult<bool>;
+
+    #[method(name = "content.requestUserInterest")]
+    async fn request_user_interest(
+        &self,
+        ctx: CallContext,
+        request: UserInterestRequest,
+    ) -> RpcResult<InterestResult>;
+
+    #[method(name = "content.onUserInterest")]
+    async fn on_user_interest(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+
+    #[method(name = "discovery.contentAccess")]
+    async fn content_access(
+        &self,
+        ctx: CallContext,
+        request: ContentAccessRequest,
+    ) -> RpcResult<()>;
+
+    #[method(name = "discovery.clearContentAccess")]
+    async fn clear_content_access(&self, ctx: CallContext) -> RpcResult<()>;
+
     #[method(name = "discovery.onPolicyChanged")]
     async fn on_policy_changed(
         &self,
@@ -201,6 +273,48 @@ impl DiscoveryImpl {
     pub fn get_share_watch_history() -> bool {
         false
     }
+
+    /// Sends the content access request for the calling app to the distributor
+    async fn send_discovery_request<F>(&self, ctx: &CallContext, request: F) -> RpcResult<()>
+    where
+        F: FnOnce(SessionParams) -> DiscoveryRequest,
+    {
+        let dist_session = self
+            .state
+            .session_state
+            .get_account_session()
+            .ok_or_else(|| rpc_err("Account session is not available"))?;
+        let request = request(SessionParams {
+            app_id: ctx.app_id.clone(),
+            dist_session,
+        });
+        if let Err(e) = self.state.get_client().send_extn_request(request).await {
+            error!("Discovery request failed: {:?}", e);
+            return Err(rpc_err("Unable to update content access"));
+        }
+        Ok(())
+    }
+
+    /// Watch history writes are queued and uploaded in batches. Data governance decides
+    /// whether the write is dropped, including when the user has not allowed the app to
+    /// remember watched programs, and the app receives success either way. Without an
+    /// upload endpoint nothing is kept.
+    async fn queue_watch_history(&self, item: WatchHistoryItem) -> RpcResult<bool> {
+        let watch_history_state = &self.state.watch_history_state;
+        if !watch_history_state.is_enabled() {
+            debug!("Watch history upload is not configured");
+            return Ok(true);
+        }
+        match DataGovernance::get_decision(&self.state, item.get_app_id(), DataEventType::Watched)
+            .await
+        {
+            DataGovernanceDecision::Forward(tags) => watch_history_state.add(item, tags),
+            DataGovernanceDecision::Drop => {
+                debug!("Watch history write dropped by data governance")
+            }
+        }
+        Ok(true)
+    }
 }
 
 #[derive(Clone)]
@@ -435,6 +549,191 @@ impl DiscoveryServer for DiscoveryImpl {
         })
     }
 
+    /// The app informs the platform of the entitlements of the user within the app, which
+    /// are recorded as its content access. The entitlements synchronized from the
+    /// distributor reach apps through `discovery.onEntitlementsChanged`.
+    async fn entitlements(
+        &self,
+        ctx: CallContext,
+        entitlements_info: EntitlementsInfo,
+    ) -> RpcResult<bool> {
+        self.content_access(ctx, entitlements_info.into()).await?;
+        Ok(true)
+    }
+
+    async fn on_entitlements_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        let listen = request.listen;
+
+        AppEvents::add_listener(
+            &self.state,
+            DISCOVERY_EVENT_ON_ENTITLEMENTS_CHANGED.into(),
+            ctx,
+            request,
+        );
+        Ok(ListenerResponse {
+            listening: listen,
+            event: DISCOVERY_EVENT_ON_ENTITLEMENTS_CHANGED.into(),
+        })
+    }
+
+    /// The interest of the user in an entity of the app is passed on to the apps listening
+    /// on `content.onUserInterest`. Data governance decides whether it is dropped, the app
+    /// receives success either way.
+    async fn user_interest(&self, ctx: CallContext, interest: UserInterest) -> RpcResult<()> {
+        if DataGovernance::get_decision(&self.state, &ctx.app_id, DataEventType::Interest).await
+            == DataGovernanceDecision::Drop
+        {
+            debug!("User interest dropped by data governance");
+            return Ok(());
+        }
+        let event = InterestEvent {
+            app_id: ctx.app_id.clone(),
+            interest_type: interest.interest_type,
+            reason: interest.reason,
+            entity: interest.entity,
+        };
+        AppEvents::emit(
+            &self.state,
+            CONTENT_EVENT_ON_USER_INTEREST,
+            &serde_json::to_value(event).unwrap(),
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn on_request_user_interest(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        let listening = request.listen;
+        ProviderBroker::register_or_unregister_provider(
+            &self.state,
+            FireboltCap::Short(USER_INTEREST_CAPABILITY.into()).as_str(),
+            String::from("userInterest"),
+            String::from(USER_INTEREST_EVENT),
+            ctx,
+            request,
+        )
+        .await;
+
+        Ok(ListenerResponse {
+            listening,
+            event: USER_INTEREST_EVENT.to_string(),
+        })
+    }
+
+    async fn handle_user_interest_response(
+        &self,
+        _ctx: CallContext,
+        response: ExternalProviderResponse<InterestResult>,
+    ) -> RpcResult<bool> {
+        let response = ProviderResponse {
+            correlation_id: response.correlation_id,
+            result: ProviderResponsePayload::UserInterestResponse(response.result),
+        };
+        ProviderBroker::provider_response(&self.state, response).await;
+        Ok(true)
+    }
+
+    /// Asks the interest provider, usually the app in focus, which entity the user is
+    /// interested in
+    async fn request_user_interest(
+        &self,
+        ctx: CallContext,
+        request: UserInterestRequest,
+    ) -> RpcResult<InterestResult> {
+        let (session_tx, session_rx) = oneshot::channel::<ProviderResponsePayload>();
+        let pr_msg = provider_broker::ProviderBrokerRequest {
+            app_id: None,
+            capability: FireboltCap::Short(USER_INTEREST_CAPABILITY.into()).as_str(),
+            method: String::from("userInterest"),
+            caller: ctx.into(),
+            request: ProviderRequestPayload::UserInterestRequest(request),
+            tx: session_tx,
+        };
+        ProviderBroker::invoke_method(&self.state, pr_msg).await;
+        match session_rx.await {
+            Ok(result) => result
+                .as_user_interest_result()
+                .ok_or_else(|| Error::Custom(String::from("Invalid response back from provider"))),
+            Err(_) => Err(Error::Custom(String::from(
+                "Error returning back from user interest provider",
+            ))),
+        }
+    }
+
+    async fn on_user_interest(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        let listen = request.listen;
+
+        AppEvents::add_listener(
+            &self.state,
+            CONTENT_EVENT_ON_USER_INTEREST.into(),
+            ctx,
+            request,
+        );
+        Ok(ListenerResponse {
+            listening: listen,
+            event: CONTENT_EVENT_ON_USER_INTEREST.into(),
+        })
+    }
+
+    async fn content_access(
+        &self,
+        ctx: CallContext,
+        request: ContentAccessRequest,
+    ) -> RpcResult<()> {
+        let content_access_info: ContentAccessInfo = request.ids.into();
+        self.state
+            .content_access_state
+            .set_content_access(&ctx.app_id, content_access_info.clone());
+        self.send_discovery_request(&ctx, |session_info| {
+            DiscoveryRequest::SetContentAccess(ContentAccessListSetParams {
+                session_info,
+                content_access_info,
+            })
+        })
+        .await
+    }
+
+    async fn clear_content_access(&self, ctx: CallContext) -> RpcResult<()> {
+        self.state
+            .content_access_state
+            .clear_content_access(&ctx.app_id);
+        self.send_discovery_request(&ctx, |session_info| {
+            DiscoveryRequest::ClearContent(ClearContentSetParams { session_info })
+        })
+        .await
+    }
+
+    async fn watched(&self, ctx: CallContext, watched_info: WatchedInfo) -> RpcResult<bool> {
+        self.queue_watch_history(WatchHistoryItem::Watched {
+            app_id: ctx.app_id.clone(),
+            info: watched_info,
+        })
+        .await
+    }
+
+    async fn watch_next(
+        &self,
+        ctx: CallContext,
+        watch_next_info: WatchNextInfo,
+    ) -> RpcResult<bool> {
+        self.queue_watch_history(WatchHistoryItem::WatchNext {
+            app_id: ctx.app_id.clone(),
+            info: Box::new(watch_next_info),
+        })
+        .await
+    }
+
     async fn get_providers(&self, _ctx: CallContext) -> RpcResult<Vec<ContentProvider>> {
         let res = ProviderBroker::get_provider_methods(&self.state);
         let provider_list = self.convert_provider_result(ProviderResult::new(res.entries));
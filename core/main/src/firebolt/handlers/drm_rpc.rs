@@ -0,0 +1,130 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::{
+        device::device_drm::{DrmInfo, LicenseRequest, DRM_CAPABILITY_PREFIX},
+        firebolt::fb_capabilities::{
+            CapabilityRole, FireboltCap, RoleInfo, CAPABILITY_NOT_PERMITTED,
+        },
+        gateway::rpc_gateway_api::CallContext,
+    },
+    log::error,
+    utils::rpc_utils::rpc_error_with_code_result,
+};
+use serde_json::json;
+
+use crate::{
+    broker::broker_utils::BrokerUtils,
+    firebolt::{handlers::capabilities_rpc::is_permitted, rpc::RippleRPCProvider},
+    state::platform_state::PlatformState,
+    utils::rpc_utils::rpc_err,
+};
+
+/// Broker rule which reports the DRM systems of the device
+const DEVICE_DRMS: &str = "sts.device.drms";
+/// Optional broker rule of the distributor which decorates license requests
+const DRM_LICENSE_REQUEST_HOOK: &str = "sts.drm.licenseRequest";
+
+#[rpc(server)]
+pub trait Drm {
+    #[method(name = "device.drms")]
+    async fn drms(&self, ctx: CallContext) -> RpcResult<Vec<DrmInfo>>;
+    #[method(name = "drm.licenseRequest")]
+    async fn license_request(
+        &self,
+        ctx: CallContext,
+        request: LicenseRequest,
+    ) -> RpcResult<LicenseRequest>;
+}
+
+pub struct DrmImpl {
+    pub state: PlatformState,
+}
+
+impl DrmImpl {
+    /// License requests carry entitlements of the distributor, only apps which may use the
+    /// protected content capability of the DRM system get them decorated.
+    async fn check_permitted(&self, ctx: &CallContext, system: &str) -> RpcResult<()> {
+        let cap = RoleInfo {
+            capability: FireboltCap::short(format!("{}{}", DRM_CAPABILITY_PREFIX, system)),
+            role: Some(CapabilityRole::Use),
+        };
+        if !is_permitted(&self.state, ctx, &cap).await? {
+            return rpc_error_with_code_result(
+                format!("{} is not permitted", cap.capability.as_str()),
+                CAPABILITY_NOT_PERMITTED,
+            );
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DrmServer for DrmImpl {
+    async fn drms(&self, _ctx: CallContext) -> RpcResult<Vec<DrmInfo>> {
+        if !self.state.endpoint_state.has_rule(DEVICE_DRMS) {
+            return Err(rpc_err("device.drms is not available"));
+        }
+        let value =
+            BrokerUtils::process_internal_main_request(&self.state, DEVICE_DRMS, None).await?;
+        serde_json::from_value(value).map_err(|e| {
+            error!("Invalid response for {}: {:?}", DEVICE_DRMS, e);
+            rpc_err("device.drms: unexpected value type")
+        })
+    }
+
+    async fn license_request(
+        &self,
+        ctx: CallContext,
+        request: LicenseRequest,
+    ) -> RpcResult<LicenseRequest> {
+        self.check_permitted(&ctx, &request.system).await?;
+        let config = &self.state.get_device_configuration().drm;
+        let system_config = match config.systems.get(&request.system) {
+            Some(system_config) => system_config,
+            None => return Ok(request),
+        };
+        let request = request.apply(system_config);
+        if !system_config.hook || !self.state.endpoint_state.has_rule(DRM_LICENSE_REQUEST_HOOK) {
+            return Ok(request);
+        }
+        let value = BrokerUtils::process_for_app_main_request(
+            &self.state,
+            DRM_LICENSE_REQUEST_HOOK,
+            Some(json!(request)),
+            &ctx.app_id,
+        )
+        .await?;
+        serde_json::from_value(value).map_err(|e| {
+            error!("Invalid response for {}: {:?}", DRM_LICENSE_REQUEST_HOOK, e);
+            rpc_err("drm.licenseRequest: unexpected value type")
+        })
+    }
+}
+
+pub struct DrmRPCProvider;
+impl RippleRPCProvider<DrmImpl> for DrmRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<DrmImpl> {
+        (DrmImpl { state }).into_rpc()
+    }
+}
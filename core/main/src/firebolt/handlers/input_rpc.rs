@@ -0,0 +1,81 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::{
+        device::device_input::{INPUT_KEYS_CAPABILITY, INPUT_KEY_EVENT},
+        firebolt::{
+            fb_capabilities::{CapabilityRole, FireboltCap, RoleInfo, CAPABILITY_NOT_PERMITTED},
+            fb_general::{ListenRequest, ListenerResponse},
+        },
+        gateway::rpc_gateway_api::CallContext,
+    },
+    utils::rpc_utils::rpc_error_with_code_result,
+};
+
+use crate::{
+    firebolt::{handlers::capabilities_rpc::is_permitted, rpc::RippleRPCProvider},
+    state::platform_state::PlatformState,
+    utils::rpc_utils::rpc_add_event_listener,
+};
+
+#[rpc(server)]
+pub trait Input {
+    #[method(name = "input.onKey")]
+    async fn on_key(&self, ctx: CallContext, request: ListenRequest)
+        -> RpcResult<ListenerResponse>;
+}
+
+/// Remote key forwarding for system apps, subscribing requires the use role of `input:keys`.
+pub struct InputImpl {
+    pub state: PlatformState,
+}
+
+#[async_trait]
+impl InputServer for InputImpl {
+    async fn on_key(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        if request.listen {
+            let cap = RoleInfo {
+                capability: FireboltCap::short(INPUT_KEYS_CAPABILITY),
+                role: Some(CapabilityRole::Use),
+            };
+            if !is_permitted(&self.state, &ctx, &cap).await? {
+                return rpc_error_with_code_result(
+                    format!("{} is not permitted", cap.capability.as_str()),
+                    CAPABILITY_NOT_PERMITTED,
+                );
+            }
+        }
+        rpc_add_event_listener(&self.state, ctx, request, INPUT_KEY_EVENT).await
+    }
+}
+
+pub struct InputRPCProvider;
+impl RippleRPCProvider<InputImpl> for InputRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<InputImpl> {
+        (InputImpl { state }).into_rpc()
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::{
+        firebolt::{
+            fb_capabilities::{CapabilityRole, FireboltCap, RoleInfo, CAPABILITY_NOT_PERMITTED},
+            fb_general::{ListenRequest, ListenerResponse},
+        },
+        gateway::rpc_gateway_api::CallContext,
+    },
+    utils::rpc_utils::rpc_error_with_code_result,
+};
+use serde_json::json;
+
+use crate::{
+    firebolt::{handlers::capabilities_rpc::is_permitted, rpc::RippleRPCProvider},
+    service::apps::app_events::AppEvents,
+    state::{
+        inspector_state::{InspectRequest, INSPECTOR_CAPABILITY, INSPECTOR_EVENT_ON_FRAME},
+        platform_state::PlatformState,
+    },
+};
+
+#[rpc(server)]
+pub trait Inspector {
+    #[method(name = "ripple.onInspectorFrame")]
+    async fn on_inspector_frame(
+        &self,
+        ctx: CallContext,
+        request: InspectRequest,
+    ) -> RpcResult<ListenerResponse>;
+}
+
+/// Live inspector for developer tools, listening requires the use role of `ripple:inspector`.
+/// The requests, responses and events of the selected app are mirrored with secrets and
+/// personal data redacted.
+pub struct InspectorImpl {
+    pub state: PlatformState,
+}
+
+#[async_trait]
+impl InspectorServer for InspectorImpl {
+    async fn on_inspector_frame(
+        &self,
+        ctx: CallContext,
+        request: InspectRequest,
+    ) -> RpcResult<ListenerResponse> {
+        if request.listen {
+            let cap = RoleInfo {
+                capability: FireboltCap::short(INSPECTOR_CAPABILITY),
+                role: Some(CapabilityRole::Use),
+            };
+            if !is_permitted(&self.state, &ctx, &cap).await? {
+                return rpc_error_with_code_result(
+                    format!("{} is not permitted", cap.capability.as_str()),
+                    CAPABILITY_NOT_PERMITTED,
+                );
+            }
+            self.state.inspector_state.add_inspector(
+                ctx.session_id.clone(),
+                request.app_id.clone(),
+                request.methods,
+            );
+        } else {
+            self.state.inspector_state.remove_session(&ctx.session_id);
+        }
+        AppEvents::add_listener_with_context(
+            &self.state,
+            INSPECTOR_EVENT_ON_FRAME.to_owned(),
+            ctx,
+            ListenRequest {
+                listen: request.listen,
+            },
+            Some(json!(request.app_id)),
+        );
+        Ok(ListenerResponse {
+            listening: request.listen,
+            event: INSPECTOR_EVENT_ON_FRAME.to_owned(),
+        })
+    }
+}
+
+pub struct InspectorRPCProvider;
+impl RippleRPCProvider<InspectorImpl> for InspectorRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<InspectorImpl> {
+        (InspectorImpl { state }).into_rpc()
+    }
+}
@@ -20,12 +20,17 @@ use ripple_sdk::{
     api::{
         apps::{AppEvent, AppManagerResponse, AppMethod, AppRequest, AppResponse},
         caps::CapsRequest,
-        firebolt::{fb_general::ListenRequestWithEvent, fb_telemetry::TelemetryPayload},
+        firebolt::{
+            fb_capabilities::JSON_RPC_STANDARD_ERROR_INVALID_PARAMS,
+            fb_general::ListenRequestWithEvent, fb_telemetry::TelemetryPayload,
+        },
         gateway::rpc_gateway_api::CallContext,
     },
     async_trait::async_trait,
     log::{debug, error},
+    serde_json::Value,
     tokio::sync::oneshot,
+    utils::rpc_utils::rpc_error_with_code,
 };
 use std::collections::HashMap;
 
@@ -39,7 +44,7 @@ use crate::{
 #[rpc(server)]
 pub trait Internal {
     #[method(name = "ripple.sendTelemetry")]
-    async fn send_telemetry(&self, ctx: CallContext, payload: TelemetryPayload) -> RpcResult<()>;
+    async fn send_telemetry(&self, ctx: CallContext, payload: Value) -> RpcResult<()>;
 
     #[method(name = "ripple.setTelemetrySessionId")]
     fn set_telemetry_session_id(&self, ctx: CallContext, session_id: String) -> RpcResult<()>;
@@ -72,7 +77,13 @@ pub struct InternalImpl {
 
 #[async_trait]
 impl InternalServer for InternalImpl {
-    async fn send_telemetry(&self, _ctx: CallContext, payload: TelemetryPayload) -> RpcResult<()> {
+    async fn send_telemetry(&self, _ctx: CallContext, payload: Value) -> RpcResult<()> {
+        let payload = TelemetryPayload::from_value(payload).map_err(|_| {
+            rpc_error_with_code::<()>(
+                "Telemetry payload does not match its schema",
+                JSON_RPC_STANDARD_ERROR_INVALID_PARAMS,
+            )
+        })?;
         let _ = TelemetryBuilder::send_telemetry(&self.state, payload);
         Ok(())
     }
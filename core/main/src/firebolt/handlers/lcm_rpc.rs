@@ -23,11 +23,13 @@ use ripple_sdk::{
             fb_capabilities::FireboltCap,
             fb_general::{ListenRequest, ListenerResponse},
             fb_lifecycle_management::{
-                AppSessionRequest, SessionResponse, SetStateRequest, LCM_EVENT_ON_REQUEST_CLOSE,
-                LCM_EVENT_ON_REQUEST_FINISHED, LCM_EVENT_ON_REQUEST_LAUNCH,
-                LCM_EVENT_ON_REQUEST_READY, LCM_EVENT_ON_SESSION_TRANSITION_CANCELED,
+                AppSessionRequest, AppUsageRequest, SessionResponse, SetStateRequest,
+                LCM_EVENT_ON_REQUEST_CLOSE, LCM_EVENT_ON_REQUEST_FINISHED,
+                LCM_EVENT_ON_REQUEST_LAUNCH, LCM_EVENT_ON_REQUEST_READY,
+                LCM_EVENT_ON_SESSION_TRANSITION_CANCELED,
                 LCM_EVENT_ON_SESSION_TRANSITION_COMPLETED,
             },
+            fb_telemetry::AppUsage,
         },
         gateway::rpc_gateway_api::CallContext,
     },
@@ -93,6 +95,13 @@ pub trait LifecycleManagement {
         ctx: CallContext,
         request: ListenRequest,
     ) -> RpcResult<ListenerResponse>;
+
+    #[method(name = "lifecyclemanagement.appUsage")]
+    async fn app_usage(
+        &self,
+        ctx: CallContext,
+        request: AppUsageRequest,
+    ) -> RpcResult<Vec<AppUsage>>;
 }
 
 #[derive(Debug)]
@@ -261,6 +270,17 @@ impl LifecycleManagementServer for LifecycleManagementImpl {
             event: LCM_EVENT_ON_SESSION_TRANSITION_CANCELED.to_string(),
         })
     }
+
+    async fn app_usage(
+        &self,
+        _ctx: CallContext,
+        request: AppUsageRequest,
+    ) -> RpcResult<Vec<AppUsage>> {
+        Ok(self
+            .state
+            .app_usage_state
+            .get_usage(request.app_id.as_deref()))
+    }
 }
 
 pub struct LifecycleManagementProvider;
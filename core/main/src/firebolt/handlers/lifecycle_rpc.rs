@@ -32,6 +32,7 @@ use ripple_sdk::{
                 LIFECYCLE_EVENT_ON_SUSPEND, LIFECYCLE_EVENT_ON_SUSPENDED,
                 LIFECYCLE_EVENT_ON_UNLOADING,
             },
+            fb_telemetry::BootMilestoneType,
         },
         gateway::rpc_gateway_api::CallContext,
     },
@@ -41,7 +42,7 @@ use ripple_sdk::{
 use crate::broker::broker_utils::BrokerUtils;
 use crate::{
     firebolt::rpc::RippleRPCProvider,
-    service::apps::app_events::AppEvents,
+    service::{apps::app_events::AppEvents, telemetry_builder::TelemetryBuilder},
     state::platform_state::PlatformState,
     utils::rpc_utils::{rpc_await_oneshot, rpc_err},
 };
@@ -176,6 +177,10 @@ impl LifecycleImpl {
 #[async_trait]
 impl LifecycleServer for LifecycleImpl {
     async fn ready(&self, ctx: CallContext) -> RpcResult<()> {
+        TelemetryBuilder::send_boot_milestone(
+            &self.platform_state,
+            BootMilestoneType::FirstAppReady,
+        );
         if ctx.is_rpc_v2() {
             if BrokerUtils::process_for_app_main_request(
                 &self.platform_state,
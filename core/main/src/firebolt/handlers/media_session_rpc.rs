@@ -0,0 +1,166 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::{
+        firebolt::{
+            fb_capabilities::{CapabilityRole, FireboltCap, RoleInfo, CAPABILITY_NOT_PERMITTED},
+            fb_general::{ListenRequest, ListenerResponse},
+            fb_media_session::{
+                MediaSession, MediaSessionReport, MEDIA_SESSION_CAPABILITY,
+                MEDIA_SESSION_EVENT_ON_ACTIVE_SESSION_CHANGED,
+            },
+        },
+        gateway::rpc_gateway_api::CallContext,
+    },
+    utils::rpc_utils::rpc_error_with_code_result,
+};
+use serde_json::json;
+
+use crate::{
+    firebolt::{handlers::capabilities_rpc::is_permitted, rpc::RippleRPCProvider},
+    service::apps::app_events::AppEvents,
+    state::platform_state::PlatformState,
+    utils::rpc_utils::rpc_add_event_listener,
+};
+
+#[rpc(server)]
+pub trait MediaSessions {
+    #[method(name = "mediasession.report")]
+    async fn report(&self, ctx: CallContext, request: MediaSessionReport) -> RpcResult<()>;
+    #[method(name = "mediasession.clear")]
+    async fn clear(&self, ctx: CallContext) -> RpcResult<()>;
+    #[method(name = "mediasession.activeSession")]
+    async fn active_session(&self, ctx: CallContext) -> RpcResult<Option<MediaSession>>;
+    #[method(name = "mediasession.sessions")]
+    async fn sessions(&self, ctx: CallContext) -> RpcResult<Vec<MediaSession>>;
+    #[method(name = "mediasession.onActiveSessionChanged")]
+    async fn on_active_session_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+}
+
+/// Apps report their own playback state, reading the sessions requires the use role of
+/// `media:sessions` which is meant for system apps like a mini player.
+pub struct MediaSessionImpl {
+    pub state: PlatformState,
+}
+
+impl MediaSessionImpl {
+    async fn check_permitted(&self, ctx: &CallContext) -> RpcResult<()> {
+        let cap = RoleInfo {
+            capability: FireboltCap::short(MEDIA_SESSION_CAPABILITY),
+            role: Some(CapabilityRole::Use),
+        };
+        if !is_permitted(&self.state, ctx, &cap).await? {
+            return rpc_error_with_code_result(
+                format!("{} is not permitted", cap.capability.as_str()),
+                CAPABILITY_NOT_PERMITTED,
+            );
+        }
+        Ok(())
+    }
+
+    async fn emit_if_changed(state: &PlatformState, previous: Option<MediaSession>) {
+        let active = state.media_session_state.get_active();
+        let unchanged = match (&active, &previous) {
+            (Some(active), Some(previous)) => active.same_state(previous),
+            (None, None) => true,
+            _ => false,
+        };
+        if !unchanged {
+            AppEvents::emit(
+                state,
+                MEDIA_SESSION_EVENT_ON_ACTIVE_SESSION_CHANGED,
+                &json!(active),
+            )
+            .await;
+        }
+    }
+
+    /// Removes the session of an app which is no longer running
+    pub async fn clear_for_app(state: &PlatformState, app_id: &str) {
+        let previous = state.media_session_state.get_active();
+        if state.media_session_state.remove(app_id).is_some() {
+            Self::emit_if_changed(state, previous).await;
+        }
+    }
+}
+
+#[async_trait]
+impl MediaSessionsServer for MediaSessionImpl {
+    async fn report(&self, ctx: CallContext, request: MediaSessionReport) -> RpcResult<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let previous = self.state.media_session_state.get_active();
+        self.state
+            .media_session_state
+            .update(&ctx.app_id, request, now);
+        Self::emit_if_changed(&self.state, previous).await;
+        Ok(())
+    }
+
+    async fn clear(&self, ctx: CallContext) -> RpcResult<()> {
+        Self::clear_for_app(&self.state, &ctx.app_id).await;
+        Ok(())
+    }
+
+    async fn active_session(&self, ctx: CallContext) -> RpcResult<Option<MediaSession>> {
+        self.check_permitted(&ctx).await?;
+        Ok(self.state.media_session_state.get_active())
+    }
+
+    async fn sessions(&self, ctx: CallContext) -> RpcResult<Vec<MediaSession>> {
+        self.check_permitted(&ctx).await?;
+        Ok(self.state.media_session_state.get_sessions())
+    }
+
+    async fn on_active_session_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        if request.listen {
+            self.check_permitted(&ctx).await?;
+        }
+        rpc_add_event_listener(
+            &self.state,
+            ctx,
+            request,
+            MEDIA_SESSION_EVENT_ON_ACTIVE_SESSION_CHANGED,
+        )
+        .await
+    }
+}
+
+pub struct MediaSessionRPCProvider;
+impl RippleRPCProvider<MediaSessionImpl> for MediaSessionRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<MediaSessionImpl> {
+        (MediaSessionImpl { state }).into_rpc()
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::{
+        device::device_peristence::SetBoolProperty,
+        firebolt::{
+            fb_capabilities::{CapabilityRole, FireboltCap, RoleInfo, CAPABILITY_NOT_PERMITTED},
+            fb_general::{ListenRequest, ListenerResponse},
+            fb_notification_policy::{
+                DoNotDisturbStatus, SetDoNotDisturbWindowsRequest, NOTIFICATION_POLICY_CAPABILITY,
+                NOTIFICATION_POLICY_EVENT_ON_DO_NOT_DISTURB_CHANGED,
+            },
+        },
+        gateway::rpc_gateway_api::CallContext,
+    },
+    utils::rpc_utils::rpc_error_with_code_result,
+};
+
+use crate::{
+    firebolt::{handlers::capabilities_rpc::is_permitted, rpc::RippleRPCProvider},
+    service::notification_policy::NotificationPolicy,
+    state::platform_state::PlatformState,
+    utils::rpc_utils::{rpc_add_event_listener, rpc_err},
+};
+
+#[rpc(server)]
+pub trait Notifications {
+    #[method(name = "notifications.doNotDisturb")]
+    async fn do_not_disturb(&self, ctx: CallContext) -> RpcResult<DoNotDisturbStatus>;
+    #[method(name = "notifications.setDoNotDisturb")]
+    async fn set_do_not_disturb(&self, ctx: CallContext, request: SetBoolProperty)
+        -> RpcResult<()>;
+    #[method(name = "notifications.setDoNotDisturbWindows")]
+    async fn set_do_not_disturb_windows(
+        &self,
+        ctx: CallContext,
+        request: SetDoNotDisturbWindowsRequest,
+    ) -> RpcResult<()>;
+    #[method(name = "notifications.onDoNotDisturbChanged")]
+    async fn on_do_not_disturb_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
+}
+
+/// Do not disturb policy for system apps, reading the policy requires the use role of
+/// `notifications:policy` and changing it the manage role.
+pub struct NotificationsImpl {
+    pub state: PlatformState,
+}
+
+impl NotificationsImpl {
+    async fn check_permitted(&self, ctx: &CallContext, role: CapabilityRole) -> RpcResult<()> {
+        let cap = RoleInfo {
+            capability: FireboltCap::short(NOTIFICATION_POLICY_CAPABILITY),
+            role: Some(role),
+        };
+        if !is_permitted(&self.state, ctx, &cap).await? {
+            return rpc_error_with_code_result(
+                format!("{} is not permitted", cap.capability.as_str()),
+                CAPABILITY_NOT_PERMITTED,
+            );
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NotificationsServer for NotificationsImpl {
+    async fn do_not_disturb(&self, ctx: CallContext) -> RpcResult<DoNotDisturbStatus> {
+        self.check_permitted(&ctx, CapabilityRole::Use).await?;
+        Ok(self.state.notification_policy_state.get_status())
+    }
+
+    async fn set_do_not_disturb(
+        &self,
+        ctx: CallContext,
+        request: SetBoolProperty,
+    ) -> RpcResult<()> {
+        self.check_permitted(&ctx, CapabilityRole::Manage).await?;
+        let mut settings = self.state.notification_policy_state.get_settings();
+        settings.enabled = request.value;
+        NotificationPolicy::set_settings(&self.state, settings).await
+    }
+
+    async fn set_do_not_disturb_windows(
+        &self,
+        ctx: CallContext,
+        request: SetDoNotDisturbWindowsRequest,
+    ) -> RpcResult<()> {
+        self.check_permitted(&ctx, CapabilityRole::Manage).await?;
+        if let Some(window) = request.windows.iter().find(|w| !w.is_valid()) {
+            return Err(rpc_err(format!(
+                "Invalid do not disturb window {}-{}",
+                window.start, window.end
+            )));
+        }
+        let mut settings = self.state.notification_policy_state.get_settings();
+        settings.windows = request.windows;
+        NotificationPolicy::set_settings(&self.state, settings).await
+    }
+
+    async fn on_do_not_disturb_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        if request.listen {
+            self.check_permitted(&ctx, CapabilityRole::Use).await?;
+        }
+        rpc_add_event_listener(
+            &self.state,
+            ctx,
+            request,
+            NOTIFICATION_POLICY_EVENT_ON_DO_NOT_DISTURB_CHANGED,
+        )
+        .await
+    }
+}
+
+pub struct NotificationsRPCProvider;
+impl RippleRPCProvider<NotificationsImpl> for NotificationsRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<NotificationsImpl> {
+        (NotificationsImpl { state }).into_rpc()
+    }
+}
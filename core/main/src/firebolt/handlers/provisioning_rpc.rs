@@ -0,0 +1,151 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::Arc;
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::{
+        device::device_provisioning::{
+            CertificateInfo, IdentityKeyInfo, InstallCertificateParams, RotateIdentityKeyParams,
+            SecureElement, PROVISIONING_CAPABILITY,
+        },
+        firebolt::fb_capabilities::{
+            CapabilityRole, FireboltCap, RoleInfo, CAPABILITY_NOT_PERMITTED,
+        },
+        gateway::rpc_gateway_api::CallContext,
+        observability::log_signal::LogSignal,
+    },
+    utils::{error::RippleError, rpc_utils::rpc_error_with_code_result},
+};
+
+use crate::{
+    firebolt::{handlers::capabilities_rpc::is_permitted, rpc::RippleRPCProvider},
+    service::secure_element::ExtnSecureElement,
+    state::platform_state::PlatformState,
+    utils::rpc_utils::rpc_err,
+};
+
+#[rpc(server)]
+pub trait Provisioning {
+    #[method(name = "provisioning.installCertificate")]
+    async fn install_certificate(
+        &self,
+        ctx: CallContext,
+        params: InstallCertificateParams,
+    ) -> RpcResult<CertificateInfo>;
+    #[method(name = "provisioning.rotateIdentityKey")]
+    async fn rotate_identity_key(
+        &self,
+        ctx: CallContext,
+        params: RotateIdentityKeyParams,
+    ) -> RpcResult<IdentityKeyInfo>;
+}
+
+/// Provisioning methods for the distributor provisioning app. Every call requires the
+/// manage role of `device:provisioning` and is recorded in the audit log.
+pub struct ProvisioningImpl {
+    pub state: PlatformState,
+    pub secure_element: Arc<dyn SecureElement>,
+}
+
+impl ProvisioningImpl {
+    async fn check_permitted(&self, ctx: &CallContext) -> RpcResult<()> {
+        let cap = RoleInfo {
+            capability: FireboltCap::short(PROVISIONING_CAPABILITY),
+            role: Some(CapabilityRole::Manage),
+        };
+        if !is_permitted(&self.state, ctx, &cap).await? {
+            Self::audit(ctx, "denied", "target", "-");
+            return rpc_error_with_code_result(
+                format!("{} is not permitted", cap.capability.as_str()),
+                CAPABILITY_NOT_PERMITTED,
+            );
+        }
+        Ok(())
+    }
+
+    fn audit(ctx: &CallContext, outcome: &str, target_key: &str, target: &str) {
+        LogSignal::new(
+            "provisioning_audit".to_string(),
+            format!("{} {}", ctx.method, outcome),
+            ctx.clone(),
+        )
+        .with_diagnostic_context_item("app_id", &ctx.app_id)
+        .with_diagnostic_context_item(target_key, target)
+        .emit();
+    }
+
+    fn audit_result<T>(
+        ctx: &CallContext,
+        target_key: &str,
+        target: &str,
+        result: Result<T, RippleError>,
+    ) -> RpcResult<T> {
+        match result {
+            Ok(value) => {
+                Self::audit(ctx, "succeeded", target_key, target);
+                Ok(value)
+            }
+            Err(e) => {
+                Self::audit(ctx, &format!("failed: {}", e), target_key, target);
+                Err(rpc_err(format!("{} failed", ctx.method)))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ProvisioningServer for ProvisioningImpl {
+    async fn install_certificate(
+        &self,
+        ctx: CallContext,
+        params: InstallCertificateParams,
+    ) -> RpcResult<CertificateInfo> {
+        self.check_permitted(&ctx).await?;
+        let slot = params.slot.clone();
+        let result = self.secure_element.install_certificate(params).await;
+        Self::audit_result(&ctx, "slot", &slot, result)
+    }
+
+    async fn rotate_identity_key(
+        &self,
+        ctx: CallContext,
+        params: RotateIdentityKeyParams,
+    ) -> RpcResult<IdentityKeyInfo> {
+        self.check_permitted(&ctx).await?;
+        let key_id = params.key_id.clone();
+        let result = self.secure_element.rotate_identity_key(params).await;
+        Self::audit_result(&ctx, "key_id", &key_id, result)
+    }
+}
+
+pub struct ProvisioningRPCProvider;
+impl RippleRPCProvider<ProvisioningImpl> for ProvisioningRPCProvider {
+    fn provide(state: PlatformState) -> RpcModule<ProvisioningImpl> {
+        let secure_element = Arc::new(ExtnSecureElement::new(state.get_client()));
+        (ProvisioningImpl {
+            state,
+            secure_element,
+        })
+        .into_rpc()
+    }
+}
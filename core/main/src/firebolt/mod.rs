@@ -18,28 +18,40 @@
 //pub mod rpc_gateway;
 //pub mod firebolt_gateway;
 pub mod handlers {
+    pub mod accessibility_rpc;
     pub mod accessory_rpc;
+    pub mod admin_rpc;
     pub mod advertising_rpc;
     pub mod audio_description_rpc;
+    pub mod audio_focus_rpc;
     pub mod capabilities_rpc;
     pub mod closed_captions_rpc;
+    pub mod compatibility_rpc;
     pub mod device_rpc;
+    pub mod diagnostics_rpc;
     pub mod discovery_rpc;
+    pub mod drm_rpc;
+    pub mod input_rpc;
+    pub mod inspector_rpc;
     pub mod internal_rpc;
     pub mod keyboard_rpc;
     pub mod lcm_rpc;
     pub mod lifecycle_rpc;
     pub mod localization_rpc;
+    pub mod media_session_rpc;
+    pub mod notifications_rpc;
     pub mod parameters_rpc;
     pub mod privacy_rpc;
     pub mod profile_rpc;
     pub mod provider_registrar;
+    pub mod provisioning_rpc;
     pub mod second_screen_rpc;
     pub mod user_grants_rpc;
     pub mod wifi_rpc;
 }
 pub mod firebolt_gatekeeper;
 pub mod firebolt_gateway;
+pub mod firebolt_http;
 pub mod firebolt_ws;
 pub mod rpc;
 pub mod rpc_router;
@@ -27,22 +27,25 @@ use jsonrpsee::{
 use ripple_sdk::{
     api::{
         gateway::rpc_gateway_api::{ApiMessage, RpcRequest},
+        manifest::device_manifest::RouteTarget,
         observability::log_signal::LogSignal,
     },
     chrono::Utc,
     extn::extn_client_message::ExtnMessage,
     log::{debug, error, info},
-    service::service_message::{
-        Id as ServiceMessageId, JsonRpcMessage as ServiceJsonRpcMessage,
-        JsonRpcSuccess as ServiceJsonRpcSuccess, ServiceMessage,
-    },
+    service::service_message::{Id as ServiceMessageId, ServiceMessage},
     tokio,
     tokio_tungstenite::tungstenite::Message,
     utils::error::RippleError,
 };
-use std::sync::{Arc, RwLock};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, RwLock},
+};
 
 use crate::{
+    broker::rules::rules_engine::{MethodPattern, RuleMatchKind},
     firebolt::firebolt_gateway::JsonRpcMessage,
     service::telemetry_builder::TelemetryBuilder,
     state::{platform_state::PlatformState, session_state::Session},
@@ -67,9 +70,27 @@ impl RouterState {
         }
     }
 
-    pub fn update_methods(&self, methods: Methods) {
+    pub fn update_methods(&self, methods: Methods) -> Result<(), RippleError> {
+        let methods = self.initialize_resources(methods)?;
         let mut methods_state = self.methods.write().unwrap();
-        let _ = methods_state.merge(methods.initialize_resources(&self.resources).unwrap());
+        let _ = methods_state.merge(methods);
+        Ok(())
+    }
+
+    /// Replaces every registered method, requests which are already routed complete with
+    /// the method they were routed to. The registered methods are kept when the new ones
+    /// can not be initialized.
+    pub fn replace_methods(&self, methods: Methods) -> Result<(), RippleError> {
+        let methods = self.initialize_resources(methods)?;
+        *self.methods.write().unwrap() = methods;
+        Ok(())
+    }
+
+    fn initialize_resources(&self, methods: Methods) -> Result<Methods, RippleError> {
+        methods.initialize_resources(&self.resources).map_err(|e| {
+            error!("Unable to initialize the resources of the methods {:?}", e);
+            RippleError::ServiceError
+        })
     }
 
     pub fn get_method_entry(&self, method_name: &str) -> Option<(String, MethodCallback)> {
@@ -79,6 +100,112 @@ impl RouterState {
             .method_with_name(method_name)
             .map(|(name, method)| (name.to_owned(), method.clone()))
     }
+
+    pub fn get_method_names(&self) -> Vec<String> {
+        let methods = self.methods.read().unwrap();
+        methods.method_names().map(|name| name.to_owned()).collect()
+    }
+
+    /// Returns a handler method which the method, which may be a pattern, would also serve.
+    /// Names are compared ignoring case as the rules are.
+    pub fn find_overlapping_method(&self, method: &str) -> Option<String> {
+        let pattern = match RuleMatchKind::of(method) {
+            RuleMatchKind::Exact => None,
+            _ => Some(MethodPattern::compile(&method.to_lowercase())),
+        };
+        let methods = self.methods.read().unwrap();
+        let found = methods.method_names().find(|name| match &pattern {
+            Some(pattern) => pattern.matches(&name.to_lowercase()),
+            None => name.eq_ignore_ascii_case(method),
+        });
+        found.map(|name| name.to_owned())
+    }
+}
+
+/// Target which serves a method, along with the targets it takes precedence over
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteEntry {
+    pub method: String,
+    pub target: RouteTarget,
+    /// Handler method name, or the alias of the rule which is the ServiceId for services and
+    /// the ExtnId for extensions
+    pub served_by: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shadowed: Vec<RouteTarget>,
+}
+
+/// Limits the routing table to the target serving one method
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutingTableParams {
+    #[serde(default)]
+    pub method: Option<String>,
+}
+
+/// Single view over every target able to serve a method: the handlers registered with the
+/// router, the rules of the device brokered to their endpoints and the methods registered at
+/// runtime by extensions and services. The target served first is the first one in the
+/// routing precedence of the device manifest which has the method.
+pub struct RoutingTable;
+
+impl RoutingTable {
+    pub fn resolve(state: &PlatformState, method: &str) -> Option<RouteEntry> {
+        let mut entry: Option<RouteEntry> = None;
+        for target in state
+            .endpoint_state
+            .get_route_precedence()
+            .targets()
+            .iter()
+            .copied()
+        {
+            let candidate = match target {
+                RouteTarget::Handler => state
+                    .router_state
+                    .get_method_entry(method)
+                    .map(|(name, _)| (name, None)),
+                _ => state
+                    .endpoint_state
+                    .find_rule(method, target)
+                    .map(|rule| (rule.alias, rule.endpoint)),
+            };
+            match (&mut entry, candidate) {
+                (Some(entry), Some(_)) => entry.shadowed.push(target),
+                (None, Some((served_by, endpoint))) => {
+                    entry = Some(RouteEntry {
+                        method: method.to_owned(),
+                        target,
+                        served_by,
+                        endpoint,
+                        shadowed: Vec::new(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        entry
+    }
+
+    /// Checks if a handler serves the method before any rule
+    pub fn is_handler_first(state: &PlatformState, method: &str) -> bool {
+        state.router_state.get_method_entry(method).is_some()
+            && !state.endpoint_state.has_rule_before_handler(method)
+    }
+
+    /// Resolves every handler method and rule name, patterns are listed as registered
+    pub fn dump(state: &PlatformState) -> Vec<RouteEntry> {
+        let methods: BTreeSet<String> = state
+            .router_state
+            .get_method_names()
+            .into_iter()
+            .chain(state.endpoint_state.get_rules().into_keys())
+            .collect();
+        methods
+            .iter()
+            .filter_map(|method| Self::resolve(state, method))
+            .collect()
+    }
 }
 
 impl Default for RouterState {
@@ -285,24 +412,31 @@ impl RpcRouter {
                         serde_json::from_str::<serde_json::Value>(msg.jsonrpc_msg.clone().as_str())
                             .unwrap();
 
-                    let result = json_rpc_response.get("result").cloned().unwrap_or_default();
-                    let jsonrpc = serde_json::to_string(
-                        &json_rpc_response
-                            .get("jsonrpc")
-                            .cloned()
-                            .unwrap_or_default(),
-                    )
-                    .unwrap();
-                    let id = ServiceMessageId::String(msg.request_id.clone());
-
-                    let service_message = ServiceMessage {
-                        message: ServiceJsonRpcMessage::Success(ServiceJsonRpcSuccess {
-                            result,
-                            jsonrpc,
+                    let id = platform_state
+                        .service_controller_state
+                        .take_original_id(&msg.request_id)
+                        .await;
+
+                    // errors are passed on so the service can tell them apart from results
+                    let mut service_message = match json_rpc_response.get("error") {
+                        Some(error) => ServiceMessage::new_error(
+                            error.get("code").and_then(|c| c.as_i64()).unwrap_or(-32603),
+                            error
+                                .get("message")
+                                .and_then(|m| m.as_str())
+                                .unwrap_or_default()
+                                .to_owned(),
+                            error.get("data").cloned(),
+                            id,
+                        ),
+                        None => ServiceMessage::new_success(
+                            json_rpc_response.get("result").cloned().unwrap_or_default(),
                             id,
-                        }),
-                        context: Some(serde_json::to_value(req.ctx.clone()).unwrap_or_default()),
+                        ),
                     };
+                    service_message.set_context(Some(
+                        serde_json::to_value(req.ctx.clone()).unwrap_or_default(),
+                    ));
                     let msg_str = serde_json::to_string(&service_message).unwrap();
                     let message = Message::Text(msg_str.clone());
                     debug!("Sending response to service {}: {:?}", service_id, message);
@@ -333,3 +467,44 @@ impl RpcRouter {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::RpcModule;
+    use ripple_tdk::utils::test_utils::Mockable;
+
+    #[test]
+    fn test_routing_table() {
+        let state = PlatformState::mock();
+        state
+            .endpoint_state
+            .add_service_rules("media", &["player.*".to_string()]);
+        state
+            .endpoint_state
+            .add_extn_rules("ripple:channel:player:extn", &["player.play".to_string()]);
+
+        let entry = RoutingTable::resolve(&state, "player.play").unwrap();
+        assert_eq!(entry.target, RouteTarget::Service);
+        assert_eq!(entry.served_by, "media");
+        assert_eq!(entry.shadowed, vec![RouteTarget::Extension]);
+        assert!(!RoutingTable::is_handler_first(&state, "player.play"));
+        assert!(RoutingTable::resolve(&state, "unknown.method").is_none());
+
+        // handlers come before the methods registered at runtime
+        let mut module = RpcModule::new(());
+        module
+            .register_method("player.pause", |_, _| Ok(true))
+            .unwrap();
+        state.router_state.update_methods(module.into()).unwrap();
+        assert!(RoutingTable::is_handler_first(&state, "player.pause"));
+        let entry = RoutingTable::resolve(&state, "player.pause").unwrap();
+        assert_eq!(entry.target, RouteTarget::Handler);
+        assert_eq!(entry.shadowed, vec![RouteTarget::Service]);
+
+        let dump = RoutingTable::dump(&state);
+        assert!(dump
+            .iter()
+            .any(|entry| entry.method.eq("player.*") && entry.target == RouteTarget::Service));
+    }
+}
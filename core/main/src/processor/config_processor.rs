@@ -16,7 +16,10 @@
 //
 
 use ripple_sdk::{
-    api::config::{Config, ConfigResponse, LauncherConfig, RfcRequest},
+    api::config::{
+        Config, ConfigResponse, ConfigSection, ConfigSectionRequest, ConfigSectionResponse,
+        LauncherConfig, RfcRequest,
+    },
     async_trait::async_trait,
     extn::{
         client::extn_processor::{
@@ -24,8 +27,10 @@ use ripple_sdk::{
         },
         extn_client_message::{ExtnMessage, ExtnPayload, ExtnPayloadProvider, ExtnResponse},
     },
+    log::error,
     serde_json,
     tokio::sync::mpsc::{Receiver as MReceiver, Sender as MSender},
+    utils::error::RippleError,
 };
 
 use crate::state::platform_state::PlatformState;
@@ -45,6 +50,52 @@ impl ConfigRequestProcessor {
             streamer: DefaultExtnStreamer::new(),
         }
     }
+
+    fn get_section_value(state: &PlatformState, section: ConfigSection) -> serde_json::Value {
+        let device_manifest = state.get_device_manifest();
+        let value = match section {
+            ConfigSection::Features => {
+                serde_json::to_value(device_manifest.configuration.features.clone())
+            }
+            ConfigSection::DefaultValues => {
+                serde_json::to_value(device_manifest.configuration.default_values.clone())
+            }
+            ConfigSection::Launcher => serde_json::to_value(LauncherConfig {
+                lifecycle_policy: device_manifest.get_lifecycle_policy(),
+                retention_policy: device_manifest.get_retention_policy(),
+                app_library_state: state.get_app_library_state(),
+            }),
+        };
+        value.unwrap_or_default()
+    }
+
+    fn get_section(
+        state: &PlatformState,
+        requestor: String,
+        request: ConfigSectionRequest,
+    ) -> ExtnResponse {
+        if !request.is_compatible() {
+            error!(
+                "Refusing config section {:?} for {}: schema_hash={} expected={}",
+                request.section,
+                requestor,
+                request.schema_hash,
+                request.section.schema_hash()
+            );
+            return ExtnResponse::Error(RippleError::InvalidInput);
+        }
+        if request.subscribe {
+            state
+                .config_section_state
+                .subscribe(request.section, requestor);
+        }
+        ExtnResponse::Config(ConfigResponse::Section(ConfigSectionResponse {
+            section: request.section,
+            schema_hash: request.schema_hash,
+            revision: state.config_section_state.get_revision(request.section),
+            value: Self::get_section_value(state, request.section),
+        }))
+    }
 }
 
 impl ExtnStreamProcessor for ConfigRequestProcessor {
@@ -89,7 +140,7 @@ impl ExtnRequestProcessor for ConfigRequestProcessor {
                 let config = LauncherConfig {
                     lifecycle_policy: device_manifest.get_lifecycle_policy(),
                     retention_policy: device_manifest.get_retention_policy(),
-                    app_library_state: state.app_library_state.clone(),
+                    app_library_state: state.get_app_library_state(),
                 };
                 if let ExtnPayload::Response(r) = config.get_extn_payload() {
                     r
@@ -147,6 +198,9 @@ impl ExtnRequestProcessor for ConfigRequestProcessor {
                 }
                 resp
             }
+            Config::Section(request) => {
+                Self::get_section(&state, msg.requestor.to_string(), request)
+            }
             _ => ExtnResponse::Error(ripple_sdk::utils::error::RippleError::InvalidInput),
         };
         Self::respond(state.get_client().get_extn_client(), msg, response)
@@ -0,0 +1,150 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::time::Duration;
+
+use ripple_sdk::{
+    api::{
+        context::{ActivationStatus, RippleContext, RippleContextUpdateType},
+        distributor::distributor_entitlements::{EntitlementsRequest, EntitlementsResponse},
+        firebolt::fb_discovery::DISCOVERY_EVENT_ON_ENTITLEMENTS_CHANGED,
+    },
+    async_trait::async_trait,
+    extn::{
+        client::extn_processor::{
+            DefaultExtnStreamer, ExtnEventProcessor, ExtnStreamProcessor, ExtnStreamer,
+        },
+        extn_client_message::ExtnMessage,
+    },
+    framework::RippleResponse,
+    log::{debug, error},
+    tokio::{
+        self,
+        sync::mpsc::{Receiver as MReceiver, Sender as MSender},
+    },
+    utils::error::RippleError,
+};
+use serde_json::json;
+
+use crate::{service::apps::app_events::AppEvents, state::platform_state::PlatformState};
+
+/// Shortest interval the entitlements are synchronized at, so a zero interval does not busy
+/// loop against the distributor
+const MIN_SYNC_INTERVAL_SECONDS: u64 = 60;
+
+/// Synchronizes the account entitlements from the distributor into the platform state.
+/// Entitlements are fetched on a schedule from the device manifest and every time the
+/// account token changes, apps are notified through `discovery.onEntitlementsChanged`.
+#[derive(Debug)]
+pub struct EntitlementsSyncProcessor {
+    state: PlatformState,
+    streamer: DefaultExtnStreamer,
+}
+
+impl EntitlementsSyncProcessor {
+    pub fn new(state: PlatformState) -> EntitlementsSyncProcessor {
+        EntitlementsSyncProcessor {
+            state,
+            streamer: DefaultExtnStreamer::new(),
+        }
+    }
+
+    /// Fetches the entitlements, `token` overrides the token of the stored account session
+    /// for syncs triggered by a token change which may not have been stored yet.
+    pub async fn sync(state: &PlatformState, token: Option<String>) -> RippleResponse {
+        let mut session = state
+            .session_state
+            .get_account_session()
+            .ok_or(RippleError::NotAvailable)?;
+        if let Some(token) = token {
+            session.token = token;
+        }
+        let response = state
+            .get_client()
+            .send_extn_request(EntitlementsRequest { session })
+            .await?;
+        let entitlements = response
+            .payload
+            .extract::<EntitlementsResponse>()
+            .ok_or(RippleError::InvalidOutput)?;
+        if state.entitlements_state.update(entitlements.clone()) {
+            debug!("Entitlements changed count={}", entitlements.len());
+            AppEvents::emit(
+                state,
+                DISCOVERY_EVENT_ON_ENTITLEMENTS_CHANGED,
+                &json!({ "entitlements": entitlements }),
+            )
+            .await;
+        }
+        Ok(())
+    }
+
+    pub fn start_schedule(state: PlatformState) {
+        let interval = state
+            .get_device_manifest()
+            .get_entitlements_sync_configuration()
+            .interval_seconds
+            .max(MIN_SYNC_INTERVAL_SECONDS);
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = Self::sync(&state, None).await {
+                    error!("Entitlements sync failed: {:?}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+            }
+        });
+    }
+}
+
+impl ExtnStreamProcessor for EntitlementsSyncProcessor {
+    type VALUE = RippleContext;
+    type STATE = PlatformState;
+
+    fn get_state(&self) -> Self::STATE {
+        self.state.clone()
+    }
+
+    fn sender(&self) -> MSender<ExtnMessage> {
+        self.streamer.sender()
+    }
+
+    fn receiver(&mut self) -> MReceiver<ExtnMessage> {
+        self.streamer.receiver()
+    }
+}
+
+#[async_trait]
+impl ExtnEventProcessor for EntitlementsSyncProcessor {
+    async fn process_event(
+        state: Self::STATE,
+        _msg: ExtnMessage,
+        extracted_message: Self::VALUE,
+    ) -> Option<bool> {
+        if let (
+            Some(RippleContextUpdateType::TokenChanged),
+            Some(ActivationStatus::AccountToken(t)),
+        ) = (
+            extracted_message.update_type,
+            extracted_message.activation_status,
+        ) {
+            if let Err(e) = Self::sync(&state, Some(t.token)).await {
+                error!("Entitlements sync on token change failed: {:?}", e);
+            }
+        }
+        None
+    }
+}
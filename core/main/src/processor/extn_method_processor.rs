@@ -0,0 +1,242 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::{
+    api::{
+        extn_method::{ExtnMethodRegistration, ExtnMethodRegistrationResult, ExtnMethodRequest},
+        firebolt::fb_capabilities::FireboltCap,
+    },
+    async_trait::async_trait,
+    extn::{
+        client::extn_processor::{
+            DefaultExtnStreamer, ExtnRequestProcessor, ExtnStreamProcessor, ExtnStreamer,
+        },
+        extn_client_message::{ExtnMessage, ExtnResponse},
+    },
+    log::info,
+    tokio::sync::mpsc::{Receiver, Sender},
+};
+
+use crate::{
+    broker::rules::rules_engine::{RuleMatchKind, RuleProvenance},
+    state::{extn_method_state::ExtnMethod, platform_state::PlatformState},
+};
+
+/// Registers the Firebolt methods served by extensions at runtime. A method is installed when
+/// the capability policy of the device grants its capability to the extension and covers the
+/// method, its params schema compiles and no router method or rule of someone else serves it
+/// already. Calls are gated by the capability of the method, checked against its schema and
+/// routed to the extension by the extn broker.
+#[derive(Debug)]
+pub struct ExtnMethodProcessor {
+    state: PlatformState,
+    streamer: DefaultExtnStreamer,
+}
+
+impl ExtnMethodProcessor {
+    pub fn new(state: PlatformState) -> ExtnMethodProcessor {
+        ExtnMethodProcessor {
+            state,
+            streamer: DefaultExtnStreamer::new(),
+        }
+    }
+
+    pub fn register(
+        state: &PlatformState,
+        extn_id: &str,
+        registrations: Vec<ExtnMethodRegistration>,
+    ) -> ExtnMethodRegistrationResult {
+        let policy = state.get_device_manifest().get_service_capability_policy();
+        let provenance = RuleProvenance::Extn {
+            extn_id: extn_id.to_owned(),
+        };
+        let mut result = ExtnMethodRegistrationResult::default();
+        let mut extn_methods = Vec::new();
+        for registration in registrations {
+            let capability = FireboltCap::parse(registration.capability.clone());
+            let (granted, _) = policy.grant_capabilities(extn_id, &[registration.capability]);
+            let served_elsewhere = state
+                .router_state
+                .find_overlapping_method(&registration.method)
+                .is_some()
+                || state
+                    .endpoint_state
+                    .find_overlapping_rules(&registration.method)
+                    .iter()
+                    .any(|(_, rule)| rule.provenance.ne(&provenance));
+            let extn_method = capability.and_then(|capability| {
+                ExtnMethod::new(
+                    extn_id,
+                    &registration.method,
+                    capability,
+                    registration.schema.as_ref(),
+                )
+                .ok()
+            });
+            match extn_method {
+                Some(extn_method)
+                    if !granted.is_empty()
+                        && RuleMatchKind::is_valid_pattern(&registration.method)
+                        && !served_elsewhere
+                        && policy.is_method_covered(&granted, &registration.method) =>
+                {
+                    extn_methods.push((registration.method.clone(), extn_method));
+                    result.methods.push(registration.method);
+                }
+                _ => result.rejected_methods.push(registration.method),
+            }
+        }
+        state
+            .endpoint_state
+            .add_extn_rules(extn_id, &result.methods);
+        state.extn_method_state.set_methods(extn_id, extn_methods);
+        info!(
+            "Extension {} registered methods={:?} rejected_methods={:?}",
+            extn_id, result.methods, result.rejected_methods
+        );
+        result
+    }
+
+    /// Stops routing the methods of the extension, when it unregisters them or its
+    /// connection is gone
+    pub fn unregister(state: &PlatformState, extn_id: &str) {
+        state.endpoint_state.remove_extn_rules(extn_id);
+        state.extn_method_state.remove_methods(extn_id);
+    }
+}
+
+impl ExtnStreamProcessor for ExtnMethodProcessor {
+    type STATE = PlatformState;
+    type VALUE = ExtnMethodRequest;
+
+    fn get_state(&self) -> Self::STATE {
+        self.state.clone()
+    }
+
+    fn sender(&self) -> Sender<ExtnMessage> {
+        self.streamer.sender()
+    }
+
+    fn receiver(&mut self) -> Receiver<ExtnMessage> {
+        self.streamer.receiver()
+    }
+}
+
+#[async_trait]
+impl ExtnRequestProcessor for ExtnMethodProcessor {
+    fn get_client(&self) -> ripple_sdk::extn::client::extn_client::ExtnClient {
+        self.state.get_client().get_extn_client()
+    }
+
+    async fn process_request(
+        state: Self::STATE,
+        msg: ExtnMessage,
+        extracted_message: Self::VALUE,
+    ) -> bool {
+        let extn_id = msg.requestor.to_string();
+        let result = match extracted_message {
+            ExtnMethodRequest::Register(registrations) => {
+                Self::register(&state, &extn_id, registrations)
+            }
+            ExtnMethodRequest::Unregister => {
+                info!("Extension {} unregistered its methods", extn_id);
+                Self::unregister(&state, &extn_id);
+                ExtnMethodRegistrationResult::default()
+            }
+        };
+        Self::respond(
+            state.get_client().get_extn_client(),
+            msg,
+            ExtnResponse::ExtnMethod(result),
+        )
+        .await
+        .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::manifest::device_manifest::ServiceCapabilityPolicy;
+    use ripple_tdk::utils::test_utils::Mockable;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_register() {
+        let extn_id = "ripple:extn:jsonrpsee:player";
+        let mut state = PlatformState::mock();
+        let mut manifest = state.get_device_manifest();
+        manifest.configuration.service_capabilities = ServiceCapabilityPolicy {
+            enforce: true,
+            services: HashMap::from([(
+                extn_id.to_owned(),
+                vec!["xrn:firebolt:capability:player:base".to_owned()],
+            )]),
+            capability_methods: HashMap::from([(
+                "xrn:firebolt:capability:player:base".to_owned(),
+                vec!["player.*".to_owned()],
+            )]),
+        };
+        state.set_device_manifest(manifest);
+        let registration = |method: &str| ExtnMethodRegistration {
+            method: method.into(),
+            capability: "xrn:firebolt:capability:player:base".into(),
+            schema: None,
+        };
+        state
+            .endpoint_state
+            .add_service_rules("service1", &["player.stop".into()]);
+        let mut module = jsonrpsee::RpcModule::new(());
+        module
+            .register_method("player.pause", |_, _| Ok("paused"))
+            .unwrap();
+        state.router_state.update_methods(module.into()).unwrap();
+
+        // patterns are refused when they would also take a method served elsewhere
+        let result = ExtnMethodProcessor::register(
+            &state,
+            extn_id,
+            vec![
+                registration("Player.play"),
+                registration("player.stop"),
+                registration("player.s*"),
+                registration("player.pa?se"),
+            ],
+        );
+        assert_eq!(result.methods, vec!["Player.play".to_string()]);
+        assert_eq!(
+            result.rejected_methods,
+            vec![
+                "player.stop".to_string(),
+                "player.s*".to_string(),
+                "player.pa?se".to_string()
+            ]
+        );
+        let rule = state
+            .endpoint_state
+            .get_rule_by_method("player.play")
+            .unwrap();
+        assert_eq!(rule.alias, extn_id);
+
+        ExtnMethodProcessor::unregister(&state, extn_id);
+        assert!(state.extn_method_state.get_method("player.play").is_none());
+        assert!(state
+            .endpoint_state
+            .get_rule_by_method("player.play")
+            .is_none());
+    }
+}
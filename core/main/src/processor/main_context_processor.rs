@@ -25,7 +25,7 @@ use ripple_sdk::{
             device_user_grants_data::GrantLifespan,
         },
         firebolt::fb_capabilities::{CapEvent, CapabilityRole, FireboltCap, FireboltPermission},
-        session::AccountSessionRequest,
+        session::{AccountSession, AccountSessionRequest},
     },
     async_trait::async_trait,
     extn::{
@@ -38,7 +38,10 @@ use ripple_sdk::{
     tokio::sync::{mpsc::Receiver as MReceiver, mpsc::Sender as MSender},
 };
 
-use crate::state::{cap::cap_state::CapState, platform_state::PlatformState};
+use crate::state::{
+    cap::cap_state::CapState, content_access_state::ContentAccessState,
+    platform_state::PlatformState,
+};
 
 #[derive(Debug, Clone)]
 pub struct ContextState {
@@ -68,7 +71,7 @@ impl MainContextProcessor {
     ///
     /// Method which gets called on bootstrap for a presence of account session
     ///
-    async fn check_account_session_token(state: &PlatformState) -> bool {
+    pub async fn check_account_session_token(state: &PlatformState) -> bool {
         let mut token_available = false;
         let mut event = CapEvent::OnUnavailable;
 
@@ -77,8 +80,9 @@ impl MainContextProcessor {
             .send_extn_request(AccountSessionRequest::Get)
             .await
         {
-            if let Some(session) = response.payload.extract() {
-                state.session_state.insert_account_session(session);
+            if let Some(session) = response.payload.extract::<AccountSession>() {
+                state.session_state.insert_account_session(session.clone());
+                ContentAccessState::send_to_distributor(state, &session).await;
                 event = CapEvent::OnAvailable;
                 token_available = true;
             }
@@ -176,6 +180,17 @@ impl ExtnEventProcessor for MainContextProcessor {
             "[REFRESH TOKEN] received context event: {:?}",
             extracted_message
         );
+        {
+            // events for older versions can still be queued after a reactivation
+            let current_context = state.current_context.read().unwrap();
+            if current_context.version > 0 && extracted_message.version <= current_context.version {
+                debug!(
+                    "Ignoring stale context version={} current={}",
+                    extracted_message.version, current_context.version
+                );
+                return None;
+            }
+        }
         if let Some(update) = &extracted_message.update_type {
             match update {
                 RippleContextUpdateType::TokenChanged => {
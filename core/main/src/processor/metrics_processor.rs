@@ -16,7 +16,11 @@
 //
 
 use ripple_sdk::{
-    api::firebolt::fb_telemetry::OperationalMetricRequest,
+    api::{
+        firebolt::fb_telemetry::OperationalMetricRequest,
+        gateway::rpc_gateway_api::RpcRequest,
+        manifest::{app_library::AppLibrary, device_manifest::MetricsEnrichmentSource},
+    },
     async_trait::async_trait,
     extn::{
         client::extn_processor::{
@@ -26,8 +30,9 @@ use ripple_sdk::{
     },
     tokio::sync::mpsc::{Receiver as MReceiver, Sender as MSender},
 };
+use serde_json::Value;
 
-use crate::state::platform_state::PlatformState;
+use crate::{service::telemetry_builder::TelemetryBuilder, state::platform_state::PlatformState};
 /// Supports processing of Metrics request from extensions and forwards the metrics accordingly.
 
 /// Supports processing of Metrics request from extensions and forwards the metrics accordingly.
@@ -75,9 +80,13 @@ impl ExtnRequestProcessor for OpMetricsProcessor {
     ) -> bool {
         let requestor = msg.requestor.to_string();
         match extracted_message {
-            OperationalMetricRequest::Subscribe => state
-                .metrics
-                .operational_telemetry_listener(&requestor, true),
+            OperationalMetricRequest::Subscribe => {
+                state
+                    .metrics
+                    .operational_telemetry_listener(&requestor, true);
+                TelemetryBuilder::send_boot_milestones_to(&state, &requestor);
+                TelemetryBuilder::send_store_repair_to(&state, &requestor);
+            }
             OperationalMetricRequest::UnSubscribe => state
                 .metrics
                 .operational_telemetry_listener(&requestor, false),
@@ -87,3 +96,105 @@ impl ExtnRequestProcessor for OpMetricsProcessor {
             .is_ok()
     }
 }
+
+/// Adds server side context to the metrics events reported by apps, as configured in the
+/// `metrics_enrichment` section of the device manifest.
+pub struct MetricsEnrichment;
+
+impl MetricsEnrichment {
+    fn get_value(
+        state: &PlatformState,
+        app_id: &str,
+        source: MetricsEnrichmentSource,
+    ) -> Option<String> {
+        match source {
+            MetricsEnrichmentSource::AppId => Some(app_id.to_owned()),
+            MetricsEnrichmentSource::AppVersion => {
+                AppLibrary::get_manifest(&state.get_app_library_state(), app_id)
+                    .and_then(|manifest| manifest.version)
+            }
+            MetricsEnrichmentSource::AppSessionId => state
+                .app_manager_state
+                .get(app_id)
+                .map(|app| app.session_id),
+            MetricsEnrichmentSource::RippleSessionId => Some(state.metrics.get_device_session_id()),
+            MetricsEnrichmentSource::RippleVersion => state.version.clone(),
+            MetricsEnrichmentSource::DeviceClass => {
+                Some(state.get_device_configuration().form_factor.clone())
+            }
+        }
+    }
+
+    /// Sets the configured fields on the params of an app metrics event, values reported by
+    /// the app are replaced. Returns true when the request was enriched.
+    pub fn enrich(state: &PlatformState, request: &mut RpcRequest) -> bool {
+        let config = &state.get_device_configuration().metrics_enrichment;
+        if !config.is_enriched(&request.method) {
+            return false;
+        }
+        let mut params = match request.get_params() {
+            Some(Value::Object(params)) => params,
+            None => Default::default(),
+            Some(_) => return false,
+        };
+        for (field, source) in config.fields.iter() {
+            if let Some(value) = Self::get_value(state, &request.ctx.app_id, *source) {
+                params.insert(field.clone(), Value::String(value));
+            }
+        }
+        *request = request.clone().with_params(Some(Value::Object(params)));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        service::extn::ripple_client::RippleClient, state::bootstrap_state::ChannelsState,
+    };
+    use ripple_sdk::api::{
+        gateway::rpc_gateway_api::CallContext,
+        manifest::{device_manifest::DeviceManifest, extn_manifest::ExtnManifest},
+    };
+    use ripple_tdk::utils::test_utils::Mockable;
+    use serde_json::json;
+
+    #[test]
+    fn test_enrich() {
+        let mut manifest = DeviceManifest::default();
+        manifest.configuration.form_factor = "ipstb".into();
+        manifest.configuration.metrics_enrichment.enabled = true;
+        manifest.configuration.metrics_enrichment.fields = [
+            ("appId".to_owned(), MetricsEnrichmentSource::AppId),
+            (
+                "deviceClass".to_owned(),
+                MetricsEnrichmentSource::DeviceClass,
+            ),
+            ("appVersion".to_owned(), MetricsEnrichmentSource::AppVersion),
+        ]
+        .into();
+        let state = PlatformState::new(
+            ExtnManifest::default(),
+            manifest,
+            RippleClient::new(ChannelsState::default()),
+            Vec::new(),
+            None,
+        );
+
+        let ctx = CallContext::mock();
+        let mut request = RpcRequest::new("metrics.page".into(), "".into(), ctx.clone())
+            .with_params(Some(json!({"pageId": "home", "appId": "spoofed"})));
+        assert!(MetricsEnrichment::enrich(&state, &mut request));
+        let params = request.get_params().unwrap();
+        assert_eq!(params["pageId"], "home");
+        assert_eq!(params["appId"], json!(ctx.app_id));
+        assert_eq!(params["deviceClass"], "ipstb");
+        // app is not in the library
+        assert!(params.get("appVersion").is_none());
+
+        let mut request =
+            RpcRequest::new("device.name".into(), "".into(), ctx).with_params(Some(json!({})));
+        assert!(!MetricsEnrichment::enrich(&state, &mut request));
+    }
+}
@@ -18,13 +18,18 @@
 pub mod app_events_processor;
 pub mod authorized_info_processor;
 pub mod config_processor;
+pub mod entitlements_sync_processor;
+pub mod extn_method_processor;
 pub mod keyboard_processor;
 pub mod lifecycle_management_processor;
 pub mod main_context_processor;
 pub mod metrics_processor;
 pub mod pin_processor;
+pub mod remote_key_processor;
 pub mod rpc_gateway_processor;
 pub mod settings_processor;
 pub mod storage;
 pub mod store_privacy_settings_processor;
 pub mod store_user_grants_processor;
+pub mod update_status_processor;
+pub mod user_activity_processor;
@@ -0,0 +1,114 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::{
+    api::{
+        device::device_input::{KeyEvent, RawKeyEvent, INPUT_KEYS_CAPABILITY, INPUT_KEY_EVENT},
+        firebolt::fb_capabilities::{CapabilityRole, FireboltCap, RoleInfo},
+    },
+    async_trait::async_trait,
+    extn::{
+        client::extn_processor::{
+            DefaultExtnStreamer, ExtnEventProcessor, ExtnStreamProcessor, ExtnStreamer,
+        },
+        extn_client_message::ExtnMessage,
+    },
+    log::trace,
+    tokio::sync::mpsc::{Receiver as MReceiver, Sender as MSender},
+};
+use serde_json::json;
+
+use crate::{
+    firebolt::handlers::capabilities_rpc::is_permitted,
+    service::{apps::app_events::AppEvents, inactivity_monitor::InactivityMonitor},
+    state::platform_state::PlatformState,
+};
+
+/// Translates the raw remote keys from the device extension using the key map in the
+/// device manifest and forwards them to the apps listening on `input.onKey`.
+#[derive(Debug)]
+pub struct RemoteKeyProcessor {
+    state: PlatformState,
+    streamer: DefaultExtnStreamer,
+}
+
+impl RemoteKeyProcessor {
+    pub fn new(state: PlatformState) -> RemoteKeyProcessor {
+        RemoteKeyProcessor {
+            state,
+            streamer: DefaultExtnStreamer::new(),
+        }
+    }
+
+    /// Restricted keys are only forwarded to listeners with the manage role
+    async fn emit_restricted(state: &PlatformState, key_event: &KeyEvent) {
+        let role_info = RoleInfo {
+            role: Some(CapabilityRole::Manage),
+            capability: FireboltCap::short(INPUT_KEYS_CAPABILITY),
+        };
+        let value = json!(key_event);
+        for listener in AppEvents::get_listeners(&state.app_events_state, INPUT_KEY_EVENT, None) {
+            if let Ok(true) = is_permitted(state, &listener.call_ctx, &role_info).await {
+                AppEvents::send_event(&listener, &value).await;
+            }
+        }
+    }
+}
+
+impl ExtnStreamProcessor for RemoteKeyProcessor {
+    type VALUE = RawKeyEvent;
+    type STATE = PlatformState;
+
+    fn get_state(&self) -> Self::STATE {
+        self.state.clone()
+    }
+
+    fn sender(&self) -> MSender<ExtnMessage> {
+        self.streamer.sender()
+    }
+
+    fn receiver(&mut self) -> MReceiver<ExtnMessage> {
+        self.streamer.receiver()
+    }
+}
+
+#[async_trait]
+impl ExtnEventProcessor for RemoteKeyProcessor {
+    async fn process_event(
+        state: Self::STATE,
+        _msg: ExtnMessage,
+        extracted_message: Self::VALUE,
+    ) -> Option<bool> {
+        InactivityMonitor::on_activity(&state).await;
+        let config = &state.get_device_configuration().input;
+        let key = match config.get_key_name(&extracted_message.code) {
+            Some(key) => key,
+            None => {
+                trace!("Dropping unmapped key {}", extracted_message.code);
+                return None;
+            }
+        };
+        let restricted = config.is_restricted(&key);
+        let key_event = KeyEvent::new(key, extracted_message);
+        if restricted {
+            Self::emit_restricted(&state, &key_event).await;
+        } else {
+            AppEvents::emit(&state, INPUT_KEY_EVENT, &json!(key_event)).await;
+        }
+        None
+    }
+}
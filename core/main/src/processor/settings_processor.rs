@@ -29,6 +29,7 @@ use ripple_sdk::{
         settings::{SettingKey, SettingValue, SettingsRequest, SettingsRequestParam},
         storage_property::{
             EVENT_ALLOW_PERSONALIZATION_CHANGED, EVENT_ALLOW_WATCH_HISTORY_CHANGED,
+            EVENT_HIGH_CONTRAST_SETTINGS_CHANGED, EVENT_MAGNIFICATION_SETTINGS_CHANGED,
             EVENT_SHARE_WATCH_HISTORY,
         },
     },
@@ -48,8 +49,9 @@ use serde_json::{json, Value};
 use crate::{
     broker::broker_utils::{self, BrokerUtils},
     firebolt::handlers::{
-        capabilities_rpc::is_permitted, closed_captions_rpc::ClosedcaptionsImpl,
-        discovery_rpc::DiscoveryImpl, privacy_rpc::PrivacyImpl,
+        accessibility_rpc::AccessibilityImpl, capabilities_rpc::is_permitted,
+        closed_captions_rpc::ClosedcaptionsImpl, discovery_rpc::DiscoveryImpl,
+        privacy_rpc::PrivacyImpl,
     },
     service::apps::app_events::{AppEventDecorationError, AppEventDecorator, AppEvents},
     state::platform_state::PlatformState,
@@ -161,6 +163,19 @@ impl SettingsProcessor {
                     }
                     SettingKey::PowerSaving => Some(SettingValue::bool(true)),
                     SettingKey::LegacyMiniGuide => Some(SettingValue::bool(false)),
+                    SettingKey::HighContrast => Some(SettingValue::bool(
+                        AccessibilityImpl::get_high_contrast_settings(state)
+                            .await
+                            .enabled,
+                    )),
+                    SettingKey::Magnification => {
+                        let magnification =
+                            AccessibilityImpl::get_magnification_settings(state).await;
+                        Some(SettingValue {
+                            value: Some(magnification.scale.to_string()),
+                            enabled: Some(magnification.enabled),
+                        })
+                    }
                 };
 
                 if let Some(v) = val {
@@ -304,6 +319,26 @@ impl SettingsProcessor {
                     .await
                     .is_ok();
                 }
+                SettingKey::HighContrast => {
+                    if !Self::subscribe_event(
+                        state,
+                        ctx.clone(),
+                        EVENT_HIGH_CONTRAST_SETTINGS_CHANGED,
+                        request.clone(),
+                    ) {
+                        resp = false;
+                    }
+                }
+                SettingKey::Magnification => {
+                    if !Self::subscribe_event(
+                        state,
+                        ctx.clone(),
+                        EVENT_MAGNIFICATION_SETTINGS_CHANGED,
+                        request.clone(),
+                    ) {
+                        resp = false;
+                    }
+                }
                 SettingKey::PowerSaving | SettingKey::LegacyMiniGuide => {
                     warn!("{} Not implemented", key.to_string());
                 }
@@ -0,0 +1,144 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::{
+    api::device::device_update::{
+        RebootPending, UpdateStatus, REBOOT_PENDING_EVENT, UPDATE_STATUS_CHANGED_EVENT,
+    },
+    async_trait::async_trait,
+    extn::{
+        client::extn_processor::{
+            DefaultExtnStreamer, ExtnEventProcessor, ExtnStreamProcessor, ExtnStreamer,
+        },
+        extn_client_message::ExtnMessage,
+    },
+    log::{debug, info},
+    tokio::sync::mpsc::{Receiver as MReceiver, Sender as MSender},
+};
+use serde_json::json;
+
+use crate::{service::apps::app_events::AppEvents, state::platform_state::PlatformState};
+
+/// Receives the status events of the platform updater. Apps are notified through
+/// `device.onUpdateStatusChanged` and get a `device.onRebootPending` warning once the
+/// updater schedules a reboot.
+#[derive(Debug)]
+pub struct UpdateStatusProcessor {
+    state: PlatformState,
+    streamer: DefaultExtnStreamer,
+}
+
+impl UpdateStatusProcessor {
+    pub fn new(state: PlatformState) -> UpdateStatusProcessor {
+        UpdateStatusProcessor {
+            state,
+            streamer: DefaultExtnStreamer::new(),
+        }
+    }
+
+    /// The reboot warning is sent once for every scheduled reboot
+    pub fn get_reboot_warning(
+        previous: Option<&UpdateStatus>,
+        status: &UpdateStatus,
+    ) -> Option<RebootPending> {
+        if !status.is_reboot_pending() || matches!(previous, Some(p) if p.is_reboot_pending()) {
+            return None;
+        }
+        Some(RebootPending {
+            reason: "update".to_owned(),
+            reboot_in_ms: status.reboot_in_ms.unwrap_or_default(),
+            version: status.version.clone(),
+        })
+    }
+}
+
+impl ExtnStreamProcessor for UpdateStatusProcessor {
+    type VALUE = UpdateStatus;
+    type STATE = PlatformState;
+
+    fn get_state(&self) -> Self::STATE {
+        self.state.clone()
+    }
+
+    fn sender(&self) -> MSender<ExtnMessage> {
+        self.streamer.sender()
+    }
+
+    fn receiver(&mut self) -> MReceiver<ExtnMessage> {
+        self.streamer.receiver()
+    }
+}
+
+#[async_trait]
+impl ExtnEventProcessor for UpdateStatusProcessor {
+    async fn process_event(
+        state: Self::STATE,
+        _msg: ExtnMessage,
+        extracted_message: Self::VALUE,
+    ) -> Option<bool> {
+        let previous = match state.update_status_state.update(extracted_message.clone()) {
+            Some(previous) => previous,
+            None => return None,
+        };
+        debug!("Update status changed {:?}", extracted_message);
+        if let Some(warning) = Self::get_reboot_warning(previous.as_ref(), &extracted_message) {
+            info!(
+                "Warning apps of an update reboot in {}ms",
+                warning.reboot_in_ms
+            );
+            AppEvents::emit(&state, REBOOT_PENDING_EVENT, &json!(warning)).await;
+        }
+        AppEvents::emit(
+            &state,
+            UPDATE_STATUS_CHANGED_EVENT,
+            &json!(extracted_message),
+        )
+        .await;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::device::device_update::SystemUpdateState;
+
+    #[test]
+    fn test_get_reboot_warning() {
+        let downloading = UpdateStatus {
+            state: SystemUpdateState::Downloading,
+            ..Default::default()
+        };
+        let ready = UpdateStatus {
+            state: SystemUpdateState::ReadyToInstall,
+            version: Some("1.2.0".into()),
+            reboot_in_ms: Some(30000),
+            ..Default::default()
+        };
+        assert!(UpdateStatusProcessor::get_reboot_warning(None, &downloading).is_none());
+        let warning =
+            UpdateStatusProcessor::get_reboot_warning(Some(&downloading), &ready).unwrap();
+        assert_eq!(warning.reboot_in_ms, 30000);
+
+        // no repeated warning while installing
+        let installing = UpdateStatus {
+            state: SystemUpdateState::Installing,
+            ..ready.clone()
+        };
+        assert!(UpdateStatusProcessor::get_reboot_warning(Some(&ready), &installing).is_none());
+    }
+}
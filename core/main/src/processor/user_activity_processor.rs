@@ -0,0 +1,75 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::{
+    api::device::device_events::UserActivity,
+    async_trait::async_trait,
+    extn::{
+        client::extn_processor::{
+            DefaultExtnStreamer, ExtnEventProcessor, ExtnStreamProcessor, ExtnStreamer,
+        },
+        extn_client_message::ExtnMessage,
+    },
+    tokio::sync::mpsc::{Receiver as MReceiver, Sender as MSender},
+};
+
+use crate::{service::inactivity_monitor::InactivityMonitor, state::platform_state::PlatformState};
+
+/// Receives the user input reported by the device extension for idle detection
+#[derive(Debug)]
+pub struct UserActivityProcessor {
+    state: PlatformState,
+    streamer: DefaultExtnStreamer,
+}
+
+impl UserActivityProcessor {
+    pub fn new(state: PlatformState) -> UserActivityProcessor {
+        UserActivityProcessor {
+            state,
+            streamer: DefaultExtnStreamer::new(),
+        }
+    }
+}
+
+impl ExtnStreamProcessor for UserActivityProcessor {
+    type VALUE = UserActivity;
+    type STATE = PlatformState;
+
+    fn get_state(&self) -> Self::STATE {
+        self.state.clone()
+    }
+
+    fn sender(&self) -> MSender<ExtnMessage> {
+        self.streamer.sender()
+    }
+
+    fn receiver(&mut self) -> MReceiver<ExtnMessage> {
+        self.streamer.receiver()
+    }
+}
+
+#[async_trait]
+impl ExtnEventProcessor for UserActivityProcessor {
+    async fn process_event(
+        state: Self::STATE,
+        _msg: ExtnMessage,
+        _extracted_message: Self::VALUE,
+    ) -> Option<bool> {
+        InactivityMonitor::on_activity(&state).await;
+        None
+    }
+}
@@ -0,0 +1,307 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{fmt::Write, fs, io::ErrorKind, path::Path, time::Duration};
+
+use ripple_sdk::{
+    api::{
+        context::RippleContextUpdateRequest,
+        distributor::distributor_activation::{
+            ActivationProgress, ActivationRequest, ActivationStep, ACTIVATION_EVENT_ON_PROGRESS,
+        },
+        manifest::device_manifest::ActivationConfiguration,
+        session::ProvisionRequest,
+    },
+    framework::{file_store::FileStore, RippleResponse},
+    log::{error, info, warn},
+    tokio,
+    utils::error::RippleError,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    processor::{
+        entitlements_sync_processor::EntitlementsSyncProcessor,
+        main_context_processor::MainContextProcessor,
+    },
+    service::apps::app_events::AppEvents,
+    state::platform_state::PlatformState,
+};
+
+/// Checkpoint saved after every completed step so activation resumes from the first step
+/// which did not complete after a reboot. The provisioned ids are only kept as a hash, they
+/// are fetched again from the distributor when a later step needs them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivationCheckpoint {
+    pub step: ActivationStep,
+    #[serde(default)]
+    pub provision_hash: Option<String>,
+}
+
+/// Drives the device from unactivated through provisioning and token acquisition to
+/// entitled using the distributor extension. Every step is retried with a backoff and the
+/// UI app is informed through `device.onActivationProgress`.
+pub struct ActivationOrchestrator;
+
+impl ActivationOrchestrator {
+    fn get_checkpoint_path(saved_dir: &str) -> String {
+        let dir_path = Path::new(saved_dir).join("activation");
+        dir_path.into_os_string().into_string().unwrap()
+    }
+
+    pub fn start(state: PlatformState) {
+        let configuration = state.get_device_configuration();
+        let config = configuration.activation.clone();
+        if !config.enabled {
+            return;
+        }
+        let path = Self::get_checkpoint_path(&configuration.saved_dir);
+        let mut store = FileStore::load(path.clone())
+            .unwrap_or_else(|_| FileStore::new(path, ActivationCheckpoint::default()));
+        info!("Starting activation from step={:?}", store.value.step);
+        let state_c = state.clone();
+        let handle = tokio::spawn(async move {
+            Self::run(&state_c, &config, &mut store).await;
+        });
+        state.activation_state.set_task(handle);
+    }
+
+    /// Forgets the checkpoint and the progress and starts activation again from the beginning
+    pub fn reset(state: PlatformState) {
+        state.activation_state.abort_task();
+        let path = Self::get_checkpoint_path(&state.get_device_configuration().saved_dir);
+        if let Err(e) = fs::remove_file(&path) {
+            if e.kind() != ErrorKind::NotFound {
+                error!("Unable to remove activation checkpoint {}: {:?}", path, e);
+            }
+        }
+        state
+            .activation_state
+            .set_progress(ActivationProgress::default());
+        info!("Activation reset");
+        Self::start(state);
+    }
+
+    async fn run(
+        state: &PlatformState,
+        config: &ActivationConfiguration,
+        store: &mut FileStore<ActivationCheckpoint>,
+    ) {
+        let mut provision = None;
+        loop {
+            let step = store.value.step;
+            if step.is_complete() {
+                Self::report(
+                    state,
+                    ActivationProgress {
+                        step,
+                        ..Default::default()
+                    },
+                )
+                .await;
+                state
+                    .get_client()
+                    .get_extn_client()
+                    .context_update(RippleContextUpdateRequest::Activation(true));
+                return;
+            }
+            let mut attempt = 0;
+            loop {
+                attempt += 1;
+                Self::report(
+                    state,
+                    ActivationProgress {
+                        step,
+                        attempt,
+                        ..Default::default()
+                    },
+                )
+                .await;
+                match Self::execute(state, step, &mut store.value, &mut provision).await {
+                    Ok(()) => break,
+                    Err(e) if attempt > config.max_retries => {
+                        error!("Activation failed at step={:?}: {:?}", step, e);
+                        Self::report(
+                            state,
+                            ActivationProgress {
+                                step,
+                                attempt,
+                                error: Some(e.to_string()),
+                                failed: true,
+                            },
+                        )
+                        .await;
+                        return;
+                    }
+                    Err(e) => {
+                        let backoff = Self::get_backoff(config, attempt);
+                        warn!(
+                            "Activation step={:?} attempt={} failed: {:?}, retrying in {:?}",
+                            step, attempt, e, backoff
+                        );
+                        tokio::time::sleep(backoff).await;
+                    }
+                }
+            }
+            store.value.step = step.next();
+            store.sync();
+        }
+    }
+
+    async fn execute(
+        state: &PlatformState,
+        step: ActivationStep,
+        checkpoint: &mut ActivationCheckpoint,
+        provision: &mut Option<ProvisionRequest>,
+    ) -> RippleResponse {
+        match step {
+            ActivationStep::Unactivated | ActivationStep::Entitled => Ok(()),
+            ActivationStep::Provisioning => {
+                *provision = Some(Self::provision(state, checkpoint).await?);
+                Ok(())
+            }
+            ActivationStep::TokenAcquisition => {
+                if !MainContextProcessor::check_account_session_token(state).await {
+                    return Err(RippleError::NotAvailable);
+                }
+                // resumed after a reboot, the ids of the provisioning step are not kept
+                if provision.is_none() {
+                    *provision = Some(Self::provision(state, checkpoint).await?);
+                }
+                if let Some(provision) = provision.clone() {
+                    state.session_state.update_account_session(provision);
+                }
+                EntitlementsSyncProcessor::sync(state, None).await
+            }
+        }
+    }
+
+    async fn provision(
+        state: &PlatformState,
+        checkpoint: &mut ActivationCheckpoint,
+    ) -> Result<ProvisionRequest, RippleError> {
+        let response = state
+            .get_client()
+            .send_extn_request(ActivationRequest::Provision)
+            .await?;
+        let response = response
+            .payload
+            .extract::<ProvisionRequest>()
+            .ok_or(RippleError::InvalidOutput)?;
+        let hash = Self::get_provision_hash(&response);
+        if checkpoint
+            .provision_hash
+            .as_ref()
+            .is_some_and(|previous| previous.ne(&hash))
+        {
+            warn!("Device was provisioned to a different account since the last boot");
+        }
+        checkpoint.provision_hash = Some(hash);
+        Ok(response)
+    }
+
+    async fn report(state: &PlatformState, progress: ActivationProgress) {
+        state.activation_state.set_progress(progress.clone());
+        AppEvents::emit(state, ACTIVATION_EVENT_ON_PROGRESS, &json!(progress)).await;
+    }
+
+    fn get_provision_hash(provision: &ProvisionRequest) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(provision.account_id.as_bytes());
+        hasher.update([0]);
+        hasher.update(provision.device_id.as_bytes());
+        hasher.update([0]);
+        if let Some(distributor_id) = &provision.distributor_id {
+            hasher.update(distributor_id.as_bytes());
+        }
+        hasher.finalize().iter().fold(String::new(), |mut hash, b| {
+            let _ = write!(hash, "{:02x}", b);
+            hash
+        })
+    }
+
+    pub fn get_backoff(config: &ActivationConfiguration, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(
+            config
+                .initial_backoff_ms
+                .saturating_mul(factor)
+                .min(config.max_backoff_ms),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_resume() {
+        // a checkpoint saved mid flow resumes from the step which did not complete
+        let checkpoint: ActivationCheckpoint =
+            serde_json::from_value(json!({ "step": "tokenAcquisition" })).unwrap();
+        assert_eq!(checkpoint.step, ActivationStep::TokenAcquisition);
+        assert!(checkpoint.provision_hash.is_none());
+    }
+
+    #[test]
+    fn test_provision_hash() {
+        let provision = ProvisionRequest {
+            account_id: "account".to_owned(),
+            device_id: "device".to_owned(),
+            distributor_id: None,
+        };
+        let hash = ActivationOrchestrator::get_provision_hash(&provision);
+        assert_eq!(hash.len(), 64);
+        assert!(!hash.contains("account") && !hash.contains("device"));
+        assert_eq!(hash, ActivationOrchestrator::get_provision_hash(&provision));
+        let other = ProvisionRequest {
+            account_id: "accountd".to_owned(),
+            device_id: "evice".to_owned(),
+            distributor_id: None,
+        };
+        assert_ne!(hash, ActivationOrchestrator::get_provision_hash(&other));
+    }
+
+    #[test]
+    fn test_get_backoff() {
+        let config = ActivationConfiguration {
+            enabled: true,
+            max_retries: 10,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 3000,
+        };
+        assert_eq!(
+            ActivationOrchestrator::get_backoff(&config, 1),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            ActivationOrchestrator::get_backoff(&config, 3),
+            Duration::from_millis(2000)
+        );
+        assert_eq!(
+            ActivationOrchestrator::get_backoff(&config, 4),
+            Duration::from_millis(3000)
+        );
+        assert_eq!(
+            ActivationOrchestrator::get_backoff(&config, 64),
+            Duration::from_millis(3000)
+        );
+    }
+}
@@ -0,0 +1,585 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::serde_json::{json, Value};
+use serde::{Deserialize, Serialize};
+
+/// Version of OpenRPC the admin schema is written in
+pub const ADMIN_OPENRPC_VERSION: &str = "1.2.4";
+
+/// OpenRPC content descriptor of a param or a result
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContentDescriptor {
+    pub name: String,
+    #[serde(default)]
+    pub required: bool,
+    pub schema: Value,
+}
+
+impl ContentDescriptor {
+    fn new(name: &str, required: bool, schema: Value) -> ContentDescriptor {
+        ContentDescriptor {
+            name: name.to_owned(),
+            required,
+            schema,
+        }
+    }
+
+    fn result(schema: Value) -> ContentDescriptor {
+        ContentDescriptor::new("result", true, schema)
+    }
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn integer() -> Value {
+    json!({ "type": "integer" })
+}
+
+fn boolean() -> Value {
+    json!({ "type": "boolean" })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn map(values: Value) -> Value {
+    json!({ "type": "object", "additionalProperties": values })
+}
+
+fn null() -> Value {
+    json!({ "type": "null" })
+}
+
+/// Object with every property required but the ones listed as optional
+fn object(properties: Value, optional: &[&str]) -> Value {
+    let required: Vec<&String> = properties
+        .as_object()
+        .map(|p| {
+            p.keys()
+                .filter(|k| !optional.contains(&k.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    json!({ "type": "object", "properties": properties, "required": required })
+}
+
+/// Params and result of an admin method, kept by hand next to the handlers. Params are by
+/// name like the Firebolt methods, so they are the fields of the request type of the handler.
+/// The tests check the entries against the registered admin methods and the handler types.
+pub fn describe_method(method: &str) -> Option<(Vec<ContentDescriptor>, ContentDescriptor)> {
+    let p = ContentDescriptor::new;
+    let (params, result) = match method {
+        "ripple.getAdminRole" => (vec![], schema_ref("AdminRole")),
+        "ripple.uploadDiagnostics" => (vec![p("uploadUrl", true, string())], integer()),
+        "ripple.setContextOverride" => (
+            vec![
+                p("appId", true, string()),
+                p("language", false, string()),
+                p("locale", false, string()),
+                p("countryCode", false, string()),
+                p("timeZone", false, string()),
+                p("deviceModel", false, string()),
+            ],
+            null(),
+        ),
+        "ripple.clearContextOverride" => (vec![p("appId", true, string())], null()),
+        "ripple.getCapabilityUsage" => (
+            vec![p("appId", false, string())],
+            map(array(schema_ref("CapabilityUsage"))),
+        ),
+        "ripple.getExtnUsage" => (vec![], array(schema_ref("ExtnUsage"))),
+        "ripple.getComplianceReport" => (vec![], schema_ref("ComplianceReport")),
+        "ripple.setRuleExplain" => (
+            vec![p("appId", true, string()), p("enabled", true, boolean())],
+            null(),
+        ),
+        "ripple.shutdown" => (vec![p("reason", false, string())], null()),
+        "ripple.getJqSandbox" => (vec![], schema_ref("JqSandboxStatus")),
+        "ripple.getRuleProvenance" => (vec![], map(schema_ref("RuleProvenance"))),
+        "ripple.setJqSandbox" => (
+            vec![
+                p("enabled", false, boolean()),
+                p("rule", false, string()),
+                p("limits", false, schema_ref("JqLimits")),
+            ],
+            null(),
+        ),
+        "ripple.releaseJqRule" => (vec![p("rule", true, string())], boolean()),
+        "ripple.injectEvent" => (
+            vec![
+                p("event", true, string()),
+                p("result", true, json!({})),
+                p("context", false, json!({})),
+                p("appIds", false, array(string())),
+                p("sessionIds", false, array(string())),
+            ],
+            integer(),
+        ),
+        "ripple.setSyntheticProvider" => (
+            vec![
+                p("capability", true, string()),
+                p("response", false, json!({})),
+            ],
+            null(),
+        ),
+        "ripple.dumpState" => (vec![p("path", false, string())], string()),
+        "ripple.loadState" => (vec![p("path", false, string())], null()),
+        "ripple.getErrorBudgets" => (vec![], array(schema_ref("AppErrorStats"))),
+        "ripple.getTenantStats" => (vec![], array(schema_ref("TenantStats"))),
+        "ripple.getRegion" => (vec![], schema_ref("RegionInfo")),
+        "ripple.setRegionSources" => (
+            vec![p("sources", true, array(schema_ref("RegionSourceType")))],
+            schema_ref("RegionInfo"),
+        ),
+        "ripple.getAdminSchema" => (vec![], schema_ref("AdminSchema")),
+        "ripple.info" => (vec![], schema_ref("RippleInfo")),
+        "ripple.softRestart" => (
+            vec![
+                p("reason", false, string()),
+                p("closeAppConnections", false, boolean()),
+            ],
+            schema_ref("SoftRestartResult"),
+        ),
+        "ripple.getRoutingTable" => (
+            vec![p("method", false, string())],
+            array(schema_ref("RouteEntry")),
+        ),
+        "ripple.resetActivation" => (vec![], null()),
+        _ => return None,
+    };
+    Some((params, ContentDescriptor::result(result)))
+}
+
+/// Schemas referenced by the admin methods
+pub fn get_components() -> Value {
+    let jq_limits = object(
+        json!({
+            "max_runtime_ms": integer(),
+            "max_output_bytes": integer(),
+            "max_nesting": integer(),
+            "max_filter_depth": integer(),
+        }),
+        &[],
+    );
+    let region_value = object(
+        json!({ "value": string(), "source": schema_ref("RegionSourceType") }),
+        &[],
+    );
+    json!({
+        "schemas": {
+            "AdminRole": { "type": "string", "enum": ["readOnly", "operator", "developer"] },
+            "CapabilityUsage": object(json!({
+                "capability": string(),
+                "role": { "type": "string", "enum": ["use", "manage", "provide"] },
+                "count": integer(),
+                "lastUsed": integer(),
+            }), &[]),
+            "ExtnUsage": object(json!({
+                "extnId": string(),
+                "requestsSent": integer(),
+                "messagesReceived": integer(),
+                "responses": integer(),
+                "expiredRequests": integer(),
+                "pendingRequests": integer(),
+                "avgLatencyMs": integer(),
+                "maxLatencyMs": integer(),
+            }), &[]),
+            "ComplianceReport": object(json!({
+                "profile": { "enum": ["gdpr", "ccpa", null] },
+                "deviations": array(object(json!({
+                    "field": string(),
+                    "preset": {},
+                    "configured": {},
+                }), &[])),
+            }), &[]),
+            "JqLimits": jq_limits,
+            "JqSandboxStatus": object(json!({
+                "config": object(json!({
+                    "enabled": boolean(),
+                    "limits": schema_ref("JqLimits"),
+                    "rule_limits": map(schema_ref("JqLimits")),
+                    "quarantine_threshold": integer(),
+                    "max_workers": integer(),
+                }), &[]),
+                "quarantined": array(string()),
+            }), &[]),
+            "RuleProvenance": object(json!({
+                "source": {
+                    "type": "string",
+                    "enum": ["builtin", "manifest", "service", "extn", "admin", "snapshot"],
+                },
+                "path": string(),
+                "serviceId": string(),
+                "extnId": string(),
+            }), &["path", "serviceId", "extnId"]),
+            "AppErrorStats": object(json!({
+                "appId": string(),
+                "requestCount": integer(),
+                "clientErrorCount": integer(),
+                "serverErrorCount": integer(),
+                "throttledUntil": integer(),
+            }), &["throttledUntil"]),
+            "TenantStats": object(json!({
+                "tenantId": string(),
+                "requestCount": integer(),
+                "errorCount": integer(),
+                "rejectedRegistrations": integer(),
+                "deniedMethods": integer(),
+            }), &[]),
+            "RegionSourceType": { "type": "string", "enum": ["platform", "manifest", "remote"] },
+            "RegionInfo": object(json!({
+                "sources": array(schema_ref("RegionSourceType")),
+                "values": {
+                    "type": "object",
+                    "properties": {
+                        "countryCode": region_value,
+                        "locale": region_value,
+                        "timeZone": region_value,
+                    },
+                    "additionalProperties": false,
+                },
+            }), &[]),
+            "AdminSchema": object(json!({
+                "openrpc": string(),
+                "info": object(json!({ "title": string(), "version": string() }), &[]),
+                "methods": array(json!({ "type": "object" })),
+                "components": { "type": "object" },
+            }), &[]),
+            "RippleInfo": object(json!({
+                "build": object(json!({
+                    "version": string(),
+                    "gitHash": string(),
+                    "buildTimestamp": string(),
+                    "target": string(),
+                    "profile": string(),
+                    "features": array(string()),
+                }), &[]),
+                "manifestHashes": map(string()),
+                "deviceManifest": { "type": "object" },
+                "extnManifest": { "type": "object" },
+            }), &[]),
+            "SoftRestartResult": object(json!({
+                "methods": integer(),
+                "rules": integer(),
+                "closedConnections": integer(),
+            }), &[]),
+            "RouteTarget": {
+                "type": "string",
+                "enum": ["handler", "broker", "extension", "service"],
+            },
+            "RouteEntry": object(json!({
+                "method": string(),
+                "target": schema_ref("RouteTarget"),
+                "servedBy": string(),
+                "endpoint": string(),
+                "shadowed": array(schema_ref("RouteTarget")),
+            }), &["endpoint", "shadowed"]),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        broker::{
+            rule_explain::SetRuleExplainParams,
+            rules::{
+                jq_sandbox::{JqSandboxStatus, ReleaseJqRuleParams, SetJqSandboxParams},
+                rules_engine::RuleProvenance,
+            },
+        },
+        firebolt::{
+            handlers::{admin_rpc::AdminRPCProvider, diagnostics_rpc::DiagnosticsRPCProvider},
+            rpc::RippleRPCProvider,
+            rpc_router::{RouteEntry, RoutingTableParams},
+        },
+        service::{
+            apps::{app_events::InjectEventParams, provider_broker::SetSyntheticProviderParams},
+            graceful_shutdown::ShutdownParams,
+            soft_restart::{SoftRestartParams, SoftRestartResult},
+            state_snapshot::StateSnapshotParams,
+        },
+        state::{
+            admin_state::ADMIN_METHOD_ROLES, platform_state::PlatformState,
+            tenant_state::TenantStats,
+        },
+    };
+    use jsonschema::JSONSchema;
+    use ripple_sdk::serde_json::Map;
+    use ripple_sdk::{
+        api::{
+            firebolt::{
+                fb_capabilities::{CapabilityRole, CapabilityUsage, CapabilityUsageRequest},
+                fb_diagnostics::UploadDiagnosticsParams,
+                fb_localization::{
+                    ClearContextOverrideParams, RegionInfo, RegionValueInfo,
+                    SetContextOverrideParams, SetRegionSourcesParams,
+                },
+                fb_telemetry::AppErrorStats,
+            },
+            manifest::{
+                compliance::{ComplianceDeviation, ComplianceReport},
+                device_manifest::{
+                    ComplianceProfile, JqLimits, JqSandboxConfiguration, RegionField,
+                    RegionSourceType, RouteTarget,
+                },
+            },
+        },
+        extn::client::extn_usage::ExtnUsage,
+        tokio,
+    };
+    use ripple_tdk::utils::test_utils::Mockable;
+    use serde::{de::DeserializeOwned, Serialize};
+
+    fn assert_valid<T: Serialize>(method: &str, value: T) {
+        let (_, result) = describe_method(method).unwrap();
+        let mut schema = result.schema;
+        schema["components"] = get_components();
+        let validator = JSONSchema::compile(&schema).unwrap();
+        let value = serde_json::to_value(value).unwrap();
+        let errors: Vec<String> = match validator.validate(&value) {
+            Ok(()) => vec![],
+            Err(errors) => errors.map(|e| e.to_string()).collect(),
+        };
+        assert!(errors.is_empty(), "{}: {:?}", method, errors);
+    }
+
+    fn parse<T: DeserializeOwned>(params: Value) -> Result<(), serde_json::Error> {
+        serde_json::from_value::<T>(params).map(|_| ())
+    }
+
+    /// Deserializes the params into the request type of the handler of the method, methods
+    /// taking no params have none
+    fn parse_params(method: &str, params: Value) -> Option<Result<(), serde_json::Error>> {
+        let result = match method {
+            "ripple.uploadDiagnostics" => parse::<UploadDiagnosticsParams>(params),
+            "ripple.setContextOverride" => parse::<SetContextOverrideParams>(params),
+            "ripple.clearContextOverride" => parse::<ClearContextOverrideParams>(params),
+            "ripple.getCapabilityUsage" => parse::<CapabilityUsageRequest>(params),
+            "ripple.setRuleExplain" => parse::<SetRuleExplainParams>(params),
+            "ripple.shutdown" => parse::<ShutdownParams>(params),
+            "ripple.setJqSandbox" => parse::<SetJqSandboxParams>(params),
+            "ripple.releaseJqRule" => parse::<ReleaseJqRuleParams>(params),
+            "ripple.injectEvent" => parse::<InjectEventParams>(params),
+            "ripple.setSyntheticProvider" => parse::<SetSyntheticProviderParams>(params),
+            "ripple.dumpState" | "ripple.loadState" => parse::<StateSnapshotParams>(params),
+            "ripple.setRegionSources" => parse::<SetRegionSourcesParams>(params),
+            "ripple.softRestart" => parse::<SoftRestartParams>(params),
+            "ripple.getRoutingTable" => parse::<RoutingTableParams>(params),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    fn get_example(schema: &Value) -> Value {
+        match schema["$ref"].as_str() {
+            Some("#/components/schemas/JqLimits") => json!({}),
+            Some("#/components/schemas/RegionSourceType") => json!("platform"),
+            Some(other) => panic!("no example for {}", other),
+            None => match schema["type"].as_str() {
+                Some("string") => json!("x"),
+                Some("integer") => json!(1),
+                Some("boolean") => json!(true),
+                Some("array") => json!([get_example(&schema["items"])]),
+                _ => json!({}),
+            },
+        }
+    }
+
+    /// Value which the param does not accept, params taking any value have none
+    fn get_invalid(schema: &Value) -> Option<Value> {
+        if schema.get("$ref").is_none() && schema.get("type").is_none() {
+            return None;
+        }
+        match schema["type"].as_str() {
+            Some("string") => Some(json!(1)),
+            _ => Some(json!("x")),
+        }
+    }
+
+    #[test]
+    fn test_params_match_handlers() {
+        for (method, _) in ADMIN_METHOD_ROLES {
+            let (params, _) = describe_method(method).unwrap();
+            let required: Map<String, Value> = params
+                .iter()
+                .filter(|p| p.required)
+                .map(|p| (p.name.clone(), get_example(&p.schema)))
+                .collect();
+            let Some(result) = parse_params(method, Value::Object(required.clone())) else {
+                assert!(params.is_empty(), "{} has no params type", method);
+                continue;
+            };
+            assert!(result.is_ok(), "{}: {:?}", method, result);
+            let all: Map<String, Value> = params
+                .iter()
+                .map(|p| (p.name.clone(), get_example(&p.schema)))
+                .collect();
+            let result = parse_params(method, Value::Object(all)).unwrap();
+            assert!(result.is_ok(), "{}: {:?}", method, result);
+
+            for param in &params {
+                if param.required {
+                    let mut missing = required.clone();
+                    missing.remove(&param.name);
+                    assert!(
+                        parse_params(method, Value::Object(missing))
+                            .unwrap()
+                            .is_err(),
+                        "{}: {} is optional",
+                        method,
+                        param.name
+                    );
+                }
+                // a param the handler does not know would be ignored
+                if let Some(invalid) = get_invalid(&param.schema) {
+                    let mut params = required.clone();
+                    params.insert(param.name.clone(), invalid);
+                    assert!(
+                        parse_params(method, Value::Object(params))
+                            .unwrap()
+                            .is_err(),
+                        "{}: {} is not a param of the handler",
+                        method,
+                        param.name
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_admin_method_is_described() {
+        for (method, _) in ADMIN_METHOD_ROLES {
+            assert!(describe_method(method).is_some(), "{}", method);
+        }
+        assert!(describe_method("device.name").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_registered_admin_methods_are_described() {
+        let state = PlatformState::mock();
+        let mut methods: Vec<&str> = AdminRPCProvider::provide(state.clone())
+            .method_names()
+            .collect();
+        methods.extend(
+            DiagnosticsRPCProvider::provide(state)
+                .method_names()
+                .filter(|m| m.starts_with("ripple.")),
+        );
+        for method in &methods {
+            assert!(
+                ADMIN_METHOD_ROLES.iter().any(|(m, _)| m == method),
+                "{} has no role",
+                method
+            );
+            assert!(describe_method(method).is_some(), "{}", method);
+        }
+        for (method, _) in ADMIN_METHOD_ROLES {
+            assert!(methods.contains(method), "{} is not registered", method);
+        }
+    }
+
+    #[test]
+    fn test_results_match_schemas() {
+        assert_valid(
+            "ripple.getCapabilityUsage",
+            std::collections::HashMap::from([(
+                "app1",
+                vec![CapabilityUsage {
+                    capability: "xrn:firebolt:capability:device:name".into(),
+                    role: CapabilityRole::Use,
+                    count: 1,
+                    last_used: 1,
+                }],
+            )]),
+        );
+        assert_valid("ripple.getExtnUsage", vec![ExtnUsage::default()]);
+        assert_valid(
+            "ripple.getComplianceReport",
+            ComplianceReport {
+                profile: Some(ComplianceProfile::Gdpr),
+                deviations: vec![ComplianceDeviation {
+                    field: "allowWatchHistory".into(),
+                    preset: json!(false),
+                    configured: json!(true),
+                }],
+            },
+        );
+        assert_valid("ripple.getComplianceReport", ComplianceReport::default());
+        assert_valid(
+            "ripple.getRuleProvenance",
+            std::collections::HashMap::from([
+                ("device.name", RuleProvenance::Builtin),
+                (
+                    "service.method",
+                    RuleProvenance::Service {
+                        service_id: "service1".into(),
+                    },
+                ),
+            ]),
+        );
+        assert_valid("ripple.getErrorBudgets", vec![AppErrorStats::default()]);
+        assert_valid("ripple.getTenantStats", vec![TenantStats::default()]);
+        assert_valid(
+            "ripple.getRegion",
+            RegionInfo {
+                sources: vec![RegionSourceType::Manifest],
+                values: std::collections::HashMap::from([(
+                    RegionField::CountryCode,
+                    RegionValueInfo {
+                        value: "US".into(),
+                        source: RegionSourceType::Manifest,
+                    },
+                )]),
+            },
+        );
+        assert_valid(
+            "ripple.getJqSandbox",
+            JqSandboxStatus {
+                config: JqSandboxConfiguration {
+                    rule_limits: std::collections::HashMap::from([(
+                        "device.name".to_owned(),
+                        JqLimits::default(),
+                    )]),
+                    ..Default::default()
+                },
+                quarantined: vec!["device.name".into()],
+            },
+        );
+        assert_valid("ripple.softRestart", SoftRestartResult::default());
+        assert_valid(
+            "ripple.getRoutingTable",
+            vec![RouteEntry {
+                method: "device.name".into(),
+                target: RouteTarget::Broker,
+                served_by: "thunder".into(),
+                endpoint: None,
+                shadowed: vec![RouteTarget::Handler],
+            }],
+        );
+    }
+}
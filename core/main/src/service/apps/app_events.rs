@@ -28,12 +28,88 @@ use ripple_sdk::{
     utils::channel_utils::mpsc_send_and_log,
 };
 
+use serde::Deserialize;
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
 };
 
-use crate::{service::telemetry_builder::TelemetryBuilder, state::platform_state::PlatformState};
+use crate::{
+    broker::rules::rules_engine::jq_compile, service::telemetry_builder::TelemetryBuilder,
+    state::platform_state::PlatformState,
+};
+
+/// Synthetic event sent through `ripple.injectEvent`. Without app or session ids the event
+/// goes to every listener. Only events served by a handler, a rule or an extension are
+/// accepted, and when Ripple is built with `openrpc_validation` and an event schema is
+/// configured the result has to match the schema of the event.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InjectEventParams {
+    pub event: String,
+    pub result: Value,
+    pub context: Option<Value>,
+    pub app_ids: Option<Vec<String>>,
+    pub session_ids: Option<Vec<String>>,
+}
+
+impl InjectEventParams {
+    fn validate(&self, state: &PlatformState) -> Result<(), String> {
+        let known = state
+            .router_state
+            .get_method_names()
+            .iter()
+            .any(|method| method.eq_ignore_ascii_case(&self.event))
+            || state.endpoint_state.has_rule(&self.event)
+            || state.extn_method_state.get_method(&self.event).is_some();
+        if !known {
+            return Err(format!("Unknown event {}", self.event));
+        }
+        #[cfg(feature = "openrpc_validation")]
+        if let Some(path) = state.admin_state.get_event_schema_path() {
+            Self::validate_result(&path, &self.event, &self.result)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the result against the result schema of the event in the Firebolt version
+    /// manifest, events missing from the manifest are rejected
+    #[cfg(feature = "openrpc_validation")]
+    fn validate_result(path: &str, event: &str, result: &Value) -> Result<(), String> {
+        use openrpc_validator::{FireboltOpenRpc, OpenRpcSpec};
+
+        let spec = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| {
+                serde_json::from_str::<FireboltOpenRpc>(&data).map_err(|e| e.to_string())
+            })
+            .map_err(|e| format!("Unable to load the event schemas from {}: {}", path, e))?;
+        let validator = spec
+            .apis
+            .into_values()
+            .find_map(|api| OpenRpcSpec::from(api).result_validator(event).ok())
+            .ok_or_else(|| format!("No schema for event {}", event))?;
+        let result = validator.validate(result).map_err(|errors| {
+            errors
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        });
+        result
+    }
+
+    fn is_selected(&self, ctx: &CallContext) -> bool {
+        let app_selected = self.app_ids.as_ref().map(|ids| ids.contains(&ctx.app_id));
+        let session_selected = self
+            .session_ids
+            .as_ref()
+            .map(|ids| ids.contains(&ctx.session_id));
+        match (app_selected, session_selected) {
+            (None, None) => true,
+            (a, s) => a.unwrap_or(false) || s.unwrap_or(false),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct AppEventDecorationError {}
@@ -124,6 +200,8 @@ pub struct EventListener {
     // Keep the session_tx package private
     session_tx: Option<mpsc::Sender<ApiMessage>>,
     decorator: Option<Box<dyn AppEventDecorator + Send + Sync>>,
+    /// Payload transform for the SDK version of the subscriber
+    transform: Option<String>,
 }
 
 impl EventListener {
@@ -235,6 +313,11 @@ impl AppEvents {
                 return;
             }
         };
+        let transform = session.get_sdk_version().and_then(|sdk_version| {
+            state
+                .get_device_manifest()
+                .get_event_transform(&event_name, &sdk_version)
+        });
         let app_events_state = &state.app_events_state;
         let mut listeners = app_events_state.listeners.write().unwrap();
         let event_ctx_string = event_context.map(|x| x.to_string());
@@ -248,6 +331,7 @@ impl AppEvents {
                 call_ctx,
                 session_tx: session.get_sender(),
                 decorator,
+                transform,
             });
         } else if let Some(entry) = listeners.get_mut(&event_name) {
             if let Some(event_listeners) = entry.get_mut(&event_ctx_string) {
@@ -280,10 +364,28 @@ impl AppEvents {
         result
     }
 
+    fn apply_transform(listener: &EventListener, data: &Value) -> Value {
+        if let Some(filter) = &listener.transform {
+            match jq_compile(
+                data.clone(),
+                filter,
+                format!("{}_sdk_event", listener.call_ctx.method),
+            ) {
+                Ok(transformed) => return transformed,
+                Err(e) => error!(
+                    "Event transform failed for {} e={:?}, sending the event as is",
+                    listener.call_ctx.method, e
+                ),
+            }
+        }
+        data.clone()
+    }
+
     pub async fn send_event(listener: &EventListener, data: &Value) {
         let protocol = listener.call_ctx.protocol.clone();
         debug!("Sending event for call context {:?}", listener.call_ctx);
         let mut event = JsonRpcApiResponse::default();
+        let data = &Self::apply_transform(listener, data);
 
         if listener.call_ctx.is_rpc_v2() {
             let params = AppEvents::get_rpc_v2_result(&listener.call_ctx.method, data.clone());
@@ -385,9 +487,51 @@ impl AppEvents {
             }
         }
 
+        state.dbus_bridge.forward(event_name, result);
         TelemetryBuilder::send_fb_event(state, event_name, result.clone());
     }
 
+    /// Sends a synthetic event to the selected listeners for testing. The payload is sent as
+    /// given, it is not decorated nor forwarded to the dbus bridge and telemetry. Returns the
+    /// number of listeners notified, or why the event was rejected.
+    pub async fn inject(
+        state: &PlatformState,
+        params: &InjectEventParams,
+    ) -> Result<usize, String> {
+        params.validate(state)?;
+        let mut notified = 0;
+        let listeners = AppEvents::get_listeners(&state.app_events_state, &params.event, None);
+        for i in listeners.iter().filter(|l| params.is_selected(&l.call_ctx)) {
+            match &params.context {
+                Some(context) => {
+                    AppEvents::send_event(
+                        i,
+                        &json!({
+                            "context": context,
+                            "value": &params.result,
+                        }),
+                    )
+                    .await
+                }
+                None => AppEvents::send_event(i, &params.result).await,
+            }
+            notified += 1;
+        }
+
+        if let Some(context) = &params.context {
+            let listeners = AppEvents::get_listeners(
+                &state.app_events_state,
+                &params.event,
+                Some(context.to_string()),
+            );
+            for i in listeners.iter().filter(|l| params.is_selected(&l.call_ctx)) {
+                AppEvents::send_event(i, &params.result).await;
+                notified += 1;
+            }
+        }
+        Ok(notified)
+    }
+
     pub async fn emit_to_app(
         state: &PlatformState,
         app_id: String,
@@ -447,8 +591,8 @@ impl AppEvents {
 }
 #[cfg(test)]
 pub mod tests {
-    use crate::state::session_state::Session;
-    use ripple_sdk::tokio;
+    use crate::state::{extn_method_state::ExtnMethod, session_state::Session};
+    use ripple_sdk::{api::firebolt::fb_capabilities::FireboltCap, tokio};
     use ripple_tdk::utils::test_utils::Mockable;
 
     use super::*;
@@ -482,4 +626,92 @@ pub mod tests {
             AppEvents::get_listeners(&platform_state.app_events_state, "test_event", None);
         assert!(listeners.len() == 1);
     }
+
+    #[test]
+    fn test_apply_transform() {
+        let mut listener = EventListener {
+            call_ctx: CallContext::mock(),
+            session_tx: None,
+            decorator: None,
+            transform: Some("{ value: . }".into()),
+        };
+        assert_eq!(
+            AppEvents::apply_transform(&listener, &json!(true)),
+            json!({ "value": true })
+        );
+        listener.transform = Some("invalid {".into());
+        assert_eq!(
+            AppEvents::apply_transform(&listener, &json!(true)),
+            json!(true)
+        );
+        listener.transform = None;
+        assert_eq!(
+            AppEvents::apply_transform(&listener, &json!(true)),
+            json!(true)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inject_event() {
+        let platform_state = PlatformState::mock();
+        let call_context = CallContext::mock();
+        platform_state.session_state.add_session(
+            call_context.session_id.clone(),
+            Session::new(call_context.app_id.clone(), None),
+        );
+        platform_state.extn_method_state.set_methods(
+            "ripple:extn:jsonrpsee:device",
+            vec![(
+                "device.onNameChanged".into(),
+                ExtnMethod::new(
+                    "ripple:extn:jsonrpsee:device",
+                    "device.onNameChanged",
+                    FireboltCap::default(),
+                    None,
+                )
+                .unwrap(),
+            )],
+        );
+        AppEvents::add_listener(
+            &platform_state,
+            "device.onNameChanged".to_string(),
+            call_context.clone(),
+            ListenRequest { listen: true },
+        );
+
+        let mut params = InjectEventParams {
+            event: "device.onNameChanged".into(),
+            result: json!("Living Room"),
+            context: None,
+            app_ids: None,
+            session_ids: None,
+        };
+        assert_eq!(AppEvents::inject(&platform_state, &params).await, Ok(1));
+        params.app_ids = Some(vec!["other_app".into()]);
+        assert_eq!(AppEvents::inject(&platform_state, &params).await, Ok(0));
+        params.session_ids = Some(vec![call_context.session_id.clone()]);
+        assert_eq!(AppEvents::inject(&platform_state, &params).await, Ok(1));
+
+        params.event = "device.onUnknown".into();
+        assert!(AppEvents::inject(&platform_state, &params).await.is_err());
+    }
+
+    #[cfg(feature = "openrpc_validation")]
+    #[test]
+    fn test_validate_injected_result() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../openrpc_validator/src/test/firebolt-open-rpc.json"
+        );
+        assert!(InjectEventParams::validate_result(
+            path,
+            "device.onNameChanged",
+            &json!("Living Room")
+        )
+        .is_ok());
+        assert!(
+            InjectEventParams::validate_result(path, "device.onNameChanged", &json!(1)).is_err()
+        );
+        assert!(InjectEventParams::validate_result(path, "device.onUnknown", &json!(1)).is_err());
+    }
 }
@@ -0,0 +1,214 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{path::Path, time::Duration};
+
+use hyper::{
+    header::{ETAG, IF_NONE_MATCH},
+    Body, Method, Request, StatusCode,
+};
+use ripple_sdk::{
+    api::{
+        config::ConfigSection,
+        manifest::{
+            app_library::{
+                AppLibraryDelta, DefaultLibrary, APP_LIBRARY_EVENT_APP_ADDED,
+                APP_LIBRARY_EVENT_APP_REMOVED,
+            },
+            device_manifest::{AppLibraryEntry, DeviceManifest},
+        },
+    },
+    log::{debug, error, info},
+    tokio,
+    utils::error::RippleError,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    bootstrap::manifest::apps::LoadAppLibraryStep,
+    service::apps::{app_events::AppEvents, app_library_source::get_app_library_source},
+    state::{config_section_state::ConfigSectionState, platform_state::PlatformState},
+    utils::http_utils::HttpClient,
+};
+
+/// Last known good copy of the app library along with the ETag it was served with
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppLibrarySnapshot {
+    pub etag: Option<String>,
+    pub default_library: Vec<AppLibraryEntry>,
+    /// Time in millis the copy was last confirmed by the distributor endpoint
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+}
+
+/// Shortest interval the distributor endpoint is polled at, so a zero interval does not
+/// busy loop against it
+const MIN_REFRESH_INTERVAL_SECONDS: u64 = 60;
+
+/// Periodically refreshes the app library from the source selected in the device
+/// manifest and applies the changes to the running platform.
+pub struct AppLibraryRefresh;
+
+impl AppLibraryRefresh {
+    pub fn get_snapshot_path(saved_dir: &str) -> String {
+        let dir_path = Path::new(saved_dir).join("app_library");
+        dir_path.into_os_string().into_string().unwrap()
+    }
+
+    /// Returns the library to boot with from the configured source, falling back to the
+    /// app library file on the device.
+    pub fn load(manifest: &DeviceManifest) -> Vec<AppLibraryEntry> {
+        let config = manifest.get_app_library_refresh_configuration();
+        get_app_library_source(&config, &manifest.configuration.saved_dir)
+            .load()
+            .unwrap_or_else(LoadAppLibraryStep::load_app_library)
+    }
+
+    pub fn start(state: PlatformState) {
+        let manifest = state.get_device_manifest();
+        let config = manifest.get_app_library_refresh_configuration();
+        let mut source = get_app_library_source(&config, &manifest.configuration.saved_dir);
+        if !source.is_refreshable() {
+            return;
+        }
+        let interval =
+            Duration::from_secs(config.interval_seconds.max(MIN_REFRESH_INTERVAL_SECONDS));
+
+        tokio::spawn(async move {
+            loop {
+                match source.fetch().await {
+                    Ok(Some(apps)) => {
+                        let delta = Self::apply(&state, apps).await;
+                        info!("App library refreshed {:?}", delta);
+                    }
+                    Ok(None) => debug!("App library not modified"),
+                    Err(e) => error!("App library refresh failed: {:?}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    pub async fn fetch(
+        client: &HttpClient,
+        url: &str,
+        etag: Option<String>,
+    ) -> Result<Option<AppLibrarySnapshot>, RippleError> {
+        let mut builder = Request::builder().method(Method::GET).uri(url);
+        if let Some(etag) = etag {
+            builder = builder.header(IF_NONE_MATCH, etag);
+        }
+        let request = builder
+            .body(Body::empty())
+            .map_err(|_| RippleError::InvalidInput)?;
+        let response = client
+            .request(request)
+            .await
+            .map_err(|e| RippleError::BrokerError(e.to_string()))?;
+
+        match response.status() {
+            StatusCode::NOT_MODIFIED => Ok(None),
+            StatusCode::OK => {
+                let etag = response
+                    .headers()
+                    .get(ETAG)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_owned());
+                let body = hyper::body::to_bytes(response.into_body())
+                    .await
+                    .map_err(|_| RippleError::InvalidOutput)?;
+                let library: DefaultLibrary =
+                    serde_json::from_slice(&body).map_err(|_| RippleError::ParseError)?;
+                Ok(Some(AppLibrarySnapshot {
+                    etag,
+                    default_library: library.default_library,
+                    updated_at: None,
+                }))
+            }
+            status => Err(RippleError::BrokerError(status.to_string())),
+        }
+    }
+
+    /// Applies a new copy of the library, cached permissions of changed apps are dropped
+    /// so they are fetched again against the new library.
+    pub async fn apply(state: &PlatformState, apps: Vec<AppLibraryEntry>) -> AppLibraryDelta {
+        let delta = state.app_library_state.write().unwrap().apply_update(apps);
+        for app_id in delta.removed.iter().chain(delta.updated.iter()) {
+            state.cap_state.permitted_state.remove_app(app_id);
+        }
+        if !delta.is_empty() {
+            ConfigSectionState::invalidate(state, ConfigSection::Launcher);
+        }
+        for app_id in delta.added.iter() {
+            AppEvents::emit(
+                state,
+                APP_LIBRARY_EVENT_APP_ADDED,
+                &json!({ "appId": app_id }),
+            )
+            .await;
+        }
+        for app_id in delta.removed.iter() {
+            AppEvents::emit(
+                state,
+                APP_LIBRARY_EVENT_APP_REMOVED,
+                &json!({ "appId": app_id }),
+            )
+            .await;
+        }
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::http_utils::get_http_client;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_fetch_with_etag() {
+        let mock_server = MockServer::start();
+        let not_modified = mock_server.mock(|when, then| {
+            when.method(GET)
+                .path("/library")
+                .header("If-None-Match", "\"v1\"");
+            then.status(304);
+        });
+        let modified = mock_server.mock(|when, then| {
+            when.method(GET).path("/library");
+            then.status(200)
+                .header("ETag", "\"v1\"")
+                .json_body(json!({"default_library": []}));
+        });
+
+        let client = get_http_client();
+        let url = mock_server.url("/library");
+        let snapshot = AppLibraryRefresh::fetch(&client, &url, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(snapshot.etag, Some("\"v1\"".to_owned()));
+        modified.assert();
+
+        let snapshot = AppLibraryRefresh::fetch(&client, &url, snapshot.etag)
+            .await
+            .unwrap();
+        assert!(snapshot.is_none());
+        not_modified.assert();
+    }
+}
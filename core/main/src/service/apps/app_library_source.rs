@@ -0,0 +1,311 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::{
+    api::manifest::device_manifest::{
+        AppLibraryEntry, AppLibraryRefreshConfiguration, AppLibrarySourceType,
+    },
+    async_trait::async_trait,
+    chrono::Utc,
+    framework::file_store::FileStore,
+    log::{info, warn},
+    utils::error::RippleError,
+};
+
+use crate::{
+    bootstrap::manifest::apps::LoadAppLibraryStep,
+    utils::http_utils::{get_http_client, HttpClient},
+};
+
+use super::app_library_refresh::{AppLibraryRefresh, AppLibrarySnapshot};
+
+/// Source of the app library, selected by the app library refresh configuration in the
+/// device manifest.
+#[async_trait]
+pub trait AppLibrarySource: Send + Sync {
+    /// Returns the library to boot with, None when the source has nothing usable
+    fn load(&self) -> Option<Vec<AppLibraryEntry>>;
+
+    /// Returns a newer library, None when it has not changed
+    async fn fetch(&mut self) -> Result<Option<Vec<AppLibraryEntry>>, RippleError>;
+
+    /// Sources which never change are not polled
+    fn is_refreshable(&self) -> bool {
+        true
+    }
+}
+
+/// App library file on the device
+pub struct LocalFileSource;
+
+#[async_trait]
+impl AppLibrarySource for LocalFileSource {
+    fn load(&self) -> Option<Vec<AppLibraryEntry>> {
+        Some(LoadAppLibraryStep::load_app_library())
+    }
+
+    async fn fetch(&mut self) -> Result<Option<Vec<AppLibraryEntry>>, RippleError> {
+        Ok(None)
+    }
+
+    fn is_refreshable(&self) -> bool {
+        false
+    }
+}
+
+/// Distributor endpoint, the last fetched copy is saved so devices can boot offline
+pub struct CloudSource {
+    url: String,
+    max_age_seconds: Option<u64>,
+    store: FileStore<AppLibrarySnapshot>,
+    client: HttpClient,
+    /// Whether the platform runs the saved copy, a copy rejected at boot is handed out on the
+    /// first fetch even when the endpoint reports it as not modified
+    applied: bool,
+}
+
+impl CloudSource {
+    pub fn new(config: &AppLibraryRefreshConfiguration, saved_dir: &str) -> Self {
+        let path = AppLibraryRefresh::get_snapshot_path(saved_dir);
+        let store = FileStore::load(path.clone())
+            .unwrap_or_else(|_| FileStore::new(path, AppLibrarySnapshot::default()));
+        let mut source = CloudSource {
+            url: config.url.clone(),
+            max_age_seconds: config.max_age_seconds,
+            store,
+            client: get_http_client(),
+            applied: false,
+        };
+        source.applied = source.load_saved().is_some();
+        source
+    }
+
+    fn load_saved(&self) -> Option<Vec<AppLibraryEntry>> {
+        if !self.is_fresh() {
+            return None;
+        }
+        Some(self.store.value.default_library.clone()).filter(|apps| !apps.is_empty())
+    }
+
+    fn is_fresh(&self) -> bool {
+        match (self.max_age_seconds, self.store.value.updated_at) {
+            (None, _) => true,
+            (Some(max_age), Some(updated_at)) => {
+                let max_age_ms = i64::try_from(max_age.saturating_mul(1000)).unwrap_or(i64::MAX);
+                Utc::now().timestamp_millis().saturating_sub(updated_at) <= max_age_ms
+            }
+            // copies saved before the timestamp was recorded have an unknown age
+            (Some(_), None) => false,
+        }
+    }
+}
+
+#[async_trait]
+impl AppLibrarySource for CloudSource {
+    fn load(&self) -> Option<Vec<AppLibraryEntry>> {
+        if !self.is_fresh() {
+            warn!("Saved app library is older than the max age, not using it");
+        }
+        self.load_saved()
+    }
+
+    async fn fetch(&mut self) -> Result<Option<Vec<AppLibraryEntry>>, RippleError> {
+        let etag = self.store.value.etag.clone();
+        let fetched = AppLibraryRefresh::fetch(&self.client, &self.url, etag).await?;
+        let changed = fetched.is_some() || !self.applied;
+        if let Some(snapshot) = fetched {
+            info!("App library fetched etag={:?}", snapshot.etag);
+            self.store.value = snapshot;
+        }
+        // a not modified response also proves the saved copy is current
+        self.store.value.updated_at = Some(Utc::now().timestamp_millis());
+        self.store.snapshot().persist();
+        self.applied = true;
+        Ok(changed.then(|| self.store.value.default_library.clone()))
+    }
+}
+
+/// App library file on the device with the apps from the distributor endpoint layered on
+/// top, a cloud entry replaces the local entry with the same app id.
+pub struct HybridSource {
+    local: Vec<AppLibraryEntry>,
+    cloud: CloudSource,
+}
+
+impl HybridSource {
+    pub fn new(local: Vec<AppLibraryEntry>, cloud: CloudSource) -> Self {
+        HybridSource { local, cloud }
+    }
+
+    fn merge(&self, cloud: Vec<AppLibraryEntry>) -> Vec<AppLibraryEntry> {
+        let mut apps: Vec<AppLibraryEntry> = self
+            .local
+            .iter()
+            .filter(|local| !cloud.iter().any(|c| c.app_id == local.app_id))
+            .cloned()
+            .collect();
+        apps.extend(cloud);
+        apps
+    }
+}
+
+#[async_trait]
+impl AppLibrarySource for HybridSource {
+    fn load(&self) -> Option<Vec<AppLibraryEntry>> {
+        Some(self.merge(self.cloud.load().unwrap_or_default()))
+    }
+
+    async fn fetch(&mut self) -> Result<Option<Vec<AppLibraryEntry>>, RippleError> {
+        let fetched = self.cloud.fetch().await?;
+        Ok(fetched.map(|cloud| self.merge(cloud)))
+    }
+}
+
+pub fn get_app_library_source(
+    config: &AppLibraryRefreshConfiguration,
+    saved_dir: &str,
+) -> Box<dyn AppLibrarySource> {
+    if !config.enabled {
+        return Box::new(LocalFileSource);
+    }
+    match config.source {
+        AppLibrarySourceType::Local => Box::new(LocalFileSource),
+        AppLibrarySourceType::Cloud => Box::new(CloudSource::new(config, saved_dir)),
+        AppLibrarySourceType::Hybrid => Box::new(HybridSource::new(
+            LoadAppLibraryStep::load_app_library(),
+            CloudSource::new(config, saved_dir),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{
+        api::manifest::device_manifest::{AppManifestLoad, BootState, SessionPriority},
+        tokio,
+    };
+    use std::fs;
+
+    fn get_entry(app_id: &str, url: &str) -> AppLibraryEntry {
+        AppLibraryEntry {
+            app_id: app_id.into(),
+            manifest: AppManifestLoad::Remote(url.into()),
+            boot_state: BootState::Unloaded,
+            session_priority: SessionPriority::Normal,
+        }
+    }
+
+    #[test]
+    fn test_hybrid_source() {
+        let dir = std::env::temp_dir().join("ripple_app_library_source_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let saved_dir = dir.to_str().unwrap();
+        let mut config = AppLibraryRefreshConfiguration {
+            enabled: true,
+            source: AppLibrarySourceType::Hybrid,
+            max_age_seconds: Some(60),
+            ..Default::default()
+        };
+
+        let mut store = FileStore::new(
+            AppLibraryRefresh::get_snapshot_path(saved_dir),
+            AppLibrarySnapshot {
+                etag: None,
+                default_library: vec![get_entry("app1", "cloud")],
+                updated_at: Some(Utc::now().timestamp_millis()),
+            },
+        );
+        store.sync();
+
+        let source = HybridSource::new(
+            vec![get_entry("app1", "local"), get_entry("app2", "local")],
+            CloudSource::new(&config, saved_dir),
+        );
+        let apps = source.load().unwrap();
+        assert_eq!(apps.len(), 2);
+        assert!(apps
+            .iter()
+            .any(|a| a.app_id == "app1" && a.manifest == AppManifestLoad::Remote("cloud".into())));
+
+        // a stale cloud copy is ignored and the local library is used
+        store.value.updated_at = Some(Utc::now().timestamp_millis() - 120_000);
+        store.sync();
+        let source = HybridSource::new(
+            vec![get_entry("app1", "local")],
+            CloudSource::new(&config, saved_dir),
+        );
+        let apps = source.load().unwrap();
+        assert_eq!(apps[0].manifest, AppManifestLoad::Remote("local".into()));
+
+        // a max age beyond the range of the timestamps keeps every copy
+        config.max_age_seconds = Some(u64::MAX);
+        let source = HybridSource::new(
+            vec![get_entry("app1", "local")],
+            CloudSource::new(&config, saved_dir),
+        );
+        let apps = source.load().unwrap();
+        assert_eq!(apps[0].manifest, AppManifestLoad::Remote("cloud".into()));
+
+        config.source = AppLibrarySourceType::Local;
+        assert!(!get_app_library_source(&config, saved_dir).is_refreshable());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_cloud_source_not_modified() {
+        use httpmock::prelude::*;
+
+        let dir = std::env::temp_dir().join("ripple_app_library_cloud_source_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let saved_dir = dir.to_str().unwrap();
+        let mock_server = MockServer::start();
+        mock_server.mock(|when, then| {
+            when.method(GET)
+                .path("/library")
+                .header("If-None-Match", "\"v1\"");
+            then.status(304);
+        });
+        let config = AppLibraryRefreshConfiguration {
+            enabled: true,
+            source: AppLibrarySourceType::Cloud,
+            url: mock_server.url("/library"),
+            max_age_seconds: Some(60),
+            ..Default::default()
+        };
+
+        // the saved copy is too old to boot with, but the endpoint still confirms it
+        let mut store = FileStore::new(
+            AppLibraryRefresh::get_snapshot_path(saved_dir),
+            AppLibrarySnapshot {
+                etag: Some("\"v1\"".into()),
+                default_library: vec![get_entry("app1", "cloud")],
+                updated_at: Some(Utc::now().timestamp_millis() - 120_000),
+            },
+        );
+        store.sync();
+        let mut source = CloudSource::new(&config, saved_dir);
+        assert!(source.load().is_none());
+
+        let apps = source.fetch().await.unwrap().unwrap();
+        assert_eq!(apps[0].app_id, "app1");
+        assert!(source.fetch().await.unwrap().is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
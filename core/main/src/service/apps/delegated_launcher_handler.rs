@@ -72,9 +72,11 @@ use serde_json::{json, Value};
 
 use crate::{
     broker::{broker_utils::BrokerUtils, endpoint_broker::BrokerCallback},
+    firebolt::handlers::{audio_focus_rpc::AudioFocusImpl, media_session_rpc::MediaSessionImpl},
     service::{
         apps::app_events::AppEvents,
         extn::ripple_client::RippleClient,
+        inactivity_monitor::InactivityMonitor,
         telemetry_builder::TelemetryBuilder,
         user_grants::{GrantHandler, GrantPolicyEnforcer, GrantState},
     },
@@ -361,6 +363,16 @@ impl AppManagerState {
         self.apps.read().unwrap().get(app_id).cloned()
     }
 
+    pub fn get_app_ids_in_state(&self, state: LifecycleState) -> Vec<String> {
+        self.apps
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, app)| app.state == state)
+            .map(|(app_id, _)| app_id.clone())
+            .collect()
+    }
+
     fn remove(&self, app_id: &str) -> Option<App> {
         let mut apps = self.apps.write().unwrap();
         apps.remove(app_id)
@@ -687,6 +699,14 @@ impl DelegatedLauncherHandler {
             // App request
             debug!("DelegatedLauncherHandler: App request: data={:?}", data);
             let method = data.method.clone();
+            if matches!(
+                method,
+                AppMethod::Launch(_)
+                    | AppMethod::Ready(_)
+                    | AppMethod::SetState(_, LifecycleState::Foreground)
+            ) {
+                InactivityMonitor::on_activity(&self.platform_state).await;
+            }
             let (resp, app_id) = match data.method.clone() {
                 AppMethod::BrowserSession(session) => (
                     self.start_session(session.clone()).await,
@@ -740,18 +760,23 @@ impl DelegatedLauncherHandler {
                     );
                     (resp, Some(app_id))
                 }
-                AppMethod::Close(app_id, reason) => (
-                    self.send_lifecycle_mgmt_event(LifecycleManagementEventRequest::Close(
-                        LifecycleManagementCloseEvent {
-                            parameters: LifecycleManagementCloseParameters {
-                                app_id: app_id.clone(),
-                                reason,
+                AppMethod::Close(app_id, reason) => {
+                    self.platform_state
+                        .app_usage_state
+                        .on_exit(&app_id, reason.as_string());
+                    (
+                        self.send_lifecycle_mgmt_event(LifecycleManagementEventRequest::Close(
+                            LifecycleManagementCloseEvent {
+                                parameters: LifecycleManagementCloseParameters {
+                                    app_id: app_id.clone(),
+                                    reason,
+                                },
                             },
-                        },
-                    ))
-                    .await,
-                    Some(app_id),
-                ),
+                        ))
+                        .await,
+                        Some(app_id),
+                    )
+                }
                 AppMethod::CheckFinished(app_id) => {
                     (self.check_finished(&app_id).await, Some(app_id))
                 }
@@ -938,6 +963,7 @@ impl DelegatedLauncherHandler {
                 if session.launch.intent.is_none() {
                     return Err(AppError::NoIntentError);
                 }
+                self.platform_state.app_usage_state.on_launch(&app_id);
                 // app is unloading
                 if self.platform_state.app_manager_state.get(&app_id).is_some() {
                     // app exist so we are creating a new session
@@ -1309,9 +1335,12 @@ impl DelegatedLauncherHandler {
         debug!("end_session: entry: app_id={}", app_id);
         let app = self.platform_state.app_manager_state.remove(app_id);
         if app.is_some() {
+            self.platform_state.app_usage_state.on_session_end(app_id);
             if let Some(timer) = self.timer_map.remove(app_id) {
                 timer.cancel();
             }
+            MediaSessionImpl::clear_for_app(&self.platform_state, app_id).await;
+            AudioFocusImpl::abandon_for_app(&self.platform_state, app_id).await;
         } else {
             error!("end_session app_id={} Not found", app_id);
             return Err(AppError::NotFound);
@@ -1425,6 +1454,9 @@ impl DelegatedLauncherHandler {
             app_id, previous_state, state
         );
         am_state.set_state(app_id, state);
+        self.platform_state
+            .app_usage_state
+            .on_state_change(app_id, previous_state, state);
         // remove active session id when the app is going back to inactive (not going to inactive for first time)
         if (previous_state != LifecycleState::Initializing) && (state == LifecycleState::Inactive) {
             am_state.update_active_session(app_id, None);
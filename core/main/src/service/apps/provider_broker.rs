@@ -26,22 +26,29 @@ use ripple_sdk::{
             },
             fb_openrpc::FireboltOpenRpcMethod,
             provider::{
-                FocusRequest, ProviderRequest, ProviderRequestPayload, ProviderResponse,
-                ProviderResponsePayload,
+                FocusRequest, GenericProviderError, ProviderRequest, ProviderRequestPayload,
+                ProviderResponse, ProviderResponsePayload,
+            },
+        },
+        gateway::{
+            rpc_gateway_api::{CallContext, CallerSession},
+            rpc_response::{
+                JSON_RPC_SERVER_ERROR_DEADLINE_EXCEEDED, JSON_RPC_SERVER_ERROR_UNAVAILABLE,
             },
         },
-        gateway::rpc_gateway_api::{CallContext, CallerSession},
     },
     log::{debug, error, info, warn},
-    serde_json,
+    serde_json::{self, Value},
     tokio::sync::oneshot,
-    utils::channel_utils::oneshot_send_and_log,
+    utils::{channel_utils::oneshot_send_and_log, expiring_map::ExpiringMap},
     uuid::Uuid,
 };
 
+use serde::Deserialize;
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 
 use crate::{
@@ -62,8 +69,10 @@ pub enum ProviderError {
 #[derive(Default, Clone)]
 pub struct ProviderBrokerState {
     provider_methods: Arc<RwLock<HashMap<String, ProviderMethod>>>,
-    active_sessions: Arc<RwLock<HashMap<String, ProviderSession>>>,
+    active_sessions: Arc<RwLock<ExpiringMap<String, ProviderSession>>>,
     request_queue: Arc<RwLock<ArrayVec<ProviderBrokerRequest, REQUEST_QUEUE_CAPACITY>>>,
+    deferred_requests: Arc<RwLock<Vec<DeferredRequest>>>,
+    synthetic_responses: Arc<RwLock<HashMap<String, Value>>>,
 }
 
 impl std::fmt::Debug for ProviderBrokerState {
@@ -98,12 +107,26 @@ pub struct ProviderBrokerRequest {
     pub app_id: Option<String>,
 }
 
+#[derive(Debug)]
+struct DeferredRequest {
+    request: ProviderBrokerRequest,
+    deadline: Instant,
+}
+
 #[derive(Debug)]
 struct ProviderCaller {
     session: CallerSession,
     tx: oneshot::Sender<ProviderResponsePayload>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetSyntheticProviderParams {
+    pub capability: String,
+    /// Canned response for the capability, `None` turns the synthetic provider off
+    pub response: Option<Value>,
+}
+
 #[derive(Debug)]
 pub struct ProviderResult {
     pub entries: HashMap<String, Vec<String>>,
@@ -219,12 +242,92 @@ impl ProviderBroker {
         ProviderResult::new(result)
     }
 
+    /// Canned responses answer the provider requests of a capability without a provider app,
+    /// so end to end tests can run headless.
+    pub fn set_synthetic_response(pst: &PlatformState, params: SetSyntheticProviderParams) {
+        info!(
+            "Synthetic provider for {} enabled={}",
+            params.capability,
+            params.response.is_some()
+        );
+        let mut synthetic_responses = pst
+            .provider_broker_state
+            .synthetic_responses
+            .write()
+            .unwrap();
+        match params.response {
+            Some(response) => {
+                synthetic_responses.insert(params.capability, response);
+            }
+            None => {
+                synthetic_responses.remove(&params.capability);
+            }
+        }
+    }
+
+    /// Returns the canned response in the shape the caller expects for the request, canned
+    /// responses are only used in developer mode
+    fn get_synthetic_response(
+        pst: &PlatformState,
+        request: &ProviderBrokerRequest,
+    ) -> Option<ProviderResponsePayload> {
+        if !pst.developer_mode.is_enabled() {
+            return None;
+        }
+        let response = pst
+            .provider_broker_state
+            .synthetic_responses
+            .read()
+            .unwrap()
+            .get(&request.capability)
+            .cloned()?;
+        let payload = match request.request {
+            ProviderRequestPayload::KeyboardSession(_) => {
+                serde_json::from_value(response).map(ProviderResponsePayload::KeyboardResult)
+            }
+            ProviderRequestPayload::PinChallenge(_) => {
+                serde_json::from_value(response).map(ProviderResponsePayload::PinChallengeResponse)
+            }
+            ProviderRequestPayload::AckChallenge(_) => {
+                serde_json::from_value(response).map(ProviderResponsePayload::ChallengeResponse)
+            }
+            _ => serde_json::from_value(response),
+        };
+        match payload {
+            Ok(payload) => Some(payload),
+            Err(e) => {
+                error!(
+                    "Invalid synthetic response for {} {:?}",
+                    request.capability, e
+                );
+                None
+            }
+        }
+    }
+
     pub async fn invoke_method(
         pst: &PlatformState,
         request: ProviderBrokerRequest,
     ) -> Option<String> {
         let mut provider_app_id = None;
 
+        if let Some(response) = ProviderBroker::get_synthetic_response(pst, &request) {
+            info!(
+                "Answering provider request {} with a synthetic response",
+                request.capability
+            );
+            oneshot_send_and_log(request.tx, response, "SyntheticProviderResponse");
+            return provider_app_id;
+        }
+
+        if pst
+            .notification_policy_state
+            .should_defer(&request.capability)
+        {
+            ProviderBroker::defer_provider_request(pst, request);
+            return provider_app_id;
+        }
+
         let cap_method = format!(
             "{}:{}",
             request.capability,
@@ -297,7 +400,10 @@ impl ProviderBroker {
         let c_id = Uuid::new_v4().to_string();
         let mut active_sessions = pst.provider_broker_state.active_sessions.write().unwrap();
         debug!("started provider session {} {}", c_id, request.capability);
-        active_sessions.insert(
+        // Sessions wait on the provider app or the user, like the methods exempt from the
+        // pending request monitor, so they never expire. They end with the provider response
+        // or when the caller or provider session is unregistered.
+        active_sessions.insert_persistent(
             c_id.clone(),
             ProviderSession {
                 caller: ProviderCaller {
@@ -324,6 +430,88 @@ impl ProviderBroker {
         request_queue.push(request);
     }
 
+    fn defer_provider_request(pst: &PlatformState, request: ProviderBrokerRequest) {
+        let config = pst.notification_policy_state.get_config();
+        ProviderBroker::expire_deferred_requests(pst);
+        let mut deferred_requests = pst.provider_broker_state.deferred_requests.write().unwrap();
+        if deferred_requests.len() >= config.max_deferred_requests {
+            warn!(
+                "invoke_method: Deferred requests full, rejecting {}",
+                request.capability
+            );
+            ProviderBroker::fail_request(
+                request.tx,
+                JSON_RPC_SERVER_ERROR_UNAVAILABLE,
+                "Too many provider requests deferred for do not disturb",
+            );
+            return;
+        }
+        debug!(
+            "deferring provider request {} for do not disturb",
+            request.capability
+        );
+        deferred_requests.push(DeferredRequest {
+            request,
+            deadline: Instant::now() + Duration::from_millis(config.max_deferred_ms),
+        });
+    }
+
+    /// Fails the provider requests which were deferred for longer than `max_deferred_ms`
+    pub fn expire_deferred_requests(pst: &PlatformState) {
+        let now = Instant::now();
+        let expired: Vec<DeferredRequest> = {
+            let mut deferred_requests =
+                pst.provider_broker_state.deferred_requests.write().unwrap();
+            let (expired, waiting) = deferred_requests
+                .drain(..)
+                .partition(|deferred| deferred.deadline <= now);
+            *deferred_requests = waiting;
+            expired
+        };
+        for deferred in expired {
+            warn!(
+                "Provider request {} deferred for do not disturb expired",
+                deferred.request.capability
+            );
+            ProviderBroker::fail_request(
+                deferred.request.tx,
+                JSON_RPC_SERVER_ERROR_DEADLINE_EXCEEDED,
+                "Provider request deferred for do not disturb expired",
+            );
+        }
+    }
+
+    fn fail_request(tx: oneshot::Sender<ProviderResponsePayload>, code: i32, message: &str) {
+        oneshot_send_and_log(
+            tx,
+            ProviderResponsePayload::GenericError(GenericProviderError {
+                code,
+                message: message.to_owned(),
+                data: None,
+            }),
+            "DeferredProviderResponse",
+        );
+    }
+
+    /// Invokes the provider requests which were held back while do not disturb was active
+    pub async fn release_deferred_requests(pst: &PlatformState) {
+        ProviderBroker::expire_deferred_requests(pst);
+        let deferred_requests: Vec<DeferredRequest> = pst
+            .provider_broker_state
+            .deferred_requests
+            .write()
+            .unwrap()
+            .drain(..)
+            .collect();
+        for deferred in deferred_requests {
+            info!(
+                "Invoking provider request {} deferred for do not disturb",
+                deferred.request.capability
+            );
+            ProviderBroker::invoke_method(pst, deferred.request).await;
+        }
+    }
+
     pub async fn provider_response(pst: &PlatformState, resp: ProviderResponse) {
         debug!(
             "provider_response, {}, {:?}",
@@ -438,3 +626,164 @@ impl ProviderBroker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{
+        api::{
+            firebolt::{
+                fb_notification_policy::DoNotDisturbSettings,
+                fb_pin::{PinChallengeRequest, PinChallengeResultReason, PinSpace},
+                provider::{ChallengeRequestor, ChallengeResponse},
+            },
+            manifest::device_manifest::NotificationPolicyConfiguration,
+        },
+        serde_json::json,
+        tokio,
+        utils::expiring_map::DEFAULT_CORRELATION_CAPACITY,
+    };
+    use ripple_tdk::utils::test_utils::Mockable;
+
+    use crate::state::{
+        developer_mode_state::DeveloperModeState,
+        notification_policy_state::NotificationPolicyState,
+    };
+
+    fn get_pin_request(tx: oneshot::Sender<ProviderResponsePayload>) -> ProviderBrokerRequest {
+        ProviderBrokerRequest {
+            capability: "xrn:firebolt:capability:usergrant:pinchallenge".into(),
+            method: "challenge".into(),
+            caller: CallerSession::default(),
+            request: ProviderRequestPayload::PinChallenge(PinChallengeRequest {
+                pin_space: PinSpace::Purchase,
+                requestor: ChallengeRequestor {
+                    id: "app1".into(),
+                    name: "App 1".into(),
+                },
+                capability: None,
+            }),
+            tx,
+            app_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_synthetic_provider_response() {
+        let mut state = PlatformState::mock();
+        state.developer_mode = DeveloperModeState::enabled();
+        ProviderBroker::set_synthetic_response(
+            &state,
+            SetSyntheticProviderParams {
+                capability: "xrn:firebolt:capability:usergrant:pinchallenge".into(),
+                response: Some(json!({ "granted": true, "reason": "correctPin" })),
+            },
+        );
+        let (tx, rx) = oneshot::channel();
+        ProviderBroker::invoke_method(&state, get_pin_request(tx)).await;
+        let response = rx.await.unwrap().as_pin_challenge_response().unwrap();
+        assert_eq!(response.granted, Some(true));
+        assert_eq!(response.reason, PinChallengeResultReason::CorrectPin);
+
+        ProviderBroker::set_synthetic_response(
+            &state,
+            SetSyntheticProviderParams {
+                capability: "xrn:firebolt:capability:usergrant:pinchallenge".into(),
+                response: None,
+            },
+        );
+        let (tx, _rx) = oneshot::channel();
+        let request = get_pin_request(tx);
+        assert!(ProviderBroker::get_synthetic_response(&state, &request).is_none());
+
+        // canned responses are ignored outside developer mode
+        ProviderBroker::set_synthetic_response(
+            &state,
+            SetSyntheticProviderParams {
+                capability: "xrn:firebolt:capability:usergrant:pinchallenge".into(),
+                response: Some(json!({ "granted": true, "reason": "correctPin" })),
+            },
+        );
+        state.developer_mode = DeveloperModeState::default();
+        assert!(ProviderBroker::get_synthetic_response(&state, &request).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_deferred_provider_requests() {
+        let mut state = PlatformState::mock();
+        let mut config = NotificationPolicyConfiguration {
+            deferred_capabilities: vec!["xrn:firebolt:capability:usergrant:pinchallenge".into()],
+            max_deferred_requests: 0,
+            max_deferred_ms: 0,
+        };
+        let get_error = |payload: ProviderResponsePayload| match payload {
+            ProviderResponsePayload::GenericError(e) => e.code,
+            _ => 0,
+        };
+        for max_deferred_requests in [0, 1] {
+            config.max_deferred_requests = max_deferred_requests;
+            state.notification_policy_state = NotificationPolicyState::new(config.clone());
+            state
+                .notification_policy_state
+                .set_settings(DoNotDisturbSettings {
+                    enabled: true,
+                    windows: Vec::new(),
+                });
+            state.notification_policy_state.evaluate(0);
+            let (tx, mut rx) = oneshot::channel();
+            ProviderBroker::invoke_method(&state, get_pin_request(tx)).await;
+            if max_deferred_requests == 0 {
+                // nothing is deferred without room for the request
+                assert_eq!(
+                    get_error(rx.try_recv().unwrap()),
+                    JSON_RPC_SERVER_ERROR_UNAVAILABLE
+                );
+            } else {
+                assert!(rx.try_recv().is_err());
+                ProviderBroker::expire_deferred_requests(&state);
+                assert_eq!(
+                    get_error(rx.try_recv().unwrap()),
+                    JSON_RPC_SERVER_ERROR_DEADLINE_EXCEEDED
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_session_outlives_correlation_ttl() {
+        let mut state = PlatformState::mock();
+        state.provider_broker_state.active_sessions = Arc::new(RwLock::new(ExpiringMap::new(
+            Duration::from_millis(1),
+            DEFAULT_CORRELATION_CAPACITY,
+        )));
+        let (tx, mut rx) = oneshot::channel();
+        let provider = ProviderMethod {
+            event_name: "pinchallenge.onRequestChallenge".into(),
+            provider: CallContext::mock(),
+        };
+        let c_id = ProviderBroker::start_provider_session(&state, get_pin_request(tx), provider);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(
+            state
+                .provider_broker_state
+                .active_sessions
+                .write()
+                .unwrap()
+                .purge_expired(),
+            0
+        );
+        assert!(rx.try_recv().is_err());
+
+        ProviderBroker::provider_response(
+            &state,
+            ProviderResponse {
+                correlation_id: c_id,
+                result: ProviderResponsePayload::ChallengeResponse(ChallengeResponse {
+                    granted: Some(true),
+                }),
+            },
+        )
+        .await;
+        assert!(rx.try_recv().is_ok());
+    }
+}
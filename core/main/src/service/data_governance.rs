@@ -0,0 +1,355 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::HashSet;
+
+use ripple_sdk::{
+    api::{
+        distributor::distributor_privacy::DataEventType,
+        firebolt::fb_telemetry::TelemetryPayload,
+        manifest::device_manifest::{
+            DataGovernanceConfig, DataGovernancePolicy, DataGovernanceSettingTag,
+        },
+        storage_property::StorageProperty,
+    },
+    log::debug,
+};
+
+use crate::{
+    firebolt::handlers::privacy_rpc::PrivacyImpl,
+    processor::storage::storage_manager::StorageManager,
+    state::{platform_state::PlatformState, ripple_cache::RippleCache},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataGovernanceDecision {
+    /// Data can be written upstream along with the tags of the enforced settings
+    Forward(HashSet<String>),
+    /// Every setting in the policy is enforced, the write is accepted and dropped
+    Drop,
+}
+
+/// Central enforcement of the data governance policies in the device manifest.
+///
+/// Handlers writing user data upstream ask for a decision before the write, apps still
+/// receive a success response when the write is dropped.
+pub struct DataGovernance;
+
+impl DataGovernance {
+    /// Policy used for watch history and user interest when the device manifest does not
+    /// configure one
+    fn get_default_policy(data_type: DataEventType) -> Option<DataGovernancePolicy> {
+        match data_type {
+            DataEventType::Interest => Some(DataGovernancePolicy::new(
+                data_type,
+                vec![DataGovernanceSettingTag::new(
+                    StorageProperty::AllowPersonalization,
+                    false,
+                    HashSet::from(["dataPlatform:personalization".to_owned()]),
+                )],
+                true,
+            )),
+            DataEventType::Watched => Some(DataGovernancePolicy::new(
+                data_type,
+                vec![
+                    DataGovernanceSettingTag::new(
+                        StorageProperty::AllowPersonalization,
+                        false,
+                        HashSet::from(["dataPlatform:personalization".to_owned()]),
+                    ),
+                    DataGovernanceSettingTag::new(
+                        StorageProperty::AllowWatchHistory,
+                        false,
+                        HashSet::from(["dataPlatform:watchHistory".to_owned()]),
+                    ),
+                ],
+                true,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn get_policy(
+        state: &PlatformState,
+        data_type: DataEventType,
+    ) -> Option<DataGovernancePolicy> {
+        state
+            .get_device_manifest()
+            .configuration
+            .data_governance
+            .get_policy(data_type.clone())
+            .or_else(|| Self::get_default_policy(data_type))
+    }
+
+    /// Decides whether the write of the app is forwarded. Watch history is dropped when the
+    /// user has not allowed the app to remember watched programs, before the policy applies.
+    pub async fn get_decision(
+        state: &PlatformState,
+        app_id: &str,
+        data_type: DataEventType,
+    ) -> DataGovernanceDecision {
+        if data_type == DataEventType::Watched
+            && !PrivacyImpl::get_allow_watch_history(state, app_id).await
+        {
+            debug!("Watch history is not allowed for {}", app_id);
+            return DataGovernanceDecision::Drop;
+        }
+        let policy = match Self::get_policy(state, data_type.clone()) {
+            Some(policy) => policy,
+            None => return DataGovernanceDecision::Forward(HashSet::new()),
+        };
+        let mut values = Vec::new();
+        for setting_tag in &policy.setting_tags {
+            let value = StorageManager::get_bool(state, setting_tag.setting.clone())
+                .await
+                .unwrap_or(false);
+            values.push(value);
+        }
+        let decision = Self::evaluate(&policy, &values);
+        debug!(
+            "Data governance decision for {:?}: {:?}",
+            data_type, decision
+        );
+        decision
+    }
+
+    /// Reads the settings of the metrics consent categories into the cache, so the consent
+    /// is known before the first telemetry event is emitted
+    pub async fn load_metrics_consent(state: &PlatformState) {
+        for consent in &state.get_data_governance_config().metrics_consent {
+            for setting in &consent.settings {
+                if let Err(e) = StorageManager::get_bool(state, setting.clone()).await {
+                    debug!("Unable to load metrics consent {:?}: {:?}", setting, e);
+                }
+            }
+        }
+    }
+
+    /// Checks the user consent for the category of a telemetry event. Events outside the
+    /// consent categories in the device manifest are always emitted, a setting which has
+    /// not been read from storage yet is treated as not consented.
+    pub fn is_metrics_consented(
+        config: &DataGovernanceConfig,
+        cache: &RippleCache,
+        payload: &TelemetryPayload,
+    ) -> bool {
+        match config.get_metrics_consent(payload.get_schema().name) {
+            Some(consent) => consent.settings.iter().all(|setting| {
+                cache
+                    .get_cached_bool_storage_property(setting)
+                    .unwrap_or(false)
+            }),
+            None => true,
+        }
+    }
+
+    /// Evaluates the policy against the current values of its settings, in the same order
+    /// as the setting tags
+    pub fn evaluate(policy: &DataGovernancePolicy, values: &[bool]) -> DataGovernanceDecision {
+        let mut tags = HashSet::new();
+        let mut all_enforced = !policy.setting_tags.is_empty();
+        for (setting_tag, value) in policy.setting_tags.iter().zip(values) {
+            if *value == setting_tag.enforcement_value {
+                tags.extend(setting_tag.tags.iter().cloned());
+            } else {
+                all_enforced = false;
+            }
+        }
+        if all_enforced && policy.drop_on_all_tags {
+            return DataGovernanceDecision::Drop;
+        }
+        DataGovernanceDecision::Forward(tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{
+        api::{
+            firebolt::fb_telemetry::{AppLoadStop, FireboltEvent, TelemetrySignIn},
+            manifest::device_manifest::MetricsCategoryConsent,
+        },
+        tokio,
+    };
+
+    fn get_config() -> DataGovernanceConfig {
+        DataGovernanceConfig {
+            policies: Vec::new(),
+            metrics_consent: vec![
+                MetricsCategoryConsent {
+                    category: "productAnalytics".to_owned(),
+                    events: vec!["SignIn".to_owned(), "SignOut".to_owned()],
+                    settings: vec![StorageProperty::AllowProductAnalytics],
+                },
+                MetricsCategoryConsent {
+                    category: "businessAnalytics".to_owned(),
+                    events: vec!["AppLoadStop".to_owned()],
+                    settings: vec![
+                        StorageProperty::AllowProductAnalytics,
+                        StorageProperty::AllowBusinessAnalytics,
+                    ],
+                },
+            ],
+        }
+    }
+
+    fn get_sign_in() -> TelemetryPayload {
+        TelemetrySignIn {
+            app_id: "app1".to_owned(),
+            ripple_session_id: "session1".to_owned(),
+            app_session_id: None,
+        }
+        .into()
+    }
+
+    fn get_app_load_stop() -> TelemetryPayload {
+        AppLoadStop {
+            app_id: "app1".to_owned(),
+            stop_time: 0,
+            ripple_session_id: "session1".to_owned(),
+            app_session_id: None,
+            success: true,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_metrics_consent_unknown_setting() {
+        let config = get_config();
+        let cache = RippleCache::default();
+        assert!(!DataGovernance::is_metrics_consented(
+            &config,
+            &cache,
+            &get_sign_in()
+        ));
+    }
+
+    #[test]
+    fn test_metrics_consent_uncategorized_event() {
+        let config = get_config();
+        let cache = RippleCache::default();
+        let payload = TelemetryPayload::FireboltEvent(FireboltEvent {
+            event_name: "device.onNameChanged".to_owned(),
+            result: serde_json::Value::Null,
+        });
+        assert!(DataGovernance::is_metrics_consented(
+            &config, &cache, &payload
+        ));
+    }
+
+    #[test]
+    fn test_metrics_consent_setting_flip() {
+        let config = get_config();
+        let cache = RippleCache::default();
+        cache.update_cached_bool_storage_property(&StorageProperty::AllowProductAnalytics, true);
+        assert!(DataGovernance::is_metrics_consented(
+            &config,
+            &cache,
+            &get_sign_in()
+        ));
+
+        cache.update_cached_bool_storage_property(&StorageProperty::AllowProductAnalytics, false);
+        assert!(!DataGovernance::is_metrics_consented(
+            &config,
+            &cache,
+            &get_sign_in()
+        ));
+
+        cache.update_cached_bool_storage_property(&StorageProperty::AllowProductAnalytics, true);
+        assert!(DataGovernance::is_metrics_consented(
+            &config,
+            &cache,
+            &get_sign_in()
+        ));
+    }
+
+    #[test]
+    fn test_metrics_consent_all_settings_required() {
+        let config = get_config();
+        let cache = RippleCache::default();
+        cache.update_cached_bool_storage_property(&StorageProperty::AllowProductAnalytics, true);
+        cache.update_cached_bool_storage_property(&StorageProperty::AllowBusinessAnalytics, false);
+        assert!(!DataGovernance::is_metrics_consented(
+            &config,
+            &cache,
+            &get_app_load_stop()
+        ));
+        // other categories are not affected
+        assert!(DataGovernance::is_metrics_consented(
+            &config,
+            &cache,
+            &get_sign_in()
+        ));
+
+        cache.update_cached_bool_storage_property(&StorageProperty::AllowBusinessAnalytics, true);
+        assert!(DataGovernance::is_metrics_consented(
+            &config,
+            &cache,
+            &get_app_load_stop()
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_policy() {
+        let policy = DataGovernance::get_default_policy(DataEventType::Watched).unwrap();
+        assert_eq!(
+            DataGovernance::evaluate(&policy, &[true, true]),
+            DataGovernanceDecision::Forward(HashSet::new())
+        );
+        assert_eq!(
+            DataGovernance::evaluate(&policy, &[false, true]),
+            DataGovernanceDecision::Forward(HashSet::from([
+                "dataPlatform:personalization".to_owned()
+            ]))
+        );
+        assert_eq!(
+            DataGovernance::evaluate(&policy, &[false, false]),
+            DataGovernanceDecision::Drop
+        );
+
+        // user interest is dropped when personalization is not allowed
+        let interest = DataGovernance::get_default_policy(DataEventType::Interest).unwrap();
+        assert_eq!(
+            DataGovernance::evaluate(&interest, &[true]),
+            DataGovernanceDecision::Forward(HashSet::new())
+        );
+        assert_eq!(
+            DataGovernance::evaluate(&interest, &[false]),
+            DataGovernanceDecision::Drop
+        );
+
+        let mut policy = policy;
+        policy.drop_on_all_tags = false;
+        match DataGovernance::evaluate(&policy, &[false, false]) {
+            DataGovernanceDecision::Forward(tags) => assert_eq!(tags.len(), 2),
+            DataGovernanceDecision::Drop => panic!("policy should not drop"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_history_not_allowed_for_app() {
+        use ripple_tdk::utils::test_utils::Mockable;
+
+        // a watch history setting which can not be read does not allow the write
+        let state = PlatformState::mock();
+        assert_eq!(
+            DataGovernance::get_decision(&state, "app1", DataEventType::Watched).await,
+            DataGovernanceDecision::Drop
+        );
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::{Arc, OnceLock};
+
+use ripple_sdk::{
+    api::manifest::device_manifest::{DbusBridgeConfiguration, DbusBusType},
+    log::{debug, error, warn},
+    tokio::{
+        self,
+        process::Command,
+        sync::mpsc::{self, error::TrySendError},
+    },
+};
+use serde_json::Value;
+
+/// Signals waiting for dbus-send, further events are dropped until the backlog drains
+const DBUS_SIGNAL_BACKLOG: usize = 64;
+
+/// Mirrors selected Firebolt events onto DBus signals so native daemons can react to
+/// Firebolt state without a websocket connection.
+///
+/// Signals are emitted on the configured object path and interface with the signal
+/// member taken from the manifest mapping. The event name and the JSON encoded event
+/// result are sent as two string arguments.
+#[derive(Debug, Clone, Default)]
+pub struct DbusBridge {
+    config: Arc<DbusBridgeConfiguration>,
+    signals: Arc<OnceLock<mpsc::Sender<Vec<String>>>>,
+}
+
+impl DbusBridge {
+    pub fn new(config: DbusBridgeConfiguration) -> Self {
+        Self {
+            config: Arc::new(config),
+            ..Default::default()
+        }
+    }
+
+    pub fn forward(&self, event_name: &str, result: &Value) {
+        if !self.config.enabled {
+            return;
+        }
+        if let Some(args) = self.get_signal_args(event_name, result) {
+            let signals = self.signals.get_or_init(Self::start_worker);
+            if let Err(TrySendError::Full(args)) = signals.try_send(args) {
+                warn!(
+                    "Dropping dbus signal {:?}, {} signals are waiting",
+                    args, DBUS_SIGNAL_BACKLOG
+                );
+            }
+        }
+    }
+
+    /// Emits the signals one after the other, so a slow bus does not pile up dbus-send
+    /// processes
+    fn start_worker() -> mpsc::Sender<Vec<String>> {
+        let (tx, mut rx) = mpsc::channel::<Vec<String>>(DBUS_SIGNAL_BACKLOG);
+        tokio::spawn(async move {
+            while let Some(args) = rx.recv().await {
+                debug!("Emitting dbus signal {:?}", args);
+                match Command::new("dbus-send").args(&args).status().await {
+                    Ok(status) if !status.success() => {
+                        error!("dbus-send failed for {:?}: {}", args, status)
+                    }
+                    Err(e) => error!("Unable to run dbus-send: {:?}", e),
+                    _ => {}
+                }
+            }
+        });
+        tx
+    }
+
+    fn get_signal_args(&self, event_name: &str, result: &Value) -> Option<Vec<String>> {
+        let signal = self.config.get_signal(event_name)?;
+        let bus = match self.config.bus {
+            DbusBusType::System => "--system",
+            DbusBusType::Session => "--session",
+        };
+        Some(vec![
+            bus.into(),
+            "--type=signal".into(),
+            self.config.object_path.clone(),
+            format!("{}.{}", self.config.interface, signal),
+            format!("string:{}", event_name),
+            format!("string:{}", result),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::manifest::device_manifest::DbusSignalMapping;
+    use serde_json::json;
+
+    fn get_bridge() -> DbusBridge {
+        DbusBridge::new(DbusBridgeConfiguration {
+            enabled: true,
+            mappings: vec![DbusSignalMapping {
+                event: "localization.onLanguageChanged".into(),
+                signal: "LanguageChanged".into(),
+            }],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_get_signal_args() {
+        let bridge = get_bridge();
+        assert!(bridge
+            .get_signal_args("device.onNameChanged", &json!("Living Room"))
+            .is_none());
+        let args = bridge
+            .get_signal_args("localization.onLanguageChanged", &json!({"value": "en"}))
+            .unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "--system",
+                "--type=signal",
+                "/com/rdk/Ripple",
+                "com.rdk.Ripple.Events.LanguageChanged",
+                "string:localization.onLanguageChanged",
+                "string:{\"value\":\"en\"}",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forward_drops_signals_beyond_backlog() {
+        let bridge = get_bridge();
+        // the worker does not get to run before the test yields
+        for _ in 0..DBUS_SIGNAL_BACKLOG + 10 {
+            bridge
+                .clone()
+                .forward("localization.onLanguageChanged", &json!({"value": "en"}));
+        }
+        assert_eq!(bridge.signals.get().unwrap().capacity(), 0);
+    }
+}
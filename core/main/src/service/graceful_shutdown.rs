@@ -0,0 +1,288 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use ripple_sdk::{
+    api::manifest::device_manifest::ShutdownConfiguration,
+    log::{error, info, warn},
+    service::service_message::ServiceMessage,
+    tokio::{
+        self,
+        signal::unix::{signal, SignalKind},
+    },
+    tokio_tungstenite::tungstenite::Message,
+};
+use serde::Deserialize;
+
+use crate::{
+    firebolt::firebolt_gateway::FireboltGatewayCommand,
+    service::{heartbeat::Heartbeat, telemetry_builder::TelemetryBuilder},
+    state::platform_state::PlatformState,
+};
+
+const DRAIN_POLL_INTERVAL_MS: u64 = 100;
+/// Time a service gets to disconnect after `service.unregister` before the next one is asked
+const SERVICE_UNREGISTER_TIMEOUT_MS: u64 = 2000;
+/// Time the launched companion services get to exit once they are stopped
+const SERVICE_STOP_TIMEOUT_MS: u64 = 2000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownParams {
+    pub reason: Option<String>,
+}
+
+/// Shuts Ripple down in a fixed order, triggered by SIGTERM or the `ripple.shutdown` admin
+/// method:
+///
+/// 1. stop accepting app connections
+/// 2. wait for the in-flight app requests to complete
+/// 3. close the app connections with the shutdown reason
+/// 4. flush the device health metrics, the queued watch history and the request journal
+/// 5. unregister the services, each one after the services depending on it disconnected
+/// 6. stop the launched companion services and delete their containers
+///
+/// The gateway is stopped once the sequence is done or the hard deadline passed, the launched
+/// services are stopped in both cases.
+pub struct GracefulShutdown;
+
+impl GracefulShutdown {
+    pub fn listen(state: PlatformState) {
+        tokio::spawn(async move {
+            match signal(SignalKind::terminate()) {
+                Ok(mut sigterm) => {
+                    if sigterm.recv().await.is_some() {
+                        Self::start(state, "SIGTERM");
+                    }
+                }
+                Err(e) => error!("Unable to listen for SIGTERM {:?}", e),
+            }
+        });
+    }
+
+    /// Returns false if a shutdown is already in progress
+    pub fn start(state: PlatformState, reason: &str) -> bool {
+        if !state.shutdown_state.start(reason) {
+            return false;
+        }
+        let config = state.get_device_manifest().get_shutdown_configuration();
+        info!(
+            "Shutting down reason={} deadline_ms={}",
+            reason, config.deadline_ms
+        );
+        tokio::spawn(async move {
+            let deadline = Duration::from_millis(config.deadline_ms);
+            if tokio::time::timeout(deadline, Self::run(&state, &config))
+                .await
+                .is_err()
+            {
+                error!("Shutdown deadline reached, stopping the gateway");
+            }
+            Self::stop_launched_services(&state).await;
+            if let Err(e) = state
+                .get_client()
+                .send_gateway_command(FireboltGatewayCommand::StopServer)
+            {
+                error!("Unable to stop the gateway {:?}", e);
+            }
+        });
+        true
+    }
+
+    async fn run(state: &PlatformState, config: &ShutdownConfiguration) {
+        Self::drain(state, Duration::from_millis(config.drain_timeout_ms)).await;
+        state.shutdown_state.close_connections();
+        Self::flush(state).await;
+        Self::unregister_services(state).await;
+        info!("Shutdown sequence complete");
+    }
+
+    async fn drain(state: &PlatformState, timeout: Duration) {
+        let start = Instant::now();
+        while !state.pending_request_state.is_empty() && start.elapsed() < timeout {
+            tokio::time::sleep(Duration::from_millis(DRAIN_POLL_INTERVAL_MS)).await;
+        }
+        if !state.pending_request_state.is_empty() {
+            warn!(
+                "{} requests still pending after draining for {}ms",
+                state.pending_request_state.len(),
+                timeout.as_millis()
+            );
+        }
+    }
+
+    async fn flush(state: &PlatformState) {
+        let heartbeat = state.get_device_manifest().get_heartbeat_configuration();
+        if heartbeat.enabled {
+            let device_health = Heartbeat::get_device_health(state, &heartbeat);
+            if let Err(e) = TelemetryBuilder::send_event(state, device_health) {
+                error!("send_telemetry={:?}", e)
+            }
+        }
+        state.watch_history_state.flush().await;
+        state.request_journal_state.mark_clean_exit();
+    }
+
+    async fn unregister_services(state: &PlatformState) {
+        let unregister: String =
+            ServiceMessage::new_unregister(state.shutdown_state.get_reason()).into();
+        let timeout = Duration::from_millis(SERVICE_UNREGISTER_TIMEOUT_MS);
+        let controller = &state.service_controller_state;
+        for service_id in Self::get_unregister_order(state).await {
+            let Some(sender) = controller.get_sender(&service_id).await else {
+                continue;
+            };
+            info!("Unregistering service {}", service_id);
+            if let Err(e) = sender.send(Message::Text(unregister.clone())).await {
+                error!("Unable to unregister service {} {:?}", service_id, e);
+                continue;
+            }
+            let start = Instant::now();
+            while controller.get_sender(&service_id).await.is_some() && start.elapsed() < timeout {
+                tokio::time::sleep(Duration::from_millis(DRAIN_POLL_INTERVAL_MS)).await;
+            }
+            if start.elapsed() >= timeout {
+                warn!("Service {} still connected after unregister", service_id);
+            }
+        }
+    }
+
+    async fn stop_launched_services(state: &PlatformState) {
+        state.shutdown_state.stop_services();
+        let launcher_state = &state.service_controller_state.launcher_state;
+        let timeout = Duration::from_millis(SERVICE_STOP_TIMEOUT_MS);
+        let start = Instant::now();
+        while launcher_state.has_running() && start.elapsed() < timeout {
+            tokio::time::sleep(Duration::from_millis(DRAIN_POLL_INTERVAL_MS)).await;
+        }
+        if launcher_state.has_running() {
+            warn!(
+                "Launched services still running {}ms after stopping them",
+                timeout.as_millis()
+            );
+        }
+    }
+
+    /// An extension depends on the extensions fulfilling the contracts it uses and a
+    /// companion service on the services launched before it, as they are launched in
+    /// manifest order.
+    async fn get_unregister_order(state: &PlatformState) -> Vec<String> {
+        let launched: Vec<String> = state
+            .get_device_configuration()
+            .service_launcher
+            .services
+            .iter()
+            .map(|s| s.service_id.clone())
+            .collect();
+        let mut dependencies: HashMap<String, HashSet<String>> = HashMap::new();
+        for (i, service_id) in launched.iter().enumerate() {
+            dependencies
+                .entry(service_id.clone())
+                .or_default()
+                .extend(launched[..i].iter().cloned());
+        }
+        let symbols: Vec<_> = state
+            .extn_manifest
+            .extns
+            .iter()
+            .flat_map(|extn| extn.symbols.iter())
+            .collect();
+        for symbol in &symbols {
+            let used_from = symbols.iter().filter(|other| {
+                other.id != symbol.id && other.fulfills.iter().any(|c| symbol.uses.contains(c))
+            });
+            dependencies
+                .entry(symbol.id.clone())
+                .or_default()
+                .extend(used_from.map(|other| other.id.clone()));
+        }
+        let mut service_ids = state
+            .service_controller_state
+            .get_connected_service_ids()
+            .await;
+        service_ids.extend(launched);
+        Self::get_reverse_dependency_order(service_ids, &dependencies)
+    }
+
+    /// Orders the services so every service comes before the services it depends on. Ties
+    /// and dependency cycles are broken by service id.
+    fn get_reverse_dependency_order(
+        service_ids: Vec<String>,
+        dependencies: &HashMap<String, HashSet<String>>,
+    ) -> Vec<String> {
+        let mut remaining: Vec<String> = service_ids;
+        remaining.sort();
+        remaining.dedup();
+        let mut order = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            let has_dependents = |id: &String| {
+                remaining.iter().any(|other| {
+                    other != id && dependencies.get(other).is_some_and(|d| d.contains(id))
+                })
+            };
+            let next = remaining
+                .iter()
+                .position(|id| !has_dependents(id))
+                .unwrap_or(0);
+            order.push(remaining.remove(next));
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reverse_dependency_order() {
+        let dependencies: HashMap<String, HashSet<String>> = HashMap::from([
+            // the distributor extension uses a contract of the device extension
+            ("distributor".into(), HashSet::from(["device".into()])),
+            // companion services launched in manifest order
+            ("launched2".into(), HashSet::from(["launched1".into()])),
+            ("cycle1".into(), HashSet::from(["cycle2".into()])),
+            ("cycle2".into(), HashSet::from(["cycle1".into()])),
+        ]);
+        let service_ids = [
+            "device",
+            "launched1",
+            "distributor",
+            "launched2",
+            "cycle2",
+            "cycle1",
+            "device",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(
+            GracefulShutdown::get_reverse_dependency_order(service_ids, &dependencies),
+            vec![
+                "distributor",
+                "device",
+                "launched2",
+                "launched1",
+                "cycle1",
+                "cycle2"
+            ]
+        );
+    }
+}
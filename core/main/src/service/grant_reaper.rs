@@ -0,0 +1,134 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::time::Duration;
+
+use ripple_sdk::{
+    api::{
+        device::device_user_grants_data::{GrantEntry, GrantStateModify},
+        firebolt::fb_capabilities::{CapEvent, FireboltCap, FireboltPermission},
+    },
+    chrono::Utc,
+    log::{debug, info},
+    tokio,
+};
+
+use crate::{
+    service::user_grants::GrantState,
+    state::{cap::cap_state::CapState, platform_state::PlatformState},
+};
+
+/// Revokes app grants which were not used within the configured period. The app gets a
+/// `capabilities.onRevoked` event so it can request the grant again when it needs it.
+pub struct GrantReaper;
+
+impl GrantReaper {
+    pub fn start(state: PlatformState) {
+        let config = state
+            .get_device_manifest()
+            .get_capability_usage_configuration();
+        if !config.reaper_enabled {
+            return;
+        }
+        info!(
+            "Reaping grants unused for {}s every {}s",
+            config.unused_grant_ttl_seconds, config.reaper_interval_seconds
+        );
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(config.reaper_interval_seconds.max(1)));
+            // the first tick completes immediately
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                Self::reap(&state, config.unused_grant_ttl_seconds).await;
+            }
+        });
+    }
+
+    /// A grant which was never used counts from the time it was granted
+    pub fn is_unused(
+        entry: &GrantEntry,
+        last_used: Option<i64>,
+        now: i64,
+        ttl_seconds: u64,
+    ) -> bool {
+        let last_active = last_used
+            .unwrap_or_default()
+            .max(entry.last_modified_time.as_millis() as i64);
+        now - last_active > (ttl_seconds * 1000) as i64
+    }
+
+    async fn reap(state: &PlatformState, ttl_seconds: u64) {
+        let now = Utc::now().timestamp_millis();
+        for (app_id, entry) in state.cap_state.grant_state.get_allowed_app_entries() {
+            let permission = FireboltPermission {
+                cap: FireboltCap::Full(entry.capability.clone()),
+                role: entry.role,
+            };
+            let last_used = state.cap_state.get_last_used(&app_id, &permission);
+            if !Self::is_unused(&entry, last_used, now, ttl_seconds) {
+                continue;
+            }
+            debug!(
+                "Revoking unused grant {} for app {}",
+                entry.capability, app_id
+            );
+            if GrantState::grant_modify(
+                state,
+                GrantStateModify::Clear,
+                Some(app_id.clone()),
+                entry.role,
+                entry.capability.clone(),
+            )
+            .await
+            {
+                state.cap_state.clear_last_used(&app_id, &permission);
+                CapState::emit(
+                    state,
+                    &CapEvent::OnRevoked,
+                    permission.cap,
+                    Some(permission.role),
+                )
+                .await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::firebolt::fb_capabilities::CapabilityRole;
+
+    #[test]
+    fn test_is_unused() {
+        let entry = GrantEntry {
+            role: CapabilityRole::Use,
+            capability: "xrn:firebolt:capability:device:info".into(),
+            status: None,
+            lifespan: None,
+            last_modified_time: Duration::from_millis(1000),
+            lifespan_ttl_in_secs: None,
+        };
+        // granted at 1s and ttl of 10s
+        assert!(!GrantReaper::is_unused(&entry, None, 11000, 10));
+        assert!(GrantReaper::is_unused(&entry, None, 11001, 10));
+        assert!(!GrantReaper::is_unused(&entry, Some(5000), 11001, 10));
+        assert!(GrantReaper::is_unused(&entry, Some(5000), 15001, 10));
+    }
+}
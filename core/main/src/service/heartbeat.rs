@@ -0,0 +1,137 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::time::Duration;
+
+use ripple_sdk::{
+    api::{
+        firebolt::fb_telemetry::DeviceHealth,
+        manifest::device_manifest::{DeviceHealthField, HeartbeatConfiguration},
+        observability::device_health::get_memory_rss_kb,
+    },
+    chrono::Utc,
+    log::error,
+    tokio,
+};
+
+use crate::{service::telemetry_builder::TelemetryBuilder, state::platform_state::PlatformState};
+
+include!(concat!(env!("OUT_DIR"), "/version.rs"));
+
+/// Sends a device health event through the metrics pipeline on the interval configured in
+/// the device manifest.
+pub struct Heartbeat;
+
+impl Heartbeat {
+    pub fn start(state: PlatformState) {
+        let config = state.get_device_manifest().get_heartbeat_configuration();
+        if !config.enabled {
+            return;
+        }
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(config.interval_seconds.max(1)));
+            // the first tick completes immediately
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let device_health = Self::get_device_health(&state, &config);
+                if let Err(e) = TelemetryBuilder::send_event(&state, device_health) {
+                    error!("send_telemetry={:?}", e)
+                }
+            }
+        });
+    }
+
+    pub fn get_device_health(
+        state: &PlatformState,
+        config: &HeartbeatConfiguration,
+    ) -> DeviceHealth {
+        let mut device_health = DeviceHealth {
+            ripple_session_id: state.metrics.get_device_session_id(),
+            ripple_version: state
+                .version
+                .clone()
+                .unwrap_or(String::from(SEMVER_LIGHTWEIGHT)),
+            ..Default::default()
+        };
+        if config.has_field(DeviceHealthField::Uptime) {
+            device_health.uptime_ms =
+                Some((Utc::now() - state.metrics.start_time).num_milliseconds());
+        }
+        if config.has_field(DeviceHealthField::Memory) {
+            device_health.memory_rss_kb = get_memory_rss_kb();
+        }
+        if config.has_field(DeviceHealthField::BrokerHealth) {
+            device_health.broker_health = Some(state.endpoint_state.get_endpoint_health());
+        }
+        if config.has_field(DeviceHealthField::Reconnects) {
+            device_health.reconnect_count = Some(state.metrics.get_reconnect_count());
+        }
+        if config.has_field(DeviceHealthField::ErrorRate) {
+            let (rpc_count, rpc_error_count) = state.metrics.take_rpc_counts();
+            device_health.rpc_count = Some(rpc_count);
+            device_health.rpc_error_rate = Some(if rpc_count > 0 {
+                rpc_error_count as f64 / rpc_count as f64
+            } else {
+                0.0
+            });
+        }
+        if config.has_field(DeviceHealthField::ExtnUsage) {
+            device_health.extn_usage = Some(state.get_client().get_extn_client().get_extn_usage());
+        }
+        if config.has_field(DeviceHealthField::AppUsage) && state.app_usage_state.is_enabled() {
+            device_health.app_usage = Some(state.app_usage_state.get_usage(None));
+        }
+        if config.has_field(DeviceHealthField::ServiceConnections) {
+            device_health.service_connection_events =
+                Some(state.metrics.take_service_connection_events());
+        }
+        device_health
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_tdk::utils::test_utils::Mockable;
+
+    #[test]
+    fn test_get_device_health() {
+        let state = PlatformState::mock();
+        state.metrics.record_rpc_result(true);
+        state.metrics.record_rpc_result(false);
+        state.metrics.record_reconnect();
+
+        let config = HeartbeatConfiguration {
+            enabled: true,
+            interval_seconds: 60,
+            fields: vec![DeviceHealthField::Reconnects, DeviceHealthField::ErrorRate],
+        };
+        let device_health = Heartbeat::get_device_health(&state, &config);
+        assert!(device_health.uptime_ms.is_none());
+        assert!(device_health.broker_health.is_none());
+        assert_eq!(device_health.reconnect_count, Some(1));
+        assert_eq!(device_health.rpc_count, Some(2));
+        assert_eq!(device_health.rpc_error_rate, Some(0.5));
+
+        // rpc counts are reset after every heartbeat
+        let device_health = Heartbeat::get_device_health(&state, &config);
+        assert_eq!(device_health.rpc_count, Some(0));
+        assert_eq!(device_health.reconnect_count, Some(1));
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use ripple_sdk::{
+    api::{
+        apps::{AppMethod, AppRequest},
+        device::device_events::{InactivityStatus, INACTIVITY_CHANGED_EVENT},
+        firebolt::fb_lifecycle::LifecycleState,
+    },
+    log::{debug, info},
+    tokio,
+};
+use serde_json::json;
+
+use crate::{
+    service::apps::app_events::AppEvents,
+    state::{inactivity_state::InactivityAction, platform_state::PlatformState},
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Emits `device.onInactivityChanged` to system apps when the device becomes idle or active
+/// and asks the app manager to suspend background apps after a prolonged idle period.
+pub struct InactivityMonitor;
+
+impl InactivityMonitor {
+    pub fn start(state: PlatformState) {
+        let config = state.get_device_manifest().get_inactivity_configuration();
+        if !config.enabled {
+            return;
+        }
+        info!(
+            "Monitoring inactivity idle_threshold={}s",
+            config.idle_threshold_seconds
+        );
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                match state.inactivity_state.check(&config, Instant::now()) {
+                    Some(InactivityAction::Idle(idle_duration)) => {
+                        Self::emit(&state, true, idle_duration).await
+                    }
+                    Some(InactivityAction::SuspendBackground) => Self::suspend_background(&state),
+                    None => {}
+                }
+            }
+        });
+    }
+
+    /// Called for user input and app lifecycle activity
+    pub async fn on_activity(state: &PlatformState) {
+        if !state.get_device_configuration().inactivity.enabled {
+            return;
+        }
+        if state.inactivity_state.record_activity(Instant::now()) {
+            Self::emit(state, false, Duration::ZERO).await;
+        }
+    }
+
+    async fn emit(state: &PlatformState, idle: bool, idle_duration: Duration) {
+        debug!("Device idle={} after {:?}", idle, idle_duration);
+        let status = InactivityStatus {
+            idle,
+            idle_seconds: idle_duration.as_secs(),
+        };
+        AppEvents::emit(state, INACTIVITY_CHANGED_EVENT, &json!(status)).await;
+    }
+
+    fn suspend_background(state: &PlatformState) {
+        let app_ids = state
+            .app_manager_state
+            .get_app_ids_in_state(LifecycleState::Background);
+        info!("Suspending background apps after idle {:?}", app_ids);
+        for app_id in app_ids {
+            // fire and forget, the app manager reports the transition to the app
+            let request = AppRequest {
+                method: AppMethod::SetState(app_id, LifecycleState::Suspended),
+                resp_tx: Arc::new(RwLock::new(None)),
+            };
+            let _ = state.get_client().send_app_request(request);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::chrono::Utc;
+use serde_json::{json, Value};
+
+use crate::{
+    service::{
+        apps::app_events::AppEvents,
+        diagnostics_bundle::{DiagnosticsBundle, REDACTED, SECRET_KEY_PATTERNS},
+    },
+    state::{
+        inspector_state::{FrameDirection, InspectorFrame, INSPECTOR_EVENT_ON_FRAME},
+        platform_state::PlatformState,
+    },
+};
+
+/// Keys holding personal data which are redacted along with the secrets before a frame is
+/// mirrored
+const PII_KEY_PATTERNS: &[&str] = &[
+    "email",
+    "postalcode",
+    "latlon",
+    "locality",
+    "accountid",
+    "householdid",
+    "deviceid",
+    "serialnumber",
+    "macaddress",
+];
+
+/// Methods whose whole result is personal data or a secret. Their results are usually
+/// scalars which can not be matched by key, either exact or ending with `*`.
+const PII_METHODS: &[&str] = &[
+    "device.uid",
+    "device.id",
+    "account.id",
+    "account.uid",
+    "authentication.*",
+    "localization.postalcode",
+    "localization.onpostalcodechanged",
+    "localization.latlon",
+    "localization.locality",
+    "localization.onlocalitychanged",
+];
+
+/// Mirrors the Firebolt frames of inspected app sessions to the developer tools listening on
+/// `ripple.onInspectorFrame`.
+pub struct Inspector;
+
+impl Inspector {
+    fn is_pii_method(method: &str) -> bool {
+        let method = method.to_lowercase();
+        PII_METHODS
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => method.starts_with(prefix),
+                None => method.eq(pattern),
+            })
+    }
+
+    /// Redacts secrets and personal data by key, and the whole result or event payload of
+    /// the methods which return them as a value.
+    fn redact(payload: &mut Value, direction: FrameDirection, method: Option<&str>) {
+        if method.is_some_and(Self::is_pii_method) {
+            if let Some(result) = payload.get_mut("result") {
+                *result = Value::String(REDACTED.to_owned());
+            }
+            if direction != FrameDirection::Request {
+                if let Some(params) = payload.get_mut("params") {
+                    *params = Value::String(REDACTED.to_owned());
+                }
+            }
+        }
+        DiagnosticsBundle::redact_keys(payload, SECRET_KEY_PATTERNS);
+        DiagnosticsBundle::redact_keys(payload, PII_KEY_PATTERNS);
+    }
+
+    /// Mirrors a frame received from or sent to the app session. Frames of inspector sessions
+    /// are never mirrored so an inspector can not feed its own frames back.
+    pub async fn mirror(
+        state: &PlatformState,
+        session_id: &str,
+        app_id: &str,
+        inbound: bool,
+        frame: &str,
+    ) {
+        let inspector_state = &state.inspector_state;
+        if !inspector_state.is_inspected(app_id) || inspector_state.is_inspector(session_id) {
+            return;
+        }
+        let Ok(mut payload) = serde_json::from_str::<Value>(frame) else {
+            return;
+        };
+        let id = payload.get("id").map(|id| id.to_string());
+        let method = payload
+            .get("method")
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        let (direction, method) = match (inbound, method, id) {
+            (true, Some(method), Some(id)) => {
+                let listen = payload
+                    .get("params")
+                    .and_then(|params| params.get("listen"))
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                inspector_state.record_request(session_id, id, method.clone(), listen);
+                (FrameDirection::Request, Some(method))
+            }
+            (true, method, _) => (FrameDirection::Request, method),
+            (false, Some(method), _) => (FrameDirection::Event, Some(method)),
+            (false, None, Some(id)) => inspector_state.resolve_response(session_id, id),
+            (false, None, None) => (FrameDirection::Response, None),
+        };
+        Self::redact(&mut payload, direction, method.as_deref());
+        let frame = json!(InspectorFrame {
+            app_id: app_id.to_owned(),
+            direction,
+            method: method.clone(),
+            timestamp: Utc::now().timestamp_millis(),
+            payload,
+        });
+        let listeners = AppEvents::get_listeners(
+            &state.app_events_state,
+            INSPECTOR_EVENT_ON_FRAME,
+            Some(json!(app_id).to_string()),
+        );
+        for listener in listeners {
+            if inspector_state.matches(&listener.call_ctx.session_id, method.as_deref()) {
+                AppEvents::send_event(&listener, &frame).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_by_method() {
+        let mut payload = json!({"jsonrpc": "2.0", "id": 1, "result": "device-uid"});
+        Inspector::redact(&mut payload, FrameDirection::Response, Some("Device.uid"));
+        assert_eq!(payload["result"], json!(REDACTED));
+
+        let mut payload = json!({"jsonrpc": "2.0", "id": 2, "result": "19103"});
+        Inspector::redact(
+            &mut payload,
+            FrameDirection::Event,
+            Some("localization.onPostalCodeChanged"),
+        );
+        assert_eq!(payload["result"], json!(REDACTED));
+
+        let mut payload = json!({"jsonrpc": "2.0", "id": 3, "result": "Living Room"});
+        Inspector::redact(&mut payload, FrameDirection::Response, Some("device.name"));
+        assert_eq!(payload["result"], json!("Living Room"));
+
+        let mut payload = json!({"jsonrpc": "2.0", "id": 4, "result": {"deviceId": "id"}});
+        Inspector::redact(&mut payload, FrameDirection::Response, None);
+        assert_eq!(payload["result"]["deviceId"], json!(REDACTED));
+    }
+}
@@ -15,8 +15,26 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+pub mod activation_orchestrator;
+pub mod admin_schema;
 pub mod apps;
+pub mod data_governance;
+pub mod dbus_bridge;
+pub mod diagnostics_bundle;
 pub mod extn;
+pub mod graceful_shutdown;
+pub mod grant_reaper;
+pub mod heartbeat;
+pub mod inactivity_monitor;
+pub mod inspector;
+pub mod notification_policy;
+pub mod pending_request_monitor;
+pub mod ripple_info;
 pub mod ripple_service;
+pub mod secure_element;
+pub mod soft_restart;
+pub mod state_snapshot;
+pub mod store_integrity;
 pub mod telemetry_builder;
 pub mod user_grants;
+pub mod watch_history;
@@ -0,0 +1,125 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::time::Duration;
+
+use jsonrpsee::core::RpcResult;
+use ripple_sdk::{
+    api::{
+        firebolt::fb_notification_policy::{
+            DoNotDisturbSettings, NOTIFICATION_POLICY_EVENT_ON_DO_NOT_DISTURB_CHANGED,
+        },
+        storage_property::StorageProperty,
+    },
+    chrono::{Local, Timelike},
+    log::{debug, info},
+    tokio,
+};
+use serde_json::json;
+
+use crate::{
+    processor::storage::storage_manager::StorageManager,
+    service::apps::{app_events::AppEvents, provider_broker::ProviderBroker},
+    state::platform_state::PlatformState,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Applies the do not disturb settings, emits `notifications.onDoNotDisturbChanged` when do
+/// not disturb starts or ends and releases the provider requests deferred in the meantime.
+/// Deferred requests past their deadline are failed on every check.
+pub struct NotificationPolicy;
+
+impl NotificationPolicy {
+    pub fn start(state: PlatformState) {
+        tokio::spawn(async move {
+            Self::load(&state).await;
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                Self::evaluate(&state).await;
+                ProviderBroker::expire_deferred_requests(&state);
+            }
+        });
+    }
+
+    async fn load(state: &PlatformState) {
+        let enabled = StorageManager::get_bool(state, StorageProperty::DoNotDisturbEnabled)
+            .await
+            .unwrap_or(false);
+        let windows = StorageManager::get_string(state, StorageProperty::DoNotDisturbWindows)
+            .await
+            .ok()
+            .and_then(|windows| serde_json::from_str(&windows).ok())
+            .unwrap_or_default();
+        let settings = DoNotDisturbSettings { enabled, windows };
+        info!("Loaded do not disturb settings {:?}", settings);
+        state.notification_policy_state.set_settings(settings);
+    }
+
+    /// Persists the settings and applies them right away
+    pub async fn set_settings(
+        state: &PlatformState,
+        settings: DoNotDisturbSettings,
+    ) -> RpcResult<()> {
+        StorageManager::set_bool(
+            state,
+            StorageProperty::DoNotDisturbEnabled,
+            settings.enabled,
+            None,
+        )
+        .await?;
+        StorageManager::set_string(
+            state,
+            StorageProperty::DoNotDisturbWindows,
+            json!(settings.windows).to_string(),
+            None,
+        )
+        .await?;
+        state.notification_policy_state.set_settings(settings);
+        // the settings are reported even when the status stays the same
+        if !Self::evaluate(state).await {
+            Self::emit(state).await;
+        }
+        Ok(())
+    }
+
+    /// Returns true if do not disturb started or ended
+    async fn evaluate(state: &PlatformState) -> bool {
+        let now = Local::now();
+        let minute_of_day = now.hour() * 60 + now.minute();
+        let Some(active) = state.notification_policy_state.evaluate(minute_of_day) else {
+            return false;
+        };
+        debug!("Do not disturb active={}", active);
+        Self::emit(state).await;
+        if !active {
+            ProviderBroker::release_deferred_requests(state).await;
+        }
+        true
+    }
+
+    async fn emit(state: &PlatformState) {
+        let status = state.notification_policy_state.get_status();
+        AppEvents::emit(
+            state,
+            NOTIFICATION_POLICY_EVENT_ON_DO_NOT_DISTURB_CHANGED,
+            &json!(status),
+        )
+        .await;
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::time::{Duration, Instant};
+
+use ripple_sdk::{api::observability::log_signal::LogSignal, log::info, tokio};
+
+use crate::{
+    firebolt::firebolt_gateway::{send_json_rpc_error, JsonRpcError},
+    state::{pending_request_state::PendingRequest, platform_state::PlatformState},
+};
+
+/// JSON-RPC internal error
+const STUCK_REQUEST_ERROR_CODE: i32 = -32603;
+
+/// Fails app requests which never got a response. A call pending for longer than its
+/// configured threshold gets an error response, and the broker correlation entries held for
+/// it are freed.
+pub struct PendingRequestMonitor;
+
+impl PendingRequestMonitor {
+    pub fn start(state: PlatformState) {
+        let config = &state.get_device_configuration().pending_requests;
+        if !config.enabled {
+            return;
+        }
+        let check_interval = Duration::from_secs(config.check_interval_seconds.max(1));
+        info!("Failing stuck requests every {}s", check_interval.as_secs());
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(check_interval);
+            loop {
+                interval.tick().await;
+                let stuck = state.pending_request_state.get_stuck(Instant::now());
+                for pending in stuck {
+                    Self::fail(state.clone(), pending).await;
+                }
+            }
+        });
+    }
+
+    async fn fail(mut state: PlatformState, pending: PendingRequest) {
+        let request = pending.request;
+        // the response may have gone out since the check
+        if !state
+            .pending_request_state
+            .complete(&request.ctx.request_id)
+        {
+            return;
+        }
+        let message = format!("{} did not respond in time", request.method);
+        let brokered = state
+            .endpoint_state
+            .fail_requests(&request.ctx.request_id, &message)
+            .await;
+        if !brokered.is_empty() {
+            state
+                .service_controller_state
+                .forget_broker_requests(&brokered)
+                .await;
+        }
+        LogSignal::new(
+            "pending_request_monitor".into(),
+            "stuck request failed".into(),
+            request.clone(),
+        )
+        .with_diagnostic_context_item("age_ms", &pending.started.elapsed().as_millis().to_string())
+        .with_diagnostic_context_item("brokered", &brokered.len().to_string())
+        .emit_error();
+
+        // brokered requests get their error through the broker output
+        if brokered.is_empty() {
+            send_json_rpc_error(
+                &mut state,
+                &request,
+                JsonRpcError {
+                    code: STUCK_REQUEST_ERROR_CODE,
+                    message,
+                    data: None,
+                },
+            )
+            .await;
+            state.metrics.remove_api_stats(&request.ctx.request_id);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{service::diagnostics_bundle::DiagnosticsBundle, state::platform_state::PlatformState};
+
+include!(concat!(env!("OUT_DIR"), "/version.rs"));
+
+/// Cargo features of main which change the behavior of the build
+const BUILD_FEATURES: &[(&str, bool)] = &[
+    ("local_dev", cfg!(feature = "local_dev")),
+    ("sysd", cfg!(feature = "sysd")),
+    ("pre_prod", cfg!(feature = "pre_prod")),
+    ("tdk", cfg!(feature = "tdk")),
+    ("openrpc_validation", cfg!(feature = "openrpc_validation")),
+    ("tls", cfg!(feature = "tls")),
+    ("contract_tests", cfg!(feature = "contract_tests")),
+];
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_hash: String,
+    pub build_timestamp: String,
+    pub target: String,
+    pub profile: String,
+    pub features: Vec<String>,
+}
+
+/// Build metadata and the effective configuration of the running Ripple, with secrets
+/// redacted, returned by `ripple.info`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RippleInfo {
+    pub build: BuildInfo,
+    /// Hash of each loaded manifest, after cascading, overlays and redaction
+    pub manifest_hashes: HashMap<String, String>,
+    pub device_manifest: Value,
+    pub extn_manifest: Value,
+}
+
+impl RippleInfo {
+    pub fn collect(state: &PlatformState) -> RippleInfo {
+        let mut device_manifest =
+            serde_json::to_value(state.get_device_manifest()).unwrap_or_default();
+        let mut extn_manifest = serde_json::to_value(state.get_manifest()).unwrap_or_default();
+        // hashed after redaction, a hash of the secrets would allow guessing them offline
+        DiagnosticsBundle::redact(&mut device_manifest);
+        DiagnosticsBundle::redact(&mut extn_manifest);
+        let manifest_hashes = HashMap::from([
+            ("device".to_owned(), Self::get_hash(&device_manifest)),
+            ("extn".to_owned(), Self::get_hash(&extn_manifest)),
+        ]);
+        RippleInfo {
+            build: Self::get_build_info(state),
+            manifest_hashes,
+            device_manifest,
+            extn_manifest,
+        }
+    }
+
+    fn get_build_info(state: &PlatformState) -> BuildInfo {
+        BuildInfo {
+            version: state
+                .version
+                .clone()
+                .unwrap_or(String::from(SEMVER_LIGHTWEIGHT)),
+            git_hash: SHA.to_owned(),
+            build_timestamp: COMPILE_TIME.to_owned(),
+            target: TARGET_TRIPLE.to_owned(),
+            profile: if cfg!(debug_assertions) {
+                "debug".to_owned()
+            } else {
+                "release".to_owned()
+            },
+            features: BUILD_FEATURES
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        }
+    }
+
+    /// FNV-1a of the serialized value. Object keys serialize in sorted order, so equal
+    /// manifests give equal hashes across runs and builds.
+    fn get_hash(value: &Value) -> String {
+        let hash = value
+            .to_string()
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, b| {
+                (hash ^ b as u64).wrapping_mul(0x100000001b3)
+            });
+        format!("{:016x}", hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_manifest_hash() {
+        let hash = RippleInfo::get_hash(&json!({"a": 1, "b": [true]}));
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash, RippleInfo::get_hash(&json!({"b": [true], "a": 1})));
+        assert_ne!(hash, RippleInfo::get_hash(&json!({"a": 2, "b": [true]})));
+    }
+}
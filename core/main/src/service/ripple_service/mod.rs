@@ -15,5 +15,10 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+pub mod oci_launcher;
+pub mod service_auth;
 pub mod service_controller_state;
+pub mod service_http;
+pub mod service_launcher;
 pub mod service_registry;
+pub mod service_subscriptions;
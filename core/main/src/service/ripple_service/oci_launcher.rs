@@ -0,0 +1,240 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ripple_sdk::{
+    api::manifest::device_manifest::{CompanionServiceConfiguration, OciLaunchConfiguration},
+    log::{error, info},
+    tokio::{self, process::Command},
+    utils::error::RippleError,
+};
+use serde_json::{json, Value};
+
+/// Launcher backend which starts a companion service through an OCI runtime.
+///
+/// Ripple Main generates a runtime bundle from the base `config.json` in the
+/// configured bundle with the service args, env, bind mounts and resource limits
+/// from the device manifest applied on top.
+pub struct OciLauncher;
+
+impl OciLauncher {
+    pub fn get_container_id(config: &CompanionServiceConfiguration) -> String {
+        config.service_id.replace(':', "_")
+    }
+
+    pub async fn command(
+        config: &CompanionServiceConfiguration,
+        oci: &OciLaunchConfiguration,
+    ) -> Result<Command, RippleError> {
+        let container_id = Self::get_container_id(config);
+        let runtime_bundle = {
+            let (config, oci, container_id) = (config.clone(), oci.clone(), container_id.clone());
+            tokio::task::spawn_blocking(move || {
+                Self::write_runtime_bundle(&config, &oci, &container_id)
+            })
+            .await
+            .map_err(|_| RippleError::InvalidOutput)??
+        };
+
+        // Clear out a container left behind by a previous run with the same id
+        Self::delete(oci, &container_id).await;
+
+        info!(
+            "Launching service {} in container {} runtime={}",
+            config.service_id, container_id, oci.runtime
+        );
+        let mut command = Command::new(&oci.runtime);
+        command
+            .arg("run")
+            .arg("--bundle")
+            .arg(runtime_bundle)
+            .arg(container_id)
+            .kill_on_drop(true);
+        Ok(command)
+    }
+
+    /// Writes the runtime bundle of the container to the state dir, blocking on the file system
+    fn write_runtime_bundle(
+        config: &CompanionServiceConfiguration,
+        oci: &OciLaunchConfiguration,
+        container_id: &str,
+    ) -> Result<PathBuf, RippleError> {
+        let bundle_path = Path::new(&oci.bundle);
+        let base = fs::read_to_string(bundle_path.join("config.json"))
+            .map_err(|_| RippleError::MissingInput)?;
+        let base: Value = serde_json::from_str(&base).map_err(|_| RippleError::ParseError)?;
+        let spec = Self::build_runtime_spec(base, config, oci);
+
+        let runtime_bundle = Path::new(&oci.state_dir).join(container_id);
+        fs::create_dir_all(&runtime_bundle).map_err(|_| RippleError::InvalidOutput)?;
+        fs::write(
+            runtime_bundle.join("config.json"),
+            serde_json::to_string_pretty(&spec).map_err(|_| RippleError::ParseError)?,
+        )
+        .map_err(|_| RippleError::InvalidOutput)?;
+        Ok(runtime_bundle)
+    }
+
+    pub fn build_runtime_spec(
+        mut spec: Value,
+        config: &CompanionServiceConfiguration,
+        oci: &OciLaunchConfiguration,
+    ) -> Value {
+        // rootfs stays in the original bundle
+        let root = spec
+            .pointer("/root/path")
+            .and_then(|p| p.as_str())
+            .unwrap_or("rootfs")
+            .to_owned();
+        let root_path = Path::new(&oci.bundle).join(root);
+        spec["root"]["path"] = json!(root_path.to_string_lossy());
+
+        let mut args = vec![config.path.clone()];
+        args.extend(config.args.iter().cloned());
+        spec["process"]["args"] = json!(args);
+
+        let mut env: Vec<Value> = spec
+            .pointer("/process/env")
+            .and_then(|e| e.as_array())
+            .cloned()
+            .unwrap_or_default();
+        for (key, value) in &config.env {
+            env.push(json!(format!("{}={}", key, value)));
+        }
+        spec["process"]["env"] = json!(env);
+
+        let mut mounts: Vec<Value> = spec
+            .get("mounts")
+            .and_then(|m| m.as_array())
+            .cloned()
+            .unwrap_or_default();
+        for mount in &oci.bind_mounts {
+            mounts.push(json!({
+                "destination": mount,
+                "type": "bind",
+                "source": mount,
+                "options": ["rbind", "rw"]
+            }));
+        }
+        spec["mounts"] = json!(mounts);
+
+        if oci.host_network {
+            if let Some(namespaces) = spec
+                .pointer_mut("/linux/namespaces")
+                .and_then(|n| n.as_array_mut())
+            {
+                namespaces.retain(|ns| ns.get("type") != Some(&json!("network")));
+            }
+        }
+
+        if let Some(limit) = oci.memory_limit_bytes {
+            spec["linux"]["resources"]["memory"]["limit"] = json!(limit);
+        }
+        if let Some(shares) = oci.cpu_shares {
+            spec["linux"]["resources"]["cpu"]["shares"] = json!(shares);
+        }
+        if let Some(limit) = oci.pids_limit {
+            spec["linux"]["resources"]["pids"]["limit"] = json!(limit);
+        }
+        spec
+    }
+
+    /// Stops a running container, the supervisor restarts it once the runtime exits
+    pub async fn kill(oci: &OciLaunchConfiguration, container_id: &str) {
+        if let Err(e) = Command::new(&oci.runtime)
+            .arg("kill")
+            .arg(container_id)
+            .arg("SIGTERM")
+            .status()
+            .await
+        {
+            error!("Failed to kill container {}: {:?}", container_id, e);
+        }
+    }
+
+    pub async fn delete(oci: &OciLaunchConfiguration, container_id: &str) {
+        if let Err(e) = Command::new(&oci.runtime)
+            .arg("delete")
+            .arg("--force")
+            .arg(container_id)
+            .status()
+            .await
+        {
+            error!("Failed to delete container {}: {:?}", container_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_build_runtime_spec() {
+        let oci = OciLaunchConfiguration {
+            runtime: "crun".into(),
+            bundle: "/opt/bundles/service1".into(),
+            state_dir: "/tmp/ripple/oci".into(),
+            bind_mounts: vec!["/tmp/ripple.sock".into()],
+            host_network: true,
+            memory_limit_bytes: Some(1024),
+            cpu_shares: None,
+            pids_limit: Some(16),
+        };
+        let config = CompanionServiceConfiguration {
+            service_id: "ripple:channel:gateway:service1".into(),
+            path: "/usr/bin/service1".into(),
+            args: vec!["--verbose".into()],
+            env: HashMap::from([("RUST_LOG".to_string(), "debug".to_string())]),
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30000,
+            max_restarts: None,
+            registration_timeout_ms: 10000,
+            oci: Some(oci.clone()),
+        };
+        let base = json!({
+            "root": {"path": "rootfs"},
+            "process": {"args": ["sh"], "env": ["PATH=/usr/bin"]},
+            "linux": {"namespaces": [{"type": "pid"}, {"type": "network"}]}
+        });
+
+        let spec = OciLauncher::build_runtime_spec(base, &config, &oci);
+        assert_eq!(
+            OciLauncher::get_container_id(&config),
+            "ripple_channel_gateway_service1"
+        );
+        assert_eq!(spec["root"]["path"], json!("/opt/bundles/service1/rootfs"));
+        assert_eq!(
+            spec["process"]["args"],
+            json!(["/usr/bin/service1", "--verbose"])
+        );
+        assert_eq!(
+            spec["process"]["env"],
+            json!(["PATH=/usr/bin", "RUST_LOG=debug"])
+        );
+        assert_eq!(spec["mounts"][0]["source"], json!("/tmp/ripple.sock"));
+        assert_eq!(spec["linux"]["namespaces"], json!([{"type": "pid"}]));
+        assert_eq!(spec["linux"]["resources"]["memory"]["limit"], json!(1024));
+        assert_eq!(spec["linux"]["resources"]["pids"]["limit"], json!(16));
+        assert!(spec["linux"]["resources"].get("cpu").is_none());
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{fmt::Debug, sync::Arc};
+
+use ripple_sdk::{
+    api::manifest::device_manifest::{ServiceAuthConfiguration, ServiceCredentials},
+    utils::tls_utils::TlsUtils,
+};
+
+use crate::utils::common::constant_time_eq;
+
+/// Credentials presented by a service when it connects
+#[derive(Debug, Clone, Default)]
+pub struct PresentedCredentials {
+    pub bearer_token: Option<String>,
+    /// SHA-256 fingerprint of the TLS client certificate
+    pub cert_fingerprint: Option<String>,
+}
+
+impl PresentedCredentials {
+    pub fn with_authorization(mut self, authorization: Option<&str>) -> Self {
+        self.bearer_token = authorization
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_owned());
+        self
+    }
+
+    pub fn with_cert_fingerprint(mut self, cert_fingerprint: Option<String>) -> Self {
+        self.cert_fingerprint = cert_fingerprint;
+        self
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bearer_token.is_none() && self.cert_fingerprint.is_none()
+    }
+}
+
+/// Body of the 401 answering a refused service connection, the same for every reason
+pub const SERVICE_UNAUTHORIZED_BODY: &str = r#"{"error":"unauthorized"}"#;
+
+/// Reason for refusing a service connection. It is only logged, the service just gets the
+/// rejected handshake with [SERVICE_UNAUTHORIZED_BODY] so it can not probe which ServiceIds
+/// are allowed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServiceConnectionError {
+    Unauthorized(String),
+}
+
+impl std::fmt::Display for ServiceConnectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceConnectionError::Unauthorized(reason) => write!(f, "Unauthorized: {}", reason),
+        }
+    }
+}
+
+/// Checks the credentials presented by a service against the credentials allowed for its
+/// ServiceId
+pub trait ServiceAuthenticator: Debug + Send + Sync {
+    fn authenticate(&self, allowed: &ServiceCredentials, presented: &PresentedCredentials) -> bool;
+}
+
+#[derive(Debug)]
+pub struct BearerTokenAuthenticator;
+
+impl ServiceAuthenticator for BearerTokenAuthenticator {
+    fn authenticate(&self, allowed: &ServiceCredentials, presented: &PresentedCredentials) -> bool {
+        // every allowed token is compared so the time taken does not reveal a match either
+        presented.bearer_token.as_ref().is_some_and(|token| {
+            allowed.tokens.iter().fold(false, |found, allowed_token| {
+                constant_time_eq(allowed_token, token) | found
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ClientCertificateAuthenticator;
+
+impl ServiceAuthenticator for ClientCertificateAuthenticator {
+    fn authenticate(&self, allowed: &ServiceCredentials, presented: &PresentedCredentials) -> bool {
+        presented.cert_fingerprint.as_ref().is_some_and(|fp| {
+            let fp = TlsUtils::normalize_fingerprint(fp);
+            allowed
+                .cert_fingerprints
+                .iter()
+                .any(|allowed_fp| TlsUtils::normalize_fingerprint(allowed_fp) == fp)
+        })
+    }
+}
+
+/// Authentication of the service connections to Ripple Main, over the websocket and the HTTP
+/// transport. A service is accepted when any of the authenticators accepts its credentials,
+/// bearer tokens and client certificates are checked by default.
+#[derive(Debug, Clone)]
+pub struct ServiceAuth {
+    config: Arc<ServiceAuthConfiguration>,
+    authenticators: Vec<Arc<dyn ServiceAuthenticator>>,
+}
+
+impl Default for ServiceAuth {
+    fn default() -> Self {
+        Self::new(ServiceAuthConfiguration::default())
+    }
+}
+
+impl ServiceAuth {
+    pub fn new(config: ServiceAuthConfiguration) -> Self {
+        Self {
+            config: Arc::new(config),
+            authenticators: vec![
+                Arc::new(BearerTokenAuthenticator),
+                Arc::new(ClientCertificateAuthenticator),
+            ],
+        }
+    }
+
+    pub fn with_authenticator(mut self, authenticator: Arc<dyn ServiceAuthenticator>) -> Self {
+        self.authenticators.push(authenticator);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn authenticate(
+        &self,
+        service_id: &str,
+        presented: &PresentedCredentials,
+    ) -> Result<(), ServiceConnectionError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let allowed = self.config.allowlist.get(service_id).ok_or_else(|| {
+            ServiceConnectionError::Unauthorized(format!(
+                "Service {} is not allowed to connect",
+                service_id
+            ))
+        })?;
+        if presented.is_empty() {
+            return Err(ServiceConnectionError::Unauthorized(format!(
+                "No credentials presented for service {}",
+                service_id
+            )));
+        }
+        if self
+            .authenticators
+            .iter()
+            .any(|a| a.authenticate(allowed, presented))
+        {
+            Ok(())
+        } else {
+            Err(ServiceConnectionError::Unauthorized(format!(
+                "Invalid credentials for service {}",
+                service_id
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_service_auth() {
+        let auth = ServiceAuth::new(ServiceAuthConfiguration {
+            enabled: true,
+            allowlist: HashMap::from([(
+                "ripple:channel:gateway:service1".to_string(),
+                ServiceCredentials {
+                    tokens: vec!["secret".into()],
+                    // as printed by openssl x509 -fingerprint -sha256
+                    cert_fingerprints: vec![
+                        "92:CB:94:1E:CC:94:10:93:45:3E:EF:CB:32:7A:B7:1C:00:DD:7B:F2:F5:CE:A4:4F:8D:88:93:1B:1B:9F:F9:8B".into(),
+                    ],
+                },
+            )]),
+        });
+        let service_id = "ripple:channel:gateway:service1";
+
+        let token = PresentedCredentials::default().with_authorization(Some("Bearer secret"));
+        assert!(auth.authenticate(service_id, &token).is_ok());
+        // as computed from the DER of the presented certificate
+        let cert = PresentedCredentials::default().with_cert_fingerprint(Some(
+            "92cb941ecc941093453eefcb327ab71c00dd7bf2f5cea44f8d88931b1b9ff98b".into(),
+        ));
+        assert!(auth.authenticate(service_id, &cert).is_ok());
+        let other_cert = PresentedCredentials::default().with_cert_fingerprint(Some(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".into(),
+        ));
+        assert!(auth.authenticate(service_id, &other_cert).is_err());
+
+        let wrong = PresentedCredentials::default().with_authorization(Some("Bearer wrong"));
+        assert!(matches!(
+            auth.authenticate(service_id, &wrong),
+            Err(ServiceConnectionError::Unauthorized(_))
+        ));
+        assert!(auth
+            .authenticate(service_id, &PresentedCredentials::default())
+            .is_err());
+        assert!(auth
+            .authenticate("ripple:channel:gateway:other", &token)
+            .is_err());
+        assert!(ServiceAuth::default()
+            .authenticate(service_id, &PresentedCredentials::default())
+            .is_ok());
+    }
+}
@@ -14,37 +14,85 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 //
-use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use futures::{stream::SplitStream, SinkExt, StreamExt};
 use ripple_sdk::api::gateway::rpc_gateway_api::JsonRpcApiResponse;
 use ripple_sdk::{
-    api::{gateway::rpc_gateway_api::ApiMessage, manifest::extn_manifest::ExtnSymbol},
+    api::{
+        firebolt::fb_telemetry::ServiceConnectionEventType,
+        gateway::rpc_gateway_api::{ApiMessage, ClientContext},
+        manifest::{device_manifest::ServiceTakeoverPolicy, extn_manifest::ExtnSymbol},
+        observability::log_signal::LogSignal,
+    },
     extn::{
+        client::extn_framing::{ExtnFrameAssembler, ExtnFraming},
         extn_client_message::{ExtnMessage, ExtnPayload, ExtnResponse},
         extn_id::ExtnId,
     },
     framework::ripple_contract::RippleContract,
-    log::{error, info, trace},
-    service::service_message::{Id, JsonRpcMessage, ServiceMessage},
+    log::{debug, error, info, trace, warn},
+    service::{
+        service_client::InProcessServiceChannel,
+        service_message::{
+            Id, JsonRpcMessage, JsonRpcRequest, ServiceEvent, ServiceMessage,
+            ServiceRegistrationConflict, ServiceRegistrationParams, ServiceRegistrationResult,
+            ServiceRegistrationUpdate, ServiceResyncParams, ServiceResyncResult,
+            SERVICE_REGISTER_METHOD, SERVICE_RESYNC_METHOD, SERVICE_UNREGISTER_METHOD,
+            SERVICE_UPDATE_REGISTRATION_METHOD,
+        },
+    },
     tokio::{
         self,
-        net::TcpStream,
         sync::{mpsc, Mutex},
     },
-    tokio_tungstenite::{tungstenite::Message, WebSocketStream},
-    utils::error::RippleError,
+    tokio_tungstenite::{
+        tungstenite::{
+            protocol::{frame::coding::CloseCode, CloseFrame},
+            Message,
+        },
+        WebSocketStream,
+    },
+    utils::{
+        error::RippleError, expiring_map::ExpiringMap, id_allocator::next_scoped_id,
+        tls_utils::ServerStream,
+    },
     uuid::Uuid,
 };
 
 use crate::{
-    broker::endpoint_broker::{BrokerCallback, BrokerOutput},
+    broker::{
+        endpoint_broker::{
+            BrokerCallback, BrokerOutput, BROKER_REQUEST_CAPACITY, BROKER_REQUEST_TTL,
+        },
+        rules::rules_engine::{RuleEngine, RuleMatchKind, RuleProvenance},
+    },
     firebolt::{firebolt_gateway::FireboltGatewayCommand, firebolt_ws::ClientIdentity},
-    service::extn::ripple_client::RippleClient,
-    state::{platform_state::PlatformState, session_state::Session},
+    processor::extn_method_processor::ExtnMethodProcessor,
+    service::{
+        apps::app_events::AppEvents, extn::ripple_client::RippleClient,
+        telemetry_builder::TelemetryBuilder,
+    },
+    state::{
+        platform_state::PlatformState, session_state::Session, shutdown_state::ShutdownState,
+        tenant_state::TenantState,
+    },
 };
 
-use super::service_registry::ServiceRegistry;
+use super::{
+    service_launcher::ServiceLauncherState,
+    service_registry::{
+        ServiceRegistry, SERVICE_ID_IN_USE_CLOSE_CODE, SERVICE_INCOMPATIBLE_CONTRACT_CLOSE_CODE,
+        SERVICE_QUOTA_EXCEEDED_CLOSE_CODE, SERVICE_UNRESPONSIVE_CLOSE_CODE,
+    },
+    service_subscriptions::ServiceSubscriptions,
+};
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 const ALLOWED_SERVICES_LIST: [&str; 2] = [
     "ripple:channel:gateway:badger",
@@ -56,12 +104,43 @@ pub struct ServiceInfo {
     pub connection_id: String,
     pub tx: mpsc::Sender<Message>,
     pub is_sevice_registered: bool,
-    callback_list: Arc<Mutex<HashMap<u64, BrokerCallback>>>,
+    /// Capabilities granted to the service by its last registration
+    pub capabilities: Vec<String>,
+    callback_list: Arc<Mutex<ExpiringMap<u64, BrokerCallback>>>,
+    /// Requests sent to the service keyed by request id, kept to replay them after a reconnect
+    requests: Arc<Mutex<ExpiringMap<u64, String>>>,
+    last_seen: Instant,
+}
+
+/// A request which was sent to a service but not answered yet
+#[derive(Debug, Clone)]
+pub struct UnansweredServiceCall {
+    pub request_id: u64,
+    pub request: Option<String>,
+    pub callback: BrokerCallback,
+}
+
+/// Methods asked for by a service, split into the ones it gets and the ones it was refused,
+/// along with the conflicts to report to it and to the services it displaced
+#[derive(Debug, Default)]
+struct MethodResolution {
+    methods: Vec<String>,
+    rejected_methods: Vec<String>,
+    conflict: ServiceRegistrationConflict,
+    displaced: HashMap<String, ServiceRegistrationConflict>,
+    /// Rules of the displaced services along with the method taking each over
+    taken_over: Vec<(String, String)>,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct ServiceControllerState {
     pub service_info: Arc<Mutex<ServiceRegistry>>,
+    pub launcher_state: ServiceLauncherState,
+    /// Original JSON-RPC ids of the service requests keyed by the allocated request id
+    request_ids: Arc<Mutex<ExpiringMap<String, Id>>>,
+    pub subscriptions: ServiceSubscriptions,
+    /// Requests left unanswered by a dropped connection, held by ServiceId until it resyncs
+    unanswered_calls: Arc<Mutex<HashMap<String, Vec<UnansweredServiceCall>>>>,
 }
 
 impl ServiceInfo {
@@ -74,7 +153,16 @@ impl ServiceInfo {
             connection_id,
             tx,
             is_sevice_registered,
-            callback_list: Arc::new(Mutex::new(HashMap::new())),
+            capabilities: Vec::new(),
+            callback_list: Arc::new(Mutex::new(ExpiringMap::new(
+                BROKER_REQUEST_TTL,
+                BROKER_REQUEST_CAPACITY,
+            ))),
+            requests: Arc::new(Mutex::new(ExpiringMap::new(
+                BROKER_REQUEST_TTL,
+                BROKER_REQUEST_CAPACITY,
+            ))),
+            last_seen: Instant::now(),
         }
     }
 
@@ -83,12 +171,19 @@ impl ServiceInfo {
         callback_list.insert(request_id, callback);
     }
 
+    pub async fn add_request(&mut self, request_id: u64, request: String) {
+        let mut requests = self.requests.lock().await;
+        requests.insert(request_id, request);
+    }
+
     // add function to get and remove callbacks for a given request_id
     pub async fn get_and_remove_callback(&mut self, request_id: u64) -> Option<BrokerCallback> {
+        self.requests.lock().await.remove(&request_id);
         let mut callback_list = self.callback_list.lock().await;
         callback_list.remove(&request_id)
     }
     pub async fn remove_callback(&mut self, request_id: u64) {
+        self.requests.lock().await.remove(&request_id);
         let mut callback_list = self.callback_list.lock().await;
         callback_list.remove(&request_id);
     }
@@ -96,6 +191,39 @@ impl ServiceInfo {
         let callback_list = self.callback_list.lock().await;
         callback_list.contains_key(&request_id)
     }
+    /// Removes and returns every pending callback along with its request id
+    pub async fn take_callbacks(&self) -> Vec<(u64, BrokerCallback)> {
+        let mut callback_list = self.callback_list.lock().await;
+        let callbacks = callback_list
+            .iter()
+            .map(|(request_id, callback)| (*request_id, callback.clone()))
+            .collect();
+        callback_list.clear();
+        self.requests.lock().await.clear();
+        callbacks
+    }
+    /// Removes and returns every pending callback along with the request sent to the service
+    pub async fn take_unanswered_calls(&self) -> Vec<UnansweredServiceCall> {
+        let mut callback_list = self.callback_list.lock().await;
+        let mut requests = self.requests.lock().await;
+        let calls = callback_list
+            .iter()
+            .map(|(request_id, callback)| UnansweredServiceCall {
+                request_id: *request_id,
+                request: requests.get(request_id).cloned(),
+                callback: callback.clone(),
+            })
+            .collect();
+        callback_list.clear();
+        requests.clear();
+        calls
+    }
+    pub fn touch(&mut self) {
+        self.last_seen = Instant::now();
+    }
+    pub fn is_stale(&self, timeout: Duration) -> bool {
+        self.last_seen.elapsed() > timeout
+    }
     pub fn is_registered(&self) -> bool {
         self.is_sevice_registered
     }
@@ -114,8 +242,18 @@ impl ServiceControllerState {
     pub fn new() -> Self {
         ServiceControllerState {
             service_info: Arc::new(Mutex::new(ServiceRegistry::default())),
+            launcher_state: ServiceLauncherState::default(),
+            request_ids: Arc::new(Mutex::new(ExpiringMap::default())),
+            subscriptions: ServiceSubscriptions::default(),
+            unanswered_calls: Arc::new(Mutex::new(HashMap::new())),
         }
     }
+
+    /// Stops the launched companion services along with the shutdown
+    pub fn with_shutdown_state(mut self, shutdown_state: ShutdownState) -> Self {
+        self.launcher_state = ServiceLauncherState::new(shutdown_state);
+        self
+    }
     // Ripple Main processing the inbound ServiceMessage received from a service.
     // This is not the brokerage path.
     async fn process_inbound_service_message(
@@ -127,6 +265,25 @@ impl ServiceControllerState {
     ) {
         match &sm.message {
             JsonRpcMessage::Request(json_rpc_request) => {
+                if json_rpc_request.method == SERVICE_UNREGISTER_METHOD {
+                    Self::unregister_service(state, connection_id, &app_id, sm, json_rpc_request)
+                        .await;
+                    return;
+                }
+                if json_rpc_request.method == SERVICE_REGISTER_METHOD {
+                    Self::register_service(state, connection_id, &app_id, sm, json_rpc_request)
+                        .await;
+                    return;
+                }
+                if json_rpc_request.method == SERVICE_UPDATE_REGISTRATION_METHOD {
+                    Self::update_registration(state, connection_id, &app_id, sm, json_rpc_request)
+                        .await;
+                    return;
+                }
+                if json_rpc_request.method == SERVICE_RESYNC_METHOD {
+                    Self::resync_service(state, connection_id, &app_id, sm, json_rpc_request).await;
+                    return;
+                }
                 // In Ripple Service Architecture Ripple Main will not honor any request originated from any connected service that is not included in `ALLOWED_SERVICES_LIST`
                 // other than service registration and unregistration request
                 // (TBD) Handling register/unregister
@@ -171,7 +328,9 @@ impl ServiceControllerState {
                 };
             }
             JsonRpcMessage::Notification(_) => {
-                // TBD: Handle notifications.
+                if let Some(event) = sm.get_event() {
+                    Self::emit_service_event(state, &app_id, event).await;
+                }
             }
             JsonRpcMessage::Success(_) | JsonRpcMessage::Error(_) => {
                 // Handling response message
@@ -183,6 +342,9 @@ impl ServiceControllerState {
                     .unwrap_or(None);
 
                 if let Some(callback) = callback {
+                    state
+                        .tenant_state
+                        .record_request(&app_id, matches!(sm.message, JsonRpcMessage::Success(_)));
                     // Handle the message using the callback
                     if let Err(err) = Self::handle_service_response(sm, callback) {
                         error!("Error handling service message: {}", err);
@@ -194,6 +356,61 @@ impl ServiceControllerState {
         }
     }
 
+    /// Passes an event originated by a service to the app listeners. A service can only emit
+    /// the events of methods routed to it, services of a tenant only within the namespaces of
+    /// the tenant.
+    async fn emit_service_event(state: &PlatformState, service_id: &str, event: ServiceEvent) {
+        if !state
+            .endpoint_state
+            .is_routed_to_service(service_id, &event.event)
+        {
+            error!(
+                "Dropping event {} from service {}, not routed to the service",
+                event.event, service_id
+            );
+            return;
+        }
+        if !state
+            .tenant_state
+            .is_method_allowed(service_id, &event.event)
+        {
+            error!(
+                "Dropping event {} from service {}, outside its namespaces",
+                event.event, service_id
+            );
+            return;
+        }
+        debug!("Service {} emitted event {}", service_id, event.event);
+        // sessions which listened through the service broker get the event as a broker output
+        let listeners = state
+            .service_controller_state
+            .subscriptions
+            .get_listeners(service_id, &event.event);
+        for listener in listeners.iter().filter(|l| {
+            event
+                .app_id
+                .as_ref()
+                .is_none_or(|app_id| l.rpc.ctx.app_id.eq(app_id))
+        }) {
+            state
+                .endpoint_state
+                .handle_broker_response(JsonRpcApiResponse {
+                    method: Some(format!("{}.{}", listener.rpc.ctx.call_id, event.event)),
+                    result: Some(event.result.clone()),
+                    ..Default::default()
+                });
+        }
+        match event.app_id {
+            Some(app_id) => {
+                AppEvents::emit_to_app(state, app_id, &event.event, &event.result).await
+            }
+            None => {
+                AppEvents::emit_with_context(state, &event.event, &event.result, event.context)
+                    .await
+            }
+        }
+    }
+
     fn is_contract_used_for_routing(symbol: &ExtnSymbol) -> bool {
         !symbol.uses.is_empty() || !symbol.fulfills.is_empty()
     }
@@ -235,7 +452,7 @@ impl ServiceControllerState {
 
     pub async fn handle_service_connection(
         _client_addr: SocketAddr,
-        ws_stream: WebSocketStream<TcpStream>,
+        mut ws_stream: WebSocketStream<ServerStream>,
         state: PlatformState,
         identity: ClientIdentity,
         connection_id: String,
@@ -254,17 +471,42 @@ impl ServiceControllerState {
             _client_addr.port()
         );
 
+        TelemetryBuilder::send_service_connection_event(
+            &state,
+            &app_id,
+            &connection_id,
+            ServiceConnectionEventType::Connect,
+            None,
+        );
+
         // Create communication channels
         let (message_tx, mut message_rx) = mpsc::channel::<Message>(32);
         let (api_message_tx, mut api_message_rx) = mpsc::channel::<ApiMessage>(32);
 
-        let _ = Self::register_service_channel(
+        if let Err(e) = Self::register_service_channel(
             &state,
-            app_id.clone(),
+            &identity,
             connection_id.clone(),
             message_tx.clone(),
         )
-        .await;
+        .await
+        {
+            let (code, reason) = Self::get_rejection(&e);
+            TelemetryBuilder::send_service_connection_event(
+                &state,
+                &app_id,
+                &connection_id,
+                ServiceConnectionEventType::Disconnect,
+                Some(reason.to_owned()),
+            );
+            let _ = ws_stream
+                .close(Some(CloseFrame {
+                    code: CloseCode::from(code),
+                    reason: reason.into(),
+                }))
+                .await;
+            return;
+        }
 
         let is_using_extn_contracts = Self::is_contract_used_for_routing(&symbol);
 
@@ -300,20 +542,27 @@ impl ServiceControllerState {
         // Spawn a task to handle outgoing `ApiMessage`
         if is_using_extn_contracts {
             let sender_clone = Arc::clone(&sender_wrap);
+            let framing = ExtnFraming::negotiate(symbol.config.as_ref());
+            if let Some(framing) = &framing {
+                info!(
+                    "Negotiated extn framing for {} compression={} max_frame_size={}",
+                    app_id, framing.compression, framing.max_frame_size
+                );
+            }
             tokio::spawn(async move {
                 while let Some(api_message) = api_message_rx.recv().await {
+                    let frames = match &framing {
+                        Some(framing) => framing.encode(api_message.jsonrpc_msg.clone()),
+                        None => vec![api_message.jsonrpc_msg.clone()],
+                    };
                     let mut sender = sender_clone.lock().await;
-                    let send_result = sender
-                        .send(Message::Text(api_message.jsonrpc_msg.clone()))
-                        .await;
-                    match send_result {
-                        Ok(_) => {
-                            trace!("Sent service ApiMessage {}", api_message.jsonrpc_msg);
-                        }
-                        Err(err) => {
+                    for frame in frames {
+                        if let Err(err) = sender.send(Message::Text(frame)).await {
                             error!("Failed to send service ApiMessage: {:?}", err);
+                            break;
                         }
                     }
+                    trace!("Sent service ApiMessage {}", api_message.jsonrpc_msg);
                 }
             });
         }
@@ -341,23 +590,134 @@ impl ServiceControllerState {
         .await;
     }
 
+    /// Close code and reason for a connection whose registration was rejected
+    fn get_rejection(e: &RippleError) -> (u16, &'static str) {
+        match e {
+            RippleError::NotAvailable => (
+                SERVICE_QUOTA_EXCEEDED_CLOSE_CODE,
+                "Tenant reached its service quota",
+            ),
+            RippleError::ExtnError => (
+                SERVICE_INCOMPATIBLE_CONTRACT_CLOSE_CODE,
+                "Incompatible contract versions",
+            ),
+            _ => (SERVICE_ID_IN_USE_CLOSE_CODE, "ServiceId already connected"),
+        }
+    }
+
     async fn register_service_channel(
         state: &PlatformState,
-        app_id: String,
+        identity: &ClientIdentity,
         connection_id: String,
         message_tx: mpsc::Sender<Message>,
     ) -> Result<(), RippleError> {
+        let app_id = identity.app_id.clone();
         // Add the Message channel to the service registry
         let service_info = ServiceInfo::new(
             connection_id.clone(),
             message_tx.clone(),
             false, // Initially not registered
         );
+        let policy = state
+            .get_device_manifest()
+            .get_service_takeover_policy(&app_id);
+        let audit_ctx = ClientContext {
+            session_id: identity.session_id.clone(),
+            app_id: app_id.clone(),
+            gateway_secure: false,
+        };
 
-        state
+        if let Some(symbol) = &identity.service_info {
+            // Negotiate the contract versions the service declared in its handshake
+            let contracts = symbol.negotiate_contract_versions();
+            let errors: Vec<String> = contracts.iter().filter_map(|c| c.get_error()).collect();
+            state
+                .extn_status_state
+                .update_contracts(&symbol.id, contracts);
+            if !errors.is_empty() {
+                LogSignal::new(
+                    "service_contract_versions".to_string(),
+                    format!("service {} rejected, {}", app_id, errors.join(", ")),
+                    audit_ctx,
+                )
+                .with_diagnostic_context_item("connection_id", &connection_id)
+                .emit_error();
+                return Err(RippleError::ExtnError);
+            }
+        }
+
+        // a service reconnecting before the liveness monitor found its old connection dead
+        // gets the ServiceId once the old connection is stale
+        Self::evict_stale_connection(state, &app_id).await;
+
+        match state
             .service_controller_state
-            .add_service_info(app_id, service_info)
+            .add_tenant_service_info(
+                &state.tenant_state,
+                app_id.clone(),
+                service_info,
+                policy.clone(),
+                identity.force_takeover,
+            )
             .await
+        {
+            Ok(replaced) => {
+                if let Some(replaced) = replaced {
+                    // the new connection resyncs the requests the displaced one left unanswered
+                    Self::hold_unanswered_calls(state, &app_id, &replaced).await;
+                    let replaced_connection_id = replaced.get_connection_id();
+                    LogSignal::new(
+                        "service_takeover".to_string(),
+                        format!("service {} taken over", app_id),
+                        audit_ctx,
+                    )
+                    .with_diagnostic_context_item("connection_id", &connection_id)
+                    .with_diagnostic_context_item("replaced_connection_id", replaced_connection_id)
+                    .emit_debug();
+                    TelemetryBuilder::send_service_connection_event(
+                        state,
+                        &app_id,
+                        &connection_id,
+                        ServiceConnectionEventType::Takeover,
+                        Some(format!("replaced connection {}", replaced_connection_id)),
+                    );
+                }
+                TelemetryBuilder::send_service_connection_event(
+                    state,
+                    &app_id,
+                    &connection_id,
+                    ServiceConnectionEventType::Register,
+                    None,
+                );
+                state
+                    .service_controller_state
+                    .launcher_state
+                    .on_service_registered(&app_id);
+                Ok(())
+            }
+            Err(RippleError::NotAvailable) => {
+                LogSignal::new(
+                    "service_tenant_quota".to_string(),
+                    format!("service {} rejected, tenant quota reached", app_id),
+                    audit_ctx,
+                )
+                .with_diagnostic_context_item("connection_id", &connection_id)
+                .emit_error();
+                Err(RippleError::NotAvailable)
+            }
+            Err(e) => {
+                LogSignal::new(
+                    "service_takeover".to_string(),
+                    format!("service {} rejected, ServiceId already connected", app_id),
+                    audit_ctx,
+                )
+                .with_diagnostic_context_item("connection_id", &connection_id)
+                .with_diagnostic_context_item("policy", &format!("{:?}", policy))
+                .with_diagnostic_context_item("force", &identity.force_takeover.to_string())
+                .emit_error();
+                Err(e)
+            }
+        }
     }
 
     fn register_extn_contract_session(
@@ -369,6 +729,16 @@ impl ServiceControllerState {
         symbol: &ExtnSymbol,
         api_message_tx: mpsc::Sender<ApiMessage>,
     ) {
+        if state.extn_status_state.is_refused(&symbol.id) {
+            error!(
+                "Refusing extension {} with incompatible contract versions {:?}",
+                symbol.id,
+                state
+                    .extn_status_state
+                    .get_incompatible_contracts(&symbol.id)
+            );
+            return;
+        }
         // Add the ApiMessage sender to RippleClient to support sending ApiMessages
         let session = Session::new(identity.app_id.clone(), Some(api_message_tx.clone()));
 
@@ -384,36 +754,34 @@ impl ServiceControllerState {
     }
 
     async fn handle_incoming_service_messages(
-        receiver: &mut SplitStream<WebSocketStream<TcpStream>>,
+        receiver: &mut SplitStream<WebSocketStream<ServerStream>>,
         state: &PlatformState,
         connection_id: &str,
         identity: &ClientIdentity,
         client: &RippleClient,
     ) {
+        let mut assembler = ExtnFrameAssembler::default();
         while let Some(msg) = receiver.next().await {
             match msg {
                 Ok(msg) if msg.is_text() && !msg.is_empty() => {
-                    let req_text = msg.to_text().unwrap().to_string();
-
-                    if let Ok(sm) = serde_json::from_str::<ServiceMessage>(&req_text) {
-                        Self::process_inbound_service_message(
-                            state,
-                            connection_id,
-                            &sm,
-                            identity.app_id.clone(),
-                            identity.session_id.clone(),
-                        )
-                        .await;
-                    } else if let Ok(extn_msg) = ExtnMessage::try_from(req_text.clone()) {
-                        client.get_extn_client().handle_message(extn_msg);
-                    } else {
-                        error!("Failed to parse incoming message: {}", req_text);
-                        return_invalid_service_error_message(
-                            state,
-                            connection_id,
-                            RippleError::ParseError,
-                        )
-                        .await;
+                    match assembler.accept(msg.to_text().unwrap().to_string()) {
+                        Ok(Some(req_text)) => {
+                            Self::handle_incoming_service_text(
+                                req_text,
+                                state,
+                                connection_id,
+                                identity,
+                                client,
+                            )
+                            .await;
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            error!(
+                                "Dropping framed message for service connection_id={}: {:?}",
+                                connection_id, e
+                            );
+                        }
                     }
                 }
                 Ok(_) => {}
@@ -427,6 +795,164 @@ impl ServiceControllerState {
         }
     }
 
+    async fn handle_incoming_service_text(
+        req_text: String,
+        state: &PlatformState,
+        connection_id: &str,
+        identity: &ClientIdentity,
+        client: &RippleClient,
+    ) {
+        // any message from the service counts as a heartbeat
+        state
+            .service_controller_state
+            .touch(&identity.app_id, connection_id)
+            .await;
+        if let Ok(sm) = serde_json::from_str::<ServiceMessage>(&req_text) {
+            if sm.is_heartbeat() {
+                trace!("Heartbeat from service {}", identity.app_id);
+                return;
+            }
+            Self::process_inbound_service_message(
+                state,
+                connection_id,
+                &sm,
+                identity.app_id.clone(),
+                identity.session_id.clone(),
+            )
+            .await;
+        } else if let Ok(extn_msg) = ExtnMessage::try_from(req_text.clone()) {
+            client.get_extn_client().handle_message(extn_msg);
+        } else {
+            error!("Failed to parse incoming message: {}", req_text);
+            return_invalid_service_error_message(state, connection_id, RippleError::ParseError)
+                .await;
+        }
+    }
+
+    /// Connects a service hosted inside the Ripple Main process. The service is
+    /// registered exactly like a websocket service connection, but messages are
+    /// exchanged over the returned channel pair instead of a socket.
+    pub async fn connect_in_process_service(
+        state: PlatformState,
+        symbol: ExtnSymbol,
+    ) -> Result<InProcessServiceChannel, RippleError> {
+        let identity = ClientIdentity {
+            session_id: Uuid::new_v4().to_string(),
+            app_id: symbol.id.clone(),
+            rpc_v2: true,
+            service_info: Some(symbol.clone()),
+            force_takeover: false,
+            admin_token: None,
+            sdk_version: None,
+            rejection: None,
+        };
+        let connection_id = Uuid::new_v4().to_string();
+        let app_id = identity.app_id.clone();
+        let session_id = identity.session_id.clone();
+        let client = state.get_client();
+
+        info!(
+            "Creating new in-process service connection_id={} app_id={} session_id={}",
+            connection_id, app_id, session_id
+        );
+
+        TelemetryBuilder::send_service_connection_event(
+            &state,
+            &app_id,
+            &connection_id,
+            ServiceConnectionEventType::Connect,
+            None,
+        );
+
+        // Messages from Ripple Main to the service
+        let (message_tx, message_rx) = mpsc::channel::<Message>(32);
+        // Messages from the service to Ripple Main
+        let (service_tx, mut service_rx) = mpsc::channel::<Message>(32);
+
+        if let Err(e) = Self::register_service_channel(
+            &state,
+            &identity,
+            connection_id.clone(),
+            message_tx.clone(),
+        )
+        .await
+        {
+            TelemetryBuilder::send_service_connection_event(
+                &state,
+                &app_id,
+                &connection_id,
+                ServiceConnectionEventType::Disconnect,
+                Some(Self::get_rejection(&e).1.to_owned()),
+            );
+            return Err(e);
+        }
+
+        let is_using_extn_contracts = Self::is_contract_used_for_routing(&symbol);
+
+        if is_using_extn_contracts {
+            let (api_message_tx, mut api_message_rx) = mpsc::channel::<ApiMessage>(32);
+            Self::register_extn_contract_session(
+                &state,
+                &client,
+                &identity,
+                &app_id,
+                &session_id,
+                &symbol,
+                api_message_tx,
+            );
+            let message_tx_c = message_tx.clone();
+            tokio::spawn(async move {
+                while let Some(api_message) = api_message_rx.recv().await {
+                    if let Err(err) = message_tx_c
+                        .send(Message::Text(api_message.jsonrpc_msg.clone()))
+                        .await
+                    {
+                        error!("Failed to send in-process service ApiMessage: {:?}", err);
+                    } else {
+                        trace!(
+                            "Sent in-process service ApiMessage {}",
+                            api_message.jsonrpc_msg
+                        );
+                    }
+                }
+            });
+        }
+
+        tokio::spawn(async move {
+            while let Some(msg) = service_rx.recv().await {
+                if msg.is_close() {
+                    break;
+                }
+                if msg.is_text() && !msg.is_empty() {
+                    Self::handle_incoming_service_text(
+                        msg.to_text().unwrap().to_string(),
+                        &state,
+                        &connection_id,
+                        &identity,
+                        &client,
+                    )
+                    .await;
+                }
+            }
+
+            Self::cleanup_service_connection(
+                &connection_id,
+                &app_id,
+                &session_id,
+                is_using_extn_contracts,
+                &client,
+                symbol,
+                &state,
+            )
+            .await;
+        });
+
+        Ok(InProcessServiceChannel {
+            inbound: message_rx,
+            outbound: service_tx,
+        })
+    }
+
     async fn cleanup_service_connection(
         connection_id: &str,
         app_id: &str,
@@ -442,15 +968,33 @@ impl ServiceControllerState {
         );
 
         if is_using_extn_contracts {
+            // methods of an extension which stopped or crashed are not routed to it anymore
+            ExtnMethodProcessor::unregister(state, &symbol.id);
+            state.config_section_state.unsubscribe(&symbol.id);
             client
                 .get_extn_client()
                 .remove_sender(app_id.to_string(), symbol);
         }
 
-        let _ = state
+        // A connection which was taken over is no longer in the registry
+        if let Ok(info) = state
             .service_controller_state
-            .remove_service_info(&connection_id.to_string())
-            .await;
+            .remove_service_info(&app_id.to_string(), connection_id)
+            .await
+        {
+            Self::hold_unanswered_calls(state, app_id, &info).await;
+            state
+                .service_controller_state
+                .launcher_state
+                .on_service_unregistered(app_id);
+        }
+        TelemetryBuilder::send_service_connection_event(
+            state,
+            app_id,
+            connection_id,
+            ServiceConnectionEventType::Disconnect,
+            Some("connection closed".to_owned()),
+        );
     }
 
     fn handle_service_response(
@@ -480,19 +1024,43 @@ impl ServiceControllerState {
         &self,
         service_id: String,
         info: ServiceInfo,
-    ) -> Result<(), RippleError> {
+        policy: ServiceTakeoverPolicy,
+        force: bool,
+    ) -> Result<Option<ServiceInfo>, RippleError> {
         self.service_info
             .lock()
             .await
-            .add_service_info(service_id, info)
+            .add_service_info(service_id, info, policy, force)
+            .await
+    }
+
+    /// Adds the service info once the quota of its tenant allows it. The quota is checked
+    /// under the registry lock, so concurrent connections of a tenant cannot overshoot it.
+    pub async fn add_tenant_service_info(
+        &self,
+        tenant_state: &TenantState,
+        service_id: String,
+        info: ServiceInfo,
+        policy: ServiceTakeoverPolicy,
+        force: bool,
+    ) -> Result<Option<ServiceInfo>, RippleError> {
+        let registry = self.service_info.lock().await;
+        let connected_service_ids = registry.get_service_ids().await;
+        tenant_state.check_registration(&service_id, &connected_service_ids)?;
+        registry
+            .add_service_info(service_id, info, policy, force)
             .await
     }
 
-    pub async fn remove_service_info(&self, service_id: &String) -> Result<(), RippleError> {
+    pub async fn remove_service_info(
+        &self,
+        service_id: &String,
+        connection_id: &str,
+    ) -> Result<ServiceInfo, RippleError> {
         self.service_info
             .lock()
             .await
-            .remove_service_info(service_id)
+            .remove_service_info(service_id, connection_id)
             .await
     }
     pub async fn set_broker_callback(
@@ -500,71 +1068,1352 @@ impl ServiceControllerState {
         service_id: &String,
         request_id: u64,
         callback: BrokerCallback,
+        request: Option<String>,
     ) -> Result<(), RippleError> {
         self.service_info
             .lock()
             .await
-            .set_broker_callback(service_id, request_id, callback)
+            .set_broker_callback(service_id, request_id, callback, request)
             .await
     }
+    /// Takes the broker callback of the request, which may be held while the service is
+    /// reconnecting
     pub async fn extract_broker_callback(
         &self,
         service_id: &String,
         request_id: u64,
     ) -> Result<Option<BrokerCallback>, RippleError> {
-        self.service_info
+        let result = self
+            .service_info
             .lock()
             .await
             .extract_broker_callback(service_id, request_id)
+            .await;
+        if let Ok(Some(callback)) = result {
+            return Ok(Some(callback));
+        }
+        match self
+            .take_held_calls(service_id, Some(&[request_id]))
             .await
+            .pop()
+        {
+            Some(call) => Ok(Some(call.callback)),
+            None => result,
+        }
     }
     pub async fn get_sender(&self, service_id: &String) -> Option<mpsc::Sender<Message>> {
         self.service_info.lock().await.get_sender(service_id).await
     }
-}
+    pub async fn get_connected_service_ids(&self) -> Vec<String> {
+        self.service_info.lock().await.get_service_ids().await
+    }
+    async fn fail_pending_requests(info: &ServiceInfo, message: String) {
+        Self::fail_callbacks(info.take_callbacks().await, message);
+    }
 
-async fn return_invalid_service_error_message(
-    state: &PlatformState,
-    connection_id: &str,
-    e: RippleError,
-) {
-    if let Some(session) = state
-        .session_state
-        .get_session_for_connection_id(connection_id)
-    {
-        let id = if let RippleError::BrokerError(id) = e.clone() {
-            id
-        } else {
-            Uuid::new_v4().to_string()
-        };
-        let msg = ExtnMessage {
-            id: id.clone(),
-            payload: ExtnPayload::Response(ExtnResponse::Error(e)),
-            requestor: ExtnId::try_from(session.get_app_id()).unwrap(),
-            target: RippleContract::Internal,
-            target_id: None,
-            ts: None,
-        };
-        let _ = session.send_json_rpc(msg.into()).await;
+    fn fail_callbacks(callbacks: Vec<(u64, BrokerCallback)>, message: String) {
+        for (request_id, callback) in callbacks {
+            let response =
+                JsonRpcApiResponse::builder(request_id).service_unavailable(message.clone());
+            if let Err(e) = callback.sender.try_send(BrokerOutput::new(response)) {
+                error!("Failed to fail pending request {}: {:?}", request_id, e);
+            }
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Holds the requests a dropped connection left unanswered until the service reconnects
+    /// and resyncs, the requests still held once the resync window elapsed fail as unavailable.
+    async fn hold_unanswered_calls(state: &PlatformState, service_id: &str, info: &ServiceInfo) {
+        let calls = info.take_unanswered_calls().await;
+        if calls.is_empty() {
+            return;
+        }
+        let request_ids: Vec<u64> = calls.iter().map(|c| c.request_id).collect();
+        info!(
+            "Holding {} unanswered requests of service {} until it resyncs",
+            calls.len(),
+            service_id
+        );
+        state
+            .service_controller_state
+            .unanswered_calls
+            .lock()
+            .await
+            .entry(service_id.to_owned())
+            .or_default()
+            .extend(calls);
 
-    #[tokio::test]
-    async fn test_validate_sender() {
-        let context = serde_json::json!({
-            "context": ["some_value", "ripple:channel:gateway:badger"]
+        let window = Duration::from_millis(
+            state
+                .get_device_manifest()
+                .get_service_liveness_configuration()
+                .resync_window_ms,
+        );
+        let state = state.clone();
+        let service_id = service_id.to_owned();
+        tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            let expired = state
+                .service_controller_state
+                .take_held_calls(&service_id, Some(&request_ids))
+                .await;
+            if !expired.is_empty() {
+                error!(
+                    "Service {} did not resync, failing {} unanswered requests",
+                    service_id,
+                    expired.len()
+                );
+                Self::fail_callbacks(
+                    expired
+                        .into_iter()
+                        .map(|c| (c.request_id, c.callback))
+                        .collect(),
+                    format!("Service {} disconnected", service_id),
+                );
+            }
         });
-        let result = ServiceControllerState::validate_sender(context).await;
-        assert!(result, "{}", true);
+    }
 
-        let context = serde_json::json!({
-            "context": ["some_value", "invalid_service_id"]
+    /// Forgets the broker requests sent to services, including the ones held for a
+    /// reconnecting service, once they were failed elsewhere
+    pub async fn forget_broker_requests(&self, request_ids: &[u64]) {
+        self.service_info
+            .lock()
+            .await
+            .remove_broker_callbacks(request_ids)
+            .await;
+        let mut unanswered_calls = self.unanswered_calls.lock().await;
+        unanswered_calls.retain(|_, calls| {
+            calls.retain(|c| !request_ids.contains(&c.request_id));
+            !calls.is_empty()
         });
-        let result = ServiceControllerState::validate_sender(context).await;
-        assert!(!result, "{}", false);
+    }
+
+    /// Takes the held requests of the service, either the given ones or all of them
+    async fn take_held_calls(
+        &self,
+        service_id: &str,
+        request_ids: Option<&[u64]>,
+    ) -> Vec<UnansweredServiceCall> {
+        let mut unanswered_calls = self.unanswered_calls.lock().await;
+        let calls = match unanswered_calls.get_mut(service_id) {
+            Some(calls) => calls,
+            None => return Vec::new(),
+        };
+        let taken = match request_ids {
+            Some(request_ids) => {
+                let (taken, kept) = calls
+                    .drain(..)
+                    .partition(|c| request_ids.contains(&c.request_id));
+                *calls = kept;
+                taken
+            }
+            None => std::mem::take(calls),
+        };
+        if calls.is_empty() {
+            unanswered_calls.remove(service_id);
+        }
+        taken
+    }
+
+    /// Resync requested by a service once it reconnected. The requests left unanswered by the
+    /// previous connection are sent again on this connection when the service asks for a
+    /// replay, otherwise they fail as unavailable. The result is returned to the service.
+    async fn resync_service(
+        state: &PlatformState,
+        connection_id: &str,
+        service_id: &str,
+        sm: &ServiceMessage,
+        request: &JsonRpcRequest,
+    ) {
+        let controller = &state.service_controller_state;
+        let params: ServiceResyncParams = request
+            .params
+            .clone()
+            .and_then(|p| serde_json::from_value(p).ok())
+            .unwrap_or_default();
+        let sender = match controller.get_sender(&service_id.to_string()).await {
+            Some(sender) => sender,
+            None => {
+                error!(
+                    "Resync from service {} which is not registered on connection_id={}",
+                    service_id, connection_id
+                );
+                return;
+            }
+        };
+
+        let mut result = ServiceResyncResult::default();
+        for call in controller.take_held_calls(service_id, None).await {
+            let replayed = match call.request.clone() {
+                Some(request) if params.replay => {
+                    controller
+                        .set_broker_callback(
+                            &service_id.to_string(),
+                            call.request_id,
+                            call.callback.clone(),
+                            Some(request.clone()),
+                        )
+                        .await
+                        .is_ok()
+                        && sender.send(Message::Text(request)).await.is_ok()
+                }
+                _ => false,
+            };
+            if replayed {
+                result.replayed += 1;
+            } else {
+                let _ = controller
+                    .extract_broker_callback(&service_id.to_string(), call.request_id)
+                    .await;
+                Self::fail_callbacks(
+                    vec![(call.request_id, call.callback)],
+                    format!("Service {} reconnected", service_id),
+                );
+                result.failed += 1;
+            }
+        }
+        info!(
+            "Service {} resynced connection_id={} replayed={} failed={}",
+            service_id, connection_id, result.replayed, result.failed
+        );
+
+        let mut ack = ServiceMessage::new_success(
+            serde_json::to_value(&result).unwrap_or_default(),
+            request.id.clone(),
+        );
+        ack.set_context(sm.context.clone());
+        if let Err(e) = sender.send(Message::Text(ack.into())).await {
+            error!("Failed to acknowledge resync of {}: {:?}", service_id, e);
+        }
+    }
+
+    /// Registration of the capabilities and methods of a service. Capabilities are granted as
+    /// allowed by the capability policy of the device manifest and only the methods covered by
+    /// the granted capabilities are routed to the service. Methods registered by another
+    /// service are resolved by the method conflict policy, methods routed by the rules of the
+    /// device are rejected. The result lists what was installed and what was refused, and a
+    /// service which did not get or lost methods to another service is told which ones.
+    async fn register_service(
+        state: &PlatformState,
+        connection_id: &str,
+        service_id: &str,
+        sm: &ServiceMessage,
+        request: &JsonRpcRequest,
+    ) {
+        let controller = &state.service_controller_state;
+        let params: ServiceRegistrationParams = match Self::parse_params(request) {
+            Ok(params) => params,
+            Err(e) => {
+                error!("Invalid registration from service {}: {}", service_id, e);
+                Self::reject_request(state, service_id, sm, request, -32602, e).await;
+                return;
+            }
+        };
+        let (capabilities, rejected_capabilities) = state
+            .get_device_manifest()
+            .get_service_capability_policy()
+            .grant_capabilities(service_id, &params.capabilities);
+        if !controller
+            .service_info
+            .lock()
+            .await
+            .set_registration(&service_id.to_string(), connection_id, capabilities.clone())
+            .await
+        {
+            error!(
+                "Registration from service {} which is not connected on connection_id={}",
+                service_id, connection_id
+            );
+            return;
+        }
+
+        state
+            .endpoint_state
+            .forget_displaced_service_rules(service_id);
+        let resolution = state
+            .endpoint_state
+            .resolve_service_rules(service_id, |rules| {
+                let resolution =
+                    Self::resolve_methods(state, service_id, &capabilities, params.methods, rules);
+                (
+                    resolution.methods.clone(),
+                    resolution.taken_over.clone(),
+                    resolution,
+                )
+            });
+        let result = ServiceRegistrationResult {
+            capabilities,
+            methods: resolution.methods,
+            rejected_capabilities,
+            rejected_methods: resolution.rejected_methods,
+        };
+        info!(
+            "Service {} registered connection_id={} methods={:?} rejected_capabilities={:?} rejected_methods={:?}",
+            service_id,
+            connection_id,
+            result.methods,
+            result.rejected_capabilities,
+            result.rejected_methods
+        );
+        Self::complete_registration(
+            state,
+            service_id,
+            sm,
+            request,
+            &result,
+            resolution.conflict,
+            resolution.displaced,
+        )
+        .await;
+    }
+
+    /// Params of a registration request, which may be left out. Params which do not match
+    /// are refused instead of being treated as an empty registration.
+    fn parse_params<T: DeserializeOwned + Default>(request: &JsonRpcRequest) -> Result<T, String> {
+        match request.params.clone() {
+            Some(params) => {
+                serde_json::from_value(params).map_err(|e| format!("Invalid params: {}", e))
+            }
+            None => Ok(T::default()),
+        }
+    }
+
+    async fn reject_request(
+        state: &PlatformState,
+        service_id: &str,
+        sm: &ServiceMessage,
+        request: &JsonRpcRequest,
+        code: i64,
+        message: String,
+    ) {
+        let mut message = ServiceMessage::new_error(code, message, None, request.id.clone());
+        message.set_context(sm.context.clone());
+        let sender = state
+            .service_controller_state
+            .get_sender(&service_id.to_string())
+            .await;
+        if let Some(sender) = sender {
+            if let Err(e) = sender.send(Message::Text(message.into())).await {
+                error!(
+                    "Failed to reject {} of {}: {:?}",
+                    request.method, service_id, e
+                );
+            }
+        }
+    }
+
+    /// Adds and removes methods of a registered service without a new registration. Added
+    /// methods go through the same capability and conflict checks as a registration, using
+    /// the capabilities granted when the service registered. A method both added and removed
+    /// is removed. The result lists every method the service now serves along with the added
+    /// methods which were refused.
+    async fn update_registration(
+        state: &PlatformState,
+        connection_id: &str,
+        service_id: &str,
+        sm: &ServiceMessage,
+        request: &JsonRpcRequest,
+    ) {
+        let controller = &state.service_controller_state;
+        let mut update: ServiceRegistrationUpdate = match Self::parse_params(request) {
+            Ok(update) => update,
+            Err(e) => {
+                error!(
+                    "Invalid registration update from service {}: {}",
+                    service_id, e
+                );
+                Self::reject_request(state, service_id, sm, request, -32602, e).await;
+                return;
+            }
+        };
+        let registration = controller
+            .service_info
+            .lock()
+            .await
+            .get_registration(&service_id.to_string(), connection_id)
+            .await;
+        let capabilities = match registration {
+            Some(capabilities) => capabilities,
+            None => {
+                error!(
+                    "Registration update from service {} which is not registered on connection_id={}",
+                    service_id, connection_id
+                );
+                Self::reject_request(
+                    state,
+                    service_id,
+                    sm,
+                    request,
+                    -32600,
+                    "Service must register before updating its registration".to_string(),
+                )
+                .await;
+                return;
+            }
+        };
+
+        let removed: Vec<String> = update.removed.iter().map(|m| m.to_lowercase()).collect();
+        update
+            .added
+            .retain(|method| !removed.contains(&method.to_lowercase()));
+        let provenance = RuleProvenance::Service {
+            service_id: service_id.to_owned(),
+        };
+        let (methods, resolution) =
+            state
+                .endpoint_state
+                .resolve_service_rules(service_id, |rules| {
+                    let resolution = Self::resolve_methods(
+                        state,
+                        service_id,
+                        &capabilities,
+                        update.added,
+                        rules,
+                    );
+                    let mut methods: Vec<String> = rules
+                        .get_registered_methods(&provenance)
+                        .into_iter()
+                        .filter(|method| !removed.contains(method))
+                        .chain(resolution.methods.iter().map(|m| m.to_lowercase()))
+                        .collect();
+                    methods.sort();
+                    methods.dedup();
+                    (
+                        methods.clone(),
+                        resolution.taken_over.clone(),
+                        (methods, resolution),
+                    )
+                });
+        let result = ServiceRegistrationResult {
+            capabilities,
+            methods,
+            rejected_methods: resolution.rejected_methods,
+            ..Default::default()
+        };
+        info!(
+            "Service {} updated registration connection_id={} added={:?} removed={:?} rejected_methods={:?}",
+            service_id, connection_id, resolution.methods, removed, result.rejected_methods
+        );
+        Self::complete_registration(
+            state,
+            service_id,
+            sm,
+            request,
+            &result,
+            resolution.conflict,
+            resolution.displaced,
+        )
+        .await;
+    }
+
+    /// Resolves the methods a service asks for against the granted capabilities and the
+    /// method conflict policy of the device, runs under the write lock of the rules
+    fn resolve_methods(
+        state: &PlatformState,
+        service_id: &str,
+        capabilities: &[String],
+        methods: Vec<String>,
+        rules: &RuleEngine,
+    ) -> MethodResolution {
+        let policy = state.get_device_manifest().get_service_capability_policy();
+        let conflict_config = state
+            .get_device_manifest()
+            .get_service_conflict_configuration();
+        let mut resolution = MethodResolution::default();
+        for method in methods {
+            // core methods served by the handlers of Ripple Main are never taken over, brokerage
+            // would otherwise route them to the service ahead of the handler. Services of a
+            // tenant only get methods within the namespaces of the tenant.
+            if !RuleMatchKind::is_valid_pattern(&method)
+                || !policy.is_method_covered(capabilities, &method)
+                || !state.tenant_state.is_method_allowed(service_id, &method)
+                || state
+                    .router_state
+                    .find_overlapping_method(&method)
+                    .is_some()
+            {
+                resolution.rejected_methods.push(method);
+                continue;
+            }
+            // a pattern is resolved against every rule sharing a method with it, the service
+            // gets it only when it wins over all of their owners
+            let mut displaced = Vec::new();
+            let mut rejected = false;
+            for (rule_name, rule) in rules.find_overlapping_rules(&method) {
+                match rule.provenance {
+                    RuleProvenance::Service { service_id: owner } if owner.eq(service_id) => {}
+                    RuleProvenance::Service { service_id: owner } => {
+                        if conflict_config.is_won_by(service_id, &owner) {
+                            displaced.push((owner, rule_name));
+                        } else {
+                            resolution.conflict.methods.insert(method.clone(), owner);
+                            rejected = true;
+                        }
+                    }
+                    _ => rejected = true,
+                }
+            }
+            if rejected {
+                resolution.rejected_methods.push(method);
+                continue;
+            }
+            for (owner, rule_name) in displaced {
+                resolution
+                    .displaced
+                    .entry(owner)
+                    .or_default()
+                    .methods
+                    .insert(rule_name.clone(), service_id.to_owned());
+                resolution.taken_over.push((rule_name, method.clone()));
+            }
+            resolution.methods.push(method);
+        }
+        resolution
+    }
+
+    /// Acknowledges a registration or registration update and tells the services involved
+    /// in a conflict which methods they did not get or lost
+    async fn complete_registration(
+        state: &PlatformState,
+        service_id: &str,
+        sm: &ServiceMessage,
+        request: &JsonRpcRequest,
+        result: &ServiceRegistrationResult,
+        conflict: ServiceRegistrationConflict,
+        displaced: HashMap<String, ServiceRegistrationConflict>,
+    ) {
+        let controller = &state.service_controller_state;
+        let mut ack = ServiceMessage::new_success(
+            serde_json::to_value(result).unwrap_or_default(),
+            request.id.clone(),
+        );
+        ack.set_context(sm.context.clone());
+        if let Some(sender) = controller.get_sender(&service_id.to_string()).await {
+            if let Err(e) = sender.send(Message::Text(ack.into())).await {
+                error!(
+                    "Failed to acknowledge registration of {}: {:?}",
+                    service_id, e
+                );
+            }
+        }
+        if !conflict.methods.is_empty() {
+            controller
+                .send_registration_conflict(service_id, conflict)
+                .await;
+        }
+        for (owner, conflict) in displaced {
+            controller
+                .send_registration_conflict(&owner, conflict)
+                .await;
+        }
+    }
+
+    async fn send_registration_conflict(
+        &self,
+        service_id: &str,
+        conflict: ServiceRegistrationConflict,
+    ) {
+        info!(
+            "Registration conflict for service {} methods={:?}",
+            service_id, conflict.methods
+        );
+        if let Some(sender) = self.get_sender(&service_id.to_string()).await {
+            let message = ServiceMessage::new_registration_conflict(conflict);
+            if let Err(e) = sender.send(Message::Text(message.into())).await {
+                error!(
+                    "Failed to send registration conflict to {}: {:?}",
+                    service_id, e
+                );
+            }
+        }
+    }
+
+    /// Graceful deregistration requested by the service. Requests stop being routed to the
+    /// service, its in-flight requests fail as unavailable and the unregister is
+    /// acknowledged on the connection, which the service may close afterwards.
+    async fn unregister_service(
+        state: &PlatformState,
+        connection_id: &str,
+        service_id: &str,
+        sm: &ServiceMessage,
+        request: &JsonRpcRequest,
+    ) {
+        let controller = &state.service_controller_state;
+        let info = match controller
+            .remove_service_info(&service_id.to_string(), connection_id)
+            .await
+        {
+            Ok(info) => info,
+            Err(_) => {
+                error!(
+                    "Unregister from service {} which is not registered on connection_id={}",
+                    service_id, connection_id
+                );
+                return;
+            }
+        };
+        info!(
+            "Service {} unregistered connection_id={}",
+            service_id, connection_id
+        );
+        Self::fail_pending_requests(&info, format!("Service {} unregistered", service_id)).await;
+        state.endpoint_state.remove_service_rules(service_id);
+        controller
+            .launcher_state
+            .on_service_unregistered(service_id);
+        TelemetryBuilder::send_service_connection_event(
+            state,
+            service_id,
+            connection_id,
+            ServiceConnectionEventType::Unregister,
+            None,
+        );
+
+        let mut ack = ServiceMessage::new_success(Value::Null, request.id.clone());
+        ack.set_context(sm.context.clone());
+        if let Err(e) = info.get_sender().send(Message::Text(ack.into())).await {
+            error!(
+                "Failed to acknowledge unregister of {}: {:?}",
+                service_id, e
+            );
+        }
+    }
+
+    pub async fn touch(&self, service_id: &String, connection_id: &str) {
+        self.service_info
+            .lock()
+            .await
+            .touch(service_id, connection_id)
+            .await
+    }
+
+    /// Sends a heartbeat to every connected service and unregisters the services which
+    /// have been silent for longer than the configured timeout.
+    pub fn start_liveness_monitor(state: PlatformState) {
+        let config = state
+            .get_device_manifest()
+            .get_service_liveness_configuration();
+        if !config.enabled {
+            return;
+        }
+        let timeout_ms = config.get_timeout_ms();
+        if timeout_ms != config.timeout_ms {
+            warn!(
+                "Service liveness timeout {}ms is not longer than the interval, using {}ms",
+                config.timeout_ms, timeout_ms
+            );
+        }
+        info!(
+            "Monitoring service liveness interval={}ms timeout={}ms",
+            config.interval_ms, timeout_ms
+        );
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_millis(config.interval_ms.max(1)));
+            loop {
+                interval.tick().await;
+                let evicted = Self::check_liveness(&state, Duration::from_millis(timeout_ms)).await;
+                for (service_id, connection_id) in evicted {
+                    TelemetryBuilder::send_service_connection_event(
+                        &state,
+                        &service_id,
+                        &connection_id,
+                        ServiceConnectionEventType::Unregister,
+                        Some("not responding".to_owned()),
+                    );
+                }
+            }
+        });
+    }
+
+    /// Unregisters the connection of the service if it missed its heartbeats, when the
+    /// liveness monitor is enabled
+    async fn evict_stale_connection(state: &PlatformState, service_id: &String) {
+        let config = state
+            .get_device_manifest()
+            .get_service_liveness_configuration();
+        if !config.enabled {
+            return;
+        }
+        let stale = state
+            .service_controller_state
+            .service_info
+            .lock()
+            .await
+            .remove_stale_service(service_id, Duration::from_millis(config.get_timeout_ms()))
+            .await;
+        if let Some(info) = stale {
+            let connection_id = info.get_connection_id().to_owned();
+            Self::unregister_dead_service(state, service_id, info).await;
+            TelemetryBuilder::send_service_connection_event(
+                state,
+                service_id,
+                &connection_id,
+                ServiceConnectionEventType::Unregister,
+                Some("not responding".to_owned()),
+            );
+        }
+    }
+
+    /// Returns the service and connection ids of the services which were unregistered
+    async fn check_liveness(state: &PlatformState, timeout: Duration) -> Vec<(String, String)> {
+        let registry = state.service_controller_state.service_info.lock().await;
+        let heartbeat: String = ServiceMessage::new_heartbeat().into();
+        for (service_id, sender) in registry.get_senders().await {
+            if let Err(e) = sender.try_send(Message::Text(heartbeat.clone())) {
+                trace!("Failed to send heartbeat to {}: {:?}", service_id, e);
+            }
+        }
+        let stale = registry.remove_stale_services(timeout).await;
+        drop(registry);
+        let mut evicted = Vec::new();
+        for (service_id, info) in stale {
+            evicted.push((service_id.clone(), info.get_connection_id().to_owned()));
+            Self::unregister_dead_service(state, &service_id, info).await;
+        }
+        evicted
+    }
+
+    /// Holds the pending requests of a dead service until it reconnects and resyncs, removes
+    /// its rules and closes its connection. The close is not awaited as the connection of a
+    /// dead service may not drain its queue.
+    async fn unregister_dead_service(state: &PlatformState, service_id: &str, info: ServiceInfo) {
+        error!(
+            "Service {} missed its heartbeats, unregistering connection_id={}",
+            service_id,
+            info.get_connection_id()
+        );
+        Self::hold_unanswered_calls(state, service_id, &info).await;
+        state.endpoint_state.remove_service_rules(service_id);
+        if let Err(e) = info.get_sender().try_send(Message::Close(Some(CloseFrame {
+            code: CloseCode::from(SERVICE_UNRESPONSIVE_CLOSE_CODE),
+            reason: "Service not responding".into(),
+        }))) {
+            trace!("Failed to close the connection of {}: {:?}", service_id, e);
+        }
+        state
+            .service_controller_state
+            .launcher_state
+            .on_service_unregistered(service_id);
+    }
+
+    /// Service contexts are not unique across services, so requests from a service get a
+    /// request id from the allocator. The original id is restored in the response.
+    pub async fn allocate_request_id(&self, service_id: &str, original_id: Id) -> String {
+        let request_id = next_scoped_id(service_id);
+        self.request_ids
+            .lock()
+            .await
+            .insert(request_id.clone(), original_id);
+        request_id
+    }
+
+    pub async fn take_original_id(&self, request_id: &str) -> Id {
+        self.request_ids
+            .lock()
+            .await
+            .remove(request_id)
+            .unwrap_or_else(|| Id::String(request_id.to_owned()))
+    }
+}
+
+async fn return_invalid_service_error_message(
+    state: &PlatformState,
+    connection_id: &str,
+    e: RippleError,
+) {
+    if let Some(session) = state
+        .session_state
+        .get_session_for_connection_id(connection_id)
+    {
+        let id = if let RippleError::BrokerError(id) = e.clone() {
+            id
+        } else {
+            Uuid::new_v4().to_string()
+        };
+        let msg = ExtnMessage {
+            id: id.clone(),
+            payload: ExtnPayload::Response(ExtnResponse::Error(e)),
+            requestor: ExtnId::try_from(session.get_app_id()).unwrap(),
+            target: RippleContract::Internal,
+            target_id: None,
+            ts: None,
+        };
+        let _ = session.send_json_rpc(msg.into()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::manifest::device_manifest::ServiceCapabilityPolicy;
+
+    const WATCHED_CAPABILITY: &str = "xrn:firebolt:capability:discovery:watched";
+    const PLAYER_CAPABILITY: &str = "xrn:firebolt:capability:player:base";
+
+    /// Mock state whose capability policy grants the test services their methods
+    fn get_registration_state() -> PlatformState {
+        use ripple_tdk::utils::test_utils::Mockable;
+
+        let mut state = PlatformState::mock();
+        let mut manifest = state.get_device_manifest();
+        manifest.configuration.service_capabilities = ServiceCapabilityPolicy {
+            enforce: true,
+            services: HashMap::from([
+                (
+                    "ripple:channel:gateway:service1".to_owned(),
+                    vec![WATCHED_CAPABILITY.to_owned()],
+                ),
+                ("service1".to_owned(), vec![PLAYER_CAPABILITY.to_owned()]),
+                ("service2".to_owned(), vec![PLAYER_CAPABILITY.to_owned()]),
+            ]),
+            capability_methods: HashMap::from([
+                (
+                    WATCHED_CAPABILITY.to_owned(),
+                    vec!["discovery.watched".to_owned(), "device.uid".to_owned()],
+                ),
+                (PLAYER_CAPABILITY.to_owned(), vec!["player.*".to_owned()]),
+            ]),
+        };
+        state.set_device_manifest(manifest);
+        state
+    }
+
+    #[tokio::test]
+    async fn test_validate_sender() {
+        let context = serde_json::json!({
+            "context": ["some_value", "ripple:channel:gateway:badger"]
+        });
+        let result = ServiceControllerState::validate_sender(context).await;
+        assert!(result, "{}", true);
+
+        let context = serde_json::json!({
+            "context": ["some_value", "invalid_service_id"]
+        });
+        let result = ServiceControllerState::validate_sender(context).await;
+        assert!(!result, "{}", false);
+    }
+
+    #[tokio::test]
+    async fn test_check_liveness() {
+        use ripple_tdk::utils::test_utils::Mockable;
+
+        let state = PlatformState::mock();
+        let service_id = "ripple:channel:gateway:service1".to_string();
+        let (tx, mut rx) = mpsc::channel::<Message>(4);
+        let controller = &state.service_controller_state;
+        controller
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn1".into(), tx, false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await
+            .unwrap();
+        let (callback_tx, mut callback_rx) = mpsc::channel::<BrokerOutput>(1);
+        controller
+            .set_broker_callback(
+                &service_id,
+                7,
+                BrokerCallback {
+                    sender: callback_tx,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        state
+            .endpoint_state
+            .add_service_rules(&service_id, &["service1.method".to_string()]);
+        assert!(state.endpoint_state.has_rule("service1.method"));
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let evicted =
+            ServiceControllerState::check_liveness(&state, Duration::from_millis(10)).await;
+        assert_eq!(evicted, vec![(service_id.clone(), "conn1".to_string())]);
+        assert!(!state.endpoint_state.has_rule("service1.method"));
+
+        let heartbeat = rx.recv().await.unwrap();
+        assert!(ServiceMessage::try_from(heartbeat.to_text().unwrap())
+            .unwrap()
+            .is_heartbeat());
+        assert!(matches!(
+            rx.recv().await,
+            Some(Message::Close(Some(CloseFrame { code, .. })))
+                if u16::from(code) == SERVICE_UNRESPONSIVE_CLOSE_CODE
+        ));
+        // the pending request is held for the service to resync instead of failing
+        assert!(callback_rx.try_recv().is_err());
+        assert_eq!(
+            controller
+                .take_held_calls(&service_id, None)
+                .await
+                .iter()
+                .map(|c| c.request_id)
+                .collect::<Vec<_>>(),
+            vec![7]
+        );
+        assert!(controller.get_sender(&service_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unregister_service() {
+        use ripple_tdk::utils::test_utils::Mockable;
+
+        let state = PlatformState::mock();
+        let service_id = "ripple:channel:gateway:service1".to_string();
+        let (tx, mut rx) = mpsc::channel::<Message>(4);
+        let controller = &state.service_controller_state;
+        controller
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn1".into(), tx, false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await
+            .unwrap();
+        let (callback_tx, mut callback_rx) = mpsc::channel::<BrokerOutput>(1);
+        controller
+            .set_broker_callback(
+                &service_id,
+                7,
+                BrokerCallback {
+                    sender: callback_tx,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let sm = ServiceMessage::new_request(
+            SERVICE_UNREGISTER_METHOD.to_string(),
+            None,
+            Id::String("unregister1".into()),
+        );
+        ServiceControllerState::process_inbound_service_message(
+            &state,
+            "conn1",
+            &sm,
+            service_id.clone(),
+            "session1".into(),
+        )
+        .await;
+
+        let output = callback_rx.recv().await.unwrap();
+        assert!(output.data.is_error());
+        let ack = ServiceMessage::try_from(rx.recv().await.unwrap().to_text().unwrap()).unwrap();
+        assert!(matches!(
+            ack.message,
+            JsonRpcMessage::Success(ref success) if matches!(success.id, Id::String(ref id) if id == "unregister1")
+        ));
+        assert!(controller.get_sender(&service_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resync_service() {
+        use ripple_tdk::utils::test_utils::Mockable;
+
+        let state = PlatformState::mock();
+        let service_id = "ripple:channel:gateway:service1".to_string();
+        let controller = &state.service_controller_state;
+        let (tx, _rx) = mpsc::channel::<Message>(4);
+        controller
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn1".into(), tx, false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await
+            .unwrap();
+        let (callback_tx, _callback_rx) = mpsc::channel::<BrokerOutput>(1);
+        controller
+            .set_broker_callback(
+                &service_id,
+                7,
+                BrokerCallback {
+                    sender: callback_tx,
+                },
+                Some("request7".into()),
+            )
+            .await
+            .unwrap();
+
+        // the connection drops and the service reconnects
+        let info = controller
+            .remove_service_info(&service_id, "conn1")
+            .await
+            .unwrap();
+        ServiceControllerState::hold_unanswered_calls(&state, &service_id, &info).await;
+        let (tx, mut rx) = mpsc::channel::<Message>(4);
+        controller
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn2".into(), tx, false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let sm = ServiceMessage::new_request(
+            SERVICE_RESYNC_METHOD.to_string(),
+            serde_json::to_value(ServiceResyncParams { replay: true }).ok(),
+            Id::String("resync1".into()),
+        );
+        ServiceControllerState::process_inbound_service_message(
+            &state,
+            "conn2",
+            &sm,
+            service_id.clone(),
+            "session1".into(),
+        )
+        .await;
+
+        assert_eq!(rx.recv().await.unwrap().to_text().unwrap(), "request7");
+        let ack = ServiceMessage::try_from(rx.recv().await.unwrap().to_text().unwrap()).unwrap();
+        match ack.message {
+            JsonRpcMessage::Success(success) => assert_eq!(
+                serde_json::from_value::<ServiceResyncResult>(success.result).unwrap(),
+                ServiceResyncResult {
+                    replayed: 1,
+                    failed: 0
+                }
+            ),
+            _ => panic!("resync not acknowledged"),
+        }
+        assert!(controller
+            .extract_broker_callback(&service_id, 7)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_register_service() {
+        use jsonrpsee::RpcModule;
+
+        let state = get_registration_state();
+        let mut module = RpcModule::new(());
+        module
+            .register_method("device.uid", |_, _| Ok("uid"))
+            .unwrap();
+        state.router_state.update_methods(module.into()).unwrap();
+        let service_id = "ripple:channel:gateway:service1".to_string();
+        let controller = &state.service_controller_state;
+        let (tx, mut rx) = mpsc::channel::<Message>(4);
+        controller
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn1".into(), tx, false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let sm = ServiceMessage::new_request(
+            SERVICE_REGISTER_METHOD.to_string(),
+            serde_json::to_value(ServiceRegistrationParams {
+                capabilities: vec![WATCHED_CAPABILITY.into()],
+                methods: vec!["Discovery.watched".into(), "device.uid".into()],
+            })
+            .ok(),
+            Id::String("register1".into()),
+        );
+        ServiceControllerState::process_inbound_service_message(
+            &state,
+            "conn1",
+            &sm,
+            service_id.clone(),
+            "session1".into(),
+        )
+        .await;
+
+        let ack = ServiceMessage::try_from(rx.recv().await.unwrap().to_text().unwrap()).unwrap();
+        match ack.message {
+            JsonRpcMessage::Success(success) => {
+                let result =
+                    serde_json::from_value::<ServiceRegistrationResult>(success.result).unwrap();
+                assert_eq!(result.methods, vec!["Discovery.watched".to_string()]);
+                // served by a handler of Ripple Main
+                assert_eq!(result.rejected_methods, vec!["device.uid".to_string()]);
+            }
+            _ => panic!("registration not acknowledged"),
+        }
+        assert_eq!(
+            state.endpoint_state.get_service_methods().get(&service_id),
+            Some(&vec!["discovery.watched".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_registration() {
+        let state = get_registration_state();
+        let service_id = "service1".to_string();
+        let controller = &state.service_controller_state;
+        let (tx, mut rx) = mpsc::channel::<Message>(4);
+        controller
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn1".into(), tx, false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await
+            .unwrap();
+        let update = ServiceMessage::new_request(
+            SERVICE_UPDATE_REGISTRATION_METHOD.to_string(),
+            serde_json::to_value(ServiceRegistrationUpdate {
+                added: vec!["player.seek".into(), "player.pause".into()],
+                removed: vec!["Player.play".into(), "player.Pause".into()],
+            })
+            .ok(),
+            Id::String("update1".into()),
+        );
+
+        // updates are refused until the service registered
+        ServiceControllerState::process_inbound_service_message(
+            &state,
+            "conn1",
+            &update,
+            service_id.clone(),
+            "session1".into(),
+        )
+        .await;
+        let reply = ServiceMessage::try_from(rx.recv().await.unwrap().to_text().unwrap()).unwrap();
+        assert!(matches!(reply.message, JsonRpcMessage::Error(_)));
+
+        let register = ServiceMessage::new_request(
+            SERVICE_REGISTER_METHOD.to_string(),
+            serde_json::to_value(ServiceRegistrationParams {
+                capabilities: vec![PLAYER_CAPABILITY.into()],
+                methods: vec!["player.play".into(), "player.stop".into()],
+            })
+            .ok(),
+            Id::String("register1".into()),
+        );
+        for sm in [&register, &update] {
+            ServiceControllerState::process_inbound_service_message(
+                &state,
+                "conn1",
+                sm,
+                service_id.clone(),
+                "session1".into(),
+            )
+            .await;
+            rx.recv().await.unwrap();
+        }
+
+        assert_eq!(
+            state.endpoint_state.get_service_methods().get(&service_id),
+            Some(&vec!["player.seek".to_string(), "player.stop".to_string()])
+        );
+
+        // malformed params are refused instead of updating nothing
+        let invalid = ServiceMessage::new_request(
+            SERVICE_UPDATE_REGISTRATION_METHOD.to_string(),
+            Some(serde_json::json!({"added": "player.play"})),
+            Id::String("update2".into()),
+        );
+        ServiceControllerState::process_inbound_service_message(
+            &state,
+            "conn1",
+            &invalid,
+            service_id.clone(),
+            "session1".into(),
+        )
+        .await;
+        let reply = ServiceMessage::try_from(rx.recv().await.unwrap().to_text().unwrap()).unwrap();
+        assert!(matches!(reply.message, JsonRpcMessage::Error(e) if e.error.code == -32602));
+    }
+
+    #[tokio::test]
+    async fn test_tenant_registration() {
+        use ripple_sdk::api::manifest::device_manifest::{
+            TenantConfiguration, TenantsConfiguration,
+        };
+
+        let mut state = get_registration_state();
+        state.tenant_state = TenantState::new(TenantsConfiguration {
+            tenants: vec![TenantConfiguration {
+                id: "vendor_a".into(),
+                service_prefix: "service".into(),
+                max_services: Some(1),
+                method_namespaces: vec!["vendora".into()],
+            }],
+        });
+        let service_id = "service1".to_string();
+        let controller = &state.service_controller_state;
+        let (tx, mut rx) = mpsc::channel::<Message>(4);
+        controller
+            .add_tenant_service_info(
+                &state.tenant_state,
+                service_id.clone(),
+                ServiceInfo::new("conn1".into(), tx.clone(), false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            controller
+                .add_tenant_service_info(
+                    &state.tenant_state,
+                    "service2".into(),
+                    ServiceInfo::new("conn2".into(), tx, false),
+                    ServiceTakeoverPolicy::Reject,
+                    false,
+                )
+                .await,
+            Err(RippleError::NotAvailable)
+        ));
+
+        // covered by the capability but outside the namespaces of the tenant
+        let register = ServiceMessage::new_request(
+            SERVICE_REGISTER_METHOD.to_string(),
+            serde_json::to_value(ServiceRegistrationParams {
+                capabilities: vec![PLAYER_CAPABILITY.into()],
+                methods: vec!["player.play".into()],
+            })
+            .ok(),
+            Id::String("register1".into()),
+        );
+        ServiceControllerState::process_inbound_service_message(
+            &state,
+            "conn1",
+            &register,
+            service_id.clone(),
+            "session1".into(),
+        )
+        .await;
+        let ack = ServiceMessage::try_from(rx.recv().await.unwrap().to_text().unwrap()).unwrap();
+        match ack.message {
+            JsonRpcMessage::Success(success) => {
+                let result =
+                    serde_json::from_value::<ServiceRegistrationResult>(success.result).unwrap();
+                assert!(result.methods.is_empty());
+                assert_eq!(result.rejected_methods, vec!["player.play".to_string()]);
+            }
+            _ => panic!("registration not acknowledged"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registration_conflict() {
+        use ripple_sdk::service::service_message::SERVICE_REGISTRATION_CONFLICT_METHOD;
+
+        let state = get_registration_state();
+        let controller = &state.service_controller_state;
+        let mut receivers = Vec::new();
+        for (service_id, connection_id, methods) in [
+            ("service1", "conn1", vec!["player.play"]),
+            ("service2", "conn2", vec!["player.play", "player.pl?y"]),
+        ] {
+            let (tx, rx) = mpsc::channel::<Message>(4);
+            controller
+                .add_service_info(
+                    service_id.into(),
+                    ServiceInfo::new(connection_id.into(), tx, false),
+                    ServiceTakeoverPolicy::Reject,
+                    false,
+                )
+                .await
+                .unwrap();
+            let sm = ServiceMessage::new_request(
+                SERVICE_REGISTER_METHOD.to_string(),
+                serde_json::to_value(ServiceRegistrationParams {
+                    capabilities: vec![PLAYER_CAPABILITY.into()],
+                    methods: methods.into_iter().map(String::from).collect(),
+                })
+                .ok(),
+                Id::String("register1".into()),
+            );
+            ServiceControllerState::process_inbound_service_message(
+                &state,
+                connection_id,
+                &sm,
+                service_id.into(),
+                "session1".into(),
+            )
+            .await;
+            receivers.push(rx);
+        }
+
+        // the method stays with the first service under the default reject policy, along
+        // with the patterns which would also serve it
+        let rx = &mut receivers[1];
+        let ack = ServiceMessage::try_from(rx.recv().await.unwrap().to_text().unwrap()).unwrap();
+        match ack.message {
+            JsonRpcMessage::Success(success) => assert_eq!(
+                serde_json::from_value::<ServiceRegistrationResult>(success.result)
+                    .unwrap()
+                    .rejected_methods,
+                vec!["player.play".to_string(), "player.pl?y".to_string()]
+            ),
+            _ => panic!("registration not acknowledged"),
+        }
+        let conflict =
+            ServiceMessage::try_from(rx.recv().await.unwrap().to_text().unwrap()).unwrap();
+        match conflict.message {
+            JsonRpcMessage::Notification(notification) => {
+                assert_eq!(notification.method, SERVICE_REGISTRATION_CONFLICT_METHOD);
+                assert_eq!(
+                    serde_json::from_value::<ServiceRegistrationConflict>(
+                        notification.params.unwrap()
+                    )
+                    .unwrap()
+                    .methods,
+                    std::collections::BTreeMap::from([
+                        ("player.play".to_string(), "service1".to_string()),
+                        ("player.pl?y".to_string(), "service1".to_string())
+                    ])
+                );
+            }
+            _ => panic!("conflict not sent"),
+        }
+        assert!(state
+            .endpoint_state
+            .get_service_methods()
+            .get("service1")
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_connect_in_process_service() {
+        use ripple_tdk::utils::test_utils::Mockable;
+
+        let state = PlatformState::mock();
+        let service_id = "ripple:channel:gateway:inprocess".to_string();
+        let symbol = ExtnSymbol {
+            id: service_id.clone(),
+            uses: vec![],
+            fulfills: vec![],
+            config: None,
+            contract_versions: HashMap::new(),
+        };
+        let channel = ServiceControllerState::connect_in_process_service(state.clone(), symbol)
+            .await
+            .unwrap();
+
+        let sender = state
+            .service_controller_state
+            .get_sender(&service_id)
+            .await
+            .unwrap();
+        assert!(!channel.outbound.is_closed());
+
+        let mut inbound = channel.inbound;
+        sender
+            .send(Message::Text("ping".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(
+            inbound.recv().await.unwrap(),
+            Message::Text("ping".to_string())
+        );
     }
 }
@@ -0,0 +1,616 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use hyper::{
+    body::{Bytes, HttpBody},
+    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use ripple_sdk::{
+    api::manifest::{device_manifest::ServiceHttpConfiguration, extn_manifest::ExtnSymbol},
+    log::{error, info, trace},
+    tokio::{
+        self,
+        sync::{mpsc, Mutex},
+    },
+    tokio_tungstenite::tungstenite::Message,
+};
+use serde_json::{json, Value};
+
+use super::{
+    service_auth::{PresentedCredentials, SERVICE_UNAUTHORIZED_BODY},
+    service_controller_state::ServiceControllerState,
+};
+use crate::{state::platform_state::PlatformState, utils::bind_utils::resolve_bind_address};
+
+pub const SERVICE_HTTP_EVENT_STREAM: &str = "text/event-stream";
+
+/// Messages from Ripple Main for the service. A message which could not be delivered is
+/// requeued ahead of the channel.
+struct HttpInbound {
+    rx: mpsc::Receiver<Message>,
+    requeued: Option<Message>,
+}
+
+impl HttpInbound {
+    async fn recv(&mut self) -> Option<Message> {
+        match self.requeued.take() {
+            Some(msg) => Some(msg),
+            None => self.rx.recv().await,
+        }
+    }
+
+    fn try_recv(&mut self) -> Option<Message> {
+        self.requeued.take().or_else(|| self.rx.try_recv().ok())
+    }
+}
+
+/// Service side of an HTTP connection, the receiver is shared by the polls of the service
+#[derive(Clone)]
+struct HttpServiceConnection {
+    inbound: Arc<Mutex<HttpInbound>>,
+    outbound: mpsc::Sender<Message>,
+    last_seen: Arc<RwLock<Instant>>,
+}
+
+impl HttpServiceConnection {
+    fn new(inbound: mpsc::Receiver<Message>, outbound: mpsc::Sender<Message>) -> Self {
+        HttpServiceConnection {
+            inbound: Arc::new(Mutex::new(HttpInbound {
+                rx: inbound,
+                requeued: None,
+            })),
+            outbound,
+            last_seen: Arc::new(RwLock::new(Instant::now())),
+        }
+    }
+
+    fn touch(&self) {
+        *self.last_seen.write().unwrap() = Instant::now();
+    }
+
+    fn is_idle(&self, idle_timeout: Duration, now: Instant) -> bool {
+        now.saturating_duration_since(*self.last_seen.read().unwrap()) > idle_timeout
+    }
+}
+
+type HttpServiceConnections = Arc<RwLock<HashMap<String, HttpServiceConnection>>>;
+
+/// HTTP transport for services which cant upgrade to a websocket.
+///
+/// - `POST /services?serviceId=<id>` registers the service and returns its `connectionId`,
+///   services present their bearer token in the `Authorization` header
+/// - `POST /services/<connectionId>/messages` sends one service message to Ripple Main
+/// - `GET /services/<connectionId>/messages` long-polls for the messages from Ripple Main
+///   as a JSON array, or streams them as server-sent events with `Accept: text/event-stream`
+/// - `DELETE /services/<connectionId>` unregisters the service
+///
+/// The connection is registered exactly like an in-process service, so routing, heartbeats
+/// and takeover behave the same as for websocket services. Connections left idle are
+/// unregistered.
+pub struct ServiceHttp;
+
+impl ServiceHttp {
+    pub async fn start(state: PlatformState) {
+        let config = state.get_device_manifest().get_service_http_configuration();
+        let addr = match resolve_bind_address(&config.gateway) {
+            Ok(addr) => addr,
+            Err(e) => {
+                error!("Invalid service HTTP address {}: {:?}", config.gateway, e);
+                return;
+            }
+        };
+        let connections: HttpServiceConnections = Arc::new(RwLock::new(HashMap::new()));
+        Self::start_idle_reaper(
+            connections.clone(),
+            Duration::from_millis(config.idle_timeout_ms.max(1)),
+        );
+        let make_service = make_service_fn(move |_| {
+            let state = state.clone();
+            let config = config.clone();
+            let connections = connections.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    Self::handle(state.clone(), config.clone(), connections.clone(), req)
+                }))
+            }
+        });
+        let server = match Server::try_bind(&addr) {
+            Ok(builder) => builder.serve(make_service),
+            Err(e) => {
+                error!("Failed to bind service HTTP transport {}: {:?}", addr, e);
+                return;
+            }
+        };
+        info!("Service HTTP transport listening on: {}", addr);
+        if let Err(e) = server.await {
+            error!("Service HTTP transport error {:?}", e);
+        }
+    }
+
+    async fn handle(
+        state: PlatformState,
+        config: ServiceHttpConfiguration,
+        connections: HttpServiceConnections,
+        req: Request<Body>,
+    ) -> Result<Response<Body>, Infallible> {
+        let segments: Vec<String> = req
+            .uri()
+            .path()
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let method = req.method().clone();
+        let response = match (&method, segments.as_slice()) {
+            (&Method::POST, ["services"]) => Self::register(state, connections, &req).await,
+            (&Method::POST, ["services", connection_id, "messages"]) => {
+                let connection_id = connection_id.to_string();
+                Self::send(connections, &connection_id, req, config.max_message_bytes).await
+            }
+            (&Method::GET, ["services", connection_id, "messages"]) => {
+                let connection = connections.read().unwrap().get(*connection_id).cloned();
+                match connection {
+                    Some(connection) if Self::accepts_event_stream(&req) => Self::stream(
+                        connections.clone(),
+                        connection_id.to_string(),
+                        connection,
+                        Duration::from_millis(config.poll_timeout_ms.max(1)),
+                    ),
+                    Some(connection) => {
+                        Self::poll(
+                            connections.clone(),
+                            connection_id,
+                            connection,
+                            Duration::from_millis(config.poll_timeout_ms),
+                        )
+                        .await
+                    }
+                    None => Self::get_status_response(StatusCode::NOT_FOUND),
+                }
+            }
+            (&Method::DELETE, ["services", connection_id]) => {
+                let connection = connections.write().unwrap().remove(*connection_id);
+                match connection {
+                    Some(connection) => {
+                        // the in-process loop cleans up the registration on close
+                        let _ = connection.outbound.send(Message::Close(None)).await;
+                        Self::get_status_response(StatusCode::NO_CONTENT)
+                    }
+                    None => Self::get_status_response(StatusCode::NOT_FOUND),
+                }
+            }
+            _ => Self::get_status_response(StatusCode::NOT_FOUND),
+        };
+        Ok(response)
+    }
+
+    async fn register(
+        state: PlatformState,
+        connections: HttpServiceConnections,
+        req: &Request<Body>,
+    ) -> Response<Body> {
+        let service_id = match req.uri().query().and_then(|qs| {
+            querystring::querify(qs)
+                .iter()
+                .find(|q| q.0 == "serviceId")
+                .map(|q| q.1.to_owned())
+        }) {
+            Some(service_id) => service_id,
+            None => return Self::get_status_response(StatusCode::BAD_REQUEST),
+        };
+        let credentials = PresentedCredentials::default().with_authorization(
+            req.headers()
+                .get(AUTHORIZATION)
+                .and_then(|h| h.to_str().ok()),
+        );
+        if let Err(e) = state.service_auth.authenticate(&service_id, &credentials) {
+            error!("Service {} refused over HTTP {}", service_id, e);
+            return Self::get_unauthorized_response();
+        }
+        let symbol = state
+            .extn_manifest
+            .get_all_extns()
+            .into_iter()
+            .find(|extn| extn.id.eq(&service_id))
+            .unwrap_or(ExtnSymbol {
+                id: service_id.clone(),
+                ..Default::default()
+            });
+        let channel = match ServiceControllerState::connect_in_process_service(state, symbol).await
+        {
+            Ok(channel) => channel,
+            Err(e) => {
+                error!("Service {} rejected over HTTP: {:?}", service_id, e);
+                return Self::get_status_response(StatusCode::CONFLICT);
+            }
+        };
+        let connection_id = ripple_sdk::uuid::Uuid::new_v4().to_string();
+        info!(
+            "Service {} connected over HTTP connection_id={}",
+            service_id, connection_id
+        );
+        connections.write().unwrap().insert(
+            connection_id.clone(),
+            HttpServiceConnection::new(channel.inbound, channel.outbound),
+        );
+        Self::get_json_response(StatusCode::OK, json!({ "connectionId": connection_id }))
+    }
+
+    /// Unregisters the connections which neither polled nor streamed within the timeout
+    fn start_idle_reaper(connections: HttpServiceConnections, idle_timeout: Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(idle_timeout);
+            loop {
+                interval.tick().await;
+                for (connection_id, connection) in
+                    Self::take_idle(&connections, idle_timeout, Instant::now())
+                {
+                    info!("Service HTTP connection {} expired", connection_id);
+                    // the in-process loop cleans up the registration on close
+                    let _ = connection.outbound.send(Message::Close(None)).await;
+                }
+            }
+        });
+    }
+
+    fn take_idle(
+        connections: &HttpServiceConnections,
+        idle_timeout: Duration,
+        now: Instant,
+    ) -> Vec<(String, HttpServiceConnection)> {
+        let mut connections = connections.write().unwrap();
+        let idle: Vec<String> = connections
+            .iter()
+            .filter(|(_, c)| c.is_idle(idle_timeout, now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        idle.into_iter()
+            .filter_map(|id| connections.remove(&id).map(|c| (id, c)))
+            .collect()
+    }
+
+    /// Reads the body up to the limit, larger messages are refused before they are buffered
+    async fn read_body(mut body: Body, limit: usize) -> Result<String, StatusCode> {
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(|e| {
+                error!("Unable to read service HTTP message {:?}", e);
+                StatusCode::BAD_REQUEST
+            })?;
+            if bytes.len() + chunk.len() > limit {
+                error!("Service HTTP message exceeds {} bytes", limit);
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+
+    async fn send(
+        connections: HttpServiceConnections,
+        connection_id: &str,
+        req: Request<Body>,
+        max_message_bytes: usize,
+    ) -> Response<Body> {
+        let connection = match connections.read().unwrap().get(connection_id).cloned() {
+            Some(connection) => connection,
+            None => return Self::get_status_response(StatusCode::NOT_FOUND),
+        };
+        connection.touch();
+        let body = match Self::read_body(req.into_body(), max_message_bytes).await {
+            Ok(body) => body,
+            Err(status) => return Self::get_status_response(status),
+        };
+        match connection.outbound.send(Message::Text(body)).await {
+            Ok(_) => Self::get_status_response(StatusCode::ACCEPTED),
+            Err(_) => {
+                connections.write().unwrap().remove(connection_id);
+                Self::get_status_response(StatusCode::GONE)
+            }
+        }
+    }
+
+    /// Waits up to `timeout` for the first message and returns it along with everything else
+    /// already queued for the service.
+    async fn poll(
+        connections: HttpServiceConnections,
+        connection_id: &str,
+        connection: HttpServiceConnection,
+        timeout: Duration,
+    ) -> Response<Body> {
+        connection.touch();
+        let mut inbound = connection.inbound.lock().await;
+        let first = match tokio::time::timeout(timeout, inbound.recv()).await {
+            Ok(Some(msg)) => msg,
+            Ok(None) => {
+                connections.write().unwrap().remove(connection_id);
+                return Self::get_status_response(StatusCode::GONE);
+            }
+            Err(_) => return Self::get_status_response(StatusCode::NO_CONTENT),
+        };
+        let mut messages = Vec::new();
+        let mut closed = !Self::collect_message(first, &mut messages);
+        while !closed {
+            match inbound.try_recv() {
+                Some(msg) => closed = !Self::collect_message(msg, &mut messages),
+                None => break,
+            }
+        }
+        connection.touch();
+        if closed {
+            connections.write().unwrap().remove(connection_id);
+            if messages.is_empty() {
+                return Self::get_status_response(StatusCode::GONE);
+            }
+        }
+        Self::get_json_response(StatusCode::OK, Value::Array(messages))
+    }
+
+    /// Adds a text message to the poll response, returns false once Ripple Main closed the
+    /// connection.
+    fn collect_message(msg: Message, messages: &mut Vec<Value>) -> bool {
+        match msg {
+            Message::Text(text) => {
+                match serde_json::from_str(&text) {
+                    Ok(value) => messages.push(value),
+                    Err(_) => messages.push(Value::String(text)),
+                }
+                true
+            }
+            Message::Close(_) => false,
+            _ => true,
+        }
+    }
+
+    /// Streams the messages as server-sent events. Idle streams get a comment every
+    /// `keepalive`, which also notices a stream dropped by the service. Undelivered messages
+    /// are requeued for the next poll.
+    fn stream(
+        connections: HttpServiceConnections,
+        connection_id: String,
+        connection: HttpServiceConnection,
+        keepalive: Duration,
+    ) -> Response<Body> {
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            let mut inbound = connection.inbound.lock().await;
+            loop {
+                // a dropped stream must not take the next message
+                if std::future::poll_fn(|cx| sender.poll_ready(cx))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                let text = match tokio::time::timeout(keepalive, inbound.recv()).await {
+                    Ok(Some(Message::Text(text))) => text,
+                    Ok(Some(Message::Close(_))) | Ok(None) => {
+                        connections.write().unwrap().remove(&connection_id);
+                        break;
+                    }
+                    Ok(Some(_)) => continue,
+                    Err(_) => {
+                        if sender
+                            .send_data(Bytes::from_static(b":\n\n"))
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                        connection.touch();
+                        continue;
+                    }
+                };
+                trace!("Streaming service message {}", text);
+                if sender
+                    .send_data(Bytes::from(Self::get_event(&text)))
+                    .await
+                    .is_err()
+                {
+                    // the service dropped the stream, it gets the message on its next poll
+                    inbound.requeued = Some(Message::Text(text));
+                    break;
+                }
+                connection.touch();
+            }
+        });
+        Response::builder()
+            .header(CONTENT_TYPE, SERVICE_HTTP_EVENT_STREAM)
+            .body(body)
+            .unwrap_or_default()
+    }
+
+    fn get_event(text: &str) -> String {
+        let mut event = String::new();
+        for line in text.lines() {
+            event.push_str("data: ");
+            event.push_str(line);
+            event.push('\n');
+        }
+        event.push('\n');
+        event
+    }
+
+    fn accepts_event_stream(req: &Request<Body>) -> bool {
+        req.headers()
+            .get(ACCEPT)
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.contains(SERVICE_HTTP_EVENT_STREAM))
+            .unwrap_or(false)
+    }
+
+    fn get_json_response(status: StatusCode, value: Value) -> Response<Body> {
+        Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(value.to_string()))
+            .unwrap_or_default()
+    }
+
+    fn get_unauthorized_response() -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(SERVICE_UNAUTHORIZED_BODY))
+            .unwrap_or_default()
+    }
+
+    fn get_status_response(status: StatusCode) -> Response<Body> {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = status;
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_event() {
+        assert_eq!(
+            ServiceHttp::get_event(r#"{"jsonrpc":"2.0"}"#),
+            "data: {\"jsonrpc\":\"2.0\"}\n\n"
+        );
+        assert_eq!(ServiceHttp::get_event("a\nb"), "data: a\ndata: b\n\n");
+    }
+
+    #[tokio::test]
+    async fn test_poll() {
+        let (tx, rx) = mpsc::channel::<Message>(4);
+        let (outbound, _outbound_rx) = mpsc::channel::<Message>(4);
+        let connection = HttpServiceConnection::new(rx, outbound);
+        let connections: HttpServiceConnections = Arc::new(RwLock::new(HashMap::from([(
+            "conn1".to_string(),
+            connection.clone(),
+        )])));
+
+        let response = ServiceHttp::poll(
+            connections.clone(),
+            "conn1",
+            connection.clone(),
+            Duration::from_millis(10),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        tx.send(Message::Text(r#"{"id":1}"#.into())).await.unwrap();
+        tx.send(Message::Text(r#"{"id":2}"#.into())).await.unwrap();
+        let response = ServiceHttp::poll(
+            connections.clone(),
+            "conn1",
+            connection.clone(),
+            Duration::from_millis(10),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let messages: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(messages, json!([{"id": 1}, {"id": 2}]));
+
+        tx.send(Message::Close(None)).await.unwrap();
+        let response = ServiceHttp::poll(
+            connections.clone(),
+            "conn1",
+            connection,
+            Duration::from_millis(10),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::GONE);
+        assert!(connections.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stream_requeue() {
+        let (tx, rx) = mpsc::channel::<Message>(4);
+        let (outbound, _outbound_rx) = mpsc::channel::<Message>(4);
+        let connection = HttpServiceConnection::new(rx, outbound);
+        let connections: HttpServiceConnections = Arc::new(RwLock::new(HashMap::from([(
+            "conn1".to_string(),
+            connection.clone(),
+        )])));
+
+        // the service drops the stream before the message arrives
+        let response = ServiceHttp::stream(
+            connections.clone(),
+            "conn1".into(),
+            connection.clone(),
+            Duration::from_millis(10),
+        );
+        drop(response);
+        tx.send(Message::Text(r#"{"id":1}"#.into())).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let response = ServiceHttp::poll(
+            connections.clone(),
+            "conn1",
+            connection,
+            Duration::from_millis(10),
+        )
+        .await;
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let messages: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(messages, json!([{"id": 1}]));
+    }
+
+    #[tokio::test]
+    async fn test_take_idle() {
+        let (_tx, rx) = mpsc::channel::<Message>(4);
+        let (outbound, _outbound_rx) = mpsc::channel::<Message>(4);
+        let connections: HttpServiceConnections = Arc::new(RwLock::new(HashMap::from([(
+            "conn1".to_string(),
+            HttpServiceConnection::new(rx, outbound),
+        )])));
+        let idle_timeout = Duration::from_secs(90);
+        assert!(ServiceHttp::take_idle(&connections, idle_timeout, Instant::now()).is_empty());
+        let later = Instant::now() + Duration::from_secs(91);
+        let idle = ServiceHttp::take_idle(&connections, idle_timeout, later);
+        assert_eq!(idle.len(), 1);
+        assert!(connections.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_unauthorized_response() {
+        let response = ServiceHttp::get_unauthorized_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, json!({"error": "unauthorized"}));
+    }
+
+    #[tokio::test]
+    async fn test_read_body() {
+        assert_eq!(
+            ServiceHttp::read_body(Body::from("{}"), 2).await,
+            Ok("{}".to_string())
+        );
+        assert_eq!(
+            ServiceHttp::read_body(Body::from("{\"id\":1}"), 2).await,
+            Err(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+    }
+}
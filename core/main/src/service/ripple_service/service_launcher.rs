@@ -0,0 +1,391 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    process::ExitStatus,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use ripple_sdk::{
+    api::manifest::device_manifest::CompanionServiceConfiguration,
+    log::{error, info, warn},
+    tokio::{
+        self,
+        process::{Child, Command},
+    },
+    utils::error::RippleError,
+};
+
+use super::oci_launcher::OciLauncher;
+use crate::{
+    state::shutdown_state::{ShutdownPhase, ShutdownState},
+    utils::bind_utils::SD_LISTEN_ENV,
+};
+
+/// Time a container gets to exit after SIGTERM during the shutdown before its runtime is killed
+const STOP_TIMEOUT_MS: u64 = 1000;
+
+#[derive(Debug, Clone, Default)]
+pub struct LaunchedServiceInfo {
+    pub pid: Option<u32>,
+    pub restarts: u32,
+    pub registered: bool,
+    pub last_exit: Option<String>,
+}
+
+/// Tracks the companion services launched by Ripple Main and correlates them with
+/// the ServiceId registrations received on the service websocket. Services are not restarted
+/// once the shutdown started, and are stopped when it reaches [ShutdownPhase::Stopping].
+#[derive(Debug, Clone, Default)]
+pub struct ServiceLauncherState {
+    launched_services: Arc<RwLock<HashMap<String, LaunchedServiceInfo>>>,
+    shutdown: ShutdownState,
+}
+
+impl ServiceLauncherState {
+    pub fn new(shutdown: ShutdownState) -> Self {
+        Self {
+            launched_services: Arc::default(),
+            shutdown,
+        }
+    }
+
+    /// Returns true while a launched service process or container is running
+    pub fn has_running(&self) -> bool {
+        self.launched_services
+            .read()
+            .unwrap()
+            .values()
+            .any(|info| info.pid.is_some())
+    }
+
+    pub fn is_launched(&self, service_id: &str) -> bool {
+        self.launched_services
+            .read()
+            .unwrap()
+            .contains_key(service_id)
+    }
+
+    pub fn get_launched_service(&self, service_id: &str) -> Option<LaunchedServiceInfo> {
+        self.launched_services
+            .read()
+            .unwrap()
+            .get(service_id)
+            .cloned()
+    }
+
+    pub fn on_service_registered(&self, service_id: &str) {
+        if let Some(info) = self.launched_services.write().unwrap().get_mut(service_id) {
+            info!(
+                "Launched service {} registered pid={:?}",
+                service_id, info.pid
+            );
+            info.registered = true;
+        }
+    }
+
+    pub fn on_service_unregistered(&self, service_id: &str) {
+        if let Some(info) = self.launched_services.write().unwrap().get_mut(service_id) {
+            info.registered = false;
+        }
+    }
+
+    fn on_started(&self, service_id: &str, pid: Option<u32>) {
+        let mut launched_services = self.launched_services.write().unwrap();
+        let info = launched_services.entry(service_id.to_owned()).or_default();
+        info.pid = pid;
+        info.registered = false;
+    }
+
+    fn on_exited(&self, service_id: &str, exit: String, restart: bool) {
+        let mut launched_services = self.launched_services.write().unwrap();
+        if let Some(info) = launched_services.get_mut(service_id) {
+            info.pid = None;
+            info.registered = false;
+            info.last_exit = Some(exit);
+            if restart {
+                info.restarts += 1;
+            }
+        }
+    }
+
+    fn is_registered(&self, service_id: &str) -> bool {
+        self.launched_services
+            .read()
+            .unwrap()
+            .get(service_id)
+            .map(|info| info.registered)
+            .unwrap_or(false)
+    }
+}
+
+pub struct ServiceLauncher;
+
+impl ServiceLauncher {
+    pub fn launch(state: ServiceLauncherState, config: CompanionServiceConfiguration) {
+        tokio::spawn(async move {
+            Self::supervise(state, config).await;
+        });
+    }
+
+    async fn supervise(state: ServiceLauncherState, config: CompanionServiceConfiguration) {
+        let service_id = config.service_id.clone();
+        let mut backoff_ms = config.initial_backoff_ms.min(config.max_backoff_ms);
+        let mut failures: u32 = 0;
+        let mut shutdown = state.shutdown.subscribe();
+
+        while !state.shutdown.is_shutting_down() {
+            let started = Instant::now();
+            let exit = match Self::command(&config).await.and_then(|mut command| {
+                command.spawn().map_err(|e| {
+                    error!("Failed to spawn service {}: {:?}", service_id, e);
+                    RippleError::ServiceError
+                })
+            }) {
+                Ok(mut child) => {
+                    info!(
+                        "Launched service {} path={} pid={:?}",
+                        service_id,
+                        config.path,
+                        child.id()
+                    );
+                    state.on_started(&service_id, child.id());
+                    // the registration is watched only while this child runs, so a late
+                    // timeout never stops the container of the next launch
+                    let registration = Self::watch_registration(&state, &config);
+                    tokio::pin!(registration);
+                    let mut watching = true;
+                    let mut draining = false;
+                    let status = loop {
+                        tokio::select! {
+                            status = child.wait() => break status,
+                            _ = &mut registration, if watching => watching = false,
+                            _ = shutdown.wait_for(if draining {
+                                ShutdownPhase::Stopping
+                            } else {
+                                ShutdownPhase::Draining
+                            }) => {
+                                if !draining {
+                                    // the service stays up until it is unregistered in
+                                    // dependency order, a missing registration does not stop
+                                    // it anymore
+                                    draining = true;
+                                    watching = false;
+                                } else {
+                                    break Self::stop(&config, &mut child).await;
+                                }
+                            }
+                        }
+                    };
+                    let exit = match status {
+                        Ok(status) => format!("{}", status),
+                        Err(e) => format!("wait failed {:?}", e),
+                    };
+                    if let Some(oci) = &config.oci {
+                        OciLauncher::delete(oci, &OciLauncher::get_container_id(&config)).await;
+                    }
+                    exit
+                }
+                Err(e) => format!("launch failed {:?}", e),
+            };
+
+            if state.shutdown.is_shutting_down() {
+                info!("Service {} stopped for shutdown: {}", service_id, exit);
+                state.on_exited(&service_id, exit, false);
+                break;
+            }
+
+            // A service which stayed up longer than the max backoff is considered healthy
+            if started.elapsed() >= Duration::from_millis(config.max_backoff_ms) {
+                backoff_ms = config.initial_backoff_ms.min(config.max_backoff_ms);
+                failures = 0;
+            }
+            failures += 1;
+
+            let restart = config
+                .max_restarts
+                .map(|max_restarts| failures <= max_restarts)
+                .unwrap_or(true);
+            error!(
+                "Service {} exited: {} restart={} backoff_ms={}",
+                service_id, exit, restart, backoff_ms
+            );
+            state.on_exited(&service_id, exit, restart);
+            if !restart {
+                break;
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_millis(backoff_ms)) => {}
+                _ = shutdown.wait_for(ShutdownPhase::Draining) => break,
+            }
+            backoff_ms = backoff_ms.saturating_mul(2).min(config.max_backoff_ms);
+        }
+    }
+
+    /// Stops the child for the shutdown and waits for it, the container is deleted once the
+    /// runtime exited
+    async fn stop(
+        config: &CompanionServiceConfiguration,
+        child: &mut Child,
+    ) -> std::io::Result<ExitStatus> {
+        info!("Stopping service {}", config.service_id);
+        if let Some(oci) = &config.oci {
+            OciLauncher::kill(oci, &OciLauncher::get_container_id(config)).await;
+            let timeout = Duration::from_millis(STOP_TIMEOUT_MS);
+            if let Ok(status) = tokio::time::timeout(timeout, child.wait()).await {
+                return status;
+            }
+            warn!("Container of {} did not stop in time", config.service_id);
+        }
+        if let Err(e) = child.start_kill() {
+            error!("Failed to stop service {}: {:?}", config.service_id, e);
+        }
+        child.wait().await
+    }
+
+    async fn command(config: &CompanionServiceConfiguration) -> Result<Command, RippleError> {
+        let mut command = match &config.oci {
+            Some(oci) => OciLauncher::command(config, oci).await?,
+            None => {
+                let mut command = Command::new(&config.path);
+                command
+                    .args(&config.args)
+                    .envs(&config.env)
+                    .kill_on_drop(true);
+                command
+            }
+        };
+        // sockets passed through systemd socket activation belong to ripple
+        for key in SD_LISTEN_ENV {
+            command.env_remove(key);
+        }
+        Ok(command)
+    }
+
+    async fn watch_registration(
+        state: &ServiceLauncherState,
+        config: &CompanionServiceConfiguration,
+    ) {
+        let timeout_ms = config.registration_timeout_ms;
+        tokio::time::sleep(Duration::from_millis(timeout_ms)).await;
+        if !state.is_registered(&config.service_id) {
+            warn!(
+                "Launched service {} did not register within {}ms",
+                config.service_id, timeout_ms
+            );
+            // Containers are tied to the service registration, stop the container
+            // so the supervisor starts it again
+            if let Some(oci) = &config.oci {
+                OciLauncher::kill(oci, &OciLauncher::get_container_id(config)).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::serde_json::{self, json};
+
+    fn get_config(service_id: &str, script: &str) -> CompanionServiceConfiguration {
+        serde_json::from_value(json!({
+            "service_id": service_id,
+            "path": "/bin/sh",
+            "args": ["-c", script],
+            "initial_backoff_ms": 10,
+            "max_backoff_ms": 10,
+        }))
+        .unwrap()
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_registration_correlation() {
+        let state = ServiceLauncherState::default();
+        state.on_service_registered("ripple:channel:gateway:unknown");
+        assert!(!state.is_launched("ripple:channel:gateway:unknown"));
+
+        state.on_started("ripple:channel:gateway:service1", Some(100));
+        state.on_service_registered("ripple:channel:gateway:service1");
+        assert!(state.is_registered("ripple:channel:gateway:service1"));
+
+        state.on_exited("ripple:channel:gateway:service1", "exit".into(), true);
+        let info = state
+            .get_launched_service("ripple:channel:gateway:service1")
+            .unwrap();
+        assert!(!info.registered);
+        assert_eq!(info.restarts, 1);
+        assert_eq!(info.pid, None);
+    }
+
+    #[tokio::test]
+    async fn test_services_stopped_on_shutdown() {
+        let shutdown = ShutdownState::default();
+        let state = ServiceLauncherState::new(shutdown.clone());
+        let running = "ripple:channel:gateway:running";
+        let exiting = "ripple:channel:gateway:exiting";
+        let running_supervisor = tokio::spawn(ServiceLauncher::supervise(
+            state.clone(),
+            get_config(running, "sleep 30"),
+        ));
+        let exiting_supervisor = tokio::spawn(ServiceLauncher::supervise(
+            state.clone(),
+            get_config(exiting, "sleep 0.2"),
+        ));
+        wait_until(|| {
+            state
+                .get_launched_service(running)
+                .and_then(|info| info.pid)
+                .is_some()
+                && state
+                    .get_launched_service(exiting)
+                    .and_then(|info| info.pid)
+                    .is_some()
+        })
+        .await;
+
+        // services are left running to be unregistered, and not restarted once they exit
+        assert!(shutdown.start("test"));
+        exiting_supervisor.await.unwrap();
+        let info = state.get_launched_service(exiting).unwrap();
+        assert_eq!((info.pid, info.restarts), (None, 0));
+        assert!(state.get_launched_service(running).unwrap().pid.is_some());
+        assert!(state.has_running());
+
+        shutdown.close_connections();
+        shutdown.stop_services();
+        tokio::time::timeout(Duration::from_secs(5), running_supervisor)
+            .await
+            .unwrap()
+            .unwrap();
+        let info = state.get_launched_service(running).unwrap();
+        assert_eq!((info.pid, info.restarts), (None, 0));
+        assert!(!state.has_running());
+    }
+}
@@ -15,70 +15,211 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 use ripple_sdk::{
+    api::manifest::device_manifest::ServiceTakeoverPolicy,
     tokio::sync::{mpsc, Mutex},
-    tokio_tungstenite::tungstenite::Message,
+    tokio_tungstenite::tungstenite::{
+        protocol::{frame::coding::CloseCode, CloseFrame},
+        Message,
+    },
     utils::error::RippleError,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use super::service_controller_state::ServiceInfo;
 use crate::broker::endpoint_broker::BrokerCallback;
+
+/// Close code sent to a service connection which was taken over by a forced connection
+pub const SERVICE_TAKEOVER_CLOSE_CODE: u16 = 4001;
+/// Close code sent to a service connection rejected because its ServiceId is already connected
+pub const SERVICE_ID_IN_USE_CLOSE_CODE: u16 = 4002;
+/// Close code sent to a service connection which missed its heartbeats
+pub const SERVICE_UNRESPONSIVE_CLOSE_CODE: u16 = 4003;
+/// Close code sent to a service connection rejected because its tenant reached the service quota
+pub const SERVICE_QUOTA_EXCEEDED_CLOSE_CODE: u16 = 4004;
+/// Close code sent to a service connection rejected because of an incompatible contract version
+pub const SERVICE_INCOMPATIBLE_CONTRACT_CLOSE_CODE: u16 = 4005;
+
 #[derive(Debug, Default)]
 pub struct ServiceRegistry {
     service_registry: Mutex<HashMap<String, ServiceInfo>>,
 }
 
 impl ServiceRegistry {
+    /// Adds the service connection for the given ServiceId. If the ServiceId is already
+    /// connected the new connection is rejected unless the takeover policy allows a
+    /// forced takeover, in which case the previous connection is closed and returned so
+    /// the requests it left unanswered can be held for the new connection.
     pub async fn add_service_info(
         &self,
         service_id: String,
         info: ServiceInfo,
-    ) -> Result<(), RippleError> {
-        let old_tx = {
+        policy: ServiceTakeoverPolicy,
+        force: bool,
+    ) -> Result<Option<ServiceInfo>, RippleError> {
+        let old_info = {
             let mut registry = self.service_registry.lock().await;
-            let old_tx = registry.get(&service_id).map(|info| info.tx.clone());
-            // insert the new service info
-            registry.insert(service_id, info);
-            old_tx
+            if let Some(existing) = registry.get(&service_id) {
+                if !(policy == ServiceTakeoverPolicy::AllowForced && force) {
+                    return Err(RippleError::InvalidAccess);
+                }
+                let old_info = existing.clone();
+                registry.insert(service_id, info);
+                Some(old_info)
+            } else {
+                registry.insert(service_id, info);
+                None
+            }
         };
-        // Now, outside the lock, optionally send a disconnect message to the old client
-        if let Some(tx) = old_tx {
-            let _ = tx.send(Message::Close(None)).await;
+        // Now, outside the lock, send a disconnect message to the old client
+        if let Some(old_info) = old_info {
+            let _ = old_info
+                .tx
+                .send(Message::Close(Some(CloseFrame {
+                    code: CloseCode::from(SERVICE_TAKEOVER_CLOSE_CODE),
+                    reason: "Service taken over".into(),
+                })))
+                .await;
+            return Ok(Some(old_info));
         }
-        Ok(())
+        Ok(None)
     }
 
-    pub async fn remove_service_info(&self, service_id: &String) -> Result<(), RippleError> {
+    /// Removes the service connection only if it is still the active connection for the
+    /// ServiceId, so cleanup of a taken over connection does not drop its replacement.
+    pub async fn remove_service_info(
+        &self,
+        service_id: &String,
+        connection_id: &str,
+    ) -> Result<ServiceInfo, RippleError> {
         let mut registry = self.service_registry.lock().await;
-        if registry.remove(service_id).is_some() {
-            Ok(())
-        } else {
-            Err(RippleError::InvalidInput)
+        match registry.get(service_id) {
+            Some(info) if info.connection_id == connection_id => {
+                registry.remove(service_id).ok_or(RippleError::InvalidInput)
+            }
+            _ => Err(RippleError::InvalidInput),
+        }
+    }
+
+    /// Records activity on the service connection, ignored for a connection which was
+    /// taken over
+    pub async fn touch(&self, service_id: &String, connection_id: &str) {
+        let mut registry = self.service_registry.lock().await;
+        if let Some(info) = registry.get_mut(service_id) {
+            if info.connection_id == connection_id {
+                info.touch();
+            }
+        }
+    }
+
+    /// Marks the service as registered with the granted capabilities, returns false when the
+    /// service is not connected on the connection
+    pub async fn set_registration(
+        &self,
+        service_id: &String,
+        connection_id: &str,
+        capabilities: Vec<String>,
+    ) -> bool {
+        let mut registry = self.service_registry.lock().await;
+        match registry.get_mut(service_id) {
+            Some(info) if info.connection_id == connection_id => {
+                info.set_registered(true);
+                info.capabilities = capabilities;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the capabilities granted to the service when it is registered on the connection
+    pub async fn get_registration(
+        &self,
+        service_id: &String,
+        connection_id: &str,
+    ) -> Option<Vec<String>> {
+        let registry = self.service_registry.lock().await;
+        registry
+            .get(service_id)
+            .filter(|info| info.connection_id == connection_id && info.is_registered())
+            .map(|info| info.capabilities.clone())
+    }
+
+    /// Removes and returns the connection of the service if it has not been seen within the
+    /// timeout
+    pub async fn remove_stale_service(
+        &self,
+        service_id: &String,
+        timeout: Duration,
+    ) -> Option<ServiceInfo> {
+        let mut registry = self.service_registry.lock().await;
+        match registry.get(service_id) {
+            Some(info) if info.is_stale(timeout) => registry.remove(service_id),
+            _ => None,
         }
     }
 
+    /// Removes and returns the services which have not been seen within the timeout
+    pub async fn remove_stale_services(&self, timeout: Duration) -> Vec<(String, ServiceInfo)> {
+        let mut registry = self.service_registry.lock().await;
+        let stale: Vec<String> = registry
+            .iter()
+            .filter(|(_, info)| info.is_stale(timeout))
+            .map(|(service_id, _)| service_id.clone())
+            .collect();
+        stale
+            .into_iter()
+            .filter_map(|service_id| registry.remove(&service_id).map(|info| (service_id, info)))
+            .collect()
+    }
+
+    pub async fn get_senders(&self) -> Vec<(String, mpsc::Sender<Message>)> {
+        let registry = self.service_registry.lock().await;
+        registry
+            .iter()
+            .map(|(service_id, info)| (service_id.clone(), info.tx.clone()))
+            .collect()
+    }
+
+    pub async fn get_service_ids(&self) -> Vec<String> {
+        let registry = self.service_registry.lock().await;
+        registry.keys().cloned().collect()
+    }
+
     // get sender for a given service_id
     pub async fn get_sender(&self, service_id: &String) -> Option<mpsc::Sender<Message>> {
         let registry = self.service_registry.lock().await;
         registry.get(service_id).map(|info| info.tx.clone())
     }
 
-    // set Broker callback for a given service_id
+    // set Broker callback for a given service_id along with the request sent to the service
     pub async fn set_broker_callback(
         &self,
         service_id: &String,
         request_id: u64,
         callback: BrokerCallback,
+        request: Option<String>,
     ) -> Result<(), RippleError> {
         let mut registry = self.service_registry.lock().await;
         if let Some(info) = registry.get_mut(service_id) {
             info.add_callback(request_id, callback).await;
+            if let Some(request) = request {
+                info.add_request(request_id, request).await;
+            }
             Ok(())
         } else {
             Err(RippleError::InvalidInput)
         }
     }
 
+    /// Drops the broker callbacks of the requests, whichever service they were sent to
+    pub async fn remove_broker_callbacks(&self, request_ids: &[u64]) {
+        let mut registry = self.service_registry.lock().await;
+        for info in registry.values_mut() {
+            for request_id in request_ids {
+                info.remove_callback(*request_id).await;
+            }
+        }
+    }
+
     // get the broker callback for a given service_id
     pub async fn extract_broker_callback(
         &self,
@@ -93,3 +234,157 @@ impl ServiceRegistry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::tokio;
+
+    #[tokio::test]
+    async fn test_service_takeover() {
+        let registry = ServiceRegistry::default();
+        let service_id = "ripple:channel:gateway:service1".to_string();
+        let (tx1, mut rx1) = mpsc::channel::<Message>(2);
+        let (tx2, _rx2) = mpsc::channel::<Message>(2);
+
+        let result = registry
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn1".into(), tx1, false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await;
+        assert!(matches!(result, Ok(None)));
+
+        // rejected without a force flag or when the policy does not allow takeover
+        let result = registry
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn2".into(), tx2.clone(), false),
+                ServiceTakeoverPolicy::AllowForced,
+                false,
+            )
+            .await;
+        assert!(matches!(result, Err(RippleError::InvalidAccess)));
+        let result = registry
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn2".into(), tx2.clone(), false),
+                ServiceTakeoverPolicy::Reject,
+                true,
+            )
+            .await;
+        assert!(matches!(result, Err(RippleError::InvalidAccess)));
+
+        let result = registry
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn2".into(), tx2, false),
+                ServiceTakeoverPolicy::AllowForced,
+                true,
+            )
+            .await;
+        assert_eq!(result.unwrap().unwrap().get_connection_id(), "conn1");
+        assert!(matches!(
+            rx1.recv().await,
+            Some(Message::Close(Some(CloseFrame { code, .. })))
+                if u16::from(code) == SERVICE_TAKEOVER_CLOSE_CODE
+        ));
+
+        // cleanup of the old connection keeps the new one
+        assert!(registry
+            .remove_service_info(&service_id, "conn1")
+            .await
+            .is_err());
+        assert!(registry.get_sender(&service_id).await.is_some());
+        assert!(registry
+            .remove_service_info(&service_id, "conn2")
+            .await
+            .is_ok());
+
+        // the default policy rejects a second connection even with a force flag
+        let (tx3, _rx3) = mpsc::channel::<Message>(2);
+        let (tx4, _rx4) = mpsc::channel::<Message>(2);
+        let result = registry
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn3".into(), tx3, false),
+                ServiceTakeoverPolicy::default(),
+                false,
+            )
+            .await;
+        assert!(matches!(result, Ok(None)));
+        let result = registry
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn4".into(), tx4, false),
+                ServiceTakeoverPolicy::default(),
+                true,
+            )
+            .await;
+        assert!(matches!(result, Err(RippleError::InvalidAccess)));
+    }
+
+    #[tokio::test]
+    async fn test_remove_stale_service() {
+        let registry = ServiceRegistry::default();
+        let service_id = "ripple:channel:gateway:service1".to_string();
+        let (tx, _rx) = mpsc::channel::<Message>(2);
+        registry
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn1".into(), tx, false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert!(registry
+            .remove_stale_service(&service_id, Duration::from_secs(60))
+            .await
+            .is_none());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let removed = registry
+            .remove_stale_service(&service_id, Duration::from_millis(10))
+            .await;
+        assert_eq!(removed.unwrap().get_connection_id(), "conn1");
+        assert!(registry.get_sender(&service_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_remove_stale_services() {
+        let registry = ServiceRegistry::default();
+        let service_id = "ripple:channel:gateway:service1".to_string();
+        let (tx, _rx) = mpsc::channel::<Message>(2);
+        registry
+            .add_service_info(
+                service_id.clone(),
+                ServiceInfo::new("conn1".into(), tx, false),
+                ServiceTakeoverPolicy::Reject,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert!(registry
+            .remove_stale_services(Duration::from_secs(60))
+            .await
+            .is_empty());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        registry.touch(&service_id, "conn1").await;
+        assert!(registry
+            .remove_stale_services(Duration::from_millis(10))
+            .await
+            .is_empty());
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let stale = registry
+            .remove_stale_services(Duration::from_millis(10))
+            .await;
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].0, service_id);
+        assert!(registry.get_sender(&service_id).await.is_none());
+    }
+}
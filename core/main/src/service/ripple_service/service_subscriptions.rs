@@ -0,0 +1,142 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::broker::endpoint_broker::BrokerRequest;
+
+/// Broker requests of the listeners keyed by event method
+type EventListeners = HashMap<String, Vec<BrokerRequest>>;
+
+/// Listeners of the events provided by services, keyed by ServiceId and event method.
+///
+/// Each listener is the broker request of the listen call, its call id is used to route the
+/// events pushed by the service back through the broker to the listening session.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceSubscriptions {
+    listeners: Arc<RwLock<HashMap<String, EventListeners>>>,
+}
+
+impl ServiceSubscriptions {
+    /// Adds the listener, replacing an existing listener of the session for the same event.
+    /// The replaced listener is returned.
+    pub fn subscribe(&self, service_id: &str, request: &BrokerRequest) -> Option<BrokerRequest> {
+        let mut listeners = self.listeners.write().unwrap();
+        let event_listeners = listeners
+            .entry(service_id.to_owned())
+            .or_default()
+            .entry(request.rpc.ctx.method.to_lowercase())
+            .or_default();
+        let replaced = Self::remove_listener(event_listeners, &request.rpc.ctx.session_id);
+        event_listeners.push(request.clone());
+        replaced
+    }
+
+    pub fn unsubscribe(&self, service_id: &str, request: &BrokerRequest) -> Option<BrokerRequest> {
+        let mut listeners = self.listeners.write().unwrap();
+        let event_listeners = listeners
+            .get_mut(service_id)?
+            .get_mut(&request.rpc.ctx.method.to_lowercase())?;
+        Self::remove_listener(event_listeners, &request.rpc.ctx.session_id)
+    }
+
+    fn remove_listener(
+        listeners: &mut Vec<BrokerRequest>,
+        session_id: &str,
+    ) -> Option<BrokerRequest> {
+        let i = listeners
+            .iter()
+            .position(|l| l.rpc.ctx.session_id.eq(session_id))?;
+        Some(listeners.remove(i))
+    }
+
+    pub fn get_listeners(&self, service_id: &str, event: &str) -> Vec<BrokerRequest> {
+        self.listeners
+            .read()
+            .unwrap()
+            .get(service_id)
+            .and_then(|events| events.get(&event.to_lowercase()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Removes every listener of the session, returned along with the ServiceId
+    pub fn remove_session(&self, session_id: &str) -> Vec<(String, BrokerRequest)> {
+        let mut removed = Vec::new();
+        let mut listeners = self.listeners.write().unwrap();
+        for (service_id, events) in listeners.iter_mut() {
+            for event_listeners in events.values_mut() {
+                if let Some(listener) = Self::remove_listener(event_listeners, session_id) {
+                    removed.push((service_id.clone(), listener));
+                }
+            }
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{api::gateway::rpc_gateway_api::RpcRequest, Mockable};
+
+    fn get_listen_request(session_id: &str, call_id: u64) -> BrokerRequest {
+        let mut rpc = RpcRequest::mock();
+        rpc.ctx.method = "svc.onChanged".into();
+        rpc.ctx.session_id = session_id.into();
+        rpc.ctx.call_id = call_id;
+        BrokerRequest {
+            rpc,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_service_subscriptions() {
+        let subscriptions = ServiceSubscriptions::default();
+        assert!(subscriptions
+            .subscribe("svc", &get_listen_request("session1", 1))
+            .is_none());
+        assert!(subscriptions
+            .subscribe("svc", &get_listen_request("session2", 2))
+            .is_none());
+        let replaced = subscriptions
+            .subscribe("svc", &get_listen_request("session1", 3))
+            .unwrap();
+        assert_eq!(replaced.rpc.ctx.call_id, 1);
+
+        let listeners = subscriptions.get_listeners("svc", "svc.onchanged");
+        assert_eq!(listeners.len(), 2);
+        assert!(subscriptions
+            .get_listeners("other", "svc.onChanged")
+            .is_empty());
+
+        let removed = subscriptions
+            .unsubscribe("svc", &get_listen_request("session2", 4))
+            .unwrap();
+        assert_eq!(removed.rpc.ctx.call_id, 2);
+
+        let removed = subscriptions.remove_session("session1");
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].0, "svc");
+        assert!(subscriptions
+            .get_listeners("svc", "svc.onChanged")
+            .is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::{
+    api::device::device_provisioning::{
+        CertificateInfo, IdentityKeyInfo, InstallCertificateParams, ProvisioningRequest,
+        ProvisioningResponse, RotateIdentityKeyParams, SecureElement,
+    },
+    async_trait::async_trait,
+    extn::extn_client_message::ExtnResponse,
+    utils::error::RippleError,
+};
+
+use crate::service::extn::ripple_client::RippleClient;
+
+/// Default [SecureElement] which forwards the operations to the extension fulfilling the
+/// `secure_element` contract.
+pub struct ExtnSecureElement {
+    client: RippleClient,
+}
+
+impl ExtnSecureElement {
+    pub fn new(client: RippleClient) -> ExtnSecureElement {
+        ExtnSecureElement { client }
+    }
+
+    async fn send(
+        &self,
+        request: ProvisioningRequest,
+    ) -> Result<ProvisioningResponse, RippleError> {
+        let response = self.client.send_extn_request(request).await?;
+        if let Some(ExtnResponse::Error(e)) = response.payload.extract() {
+            return Err(e);
+        }
+        response.payload.extract().ok_or(RippleError::InvalidOutput)
+    }
+}
+
+#[async_trait]
+impl SecureElement for ExtnSecureElement {
+    async fn install_certificate(
+        &self,
+        params: InstallCertificateParams,
+    ) -> Result<CertificateInfo, RippleError> {
+        match self
+            .send(ProvisioningRequest::InstallCertificate(params))
+            .await?
+        {
+            ProvisioningResponse::Certificate(info) => Ok(info),
+            _ => Err(RippleError::InvalidOutput),
+        }
+    }
+
+    async fn rotate_identity_key(
+        &self,
+        params: RotateIdentityKeyParams,
+    ) -> Result<IdentityKeyInfo, RippleError> {
+        match self
+            .send(ProvisioningRequest::RotateIdentityKey(params))
+            .await?
+        {
+            ProvisioningResponse::IdentityKey(info) => Ok(info),
+            _ => Err(RippleError::InvalidOutput),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::{
+    log::{error, info},
+    tokio::{
+        self,
+        signal::unix::{signal, SignalKind},
+    },
+    utils::error::RippleError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bootstrap::start_fbgateway_step::FireboltGatewayStep, state::platform_state::PlatformState,
+};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoftRestartParams {
+    pub reason: Option<String>,
+    /// Closes the app connections so apps reconnect against the restarted gateway, this
+    /// includes the connection which requested the restart
+    #[serde(default)]
+    pub close_app_connections: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SoftRestartResult {
+    pub methods: usize,
+    pub rules: usize,
+    pub closed_connections: usize,
+}
+
+/// Restarts the app facing layer of the gateway, triggered by SIGHUP or the
+/// `ripple.softRestart` admin method after a config push:
+///
+/// 1. reload the rules from the extn manifest, keeping the rules registered by services,
+///    and restart the brokers of the endpoints whose configuration changed
+/// 2. register the Firebolt methods with the router again
+/// 3. optionally close the app connections so apps reconnect
+///
+/// Service connections, extension channels and the other brokers are left untouched.
+pub struct SoftRestart;
+
+impl SoftRestart {
+    pub fn listen(state: PlatformState) {
+        tokio::spawn(async move {
+            match signal(SignalKind::hangup()) {
+                Ok(mut sighup) => {
+                    while sighup.recv().await.is_some() {
+                        if let Err(e) = Self::run(&state, "SIGHUP", true).await {
+                            error!("Soft restart failed {:?}", e);
+                        }
+                    }
+                }
+                Err(e) => error!("Unable to listen for SIGHUP {:?}", e),
+            }
+        });
+    }
+
+    pub async fn run(
+        state: &PlatformState,
+        reason: &str,
+        close_app_connections: bool,
+    ) -> Result<SoftRestartResult, RippleError> {
+        if state.shutdown_state.is_shutting_down() {
+            return Err(RippleError::ServiceError);
+        }
+        info!("Soft restart of the gateway reason={}", reason);
+        let rules = state.endpoint_state.clone().reload_rules(state.clone());
+        let methods = FireboltGatewayStep::init_handlers(state.clone()).await;
+        state.router_state.replace_methods(methods)?;
+        let closed_connections = if close_app_connections {
+            state.session_state.evict_all(reason)
+        } else {
+            0
+        };
+        let result = SoftRestartResult {
+            methods: state.router_state.get_method_names().len(),
+            rules,
+            closed_connections,
+        };
+        info!("Soft restart complete {:?}", result);
+        Ok(result)
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path},
+};
+
+use ripple_sdk::{
+    api::{
+        distributor::distributor_privacy::PrivacySettingsData,
+        firebolt::fb_capabilities::{FireboltCap, FireboltPermission},
+    },
+    chrono::Utc,
+    log::{info, warn},
+    tokio,
+    utils::error::RippleError,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    broker::rules::rules_engine::{Rule, RuleProvenance},
+    service::diagnostics_bundle::DiagnosticsBundle,
+    state::platform_state::PlatformState,
+};
+
+const SNAPSHOT_FILE: &str = "state_snapshot.json";
+
+/// `path` is relative to the saved dir of the device
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSnapshotParams {
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapsSnapshot {
+    pub supported: Vec<FireboltPermission>,
+    pub not_available: Vec<String>,
+    pub permitted: HashMap<String, Vec<FireboltPermission>>,
+}
+
+/// Session ids are left out, only the number of sessions of each app is kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsSnapshot {
+    pub count: usize,
+    pub apps: HashMap<String, usize>,
+}
+
+/// Sanitized snapshot of the platform state which can be loaded into a dev instance to
+/// reproduce a field issue with realistic state.
+///
+/// Secrets in the rules are redacted like in the diagnostics bundle and the endpoints only
+/// keep their URL without the query and the credentials. Loading restores the caps, the cached privacy
+/// settings and the rules, the sessions and the endpoints of the dev instance are kept.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSnapshot {
+    pub ripple_version: Option<String>,
+    pub timestamp: i64,
+    pub caps: CapsSnapshot,
+    pub sessions: SessionsSnapshot,
+    pub privacy_settings: PrivacySettingsData,
+    pub rules: HashMap<String, Value>,
+    pub endpoints: HashMap<String, String>,
+}
+
+impl StateSnapshot {
+    /// Resolves the path of the snapshot in the saved dir, paths leaving the saved dir are
+    /// refused
+    pub fn get_path(state: &PlatformState, path: Option<String>) -> Result<String, RippleError> {
+        let path = path.unwrap_or_else(|| SNAPSHOT_FILE.to_owned());
+        let relative = Path::new(&path);
+        if relative.as_os_str().is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(RippleError::InvalidInput);
+        }
+        let saved_dir = state.get_device_manifest().configuration.saved_dir;
+        Ok(Path::new(&saved_dir)
+            .join(relative)
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    pub fn collect(state: &PlatformState) -> StateSnapshot {
+        let rules = state
+            .endpoint_state
+            .get_rules()
+            .into_iter()
+            .map(|(name, rule)| {
+                let mut value = serde_json::to_value(rule).unwrap_or_default();
+                DiagnosticsBundle::redact(&mut value);
+                (name, value)
+            })
+            .collect();
+        let endpoints = state
+            .endpoint_state
+            .get_rule_endpoints()
+            .into_iter()
+            .map(|(name, endpoint)| {
+                let url = endpoint.url.split('?').next().unwrap_or_default();
+                let url = DiagnosticsBundle::redact_url(url).unwrap_or_else(|| url.to_owned());
+                (name, url)
+            })
+            .collect();
+        let app_sessions = state.session_state.get_app_session_counts();
+
+        StateSnapshot {
+            ripple_version: state.version.clone(),
+            timestamp: Utc::now().timestamp_millis(),
+            caps: CapsSnapshot {
+                supported: state.cap_state.generic.get_supported(),
+                not_available: state.cap_state.generic.get_not_available(),
+                permitted: state.cap_state.permitted_state.get_all_permissions(),
+            },
+            sessions: SessionsSnapshot {
+                count: app_sessions.values().sum(),
+                apps: app_sessions,
+            },
+            privacy_settings: state.ripple_cache.get_privacy_settings(),
+            rules,
+            endpoints,
+        }
+    }
+
+    pub async fn dump(state: &PlatformState, path: &str) -> Result<(), RippleError> {
+        let snapshot = Self::collect(state);
+        let contents =
+            serde_json::to_string_pretty(&snapshot).map_err(|_| RippleError::ParseError)?;
+        let file = path.to_owned();
+        tokio::task::spawn_blocking(move || fs::write(file, contents))
+            .await
+            .map_err(|_| RippleError::InvalidOutput)?
+            .map_err(|_| RippleError::InvalidOutput)?;
+        info!("State snapshot written to {}", path);
+        Ok(())
+    }
+
+    pub async fn load(state: &PlatformState, path: &str) -> Result<(), RippleError> {
+        let file = path.to_owned();
+        let contents = tokio::task::spawn_blocking(move || fs::read_to_string(file))
+            .await
+            .map_err(|_| RippleError::InvalidInput)?
+            .map_err(|_| RippleError::InvalidInput)?;
+        let snapshot: StateSnapshot =
+            serde_json::from_str(&contents).map_err(|_| RippleError::ParseError)?;
+        snapshot.restore(state, path);
+        Ok(())
+    }
+
+    fn restore(self, state: &PlatformState, path: &str) {
+        let generic = &state.cap_state.generic;
+        generic.ingest_supported(self.caps.supported);
+        generic.ingest_availability(
+            self.caps
+                .not_available
+                .into_iter()
+                .map(FireboltCap::Full)
+                .collect(),
+            false,
+        );
+        state
+            .cap_state
+            .permitted_state
+            .clone()
+            .set_permissions(self.caps.permitted);
+        state
+            .ripple_cache
+            .set_privacy_settings(self.privacy_settings);
+
+        let mut rules = HashMap::new();
+        for (name, value) in self.rules {
+            match serde_json::from_value::<Rule>(value) {
+                Ok(mut rule) => {
+                    rule.with_provenance(RuleProvenance::Snapshot {
+                        path: path.to_owned(),
+                    });
+                    rules.insert(name, rule);
+                }
+                Err(e) => warn!("Skipping rule {} from the snapshot {:?}", name, e),
+            }
+        }
+        info!(
+            "State snapshot {} from {:?} loaded with {} rules",
+            path,
+            self.ripple_version,
+            rules.len()
+        );
+        state.endpoint_state.restore_rules(rules);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_tdk::utils::test_utils::Mockable;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_state_snapshot() {
+        let mut state = PlatformState::mock();
+        let saved_dir = std::env::temp_dir().join(format!(
+            "ripple_state_snapshot_test_{}",
+            ripple_sdk::uuid::Uuid::new_v4()
+        ));
+        fs::create_dir_all(&saved_dir).unwrap();
+        let mut manifest = state.get_device_manifest();
+        manifest.configuration.saved_dir = saved_dir.to_string_lossy().into_owned();
+        state.set_device_manifest(manifest);
+
+        for path in ["../state.json", "/tmp/state.json", "", "./"] {
+            assert!(StateSnapshot::get_path(&state, Some(path.into())).is_err());
+        }
+        let path = StateSnapshot::get_path(&state, None).unwrap();
+        assert!(path.starts_with(saved_dir.to_str().unwrap()));
+
+        let mut snapshot = StateSnapshot::collect(&state);
+        snapshot.rules.insert(
+            "device.name".into(),
+            json!({ "alias": "org.rdk.System.getFriendlyName" }),
+        );
+        snapshot.caps.not_available = vec!["xrn:firebolt:capability:device:name".into()];
+        snapshot.privacy_settings.allow_watch_history = Some(true);
+
+        fs::write(&path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+        StateSnapshot::load(&state, &path).await.unwrap();
+
+        let restored = StateSnapshot::collect(&state);
+        assert!(restored.rules.contains_key("device.name"));
+        assert!(restored
+            .caps
+            .not_available
+            .contains(&"xrn:firebolt:capability:device:name".to_owned()));
+        assert_eq!(restored.privacy_settings.allow_watch_history, Some(true));
+        assert_eq!(
+            state
+                .endpoint_state
+                .get_rule_provenance()
+                .get("device.name"),
+            Some(&RuleProvenance::Snapshot { path: path.clone() })
+        );
+        let _ = fs::remove_dir_all(saved_dir);
+    }
+}
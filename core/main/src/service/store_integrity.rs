@@ -0,0 +1,158 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::ErrorKind,
+    path::Path,
+};
+
+use ripple_sdk::{
+    api::{
+        device::device_user_grants_data::GrantEntry,
+        firebolt::{
+            fb_capabilities::FireboltPermission,
+            fb_discovery::ContentAccessInfo,
+            fb_telemetry::{AppUsage, RepairedStore},
+        },
+    },
+    chrono::Utc,
+    log::{info, warn},
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    service::{
+        activation_orchestrator::ActivationCheckpoint,
+        apps::app_library_refresh::AppLibrarySnapshot, watch_history::WatchHistoryQueue,
+    },
+    state::request_journal_state::RequestJournal,
+};
+
+type StoreValidator = fn(&str) -> bool;
+
+fn parses<T: DeserializeOwned>(contents: &str) -> bool {
+    serde_json::from_str::<T>(contents).is_ok()
+}
+
+/// Stores persisted in the saved dir along with the check of their content
+const PERSISTED_STORES: &[(&str, StoreValidator)] = &[
+    ("device_grants", parses::<HashSet<GrantEntry>>),
+    ("app_grants", parses::<HashMap<String, HashSet<GrantEntry>>>),
+    (
+        "app_perms",
+        parses::<HashMap<String, Vec<FireboltPermission>>>,
+    ),
+    ("app_info/appInfo.json", parses::<HashMap<String, String>>),
+    (
+        "apps/migrations.json",
+        parses::<HashMap<String, Vec<String>>>,
+    ),
+    ("watch_history_queue", parses::<WatchHistoryQueue>),
+    ("activation", parses::<ActivationCheckpoint>),
+    ("app_library", parses::<AppLibrarySnapshot>),
+    (
+        "content_access",
+        parses::<HashMap<String, ContentAccessInfo>>,
+    ),
+    ("app_usage", parses::<HashMap<String, AppUsage>>),
+    ("request_journal", parses::<RequestJournal>),
+];
+
+/// Verifies the persisted stores on boot before the platform state loads them.
+///
+/// A store which cannot be read or parsed is moved aside with a timestamp suffix so it can be
+/// inspected later, and the state starts from its defaults.
+pub struct StoreIntegrity;
+
+impl StoreIntegrity {
+    pub fn check(saved_dir: &str) -> Vec<RepairedStore> {
+        let timestamp = Utc::now().timestamp_millis();
+        let repaired: Vec<RepairedStore> = PERSISTED_STORES
+            .iter()
+            .filter_map(|(store, validator)| {
+                Self::check_store(saved_dir, store, *validator, timestamp)
+            })
+            .collect();
+        info!(
+            "Checked {} persisted stores, repaired={}",
+            PERSISTED_STORES.len(),
+            repaired.len()
+        );
+        repaired
+    }
+
+    fn check_store(
+        saved_dir: &str,
+        store: &str,
+        validator: StoreValidator,
+        timestamp: i64,
+    ) -> Option<RepairedStore> {
+        let path = Path::new(saved_dir).join(store);
+        let reason = match fs::read_to_string(&path) {
+            Ok(contents) if validator(&contents) => return None,
+            Ok(_) => "unparsable".to_owned(),
+            Err(e) if e.kind() == ErrorKind::NotFound => return None,
+            Err(e) => format!("unreadable: {}", e),
+        };
+        let quarantined_path = format!("{}.corrupt.{}", path.display(), timestamp);
+        if let Err(e) = fs::rename(&path, &quarantined_path) {
+            // the store cannot be moved aside, remove it so the defaults are used
+            warn!("Unable to quarantine {}: {}", path.display(), e);
+            let _ = fs::remove_file(&path);
+        }
+        warn!(
+            "Persisted store {} is {}, moved to {}",
+            store, reason, quarantined_path
+        );
+        Some(RepairedStore {
+            store: store.to_owned(),
+            quarantined_path,
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_stores() {
+        let saved_dir = std::env::temp_dir().join("ripple_store_integrity_test");
+        let _ = fs::remove_dir_all(&saved_dir);
+        fs::create_dir_all(saved_dir.join("app_info")).unwrap();
+        fs::write(saved_dir.join("app_perms"), "{\"app1\": [").unwrap();
+        fs::write(
+            saved_dir.join("app_info/appInfo.json"),
+            "{\"app1\": \"App\"}",
+        )
+        .unwrap();
+        fs::write(saved_dir.join("content_access"), "[1, 2]").unwrap();
+
+        let repaired = StoreIntegrity::check(saved_dir.to_str().unwrap());
+        assert_eq!(repaired.len(), 2);
+        assert!(repaired.iter().all(|r| r.reason.eq("unparsable")));
+        assert!(!saved_dir.join("app_perms").exists());
+        assert!(!saved_dir.join("content_access").exists());
+        assert!(Path::new(&repaired[0].quarantined_path).exists());
+        assert!(saved_dir.join("app_info/appInfo.json").exists());
+
+        assert!(StoreIntegrity::check(saved_dir.to_str().unwrap()).is_empty());
+    }
+}
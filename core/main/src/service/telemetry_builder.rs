@@ -18,22 +18,30 @@
 use ripple_sdk::{
     api::{
         firebolt::{
+            fb_capabilities::DenyReasonWithCap,
+            fb_diagnostics::DiagnosticsLogParams,
             fb_metrics::{ErrorParams, InternalInitializeParams, SystemErrorParams},
             fb_telemetry::{
-                AppLoadStart, AppLoadStop, FireboltEvent, FireboltInteraction, InternalInitialize,
-                TelemetryAppError, TelemetryPayload, TelemetrySignIn, TelemetrySignOut,
+                AppDiagnostic, AppLoadStart, AppLoadStop, BootMilestoneType, ConnectionRejected,
+                CrashBreadcrumb, ErrorBudgetExceeded, FireboltEvent, FireboltInteraction,
+                InternalInitialize, RelaxedCapabilityCall, RepairedStore, RequestJournalEntry,
+                ServiceConnectionEvent, ServiceConnectionEventType, StoreRepair, TelemetryAppError,
+                TelemetryEvent, TelemetryPayload, TelemetrySignIn, TelemetrySignOut,
                 TelemetrySystemError,
             },
         },
-        gateway::rpc_gateway_api::{ApiMessage, CallContext, RpcRequest},
+        gateway::rpc_gateway_api::{ApiMessage, ApiProtocol, CallContext, RpcRequest},
     },
     chrono::{DateTime, Utc},
     framework::RippleResponse,
-    log::{error, trace},
+    log::{error, info, trace, warn},
 };
 use serde_json::Value;
 
-use crate::state::platform_state::PlatformState;
+use crate::{
+    service::data_governance::DataGovernance,
+    state::{error_budget_state::RpcOutcome, platform_state::PlatformState},
+};
 
 pub struct TelemetryBuilder;
 include!(concat!(env!("OUT_DIR"), "/version.rs"));
@@ -45,9 +53,9 @@ impl TelemetryBuilder {
         app_version: Option<String>,
         start_time: Option<DateTime<Utc>>,
     ) {
-        if let Err(e) = Self::send_telemetry(
+        if let Err(e) = Self::send_event(
             ps,
-            TelemetryPayload::AppLoadStart(AppLoadStart {
+            AppLoadStart {
                 app_id,
                 app_version,
                 start_time: start_time.unwrap_or_default().timestamp_millis(),
@@ -57,22 +65,22 @@ impl TelemetryBuilder {
                     .clone()
                     .unwrap_or(String::from(SEMVER_LIGHTWEIGHT)),
                 ripple_context: None,
-            }),
+            },
         ) {
             error!("send_telemetry={:?}", e)
         }
     }
 
     pub fn send_app_load_stop(ps: &PlatformState, app_id: String, success: bool) {
-        if let Err(e) = Self::send_telemetry(
+        if let Err(e) = Self::send_event(
             ps,
-            TelemetryPayload::AppLoadStop(AppLoadStop {
+            AppLoadStop {
                 app_id,
                 stop_time: Utc::now().timestamp_millis(),
                 app_session_id: None,
                 ripple_session_id: ps.metrics.get_device_session_id(),
                 success,
-            }),
+            },
         ) {
             error!("send_telemetry={:?}", e)
         }
@@ -87,8 +95,21 @@ impl TelemetryBuilder {
         Self::send_telemetry(ps, t)
     }
 
+    /// Sends a telemetry event from the schema registry
+    pub fn send_event<T: TelemetryEvent>(ps: &PlatformState, event: T) -> RippleResponse {
+        Self::send_telemetry(ps, event.into())
+    }
+
     pub fn send_telemetry(ps: &PlatformState, t: TelemetryPayload) -> RippleResponse {
-        trace!("send_telemetry: t={:?}", t);
+        trace!("send_telemetry: schema={:?} t={:?}", t.get_schema(), t);
+        if !DataGovernance::is_metrics_consented(
+            ps.get_data_governance_config(),
+            &ps.ripple_cache,
+            &t,
+        ) {
+            trace!("send_telemetry: no consent for {}", t.get_schema().name);
+            return Ok(());
+        }
 
         let listeners = ps.metrics.get_listeners();
         let client = ps.get_client().get_extn_client();
@@ -102,6 +123,152 @@ impl TelemetryBuilder {
         result
     }
 
+    pub fn send_boot_milestone(ps: &PlatformState, milestone: BootMilestoneType) {
+        let ripple_version = ps
+            .version
+            .clone()
+            .unwrap_or(String::from(SEMVER_LIGHTWEIGHT));
+        if let Some(boot_milestone) = ps.metrics.add_boot_milestone(milestone, ripple_version) {
+            info!(
+                "Boot milestone {:?} elapsed_ms={}",
+                milestone, boot_milestone.elapsed_ms
+            );
+            if let Err(e) = Self::send_event(ps, boot_milestone) {
+                error!("send_telemetry={:?}", e)
+            }
+        }
+    }
+
+    /// Sends the boot milestones reached before the listener subscribed
+    pub fn send_boot_milestones_to(ps: &PlatformState, listener: &str) {
+        let client = ps.get_client().get_extn_client();
+        for boot_milestone in ps.metrics.get_boot_milestones() {
+            let t: TelemetryPayload = boot_milestone.into();
+            if !DataGovernance::is_metrics_consented(
+                ps.get_data_governance_config(),
+                &ps.ripple_cache,
+                &t,
+            ) {
+                continue;
+            }
+            if let Err(e) = client.send_event_with_id(listener, t) {
+                error!("telemetry_send_error target={} e={:?}", listener, e);
+            }
+        }
+    }
+
+    /// Reports the persisted stores which were repaired on boot, the report is kept for
+    /// listeners which subscribe later
+    pub fn send_store_repair(ps: &PlatformState, repaired: Vec<RepairedStore>) {
+        if repaired.is_empty() {
+            return;
+        }
+        let store_repair = StoreRepair {
+            ripple_session_id: ps.metrics.get_device_session_id(),
+            ripple_version: ps
+                .version
+                .clone()
+                .unwrap_or(String::from(SEMVER_LIGHTWEIGHT)),
+            repaired,
+            timestamp: Utc::now().timestamp_millis(),
+        };
+        ps.metrics.set_store_repair(store_repair.clone());
+        if let Err(e) = Self::send_event(ps, store_repair) {
+            error!("send_telemetry={:?}", e)
+        }
+    }
+
+    pub fn send_crash_breadcrumb(
+        ps: &PlatformState,
+        previous_session_id: Option<String>,
+        recent_requests: Vec<RequestJournalEntry>,
+    ) {
+        if let Err(e) = Self::send_event(
+            ps,
+            CrashBreadcrumb {
+                ripple_session_id: ps.metrics.get_device_session_id(),
+                ripple_version: ps
+                    .version
+                    .clone()
+                    .unwrap_or(String::from(SEMVER_LIGHTWEIGHT)),
+                previous_session_id,
+                recent_requests,
+                timestamp: Utc::now().timestamp_millis(),
+            },
+        ) {
+            error!("send_telemetry={:?}", e)
+        }
+    }
+
+    pub fn send_connection_rejected(ps: &PlatformState, app_id: &str, reason: String) {
+        if let Err(e) = Self::send_event(
+            ps,
+            ConnectionRejected {
+                ripple_session_id: ps.metrics.get_device_session_id(),
+                ripple_version: ps
+                    .version
+                    .clone()
+                    .unwrap_or(String::from(SEMVER_LIGHTWEIGHT)),
+                app_id: app_id.to_owned(),
+                reason,
+                timestamp: Utc::now().timestamp_millis(),
+            },
+        ) {
+            error!("send_telemetry={:?}", e)
+        }
+    }
+
+    /// Records a lifecycle change of a service connection for fleet operators tracking the
+    /// churn of on-device services.
+    pub fn send_service_connection_event(
+        ps: &PlatformState,
+        service_id: &str,
+        connection_id: &str,
+        event: ServiceConnectionEventType,
+        reason: Option<String>,
+    ) {
+        ps.metrics.record_service_connection_event(event);
+        if let Err(e) = Self::send_event(
+            ps,
+            ServiceConnectionEvent {
+                ripple_session_id: ps.metrics.get_device_session_id(),
+                ripple_version: ps
+                    .version
+                    .clone()
+                    .unwrap_or(String::from(SEMVER_LIGHTWEIGHT)),
+                service_id: service_id.to_owned(),
+                connection_id: connection_id.to_owned(),
+                event,
+                reason,
+                timestamp: Utc::now().timestamp_millis(),
+            },
+        ) {
+            error!("send_telemetry={:?}", e)
+        }
+    }
+
+    /// Sends the store repair report from boot to a listener which subscribed after it
+    pub fn send_store_repair_to(ps: &PlatformState, listener: &str) {
+        let Some(store_repair) = ps.metrics.get_store_repair() else {
+            return;
+        };
+        let t: TelemetryPayload = store_repair.into();
+        if !DataGovernance::is_metrics_consented(
+            ps.get_data_governance_config(),
+            &ps.ripple_cache,
+            &t,
+        ) {
+            return;
+        }
+        if let Err(e) = ps
+            .get_client()
+            .get_extn_client()
+            .send_event_with_id(listener, t)
+        {
+            error!("telemetry_send_error target={} e={:?}", listener, e);
+        }
+    }
+
     pub fn send_ripple_telemetry(ps: &PlatformState) {
         Self::send_app_load_start(
             ps,
@@ -121,7 +288,95 @@ impl TelemetryBuilder {
         app_error.ripple_session_id = ps.metrics.get_device_session_id();
         app_error.app_id = app_id;
 
-        if let Err(e) = Self::send_telemetry(ps, TelemetryPayload::AppError(app_error)) {
+        if let Err(e) = Self::send_event(ps, app_error) {
+            error!("send_telemetry={:?}", e)
+        }
+    }
+
+    pub fn send_app_diagnostic(
+        ps: &PlatformState,
+        ctx: &CallContext,
+        params: DiagnosticsLogParams,
+    ) -> RippleResponse {
+        let app_diagnostic = AppDiagnostic {
+            app_id: ctx.app_id.to_owned(),
+            app_session_id: Some(ctx.session_id.to_owned()),
+            ripple_session_id: ps.metrics.get_device_session_id(),
+            ripple_version: ps
+                .version
+                .clone()
+                .unwrap_or(String::from(SEMVER_LIGHTWEIGHT)),
+            form_factor: ps.get_device_manifest().get_form_factor(),
+            timestamp: Utc::now().timestamp_millis(),
+            level: params.level,
+            message: params.message,
+            attributes: params.attributes,
+        };
+        let recent_records = ps
+            .get_device_manifest()
+            .get_diagnostics_configuration()
+            .recent_records;
+        ps.metrics
+            .add_app_diagnostic(app_diagnostic.clone(), recent_records);
+        Self::send_event(ps, app_diagnostic)
+    }
+
+    pub fn send_relaxed_capability_call(
+        ps: &PlatformState,
+        request: &RpcRequest,
+        deny: &DenyReasonWithCap,
+    ) {
+        let relaxed_call = RelaxedCapabilityCall {
+            app_id: request.ctx.app_id.to_owned(),
+            app_session_id: Some(request.ctx.session_id.to_owned()),
+            ripple_session_id: ps.metrics.get_device_session_id(),
+            ripple_version: ps
+                .version
+                .clone()
+                .unwrap_or(String::from(SEMVER_LIGHTWEIGHT)),
+            method: request.method.to_owned(),
+            reason: deny.reason.to_string(),
+            capabilities: deny.caps.iter().map(|x| x.as_str()).collect(),
+            timestamp: Utc::now().timestamp_millis(),
+        };
+        if let Err(e) = Self::send_event(ps, relaxed_call) {
+            error!("send_telemetry={:?}", e)
+        }
+    }
+
+    /// Counts the outcome of an app call against its error budget and alerts the operator
+    /// when the app gets throttled
+    pub fn record_app_outcome(ps: &PlatformState, app_id: &str, outcome: RpcOutcome) {
+        let throttle_ms = match ps.error_budget_state.record(app_id, outcome) {
+            Some(throttle_ms) => throttle_ms,
+            None => return,
+        };
+        let stats = ps
+            .error_budget_state
+            .get_stats()
+            .into_iter()
+            .find(|s| s.app_id.eq(app_id))
+            .unwrap_or_default();
+        warn!(
+            "app_id={} exceeded its error budget client_errors={} requests={}, throttling for {}ms",
+            app_id, stats.client_error_count, stats.request_count, throttle_ms
+        );
+        if let Err(e) = Self::send_event(
+            ps,
+            ErrorBudgetExceeded {
+                app_id: app_id.to_owned(),
+                ripple_session_id: ps.metrics.get_device_session_id(),
+                ripple_version: ps
+                    .version
+                    .clone()
+                    .unwrap_or(String::from(SEMVER_LIGHTWEIGHT)),
+                request_count: stats.request_count,
+                client_error_count: stats.client_error_count,
+                server_error_count: stats.server_error_count,
+                throttle_ms,
+                timestamp: Utc::now().timestamp_millis(),
+            },
+        ) {
             error!("send_telemetry={:?}", e)
         }
     }
@@ -130,32 +385,32 @@ impl TelemetryBuilder {
         let mut system_error: TelemetrySystemError = error_params.into();
         system_error.ripple_session_id = ps.metrics.get_device_session_id();
 
-        if let Err(e) = Self::send_telemetry(ps, TelemetryPayload::SystemError(system_error)) {
+        if let Err(e) = Self::send_event(ps, system_error) {
             error!("send_telemetry={:?}", e)
         }
     }
 
     pub fn send_sign_in(ps: &PlatformState, ctx: &CallContext) {
-        if let Err(e) = Self::send_telemetry(
+        if let Err(e) = Self::send_event(
             ps,
-            TelemetryPayload::SignIn(TelemetrySignIn {
+            TelemetrySignIn {
                 app_id: ctx.app_id.to_owned(),
                 ripple_session_id: ps.metrics.get_device_session_id(),
                 app_session_id: Some(ctx.session_id.to_owned()),
-            }),
+            },
         ) {
             error!("send_telemetry={:?}", e)
         }
     }
 
     pub fn send_sign_out(ps: &PlatformState, ctx: &CallContext) {
-        if let Err(e) = Self::send_telemetry(
+        if let Err(e) = Self::send_event(
             ps,
-            TelemetryPayload::SignOut(TelemetrySignOut {
+            TelemetrySignOut {
                 app_id: ctx.app_id.to_owned(),
                 ripple_session_id: ps.metrics.get_device_session_id(),
                 app_session_id: Some(ctx.session_id.to_owned()),
-            }),
+            },
         ) {
             error!("send_telemetry={:?}", e)
         }
@@ -166,14 +421,14 @@ impl TelemetryBuilder {
         ctx: &CallContext,
         params: &InternalInitializeParams,
     ) {
-        if let Err(e) = Self::send_telemetry(
+        if let Err(e) = Self::send_event(
             ps,
-            TelemetryPayload::InternalInitialize(InternalInitialize {
+            InternalInitialize {
                 app_id: ctx.app_id.to_owned(),
                 ripple_session_id: ps.metrics.get_device_session_id(),
                 app_session_id: Some(ctx.session_id.to_owned()),
                 semantic_version: params.version.to_string(),
-            }),
+            },
         ) {
             error!("send_telemetry={:?}", e)
         }
@@ -186,6 +441,18 @@ impl TelemetryBuilder {
         success: bool,
         resp: &ApiMessage,
     ) {
+        ps.metrics.record_rpc_result(success);
+        let error_code = resp.get_error_code_from_msg().ok().flatten();
+        ps.request_journal_state
+            .record(&req.ctx.app_id, &req.method, error_code.unwrap_or(0));
+        if matches!(req.ctx.protocol, ApiProtocol::JsonRpc) {
+            let outcome = match error_code {
+                Some(code) if !success => RpcOutcome::from_error_code(code),
+                _ if !success => RpcOutcome::ServerError,
+                _ => RpcOutcome::Success,
+            };
+            Self::record_app_outcome(ps, &req.ctx.app_id, outcome);
+        }
         let ctx = req.ctx;
         let method = req.method;
         let params = if let Ok(mut p) = serde_json::from_str::<Vec<Value>>(&req.params_json) {
@@ -200,9 +467,9 @@ impl TelemetryBuilder {
             None
         };
         let response = serde_json::to_string(resp).unwrap_or_default();
-        if let Err(e) = Self::send_telemetry(
+        if let Err(e) = Self::send_event(
             ps,
-            TelemetryPayload::FireboltInteraction(FireboltInteraction {
+            FireboltInteraction {
                 app_id: ctx.app_id.to_owned(),
                 ripple_session_id: ps.metrics.get_device_session_id(),
                 app_session_id: Some(ctx.session_id),
@@ -211,19 +478,19 @@ impl TelemetryBuilder {
                 params,
                 success,
                 response,
-            }),
+            },
         ) {
             error!("send_telemetry={:?}", e)
         }
     }
 
     pub fn send_fb_event(ps: &PlatformState, event: &str, result: Value) {
-        if let Err(e) = Self::send_telemetry(
+        if let Err(e) = Self::send_event(
             ps,
-            TelemetryPayload::FireboltEvent(FireboltEvent {
+            FireboltEvent {
                 event_name: event.into(),
                 result,
-            }),
+            },
         ) {
             error!("send_fb_event: e={:?}", e)
         }
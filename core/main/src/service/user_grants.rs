@@ -686,6 +686,23 @@ impl GrantState {
         }
     }
 
+    // Returns the allowed user grant entries of every app
+    pub fn get_allowed_app_entries(&self) -> Vec<(String, GrantEntry)> {
+        self.delete_all_expired_entries();
+        self.grant_app_map
+            .read()
+            .unwrap()
+            .value
+            .iter()
+            .flat_map(|(app_id, entries)| {
+                entries
+                    .iter()
+                    .filter(|entry| matches!(entry.status, Some(GrantStatus::Allowed)))
+                    .map(move |entry| (app_id.clone(), entry.clone()))
+            })
+            .collect()
+    }
+
     // Returns all active and denied user grant entries for the given `app_id`.
     // Pass None for device scope
     pub fn get_device_entries(&self) -> HashSet<GrantEntry> {
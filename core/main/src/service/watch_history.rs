@@ -0,0 +1,327 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashSet,
+    path::Path,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use hyper::{header::CONTENT_TYPE, Body, Method, Request};
+use ripple_sdk::{
+    api::{
+        firebolt::fb_discovery::{WatchNextInfo, WatchedInfo},
+        manifest::device_manifest::{DeviceManifest, WatchHistoryUploadConfiguration},
+    },
+    framework::file_store::FileStore,
+    log::{debug, error, warn},
+    tokio::{
+        self,
+        sync::{Mutex, Notify},
+    },
+    utils::error::RippleError,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::utils::http_utils::{get_http_client, HttpClient};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum WatchHistoryItem {
+    #[serde(rename_all = "camelCase")]
+    Watched { app_id: String, info: WatchedInfo },
+    #[serde(rename_all = "camelCase")]
+    WatchNext {
+        app_id: String,
+        info: Box<WatchNextInfo>,
+    },
+}
+
+impl WatchHistoryItem {
+    pub fn get_app_id(&self) -> &str {
+        match self {
+            WatchHistoryItem::Watched { app_id, .. }
+            | WatchHistoryItem::WatchNext { app_id, .. } => app_id,
+        }
+    }
+
+    /// Rapid updates for the same content from the same app replace each other
+    fn get_key(&self) -> String {
+        match self {
+            WatchHistoryItem::Watched { app_id, info } => {
+                format!("watched:{}:{}", app_id, info.entity_id)
+            }
+            WatchHistoryItem::WatchNext { app_id, info } => format!(
+                "watchNext:{}:{}",
+                app_id,
+                info.identifiers.entity_id.clone().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueuedWatchHistoryItem {
+    seq: u64,
+    #[serde(flatten)]
+    item: WatchHistoryItem,
+    /// Data governance tags of the privacy settings enforced when the item was written
+    #[serde(default)]
+    tags: HashSet<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct WatchHistoryQueue {
+    next_seq: u64,
+    items: Vec<QueuedWatchHistoryItem>,
+}
+
+/// Shortest interval between two uploads, so a zero interval does not busy loop against
+/// the endpoint
+const MIN_FLUSH_INTERVAL_MS: u64 = 1000;
+
+/// Queues watch history and watch next writes from apps and pushes them upstream in
+/// batches. The queue is persisted so unsent items survive a reboot, and is bounded so it
+/// does not grow while the endpoint is unreachable.
+#[derive(Debug, Clone)]
+pub struct WatchHistoryState {
+    config: Arc<WatchHistoryUploadConfiguration>,
+    queue: Arc<RwLock<FileStore<WatchHistoryQueue>>>,
+    flush: Arc<Notify>,
+    /// Held while batches are uploaded, so a flush on shutdown and the upload loop do not
+    /// send the same items twice
+    uploading: Arc<Mutex<()>>,
+    client: HttpClient,
+}
+
+impl WatchHistoryState {
+    pub fn new(manifest: &DeviceManifest) -> WatchHistoryState {
+        let path = Path::new(&manifest.configuration.saved_dir)
+            .join("watch_history_queue")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let store = FileStore::load(path.clone())
+            .unwrap_or_else(|_| FileStore::new(path, WatchHistoryQueue::default()));
+        WatchHistoryState {
+            config: Arc::new(manifest.get_watch_history_upload_configuration()),
+            queue: Arc::new(RwLock::new(store)),
+            flush: Arc::new(Notify::new()),
+            uploading: Arc::new(Mutex::new(())),
+            client: get_http_client(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn get_batch_size(&self) -> usize {
+        self.config.batch_size.max(1)
+    }
+
+    fn get_max_queued_items(&self) -> usize {
+        self.config.max_queued_items.max(self.get_batch_size())
+    }
+
+    pub fn add(&self, item: WatchHistoryItem, tags: HashSet<String>) {
+        let key = item.get_key();
+        let max_queued_items = self.get_max_queued_items();
+        let (pending, dropped, snapshot) = {
+            let mut store = self.queue.write().unwrap();
+            let queue = &mut store.value;
+            queue.items.retain(|i| i.item.get_key() != key);
+            queue.items.push(QueuedWatchHistoryItem {
+                seq: queue.next_seq,
+                item,
+                tags,
+            });
+            queue.next_seq += 1;
+            let dropped = queue.items.len().saturating_sub(max_queued_items);
+            queue.items.drain(..dropped);
+            let pending = queue.items.len();
+            (pending, dropped, store.snapshot())
+        };
+        snapshot.persist();
+        if dropped > 0 {
+            warn!(
+                "Watch history queue is full, dropped {} oldest items",
+                dropped
+            );
+        }
+        if pending >= self.get_batch_size() {
+            self.flush.notify_one();
+        }
+    }
+
+    fn get_batch(&self) -> Vec<QueuedWatchHistoryItem> {
+        let store = self.queue.read().unwrap();
+        store
+            .value
+            .items
+            .iter()
+            .take(self.get_batch_size())
+            .cloned()
+            .collect()
+    }
+
+    fn remove_sent(&self, sent: &[QueuedWatchHistoryItem]) {
+        let sent: HashSet<u64> = sent.iter().map(|s| s.seq).collect();
+        let snapshot = {
+            let mut store = self.queue.write().unwrap();
+            store.value.items.retain(|i| !sent.contains(&i.seq));
+            store.snapshot()
+        };
+        snapshot.persist();
+    }
+
+    /// Uploads the queued items without waiting for the flush interval and returns once
+    /// the queue is empty or an upload failed
+    pub async fn flush(&self) {
+        if self.is_enabled() {
+            self.upload_queued().await;
+        }
+    }
+
+    pub fn start(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+        let state = self.clone();
+        tokio::spawn(async move {
+            let interval =
+                Duration::from_millis(state.config.flush_interval_ms.max(MIN_FLUSH_INTERVAL_MS));
+            loop {
+                tokio::select! {
+                    _ = state.flush.notified() => {}
+                    _ = tokio::time::sleep(interval) => {}
+                }
+                state.upload_queued().await;
+            }
+        });
+    }
+
+    async fn upload_queued(&self) {
+        let _uploading = self.uploading.lock().await;
+        loop {
+            let batch = self.get_batch();
+            if batch.is_empty() {
+                break;
+            }
+            if let Err(e) = self.upload(&batch).await {
+                // Items stay queued and are retried on the next flush
+                error!("Watch history upload failed: {:?}", e);
+                break;
+            }
+            debug!("Uploaded {} watch history items", batch.len());
+            self.remove_sent(&batch);
+        }
+    }
+
+    async fn upload(&self, batch: &[QueuedWatchHistoryItem]) -> Result<(), RippleError> {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.config.url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "items": batch }).to_string()))
+            .map_err(|_| RippleError::InvalidInput)?;
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(|e| RippleError::BrokerError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(RippleError::BrokerError(response.status().to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_watched(app_id: &str, entity_id: &str, progress: f32) -> WatchHistoryItem {
+        WatchHistoryItem::Watched {
+            app_id: app_id.into(),
+            info: WatchedInfo {
+                entity_id: entity_id.into(),
+                progress,
+                completed: None,
+                watched_on: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_watch_history_dedup() {
+        let saved_dir = std::env::temp_dir().join("ripple_watch_history_test");
+        let _ = std::fs::remove_dir_all(&saved_dir);
+        let mut manifest = DeviceManifest::default();
+        manifest.configuration.saved_dir = saved_dir.into_os_string().into_string().unwrap();
+        let state = WatchHistoryState::new(&manifest);
+        state.add(get_watched("app1", "entity1", 0.1), HashSet::new());
+        state.add(get_watched("app2", "entity1", 0.2), HashSet::new());
+        state.add(
+            get_watched("app1", "entity1", 0.3),
+            HashSet::from(["dataPlatform:watchHistory".to_owned()]),
+        );
+
+        let batch = state.get_batch();
+        assert_eq!(batch.len(), 2);
+        assert!(matches!(
+            &batch[1].item,
+            WatchHistoryItem::Watched { app_id, info } if app_id == "app1" && info.progress == 0.3
+        ));
+        assert_eq!(batch[1].tags.len(), 1);
+
+        state.remove_sent(&batch[..1]);
+        assert_eq!(state.get_batch().len(), 1);
+        // unsent items are reloaded from disk
+        assert_eq!(WatchHistoryState::new(&manifest).get_batch().len(), 1);
+    }
+
+    #[test]
+    fn test_watch_history_bounded() {
+        let saved_dir = std::env::temp_dir().join("ripple_watch_history_bounded_test");
+        let _ = std::fs::remove_dir_all(&saved_dir);
+        let mut manifest = DeviceManifest::default();
+        manifest.configuration.saved_dir = saved_dir.into_os_string().into_string().unwrap();
+        manifest.configuration.watch_history_upload.batch_size = 0;
+        manifest.configuration.watch_history_upload.max_queued_items = 3;
+        let state = WatchHistoryState::new(&manifest);
+        for i in 0..5 {
+            state.add(
+                get_watched("app1", &format!("entity{}", i), 0.1),
+                HashSet::new(),
+            );
+        }
+
+        // a zero batch size still drains the queue one item at a time
+        let batch = state.get_batch();
+        assert_eq!(batch.len(), 1);
+        // the oldest items were dropped
+        assert!(matches!(
+            &batch[0].item,
+            WatchHistoryItem::Watched { info, .. } if info.entity_id == "entity2"
+        ));
+        state.remove_sent(&batch);
+        assert_eq!(WatchHistoryState::new(&manifest).get_batch().len(), 1);
+    }
+}
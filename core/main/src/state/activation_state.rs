@@ -0,0 +1,51 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::{Arc, Mutex, RwLock};
+
+use ripple_sdk::{
+    api::distributor::distributor_activation::ActivationProgress, tokio::task::JoinHandle,
+};
+
+/// Last progress reported by the activation orchestrator
+#[derive(Debug, Clone, Default)]
+pub struct ActivationState {
+    progress: Arc<RwLock<ActivationProgress>>,
+    task: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl ActivationState {
+    pub fn get_progress(&self) -> ActivationProgress {
+        self.progress.read().unwrap().clone()
+    }
+
+    pub fn set_progress(&self, progress: ActivationProgress) {
+        *self.progress.write().unwrap() = progress;
+    }
+
+    pub fn set_task(&self, handle: JoinHandle<()>) {
+        if let Some(previous) = self.task.lock().unwrap().replace(handle) {
+            previous.abort();
+        }
+    }
+
+    pub fn abort_task(&self) {
+        if let Some(handle) = self.task.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+}
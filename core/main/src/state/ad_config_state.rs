@@ -0,0 +1,135 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use ripple_sdk::{
+    api::{
+        firebolt::fb_advertising::{AdConfigRequestParams, AdConfigResponse},
+        manifest::device_manifest::AdConfigConfiguration,
+    },
+    log::debug,
+    utils::{error::RippleError, expiring_map::ExpiringMap},
+};
+
+use super::platform_state::PlatformState;
+
+/// Cache of the ad framework configuration served by the distributor through the
+/// advertising contract. Entries are keyed by the whole request, so a change of account,
+/// environment or privacy settings is fetched again instead of served from the cache.
+#[derive(Debug, Clone, Default)]
+pub struct AdConfigState {
+    cache: Arc<RwLock<ExpiringMap<String, AdConfigResponse>>>,
+}
+
+impl AdConfigState {
+    pub fn new(config: AdConfigConfiguration) -> Self {
+        Self {
+            cache: Arc::new(RwLock::new(ExpiringMap::new(
+                Duration::from_secs(config.cache_ttl_seconds),
+                config.cache_size,
+            ))),
+        }
+    }
+
+    fn get_key(params: &AdConfigRequestParams) -> String {
+        let privacy_data: BTreeMap<_, _> = params.privacy_data.iter().collect();
+        let scope: BTreeMap<_, _> = params.scope.iter().collect();
+        format!(
+            "{}:{}:{}:{:?}:{:?}",
+            params.dist_session.account_id,
+            params.durable_app_id,
+            params.environment,
+            privacy_data,
+            scope
+        )
+    }
+
+    pub fn get(&self, params: &AdConfigRequestParams) -> Option<AdConfigResponse> {
+        self.cache
+            .read()
+            .unwrap()
+            .get(&Self::get_key(params))
+            .cloned()
+    }
+
+    pub fn set(&self, params: &AdConfigRequestParams, response: AdConfigResponse) {
+        self.cache
+            .write()
+            .unwrap()
+            .insert(Self::get_key(params), response);
+    }
+
+    /// Returns the ad config from the cache or from the distributor contract, which can be
+    /// fulfilled by an extension or a service.
+    pub async fn get_ad_config(
+        state: &PlatformState,
+        params: AdConfigRequestParams,
+    ) -> Result<AdConfigResponse, RippleError> {
+        if let Some(response) = state.ad_config_state.get(&params) {
+            debug!("Ad config served from cache for {}", params.durable_app_id);
+            return Ok(response);
+        }
+        let response = state
+            .get_client()
+            .send_extn_request(params.clone())
+            .await?
+            .payload
+            .extract::<AdConfigResponse>()
+            .ok_or(RippleError::InvalidOutput)?;
+        state.ad_config_state.set(&params, response.clone());
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::session::AccountSession;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_ad_config_cache() {
+        let state = AdConfigState::new(AdConfigConfiguration::default());
+        let mut params = AdConfigRequestParams {
+            privacy_data: HashMap::from([("lmt".to_owned(), "0".to_owned())]),
+            durable_app_id: "app1".to_owned(),
+            dist_session: AccountSession {
+                id: "session".to_owned(),
+                token: "token".to_owned(),
+                account_id: "account1".to_owned(),
+                device_id: "device".to_owned(),
+            },
+            environment: "prod".to_owned(),
+            scope: HashMap::new(),
+        };
+        let response = AdConfigResponse {
+            ad_network_id: "network".to_owned(),
+            ..Default::default()
+        };
+        state.set(&params, response.clone());
+        assert_eq!(state.get(&params), Some(response));
+
+        // a change of the privacy settings misses the cache
+        params.privacy_data.insert("lmt".to_owned(), "1".to_owned());
+        assert!(state.get(&params).is_none());
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use ripple_sdk::api::{
+    firebolt::fb_capabilities::{DenyReason, DenyReasonWithCap, FireboltCap},
+    gateway::rpc_gateway_api::CallContext,
+    manifest::device_manifest::{AdminConfiguration, AdminRole},
+};
+use ripple_sdk::serde_json::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    service::admin_schema::{self, ContentDescriptor, ADMIN_OPENRPC_VERSION},
+    utils::common::constant_time_eq,
+};
+
+/// Admin methods along with the minimum role needed to call them. The device manifest
+/// can override the role for any of these methods.
+pub const ADMIN_METHOD_ROLES: &[(&str, AdminRole)] = &[
+    ("ripple.getAdminRole", AdminRole::ReadOnly),
+    ("ripple.uploadDiagnostics", AdminRole::Operator),
+    ("ripple.setContextOverride", AdminRole::Developer),
+    ("ripple.clearContextOverride", AdminRole::Developer),
+    ("ripple.getCapabilityUsage", AdminRole::ReadOnly),
+    ("ripple.getExtnUsage", AdminRole::ReadOnly),
+    ("ripple.getComplianceReport", AdminRole::ReadOnly),
+    ("ripple.setRuleExplain", AdminRole::Developer),
+    ("ripple.shutdown", AdminRole::Operator),
+    ("ripple.getJqSandbox", AdminRole::ReadOnly),
+    ("ripple.getRuleProvenance", AdminRole::ReadOnly),
+    ("ripple.setJqSandbox", AdminRole::Operator),
+    ("ripple.releaseJqRule", AdminRole::Operator),
+    ("ripple.injectEvent", AdminRole::Developer),
+    ("ripple.setSyntheticProvider", AdminRole::Developer),
+    ("ripple.dumpState", AdminRole::Operator),
+    ("ripple.loadState", AdminRole::Developer),
+    ("ripple.getErrorBudgets", AdminRole::ReadOnly),
+    ("ripple.getTenantStats", AdminRole::ReadOnly),
+    ("ripple.getRegion", AdminRole::ReadOnly),
+    ("ripple.setRegionSources", AdminRole::Operator),
+    ("ripple.getAdminSchema", AdminRole::ReadOnly),
+    ("ripple.info", AdminRole::Operator),
+    ("ripple.softRestart", AdminRole::Operator),
+    ("ripple.getRoutingTable", AdminRole::ReadOnly),
+    ("ripple.resetActivation", AdminRole::Operator),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminSchemaInfo {
+    pub title: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminMethodSchema {
+    pub name: String,
+    pub params: Vec<ContentDescriptor>,
+    pub result: ContentDescriptor,
+    #[serde(rename = "x-role")]
+    pub role: AdminRole,
+    /// Whether the role of the calling connection is enough for the method
+    #[serde(rename = "x-allowed")]
+    pub allowed: bool,
+}
+
+/// Schema of the admin methods laid out like an OpenRPC document, so tooling does not need to
+/// know them per firmware version. It lists the admin methods registered with the router, their
+/// params and results come from [admin_schema::describe_method]. The role needed for a method
+/// is in the `x-role` extension.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminSchema {
+    pub openrpc: String,
+    pub info: AdminSchemaInfo,
+    pub methods: Vec<AdminMethodSchema>,
+    pub components: Value,
+}
+
+/// Admin state holds the role based access for the admin API.
+///
+/// Connections present an admin token during the websocket handshake in an
+/// `Authorization: Bearer <token>` header. The role of a valid token is bound to the
+/// connection and checked for every admin method.
+#[derive(Debug, Clone, Default)]
+pub struct AdminState {
+    config: Arc<AdminConfiguration>,
+    connection_roles: Arc<RwLock<HashMap<String, AdminRole>>>,
+}
+
+impl AdminState {
+    pub fn new(config: AdminConfiguration) -> Self {
+        Self {
+            config: Arc::new(config),
+            connection_roles: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn get_event_schema_path(&self) -> Option<String> {
+        self.config.event_schema_path.clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn resolve_token(&self, token: &str) -> Option<AdminRole> {
+        if !self.is_enabled() {
+            return None;
+        }
+        self.config
+            .tokens
+            .iter()
+            .find(|t| constant_time_eq(&t.token, token))
+            .map(|t| t.role)
+    }
+
+    pub fn add_connection(&self, connection_id: String, role: AdminRole) {
+        self.connection_roles
+            .write()
+            .unwrap()
+            .insert(connection_id, role);
+    }
+
+    pub fn remove_connection(&self, connection_id: &str) {
+        self.connection_roles.write().unwrap().remove(connection_id);
+    }
+
+    pub fn get_role(&self, ctx: &CallContext) -> Option<AdminRole> {
+        let cid = ctx.cid.as_ref()?;
+        self.connection_roles.read().unwrap().get(cid).cloned()
+    }
+
+    pub fn is_admin_method(&self, method: &str) -> bool {
+        self.get_required_role(method).is_some()
+    }
+
+    pub fn get_required_role(&self, method: &str) -> Option<AdminRole> {
+        let default_role = ADMIN_METHOD_ROLES
+            .iter()
+            .find(|(m, _)| m.eq(&method))
+            .map(|(_, role)| *role)?;
+        Some(
+            self.config
+                .method_roles
+                .get(method)
+                .cloned()
+                .unwrap_or(default_role),
+        )
+    }
+
+    /// Checks the connection role against the role required by the admin method. The admin
+    /// surface is reported as not found when it is disabled in the device manifest.
+    pub fn check(&self, ctx: &CallContext, method: &str) -> Result<(), DenyReasonWithCap> {
+        let required_role = match self.get_required_role(method) {
+            Some(role) if self.is_enabled() => role,
+            _ => {
+                return Err(DenyReasonWithCap {
+                    reason: DenyReason::NotFound,
+                    caps: Vec::new(),
+                })
+            }
+        };
+        match self.get_role(ctx) {
+            Some(role) if role >= required_role => Ok(()),
+            _ => Err(DenyReasonWithCap {
+                reason: DenyReason::Unpermitted,
+                caps: vec![FireboltCap::short(format!(
+                    "ripple:admin:{:?}",
+                    required_role
+                ))],
+            }),
+        }
+    }
+
+    /// Describes the registered admin methods. Registered methods without an entry in
+    /// [ADMIN_METHOD_ROLES] are left out as they cannot be called.
+    pub fn get_schema(
+        &self,
+        ctx: &CallContext,
+        version: String,
+        registered_methods: Vec<String>,
+    ) -> AdminSchema {
+        let role = self.get_role(ctx);
+        let mut methods: Vec<AdminMethodSchema> = registered_methods
+            .into_iter()
+            .filter_map(|name| {
+                let required_role = self.get_required_role(&name)?;
+                let (params, result) = admin_schema::describe_method(&name)?;
+                Some(AdminMethodSchema {
+                    params,
+                    result,
+                    allowed: role.is_some_and(|r| r >= required_role),
+                    role: required_role,
+                    name,
+                })
+            })
+            .collect();
+        methods.sort_by(|a, b| a.name.cmp(&b.name));
+        AdminSchema {
+            openrpc: ADMIN_OPENRPC_VERSION.to_owned(),
+            info: AdminSchemaInfo {
+                title: "Ripple Admin API".to_owned(),
+                version,
+            },
+            methods,
+            components: admin_schema::get_components(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{api::manifest::device_manifest::AdminToken, serde_json::json, Mockable};
+
+    fn get_admin_state() -> AdminState {
+        AdminState::new(AdminConfiguration {
+            enabled: true,
+            tokens: vec![
+                AdminToken {
+                    token: "viewer".into(),
+                    role: AdminRole::ReadOnly,
+                },
+                AdminToken {
+                    token: "dev".into(),
+                    role: AdminRole::Developer,
+                },
+            ],
+            method_roles: HashMap::from([("ripple.getAdminRole".to_string(), AdminRole::Operator)]),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_admin_role_check() {
+        let state = get_admin_state();
+        let mut ctx = CallContext::mock();
+        ctx.cid = Some("conn1".into());
+
+        assert!(state.resolve_token("unknown").is_none());
+        assert_eq!(
+            state.get_required_role("ripple.getAdminRole"),
+            Some(AdminRole::Operator)
+        );
+        assert!(!state.is_admin_method("device.name"));
+
+        // no token presented
+        assert!(state.check(&ctx, "ripple.getAdminRole").is_err());
+
+        state.add_connection("conn1".into(), state.resolve_token("viewer").unwrap());
+        let err = state.check(&ctx, "ripple.getAdminRole").unwrap_err();
+        assert_eq!(err.reason, DenyReason::Unpermitted);
+
+        state.add_connection("conn1".into(), state.resolve_token("dev").unwrap());
+        assert!(state.check(&ctx, "ripple.getAdminRole").is_ok());
+
+        state.remove_connection("conn1");
+        assert!(state.check(&ctx, "ripple.getAdminRole").is_err());
+    }
+
+    #[test]
+    fn test_admin_disabled() {
+        let state = AdminState::default();
+        let mut ctx = CallContext::mock();
+        ctx.cid = Some("conn1".into());
+        state.add_connection("conn1".into(), AdminRole::Developer);
+        let err = state.check(&ctx, "ripple.getAdminRole").unwrap_err();
+        assert_eq!(err.reason, DenyReason::NotFound);
+    }
+
+    #[test]
+    fn test_admin_schema() {
+        let state = get_admin_state();
+        let mut ctx = CallContext::mock();
+        ctx.cid = Some("conn1".into());
+        state.add_connection("conn1".into(), AdminRole::ReadOnly);
+        let schema = state.get_schema(
+            &ctx,
+            "1.0.0".into(),
+            vec![
+                "ripple.shutdown".into(),
+                "device.name".into(),
+                "ripple.getAdminRole".into(),
+            ],
+        );
+        assert_eq!(schema.openrpc, ADMIN_OPENRPC_VERSION);
+        assert_eq!(schema.info.version, "1.0.0");
+        let names: Vec<(&str, AdminRole, bool)> = schema
+            .methods
+            .iter()
+            .map(|m| (m.name.as_str(), m.role, m.allowed))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("ripple.getAdminRole", AdminRole::Operator, false),
+                ("ripple.shutdown", AdminRole::Operator, false),
+            ]
+        );
+        let shutdown = &schema.methods[1];
+        assert_eq!(shutdown.params[0].name, "reason");
+        assert!(!shutdown.params[0].required);
+        let value = serde_json::to_value(&schema).unwrap();
+        assert_eq!(value["methods"][0]["x-role"], json!("operator"));
+        assert_eq!(
+            value["methods"][0]["result"]["schema"]["$ref"],
+            json!("#/components/schemas/AdminRole")
+        );
+        assert!(value["components"]["schemas"]["AdminRole"].is_object());
+    }
+}
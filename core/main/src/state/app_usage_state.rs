@@ -0,0 +1,219 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use ripple_sdk::{
+    api::{
+        firebolt::{fb_lifecycle::LifecycleState, fb_telemetry::AppUsage},
+        manifest::device_manifest::AppUsageConfiguration,
+    },
+    chrono::Utc,
+    framework::file_store::FileStore,
+    log::debug,
+};
+
+/// Per app lifecycle statistics for the launcher UI and the device health telemetry.
+///
+/// Launch counts, foreground time and exit reasons are persisted in the saved dir. Only the
+/// most recently used apps are kept so the store stays bounded on devices with large app
+/// catalogs. The store is always locked before the foreground timers.
+#[derive(Debug, Clone)]
+pub struct AppUsageState {
+    config: Arc<AppUsageConfiguration>,
+    store: Arc<RwLock<FileStore<HashMap<String, AppUsage>>>>,
+    foreground_since: Arc<RwLock<HashMap<String, i64>>>,
+}
+
+impl AppUsageState {
+    pub fn new(config: AppUsageConfiguration, saved_dir: &str) -> Self {
+        let path = Path::new(saved_dir)
+            .join("app_usage")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let store =
+            FileStore::load(path.clone()).unwrap_or_else(|_| FileStore::new(path, HashMap::new()));
+        Self {
+            config: Arc::new(config),
+            store: Arc::new(RwLock::new(store)),
+            foreground_since: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn update<F>(&self, app_id: &str, f: F)
+    where
+        F: FnOnce(&mut AppUsage),
+    {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut store = self.store.write().unwrap();
+        let usage = store
+            .value
+            .entry(app_id.to_owned())
+            .or_insert_with(|| AppUsage {
+                app_id: app_id.to_owned(),
+                ..Default::default()
+            });
+        f(usage);
+        usage.last_used = usage.last_used.max(Utc::now().timestamp_millis());
+
+        // evict the least recently used apps beyond the configured bound
+        while store.value.len() > self.config.max_apps {
+            let oldest = store
+                .value
+                .values()
+                .min_by_key(|u| u.last_used)
+                .map(|u| u.app_id.clone());
+            match oldest {
+                Some(oldest) => {
+                    debug!("Evicting app usage for {}", oldest);
+                    store.value.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        let snapshot = store.snapshot();
+        drop(store);
+        snapshot.persist();
+    }
+
+    fn end_foreground(&self, app_id: &str, usage: &mut AppUsage) {
+        if let Some(since) = self.foreground_since.write().unwrap().remove(app_id) {
+            let elapsed = Utc::now().timestamp_millis() - since;
+            usage.foreground_ms += elapsed.max(0) as u64;
+        }
+    }
+
+    pub fn on_launch(&self, app_id: &str) {
+        self.update(app_id, |usage| usage.launch_count += 1);
+    }
+
+    pub fn on_state_change(&self, app_id: &str, previous: LifecycleState, state: LifecycleState) {
+        if previous != LifecycleState::Foreground && state != LifecycleState::Foreground {
+            return;
+        }
+        self.update(app_id, |usage| {
+            if previous == LifecycleState::Foreground {
+                self.end_foreground(app_id, usage);
+            }
+            if state == LifecycleState::Foreground {
+                self.foreground_since
+                    .write()
+                    .unwrap()
+                    .insert(app_id.to_owned(), Utc::now().timestamp_millis());
+            }
+        });
+    }
+
+    pub fn on_exit(&self, app_id: &str, reason: &str) {
+        self.update(app_id, |usage| {
+            self.end_foreground(app_id, usage);
+            *usage.exit_reasons.entry(reason.to_owned()).or_default() += 1;
+        });
+    }
+
+    /// Stops the foreground timer of an app whose session ended without a close request
+    pub fn on_session_end(&self, app_id: &str) {
+        if !self.foreground_since.read().unwrap().contains_key(app_id) {
+            return;
+        }
+        self.update(app_id, |usage| self.end_foreground(app_id, usage));
+    }
+
+    /// Returns the statistics of an app or of all the tracked apps, most recently used first.
+    /// Time spent in the current foreground stint is included.
+    pub fn get_usage(&self, app_id: Option<&str>) -> Vec<AppUsage> {
+        let now = Utc::now().timestamp_millis();
+        let store = self.store.read().unwrap();
+        let foreground_since = self.foreground_since.read().unwrap();
+        let mut usage: Vec<AppUsage> = store
+            .value
+            .values()
+            .filter(|u| app_id.is_none_or(|id| u.app_id.eq(id)))
+            .cloned()
+            .map(|mut u| {
+                if let Some(since) = foreground_since.get(&u.app_id) {
+                    u.foreground_ms += (now - since).max(0) as u64;
+                }
+                u
+            })
+            .collect();
+        usage.sort_by_key(|u| Reverse(u.last_used));
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_state(name: &str, max_apps: usize) -> AppUsageState {
+        let saved_dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&saved_dir);
+        AppUsageState::new(
+            AppUsageConfiguration {
+                enabled: true,
+                max_apps,
+            },
+            saved_dir.to_str().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_app_usage() {
+        let state = get_state("ripple_app_usage_test", 10);
+        state.on_launch("app1");
+        state.on_state_change("app1", LifecycleState::Inactive, LifecycleState::Foreground);
+        state.on_exit("app1", "userExit");
+        state.on_launch("app1");
+        state.on_exit("app1", "error");
+
+        let usage = state.get_usage(Some("app1"));
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].launch_count, 2);
+        assert_eq!(usage[0].exit_reasons.get("userExit"), Some(&1));
+        assert_eq!(usage[0].exit_reasons.get("error"), Some(&1));
+        assert!(state.foreground_since.read().unwrap().is_empty());
+        assert!(state.get_usage(Some("app2")).is_empty());
+    }
+
+    #[test]
+    fn test_app_usage_bounded() {
+        let state = get_state("ripple_app_usage_bounded_test", 2);
+        state.on_launch("app1");
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        state.on_launch("app2");
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        state.on_launch("app3");
+
+        let usage = state.get_usage(None);
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].app_id, "app3");
+        assert!(state.get_usage(Some("app1")).is_empty());
+    }
+}
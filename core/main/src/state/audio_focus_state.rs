@@ -0,0 +1,169 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::{Arc, RwLock};
+
+use ripple_sdk::api::firebolt::fb_audio_focus::{
+    AudioFocusChange, AudioFocusStatus, AudioFocusType,
+};
+
+#[derive(Debug, Clone)]
+struct FocusHolder {
+    app_id: String,
+    focus_type: AudioFocusType,
+    priority: bool,
+}
+
+/// Focus changes which need to be sent to the apps
+pub type FocusChanges = Vec<(String, AudioFocusChange)>;
+
+/// Arbitrates the audio focus between apps. The holders are kept as a stack, the top of
+/// the stack owns the focus and the holders below it were interrupted by a transient
+/// request and get the focus back when it is abandoned.
+#[derive(Debug, Clone, Default)]
+pub struct AudioFocusState {
+    holders: Arc<RwLock<Vec<FocusHolder>>>,
+}
+
+impl AudioFocusState {
+    /// Returns `None` when the focus is held by a priority app which can not be preempted
+    pub fn request(
+        &self,
+        app_id: &str,
+        focus_type: AudioFocusType,
+        priority: bool,
+    ) -> Option<FocusChanges> {
+        let mut holders = self.holders.write().unwrap();
+        if let Some(top) = holders.last() {
+            if top.priority && !priority && top.app_id.ne(app_id) {
+                return None;
+            }
+        }
+        holders.retain(|h| h.app_id.ne(app_id));
+        let mut changes = Vec::new();
+        if focus_type.is_transient() {
+            if let Some(top) = holders.last() {
+                let status = if focus_type == AudioFocusType::TransientMayDuck {
+                    AudioFocusStatus::Ducked
+                } else {
+                    AudioFocusStatus::LostTransient
+                };
+                changes.push((top.app_id.clone(), Self::change(status, None)));
+            }
+        } else {
+            for h in holders.drain(..) {
+                changes.push((h.app_id, Self::change(AudioFocusStatus::Lost, None)));
+            }
+        }
+        holders.push(FocusHolder {
+            app_id: app_id.to_owned(),
+            focus_type,
+            priority,
+        });
+        changes.push((
+            app_id.to_owned(),
+            Self::change(AudioFocusStatus::Granted, Some(focus_type)),
+        ));
+        Some(changes)
+    }
+
+    /// Releases the focus of the app, the interrupted holder gets the focus back when the
+    /// app was on top of the stack
+    pub fn abandon(&self, app_id: &str) -> FocusChanges {
+        let mut holders = self.holders.write().unwrap();
+        let was_top = holders.last().map(|h| h.app_id.eq(app_id)).unwrap_or(false);
+        holders.retain(|h| h.app_id.ne(app_id));
+        match holders.last() {
+            Some(top) if was_top => vec![(
+                top.app_id.clone(),
+                Self::change(AudioFocusStatus::Granted, Some(top.focus_type)),
+            )],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn get_holder(&self) -> Option<String> {
+        self.holders
+            .read()
+            .unwrap()
+            .last()
+            .map(|h| h.app_id.clone())
+    }
+
+    fn change(status: AudioFocusStatus, focus_type: Option<AudioFocusType>) -> AudioFocusChange {
+        AudioFocusChange { status, focus_type }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statuses(changes: FocusChanges) -> Vec<(String, AudioFocusStatus)> {
+        changes.into_iter().map(|(a, c)| (a, c.status)).collect()
+    }
+
+    #[test]
+    fn test_transient_focus_is_returned() {
+        let state = AudioFocusState::default();
+        state
+            .request("player", AudioFocusType::Exclusive, false)
+            .unwrap();
+        let changes = state
+            .request("assistant", AudioFocusType::TransientMayDuck, true)
+            .unwrap();
+        assert_eq!(
+            statuses(changes),
+            vec![
+                ("player".to_owned(), AudioFocusStatus::Ducked),
+                ("assistant".to_owned(), AudioFocusStatus::Granted)
+            ]
+        );
+
+        // the priority app can not be preempted
+        assert!(state
+            .request("other", AudioFocusType::Exclusive, false)
+            .is_none());
+
+        assert_eq!(
+            statuses(state.abandon("assistant")),
+            vec![("player".to_owned(), AudioFocusStatus::Granted)]
+        );
+        assert_eq!(state.get_holder(), Some("player".to_owned()));
+    }
+
+    #[test]
+    fn test_exclusive_focus_preempts() {
+        let state = AudioFocusState::default();
+        state
+            .request("player", AudioFocusType::Exclusive, false)
+            .unwrap();
+        let changes = state
+            .request("other", AudioFocusType::Exclusive, false)
+            .unwrap();
+        assert_eq!(
+            statuses(changes),
+            vec![
+                ("player".to_owned(), AudioFocusStatus::Lost),
+                ("other".to_owned(), AudioFocusStatus::Granted)
+            ]
+        );
+        assert!(state.abandon("player").is_empty());
+        assert!(state.abandon("other").is_empty());
+        assert!(state.get_holder().is_none());
+    }
+}
@@ -18,7 +18,10 @@
 use std::time::Instant;
 
 use ripple_sdk::{
-    api::{apps::AppRequest, manifest::ripple_manifest_loader::RippleManifestLoader},
+    api::{
+        apps::AppRequest, firebolt::fb_telemetry::BootMilestoneType,
+        manifest::ripple_manifest_loader::RippleManifestLoader,
+    },
     framework::bootstrap::TransientChannel,
     log::{error, info, warn},
     tokio::sync::mpsc::{self, Receiver, Sender},
@@ -26,10 +29,12 @@ use ripple_sdk::{
 };
 
 use crate::{
-    bootstrap::manifest::apps::LoadAppLibraryStep,
     broker::endpoint_broker::{BrokerOutput, BROKER_CHANNEL_BUFFER_SIZE},
     firebolt::firebolt_gateway::FireboltGatewayCommand,
-    service::extn::ripple_client::RippleClient,
+    service::{
+        apps::app_library_refresh::AppLibraryRefresh, extn::ripple_client::RippleClient,
+        store_integrity::StoreIntegrity, telemetry_builder::TelemetryBuilder,
+    },
 };
 
 use super::platform_state::PlatformState;
@@ -97,11 +102,14 @@ impl BootstrapState {
     pub fn build() -> Result<BootstrapState, RippleError> {
         let channels_state = ChannelsState::new();
         let client = RippleClient::new(channels_state.clone());
-        let Ok((extn_manifest, device_manifest)) = RippleManifestLoader::initialize() else {
+        let Ok((extn_manifest, mut device_manifest)) = RippleManifestLoader::initialize() else {
             error!("Error initializing manifests");
             return Err(RippleError::BootstrapError);
         };
-        let app_manifest_result = LoadAppLibraryStep::load_app_library();
+        device_manifest.configuration.apply_compliance_profile();
+        // corrupt stores are reset before anything loads them
+        let repaired_stores = StoreIntegrity::check(&device_manifest.configuration.saved_dir);
+        let app_manifest_result = AppLibraryRefresh::load(&device_manifest);
         let platform_state = PlatformState::new(
             extn_manifest,
             device_manifest,
@@ -109,6 +117,8 @@ impl BootstrapState {
             app_manifest_result,
             ripple_version_from_etc(),
         );
+        TelemetryBuilder::send_boot_milestone(&platform_state, BootMilestoneType::ManifestLoaded);
+        TelemetryBuilder::send_store_repair(&platform_state, repaired_stores);
 
         fn ripple_version_from_etc() -> Option<String> {
             static RIPPLE_VER_FILE_DEFAULT: &str = "/etc/rippleversion.txt";
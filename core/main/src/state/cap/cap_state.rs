@@ -16,8 +16,10 @@
 //
 
 use std::{
-    collections::HashSet,
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
     hash::{Hash, Hasher},
+    path::Path,
     sync::{Arc, RwLock},
 };
 
@@ -32,8 +34,8 @@ use ripple_sdk::{
     api::{
         firebolt::{
             fb_capabilities::{
-                CapEvent, CapListenRPCRequest, CapabilityInfo, CapabilityRole, DenyReason,
-                FireboltCap,
+                CapEvent, CapListenRPCRequest, CapabilityInfo, CapabilityRole, CapabilityUsage,
+                DenyReason, FireboltCap, FireboltPermission,
             },
             fb_general::ListenRequest,
             fb_openrpc::CapabilitySet,
@@ -41,6 +43,8 @@ use ripple_sdk::{
         gateway::rpc_gateway_api::CallContext,
         manifest::device_manifest::DeviceManifest,
     },
+    chrono::Utc,
+    framework::file_store::FileStore,
     log::debug,
     utils::error::RippleError,
 };
@@ -56,18 +60,130 @@ pub struct CapState {
     pub permitted_state: PermittedState,
     primed_listeners: Arc<RwLock<HashSet<CapEventEntry>>>,
     pub grant_state: GrantState,
+    usage: Arc<RwLock<HashMap<String, HashMap<FireboltPermission, CapUsageEntry>>>>,
+    /// Last use of each grant in millis, persisted so unused grants are found across restarts
+    last_used: Arc<RwLock<FileStore<HashMap<String, i64>>>>,
+}
+
+/// Last use of a grant is persisted at most once per this period, the reaper works in much
+/// longer periods so the precision lost is not noticeable
+const LAST_USED_PERSIST_INTERVAL_MS: i64 = 60_000;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct CapUsageEntry {
+    count: u64,
+    last_used: i64,
 }
 
 impl CapState {
     pub fn new(manifest: DeviceManifest) -> Self {
+        let path = Path::new(&manifest.configuration.saved_dir)
+            .join("grant_last_used")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let last_used =
+            FileStore::load(path.clone()).unwrap_or_else(|_| FileStore::new(path, HashMap::new()));
         CapState {
             generic: GenericCapState::new(manifest.clone()),
             permitted_state: PermittedState::new(manifest.clone()),
             primed_listeners: Arc::new(RwLock::new(HashSet::new())),
             grant_state: GrantState::new(manifest),
+            usage: Arc::new(RwLock::new(HashMap::new())),
+            last_used: Arc::new(RwLock::new(last_used)),
+        }
+    }
+
+    fn get_grant_key(app_id: &str, permission: &FireboltPermission) -> String {
+        format!(
+            "{}|{}|{}",
+            app_id,
+            permission.cap.as_str(),
+            permission.role.as_string()
+        )
+    }
+
+    /// Records the capabilities used by a call which passed the gatekeeper
+    pub fn record_usage(&self, app_id: &str, permissions: &[FireboltPermission]) {
+        if permissions.is_empty() {
+            return;
+        }
+        let now = Utc::now().timestamp_millis();
+        let mut usage = self.usage.write().unwrap();
+        let app_usage = usage.entry(app_id.to_owned()).or_default();
+        for permission in permissions {
+            let entry = app_usage.entry(permission.clone()).or_default();
+            entry.count += 1;
+            entry.last_used = now;
+        }
+        drop(usage);
+
+        let mut last_used = self.last_used.write().unwrap();
+        let mut changed = false;
+        for permission in permissions {
+            let used = last_used
+                .value
+                .entry(Self::get_grant_key(app_id, permission))
+                .or_default();
+            if now - *used >= LAST_USED_PERSIST_INTERVAL_MS {
+                *used = now;
+                changed = true;
+            }
+        }
+        if changed {
+            let snapshot = last_used.snapshot();
+            drop(last_used);
+            snapshot.persist();
         }
     }
 
+    /// Last use of the grant in millis, also from before a restart
+    pub fn get_last_used(&self, app_id: &str, permission: &FireboltPermission) -> Option<i64> {
+        self.last_used
+            .read()
+            .unwrap()
+            .value
+            .get(&Self::get_grant_key(app_id, permission))
+            .copied()
+    }
+
+    /// Forgets the last use of a grant which was revoked
+    pub fn clear_last_used(&self, app_id: &str, permission: &FireboltPermission) {
+        let mut last_used = self.last_used.write().unwrap();
+        if last_used
+            .value
+            .remove(&Self::get_grant_key(app_id, permission))
+            .is_some()
+        {
+            let snapshot = last_used.snapshot();
+            drop(last_used);
+            snapshot.persist();
+        }
+    }
+
+    /// Capability usage report per app, for a single app when `app_id` is given
+    pub fn get_usage_report(&self, app_id: Option<&str>) -> HashMap<String, Vec<CapabilityUsage>> {
+        self.usage
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(id, _)| app_id.map(|a| a.eq(id.as_str())).unwrap_or(true))
+            .map(|(id, app_usage)| {
+                let mut report: Vec<CapabilityUsage> = app_usage
+                    .iter()
+                    .map(|(permission, entry)| CapabilityUsage {
+                        capability: permission.cap.as_str(),
+                        role: permission.role,
+                        count: entry.count,
+                        last_used: entry.last_used,
+                    })
+                    .collect();
+                report.sort_by_key(|r| Reverse(r.count));
+                (id.clone(), report)
+            })
+            .collect()
+    }
+
     pub async fn setup_listener(
         ps: &PlatformState,
         call_context: CallContext,
@@ -341,6 +457,35 @@ mod tests {
     };
     use ripple_sdk::tokio;
 
+    #[test]
+    fn test_capability_usage() {
+        let runtime = test_utils::MockRuntime::new();
+        let cap_state = &runtime.platform_state.cap_state;
+        let device_info = FireboltPermission {
+            cap: FireboltCap::Short("device:info".to_owned()),
+            role: CapabilityRole::Use,
+        };
+        let device_model = FireboltPermission {
+            cap: FireboltCap::Short("device:model".to_owned()),
+            role: CapabilityRole::Use,
+        };
+        cap_state.record_usage("app1", &[device_info.clone(), device_model.clone()]);
+        cap_state.record_usage("app1", &[device_model.clone()]);
+        cap_state.record_usage("app2", &[device_info.clone()]);
+
+        let report = cap_state.get_usage_report(Some("app1"));
+        assert_eq!(report.len(), 1);
+        let app1 = report.get("app1").unwrap();
+        assert_eq!(app1[0].capability, "xrn:firebolt:capability:device:model");
+        assert_eq!(app1[0].count, 2);
+        assert_eq!(app1[1].count, 1);
+        assert_eq!(cap_state.get_usage_report(None).len(), 2);
+        assert!(cap_state.get_last_used("app2", &device_model).is_none());
+        assert!(cap_state.get_last_used("app2", &device_info).is_some());
+        cap_state.clear_last_used("app2", &device_info);
+        assert!(cap_state.get_last_used("app2", &device_info).is_none());
+    }
+
     #[tokio::test]
     async fn test_app_ignore() {
         let mut runtime = test_utils::MockRuntime::new();
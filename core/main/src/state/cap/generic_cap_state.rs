@@ -77,6 +77,19 @@ impl GenericCapState {
         info!("Caps that are not available: {:?}", not_available);
     }
 
+    pub fn get_supported(&self) -> Vec<FireboltPermission> {
+        self.supported
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|p| serde_json::from_str(p).ok())
+            .collect()
+    }
+
+    pub fn get_not_available(&self) -> Vec<String> {
+        self.not_available.read().unwrap().iter().cloned().collect()
+    }
+
     pub fn check_for_processor(&self, request: Vec<String>) -> HashMap<String, bool> {
         let supported = self.supported.read().unwrap();
         let mut result = HashMap::new();
@@ -67,14 +67,21 @@ impl PermittedState {
         perms.sync();
     }
 
-    #[cfg(test)]
+    /// Drops the cached permissions of an app so they are fetched again on next use
+    pub fn remove_app(&self, app_id: &str) {
+        let mut perms = self.permitted.write().unwrap();
+        if perms.value.remove(app_id).is_some() {
+            perms.sync();
+        }
+    }
+
     pub fn set_permissions(&mut self, permissions: HashMap<String, Vec<FireboltPermission>>) {
         let mut perms = self.permitted.write().unwrap();
         perms.value = permissions;
         perms.sync();
     }
 
-    fn get_all_permissions(&self) -> HashMap<String, Vec<FireboltPermission>> {
+    pub fn get_all_permissions(&self) -> HashMap<String, Vec<FireboltPermission>> {
         self.permitted.read().unwrap().value.clone()
     }
     fn has_cached_permissions(&self, app_id: &String) -> bool {
@@ -0,0 +1,153 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+};
+
+use ripple_sdk::{
+    api::config::{ConfigInvalidation, ConfigSection},
+    log::{debug, error},
+};
+
+use super::platform_state::PlatformState;
+
+/// Revisions of the typed config sections along with the extensions subscribed to them.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSectionState {
+    revisions: Arc<RwLock<HashMap<ConfigSection, u64>>>,
+    subscribers: Arc<RwLock<HashMap<ConfigSection, HashSet<String>>>>,
+}
+
+impl ConfigSectionState {
+    pub fn get_revision(&self, section: ConfigSection) -> u64 {
+        self.revisions
+            .read()
+            .unwrap()
+            .get(&section)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn subscribe(&self, section: ConfigSection, extn_id: String) {
+        self.subscribers
+            .write()
+            .unwrap()
+            .entry(section)
+            .or_default()
+            .insert(extn_id);
+    }
+
+    /// Drops every subscription of an extension whose connection is gone
+    pub fn unsubscribe(&self, extn_id: &str) {
+        self.subscribers.write().unwrap().retain(|_, subscribers| {
+            subscribers.remove(extn_id);
+            !subscribers.is_empty()
+        });
+    }
+
+    pub fn get_subscribers(&self, section: ConfigSection) -> Vec<String> {
+        self.subscribers
+            .read()
+            .unwrap()
+            .get(&section)
+            .map(|s| s.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn next_revision(&self, section: ConfigSection) -> u64 {
+        let mut revisions = self.revisions.write().unwrap();
+        let revision = revisions.entry(section).or_default();
+        *revision += 1;
+        *revision
+    }
+
+    /// Bumps the revision of the section and notifies the subscribed extensions
+    pub fn invalidate(state: &PlatformState, section: ConfigSection) {
+        let revision = state.config_section_state.next_revision(section);
+        let client = state.get_client().get_extn_client();
+        for extn_id in state.config_section_state.get_subscribers(section) {
+            debug!(
+                "Invalidating config section {:?} revision={} for {}",
+                section, revision, extn_id
+            );
+            if let Err(e) = client.send_event_with_id(
+                &extn_id,
+                ConfigInvalidation {
+                    config_section: section,
+                    revision,
+                },
+            ) {
+                error!(
+                    "Failed to invalidate config section {:?} for {}: {:?}",
+                    section, extn_id, e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_tdk::utils::test_utils::Mockable;
+
+    #[test]
+    fn test_invalidate() {
+        let state = PlatformState::mock();
+        state.config_section_state.subscribe(
+            ConfigSection::Launcher,
+            "ripple:channel:launcher:internal".into(),
+        );
+        assert_eq!(
+            state
+                .config_section_state
+                .get_revision(ConfigSection::Launcher),
+            0
+        );
+        ConfigSectionState::invalidate(&state, ConfigSection::Launcher);
+        assert_eq!(
+            state
+                .config_section_state
+                .get_revision(ConfigSection::Launcher),
+            1
+        );
+        assert_eq!(
+            state
+                .config_section_state
+                .get_revision(ConfigSection::Features),
+            0
+        );
+    }
+
+    #[test]
+    fn test_unsubscribe() {
+        let state = ConfigSectionState::default();
+        state.subscribe(ConfigSection::Launcher, "extn1".into());
+        state.subscribe(ConfigSection::Features, "extn1".into());
+        state.subscribe(ConfigSection::Features, "extn2".into());
+        state.unsubscribe("extn1");
+        assert!(state.get_subscribers(ConfigSection::Launcher).is_empty());
+        assert_eq!(
+            state.get_subscribers(ConfigSection::Features),
+            vec!["extn2".to_owned()]
+        );
+        state.unsubscribe("extn2");
+        assert!(state.subscribers.read().unwrap().is_empty());
+    }
+}
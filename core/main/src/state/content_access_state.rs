@@ -0,0 +1,129 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use ripple_sdk::{
+    api::{
+        firebolt::fb_discovery::{
+            ContentAccessInfo, ContentAccessListSetParams, DiscoveryRequest, SessionParams,
+        },
+        session::AccountSession,
+    },
+    framework::file_store::FileStore,
+    log::error,
+};
+
+use super::platform_state::PlatformState;
+
+/// Content access info (availabilities and entitlements) reported by each app through
+/// `discovery.contentAccess`, persisted across reboots.
+#[derive(Debug, Clone)]
+pub struct ContentAccessState {
+    store: Arc<RwLock<FileStore<HashMap<String, ContentAccessInfo>>>>,
+}
+
+impl ContentAccessState {
+    pub fn new(saved_dir: String) -> ContentAccessState {
+        let path = Path::new(&saved_dir)
+            .join("content_access")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let store =
+            FileStore::load(path.clone()).unwrap_or_else(|_| FileStore::new(path, HashMap::new()));
+        ContentAccessState {
+            store: Arc::new(RwLock::new(store)),
+        }
+    }
+
+    pub fn get_content_access(&self, app_id: &str) -> Option<ContentAccessInfo> {
+        self.store.read().unwrap().value.get(app_id).cloned()
+    }
+
+    pub fn set_content_access(&self, app_id: &str, info: ContentAccessInfo) {
+        let mut store = self.store.write().unwrap();
+        store.value.insert(app_id.to_owned(), info);
+        store.sync();
+    }
+
+    pub fn get_all_content_access(&self) -> HashMap<String, ContentAccessInfo> {
+        self.store.read().unwrap().value.clone()
+    }
+
+    /// Resends the persisted content access of every app to the distributor, so it is restored
+    /// once an account session is available after a reboot or a sign in
+    pub async fn send_to_distributor(state: &PlatformState, dist_session: &AccountSession) {
+        let content_access = state.content_access_state.get_all_content_access();
+        for (app_id, content_access_info) in content_access {
+            let request = DiscoveryRequest::SetContentAccess(ContentAccessListSetParams {
+                session_info: SessionParams {
+                    app_id: app_id.clone(),
+                    dist_session: dist_session.clone(),
+                },
+                content_access_info,
+            });
+            if let Err(e) = state.get_client().send_extn_request(request).await {
+                error!("Unable to restore content access of {}: {:?}", app_id, e);
+            }
+        }
+    }
+
+    pub fn clear_content_access(&self, app_id: &str) {
+        let mut store = self.store.write().unwrap();
+        if store.value.remove(app_id).is_some() {
+            store.sync();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::firebolt::fb_discovery::ContentAccessEntitlement;
+
+    #[test]
+    fn test_content_access_persistence() {
+        let saved_dir = std::env::temp_dir().join("ripple_content_access_test");
+        let _ = std::fs::remove_dir_all(&saved_dir);
+        let saved_dir = saved_dir.into_os_string().into_string().unwrap();
+        let state = ContentAccessState::new(saved_dir.clone());
+        let info = ContentAccessInfo {
+            availabilities: None,
+            entitlements: Some(vec![ContentAccessEntitlement {
+                entitlement_id: "entitlement1".to_string(),
+                start_time: None,
+                end_time: None,
+            }]),
+        };
+        state.set_content_access("app1", info.clone());
+        assert_eq!(state.get_all_content_access().len(), 1);
+        assert_eq!(
+            ContentAccessState::new(saved_dir.clone()).get_content_access("app1"),
+            Some(info)
+        );
+
+        state.clear_content_access("app1");
+        assert!(ContentAccessState::new(saved_dir)
+            .get_content_access("app1")
+            .is_none());
+    }
+}
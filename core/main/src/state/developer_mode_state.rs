@@ -0,0 +1,171 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::{Arc, RwLock};
+
+use ripple_sdk::{
+    api::{
+        firebolt::fb_capabilities::{DenyReason, DenyReasonWithCap},
+        gateway::{rpc_error::RpcError, rpc_gateway_api::ApiMessage},
+        manifest::device_manifest::RippleFeatures,
+    },
+    log::warn,
+    serde_json,
+    utils::expiring_map::ExpiringMap,
+};
+
+/// Developer mode lets app developers iterate on an app before the distributor grants are
+/// configured. Calls denied for a missing permission or grant go through, the response
+/// carries a `warning` field describing what would have been denied. Warnings of requests
+/// which never get a response expire like the other request correlations.
+///
+/// The `developerMode` feature is ignored in production builds, only `local_dev` and
+/// `pre_prod` builds honor it.
+#[derive(Debug, Clone, Default)]
+pub struct DeveloperModeState {
+    enabled: bool,
+    warnings: Arc<RwLock<ExpiringMap<String, String>>>,
+}
+
+impl DeveloperModeState {
+    pub fn new(features: &RippleFeatures) -> Self {
+        let enabled =
+            features.developer_mode && cfg!(any(feature = "local_dev", feature = "pre_prod"));
+        if features.developer_mode && !enabled {
+            warn!("Developer mode is not available in production builds");
+        }
+        Self {
+            enabled,
+            warnings: Arc::new(RwLock::new(ExpiringMap::default())),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[cfg(test)]
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_relaxable(reason: &DenyReason) -> bool {
+        matches!(
+            reason,
+            DenyReason::Unpermitted
+                | DenyReason::Ungranted
+                | DenyReason::GrantDenied
+                | DenyReason::GrantProviderMissing
+        )
+    }
+
+    /// Records a warning for the request when developer mode lets the denied call through.
+    /// Returns false if the call has to be denied.
+    pub fn relax(&self, request_id: &str, deny: &DenyReasonWithCap) -> bool {
+        if !self.enabled || !Self::is_relaxable(&deny.reason) {
+            return false;
+        }
+        let caps: Vec<String> = deny.caps.iter().map(|x| x.as_str()).collect();
+        let warning = format!(
+            "{} (allowed in developer mode)",
+            deny.reason.get_rpc_error_message(caps)
+        );
+        self.warnings
+            .write()
+            .unwrap()
+            .insert(request_id.to_owned(), warning);
+        true
+    }
+
+    /// Adds the pending warning of the request to the response message
+    pub fn attach_warning(&self, api_message: &mut ApiMessage) {
+        if !self.enabled || self.warnings.read().unwrap().is_empty() {
+            return;
+        }
+        let warning = match self
+            .warnings
+            .write()
+            .unwrap()
+            .remove(&api_message.request_id)
+        {
+            Some(warning) => warning,
+            None => return,
+        };
+        // The response is a serialized object, append the field instead of parsing it again
+        let response = api_message.jsonrpc_msg.trim_end();
+        if let (Some(body), Ok(warning)) =
+            (response.strip_suffix('}'), serde_json::to_string(&warning))
+        {
+            let separator = if body.trim_end().ends_with('{') {
+                ""
+            } else {
+                ","
+            };
+            api_message.jsonrpc_msg = format!("{}{}\"warning\":{}}}", body, separator, warning);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::{
+        firebolt::fb_capabilities::FireboltCap, gateway::rpc_gateway_api::ApiProtocol,
+    };
+    use ripple_sdk::serde_json::Value;
+
+    #[test]
+    fn test_developer_mode_warning() {
+        let state = DeveloperModeState {
+            enabled: true,
+            ..Default::default()
+        };
+        let deny = |reason| DenyReasonWithCap {
+            reason,
+            caps: vec![FireboltCap::short("device:model")],
+        };
+        assert!(!state.relax("req1", &deny(DenyReason::Unsupported)));
+        assert!(state.relax("req1", &deny(DenyReason::Unpermitted)));
+
+        let mut api_message = ApiMessage::new(
+            ApiProtocol::JsonRpc,
+            r#"{"jsonrpc":"2.0","id":1,"result":"model"}"#.into(),
+            "req1".into(),
+        );
+        state.attach_warning(&mut api_message);
+        let response: Value = serde_json::from_str(&api_message.jsonrpc_msg).unwrap();
+        assert_eq!(response["result"], "model");
+        assert!(response["warning"]
+            .as_str()
+            .unwrap()
+            .contains("xrn:firebolt:capability:device:model is not permitted"));
+
+        // warnings are only attached once
+        let mut api_message = ApiMessage::new(
+            ApiProtocol::JsonRpc,
+            r#"{"jsonrpc":"2.0","id":2,"result":"model"}"#.into(),
+            "req1".into(),
+        );
+        state.attach_warning(&mut api_message);
+        assert!(!api_message.jsonrpc_msg.contains("warning"));
+
+        assert!(!DeveloperModeState::default().relax("req2", &deny(DenyReason::Ungranted)));
+    }
+}
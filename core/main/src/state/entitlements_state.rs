@@ -0,0 +1,56 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::{Arc, RwLock};
+
+use ripple_sdk::api::firebolt::fb_discovery::EntitlementData;
+
+/// Entitlements of the account last synchronized from the distributor
+#[derive(Debug, Clone, Default)]
+pub struct EntitlementsState {
+    entitlements: Arc<RwLock<Vec<EntitlementData>>>,
+}
+
+impl EntitlementsState {
+    /// Stores the entitlements and returns true if they differ from the previous sync
+    pub fn update(&self, entitlements: Vec<EntitlementData>) -> bool {
+        let mut current = self.entitlements.write().unwrap();
+        if current.eq(&entitlements) {
+            return false;
+        }
+        *current = entitlements;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_entitlements() {
+        let state = EntitlementsState::default();
+        let entitlements = vec![EntitlementData {
+            entitlement_id: "entitlement1".to_string(),
+            start_time: None,
+            end_time: None,
+        }];
+        assert!(state.update(entitlements.clone()));
+        assert!(!state.update(entitlements.clone()));
+        assert!(state.update(Vec::new()));
+    }
+}
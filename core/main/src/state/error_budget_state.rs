@@ -0,0 +1,249 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use ripple_sdk::{
+    api::{
+        firebolt::{
+            fb_capabilities::{
+                JSON_RPC_STANDARD_ERROR_INVALID_PARAMS, JSON_RPC_STANDARD_ERROR_METHOD_NOT_FOUND,
+            },
+            fb_telemetry::AppErrorStats,
+        },
+        gateway::rpc_response::{
+            JSON_RPC_STANDARD_ERROR_INVALID_REQUEST, JSON_RPC_STANDARD_ERROR_PARSE,
+        },
+        manifest::device_manifest::ErrorBudgetConfiguration,
+    },
+    chrono::Utc,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcOutcome {
+    Success,
+    /// Denied or invalid calls, the 4xx range of the Firebolt error codes
+    ClientError,
+    /// Failures within Ripple or its endpoints, the 5xx range of the Firebolt error codes
+    ServerError,
+}
+
+impl RpcOutcome {
+    pub fn from_error_code(code: i32) -> RpcOutcome {
+        match code {
+            -49999..=-40000
+            | JSON_RPC_STANDARD_ERROR_PARSE
+            | JSON_RPC_STANDARD_ERROR_INVALID_REQUEST
+            | JSON_RPC_STANDARD_ERROR_METHOD_NOT_FOUND
+            | JSON_RPC_STANDARD_ERROR_INVALID_PARAMS => RpcOutcome::ClientError,
+            _ => RpcOutcome::ServerError,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct AppErrorWindow {
+    window_start: i64,
+    request_count: u64,
+    client_error_count: u64,
+    server_error_count: u64,
+    /// Consecutive windows the app went over its budget
+    throttle_level: u32,
+    over_budget: bool,
+    throttled_until: Option<i64>,
+}
+
+/// Tracks the error rate of each app and throttles apps which go over their error budget,
+/// for example an app hammering methods it was never granted.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorBudgetState {
+    config: Arc<ErrorBudgetConfiguration>,
+    windows: Arc<RwLock<HashMap<String, AppErrorWindow>>>,
+}
+
+impl ErrorBudgetState {
+    pub fn new(config: ErrorBudgetConfiguration) -> Self {
+        Self {
+            config: Arc::new(config),
+            windows: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Records the outcome of a call, returns the throttle applied to the app when the call
+    /// took it over its error budget
+    pub fn record(&self, app_id: &str, outcome: RpcOutcome) -> Option<u64> {
+        self.record_at(app_id, outcome, Utc::now().timestamp_millis())
+    }
+
+    fn record_at(&self, app_id: &str, outcome: RpcOutcome, now: i64) -> Option<u64> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let window_ms = (self.config.window_seconds * 1000) as i64;
+        let mut windows = self.windows.write().unwrap();
+        let window = windows.entry(app_id.to_owned()).or_default();
+        if now - window.window_start >= window_ms {
+            // a window within budget resets the progressive throttling
+            if !window.over_budget || now - window.window_start >= 2 * window_ms {
+                window.throttle_level = 0;
+            }
+            window.window_start = now;
+            window.request_count = 0;
+            window.client_error_count = 0;
+            window.server_error_count = 0;
+            window.over_budget = false;
+        }
+        window.request_count += 1;
+        match outcome {
+            RpcOutcome::Success => return None,
+            RpcOutcome::ClientError => window.client_error_count += 1,
+            RpcOutcome::ServerError => {
+                window.server_error_count += 1;
+                return None;
+            }
+        }
+        if window.over_budget || window.client_error_count <= self.config.client_error_budget {
+            return None;
+        }
+        window.over_budget = true;
+        let throttle_ms = self
+            .config
+            .throttle_ms
+            .saturating_mul(1 << window.throttle_level.min(16))
+            .min(self.config.max_throttle_ms);
+        window.throttle_level += 1;
+        window.throttled_until = Some(now + throttle_ms as i64);
+        Some(throttle_ms)
+    }
+
+    /// Returns the remaining throttle of the app in millis
+    pub fn get_throttle_remaining(&self, app_id: &str) -> Option<u64> {
+        self.get_throttle_remaining_at(app_id, Utc::now().timestamp_millis())
+    }
+
+    fn get_throttle_remaining_at(&self, app_id: &str, now: i64) -> Option<u64> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let windows = self.windows.read().unwrap();
+        let throttled_until = windows.get(app_id)?.throttled_until?;
+        if throttled_until > now {
+            Some((throttled_until - now) as u64)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_stats(&self) -> Vec<AppErrorStats> {
+        let now = Utc::now().timestamp_millis();
+        self.windows
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(app_id, window)| AppErrorStats {
+                app_id: app_id.clone(),
+                request_count: window.request_count,
+                client_error_count: window.client_error_count,
+                server_error_count: window.server_error_count,
+                throttled_until: window.throttled_until.filter(|until| *until > now),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_state() -> ErrorBudgetState {
+        ErrorBudgetState::new(ErrorBudgetConfiguration {
+            enabled: true,
+            window_seconds: 10,
+            client_error_budget: 2,
+            throttle_ms: 100,
+            max_throttle_ms: 300,
+        })
+    }
+
+    #[test]
+    fn test_error_outcome() {
+        assert_eq!(RpcOutcome::from_error_code(-40300), RpcOutcome::ClientError);
+        assert_eq!(
+            RpcOutcome::from_error_code(JSON_RPC_STANDARD_ERROR_METHOD_NOT_FOUND),
+            RpcOutcome::ClientError
+        );
+        assert_eq!(RpcOutcome::from_error_code(-50300), RpcOutcome::ServerError);
+        assert_eq!(RpcOutcome::from_error_code(-32603), RpcOutcome::ServerError);
+    }
+
+    #[test]
+    fn test_progressive_throttle() {
+        let state = get_state();
+        let mut now = 1000;
+        assert!(state
+            .record_at("app1", RpcOutcome::ServerError, now)
+            .is_none());
+        assert!(state
+            .record_at("app1", RpcOutcome::ClientError, now)
+            .is_none());
+        assert!(state
+            .record_at("app1", RpcOutcome::ClientError, now)
+            .is_none());
+        assert_eq!(
+            state.record_at("app1", RpcOutcome::ClientError, now),
+            Some(100)
+        );
+        // alerted once per window
+        assert!(state
+            .record_at("app1", RpcOutcome::ClientError, now)
+            .is_none());
+        assert_eq!(state.get_throttle_remaining_at("app1", now + 50), Some(50));
+        assert!(state.get_throttle_remaining_at("app1", now + 100).is_none());
+        assert!(state.get_throttle_remaining_at("app2", now).is_none());
+
+        // consecutive windows over budget double the throttle up to the max
+        for expected in [200, 300] {
+            now += 10000;
+            for _ in 0..2 {
+                state.record_at("app1", RpcOutcome::ClientError, now);
+            }
+            assert_eq!(
+                state.record_at("app1", RpcOutcome::ClientError, now),
+                Some(expected)
+            );
+        }
+
+        // a window within budget resets the throttle
+        now += 10000;
+        state.record_at("app1", RpcOutcome::Success, now);
+        now += 10000;
+        for _ in 0..2 {
+            state.record_at("app1", RpcOutcome::ClientError, now);
+        }
+        assert_eq!(
+            state.record_at("app1", RpcOutcome::ClientError, now),
+            Some(100)
+        );
+    }
+}
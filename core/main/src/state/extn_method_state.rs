@@ -0,0 +1,209 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use jsonschema::JSONSchema;
+use ripple_sdk::api::firebolt::fb_capabilities::{CapabilityRole, FireboltCap, FireboltPermission};
+use serde_json::Value;
+
+use crate::broker::rules::rules_engine::{MethodPattern, RuleMatchKind};
+
+/// Firebolt method registered by an extension with the capability gating its calls and the
+/// compiled schema of its params
+#[derive(Debug)]
+pub struct ExtnMethod {
+    pub extn_id: String,
+    pub capability: FireboltCap,
+    pattern: Option<MethodPattern>,
+    schema: Option<JSONSchema>,
+}
+
+impl ExtnMethod {
+    /// Fails when the schema of the params does not compile
+    pub fn new(
+        extn_id: &str,
+        method: &str,
+        capability: FireboltCap,
+        schema: Option<&Value>,
+    ) -> Result<ExtnMethod, String> {
+        let schema = match schema {
+            Some(schema) => Some(JSONSchema::compile(schema).map_err(|e| e.to_string())?),
+            None => None,
+        };
+        let pattern = match RuleMatchKind::of(method) {
+            RuleMatchKind::Exact => None,
+            _ => Some(MethodPattern::compile(&method.to_lowercase())),
+        };
+        Ok(ExtnMethod {
+            extn_id: extn_id.to_owned(),
+            capability,
+            pattern,
+            schema,
+        })
+    }
+}
+
+/// Methods registered by extensions at runtime keyed by their lowercase name. The gatekeeper
+/// takes the capability of a call from here and the extn broker validates its params before
+/// they reach the extension.
+#[derive(Debug, Clone, Default)]
+pub struct ExtnMethodState {
+    methods: Arc<RwLock<HashMap<String, Arc<ExtnMethod>>>>,
+}
+
+impl ExtnMethodState {
+    /// Replaces the methods registered before by the extension
+    pub fn set_methods(&self, extn_id: &str, methods: Vec<(String, ExtnMethod)>) {
+        let mut registered = self.methods.write().unwrap();
+        registered.retain(|_, method| method.extn_id.ne(extn_id));
+        for (name, method) in methods {
+            registered.insert(name.to_lowercase(), Arc::new(method));
+        }
+    }
+
+    pub fn remove_methods(&self, extn_id: &str) {
+        self.methods
+            .write()
+            .unwrap()
+            .retain(|_, method| method.extn_id.ne(extn_id));
+    }
+
+    /// Returns the registration serving the method, exact names before the most specific
+    /// pattern
+    pub fn get_method(&self, method: &str) -> Option<Arc<ExtnMethod>> {
+        let method = method.to_lowercase();
+        let registered = self.methods.read().unwrap();
+        if let Some(found) = registered.get(&method) {
+            return Some(found.clone());
+        }
+        registered
+            .iter()
+            .filter(|(_, registration)| {
+                registration
+                    .pattern
+                    .as_ref()
+                    .is_some_and(|pattern| pattern.matches(&method))
+            })
+            .min_by(|(a, _), (b, _)| {
+                RuleMatchKind::of(a)
+                    .cmp(&RuleMatchKind::of(b))
+                    .then_with(|| b.len().cmp(&a.len()))
+                    .then_with(|| a.cmp(b))
+            })
+            .map(|(_, registration)| registration.clone())
+    }
+
+    /// Capability an app needs to call the method, `None` when no extension registered it
+    pub fn get_permissions(&self, method: &str) -> Option<Vec<FireboltPermission>> {
+        self.get_method(method).map(|registration| {
+            vec![FireboltPermission {
+                cap: registration.capability.clone(),
+                role: CapabilityRole::Use,
+            }]
+        })
+    }
+
+    /// Checks the params of a call against the schema the extension registered for the
+    /// method, methods without a schema take any params
+    pub fn validate_params(&self, method: &str, params: Option<&Value>) -> Result<(), String> {
+        let registration = match self.get_method(method) {
+            Some(registration) => registration,
+            None => return Ok(()),
+        };
+        let schema = match &registration.schema {
+            Some(schema) => schema,
+            None => return Ok(()),
+        };
+        let params = params.cloned().unwrap_or(Value::Null);
+        let result = schema.validate(&params).map_err(|errors| {
+            errors
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extn_methods() {
+        let state = ExtnMethodState::default();
+        let cap = FireboltCap::Full("xrn:firebolt:capability:player:base".into());
+        let schema = json!({
+            "type": "object",
+            "properties": { "position": { "type": "integer" } },
+            "required": ["position"]
+        });
+        state.set_methods(
+            "ripple:extn:jsonrpsee:player",
+            vec![
+                (
+                    "Player.seek".into(),
+                    ExtnMethod::new(
+                        "ripple:extn:jsonrpsee:player",
+                        "Player.seek",
+                        cap.clone(),
+                        Some(&schema),
+                    )
+                    .unwrap(),
+                ),
+                (
+                    "player.s*".into(),
+                    ExtnMethod::new("ripple:extn:jsonrpsee:player", "player.s*", cap, None)
+                        .unwrap(),
+                ),
+            ],
+        );
+        assert!(ExtnMethod::new(
+            "extn",
+            "player.seek",
+            FireboltCap::default(),
+            Some(&json!({"type": 1}))
+        )
+        .is_err());
+
+        assert_eq!(
+            state.get_permissions("player.seek").unwrap()[0]
+                .cap
+                .as_str(),
+            "xrn:firebolt:capability:player:base"
+        );
+        assert!(state.get_permissions("player.stop").is_some());
+        assert!(state.get_permissions("player.play").is_none());
+
+        assert!(state
+            .validate_params("player.seek", Some(&json!({"position": 10})))
+            .is_ok());
+        assert!(state
+            .validate_params("player.seek", Some(&json!({"position": "start"})))
+            .is_err());
+        assert!(state.validate_params("player.seek", None).is_err());
+        assert!(state.validate_params("player.stop", None).is_ok());
+
+        state.remove_methods("ripple:extn:jsonrpsee:player");
+        assert!(state.get_permissions("player.seek").is_none());
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::{Arc, RwLock};
+
+use ripple_sdk::api::status_update::ExtnContractStatus;
+
+/// Holds the contract version matrix negotiated with the extensions during the extn client
+/// setup and when a service connects. Extensions with an incompatible contract are refused
+/// when they connect.
+#[derive(Debug, Clone, Default)]
+pub struct ExtnStatusState {
+    contracts: Arc<RwLock<Vec<ExtnContractStatus>>>,
+}
+
+impl ExtnStatusState {
+    pub fn set_contract_matrix(&self, contracts: Vec<ExtnContractStatus>) {
+        *self.contracts.write().unwrap() = contracts;
+    }
+
+    /// Replaces the contracts negotiated with an extension, e.g. with the versions it declared
+    /// when connecting.
+    pub fn update_contracts(&self, extn_id: &str, contracts: Vec<ExtnContractStatus>) {
+        let mut matrix = self.contracts.write().unwrap();
+        matrix.retain(|c| !c.extn_id.eq(extn_id));
+        matrix.extend(contracts);
+    }
+
+    pub fn get_contract_matrix(&self) -> Vec<ExtnContractStatus> {
+        self.contracts.read().unwrap().clone()
+    }
+
+    pub fn get_incompatible_contracts(&self, extn_id: &str) -> Vec<ExtnContractStatus> {
+        self.contracts
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|c| c.extn_id.eq(extn_id) && !c.is_compatible())
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_refused(&self, extn_id: &str) -> bool {
+        !self.get_incompatible_contracts(extn_id).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refused_extension() {
+        let state = ExtnStatusState::default();
+        state.set_contract_matrix(vec![
+            ExtnContractStatus {
+                extn_id: "ripple:channel:device:thunder".into(),
+                contract: "device_info".into(),
+                negotiated_version: Some(1),
+                extn_versions: vec![1],
+                core_versions: vec![1],
+            },
+            ExtnContractStatus {
+                extn_id: "ripple:extn:jsonrpsee:custom".into(),
+                contract: "config".into(),
+                negotiated_version: None,
+                extn_versions: vec![2],
+                core_versions: vec![1],
+            },
+        ]);
+        assert!(!state.is_refused("ripple:channel:device:thunder"));
+        assert!(state.is_refused("ripple:extn:jsonrpsee:custom"));
+        assert_eq!(state.get_contract_matrix().len(), 2);
+
+        state.update_contracts(
+            "ripple:extn:jsonrpsee:custom",
+            vec![ExtnContractStatus {
+                extn_id: "ripple:extn:jsonrpsee:custom".into(),
+                contract: "config".into(),
+                negotiated_version: Some(2),
+                extn_versions: vec![2],
+                core_versions: vec![1, 2],
+            }],
+        );
+        assert!(!state.is_refused("ripple:extn:jsonrpsee:custom"));
+        assert_eq!(state.get_contract_matrix().len(), 2);
+    }
+}
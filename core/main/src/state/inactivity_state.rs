@@ -0,0 +1,139 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use ripple_sdk::api::manifest::device_manifest::InactivityConfiguration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InactivityAction {
+    Idle(Duration),
+    SuspendBackground,
+}
+
+#[derive(Debug)]
+struct ActivityTracker {
+    last_activity: Instant,
+    idle: bool,
+    suspended: bool,
+}
+
+/// Tracks the last user input or app lifecycle activity for idle detection
+#[derive(Debug, Clone)]
+pub struct InactivityState {
+    tracker: Arc<RwLock<ActivityTracker>>,
+}
+
+impl Default for InactivityState {
+    fn default() -> Self {
+        InactivityState {
+            tracker: Arc::new(RwLock::new(ActivityTracker {
+                last_activity: Instant::now(),
+                idle: false,
+                suspended: false,
+            })),
+        }
+    }
+}
+
+impl InactivityState {
+    /// Records activity and returns true if the device was idle
+    pub fn record_activity(&self, now: Instant) -> bool {
+        let mut tracker = self.tracker.write().unwrap();
+        tracker.last_activity = now;
+        tracker.suspended = false;
+        std::mem::replace(&mut tracker.idle, false)
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.tracker.read().unwrap().idle
+    }
+
+    pub fn get_idle_duration(&self, now: Instant) -> Duration {
+        now.duration_since(self.tracker.read().unwrap().last_activity)
+    }
+
+    /// Returns the next action for the idle period, every action is only returned once until
+    /// the next activity.
+    pub fn check(
+        &self,
+        config: &InactivityConfiguration,
+        now: Instant,
+    ) -> Option<InactivityAction> {
+        let mut tracker = self.tracker.write().unwrap();
+        let idle_duration = now.duration_since(tracker.last_activity);
+        if !tracker.idle {
+            if idle_duration >= Duration::from_secs(config.idle_threshold_seconds) {
+                tracker.idle = true;
+                return Some(InactivityAction::Idle(idle_duration));
+            }
+            return None;
+        }
+        match config.suspend_background_after_seconds {
+            Some(suspend_after)
+                if !tracker.suspended && idle_duration >= Duration::from_secs(suspend_after) =>
+            {
+                tracker.suspended = true;
+                Some(InactivityAction::SuspendBackground)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inactivity_check() {
+        let config = InactivityConfiguration {
+            enabled: true,
+            idle_threshold_seconds: 60,
+            suspend_background_after_seconds: Some(120),
+        };
+        let state = InactivityState::default();
+        let start = Instant::now();
+        state.record_activity(start);
+
+        assert!(state
+            .check(&config, start + Duration::from_secs(30))
+            .is_none());
+        assert_eq!(
+            state.check(&config, start + Duration::from_secs(60)),
+            Some(InactivityAction::Idle(Duration::from_secs(60)))
+        );
+        assert!(state.is_idle());
+        assert!(state
+            .check(&config, start + Duration::from_secs(90))
+            .is_none());
+        assert_eq!(
+            state.check(&config, start + Duration::from_secs(120)),
+            Some(InactivityAction::SuspendBackground)
+        );
+        assert!(state
+            .check(&config, start + Duration::from_secs(180))
+            .is_none());
+
+        assert!(state.record_activity(start + Duration::from_secs(200)));
+        assert!(!state.is_idle());
+        assert!(!state.record_activity(start + Duration::from_secs(201)));
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use ripple_sdk::utils::expiring_map::ExpiringMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const INSPECTOR_CAPABILITY: &str = "ripple:inspector";
+pub const INSPECTOR_EVENT_ON_FRAME: &str = "ripple.onInspectorFrame";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectRequest {
+    pub listen: bool,
+    pub app_id: String,
+    /// Method patterns to mirror, either exact or ending with `*`. All methods are mirrored
+    /// when empty.
+    #[serde(default)]
+    pub methods: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FrameDirection {
+    Request,
+    Response,
+    Event,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectorFrame {
+    pub app_id: String,
+    pub direction: FrameDirection,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    pub timestamp: i64,
+    pub payload: Value,
+}
+
+#[derive(Debug, Clone)]
+struct InspectorFilter {
+    app_id: String,
+    methods: Vec<String>,
+}
+
+impl InspectorFilter {
+    fn matches(&self, method: Option<&str>) -> bool {
+        if self.methods.is_empty() {
+            return true;
+        }
+        let Some(method) = method.map(|m| m.to_lowercase()) else {
+            return false;
+        };
+        self.methods.iter().any(|pattern| {
+            let pattern = pattern.to_lowercase();
+            match pattern.strip_suffix('*') {
+                Some(prefix) => method.starts_with(prefix),
+                None => method.eq(&pattern),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PendingRequest {
+    method: String,
+    /// Events of listeners reuse the request id
+    listener: bool,
+    responded: bool,
+}
+
+/// Developer tools inspecting the Firebolt traffic of an app session, keyed by the session
+/// of the inspector. Requests of inspected apps are tracked until the response, so responses
+/// and the events of a listener can be attributed to their method. Requests which are never
+/// answered expire, listeners are kept until the app stops listening or disconnects.
+#[derive(Debug, Clone, Default)]
+pub struct InspectorState {
+    inspectors: Arc<RwLock<HashMap<String, InspectorFilter>>>,
+    pending_requests: Arc<RwLock<ExpiringMap<(String, String), PendingRequest>>>,
+}
+
+fn is_event_method(method: &str) -> bool {
+    method
+        .split_once('.')
+        .map(|(_, name)| {
+            name.strip_prefix("on")
+                .and_then(|n| n.chars().next())
+                .map(|c| c.is_ascii_uppercase())
+                .unwrap_or(false)
+        })
+        .unwrap_or(false)
+}
+
+impl InspectorState {
+    pub fn add_inspector(&self, session_id: String, app_id: String, methods: Vec<String>) {
+        self.inspectors
+            .write()
+            .unwrap()
+            .insert(session_id, InspectorFilter { app_id, methods });
+    }
+
+    pub fn remove_session(&self, session_id: &str) {
+        self.inspectors.write().unwrap().remove(session_id);
+        self.pending_requests
+            .write()
+            .unwrap()
+            .retain(|(s, _), _| s.ne(session_id));
+    }
+
+    pub fn is_inspector(&self, session_id: &str) -> bool {
+        self.inspectors.read().unwrap().contains_key(session_id)
+    }
+
+    pub fn is_inspected(&self, app_id: &str) -> bool {
+        self.inspectors
+            .read()
+            .unwrap()
+            .values()
+            .any(|f| f.app_id.eq(app_id))
+    }
+
+    /// Checks the method against the filter of the inspector session
+    pub fn matches(&self, inspector_session_id: &str, method: Option<&str>) -> bool {
+        self.inspectors
+            .read()
+            .unwrap()
+            .get(inspector_session_id)
+            .map(|f| f.matches(method))
+            .unwrap_or(false)
+    }
+
+    pub fn record_request(&self, session_id: &str, id: String, method: String, listen: bool) {
+        let mut pending_requests = self.pending_requests.write().unwrap();
+        let event_method = is_event_method(&method);
+        if event_method && !listen {
+            pending_requests.retain(|(s, _), pending| {
+                !(s.eq(session_id) && pending.method.eq_ignore_ascii_case(&method))
+            });
+        }
+        let listener = event_method && listen;
+        let key = (session_id.to_owned(), id);
+        let pending = PendingRequest {
+            method,
+            listener,
+            responded: false,
+        };
+        if listener {
+            pending_requests.insert_persistent(key, pending);
+        } else {
+            pending_requests.insert(key, pending);
+        }
+    }
+
+    /// Returns the direction and method of a message sent to the app. Listener requests are
+    /// kept after the first response as their events reuse the request id.
+    pub fn resolve_response(
+        &self,
+        session_id: &str,
+        id: String,
+    ) -> (FrameDirection, Option<String>) {
+        let key = (session_id.to_owned(), id);
+        let mut pending_requests = self.pending_requests.write().unwrap();
+        let Some(pending) = pending_requests.get_mut(&key) else {
+            return (FrameDirection::Response, None);
+        };
+        let method = pending.method.clone();
+        if !pending.listener {
+            pending_requests.remove(&key);
+            return (FrameDirection::Response, Some(method));
+        }
+        let direction = if pending.responded {
+            FrameDirection::Event
+        } else {
+            FrameDirection::Response
+        };
+        pending.responded = true;
+        (direction, Some(method))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspector_filter_and_responses() {
+        let state = InspectorState::default();
+        state.add_inspector(
+            "inspector".into(),
+            "app1".into(),
+            vec!["device.*".into(), "lifecycle.ready".into()],
+        );
+        assert!(state.is_inspected("app1"));
+        assert!(!state.is_inspected("app2"));
+        assert!(state.matches("inspector", Some("Device.name")));
+        assert!(state.matches("inspector", Some("lifecycle.ready")));
+        assert!(!state.matches("inspector", Some("lifecycle.close")));
+        assert!(!state.matches("inspector", None));
+
+        state.record_request("app1_session", "1".into(), "device.name".into(), false);
+        state.record_request(
+            "app1_session",
+            "2".into(),
+            "device.onNameChanged".into(),
+            true,
+        );
+        assert_eq!(
+            state.resolve_response("app1_session", "1".into()),
+            (FrameDirection::Response, Some("device.name".into()))
+        );
+        assert_eq!(
+            state.resolve_response("app1_session", "1".into()),
+            (FrameDirection::Response, None)
+        );
+        assert_eq!(
+            state.resolve_response("app1_session", "2".into()).0,
+            FrameDirection::Response
+        );
+        assert_eq!(
+            state.resolve_response("app1_session", "2".into()).0,
+            FrameDirection::Event
+        );
+
+        // stopping to listen forgets the listener, the unlisten request is answered once
+        state.record_request(
+            "app1_session",
+            "3".into(),
+            "device.onNameChanged".into(),
+            false,
+        );
+        assert_eq!(
+            state.resolve_response("app1_session", "2".into()),
+            (FrameDirection::Response, None)
+        );
+        assert_eq!(
+            state.resolve_response("app1_session", "3".into()),
+            (
+                FrameDirection::Response,
+                Some("device.onNameChanged".into())
+            )
+        );
+        assert_eq!(
+            state.resolve_response("app1_session", "3".into()),
+            (FrameDirection::Response, None)
+        );
+
+        state.remove_session("inspector");
+        assert!(!state.is_inspected("app1"));
+    }
+}
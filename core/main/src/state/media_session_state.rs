@@ -0,0 +1,116 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use ripple_sdk::api::firebolt::fb_media_session::{MediaSession, MediaSessionReport};
+
+/// Registry of the media sessions reported by apps. The active session is the most
+/// recently updated playing session, or the most recently updated session when nothing
+/// is playing.
+#[derive(Debug, Clone, Default)]
+pub struct MediaSessionState {
+    sessions: Arc<RwLock<HashMap<String, MediaSession>>>,
+}
+
+impl MediaSessionState {
+    pub fn update(&self, app_id: &str, report: MediaSessionReport, now: u64) -> MediaSession {
+        let mut sessions = self.sessions.write().unwrap();
+        let metadata = match (report.metadata, sessions.get(app_id)) {
+            (Some(metadata), _) => metadata,
+            (None, Some(previous)) => previous.metadata.clone(),
+            (None, None) => Default::default(),
+        };
+        let session = MediaSession {
+            app_id: app_id.to_owned(),
+            state: report.state,
+            position_ms: report.position_ms,
+            metadata,
+            updated_at: now,
+        };
+        sessions.insert(app_id.to_owned(), session.clone());
+        session
+    }
+
+    pub fn remove(&self, app_id: &str) -> Option<MediaSession> {
+        self.sessions.write().unwrap().remove(app_id)
+    }
+
+    pub fn get_sessions(&self) -> Vec<MediaSession> {
+        self.sessions.read().unwrap().values().cloned().collect()
+    }
+
+    pub fn get_active(&self) -> Option<MediaSession> {
+        let sessions = self.sessions.read().unwrap();
+        sessions
+            .values()
+            .max_by_key(|s| (s.is_playing(), s.updated_at))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::firebolt::fb_media_session::{MediaMetadata, PlaybackState};
+
+    fn report(state: PlaybackState, title: Option<&str>) -> MediaSessionReport {
+        MediaSessionReport {
+            state,
+            position_ms: None,
+            metadata: title.map(|t| MediaMetadata {
+                title: Some(t.into()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_active_media_session() {
+        let state = MediaSessionState::default();
+        assert!(state.get_active().is_none());
+
+        state.update("app1", report(PlaybackState::Playing, Some("News")), 1);
+        state.update("app2", report(PlaybackState::Paused, Some("Movie")), 2);
+        assert_eq!(state.get_active().unwrap().app_id, "app1");
+
+        // metadata is kept when it is not reported again
+        let session = state.update("app2", report(PlaybackState::Playing, None), 3);
+        assert_eq!(session.metadata.title, Some("Movie".into()));
+        assert_eq!(state.get_active().unwrap().app_id, "app2");
+
+        state.remove("app2");
+        assert_eq!(state.get_active().unwrap().app_id, "app1");
+        assert_eq!(state.get_sessions().len(), 1);
+    }
+
+    #[test]
+    fn test_same_state_ignores_position() {
+        let state = MediaSessionState::default();
+        let first = state.update("app1", report(PlaybackState::Playing, Some("News")), 1);
+        let mut progress = report(PlaybackState::Playing, None);
+        progress.position_ms = Some(5000);
+        let second = state.update("app1", progress, 2);
+        assert!(first.same_state(&second));
+
+        let paused = state.update("app1", report(PlaybackState::Paused, None), 3);
+        assert!(!second.same_state(&paused));
+    }
+}
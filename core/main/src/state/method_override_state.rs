@@ -0,0 +1,173 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use ripple_sdk::{
+    api::manifest::{
+        device_manifest::{MethodOverride, MethodOverrideAction, MethodOverridesConfiguration},
+        remote_feature::RemoteFeature,
+    },
+    log::{debug, info},
+    tokio,
+};
+
+use super::platform_state::PlatformState;
+
+/// Per app method overrides from the device manifest which are consulted by the gatekeeper
+/// before the capability checks.
+///
+/// Overrides gated by a feature flag are re-evaluated on the configured reload interval, so
+/// operators can turn an override on or off remotely without a restart.
+#[derive(Debug, Clone, Default)]
+pub struct MethodOverrideState {
+    config: Arc<MethodOverridesConfiguration>,
+    active_overrides: Arc<RwLock<Vec<MethodOverride>>>,
+}
+
+impl MethodOverrideState {
+    pub fn new(config: MethodOverridesConfiguration) -> Self {
+        // flagged overrides start with the flag default until the first reload
+        let active_overrides = config
+            .overrides
+            .iter()
+            .filter(|o| o.flag.as_ref().map(|f| f.default).unwrap_or(true))
+            .cloned()
+            .collect();
+        Self {
+            config: Arc::new(config),
+            active_overrides: Arc::new(RwLock::new(active_overrides)),
+        }
+    }
+
+    /// Returns the matching override for the app and method, deny takes precedence over allow
+    pub fn get_override(&self, app_id: &str, method: &str) -> Option<MethodOverride> {
+        let active_overrides = self.active_overrides.read().unwrap();
+        let mut matching = active_overrides
+            .iter()
+            .filter(|o| o.matches(app_id, method));
+        let first = matching.next()?;
+        if first.action == MethodOverrideAction::Deny {
+            return Some(first.clone());
+        }
+        Some(
+            matching
+                .find(|o| o.action == MethodOverrideAction::Deny)
+                .unwrap_or(first)
+                .clone(),
+        )
+    }
+
+    fn set_active_overrides(&self, active_overrides: Vec<MethodOverride>) {
+        *self.active_overrides.write().unwrap() = active_overrides;
+    }
+
+    pub async fn reload(state: &PlatformState) {
+        let config = state.method_override_state.config.clone();
+        let mut client = state.get_client().get_extn_client();
+        let mut active_overrides = Vec::new();
+        for method_override in config.overrides.iter() {
+            let active = match &method_override.flag {
+                Some(flag) => RemoteFeature::flag(&mut client, flag.clone()).await,
+                None => true,
+            };
+            if active {
+                active_overrides.push(method_override.clone());
+            }
+        }
+        debug!(
+            "Reloaded method overrides active={} configured={}",
+            active_overrides.len(),
+            config.overrides.len()
+        );
+        state
+            .method_override_state
+            .set_active_overrides(active_overrides);
+    }
+
+    pub fn start(state: PlatformState) {
+        let config = state.method_override_state.config.clone();
+        if !config.overrides.iter().any(|o| o.flag.is_some()) {
+            return;
+        }
+        info!(
+            "Reloading method overrides every {}s",
+            config.reload_interval_seconds
+        );
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(config.reload_interval_seconds.max(1)));
+            loop {
+                interval.tick().await;
+                Self::reload(&state).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::manifest::remote_feature::FeatureFlag;
+
+    fn get_override(app_id: &str, action: MethodOverrideAction) -> MethodOverride {
+        MethodOverride {
+            app_id: app_id.into(),
+            method: "device.name".into(),
+            action,
+            reason: None,
+            flag: None,
+        }
+    }
+
+    #[test]
+    fn test_get_override() {
+        let mut flagged = get_override("app2", MethodOverrideAction::Deny);
+        flagged.flag = Some(FeatureFlag {
+            default: false,
+            remote_key: Some("ripple_block_app2".into()),
+        });
+        let state = MethodOverrideState::new(MethodOverridesConfiguration {
+            overrides: vec![
+                get_override("*", MethodOverrideAction::Allow),
+                get_override("app1", MethodOverrideAction::Deny),
+                flagged,
+            ],
+            ..Default::default()
+        });
+
+        assert_eq!(
+            state.get_override("app1", "device.name").unwrap().action,
+            MethodOverrideAction::Deny
+        );
+        assert_eq!(
+            state.get_override("app2", "device.name").unwrap().action,
+            MethodOverrideAction::Allow
+        );
+        assert!(state.get_override("app1", "device.model").is_none());
+
+        state.set_active_overrides(vec![get_override("app2", MethodOverrideAction::Deny)]);
+        assert_eq!(
+            state.get_override("app2", "device.name").unwrap().action,
+            MethodOverrideAction::Deny
+        );
+        assert!(state.get_override("app1", "device.name").is_none());
+    }
+}
@@ -15,11 +15,34 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+pub mod activation_state;
+pub mod ad_config_state;
+pub mod admin_state;
+pub mod app_usage_state;
+pub mod audio_focus_state;
 pub mod bootstrap_state;
+pub mod config_section_state;
+pub mod content_access_state;
+pub mod developer_mode_state;
+pub mod entitlements_state;
+pub mod error_budget_state;
+pub mod extn_method_state;
+pub mod extn_status_state;
+pub mod inactivity_state;
+pub mod inspector_state;
+pub mod media_session_state;
+pub mod method_override_state;
+pub mod notification_policy_state;
 pub mod ops_metrics_state;
+pub mod pending_request_state;
 pub mod platform_state;
+pub mod region_state;
+pub mod request_journal_state;
 pub mod ripple_cache;
 pub mod session_state;
+pub mod shutdown_state;
+pub mod tenant_state;
+pub mod update_status_state;
 pub mod cap {
     pub mod cap_state;
     pub mod generic_cap_state;
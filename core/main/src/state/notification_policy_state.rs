@@ -0,0 +1,120 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::{Arc, RwLock};
+
+use ripple_sdk::api::{
+    firebolt::fb_notification_policy::{DoNotDisturbSettings, DoNotDisturbStatus},
+    manifest::device_manifest::NotificationPolicyConfiguration,
+};
+
+#[derive(Debug, Default)]
+struct DoNotDisturbTracker {
+    settings: DoNotDisturbSettings,
+    active: bool,
+}
+
+/// Do not disturb settings of the device along with the last evaluated status, the settings
+/// are persisted by the notification policy and cached here for the provider broker.
+#[derive(Debug, Clone, Default)]
+pub struct NotificationPolicyState {
+    config: Arc<NotificationPolicyConfiguration>,
+    tracker: Arc<RwLock<DoNotDisturbTracker>>,
+}
+
+impl NotificationPolicyState {
+    pub fn new(config: NotificationPolicyConfiguration) -> Self {
+        Self {
+            config: Arc::new(config),
+            tracker: Arc::new(RwLock::new(DoNotDisturbTracker::default())),
+        }
+    }
+
+    pub fn get_config(&self) -> Arc<NotificationPolicyConfiguration> {
+        self.config.clone()
+    }
+
+    pub fn get_settings(&self) -> DoNotDisturbSettings {
+        self.tracker.read().unwrap().settings.clone()
+    }
+
+    pub fn set_settings(&self, settings: DoNotDisturbSettings) {
+        self.tracker.write().unwrap().settings = settings;
+    }
+
+    pub fn get_status(&self) -> DoNotDisturbStatus {
+        let tracker = self.tracker.read().unwrap();
+        DoNotDisturbStatus {
+            active: tracker.active,
+            enabled: tracker.settings.enabled,
+            windows: tracker.settings.windows.clone(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.tracker.read().unwrap().active
+    }
+
+    /// Provider requests for the capability are held back while do not disturb is active
+    pub fn should_defer(&self, capability: &str) -> bool {
+        self.is_active() && self.config.is_deferred(capability)
+    }
+
+    /// Evaluates the settings for the local minute of the day and returns the new status if
+    /// do not disturb started or ended.
+    pub fn evaluate(&self, minute_of_day: u32) -> Option<bool> {
+        let mut tracker = self.tracker.write().unwrap();
+        let active = tracker.settings.is_active(minute_of_day);
+        if tracker.active == active {
+            return None;
+        }
+        tracker.active = active;
+        Some(active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::firebolt::{
+        fb_keyboard::KEYBOARD_PROVIDER_CAPABILITY, fb_notification_policy::DoNotDisturbWindow,
+        fb_pin::PIN_CHALLENGE_CAPABILITY,
+    };
+
+    #[test]
+    fn test_evaluate_do_not_disturb() {
+        let state = NotificationPolicyState::new(NotificationPolicyConfiguration::default());
+        state.set_settings(DoNotDisturbSettings {
+            enabled: false,
+            windows: vec![DoNotDisturbWindow {
+                start: "22:00".into(),
+                end: "07:00".into(),
+            }],
+        });
+
+        assert_eq!(state.evaluate(12 * 60), None);
+        assert!(!state.should_defer(KEYBOARD_PROVIDER_CAPABILITY));
+
+        assert_eq!(state.evaluate(23 * 60), Some(true));
+        assert_eq!(state.evaluate(23 * 60 + 1), None);
+        assert!(state.should_defer(KEYBOARD_PROVIDER_CAPABILITY));
+        assert!(!state.should_defer(PIN_CHALLENGE_CAPABILITY));
+
+        assert_eq!(state.evaluate(7 * 60), Some(false));
+        assert!(!state.get_status().active);
+    }
+}
@@ -16,12 +16,21 @@
 //
 
 use std::{
-    collections::{HashMap, HashSet},
-    sync::{Arc, RwLock},
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
 };
 
 use ripple_sdk::{
-    api::observability::metrics_util::ApiStats,
+    api::{
+        firebolt::fb_telemetry::{
+            AppDiagnostic, BootMilestone, BootMilestoneType, ServiceConnectionEventType,
+            StoreRepair,
+        },
+        observability::metrics_util::ApiStats,
+    },
     chrono::{DateTime, Utc},
     log::{error, warn},
 };
@@ -36,9 +45,24 @@ pub struct OpMetricState {
     operational_telemetry_listeners: Arc<RwLock<HashSet<String>>>,
     api_stats_map: Arc<RwLock<HashMap<String, ApiStats>>>,
     device_session_id: Arc<RwLock<Option<String>>>,
+    rpc_count: Arc<AtomicU64>,
+    rpc_error_count: Arc<AtomicU64>,
+    reconnect_count: Arc<AtomicU64>,
+    boot_milestones: Arc<RwLock<Vec<BootMilestone>>>,
+    store_repair: Arc<RwLock<Option<StoreRepair>>>,
+    recent_diagnostics: Arc<RwLock<VecDeque<AppDiagnostic>>>,
+    listener_connections: Arc<RwLock<HashMap<String, u64>>>,
+    service_connection_events: Arc<RwLock<HashMap<String, u64>>>,
 }
 
 impl OpMetricState {
+    pub fn new() -> OpMetricState {
+        OpMetricState {
+            start_time: Utc::now(),
+            ..Default::default()
+        }
+    }
+
     pub fn get_device_session_id(&self) -> String {
         self.device_session_id
             .read()
@@ -117,4 +141,177 @@ impl OpMetricState {
         let api_stats_map = self.api_stats_map.read().unwrap();
         api_stats_map.get(request_id).cloned()
     }
+
+    /// Records a boot milestone, returns None if the milestone was already reached
+    pub fn add_boot_milestone(
+        &self,
+        milestone: BootMilestoneType,
+        ripple_version: String,
+    ) -> Option<BootMilestone> {
+        let mut boot_milestones = self.boot_milestones.write().unwrap();
+        if boot_milestones.iter().any(|m| m.milestone == milestone) {
+            return None;
+        }
+        let now = Utc::now();
+        let boot_milestone = BootMilestone {
+            milestone,
+            timestamp: now.timestamp_millis(),
+            elapsed_ms: (now - self.start_time).num_milliseconds(),
+            ripple_session_id: self.get_device_session_id(),
+            ripple_version,
+        };
+        boot_milestones.push(boot_milestone.clone());
+        Some(boot_milestone)
+    }
+
+    pub fn get_boot_milestones(&self) -> Vec<BootMilestone> {
+        self.boot_milestones.read().unwrap().clone()
+    }
+
+    pub fn set_store_repair(&self, store_repair: StoreRepair) {
+        let _ = self.store_repair.write().unwrap().insert(store_repair);
+    }
+
+    pub fn get_store_repair(&self) -> Option<StoreRepair> {
+        self.store_repair.read().unwrap().clone()
+    }
+
+    /// Keeps the most recent diagnostic records pushed by apps, older records are dropped
+    /// once `capacity` is reached
+    pub fn add_app_diagnostic(&self, app_diagnostic: AppDiagnostic, capacity: usize) {
+        let mut recent_diagnostics = self.recent_diagnostics.write().unwrap();
+        while recent_diagnostics.len() >= capacity.max(1) {
+            recent_diagnostics.pop_front();
+        }
+        recent_diagnostics.push_back(app_diagnostic);
+    }
+
+    pub fn get_recent_diagnostics(&self) -> Vec<AppDiagnostic> {
+        self.recent_diagnostics
+            .read()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Counts the connections accepted on each listener address
+    pub fn record_connection(&self, listener: &str) {
+        *self
+            .listener_connections
+            .write()
+            .unwrap()
+            .entry(listener.to_owned())
+            .or_default() += 1;
+    }
+
+    pub fn get_listener_connections(&self) -> HashMap<String, u64> {
+        self.listener_connections.read().unwrap().clone()
+    }
+
+    pub fn record_service_connection_event(&self, event: ServiceConnectionEventType) {
+        *self
+            .service_connection_events
+            .write()
+            .unwrap()
+            .entry(format!("{:?}", event).to_lowercase())
+            .or_default() += 1;
+    }
+
+    /// Returns the service connection events counted since the previous call
+    pub fn take_service_connection_events(&self) -> HashMap<String, u64> {
+        std::mem::take(&mut *self.service_connection_events.write().unwrap())
+    }
+
+    pub fn record_rpc_result(&self, success: bool) {
+        self.rpc_count.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            self.rpc_error_count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_reconnect(&self) {
+        self.reconnect_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get_reconnect_count(&self) -> u64 {
+        self.reconnect_count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the count of Firebolt calls and failures since the previous call
+    pub fn take_rpc_counts(&self) -> (u64, u64) {
+        (
+            self.rpc_count.swap(0, Ordering::Relaxed),
+            self.rpc_error_count.swap(0, Ordering::Relaxed),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::firebolt::fb_diagnostics::DiagnosticsLogLevel;
+
+    #[test]
+    fn test_add_boot_milestone() {
+        let state = OpMetricState::new();
+        let milestone = state
+            .add_boot_milestone(BootMilestoneType::WsListening, "1.0.0".into())
+            .unwrap();
+        assert!(milestone.elapsed_ms >= 0);
+        assert!(state
+            .add_boot_milestone(BootMilestoneType::WsListening, "1.0.0".into())
+            .is_none());
+        assert!(state
+            .add_boot_milestone(BootMilestoneType::FirstAppReady, "1.0.0".into())
+            .is_some());
+        assert_eq!(state.get_boot_milestones().len(), 2);
+    }
+
+    #[test]
+    fn test_recent_diagnostics() {
+        let state = OpMetricState::default();
+        for i in 0..3 {
+            state.add_app_diagnostic(
+                AppDiagnostic {
+                    app_id: "app1".into(),
+                    app_session_id: None,
+                    ripple_session_id: String::default(),
+                    ripple_version: "1.0.0".into(),
+                    form_factor: "tv".into(),
+                    timestamp: i,
+                    level: DiagnosticsLogLevel::Info,
+                    message: format!("message {}", i),
+                    attributes: None,
+                },
+                2,
+            );
+        }
+        let recent_diagnostics = state.get_recent_diagnostics();
+        assert_eq!(recent_diagnostics.len(), 2);
+        assert_eq!(recent_diagnostics[0].timestamp, 1);
+    }
+
+    #[test]
+    fn test_record_connection() {
+        let state = OpMetricState::default();
+        state.record_connection("127.0.0.1:3473");
+        state.record_connection("127.0.0.1:3473");
+        state.record_connection("[::1]:3473");
+        let listener_connections = state.get_listener_connections();
+        assert_eq!(listener_connections.get("127.0.0.1:3473"), Some(&2));
+        assert_eq!(listener_connections.get("[::1]:3473"), Some(&1));
+    }
+
+    #[test]
+    fn test_service_connection_events() {
+        let state = OpMetricState::default();
+        state.record_service_connection_event(ServiceConnectionEventType::Connect);
+        state.record_service_connection_event(ServiceConnectionEventType::Register);
+        state.record_service_connection_event(ServiceConnectionEventType::Connect);
+        let events = state.take_service_connection_events();
+        assert_eq!(events.get("connect"), Some(&2));
+        assert_eq!(events.get("register"), Some(&1));
+        assert!(state.take_service_connection_events().is_empty());
+    }
 }
@@ -0,0 +1,131 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use ripple_sdk::api::gateway::rpc_gateway_api::RpcRequest;
+
+#[derive(Debug, Clone)]
+pub struct PendingRequest {
+    pub request: RpcRequest,
+    pub started: Instant,
+    /// Requests without a threshold are only counted as in flight, they are never stuck
+    pub threshold: Option<Duration>,
+}
+
+/// Registry of the app requests which were received by the gateway and are still waiting
+/// for a response, keyed by the request id. Requests are removed once the response is sent
+/// to the app or the app disconnects.
+#[derive(Debug, Clone, Default)]
+pub struct PendingRequestState {
+    requests: Arc<RwLock<HashMap<String, PendingRequest>>>,
+}
+
+impl PendingRequestState {
+    /// Tracks the request until it is answered, it is stuck after the threshold if any
+    pub fn add(&self, request: &RpcRequest, threshold: Option<Duration>) {
+        self.requests.write().unwrap().insert(
+            request.ctx.request_id.clone(),
+            PendingRequest {
+                request: request.clone(),
+                started: Instant::now(),
+                threshold,
+            },
+        );
+    }
+
+    /// Stops tracking the request, returns false when it was already answered
+    pub fn complete(&self, request_id: &str) -> bool {
+        self.requests.write().unwrap().remove(request_id).is_some()
+    }
+
+    pub fn remove_session(&self, session_id: &str) {
+        self.requests
+            .write()
+            .unwrap()
+            .retain(|_, p| p.request.ctx.session_id.ne(session_id));
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the requests which are pending for longer than their threshold. They stay
+    /// tracked, so a response sent meanwhile can still be told apart with
+    /// [PendingRequestState::complete].
+    pub fn get_stuck(&self, now: Instant) -> Vec<PendingRequest> {
+        self.requests
+            .read()
+            .unwrap()
+            .values()
+            .filter(|p| {
+                p.threshold
+                    .is_some_and(|threshold| now.saturating_duration_since(p.started) > threshold)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::Mockable;
+
+    #[test]
+    fn test_get_stuck() {
+        let state = PendingRequestState::default();
+        let threshold = Some(Duration::from_secs(30));
+        let mut request = RpcRequest::mock();
+        request.ctx.request_id = "req1".into();
+        state.add(&request, threshold);
+        request.ctx.request_id = "req2".into();
+        state.add(&request, threshold);
+        assert!(state.complete("req2"));
+        assert_eq!(state.len(), 1);
+        request.ctx.request_id = "req3".into();
+        state.add(&request, Some(Duration::from_secs(60)));
+        // counted as in flight without ever getting stuck
+        request.ctx.request_id = "req4".into();
+        state.add(&request, None);
+        assert_eq!(state.len(), 3);
+
+        assert!(state.get_stuck(Instant::now()).is_empty());
+
+        let later = Instant::now() + Duration::from_secs(3600);
+        let stuck = state.get_stuck(later);
+        assert_eq!(stuck.len(), 2);
+        assert!(state.complete("req1"));
+        // a request answered meanwhile is not failed again
+        assert!(!state.complete("req1"));
+        assert!(state.complete("req3"));
+        assert!(state.complete("req4"));
+        assert!(state.is_empty());
+
+        state.add(&request, threshold);
+        state.remove_session(&request.ctx.session_id);
+        assert!(state.is_empty());
+    }
+}
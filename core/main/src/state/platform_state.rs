@@ -21,7 +21,9 @@ use ripple_sdk::{
         gateway::rpc_gateway_api::RpcRequest,
         manifest::{
             app_library::AppLibraryState,
-            device_manifest::{AppLibraryEntry, DeviceManifest},
+            device_manifest::{
+                AppLibraryEntry, DataGovernanceConfig, DeviceManifest, RippleConfiguration,
+            },
             exclusory::ExclusoryImpl,
             extn_manifest::ExtnManifest,
         },
@@ -35,10 +37,16 @@ use ripple_sdk::{
     utils::error::RippleError,
     uuid::Uuid,
 };
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 use crate::{
-    broker::{endpoint_broker::EndpointBrokerState, rules::rules_engine::RuleEngine},
+    broker::{
+        endpoint_broker::EndpointBrokerState,
+        rules::{jq_sandbox::JqSandbox, rules_engine::RuleEngine},
+    },
     firebolt::rpc_router::RouterState,
     service::{
         apps::{
@@ -46,14 +54,29 @@ use crate::{
             delegated_launcher_handler::{AppManagerState, AppManagerState2_0},
             provider_broker::ProviderBrokerState,
         },
+        dbus_bridge::DbusBridge,
         extn::ripple_client::RippleClient,
-        ripple_service::service_controller_state::ServiceControllerState,
+        ripple_service::{
+            service_auth::ServiceAuth, service_controller_state::ServiceControllerState,
+        },
+        watch_history::WatchHistoryState,
     },
+    utils::runtime_topology::RuntimeTopology,
 };
 
 use super::{
-    cap::cap_state::CapState, ops_metrics_state::OpMetricState,
-    ripple_cache::RippleCache, session_state::SessionState,
+    activation_state::ActivationState, ad_config_state::AdConfigState, admin_state::AdminState,
+    app_usage_state::AppUsageState, audio_focus_state::AudioFocusState, cap::cap_state::CapState,
+    config_section_state::ConfigSectionState, content_access_state::ContentAccessState,
+    developer_mode_state::DeveloperModeState, entitlements_state::EntitlementsState,
+    error_budget_state::ErrorBudgetState, extn_method_state::ExtnMethodState,
+    extn_status_state::ExtnStatusState, inactivity_state::InactivityState,
+    inspector_state::InspectorState, media_session_state::MediaSessionState,
+    method_override_state::MethodOverrideState, notification_policy_state::NotificationPolicyState,
+    ops_metrics_state::OpMetricState, pending_request_state::PendingRequestState,
+    region_state::RegionState, request_journal_state::RequestJournalState,
+    ripple_cache::RippleCache, session_state::SessionState, shutdown_state::ShutdownState,
+    tenant_state::TenantState, update_status_state::UpdateStatusState,
 };
 
 /// Platform state encapsulates the internal state of the Ripple Main application.
@@ -97,7 +120,7 @@ pub struct PlatformState {
     pub extn_manifest: Arc<ExtnManifest>,
     device_manifest: Arc<DeviceManifest>,
     pub ripple_client: RippleClient,
-    pub app_library_state: AppLibraryState,
+    pub app_library_state: Arc<RwLock<AppLibraryState>>,
     pub session_state: SessionState,
     pub cap_state: CapState,
     pub app_events_state: AppEventsState,
@@ -111,6 +134,33 @@ pub struct PlatformState {
     pub endpoint_state: EndpointBrokerState,
     pub lifecycle2_app_state: AppManagerState2_0,
     pub service_controller_state: ServiceControllerState,
+    pub admin_state: AdminState,
+    pub developer_mode: DeveloperModeState,
+    pub method_override_state: MethodOverrideState,
+    pub extn_status_state: ExtnStatusState,
+    pub extn_method_state: ExtnMethodState,
+    pub config_section_state: ConfigSectionState,
+    pub activation_state: ActivationState,
+    pub update_status_state: UpdateStatusState,
+    pub inactivity_state: InactivityState,
+    pub media_session_state: MediaSessionState,
+    pub audio_focus_state: AudioFocusState,
+    pub notification_policy_state: NotificationPolicyState,
+    pub inspector_state: InspectorState,
+    pub pending_request_state: PendingRequestState,
+    pub runtime_topology: RuntimeTopology,
+    pub shutdown_state: ShutdownState,
+    pub dbus_bridge: DbusBridge,
+    pub entitlements_state: EntitlementsState,
+    pub watch_history_state: WatchHistoryState,
+    pub content_access_state: ContentAccessState,
+    pub app_usage_state: AppUsageState,
+    pub error_budget_state: ErrorBudgetState,
+    pub ad_config_state: AdConfigState,
+    pub tenant_state: TenantState,
+    pub region_state: RegionState,
+    pub request_journal_state: RequestJournalState,
+    pub service_auth: ServiceAuth,
 }
 
 impl PlatformState {
@@ -124,16 +174,19 @@ impl PlatformState {
         let exclusory = ExclusoryImpl::get(&manifest);
         let broker_sender = client.get_broker_sender();
         let rule_engine = RuleEngine::build(&extn_manifest);
+        JqSandbox::get().configure(manifest.get_jq_sandbox_configuration());
         let extn_sdks = extn_manifest.extn_sdks.clone();
         let provider_registations = extn_manifest.provider_registrations.clone();
-        let metrics_state = OpMetricState::default();
+        let metrics_state = OpMetricState::new();
+        let runtime_topology = RuntimeTopology::new(&manifest.get_runtime_topology_configuration());
+        let shutdown_state = ShutdownState::default();
         Self {
             extn_manifest: Arc::new(extn_manifest),
             cap_state: CapState::new(manifest.clone()),
             session_state: SessionState::default(),
             device_manifest: Arc::new(manifest.clone()),
             ripple_client: client.clone(),
-            app_library_state: AppLibraryState::new(app_library),
+            app_library_state: Arc::new(RwLock::new(AppLibraryState::new(app_library))),
             app_events_state: AppEventsState::default(),
             provider_broker_state: ProviderBrokerState::default(),
             app_manager_state: AppManagerState::new(&manifest.configuration.saved_dir.clone()),
@@ -147,9 +200,49 @@ impl PlatformState {
                 broker_sender,
                 rule_engine,
                 client,
-            ),
+            )
+            .with_route_precedence(manifest.get_routing_configuration().get_precedence())
+            .with_runtime_topology(runtime_topology.clone()),
             lifecycle2_app_state: AppManagerState2_0::new(),
-            service_controller_state: ServiceControllerState::default(),
+            service_controller_state: ServiceControllerState::default()
+                .with_shutdown_state(shutdown_state.clone()),
+            admin_state: AdminState::new(manifest.get_admin_configuration()),
+            developer_mode: DeveloperModeState::new(&manifest.get_features()),
+            method_override_state: MethodOverrideState::new(
+                manifest.get_method_overrides_configuration(),
+            ),
+            extn_status_state: ExtnStatusState::default(),
+            extn_method_state: ExtnMethodState::default(),
+            config_section_state: ConfigSectionState::default(),
+            activation_state: ActivationState::default(),
+            update_status_state: UpdateStatusState::default(),
+            inactivity_state: InactivityState::default(),
+            media_session_state: MediaSessionState::default(),
+            audio_focus_state: AudioFocusState::default(),
+            notification_policy_state: NotificationPolicyState::new(
+                manifest.get_notification_policy_configuration(),
+            ),
+            inspector_state: InspectorState::default(),
+            pending_request_state: PendingRequestState::default(),
+            runtime_topology,
+            shutdown_state,
+            dbus_bridge: DbusBridge::new(manifest.get_dbus_bridge_configuration()),
+            entitlements_state: EntitlementsState::default(),
+            watch_history_state: WatchHistoryState::new(&manifest),
+            content_access_state: ContentAccessState::new(manifest.configuration.saved_dir.clone()),
+            app_usage_state: AppUsageState::new(
+                manifest.get_app_usage_configuration(),
+                &manifest.configuration.saved_dir,
+            ),
+            error_budget_state: ErrorBudgetState::new(manifest.get_error_budget_configuration()),
+            ad_config_state: AdConfigState::new(manifest.get_ad_config_configuration()),
+            tenant_state: TenantState::new(manifest.get_tenants_configuration()),
+            region_state: RegionState::new(manifest.get_region_configuration()),
+            request_journal_state: RequestJournalState::new(
+                manifest.get_request_journal_configuration(),
+                &manifest.configuration.saved_dir,
+            ),
+            service_auth: ServiceAuth::new(manifest.get_service_auth_configuration()),
         }
     }
 
@@ -177,6 +270,20 @@ impl PlatformState {
         (*self.device_manifest).clone()
     }
 
+    /// Borrows the device configuration, for per request lookups which should not clone the
+    /// whole manifest
+    pub fn get_device_configuration(&self) -> &RippleConfiguration {
+        &self.device_manifest.configuration
+    }
+
+    pub fn get_data_governance_config(&self) -> &DataGovernanceConfig {
+        &self.device_manifest.configuration.data_governance
+    }
+
+    pub fn get_app_library_state(&self) -> AppLibraryState {
+        self.app_library_state.read().unwrap().clone()
+    }
+
     pub fn get_client(&self) -> RippleClient {
         self.ripple_client.clone()
     }
@@ -255,4 +362,12 @@ mod tests {
             )
         }
     }
+
+    impl PlatformState {
+        /// Replaces the device manifest of a mock state, for tests of configuration which is
+        /// read from the manifest on every use
+        pub fn set_device_manifest(&mut self, manifest: DeviceManifest) {
+            self.device_manifest = Arc::new(manifest);
+        }
+    }
 }
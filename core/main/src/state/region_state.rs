@@ -0,0 +1,329 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+use ripple_sdk::{
+    api::{
+        config::Config,
+        firebolt::fb_localization::{RegionInfo, RegionValueInfo},
+        manifest::device_manifest::{
+            RegionConfiguration, RegionField, RegionSourceType, RegionValues,
+        },
+    },
+    async_trait::async_trait,
+    extn::extn_client_message::ExtnResponse,
+    log::{debug, error, info},
+    tokio,
+};
+use serde_json::Value;
+
+use crate::{broker::broker_utils::BrokerUtils, service::apps::app_events::AppEvents};
+
+use super::platform_state::PlatformState;
+
+/// Source of the region values, selected by the region configuration in the device manifest
+#[async_trait]
+pub trait RegionSource: Send + Sync {
+    async fn get_values(&self, state: &PlatformState) -> RegionValues;
+}
+
+/// Values from the platform through the regular firebolt routing
+pub struct PlatformRegionSource;
+
+#[async_trait]
+impl RegionSource for PlatformRegionSource {
+    async fn get_values(&self, state: &PlatformState) -> RegionValues {
+        let mut values = RegionValues::default();
+        for field in RegionField::ALL {
+            match BrokerUtils::process_internal_main_request(state, field.get_method(), None).await
+            {
+                Ok(Value::String(value)) => values.set(field, Some(value)),
+                Ok(value) => error!("Unexpected {} from platform {}", field.get_method(), value),
+                Err(e) => debug!("No {} from platform {:?}", field.get_method(), e),
+            }
+        }
+        values
+    }
+}
+
+/// Fixed values from the device manifest
+pub struct ManifestRegionSource {
+    values: RegionValues,
+}
+
+#[async_trait]
+impl RegionSource for ManifestRegionSource {
+    async fn get_values(&self, _state: &PlatformState) -> RegionValues {
+        self.values.clone()
+    }
+}
+
+/// Values from the remote configuration, each field is read from its RFC key
+pub struct RemoteRegionSource {
+    keys: RegionValues,
+}
+
+#[async_trait]
+impl RegionSource for RemoteRegionSource {
+    async fn get_values(&self, state: &PlatformState) -> RegionValues {
+        let mut client = state.get_client().get_extn_client();
+        let mut values = RegionValues::default();
+        for field in RegionField::ALL {
+            let Some(key) = self.keys.get(field) else {
+                continue;
+            };
+            match client.request(Config::RFC(key.clone())).await {
+                Ok(response) => {
+                    if let Some(ExtnResponse::Value(Value::String(value))) =
+                        response.payload.extract::<ExtnResponse>()
+                    {
+                        values.set(field, Some(value).filter(|v| !v.is_empty()));
+                    }
+                }
+                Err(e) => error!("Failure to retrieve RFC {} {:?}", key, e),
+            }
+        }
+        values
+    }
+}
+
+pub fn get_region_source(
+    source_type: RegionSourceType,
+    config: &RegionConfiguration,
+) -> Box<dyn RegionSource> {
+    match source_type {
+        RegionSourceType::Platform => Box::new(PlatformRegionSource),
+        RegionSourceType::Manifest => Box::new(ManifestRegionSource {
+            values: config.fixed.clone(),
+        }),
+        RegionSourceType::Remote => Box::new(RemoteRegionSource {
+            keys: config.remote_keys.clone(),
+        }),
+    }
+}
+
+/// Country code, locale and time zone reported to apps for devices which ship one image to
+/// many regions.
+///
+/// The values are resolved from the sources in order of precedence and re-resolved on the
+/// configured reload interval or when the sources are switched through the admin API. Values
+/// which do not come from the platform are answered by the gateway, the platform emits its own
+/// change events so only the changes involving other sources are emitted here, and the platform
+/// events of the fields answered from other sources are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct RegionState {
+    config: Arc<RegionConfiguration>,
+    sources: Arc<RwLock<Vec<RegionSourceType>>>,
+    resolved: Arc<RwLock<HashMap<RegionField, RegionValueInfo>>>,
+    reloading: Arc<AtomicBool>,
+}
+
+impl RegionState {
+    pub fn new(config: RegionConfiguration) -> Self {
+        Self {
+            sources: Arc::new(RwLock::new(config.sources.clone())),
+            config: Arc::new(config),
+            resolved: Arc::new(RwLock::new(HashMap::new())),
+            reloading: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Only the platform source leaves the regular routing in charge of every field
+    pub fn is_enabled(&self) -> bool {
+        self.sources
+            .read()
+            .unwrap()
+            .iter()
+            .any(|s| *s != RegionSourceType::Platform)
+    }
+
+    /// Returns the value the gateway answers the firebolt getter with
+    pub fn get_value(&self, method: &str) -> Option<String> {
+        let field = RegionField::from_method(method)?;
+        self.resolved
+            .read()
+            .unwrap()
+            .get(&field)
+            .filter(|info| info.source != RegionSourceType::Platform)
+            .map(|info| info.value.clone())
+    }
+
+    /// Whether the platform event reports a field which is answered from another source
+    pub fn is_overridden_event(&self, event: &str) -> bool {
+        let Some(field) = RegionField::from_event(event) else {
+            return false;
+        };
+        self.resolved
+            .read()
+            .unwrap()
+            .get(&field)
+            .map(|info| info.source != RegionSourceType::Platform)
+            .unwrap_or(false)
+    }
+
+    pub fn get_info(&self) -> RegionInfo {
+        RegionInfo {
+            sources: self.sources.read().unwrap().clone(),
+            values: self.resolved.read().unwrap().clone(),
+        }
+    }
+
+    pub fn set_sources(&self, sources: Vec<RegionSourceType>) {
+        *self.sources.write().unwrap() = sources;
+    }
+
+    fn resolve(
+        values: &[(RegionSourceType, RegionValues)],
+    ) -> HashMap<RegionField, RegionValueInfo> {
+        let mut resolved = HashMap::new();
+        for field in RegionField::ALL {
+            if let Some(info) = values.iter().find_map(|(source, values)| {
+                values.get(field).map(|value| RegionValueInfo {
+                    value,
+                    source: *source,
+                })
+            }) {
+                resolved.insert(field, info);
+            }
+        }
+        resolved
+    }
+
+    /// Stores the resolved values and returns the changes which need an event
+    fn update(
+        &self,
+        resolved: HashMap<RegionField, RegionValueInfo>,
+    ) -> Vec<(RegionField, RegionValueInfo)> {
+        let mut current = self.resolved.write().unwrap();
+        let changes = resolved
+            .iter()
+            .filter(|(field, info)| match current.get(*field) {
+                Some(previous) => {
+                    previous.value != info.value
+                        && (previous.source != RegionSourceType::Platform
+                            || info.source != RegionSourceType::Platform)
+                }
+                None => false,
+            })
+            .map(|(field, info)| (*field, info.clone()))
+            .collect();
+        *current = resolved;
+        changes
+    }
+
+    pub async fn reload(state: &PlatformState) {
+        let region_state = &state.region_state;
+        let sources = region_state.sources.read().unwrap().clone();
+        let mut values = Vec::new();
+        for source in sources {
+            let source_values = get_region_source(source, &region_state.config)
+                .get_values(state)
+                .await;
+            values.push((source, source_values));
+        }
+        let changes = region_state.update(Self::resolve(&values));
+        for (field, info) in changes {
+            info!(
+                "Region {:?} changed to {} from {:?}",
+                field, info.value, info.source
+            );
+            AppEvents::emit(state, field.get_event(), &Value::String(info.value)).await;
+        }
+    }
+
+    /// Starts the reload loop once any source other than the platform is active, the loop
+    /// keeps running when the sources are switched back to the platform and idles until
+    /// another source is selected.
+    pub fn start(state: PlatformState) {
+        let region_state = &state.region_state;
+        if !region_state.is_enabled() || region_state.reloading.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let interval_seconds = region_state.config.reload_interval_seconds.max(1);
+        info!("Reloading region values every {}s", interval_seconds);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+            loop {
+                interval.tick().await;
+                if state.region_state.is_enabled() {
+                    Self::reload(&state).await;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_values(country_code: Option<&str>, time_zone: Option<&str>) -> RegionValues {
+        RegionValues {
+            country_code: country_code.map(String::from),
+            locale: None,
+            time_zone: time_zone.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_resolve_region() {
+        let state = RegionState::new(RegionConfiguration {
+            sources: vec![RegionSourceType::Remote, RegionSourceType::Platform],
+            ..Default::default()
+        });
+        assert!(state.is_enabled());
+
+        let resolved = RegionState::resolve(&[
+            (RegionSourceType::Remote, get_values(Some("GB"), None)),
+            (
+                RegionSourceType::Platform,
+                get_values(Some("US"), Some("America/New_York")),
+            ),
+        ]);
+        assert!(state.update(resolved).is_empty());
+        assert_eq!(
+            state.get_value("localization.countryCode"),
+            Some("GB".into())
+        );
+        // platform values are answered by the regular routing
+        assert_eq!(state.get_value("localization.timeZone"), None);
+
+        let resolved = RegionState::resolve(&[
+            (RegionSourceType::Remote, get_values(Some("DE"), None)),
+            (
+                RegionSourceType::Platform,
+                get_values(Some("US"), Some("America/Chicago")),
+            ),
+        ]);
+        let changes = state.update(resolved);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, RegionField::CountryCode);
+        assert_eq!(changes[0].1.value, "DE");
+        assert!(state.is_overridden_event("localization.onCountryCodeChanged"));
+        assert!(!state.is_overridden_event("localization.onTimeZoneChanged"));
+        assert!(!state.is_overridden_event("localization.onLocaleChanged"));
+
+        assert!(!RegionState::default().is_enabled());
+    }
+}
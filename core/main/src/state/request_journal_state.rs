@@ -0,0 +1,223 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::VecDeque,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+use ripple_sdk::{
+    api::{
+        firebolt::fb_telemetry::RequestJournalEntry,
+        manifest::device_manifest::RequestJournalConfiguration,
+    },
+    chrono::Utc,
+    framework::file_store::FileStore,
+    log::{info, warn},
+    tokio,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::service::telemetry_builder::TelemetryBuilder;
+
+use super::platform_state::PlatformState;
+
+/// Request journal as persisted in the saved dir
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequestJournal {
+    /// Ripple session which wrote the journal
+    pub session_id: Option<String>,
+    /// Set when the session shut down gracefully
+    pub clean_exit: bool,
+    pub entries: VecDeque<RequestJournalEntry>,
+}
+
+/// Rolling journal of the last Firebolt request envelopes, used to correlate a crash with the
+/// requests Ripple was handling at the time.
+///
+/// Entries are kept in memory and written to the saved dir on the flush interval, so
+/// recording a request never waits on the disk. The journal of the previous session is read
+/// on start, and when that session did not shut down gracefully its tail is sent with the
+/// crash breadcrumb telemetry.
+#[derive(Debug, Clone)]
+pub struct RequestJournalState {
+    config: Arc<RequestJournalConfiguration>,
+    store: Arc<RwLock<FileStore<RequestJournal>>>,
+    previous: Arc<RequestJournal>,
+    dirty: Arc<AtomicBool>,
+}
+
+impl RequestJournalState {
+    pub fn new(config: RequestJournalConfiguration, saved_dir: &str) -> Self {
+        let path = Path::new(saved_dir)
+            .join("request_journal")
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let previous = FileStore::<RequestJournal>::load(path.clone())
+            .map(|store| store.value)
+            .unwrap_or_default();
+        Self {
+            config: Arc::new(config),
+            store: Arc::new(RwLock::new(FileStore::new(path, RequestJournal::default()))),
+            previous: Arc::new(previous),
+            dirty: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn record(&self, app_id: &str, method: &str, code: i32) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut store = self.store.write().unwrap();
+        store.value.entries.push_back(RequestJournalEntry {
+            method: method.to_owned(),
+            app_id: app_id.to_owned(),
+            timestamp: Utc::now().timestamp_millis(),
+            code,
+        });
+        while store.value.entries.len() > self.config.max_entries {
+            store.value.entries.pop_front();
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Writes the journal on a blocking thread, the lock is only held to take the snapshot
+    pub fn flush(&self) {
+        if self.dirty.swap(false, Ordering::Relaxed) {
+            let snapshot = self.store.write().unwrap().snapshot();
+            snapshot.persist();
+        }
+    }
+
+    /// Marks the journal of this session as cleanly shut down
+    pub fn mark_clean_exit(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+        let snapshot = {
+            let mut store = self.store.write().unwrap();
+            store.value.clean_exit = true;
+            store.snapshot()
+        };
+        self.dirty.store(false, Ordering::Relaxed);
+        // written right away, the process is about to exit
+        snapshot.write();
+    }
+
+    /// Returns the session id and the last entries of the previous session if it did not
+    /// shut down gracefully
+    pub fn get_crash_tail(&self) -> Option<(Option<String>, Vec<RequestJournalEntry>)> {
+        if self.previous.clean_exit || self.previous.entries.is_empty() {
+            return None;
+        }
+        let skip = self
+            .previous
+            .entries
+            .len()
+            .saturating_sub(self.config.crash_tail_size);
+        Some((
+            self.previous.session_id.clone(),
+            self.previous.entries.iter().skip(skip).cloned().collect(),
+        ))
+    }
+
+    pub fn start(state: PlatformState) {
+        let journal = state.request_journal_state.clone();
+        if !journal.is_enabled() {
+            return;
+        }
+        if let Some((previous_session_id, recent_requests)) = journal.get_crash_tail() {
+            warn!(
+                "Previous session {:?} exited uncleanly, reporting its last {} requests",
+                previous_session_id,
+                recent_requests.len()
+            );
+            TelemetryBuilder::send_crash_breadcrumb(&state, previous_session_id, recent_requests);
+        }
+        let snapshot = {
+            let mut store = journal.store.write().unwrap();
+            store.value.session_id = Some(state.metrics.get_device_session_id());
+            store.snapshot()
+        };
+        snapshot.persist();
+        info!(
+            "Flushing the request journal every {}ms",
+            journal.config.flush_interval_ms
+        );
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(
+                journal.config.flush_interval_ms.max(1),
+            ));
+            loop {
+                interval.tick().await;
+                journal.flush();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_config() -> RequestJournalConfiguration {
+        RequestJournalConfiguration {
+            enabled: true,
+            max_entries: 3,
+            crash_tail_size: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_request_journal() {
+        let saved_dir = std::env::temp_dir().join(format!(
+            "ripple_request_journal_test_{}",
+            ripple_sdk::uuid::Uuid::new_v4()
+        ));
+        let saved_dir = saved_dir.to_str().unwrap();
+
+        let journal = RequestJournalState::new(get_config(), saved_dir);
+        assert!(journal.get_crash_tail().is_none());
+        for method in ["device.name", "device.model", "device.id", "device.make"] {
+            journal.record("app1", method, 0);
+        }
+        journal.flush();
+
+        // the session ended without a graceful shutdown
+        let journal = RequestJournalState::new(get_config(), saved_dir);
+        let (_, tail) = journal.get_crash_tail().unwrap();
+        let methods: Vec<&str> = tail.iter().map(|e| e.method.as_str()).collect();
+        assert_eq!(methods, vec!["device.id", "device.make"]);
+
+        journal.record("app1", "device.name", -32601);
+        journal.mark_clean_exit();
+        let journal = RequestJournalState::new(get_config(), saved_dir);
+        assert!(journal.get_crash_tail().is_none());
+        let _ = std::fs::remove_dir_all(saved_dir);
+    }
+}
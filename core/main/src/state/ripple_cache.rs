@@ -39,6 +39,14 @@ impl RippleCache {
         }
     }
 
+    pub fn get_privacy_settings(&self) -> PrivacySettingsData {
+        self.privacy_settings_cache.read().unwrap().clone()
+    }
+
+    pub fn set_privacy_settings(&self, privacy_settings: PrivacySettingsData) {
+        *self.privacy_settings_cache.write().unwrap() = privacy_settings;
+    }
+
     pub fn update_cached_bool_storage_property(&self, property: &StorageProperty, value: bool) {
         if property.is_a_privacy_setting_property() {
             // update the privacy setting property in cache
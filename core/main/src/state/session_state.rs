@@ -18,21 +18,26 @@
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 
 use ripple_sdk::{
     api::{
         apps::AppSession,
+        firebolt::fb_localization::ContextOverride,
         gateway::rpc_gateway_api::{ApiMessage, CallContext},
+        manifest::device_manifest::{ConnectionLimitsConfiguration, SessionPriority},
         session::{AccountSession, ProvisionRequest},
     },
-    tokio::sync::mpsc::Sender,
+    log::info,
+    tokio::sync::{mpsc::Sender, oneshot},
     utils::error::RippleError,
 };
 
 #[derive(Debug, Clone)]
 pub struct SessionData {
     app_id: String,
+    sdk_version: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -45,10 +50,18 @@ impl Session {
     pub fn new(app_id: String, sender: Option<Sender<ApiMessage>>) -> Session {
         Session {
             sender,
-            data: SessionData { app_id },
+            data: SessionData {
+                app_id,
+                sdk_version: None,
+            },
         }
     }
 
+    pub fn with_sdk_version(mut self, sdk_version: Option<String>) -> Self {
+        self.data.sdk_version = sdk_version;
+        self
+    }
+
     pub fn get_sender(&self) -> Option<Sender<ApiMessage>> {
         self.sender.clone()
     }
@@ -65,6 +78,10 @@ impl Session {
     pub fn get_app_id(&self) -> String {
         self.data.app_id.clone()
     }
+
+    pub fn get_sdk_version(&self) -> Option<String> {
+        self.data.sdk_version.clone()
+    }
 }
 
 /// Session state encapsulates the session table with mappings to Application identifier and
@@ -83,6 +100,51 @@ pub struct SessionState {
     session_map: Arc<RwLock<HashMap<String, Session>>>,
     account_session: Arc<RwLock<Option<AccountSession>>>,
     pending_sessions: Arc<RwLock<HashMap<String, Option<PendingSessionInfo>>>>,
+    context_overrides: Arc<RwLock<HashMap<String, ContextOverride>>>,
+    /// Admitted app connections keyed by connection id, counted from the handshake so
+    /// connection storms are capped before the sessions are registered
+    connections: Arc<RwLock<HashMap<String, AdmittedConnection>>>,
+}
+
+#[derive(Debug)]
+struct AdmittedConnection {
+    app_id: String,
+    priority: SessionPriority,
+    last_activity: Instant,
+    /// Closes the connection when it is preempted or the gateway restarts
+    evict_tx: Option<oneshot::Sender<ConnectionEviction>>,
+}
+
+/// Admission slot of an app connection, the slot is released when the guard is dropped so
+/// a connection task which panics or is cancelled does not leak it
+#[derive(Debug)]
+pub struct AdmissionGuard {
+    connections: Arc<RwLock<HashMap<String, AdmittedConnection>>>,
+    connection_id: String,
+}
+
+impl Drop for AdmissionGuard {
+    fn drop(&mut self) {
+        if let Ok(mut connections) = self.connections.write() {
+            connections.remove(&self.connection_id);
+        }
+    }
+}
+
+/// Reason Ripple closes an admitted app connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionEviction {
+    /// Preempted by a connection of the app
+    Preempted(String),
+    /// The app facing gateway restarted, with the restart reason
+    Restart(String),
+}
+
+/// Connection cap which an app connection exceeded, along with the configured limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionLimit {
+    Sessions(usize),
+    App(usize),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -137,6 +199,54 @@ impl SessionState {
     pub fn clear_session(&self, id: &str) {
         let mut session_state = self.session_map.write().unwrap();
         session_state.remove(id);
+        self.context_overrides.write().unwrap().remove(id);
+    }
+
+    /// Returns the number of open sessions of each app
+    pub fn get_app_session_counts(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for session in self.session_map.read().unwrap().values() {
+            *counts.entry(session.data.app_id.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    fn get_session_ids_for_app(&self, app_id: &str) -> Vec<String> {
+        self.session_map
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, session)| session.data.app_id.eq(app_id))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Overrides context values for the active sessions of the app, returns the number of
+    /// sessions updated. Overrides are dropped along with the session.
+    pub fn set_context_override(&self, app_id: &str, context: ContextOverride) -> usize {
+        let ids = self.get_session_ids_for_app(app_id);
+        let mut context_overrides = self.context_overrides.write().unwrap();
+        for id in &ids {
+            context_overrides.insert(id.clone(), context.clone());
+        }
+        ids.len()
+    }
+
+    pub fn clear_context_override(&self, app_id: &str) -> usize {
+        let ids = self.get_session_ids_for_app(app_id);
+        let mut context_overrides = self.context_overrides.write().unwrap();
+        ids.iter()
+            .filter(|id| context_overrides.remove(*id).is_some())
+            .count()
+    }
+
+    pub fn get_context_override(&self, ctx: &CallContext) -> Option<ContextOverride> {
+        let context_overrides = self.context_overrides.read().unwrap();
+        if let Some(cid) = &ctx.cid {
+            context_overrides.get(cid).cloned()
+        } else {
+            context_overrides.get(&ctx.session_id).cloned()
+        }
     }
 
     pub fn update_account_session(&self, provision: ProvisionRequest) {
@@ -177,4 +287,205 @@ impl SessionState {
     pub fn clear_pending_session(&self, app_id: &String) {
         self.pending_sessions.write().unwrap().remove(app_id);
     }
+
+    /// Admits a new connection of the app unless it exceeds the session or app caps, the
+    /// connection stays admitted as long as the returned guard is held. A critical app at the session
+    /// cap evicts the lowest priority idle session when preemption is enabled, the evicted
+    /// connection is told through its eviction channel.
+    pub fn admit_connection(
+        &self,
+        connection_id: &str,
+        app_id: &str,
+        priority: SessionPriority,
+        limits: &ConnectionLimitsConfiguration,
+        evict_tx: oneshot::Sender<ConnectionEviction>,
+    ) -> Result<AdmissionGuard, ConnectionLimit> {
+        let mut connections = self.connections.write().unwrap();
+        if let Some(app_limit) = limits.get_app_limit(app_id) {
+            if connections.values().filter(|c| c.app_id == app_id).count() >= app_limit {
+                return Err(ConnectionLimit::App(app_limit));
+            }
+        }
+        if let Some(max_sessions) = limits.max_sessions {
+            if connections.len() >= max_sessions {
+                let victim = if limits.preemption && priority == SessionPriority::Critical {
+                    Self::get_preemption_victim(
+                        &connections,
+                        Duration::from_millis(limits.preemption_idle_ms),
+                    )
+                } else {
+                    None
+                };
+                let Some(victim) = victim else {
+                    return Err(ConnectionLimit::Sessions(max_sessions));
+                };
+                if let Some(evicted) = connections.remove(&victim) {
+                    info!(
+                        "Preempting connection_id={} app_id={} for app_id={}",
+                        victim, evicted.app_id, app_id
+                    );
+                    if let Some(tx) = evicted.evict_tx {
+                        let _ = tx.send(ConnectionEviction::Preempted(app_id.to_owned()));
+                    }
+                }
+            }
+        }
+        connections.insert(
+            connection_id.to_owned(),
+            AdmittedConnection {
+                app_id: app_id.to_owned(),
+                priority,
+                last_activity: Instant::now(),
+                evict_tx: Some(evict_tx),
+            },
+        );
+        Ok(AdmissionGuard {
+            connections: self.connections.clone(),
+            connection_id: connection_id.to_owned(),
+        })
+    }
+
+    /// Idle connection of the lowest priority below critical, the longest idle one first
+    fn get_preemption_victim(
+        connections: &HashMap<String, AdmittedConnection>,
+        idle: Duration,
+    ) -> Option<String> {
+        connections
+            .iter()
+            .filter(|(_, c)| {
+                c.priority < SessionPriority::Critical && c.last_activity.elapsed() >= idle
+            })
+            .min_by_key(|(_, c)| (c.priority, c.last_activity))
+            .map(|(id, _)| id.clone())
+    }
+
+    pub fn touch_connection(&self, connection_id: &str) {
+        if let Some(c) = self.connections.write().unwrap().get_mut(connection_id) {
+            c.last_activity = Instant::now();
+        }
+    }
+
+    /// Tells every admitted connection to close for a gateway restart, returns the number of
+    /// connections told. The connections stay admitted until their guards are dropped.
+    pub fn evict_all(&self, reason: &str) -> usize {
+        self.connections
+            .write()
+            .unwrap()
+            .values_mut()
+            .filter_map(|c| c.evict_tx.take())
+            .map(|tx| tx.send(ConnectionEviction::Restart(reason.to_owned())))
+            .filter(Result::is_ok)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::Mockable;
+
+    #[test]
+    fn test_context_override() {
+        let state = SessionState::default();
+        state.add_session("cid1".into(), Session::new("app1".into(), None));
+        state.add_session("cid2".into(), Session::new("app2".into(), None));
+        let context = ContextOverride {
+            language: Some("fr".into()),
+            ..Default::default()
+        };
+        assert_eq!(state.set_context_override("app1", context.clone()), 1);
+        assert_eq!(state.set_context_override("unknown", context.clone()), 0);
+
+        let mut ctx = CallContext::mock();
+        ctx.cid = Some("cid1".into());
+        assert_eq!(state.get_context_override(&ctx), Some(context));
+        ctx.cid = Some("cid2".into());
+        assert!(state.get_context_override(&ctx).is_none());
+
+        state.clear_session("cid1");
+        ctx.cid = Some("cid1".into());
+        assert!(state.get_context_override(&ctx).is_none());
+        assert_eq!(state.clear_context_override("app1"), 0);
+    }
+
+    #[test]
+    fn test_admit_connection() {
+        let state = SessionState::default();
+        let limits = ConnectionLimitsConfiguration {
+            max_sessions: Some(3),
+            max_connections_per_app: Some(2),
+            ..Default::default()
+        };
+        let admit = |cid: &str, app_id: &str| {
+            let (tx, _) = oneshot::channel();
+            state.admit_connection(cid, app_id, SessionPriority::Normal, &limits, tx)
+        };
+        let cid1 = admit("cid1", "app1").unwrap();
+        let _cid2 = admit("cid2", "app1").unwrap();
+        assert_eq!(admit("cid3", "app1").err(), Some(ConnectionLimit::App(2)));
+        let _cid3 = admit("cid3", "app2").unwrap();
+        assert_eq!(
+            admit("cid4", "app3").err(),
+            Some(ConnectionLimit::Sessions(3))
+        );
+
+        drop(cid1);
+        assert!(admit("cid4", "app3").is_ok());
+    }
+
+    #[test]
+    fn test_preempt_connection() {
+        let state = SessionState::default();
+        let limits = ConnectionLimitsConfiguration {
+            max_sessions: Some(2),
+            preemption: true,
+            preemption_idle_ms: 0,
+            ..Default::default()
+        };
+        let (low_tx, mut low_rx) = oneshot::channel();
+        let _cid1 = state
+            .admit_connection("cid1", "app1", SessionPriority::Low, &limits, low_tx)
+            .unwrap();
+        let (normal_tx, mut normal_rx) = oneshot::channel();
+        let _cid2 = state
+            .admit_connection("cid2", "app2", SessionPriority::Normal, &limits, normal_tx)
+            .unwrap();
+
+        let (tx, _) = oneshot::channel();
+        assert_eq!(
+            state
+                .admit_connection("cid3", "app3", SessionPriority::Normal, &limits, tx)
+                .err(),
+            Some(ConnectionLimit::Sessions(2))
+        );
+
+        let (tx, _) = oneshot::channel();
+        assert!(state
+            .admit_connection("cid3", "launcher", SessionPriority::Critical, &limits, tx)
+            .is_ok());
+        assert_eq!(
+            low_rx.try_recv().unwrap(),
+            ConnectionEviction::Preempted("launcher".into())
+        );
+        assert!(normal_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_evict_all() {
+        let state = SessionState::default();
+        let limits = ConnectionLimitsConfiguration::default();
+        let (tx, mut rx) = oneshot::channel();
+        let cid1 = state
+            .admit_connection("cid1", "app1", SessionPriority::Normal, &limits, tx)
+            .unwrap();
+        assert_eq!(state.evict_all("rules reload"), 1);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            ConnectionEviction::Restart("rules reload".into())
+        );
+        assert_eq!(state.evict_all("rules reload"), 0);
+
+        drop(cid1);
+        assert!(state.connections.read().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,137 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::{Arc, RwLock};
+
+use ripple_sdk::tokio::sync::watch;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ShutdownPhase {
+    #[default]
+    Running,
+    /// New connections are refused while the in-flight requests complete
+    Draining,
+    /// Open app connections are closed with the shutdown reason
+    Closing,
+    /// The services were unregistered, the launched companion services are stopped
+    Stopping,
+}
+
+/// Shutdown progress shared with the connection handlers, which react to the phase changes
+/// started by [crate::service::graceful_shutdown::GracefulShutdown].
+#[derive(Debug, Clone)]
+pub struct ShutdownState {
+    phase: Arc<watch::Sender<ShutdownPhase>>,
+    reason: Arc<RwLock<String>>,
+}
+
+impl Default for ShutdownState {
+    fn default() -> Self {
+        Self {
+            phase: Arc::new(watch::channel(ShutdownPhase::Running).0),
+            reason: Arc::new(RwLock::new(String::default())),
+        }
+    }
+}
+
+impl ShutdownState {
+    /// Moves to draining, returns false if a shutdown was already started
+    pub fn start(&self, reason: &str) -> bool {
+        let reason_lock = self.reason.clone();
+        self.phase.send_if_modified(|phase| {
+            if *phase != ShutdownPhase::Running {
+                return false;
+            }
+            *reason_lock.write().unwrap() = reason.to_owned();
+            *phase = ShutdownPhase::Draining;
+            true
+        })
+    }
+
+    pub fn close_connections(&self) {
+        self.phase.send_replace(ShutdownPhase::Closing);
+    }
+
+    pub fn stop_services(&self) {
+        self.phase.send_replace(ShutdownPhase::Stopping);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.phase.borrow() != ShutdownPhase::Running
+    }
+
+    pub fn get_reason(&self) -> String {
+        self.reason.read().unwrap().clone()
+    }
+
+    /// Waits until the shutdown reached the phase and returns the shutdown reason
+    pub async fn wait_for(&self, phase: ShutdownPhase) -> String {
+        self.subscribe().wait_for(phase).await
+    }
+
+    /// Returns a listener for loops which wait for a phase in every iteration, so they do not
+    /// subscribe again for each message
+    pub fn subscribe(&self) -> ShutdownListener {
+        ShutdownListener {
+            rx: self.phase.subscribe(),
+            reason: self.reason.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ShutdownListener {
+    rx: watch::Receiver<ShutdownPhase>,
+    reason: Arc<RwLock<String>>,
+}
+
+impl ShutdownListener {
+    /// Waits until the shutdown reached the phase and returns the shutdown reason. Safe to
+    /// use in `select!` as the current phase is checked first on every call.
+    pub async fn wait_for(&mut self, phase: ShutdownPhase) -> String {
+        // the sender is owned by the shutdown state, so it outlives the receiver
+        let _ = self.rx.wait_for(|current| *current >= phase).await;
+        self.reason.read().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::tokio;
+
+    #[tokio::test]
+    async fn test_shutdown_phases() {
+        let state = ShutdownState::default();
+        assert!(!state.is_shutting_down());
+
+        let waiter = state.clone();
+        let closing = tokio::spawn(async move { waiter.wait_for(ShutdownPhase::Closing).await });
+        assert!(state.start("sigterm"));
+        assert!(!state.start("admin"));
+        assert!(state.is_shutting_down());
+        assert_eq!(state.wait_for(ShutdownPhase::Draining).await, "sigterm");
+        assert!(!closing.is_finished());
+
+        let mut listener = state.subscribe();
+        state.close_connections();
+        assert_eq!(closing.await.unwrap(), "sigterm");
+        assert_eq!(listener.wait_for(ShutdownPhase::Closing).await, "sigterm");
+        // the phase stays reached for the following iterations of a loop
+        assert_eq!(listener.wait_for(ShutdownPhase::Draining).await, "sigterm");
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use ripple_sdk::{
+    api::manifest::device_manifest::TenantsConfiguration, log::warn, utils::error::RippleError,
+};
+use serde::{Deserialize, Serialize};
+
+/// Gateway side counters of a tenant, kept apart from the other tenants so a misbehaving
+/// vendor does not hide in the device wide numbers.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TenantStats {
+    pub tenant_id: String,
+    pub request_count: u64,
+    pub error_count: u64,
+    pub rejected_registrations: u64,
+    pub denied_methods: u64,
+}
+
+/// Enforces the tenants section of the device manifest for service connections, the
+/// registration quota and the method namespaces of each tenant.
+#[derive(Debug, Clone, Default)]
+pub struct TenantState {
+    config: Arc<TenantsConfiguration>,
+    stats: Arc<RwLock<HashMap<String, TenantStats>>>,
+}
+
+impl TenantState {
+    pub fn new(config: TenantsConfiguration) -> Self {
+        Self {
+            config: Arc::new(config),
+            stats: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn get_tenant_id(&self, service_id: &str) -> Option<String> {
+        self.config.get_tenant(service_id).map(|t| t.id.clone())
+    }
+
+    /// Checks the quota of the tenant before a service registers. The service itself is not
+    /// counted against the quota, so a takeover of a connected ServiceId is allowed.
+    pub fn check_registration(
+        &self,
+        service_id: &str,
+        connected_service_ids: &[String],
+    ) -> Result<(), RippleError> {
+        let tenant = match self.config.get_tenant(service_id) {
+            Some(tenant) => tenant,
+            None => return Ok(()),
+        };
+        let max_services = match tenant.max_services {
+            Some(max_services) => max_services,
+            None => return Ok(()),
+        };
+        let connected = connected_service_ids
+            .iter()
+            .filter(|id| !id.eq(&service_id) && id.starts_with(&tenant.service_prefix))
+            .count();
+        if connected < max_services {
+            return Ok(());
+        }
+        warn!(
+            "Service {} rejected, tenant {} reached its quota of {} services",
+            service_id, tenant.id, max_services
+        );
+        self.update_stats(&tenant.id, |stats| stats.rejected_registrations += 1);
+        Err(RippleError::NotAvailable)
+    }
+
+    pub fn is_method_allowed(&self, service_id: &str, method: &str) -> bool {
+        let tenant = match self.config.get_tenant(service_id) {
+            Some(tenant) => tenant,
+            None => return true,
+        };
+        if tenant.is_method_allowed(method) {
+            return true;
+        }
+        warn!(
+            "Method {} is outside the namespaces of tenant {}",
+            method, tenant.id
+        );
+        self.update_stats(&tenant.id, |stats| stats.denied_methods += 1);
+        false
+    }
+
+    /// Records the outcome of a request routed to a service of a tenant
+    pub fn record_request(&self, service_id: &str, success: bool) {
+        if let Some(tenant_id) = self.get_tenant_id(service_id) {
+            self.update_stats(&tenant_id, |stats| {
+                stats.request_count += 1;
+                if !success {
+                    stats.error_count += 1;
+                }
+            });
+        }
+    }
+
+    fn update_stats(&self, tenant_id: &str, update: impl FnOnce(&mut TenantStats)) {
+        let mut stats = self.stats.write().unwrap();
+        let entry = stats
+            .entry(tenant_id.to_owned())
+            .or_insert_with(|| TenantStats {
+                tenant_id: tenant_id.to_owned(),
+                ..Default::default()
+            });
+        update(entry);
+    }
+
+    pub fn get_stats(&self) -> Vec<TenantStats> {
+        let mut stats: Vec<TenantStats> = self.stats.read().unwrap().values().cloned().collect();
+        stats.sort_by(|a, b| a.tenant_id.cmp(&b.tenant_id));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::manifest::device_manifest::TenantConfiguration;
+
+    fn get_state() -> TenantState {
+        TenantState::new(TenantsConfiguration {
+            tenants: vec![TenantConfiguration {
+                id: "vendor_a".into(),
+                service_prefix: "ripple:channel:vendora:".into(),
+                max_services: Some(1),
+                method_namespaces: vec!["vendora".into()],
+            }],
+        })
+    }
+
+    #[test]
+    fn test_check_registration() {
+        let state = get_state();
+        let connected = vec!["ripple:channel:vendora:player".to_string()];
+        assert!(state
+            .check_registration("ripple:channel:vendora:player", &connected)
+            .is_ok());
+        assert_eq!(
+            state.check_registration("ripple:channel:vendora:store", &connected),
+            Err(RippleError::NotAvailable)
+        );
+        assert!(state
+            .check_registration("ripple:channel:gateway:service1", &connected)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_tenant_stats() {
+        let state = get_state();
+        assert!(state.is_method_allowed("ripple:channel:vendora:player", "vendora.play"));
+        assert!(!state.is_method_allowed("ripple:channel:vendora:player", "device.name"));
+        assert!(state.is_method_allowed("ripple:channel:gateway:service1", "device.name"));
+        state.record_request("ripple:channel:vendora:player", true);
+        state.record_request("ripple:channel:vendora:player", false);
+        state.record_request("ripple:channel:gateway:service1", false);
+
+        assert_eq!(
+            state.get_stats(),
+            vec![TenantStats {
+                tenant_id: "vendor_a".into(),
+                request_count: 2,
+                error_count: 1,
+                rejected_registrations: 0,
+                denied_methods: 1,
+            }]
+        );
+    }
+}
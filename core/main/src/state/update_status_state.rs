@@ -0,0 +1,41 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::{Arc, RwLock};
+
+use ripple_sdk::api::device::device_update::UpdateStatus;
+
+/// Last status reported by the platform updater
+#[derive(Debug, Clone, Default)]
+pub struct UpdateStatusState {
+    status: Arc<RwLock<Option<UpdateStatus>>>,
+}
+
+impl UpdateStatusState {
+    pub fn get_status(&self) -> Option<UpdateStatus> {
+        self.status.read().unwrap().clone()
+    }
+
+    /// Stores the status and returns the previous one if it changed
+    pub fn update(&self, status: UpdateStatus) -> Option<Option<UpdateStatus>> {
+        let mut current = self.status.write().unwrap();
+        if current.as_ref() == Some(&status) {
+            return None;
+        }
+        Some(current.replace(status))
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    fs,
+    net::{SocketAddr, SocketAddrV6, TcpListener, ToSocketAddrs},
+    os::fd::{FromRawFd, RawFd},
+    sync::{Mutex, OnceLock},
+};
+
+use ripple_sdk::{log::info, utils::error::RippleError};
+
+/// First file descriptor passed by systemd socket activation
+const SD_LISTEN_FDS_START: RawFd = 3;
+/// Environment of systemd socket activation, removed from the launched services
+pub const SD_LISTEN_ENV: [&str; 3] = ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"];
+
+static INHERITED_LISTENERS: OnceLock<Mutex<HashMap<String, RawFd>>> = OnceLock::new();
+
+/// Resolves a listener address from the device manifest. IPv6 addresses can carry the
+/// interface as scope, either as an index like `[fe80::1%2]:3473` or as a name like
+/// `[fe80::1%eth0]:3473`.
+pub fn resolve_bind_address(address: &str) -> Result<SocketAddr, RippleError> {
+    if let Some((host, port)) = address.strip_prefix('[').and_then(|a| a.split_once("]:")) {
+        if let Some((ip, scope)) = host.split_once('%') {
+            let ip = ip.parse().map_err(|_| RippleError::InvalidInput)?;
+            let port = port.parse().map_err(|_| RippleError::InvalidInput)?;
+            let scope_id = get_scope_id(scope).ok_or(RippleError::InvalidInput)?;
+            return Ok(SocketAddr::V6(SocketAddrV6::new(ip, port, 0, scope_id)));
+        }
+    }
+    address
+        .to_socket_addrs()
+        .map_err(|_| RippleError::InvalidInput)?
+        .next()
+        .ok_or(RippleError::InvalidInput)
+}
+
+fn get_scope_id(scope: &str) -> Option<u32> {
+    if let Ok(scope_id) = scope.parse() {
+        return Some(scope_id);
+    }
+    fs::read_to_string(format!("/sys/class/net/{}/ifindex", scope))
+        .ok()
+        .and_then(|index| index.trim().parse().ok())
+}
+
+/// Gets the file descriptors passed through systemd socket activation by their
+/// `FileDescriptorName`. The descriptors are only used when `LISTEN_PID` is this process.
+fn get_listen_fds(
+    listen_pid: Option<String>,
+    listen_fds: Option<String>,
+    listen_fdnames: Option<String>,
+    pid: u32,
+) -> HashMap<String, RawFd> {
+    let mut fds = HashMap::new();
+    if listen_pid.and_then(|p| p.parse::<u32>().ok()) != Some(pid) {
+        return fds;
+    }
+    let count = listen_fds
+        .and_then(|n| n.parse::<RawFd>().ok())
+        .unwrap_or(0);
+    let names: Vec<String> = listen_fdnames
+        .map(|names| names.split(':').map(|n| n.to_owned()).collect())
+        .unwrap_or_default();
+    for i in 0..count {
+        let name = names
+            .get(i as usize)
+            .cloned()
+            .unwrap_or_else(|| i.to_string());
+        fds.insert(name, SD_LISTEN_FDS_START + i);
+    }
+    fds
+}
+
+fn get_inherited_listeners() -> &'static Mutex<HashMap<String, RawFd>> {
+    INHERITED_LISTENERS.get_or_init(|| {
+        let fds = get_listen_fds(
+            std::env::var("LISTEN_PID").ok(),
+            std::env::var("LISTEN_FDS").ok(),
+            std::env::var("LISTEN_FDNAMES").ok(),
+            std::process::id(),
+        );
+        Mutex::new(fds)
+    })
+}
+
+/// Takes the listening socket with the given name inherited through systemd socket
+/// activation. Each socket can only be taken once.
+pub fn take_inherited_listener(name: &str) -> Option<TcpListener> {
+    let fd = get_inherited_listeners().lock().unwrap().remove(name)?;
+    info!("Using socket {} inherited from systemd fd={}", name, fd);
+    // Safety: systemd hands over the descriptor to this process and it is taken only once
+    let listener = unsafe { TcpListener::from_raw_fd(fd) };
+    listener.set_nonblocking(true).ok()?;
+    Some(listener)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_bind_address() {
+        assert_eq!(
+            resolve_bind_address("127.0.0.1:3473").unwrap(),
+            "127.0.0.1:3473".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            resolve_bind_address("[::1]:3473").unwrap(),
+            "[::1]:3473".parse::<SocketAddr>().unwrap()
+        );
+        match resolve_bind_address("[fe80::1%2]:3473").unwrap() {
+            SocketAddr::V6(addr) => {
+                assert_eq!(addr.scope_id(), 2);
+                assert_eq!(addr.port(), 3473);
+            }
+            _ => panic!("expected an IPv6 address"),
+        }
+        assert!(resolve_bind_address("[fe80::1%unknown-interface]:3473").is_err());
+        assert!(resolve_bind_address("not an address").is_err());
+    }
+
+    #[test]
+    fn test_get_listen_fds() {
+        let fds = get_listen_fds(
+            Some("100".into()),
+            Some("2".into()),
+            Some("firebolt:services".into()),
+            100,
+        );
+        assert_eq!(fds.get("firebolt"), Some(&3));
+        assert_eq!(fds.get("services"), Some(&4));
+
+        // sockets passed to another process are ignored
+        let fds = get_listen_fds(Some("101".into()), Some("2".into()), None, 100);
+        assert!(fds.is_empty());
+
+        let fds = get_listen_fds(Some("100".into()), Some("1".into()), None, 100);
+        assert_eq!(fds.get("0"), Some(&3));
+    }
+}
@@ -28,3 +28,16 @@ pub async fn get_uid(
     }
     Ok(uid)
 }
+
+/// Compares two secrets without returning early on the first mismatching byte, so the
+/// time taken does not reveal how much of a presented token was correct.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= (x ^ y) as usize;
+    }
+    diff == 0
+}
@@ -15,9 +15,12 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+pub mod bind_utils;
 pub mod common;
+pub mod http_utils;
 pub mod router_utils;
 pub mod rpc_utils;
+pub mod runtime_topology;
 pub mod serde_utils;
 
 #[cfg(test)]
@@ -0,0 +1,145 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{future::Future, sync::Arc};
+
+use ripple_sdk::{
+    api::manifest::device_manifest::{DedicatedRuntimeConfiguration, RuntimeTopologyConfiguration},
+    log::{error, info},
+    tokio::{
+        self,
+        runtime::{Builder, EnterGuard, Handle, Runtime},
+        task::JoinHandle,
+    },
+};
+
+/// Dedicated runtime shared by the clones of the [RuntimeTopology]. Dropping a runtime from
+/// async context panics, so it is shut down in the background with the last clone.
+#[derive(Debug)]
+struct DedicatedRuntime {
+    handle: Handle,
+    runtime: Option<Runtime>,
+}
+
+impl Drop for DedicatedRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Dedicated runtimes configured in the device manifest.
+///
+/// Brokers and the forwarder spawn their tasks with `tokio::spawn`, so they are moved by
+/// entering the runtime while they are started.
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeTopology {
+    websocket: Option<Arc<DedicatedRuntime>>,
+    broker: Option<Arc<DedicatedRuntime>>,
+    transform: Option<Arc<DedicatedRuntime>>,
+}
+
+impl RuntimeTopology {
+    pub fn new(config: &RuntimeTopologyConfiguration) -> Self {
+        Self {
+            websocket: Self::build("websocket", config.websocket.as_ref()),
+            broker: Self::build("broker", config.broker.as_ref()),
+            transform: Self::build("transform", config.transform.as_ref()),
+        }
+    }
+
+    fn build(
+        name: &str,
+        config: Option<&DedicatedRuntimeConfiguration>,
+    ) -> Option<Arc<DedicatedRuntime>> {
+        let config = config?;
+        match Builder::new_multi_thread()
+            .worker_threads(config.worker_threads.max(1))
+            .thread_name(format!("ripple-{}", name))
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => {
+                info!(
+                    "Started {} runtime with {} worker threads",
+                    name, config.worker_threads
+                );
+                Some(Arc::new(DedicatedRuntime {
+                    handle: runtime.handle().clone(),
+                    runtime: Some(runtime),
+                }))
+            }
+            Err(e) => {
+                error!(
+                    "Failed to build {} runtime, using the main runtime {:?}",
+                    name, e
+                );
+                None
+            }
+        }
+    }
+
+    fn spawn<F>(runtime: Option<&Arc<DedicatedRuntime>>, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        match runtime {
+            Some(runtime) => runtime.handle.spawn(future),
+            None => tokio::spawn(future),
+        }
+    }
+
+    pub fn spawn_websocket<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        Self::spawn(self.websocket.as_ref(), future)
+    }
+
+    pub fn enter_broker(&self) -> Option<EnterGuard<'_>> {
+        self.broker.as_ref().map(|r| r.handle.enter())
+    }
+
+    pub fn enter_transform(&self) -> Option<EnterGuard<'_>> {
+        self.transform.as_ref().map(|r| r.handle.enter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_runtime_topology() {
+        let topology = RuntimeTopology::new(&RuntimeTopologyConfiguration {
+            websocket: Some(DedicatedRuntimeConfiguration::default()),
+            ..Default::default()
+        });
+        assert!(topology.enter_broker().is_none());
+
+        let name = topology
+            .spawn_websocket(async { std::thread::current().name().map(String::from) })
+            .await
+            .unwrap();
+        assert_eq!(name, Some("ripple-websocket".into()));
+        // the runtime is shut down without panicking in async context
+        drop(topology);
+    }
+}
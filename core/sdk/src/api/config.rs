@@ -15,19 +15,23 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::OnceLock};
 
-use serde::{Deserialize, Serialize};
+use flate2::Crc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
-    extn::extn_client_message::{ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse},
+    extn::extn_client_message::{
+        ExtnEvent, ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse,
+    },
     framework::ripple_contract::RippleContract,
+    utils::{error::RippleError, schema_utils::get_schema},
 };
 
 use super::manifest::{
     app_library::AppLibraryState,
-    device_manifest::{IdSalt, LifecyclePolicy, RetentionPolicy},
+    device_manifest::{DefaultValues, IdSalt, LifecyclePolicy, RetentionPolicy, RippleFeatures},
 };
 
 use super::manifest::device_manifest::AppLibraryEntry;
@@ -67,6 +71,7 @@ pub enum Config {
     SupportsDistributorSession,
     Firebolt,
     RFC(String),
+    Section(ConfigSectionRequest),
 }
 
 impl ExtnPayloadProvider for Config {
@@ -120,6 +125,139 @@ pub enum ConfigResponse {
     List(Vec<String>),
     AllApps(Vec<AppLibraryEntry>),
     IdSalt(IdSalt),
+    Section(ConfigSectionResponse),
+}
+
+/// Named configuration sections served by the typed config API
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSection {
+    Features,
+    DefaultValues,
+    Launcher,
+}
+
+impl ConfigSection {
+    /// Schema hash of the section payload in this build of the sdk, generated once
+    pub fn schema_hash(&self) -> String {
+        static SCHEMA_HASHES: OnceLock<HashMap<ConfigSection, String>> = OnceLock::new();
+        SCHEMA_HASHES
+            .get_or_init(|| {
+                HashMap::from([
+                    (Self::Features, get_schema_hash::<RippleFeatures>()),
+                    (Self::DefaultValues, get_schema_hash::<DefaultValues>()),
+                    (Self::Launcher, get_schema_hash::<LauncherConfig>()),
+                ])
+            })
+            .get(self)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Hash of the schema generated from the fields and types of the section payload, so an
+/// extension built against a different payload is refused even if the version was not bumped
+fn get_schema_hash<T: ConfigSectionValue>() -> String {
+    let mut crc = Crc::new();
+    crc.update(format!("{:?}:{}", T::SECTION, T::SCHEMA_VERSION).as_bytes());
+    for entry in get_schema::<T>() {
+        crc.update(entry.as_bytes());
+        crc.update(b"\n");
+    }
+    format!("{:08x}", crc.sum())
+}
+
+/// Typed payload of a [ConfigSection]. The schema version has to be bumped whenever the
+/// payload changes in a way which older extensions cannot decode.
+pub trait ConfigSectionValue: Serialize + DeserializeOwned {
+    const SECTION: ConfigSection;
+    const SCHEMA_VERSION: u32;
+
+    fn schema_hash() -> String {
+        Self::SECTION.schema_hash()
+    }
+}
+
+impl ConfigSectionValue for RippleFeatures {
+    const SECTION: ConfigSection = ConfigSection::Features;
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl ConfigSectionValue for DefaultValues {
+    const SECTION: ConfigSection = ConfigSection::DefaultValues;
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl ConfigSectionValue for LauncherConfig {
+    const SECTION: ConfigSection = ConfigSection::Launcher;
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+/// Request for a config section along with the schema hash the extension was built with.
+/// Subscribed extensions receive a [ConfigInvalidation] whenever the section changes.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ConfigSectionRequest {
+    pub section: ConfigSection,
+    pub schema_hash: String,
+    #[serde(default)]
+    pub subscribe: bool,
+}
+
+impl ConfigSectionRequest {
+    pub fn new<T: ConfigSectionValue>(subscribe: bool) -> Self {
+        Self {
+            section: T::SECTION,
+            schema_hash: T::schema_hash(),
+            subscribe,
+        }
+    }
+
+    pub fn is_compatible(&self) -> bool {
+        self.section.schema_hash().eq(&self.schema_hash)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ConfigSectionResponse {
+    pub section: ConfigSection,
+    pub schema_hash: String,
+    pub revision: u64,
+    pub value: Value,
+}
+
+impl ConfigSectionResponse {
+    pub fn get_value<T: ConfigSectionValue>(&self) -> Result<T, RippleError> {
+        if self.section != T::SECTION || !self.schema_hash.eq(&T::schema_hash()) {
+            return Err(RippleError::InvalidInput);
+        }
+        serde_json::from_value(self.value.clone()).map_err(|_| RippleError::ParseError)
+    }
+}
+
+/// Event sent to the extensions subscribed to a config section when the section changes
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigInvalidation {
+    pub config_section: ConfigSection,
+    pub revision: u64,
+}
+
+impl ExtnPayloadProvider for ConfigInvalidation {
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Event(ExtnEvent::Value(serde_json::to_value(self).unwrap()))
+    }
+
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Event(ExtnEvent::Value(value)) = payload {
+            return serde_json::from_value(value).ok();
+        }
+
+        None
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::Config
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -154,7 +292,7 @@ impl ExtnPayloadProvider for LauncherConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::api::manifest::device_manifest::{AppManifestLoad, BootState};
+    use crate::api::manifest::device_manifest::{AppManifestLoad, BootState, SessionPriority};
     use crate::utils::test_utils::test_extn_payload_provider;
     use std::collections::HashMap;
 
@@ -164,6 +302,52 @@ mod tests {
         test_extn_payload_provider(Config::DefaultValues, contract_type);
     }
 
+    #[test]
+    fn test_config_section_schema() {
+        let request = ConfigSectionRequest::new::<LauncherConfig>(true);
+        assert_eq!(request.section, ConfigSection::Launcher);
+        assert!(request.is_compatible());
+
+        let stale = ConfigSectionRequest {
+            schema_hash: "00000000".into(),
+            ..request
+        };
+        assert!(!stale.is_compatible());
+
+        // the hash covers the payload down to the nested types
+        let schema = get_schema::<LauncherConfig>();
+        assert!(schema
+            .iter()
+            .any(|e| e.starts_with("struct RetentionPolicy")));
+        assert!(schema
+            .iter()
+            .any(|e| e.starts_with("struct AppLibraryEntry")));
+        assert_ne!(
+            ConfigSection::Launcher.schema_hash(),
+            ConfigSection::Features.schema_hash()
+        );
+
+        let response = ConfigSectionResponse {
+            section: ConfigSection::Launcher,
+            schema_hash: LauncherConfig::schema_hash(),
+            revision: 1,
+            value: serde_json::json!({}),
+        };
+        assert_eq!(
+            response.get_value::<RippleFeatures>().unwrap_err(),
+            RippleError::InvalidInput
+        );
+    }
+
+    #[test]
+    fn test_extn_payload_provider_for_config_invalidation() {
+        let invalidation = ConfigInvalidation {
+            config_section: ConfigSection::Features,
+            revision: 2,
+        };
+        test_extn_payload_provider(invalidation, RippleContract::Config);
+    }
+
     #[test]
     fn test_extn_payload_provider_for_rfc_request() {
         let rfc_request = RfcRequest {
@@ -192,6 +376,7 @@ mod tests {
                         "https://example.com/app1/manifest".to_string(),
                     ),
                     boot_state: BootState::Inactive,
+                    session_priority: SessionPriority::Normal,
                 }],
                 providers: HashMap::new(),
             },
@@ -18,7 +18,7 @@
 use crate::{
     extn::{
         extn_client_message::{
-            ExtnEvent, ExtnMessage, ExtnPayload, ExtnPayloadProvider, ExtnRequest,
+            ExtnEvent, ExtnMessage, ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse,
         },
         extn_id::ExtnId,
     },
@@ -55,6 +55,10 @@ impl From<AccountToken> for ActivationStatus {
 
 // Instead of we chosing the default value, we make them as optional
 // This enables us to differentiate from the default value to actual value
+//
+// Every change made by main bumps the version. Main propagates deltas which only carry the
+// changed member, receivers which detect a gap in the versions request a full snapshot
+// using the [ContextSnapshotRequest].
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct RippleContext {
     pub activation_status: Option<ActivationStatus>,
@@ -63,6 +67,18 @@ pub struct RippleContext {
     pub time_zone: Option<TimeZone>,
     pub update_type: Option<RippleContextUpdateType>,
     pub features: Vec<String>,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default)]
+    pub delta: bool,
+}
+
+/// Result of applying a context received from main
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextApplyResult {
+    Applied,
+    Stale,
+    Gap,
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -91,6 +107,8 @@ impl RippleContext {
             time_zone,
             update_type,
             features,
+            version: 0,
+            delta: false,
         }
     }
 
@@ -122,6 +140,14 @@ impl RippleContext {
     }
 
     pub fn update(&mut self, request: RippleContextUpdateRequest) -> bool {
+        let changed = self.update_members(request);
+        if changed {
+            self.version += 1;
+        }
+        changed
+    }
+
+    fn update_members(&mut self, request: RippleContextUpdateRequest) -> bool {
         match request {
             RippleContextUpdateRequest::Activation(a) => {
                 let activation_status: ActivationStatus = a.into();
@@ -207,6 +233,60 @@ impl RippleContext {
         self.internet_connectivity = context.internet_connectivity;
         self.time_zone = context.time_zone;
         self.features = context.features;
+        self.version = context.version;
+    }
+
+    /// Returns a copy which only carries the member changed by the last update
+    pub fn get_delta(&self) -> RippleContext {
+        let mut delta = RippleContext {
+            update_type: self.update_type.clone(),
+            version: self.version,
+            delta: true,
+            ..Default::default()
+        };
+        if let Some(update_type) = &self.update_type {
+            match update_type {
+                RippleContextUpdateType::ActivationStatusChanged
+                | RippleContextUpdateType::TokenChanged => {
+                    delta.activation_status = self.activation_status.clone()
+                }
+                RippleContextUpdateType::InternetConnectionChanged => {
+                    delta.internet_connectivity = self.internet_connectivity.clone()
+                }
+                RippleContextUpdateType::FeaturesChanged => delta.features = self.features.clone(),
+                RippleContextUpdateType::PowerStateChanged => {
+                    delta.system_power_state = self.system_power_state.clone()
+                }
+                RippleContextUpdateType::TimeZoneChanged => {
+                    delta.time_zone = self.time_zone.clone()
+                }
+            }
+        }
+        delta
+    }
+
+    /// Applies a snapshot or a delta received from main. Contexts older than the current
+    /// version are ignored, a delta which skips a version is not applied as the receiver
+    /// has missed an update and needs a snapshot.
+    pub fn apply(&mut self, context: RippleContext) -> ContextApplyResult {
+        if self.version > 0 && context.version <= self.version {
+            return ContextApplyResult::Stale;
+        }
+        if context.delta {
+            if context.version != self.version + 1 {
+                return ContextApplyResult::Gap;
+            }
+            self.update_with_context(&context);
+            self.version = context.version;
+        } else {
+            self.system_power_state = context.system_power_state.clone();
+            self.deep_copy(context);
+        }
+        ContextApplyResult::Applied
+    }
+
+    pub fn is_at_least(&self, version: u64) -> bool {
+        self.version >= version
     }
 
     pub fn get_event_message(&self) -> ExtnMessage {
@@ -220,6 +300,10 @@ impl RippleContext {
         }
     }
 
+    pub fn get_delta_event_message(&self) -> ExtnMessage {
+        self.get_delta().get_event_message()
+    }
+
     pub fn what_changed(&self, context: &RippleContext) -> RippleContextUpdateType {
         if self.internet_connectivity != context.internet_connectivity {
             RippleContextUpdateType::InternetConnectionChanged
@@ -249,6 +333,52 @@ impl ExtnPayloadProvider for RippleContext {
     }
 }
 
+/// Request to main for the full context at or newer than the given version
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ContextSnapshotRequest {
+    pub min_version: u64,
+}
+
+impl ExtnPayloadProvider for ContextSnapshotRequest {
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Request(ExtnRequest::ContextSnapshot(self.clone()))
+    }
+
+    fn get_from_payload(payload: ExtnPayload) -> Option<ContextSnapshotRequest> {
+        if let ExtnPayload::Request(ExtnRequest::ContextSnapshot(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::RippleContext
+    }
+}
+
+/// Full context returned for a [ContextSnapshotRequest]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ContextSnapshot(pub RippleContext);
+
+impl ExtnPayloadProvider for ContextSnapshot {
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Response(ExtnResponse::Context(self.0.clone()))
+    }
+
+    fn get_from_payload(payload: ExtnPayload) -> Option<ContextSnapshot> {
+        if let ExtnPayload::Response(ExtnResponse::Context(r)) = payload {
+            return Some(ContextSnapshot(r));
+        }
+
+        None
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::RippleContext
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FeatureUpdate {
     name: String,
@@ -336,6 +466,8 @@ mod tests {
             }),
             update_type: None,
             features: Vec::default(),
+            version: 0,
+            delta: false,
         };
 
         let context2 = RippleContext {
@@ -348,6 +480,8 @@ mod tests {
             }),
             update_type: None,
             features: Vec::default(),
+            version: 0,
+            delta: false,
         };
 
         assert_eq!(
@@ -356,6 +490,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ripple_context_versioning() {
+        let mut main_context = RippleContext::default();
+        let mut extn_context = RippleContext::default();
+        assert!(main_context.update(RippleContextUpdateRequest::Activation(true)));
+        assert!(!main_context.update(RippleContextUpdateRequest::Activation(true)));
+        assert_eq!(main_context.version, 1);
+
+        let delta = main_context.get_delta();
+        assert!(delta.delta);
+        assert_eq!(
+            extn_context.apply(delta.clone()),
+            ContextApplyResult::Applied
+        );
+        assert_eq!(
+            extn_context.activation_status,
+            Some(ActivationStatus::Activated)
+        );
+        assert_eq!(extn_context.apply(delta), ContextApplyResult::Stale);
+
+        // reactivation while the extension missed an update
+        main_context.update(RippleContextUpdateRequest::Activation(false));
+        main_context.update(RippleContextUpdateRequest::Activation(true));
+        assert_eq!(
+            extn_context.apply(main_context.get_delta()),
+            ContextApplyResult::Gap
+        );
+        assert!(!extn_context.is_at_least(3));
+        assert_eq!(
+            extn_context.apply(main_context.clone()),
+            ContextApplyResult::Applied
+        );
+        assert!(extn_context.is_at_least(3));
+    }
+
     #[test]
     fn test_extn_request_ripple_context_update() {
         let activation_request = RippleContextUpdateRequest::Activation(true);
@@ -378,6 +547,8 @@ mod tests {
             }),
             update_type: None,
             features: Vec::default(),
+            version: 0,
+            delta: false,
         };
 
         let contract_type: RippleContract = RippleContract::RippleContext;
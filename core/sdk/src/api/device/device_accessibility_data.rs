@@ -97,6 +97,46 @@ pub struct AudioDescriptionSettingsSet {
     pub value: bool,
 }
 
+pub const MAGNIFICATION_SCALE_DEFAULT: f32 = 1.0;
+pub const MAGNIFICATION_SCALE_RANGE: std::ops::RangeInclusive<f32> = 1.0..=4.0;
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HighContrastSettings {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MagnificationSettings {
+    pub enabled: bool,
+    pub scale: f32,
+}
+
+impl Default for MagnificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scale: MAGNIFICATION_SCALE_DEFAULT,
+        }
+    }
+}
+
+/// Partial update for the magnification settings, fields which are not provided keep their
+/// current value.
+#[derive(Default, Debug, Deserialize, Clone)]
+pub struct MagnificationSettingsSet {
+    pub enabled: Option<bool>,
+    pub scale: Option<f32>,
+}
+
+impl MagnificationSettingsSet {
+    pub fn is_valid(&self) -> bool {
+        match self.scale {
+            Some(scale) => MAGNIFICATION_SCALE_RANGE.contains(&scale),
+            None => true,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,4 +158,16 @@ mod tests {
         let serialized_str = String::from_utf8(buf).unwrap();
         assert_eq!(serialized_str, expected_output);
     }
+
+    #[test]
+    fn test_magnification_settings_set_is_valid() {
+        let set = |scale| MagnificationSettingsSet {
+            enabled: None,
+            scale,
+        };
+        assert!(set(None).is_valid());
+        assert!(set(Some(2.5)).is_valid());
+        assert!(!set(Some(0.5)).is_valid());
+        assert!(!set(Some(10.0)).is_valid());
+    }
 }
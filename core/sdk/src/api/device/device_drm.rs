@@ -0,0 +1,131 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::api::manifest::device_manifest::DrmSystemConfiguration;
+
+/// Prefix of the capability which guards the license requests of a DRM system, e.g.
+/// `protected-content:widevine`
+pub const DRM_CAPABILITY_PREFIX: &str = "protected-content:";
+
+/// DRM system supported by the device along with its security level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrmInfo {
+    pub system: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security_level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// License request of a player which is decorated before it is sent to the license server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LicenseRequest {
+    pub system: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+impl LicenseRequest {
+    /// Applies the proxy url and headers configured for the DRM system. Headers reserved for
+    /// the distributor are dropped from the request of the app first, so an app can neither
+    /// supply nor shadow them. Without a proxy the headers are only added when the url of the
+    /// app points to one of the allowed hosts.
+    pub fn apply(mut self, config: &DrmSystemConfiguration) -> Self {
+        self.headers
+            .retain(|name, _| !config.is_reserved_header(name));
+        let allowed = match &config.proxy_url {
+            Some(proxy_url) => {
+                self.url = proxy_url.clone();
+                true
+            }
+            None => url::Url::parse(&self.url)
+                .ok()
+                .and_then(|url| url.host_str().map(|host| config.is_allowed_host(host)))
+                .unwrap_or(false),
+        };
+        if allowed {
+            self.headers.extend(config.headers.clone());
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_license_request_apply() {
+        let request = LicenseRequest {
+            system: "widevine".into(),
+            url: "https://license.example.com".into(),
+            headers: HashMap::from([
+                ("X-Entitlement".to_owned(), "app".to_owned()),
+                ("x-partner-token".to_owned(), "app".to_owned()),
+                ("x-app".to_owned(), "app".to_owned()),
+            ]),
+        };
+        let config = DrmSystemConfiguration {
+            proxy_url: Some("https://proxy.example.com".into()),
+            headers: HashMap::from([("x-entitlement".to_owned(), "distributor".to_owned())]),
+            hook: false,
+            strip_headers: vec!["X-Partner-Token".to_owned()],
+            ..Default::default()
+        };
+        let decorated = request.clone().apply(&config);
+        assert_eq!(decorated.url, "https://proxy.example.com");
+        assert_eq!(
+            decorated.headers,
+            HashMap::from([
+                ("x-entitlement".to_owned(), "distributor".to_owned()),
+                ("x-app".to_owned(), "app".to_owned()),
+            ])
+        );
+
+        assert_eq!(request.clone().apply(&Default::default()), request);
+    }
+
+    #[test]
+    fn test_license_request_apply_without_proxy() {
+        let request = LicenseRequest {
+            system: "widevine".into(),
+            url: "https://License.example.com/widevine".into(),
+            headers: HashMap::new(),
+        };
+        let mut config = DrmSystemConfiguration {
+            headers: HashMap::from([("x-entitlement".to_owned(), "distributor".to_owned())]),
+            ..Default::default()
+        };
+        // headers never go to a license server chosen by the app
+        assert_eq!(request.clone().apply(&config), request);
+
+        config.allowed_hosts = vec!["license.example.com".to_owned()];
+        let decorated = request.clone().apply(&config);
+        assert_eq!(decorated.url, request.url);
+        assert_eq!(
+            decorated.headers,
+            HashMap::from([("x-entitlement".to_owned(), "distributor".to_owned())])
+        );
+    }
+}
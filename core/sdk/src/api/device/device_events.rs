@@ -19,7 +19,7 @@ use std::str::FromStr;
 
 use crate::{
     api::session::EventAdjective,
-    extn::extn_client_message::{ExtnPayload, ExtnPayloadProvider, ExtnRequest},
+    extn::extn_client_message::{ExtnEvent, ExtnPayload, ExtnPayloadProvider, ExtnRequest},
     framework::ripple_contract::RippleContract,
 };
 use serde::{Deserialize, Serialize};
@@ -33,6 +33,7 @@ pub const AUDIO_CHANGED_EVENT: &str = "device.onAudioChanged";
 pub const VOICE_GUIDANCE_SETTINGS_CHANGED: &str = "accessibility.onVoiceGuidanceSettingsChanged";
 pub const VOICE_GUIDANCE_ENABLED_CHANGED: &str = "voiceguidance.onEnabledChanged";
 pub const VOICE_GUIDANCE_SPEED_CHANGED: &str = "voiceguidance.onSpeedChanged";
+pub const INACTIVITY_CHANGED_EVENT: &str = "device.onInactivityChanged";
 
 // Is this from the device to thunder event handler???
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -53,6 +54,37 @@ impl FromStr for DeviceEvent {
     }
 }
 
+/// User input reported by the device extension, used by Main for idle detection
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UserActivity {
+    pub source: String,
+}
+
+impl ExtnPayloadProvider for UserActivity {
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Event(ExtnEvent::UserActivity(self.clone()))
+    }
+
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Event(ExtnEvent::UserActivity(a)) = payload {
+            return Some(a);
+        }
+
+        None
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::DeviceEvents(EventAdjective::Input)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InactivityStatus {
+    pub idle: bool,
+    pub idle_seconds: u64,
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum DeviceEventCallback {
     FireboltAppEvent(String),
@@ -133,4 +165,13 @@ mod tests {
         let contract_type: RippleContract = RippleContract::DeviceEvents(EventAdjective::Input);
         test_extn_payload_provider(device_event_request, contract_type);
     }
+
+    #[test]
+    fn test_extn_event_user_activity() {
+        let user_activity = UserActivity {
+            source: "remote".to_string(),
+        };
+        let contract_type: RippleContract = RippleContract::DeviceEvents(EventAdjective::Input);
+        test_extn_payload_provider(user_activity, contract_type);
+    }
 }
@@ -0,0 +1,103 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::session::EventAdjective,
+    extn::extn_client_message::{ExtnEvent, ExtnPayload, ExtnPayloadProvider},
+    framework::ripple_contract::RippleContract,
+};
+
+pub const INPUT_KEYS_CAPABILITY: &str = "input:keys";
+pub const INPUT_KEY_EVENT: &str = "input.onKey";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyAction {
+    Pressed,
+    Released,
+    Repeat,
+}
+
+/// Remote key reported by the device extension with the platform key code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawKeyEvent {
+    pub code: String,
+    pub action: KeyAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+}
+
+impl ExtnPayloadProvider for RawKeyEvent {
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Event(ExtnEvent::RawKey(self.clone()))
+    }
+
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Event(ExtnEvent::RawKey(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::DeviceEvents(EventAdjective::RemoteKey)
+    }
+}
+
+/// Remote key forwarded to subscribed apps after the key map from the device manifest
+/// was applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyEvent {
+    pub key: String,
+    pub code: String,
+    pub action: KeyAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+}
+
+impl KeyEvent {
+    pub fn new(key: String, raw: RawKeyEvent) -> Self {
+        Self {
+            key,
+            code: raw.code,
+            action: raw.action,
+            device: raw.device,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::test_extn_payload_provider;
+
+    #[test]
+    fn test_extn_event_raw_key() {
+        let raw_key = RawKeyEvent {
+            code: "0x1c".to_string(),
+            action: KeyAction::Pressed,
+            device: None,
+        };
+        let contract_type: RippleContract = RippleContract::DeviceEvents(EventAdjective::RemoteKey);
+        test_extn_payload_provider(raw_key, contract_type);
+    }
+}
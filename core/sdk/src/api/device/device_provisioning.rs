@@ -0,0 +1,148 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    extn::extn_client_message::{ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse},
+    framework::ripple_contract::RippleContract,
+    utils::error::RippleError,
+};
+
+pub const PROVISIONING_CAPABILITY: &str = "device:provisioning";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallCertificateParams {
+    pub slot: String,
+    /// PEM encoded device certificate
+    pub certificate: String,
+    #[serde(default)]
+    pub chain: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateIdentityKeyParams {
+    pub key_id: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    pub slot: String,
+    pub fingerprint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityKeyInfo {
+    pub key_id: String,
+    pub public_key: String,
+    pub version: u32,
+}
+
+/// Storage for the device certificates and identity keys. Private key material never leaves
+/// the secure element, only the public parts are returned.
+#[async_trait]
+pub trait SecureElement: Send + Sync {
+    async fn install_certificate(
+        &self,
+        params: InstallCertificateParams,
+    ) -> Result<CertificateInfo, RippleError>;
+    async fn rotate_identity_key(
+        &self,
+        params: RotateIdentityKeyParams,
+    ) -> Result<IdentityKeyInfo, RippleError>;
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub enum ProvisioningRequest {
+    InstallCertificate(InstallCertificateParams),
+    RotateIdentityKey(RotateIdentityKeyParams),
+}
+
+impl ExtnPayloadProvider for ProvisioningRequest {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Request(ExtnRequest::Provisioning(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Request(ExtnRequest::Provisioning(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::SecureElement
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub enum ProvisioningResponse {
+    Certificate(CertificateInfo),
+    IdentityKey(IdentityKeyInfo),
+}
+
+impl ExtnPayloadProvider for ProvisioningResponse {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Response(ExtnResponse::Provisioning(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Response(ExtnResponse::Provisioning(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::SecureElement
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::test_extn_payload_provider;
+
+    #[test]
+    fn test_extn_request_provisioning() {
+        let request = ProvisioningRequest::InstallCertificate(InstallCertificateParams {
+            slot: "device".to_string(),
+            certificate: "-----BEGIN CERTIFICATE-----".to_string(),
+            chain: Vec::new(),
+        });
+        test_extn_payload_provider(request, RippleContract::SecureElement);
+    }
+
+    #[test]
+    fn test_extn_response_provisioning() {
+        let response = ProvisioningResponse::IdentityKey(IdentityKeyInfo {
+            key_id: "identity".to_string(),
+            public_key: "public".to_string(),
+            version: 2,
+        });
+        test_extn_payload_provider(response, RippleContract::SecureElement);
+    }
+}
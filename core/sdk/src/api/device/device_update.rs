@@ -0,0 +1,146 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    extn::extn_client_message::{
+        ExtnEvent, ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse,
+    },
+    framework::ripple_contract::RippleContract,
+};
+
+pub const UPDATE_STATUS_CHANGED_EVENT: &str = "device.onUpdateStatusChanged";
+pub const REBOOT_PENDING_EVENT: &str = "device.onRebootPending";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SystemUpdateState {
+    #[default]
+    Idle,
+    Downloading,
+    ReadyToInstall,
+    Installing,
+    Failed,
+}
+
+/// Status of the platform updater, sent by the updater extension as an event whenever it
+/// changes. `reboot_in_ms` is set once the updater has scheduled a reboot.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateStatus {
+    pub state: SystemUpdateState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reboot_in_ms: Option<u64>,
+}
+
+impl UpdateStatus {
+    pub fn is_reboot_pending(&self) -> bool {
+        self.reboot_in_ms.is_some()
+            && matches!(
+                self.state,
+                SystemUpdateState::ReadyToInstall | SystemUpdateState::Installing
+            )
+    }
+}
+
+/// Warning sent to apps before an update triggered reboot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebootPending {
+    pub reason: String,
+    pub reboot_in_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SystemUpdateRequest {
+    GetStatus,
+}
+
+impl ExtnPayloadProvider for SystemUpdateRequest {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Request(ExtnRequest::SystemUpdate(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Request(ExtnRequest::SystemUpdate(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::SystemUpdate
+    }
+}
+
+impl ExtnPayloadProvider for UpdateStatus {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        match payload {
+            ExtnPayload::Response(ExtnResponse::UpdateStatus(v))
+            | ExtnPayload::Event(ExtnEvent::UpdateStatus(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Event(ExtnEvent::UpdateStatus(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::SystemUpdate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::test_extn_payload_provider;
+
+    #[test]
+    fn test_extn_request_system_update() {
+        test_extn_payload_provider(SystemUpdateRequest::GetStatus, RippleContract::SystemUpdate);
+    }
+
+    #[test]
+    fn test_update_status() {
+        let status: UpdateStatus = serde_json::from_value(serde_json::json!({
+            "state": "readyToInstall",
+            "version": "1.2.0",
+            "rebootInMs": 30000
+        }))
+        .unwrap();
+        assert!(status.is_reboot_pending());
+        test_extn_payload_provider(status, RippleContract::SystemUpdate);
+
+        let status = UpdateStatus {
+            state: SystemUpdateState::Downloading,
+            progress: Some(40),
+            ..Default::default()
+        };
+        assert!(!status.is_reboot_pending());
+    }
+}
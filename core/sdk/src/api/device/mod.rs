@@ -18,10 +18,14 @@
 pub mod device_accessibility_data;
 pub mod device_accessory;
 pub mod device_browser;
+pub mod device_drm;
 pub mod device_events;
 pub mod device_info_request;
+pub mod device_input;
 pub mod device_peristence;
+pub mod device_provisioning;
 pub mod device_request;
+pub mod device_update;
 pub mod device_user_grants_data;
 pub mod device_wifi;
 pub mod device_window_manager;
@@ -0,0 +1,144 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::session::ProvisionRequest,
+    extn::extn_client_message::{ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse},
+    framework::ripple_contract::RippleContract,
+};
+
+pub const ACTIVATION_EVENT_ON_PROGRESS: &str = "device.onActivationProgress";
+
+/// Steps of the power-on activation workflow in the order they are performed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivationStep {
+    #[default]
+    Unactivated,
+    Provisioning,
+    TokenAcquisition,
+    Entitled,
+}
+
+impl ActivationStep {
+    pub fn next(&self) -> ActivationStep {
+        match self {
+            ActivationStep::Unactivated => ActivationStep::Provisioning,
+            ActivationStep::Provisioning => ActivationStep::TokenAcquisition,
+            ActivationStep::TokenAcquisition | ActivationStep::Entitled => ActivationStep::Entitled,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        matches!(self, ActivationStep::Entitled)
+    }
+}
+
+/// Progress of the activation workflow sent to the UI app
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivationProgress {
+    pub step: ActivationStep,
+    pub attempt: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub failed: bool,
+}
+
+/// Requests which the distributor fulfills during activation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ActivationRequest {
+    Provision,
+}
+
+impl ExtnPayloadProvider for ActivationRequest {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Request(ExtnRequest::Activation(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Request(ExtnRequest::Activation(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::Activation
+    }
+}
+
+impl ExtnPayloadProvider for ProvisionRequest {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Response(ExtnResponse::Provision(v)) = payload {
+            return Some(v);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Response(ExtnResponse::Provision(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::Activation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::test_extn_payload_provider;
+
+    #[test]
+    fn test_activation_step_order() {
+        let mut step = ActivationStep::default();
+        let mut steps = vec![step];
+        while !step.is_complete() {
+            step = step.next();
+            steps.push(step);
+        }
+        assert_eq!(
+            steps,
+            vec![
+                ActivationStep::Unactivated,
+                ActivationStep::Provisioning,
+                ActivationStep::TokenAcquisition,
+                ActivationStep::Entitled
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extn_request_activation() {
+        test_extn_payload_provider(ActivationRequest::Provision, RippleContract::Activation);
+    }
+
+    #[test]
+    fn test_extn_response_provision() {
+        let provision = ProvisionRequest {
+            account_id: "test_account_id".to_string(),
+            device_id: "test_device_id".to_string(),
+            distributor_id: None,
+        };
+        test_extn_payload_provider(provision, RippleContract::Activation);
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+use crate::{
+    api::firebolt::fb_advertising::{AdConfigRequestParams, AdConfigResponse},
+    extn::extn_client_message::{ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse},
+    framework::ripple_contract::RippleContract,
+};
+
+impl ExtnPayloadProvider for AdConfigRequestParams {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Request(ExtnRequest::AdConfig(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Request(ExtnRequest::AdConfig(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::Advertising
+    }
+}
+
+impl ExtnPayloadProvider for AdConfigResponse {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Response(ExtnResponse::AdConfig(v)) = payload {
+            return Some(v);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Response(ExtnResponse::AdConfig(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::Advertising
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::session::AccountSession, utils::test_utils::test_extn_payload_provider};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_extn_request_ad_config() {
+        let request = AdConfigRequestParams {
+            privacy_data: HashMap::from([("lmt".to_owned(), "0".to_owned())]),
+            durable_app_id: "app1".to_owned(),
+            dist_session: AccountSession {
+                id: "test_session_id".to_string(),
+                token: "test_token".to_string(),
+                account_id: "test_account_id".to_string(),
+                device_id: "test_device_id".to_string(),
+            },
+            environment: "prod".to_owned(),
+            scope: HashMap::new(),
+        };
+        test_extn_payload_provider(request, RippleContract::Advertising);
+    }
+
+    #[test]
+    fn test_extn_response_ad_config() {
+        test_extn_payload_provider(AdConfigResponse::default(), RippleContract::Advertising);
+    }
+}
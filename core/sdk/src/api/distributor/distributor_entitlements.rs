@@ -0,0 +1,96 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{firebolt::fb_discovery::EntitlementData, session::AccountSession},
+    extn::extn_client_message::{ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse},
+    framework::ripple_contract::RippleContract,
+};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct EntitlementsRequest {
+    pub session: AccountSession,
+}
+
+impl ExtnPayloadProvider for EntitlementsRequest {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Request(ExtnRequest::Entitlements(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Request(ExtnRequest::Entitlements(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::Entitlements
+    }
+}
+
+pub type EntitlementsResponse = Vec<EntitlementData>;
+
+impl ExtnPayloadProvider for EntitlementsResponse {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Response(ExtnResponse::Entitlements(v)) = payload {
+            return Some(v);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Response(ExtnResponse::Entitlements(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::Entitlements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::test_extn_payload_provider;
+
+    #[test]
+    fn test_extn_request_entitlements() {
+        let entitlements_request = EntitlementsRequest {
+            session: AccountSession {
+                id: "test_session_id".to_string(),
+                token: "test_token".to_string(),
+                account_id: "test_account_id".to_string(),
+                device_id: "test_device_id".to_string(),
+            },
+        };
+        test_extn_payload_provider(entitlements_request, RippleContract::Entitlements);
+    }
+
+    #[test]
+    fn test_extn_response_entitlements() {
+        let entitlements_response: EntitlementsResponse = vec![EntitlementData {
+            entitlement_id: "test_entitlement_id".to_string(),
+            start_time: None,
+            end_time: None,
+        }];
+        test_extn_payload_provider(entitlements_response, RippleContract::Entitlements);
+    }
+}
@@ -380,6 +380,8 @@ impl ExtnPayloadProvider for ExclusionPolicy {
 pub enum DataEventType {
     Watched,
     BusinessIntelligence,
+    RemoteDiagnostics,
+    Interest,
     Unknown,
 }
 
@@ -389,6 +391,7 @@ impl FromStr for DataEventType {
         match input {
             "Watch_History" => Ok(DataEventType::Watched),
             "Product_Analytics" => Ok(DataEventType::BusinessIntelligence),
+            "Remote_Diagnostics" => Ok(DataEventType::RemoteDiagnostics),
             _ => Ok(DataEventType::Unknown),
         }
     }
@@ -0,0 +1,116 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    extn::extn_client_message::{ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse},
+    framework::ripple_contract::RippleContract,
+};
+
+/// Firebolt method served by an extension along with the capability it belongs to and the
+/// optional JSON schema of its params.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnMethodRegistration {
+    pub method: String,
+    pub capability: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+}
+
+/// Runtime registration of the Firebolt methods of an extension. Calls to the registered
+/// methods are sent to the extension as [crate::extn::extn_id::ExtnProviderRequest]. A
+/// registration replaces the methods of the previous one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExtnMethodRequest {
+    Register(Vec<ExtnMethodRegistration>),
+    Unregister,
+}
+
+/// Methods installed for the extension and the ones refused by the capability policy of the
+/// device or because they are already served by someone else
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnMethodRegistrationResult {
+    pub methods: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rejected_methods: Vec<String>,
+}
+
+impl ExtnPayloadProvider for ExtnMethodRequest {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Request(ExtnRequest::ExtnMethod(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Request(ExtnRequest::ExtnMethod(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::ExtnMethodRegistration
+    }
+}
+
+impl ExtnPayloadProvider for ExtnMethodRegistrationResult {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Response(ExtnResponse::ExtnMethod(v)) = payload {
+            return Some(v);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Response(ExtnResponse::ExtnMethod(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::ExtnMethodRegistration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::test_extn_payload_provider;
+
+    #[test]
+    fn test_extn_request_extn_method() {
+        let request = ExtnMethodRequest::Register(vec![ExtnMethodRegistration {
+            method: "player.play".into(),
+            capability: "xrn:firebolt:capability:player:base".into(),
+            schema: Some(serde_json::json!({ "type": "object" })),
+        }]);
+        test_extn_payload_provider(request, RippleContract::ExtnMethodRegistration);
+    }
+
+    #[test]
+    fn test_extn_response_extn_method() {
+        let result = ExtnMethodRegistrationResult {
+            methods: vec!["player.play".into()],
+            rejected_methods: vec!["device.name".into()],
+        };
+        test_extn_payload_provider(result, RippleContract::ExtnMethodRegistration);
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+pub const AUDIO_FOCUS_EVENT_ON_FOCUS_CHANGED: &str = "audiofocus.onFocusChanged";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AudioFocusType {
+    /// Long running playback, the previous holder loses focus permanently
+    Exclusive,
+    /// Short playback like a voice response, the previous holder pauses and gets the focus
+    /// back once the transient focus is abandoned
+    Transient,
+    /// Same as transient but the previous holder may keep playing at a lower volume
+    TransientMayDuck,
+}
+
+impl AudioFocusType {
+    pub fn is_transient(&self) -> bool {
+        !matches!(self, AudioFocusType::Exclusive)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AudioFocusStatus {
+    Granted,
+    Ducked,
+    LostTransient,
+    Lost,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioFocusRequest {
+    pub focus_type: AudioFocusType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioFocusResponse {
+    pub granted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioFocusChange {
+    pub status: AudioFocusStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus_type: Option<AudioFocusType>,
+}
@@ -378,6 +378,23 @@ pub struct CapabilityInfo {
     pub details: Option<Vec<DenyReason>>,
 }
 
+/// Usage of a capability by an app since Ripple started, `last_used` is in epoch millis
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityUsage {
+    pub capability: String,
+    pub role: CapabilityRole,
+    pub count: u64,
+    pub last_used: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityUsageRequest {
+    #[serde(default)]
+    pub app_id: Option<String>,
+}
+
 impl CapabilityInfo {
     pub fn get(cap: String, reason: Option<DenyReason>) -> CapabilityInfo {
         let (mut supported, mut available, mut permitted, mut granted) =
@@ -434,6 +451,7 @@ pub enum DenyReason {
     Ungranted,
     GrantProviderMissing,
     AppNotInActiveState,
+    BlockedByPolicy,
 }
 impl std::fmt::Display for DenyReason {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -447,6 +465,7 @@ impl std::fmt::Display for DenyReason {
             DenyReason::Ungranted => write!(f, "Ungranted"),
             DenyReason::GrantProviderMissing => write!(f, "GrantProviderMissing"),
             DenyReason::AppNotInActiveState => write!(f, "AppNotInActiveState"),
+            DenyReason::BlockedByPolicy => write!(f, "BlockedByPolicy"),
         }
     }
 }
@@ -471,6 +490,8 @@ pub const CAPABILITY_APP_NOT_IN_ACTIVE_STATE: i32 = -40402;
 
 pub const CAPABILITY_GRANT_PROVIDER_MISSING: i32 = -40403;
 
+pub const METHOD_BLOCKED_BY_POLICY: i32 = -40404;
+
 impl RpcError for DenyReason {
     type E = Vec<String>;
     fn get_rpc_error_code(&self) -> i32 {
@@ -483,6 +504,7 @@ impl RpcError for DenyReason {
             Self::NotFound => JSON_RPC_STANDARD_ERROR_METHOD_NOT_FOUND,
             Self::AppNotInActiveState => CAPABILITY_NOT_PERMITTED,
             Self::GrantProviderMissing => CAPABILITY_GRANT_PROVIDER_MISSING,
+            Self::BlockedByPolicy => CAPABILITY_NOT_PERMITTED,
             _ => CAPABILITY_GET_ERROR,
         }
     }
@@ -500,6 +522,7 @@ impl RpcError for DenyReason {
                 "Capability cannot be used when app is not in foreground state due to requiring a user grant".to_string()
             }
             Self::GrantProviderMissing => format!("Grant provider is missing for {}", caps_disp),
+            Self::BlockedByPolicy => "Method is blocked by policy for the app".to_string(),
             _ => format!("Error with {}", caps_disp),
         }
     }
@@ -514,6 +537,7 @@ impl RpcError for DenyReason {
             Self::NotFound => JSON_RPC_STANDARD_ERROR_METHOD_NOT_FOUND,
             Self::AppNotInActiveState => CAPABILITY_APP_NOT_IN_ACTIVE_STATE,
             Self::GrantProviderMissing => CAPABILITY_GRANT_PROVIDER_MISSING,
+            Self::BlockedByPolicy => METHOD_BLOCKED_BY_POLICY,
             _ => CAPABILITY_GET_ERROR,
         }
     }
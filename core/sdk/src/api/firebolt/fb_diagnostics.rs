@@ -0,0 +1,66 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::fb_metrics::FlatMapValue;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticsLogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// Structured diagnostic record pushed by an app through `diagnostics.log`
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct DiagnosticsLogParams {
+    pub level: DiagnosticsLogLevel,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<HashMap<String, FlatMapValue>>,
+}
+
+/// Request from the distributor backend to upload a diagnostics bundle to a signed URL
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadDiagnosticsParams {
+    pub upload_url: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics_log_params() {
+        let params: DiagnosticsLogParams = serde_json::from_value(serde_json::json!({
+            "level": "warn",
+            "message": "player stalled",
+            "attributes": {"bitrate": 4500, "cdn": "edge1"}
+        }))
+        .unwrap();
+        assert_eq!(params.level, DiagnosticsLogLevel::Warn);
+        assert_eq!(
+            params.attributes.unwrap().get("cdn"),
+            Some(&FlatMapValue::String("edge1".into()))
+        );
+    }
+}
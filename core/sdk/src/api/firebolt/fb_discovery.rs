@@ -25,11 +25,14 @@ use crate::{
         device::entertainment_data::{ContentIdentifiers, NavigationIntent},
         session::AccountSession,
     },
+    extn::extn_client_message::{ExtnPayload, ExtnPayloadProvider, ExtnRequest},
+    framework::ripple_contract::RippleContract,
     utils::serde_utils::{optional_date_time_str_serde, progress_value_deserialize},
 };
 use async_trait::async_trait;
 
 pub const DISCOVERY_EVENT_ON_NAVIGATE_TO: &str = "discovery.onNavigateTo";
+pub const DISCOVERY_EVENT_ON_ENTITLEMENTS_CHANGED: &str = "discovery.onEntitlementsChanged";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct DiscoveryContext {
@@ -156,6 +159,60 @@ pub const EVENT_ON_SIGN_IN: &str = "discovery.onSignIn";
 pub const EVENT_ON_SIGN_OUT: &str = "discovery.onSignOut";
 pub const PURCHASED_CONTENT_CAPABILITY: &str = "discovery:purchased-content";
 pub const EVENT_DISCOVERY_POLICY_CHANGED: &str = "discovery.onPolicyChanged";
+pub const USER_INTEREST_EVENT: &str = "discovery.onRequestUserInterest";
+pub const USER_INTEREST_CAPABILITY: &str = "discovery:interest";
+pub const CONTENT_EVENT_ON_USER_INTEREST: &str = "content.onUserInterest";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum InterestType {
+    Interest,
+    Disinterest,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum InterestReason {
+    Playlist,
+    Reaction,
+    Recording,
+}
+
+/// Parameters of `content.requestUserInterest`, passed on to the interest provider
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct UserInterestRequest {
+    #[serde(rename = "type")]
+    pub interest_type: InterestType,
+    pub reason: InterestReason,
+}
+
+/// Parameters of `discovery.userInterest`, the entity is passed through as the app sent it
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct UserInterest {
+    #[serde(rename = "type")]
+    pub interest_type: InterestType,
+    pub reason: InterestReason,
+    pub entity: serde_json::Value,
+}
+
+/// Entity the interest provider answered `content.requestUserInterest` with
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InterestResult {
+    pub app_id: String,
+    pub entity: serde_json::Value,
+}
+
+/// Payload of `content.onUserInterest`
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InterestEvent {
+    pub app_id: String,
+    #[serde(rename = "type")]
+    pub interest_type: InterestType,
+    pub reason: InterestReason,
+    pub entity: serde_json::Value,
+}
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "kebab-case")]
@@ -229,12 +286,30 @@ impl From<SignInInfo> for ContentAccessRequest {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub enum DiscoveryRequest {
     SetContentAccess(ContentAccessListSetParams),
     ClearContent(ClearContentSetParams),
 }
 
+impl ExtnPayloadProvider for DiscoveryRequest {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Request(ExtnRequest::Discovery(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Request(ExtnRequest::Discovery(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::Discovery
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentAccessEntitlement {
@@ -273,6 +348,35 @@ pub struct ContentAccessInfo {
     pub entitlements: Option<Vec<ContentAccessEntitlement>>,
 }
 
+impl From<ContentAccessIdentifiers> for ContentAccessInfo {
+    fn from(ids: ContentAccessIdentifiers) -> Self {
+        ContentAccessInfo {
+            availabilities: ids.availabilities.map(|availabilities| {
+                availabilities
+                    .into_iter()
+                    .map(|a| ContentAccessAvailability {
+                        _type: a._type.as_string().to_owned(),
+                        id: a.id,
+                        catalog_id: a.catalog_id,
+                        start_time: a.start_time,
+                        end_time: a.end_time,
+                    })
+                    .collect()
+            }),
+            entitlements: ids.entitlements.map(|entitlements| {
+                entitlements
+                    .into_iter()
+                    .map(|e| ContentAccessEntitlement {
+                        entitlement_id: e.entitlement_id,
+                        start_time: e.start_time,
+                        end_time: e.end_time,
+                    })
+                    .collect()
+            }),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct ClearContentSetParams {
     pub session_info: SessionParams,
@@ -401,6 +505,7 @@ pub trait AccountLinkService {
 mod tests {
     use super::*;
     use crate::api::device::entertainment_data::{HomeIntent, NavigationIntentStrict};
+    use crate::utils::test_utils::test_extn_payload_provider;
 
     #[test]
     fn test_new_discovery_context() {
@@ -562,6 +667,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_extn_request_discovery() {
+        let ids = serde_json::from_str::<ContentAccessIdentifiers>(
+            "{\"availabilities\":[{\"type\":\"channel-lineup\",\"id\":\"partner.com/lineup/1\"}],\"entitlements\":[{\"entitlementId\":\"123\"}]}",
+        )
+        .unwrap();
+        let content_access_info: ContentAccessInfo = ids.into();
+        assert_eq!(
+            content_access_info.availabilities.as_ref().unwrap()[0]._type,
+            "channel-lineup"
+        );
+        let request = DiscoveryRequest::SetContentAccess(ContentAccessListSetParams {
+            session_info: SessionParams {
+                app_id: "test_app".to_string(),
+                dist_session: AccountSession {
+                    id: "test_session_id".to_string(),
+                    token: "test_token".to_string(),
+                    account_id: "test_account_id".to_string(),
+                    device_id: "test_device_id".to_string(),
+                },
+            },
+            content_access_info,
+        });
+        test_extn_payload_provider(request, RippleContract::Discovery);
+    }
+
     #[test]
     fn test_schema() {
         if let Ok(v) = serde_json::from_str::<LaunchRequest>("{\"appId\":\"test\",\"intent\":{\"action\":\"playback\",\"data\":{\"programType\":\"movie\",\"entityId\":\"example-movie-id\"},\"context\":{\"source\":\"voice\"}}}"){
@@ -570,4 +701,18 @@ mod tests {
             panic!("Launch Schema Fail")
         }
     }
+
+    #[test]
+    fn test_user_interest_schema() {
+        let interest: UserInterest = serde_json::from_str(
+            r#"{"type":"interest","reason":"playlist","entity":{"identifiers":{"entityId":"345"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(interest.interest_type, InterestType::Interest);
+        assert_eq!(interest.reason, InterestReason::Playlist);
+        assert!(serde_json::from_str::<UserInterestRequest>(
+            r#"{"type":"disinterest","reason":"watched"}"#
+        )
+        .is_err());
+    }
 }
@@ -172,6 +172,13 @@ pub struct SetStateRequest {
     pub state: LifecycleState,
 }
 
+#[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppUsageRequest {
+    #[serde(default)]
+    pub app_id: Option<String>,
+}
+
 #[derive(Serialize, PartialEq, Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum SessionResponse {
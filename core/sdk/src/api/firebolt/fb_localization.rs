@@ -15,9 +15,13 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+use std::collections::HashMap;
+
 use regex::Regex;
 use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::api::manifest::device_manifest::{RegionField, RegionSourceType};
+
 #[derive(Debug, Default, Serialize, Clone)]
 pub struct PreferredLanguage(String);
 
@@ -46,6 +50,69 @@ impl SetPreferredAudioLanguage {
     }
 }
 
+/// Context values overridden for a single app session, used to test an app against
+/// other locales or device models without changing the device state.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_model: Option<String>,
+}
+
+impl ContextOverride {
+    /// Returns the overridden value for a firebolt getter, module names are lowercase
+    pub fn get_value(&self, method: &str) -> Option<String> {
+        match method {
+            "localization.language" => self.language.clone(),
+            "localization.locale" => self.locale.clone(),
+            "localization.countryCode" => self.country_code.clone(),
+            "localization.timeZone" => self.time_zone.clone(),
+            "device.model" => self.device_model.clone(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SetContextOverrideParams {
+    pub app_id: String,
+    #[serde(flatten)]
+    pub context: ContextOverride,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearContextOverrideParams {
+    pub app_id: String,
+}
+
+/// Region value reported to apps along with the source it was resolved from
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RegionValueInfo {
+    pub value: String,
+    pub source: RegionSourceType,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RegionInfo {
+    pub sources: Vec<RegionSourceType>,
+    pub values: HashMap<RegionField, RegionValueInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetRegionSourcesParams {
+    pub sources: Vec<RegionSourceType>,
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -63,4 +130,21 @@ mod tests {
             panic!("bad language entry should not serialize")
         }
     }
+
+    #[test]
+    fn test_context_override() {
+        let params: SetContextOverrideParams = serde_json::from_value(json!({
+            "appId": "app1",
+            "language": "fr",
+            "timeZone": "Europe/Paris"
+        }))
+        .unwrap();
+        assert_eq!(params.app_id, "app1");
+        assert_eq!(
+            params.context.get_value("localization.timeZone"),
+            Some("Europe/Paris".into())
+        );
+        assert_eq!(params.context.get_value("device.model"), None);
+        assert_eq!(params.context.get_value("localization.postalCode"), None);
+    }
 }
@@ -0,0 +1,83 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+pub const MEDIA_SESSION_CAPABILITY: &str = "media:sessions";
+pub const MEDIA_SESSION_EVENT_ON_ACTIVE_SESSION_CHANGED: &str =
+    "mediasession.onActiveSessionChanged";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlaybackState {
+    Playing,
+    Paused,
+    Buffering,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artwork_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+/// Playback state reported by an app, metadata which is not provided is kept from the
+/// previous report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaSessionReport {
+    pub state: PlaybackState,
+    #[serde(default)]
+    pub position_ms: Option<u64>,
+    #[serde(default)]
+    pub metadata: Option<MediaMetadata>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaSession {
+    pub app_id: String,
+    pub state: PlaybackState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position_ms: Option<u64>,
+    #[serde(default)]
+    pub metadata: MediaMetadata,
+    pub updated_at: u64,
+}
+
+impl MediaSession {
+    pub fn is_playing(&self) -> bool {
+        matches!(
+            self.state,
+            PlaybackState::Playing | PlaybackState::Buffering
+        )
+    }
+
+    /// Compares the fields apps subscribe to, ignoring the playback position and
+    /// the time of the last report.
+    pub fn same_state(&self, other: &MediaSession) -> bool {
+        self.app_id == other.app_id && self.state == other.state && self.metadata == other.metadata
+    }
+}
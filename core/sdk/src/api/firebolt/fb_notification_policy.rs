@@ -0,0 +1,129 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+pub const NOTIFICATION_POLICY_CAPABILITY: &str = "notifications:policy";
+pub const NOTIFICATION_POLICY_EVENT_ON_DO_NOT_DISTURB_CHANGED: &str =
+    "notifications.onDoNotDisturbChanged";
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Daily do not disturb window in local time, formatted as `HH:MM`. A window with an end
+/// before the start spans midnight.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoNotDisturbWindow {
+    pub start: String,
+    pub end: String,
+}
+
+fn parse_minute_of_day(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.split_once(':')?;
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    if hours < 24 && minutes < 60 {
+        Some(hours * 60 + minutes)
+    } else {
+        None
+    }
+}
+
+impl DoNotDisturbWindow {
+    pub fn is_valid(&self) -> bool {
+        matches!(
+            (parse_minute_of_day(&self.start), parse_minute_of_day(&self.end)),
+            (Some(start), Some(end)) if start != end
+        )
+    }
+
+    pub fn contains(&self, minute_of_day: u32) -> bool {
+        let (Some(start), Some(end)) = (
+            parse_minute_of_day(&self.start),
+            parse_minute_of_day(&self.end),
+        ) else {
+            return false;
+        };
+        let minute_of_day = minute_of_day % MINUTES_PER_DAY;
+        if start <= end {
+            start <= minute_of_day && minute_of_day < end
+        } else {
+            minute_of_day >= start || minute_of_day < end
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoNotDisturbSettings {
+    /// Manual do not disturb which applies regardless of the windows
+    pub enabled: bool,
+    #[serde(default)]
+    pub windows: Vec<DoNotDisturbWindow>,
+}
+
+impl DoNotDisturbSettings {
+    pub fn is_active(&self, minute_of_day: u32) -> bool {
+        self.enabled || self.windows.iter().any(|w| w.contains(minute_of_day))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoNotDisturbStatus {
+    pub active: bool,
+    pub enabled: bool,
+    pub windows: Vec<DoNotDisturbWindow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetDoNotDisturbWindowsRequest {
+    pub windows: Vec<DoNotDisturbWindow>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: &str, end: &str) -> DoNotDisturbWindow {
+        DoNotDisturbWindow {
+            start: start.into(),
+            end: end.into(),
+        }
+    }
+
+    #[test]
+    fn test_do_not_disturb_window() {
+        assert!(!window("25:00", "07:00").is_valid());
+        assert!(!window("22:00", "22:00").is_valid());
+
+        let overnight = window("22:00", "07:00");
+        assert!(overnight.is_valid());
+        assert!(overnight.contains(23 * 60));
+        assert!(overnight.contains(6 * 60 + 59));
+        assert!(!overnight.contains(7 * 60));
+        assert!(!overnight.contains(12 * 60));
+
+        let settings = DoNotDisturbSettings {
+            enabled: false,
+            windows: vec![window("13:00", "14:30")],
+        };
+        assert!(settings.is_active(14 * 60));
+        assert!(!settings.is_active(15 * 60));
+    }
+}
@@ -19,19 +19,25 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
+#[cfg(feature = "service_client")]
+use crate::{api::gateway::rpc_gateway_api::CallContext, service::service_client::ServiceClient};
 use crate::{
-    api::gateway::rpc_gateway_api::CallContext,
-    extn::extn_client_message::{ExtnEvent, ExtnPayload, ExtnPayloadProvider},
+    extn::{
+        client::extn_usage::ExtnUsage,
+        extn_client_message::{ExtnEvent, ExtnPayload, ExtnPayloadProvider},
+    },
     framework::ripple_contract::RippleContract,
-    service::service_client::ServiceClient,
+    utils::error::RippleError,
 };
 
-use super::fb_metrics::{
-    ErrorParams, ErrorType, FlatMapValue, InternalInitializeParams, Param, SystemErrorParams,
-};
+use super::fb_diagnostics::DiagnosticsLogLevel;
+#[cfg(feature = "service_client")]
+use super::fb_metrics::InternalInitializeParams;
+use super::fb_metrics::{ErrorParams, ErrorType, FlatMapValue, Param, SystemErrorParams};
 
 use log::error;
 
+#[cfg(feature = "service_client")]
 const EOS_DISTRIBUTOR_SERVICE_ID: &str = "ripple:channel:distributor:eos";
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
@@ -166,6 +172,197 @@ pub struct FireboltEvent {
     pub result: Value,
 }
 
+/// Diagnostic record pushed by an app, annotated with the session and device context
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct AppDiagnostic {
+    pub app_id: String,
+    pub app_session_id: Option<String>,
+    pub ripple_session_id: String,
+    pub ripple_version: String,
+    pub form_factor: String,
+    pub timestamp: i64,
+    pub level: DiagnosticsLogLevel,
+    pub message: String,
+    pub attributes: Option<HashMap<String, FlatMapValue>>,
+}
+
+/// Call which went through in developer mode even though the app was not permitted or
+/// granted the capabilities of the method
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct RelaxedCapabilityCall {
+    pub app_id: String,
+    pub app_session_id: Option<String>,
+    pub ripple_session_id: String,
+    pub ripple_version: String,
+    pub method: String,
+    pub reason: String,
+    pub capabilities: Vec<String>,
+    pub timestamp: i64,
+}
+
+/// Operator alert sent when an app goes over its error budget and is throttled
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ErrorBudgetExceeded {
+    pub app_id: String,
+    pub ripple_session_id: String,
+    pub ripple_version: String,
+    pub request_count: u64,
+    pub client_error_count: u64,
+    pub server_error_count: u64,
+    pub throttle_ms: u64,
+    pub timestamp: i64,
+}
+
+/// Calls and failures of an app in the current error budget window, client errors are
+/// denials and invalid calls while server errors are failures within Ripple or its endpoints
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AppErrorStats {
+    pub app_id: String,
+    pub request_count: u64,
+    pub client_error_count: u64,
+    pub server_error_count: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttled_until: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct RepairedStore {
+    pub store: String,
+    /// Path the corrupt file was moved to
+    pub quarantined_path: String,
+    pub reason: String,
+}
+
+/// Persisted stores which were found corrupt on boot and reset to their defaults
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StoreRepair {
+    pub ripple_session_id: String,
+    pub ripple_version: String,
+    pub repaired: Vec<RepairedStore>,
+    pub timestamp: i64,
+}
+
+/// App connection rejected at admission because a connection cap was reached
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ConnectionRejected {
+    pub ripple_session_id: String,
+    pub ripple_version: String,
+    pub app_id: String,
+    pub reason: String,
+    pub timestamp: i64,
+}
+
+/// Envelope of a Firebolt request kept in the request journal, payloads are left out
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestJournalEntry {
+    pub method: String,
+    pub app_id: String,
+    pub timestamp: i64,
+    /// JSON-RPC error code of the response, 0 for a successful request
+    pub code: i32,
+}
+
+/// Sent on the first start after Ripple exited without shutting down, with the last requests
+/// handled by the previous Ripple session
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct CrashBreadcrumb {
+    pub ripple_session_id: String,
+    pub ripple_version: String,
+    pub previous_session_id: Option<String>,
+    pub recent_requests: Vec<RequestJournalEntry>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum ServiceConnectionEventType {
+    Connect,
+    Register,
+    Unregister,
+    Takeover,
+    Disconnect,
+}
+
+/// Lifecycle change of a service connection on the gateway, `reason` explains rejections,
+/// takeovers and disconnects.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ServiceConnectionEvent {
+    pub ripple_session_id: String,
+    pub ripple_version: String,
+    pub service_id: String,
+    pub connection_id: String,
+    pub event: ServiceConnectionEventType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum BootMilestoneType {
+    ManifestLoaded,
+    ExtensionsUp,
+    WsListening,
+    FirstAppConnected,
+    FirstAppReady,
+}
+
+/// Milestone reached while Ripple boots, `elapsed_ms` is measured from the start of Ripple
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct BootMilestone {
+    pub milestone: BootMilestoneType,
+    pub timestamp: i64,
+    pub elapsed_ms: i64,
+    pub ripple_session_id: String,
+    pub ripple_version: String,
+}
+
+/// Periodic device health summary, fields which are not enabled in the device manifest
+/// are left out. Version 2 added `extnUsage`, `appUsage` and `serviceConnectionEvents`.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct DeviceHealth {
+    pub ripple_session_id: String,
+    pub ripple_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_rss_kb: Option<u64>,
+    /// Connection state of each broker endpoint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broker_health: Option<HashMap<String, bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reconnect_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc_count: Option<u64>,
+    /// Ratio of failed Firebolt calls since the previous heartbeat
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc_error_rate: Option<f64>,
+    /// Channel usage of each extension
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extn_usage: Option<Vec<ExtnUsage>>,
+    /// Lifecycle statistics of the most recently used apps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_usage: Option<Vec<AppUsage>>,
+    /// Service connection lifecycle events of each type since the previous heartbeat
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_connection_events: Option<HashMap<String, u64>>,
+}
+
+/// Lifecycle statistics of an app which are kept across reboots
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AppUsage {
+    pub app_id: String,
+    pub launch_count: u64,
+    pub foreground_ms: u64,
+    /// Number of exits for each close reason, crashes are reported as `error`
+    pub exit_reasons: HashMap<String, u64>,
+    /// Epoch millis of the last launch or foreground transition
+    pub last_used: i64,
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub enum TelemetryPayload {
     AppLoadStart(AppLoadStart),
@@ -178,9 +375,125 @@ pub enum TelemetryPayload {
     InternalInitialize(InternalInitialize),
     FireboltInteraction(FireboltInteraction), // External Service failures (service, error)
     FireboltEvent(FireboltEvent),
+    DeviceHealth(DeviceHealth),
+    BootMilestone(BootMilestone),
+    AppDiagnostic(AppDiagnostic),
+    RelaxedCapabilityCall(RelaxedCapabilityCall),
+    ErrorBudgetExceeded(ErrorBudgetExceeded),
+    StoreRepair(StoreRepair),
+    ServiceConnectionEvent(ServiceConnectionEvent),
+    ConnectionRejected(ConnectionRejected),
+    CrashBreadcrumb(CrashBreadcrumb),
+}
+
+/// Name and version of a telemetry event in the backend analytics contract. The version
+/// is bumped with every change to the fields of the event.
+#[derive(Debug, PartialEq, Eq, Serialize, Clone, Copy)]
+pub struct TelemetrySchema {
+    pub name: &'static str,
+    pub version: u32,
+}
+
+/// Implemented by every telemetry event in [TELEMETRY_SCHEMAS], only registered events can
+/// be turned into a [TelemetryPayload].
+pub trait TelemetryEvent: Into<TelemetryPayload> {
+    const SCHEMA: TelemetrySchema;
+}
+
+macro_rules! telemetry_schemas {
+    ($($variant:ident($event:ty) = $version:expr),* $(,)?) => {
+        $(
+            impl TelemetryEvent for $event {
+                const SCHEMA: TelemetrySchema = TelemetrySchema {
+                    name: stringify!($variant),
+                    version: $version,
+                };
+            }
+
+            impl From<$event> for TelemetryPayload {
+                fn from(event: $event) -> Self {
+                    TelemetryPayload::$variant(event)
+                }
+            }
+        )*
+
+        /// Registry of the telemetry events sent by Ripple
+        pub const TELEMETRY_SCHEMAS: &[TelemetrySchema] =
+            &[$(<$event as TelemetryEvent>::SCHEMA),*];
+
+        impl TelemetryPayload {
+            pub fn get_schema(&self) -> TelemetrySchema {
+                match self {
+                    $(TelemetryPayload::$variant(_) => <$event as TelemetryEvent>::SCHEMA),*
+                }
+            }
+        }
+    };
+}
+
+telemetry_schemas! {
+    AppLoadStart(AppLoadStart) = 1,
+    AppLoadStop(AppLoadStop) = 1,
+    AppSDKLoaded(AppSDKLoaded) = 1,
+    AppError(TelemetryAppError) = 1,
+    SystemError(TelemetrySystemError) = 1,
+    SignIn(TelemetrySignIn) = 1,
+    SignOut(TelemetrySignOut) = 1,
+    InternalInitialize(InternalInitialize) = 1,
+    FireboltInteraction(FireboltInteraction) = 1,
+    FireboltEvent(FireboltEvent) = 1,
+    DeviceHealth(DeviceHealth) = 2,
+    BootMilestone(BootMilestone) = 1,
+    AppDiagnostic(AppDiagnostic) = 1,
+    RelaxedCapabilityCall(RelaxedCapabilityCall) = 1,
+    ErrorBudgetExceeded(ErrorBudgetExceeded) = 1,
+    StoreRepair(StoreRepair) = 1,
+    ServiceConnectionEvent(ServiceConnectionEvent) = 1,
+    ConnectionRejected(ConnectionRejected) = 1,
+    CrashBreadcrumb(CrashBreadcrumb) = 1,
 }
 
 impl TelemetryPayload {
+    /// Validates a telemetry payload received from outside Ripple Main, events which are
+    /// not in the registry or carry unknown fields are rejected. The events themselves
+    /// accept unknown fields, so older producers of the extension events keep working.
+    pub fn from_value(value: Value) -> Result<TelemetryPayload, RippleError> {
+        let payload: TelemetryPayload = serde_json::from_value(value.clone()).map_err(|e| {
+            error!("Invalid telemetry payload {:?}", e);
+            RippleError::ParseError
+        })?;
+        let known = serde_json::to_value(&payload).map_err(|_| RippleError::ParseError)?;
+        let unknown = Self::get_unknown_fields(&value, &known);
+        if !unknown.is_empty() {
+            error!(
+                "Invalid telemetry payload {} unknown fields {:?}",
+                payload.get_schema().name,
+                unknown
+            );
+            return Err(RippleError::ParseError);
+        }
+        Ok(payload)
+    }
+
+    /// Fields of the event which do not survive a round trip through the registered struct,
+    /// null fields are left out as optional fields are not serialized when they are unset
+    fn get_unknown_fields(value: &Value, known: &Value) -> Vec<String> {
+        let (Some(event), Some(known_event)) = (
+            value.as_object().and_then(|v| v.values().next()),
+            known.as_object().and_then(|v| v.values().next()),
+        ) else {
+            return Vec::new();
+        };
+        match (event.as_object(), known_event.as_object()) {
+            (Some(fields), Some(known_fields)) => fields
+                .iter()
+                .filter(|(k, v)| !v.is_null() && !known_fields.contains_key(*k))
+                .map(|(k, _)| k.clone())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
     pub fn update_session_id(&mut self, session_id: String) {
         match self {
             Self::AppLoadStart(a) => a.ripple_session_id = session_id,
@@ -192,6 +505,15 @@ impl TelemetryPayload {
             Self::SignOut(s) => s.ripple_session_id = session_id,
             Self::InternalInitialize(i) => i.ripple_session_id = session_id,
             Self::FireboltInteraction(f) => f.ripple_session_id = session_id,
+            Self::DeviceHealth(d) => d.ripple_session_id = session_id,
+            Self::BootMilestone(b) => b.ripple_session_id = session_id,
+            Self::AppDiagnostic(a) => a.ripple_session_id = session_id,
+            Self::RelaxedCapabilityCall(r) => r.ripple_session_id = session_id,
+            Self::ErrorBudgetExceeded(e) => e.ripple_session_id = session_id,
+            Self::StoreRepair(s) => s.ripple_session_id = session_id,
+            Self::ServiceConnectionEvent(s) => s.ripple_session_id = session_id,
+            Self::ConnectionRejected(c) => c.ripple_session_id = session_id,
+            Self::CrashBreadcrumb(c) => c.ripple_session_id = session_id,
             Self::FireboltEvent(_) => {}
         }
     }
@@ -220,8 +542,10 @@ pub enum OperationalMetricRequest {
     UnSubscribe,
 }
 
+#[cfg(feature = "service_client")]
 pub struct TelemetryUtil;
 
+#[cfg(feature = "service_client")]
 impl TelemetryUtil {
     pub fn send_telemetry(client: &ServiceClient, payload: TelemetryPayload) {
         if let Err(e) = client.request_transient(
@@ -321,6 +645,39 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_telemetry_schemas() {
+        for (i, schema) in TELEMETRY_SCHEMAS.iter().enumerate() {
+            assert!(!TELEMETRY_SCHEMAS[i + 1..]
+                .iter()
+                .any(|s| s.name == schema.name));
+        }
+
+        let payload: TelemetryPayload = TelemetrySignIn {
+            app_id: String::from("app1"),
+            ripple_session_id: String::from("session1"),
+            app_session_id: None,
+        }
+        .into();
+        assert_eq!(payload.get_schema(), TelemetrySignIn::SCHEMA);
+        assert_eq!(payload.get_schema().name, "SignIn");
+
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(TelemetryPayload::from_value(value).unwrap(), payload);
+
+        let value = serde_json::json!({
+            "SignIn": {
+                "app_id": "app1",
+                "ripple_session_id": "session1",
+                "unknown": true
+            }
+        });
+        assert!(TelemetryPayload::from_value(value.clone()).is_err());
+        // events carrying fields of other producers still deserialize outside the validation
+        assert!(serde_json::from_value::<TelemetryPayload>(value).is_ok());
+        assert!(TelemetryPayload::from_value(serde_json::json!({"Unknown": {}})).is_err());
+    }
+
     #[test]
     fn test_get_params() {
         let error_params = get_params(Some(vec![
@@ -22,6 +22,7 @@ use crate::api::device::entertainment_data::{
 };
 
 use super::{
+    fb_discovery::{InterestResult, UserInterestRequest},
     fb_keyboard::{KeyboardSessionRequest, KeyboardSessionResponse},
     fb_pin::{PinChallengeRequest, PinChallengeResponse},
 };
@@ -37,6 +38,7 @@ pub enum ProviderRequestPayload {
     AckChallenge(Challenge),
     EntityInfoRequest(EntityInfoParameters),
     PurchasedContentRequest(PurchasedContentParameters),
+    UserInterestRequest(UserInterestRequest),
     Generic(serde_json::Value),
 }
 
@@ -47,6 +49,7 @@ pub enum ProviderResponsePayloadType {
     KeyboardResult,
     EntityInfoResponse,
     PurchasedContentResponse,
+    UserInterestResponse,
     GenericResponse,
     GenericError,
 }
@@ -61,6 +64,7 @@ impl std::fmt::Display for ProviderResponsePayloadType {
             ProviderResponsePayloadType::PurchasedContentResponse => {
                 write!(f, "PurchasedContentResponse")
             }
+            ProviderResponsePayloadType::UserInterestResponse => write!(f, "UserInterestResponse"),
             ProviderResponsePayloadType::GenericResponse => write!(f, "GenericResponse"),
             ProviderResponsePayloadType::GenericError => write!(f, "GenericError"),
         }
@@ -77,6 +81,7 @@ pub enum ProviderResponsePayload {
     KeyboardResult(KeyboardSessionResponse),
     EntityInfoResponse(Option<EntityInfoResult>),
     PurchasedContentResponse(PurchasedContentResult),
+    UserInterestResponse(InterestResult),
     GenericResponse(serde_json::Value),
 }
 
@@ -121,6 +126,13 @@ impl ProviderResponsePayload {
         }
     }
 
+    pub fn as_user_interest_result(&self) -> Option<InterestResult> {
+        match self {
+            ProviderResponsePayload::UserInterestResponse(res) => Some(res.clone()),
+            _ => None,
+        }
+    }
+
     pub fn as_value(&self) -> serde_json::Value {
         match self {
             ProviderResponsePayload::ChallengeResponse(res) => serde_json::to_value(res).unwrap(),
@@ -133,6 +145,9 @@ impl ProviderResponsePayload {
             ProviderResponsePayload::PurchasedContentResponse(res) => {
                 serde_json::to_value(res).unwrap()
             }
+            ProviderResponsePayload::UserInterestResponse(res) => {
+                serde_json::to_value(res).unwrap()
+            }
             ProviderResponsePayload::GenericResponse(res) => res.clone(),
         }
     }
@@ -372,4 +387,18 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_as_user_interest_result() {
+        let result = InterestResult {
+            app_id: "app1".to_string(),
+            entity: serde_json::json!({"identifiers": {"entityId": "345"}}),
+        };
+        let response = ProviderResponsePayload::UserInterestResponse(result.clone());
+        assert_eq!(response.as_user_interest_result(), Some(result));
+        assert_eq!(
+            response.as_value(),
+            serde_json::json!({"appId": "app1", "entity": {"identifiers": {"entityId": "345"}}})
+        );
+    }
 }
@@ -0,0 +1,106 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    extn::extn_client_message::{ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse},
+    framework::ripple_contract::RippleContract,
+};
+
+/// Authorization request sent by the gatekeeper for the methods configured in the
+/// `gatekeeper_policy` section of the device manifest.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GatekeeperPolicyRequest {
+    pub app_id: String,
+    pub method: String,
+    pub session_id: String,
+}
+
+/// Decision of the policy provider. `Fallthrough` leaves the call to the regular capability
+/// and permission checks.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "decision")]
+pub enum GatekeeperPolicyDecision {
+    Allow,
+    Deny { reason: Option<String> },
+    Fallthrough,
+}
+
+impl ExtnPayloadProvider for GatekeeperPolicyRequest {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Request(ExtnRequest::GatekeeperPolicy(r)) = payload {
+            return Some(r);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Request(ExtnRequest::GatekeeperPolicy(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::GatekeeperPolicy
+    }
+}
+
+impl ExtnPayloadProvider for GatekeeperPolicyDecision {
+    fn get_from_payload(payload: ExtnPayload) -> Option<Self> {
+        if let ExtnPayload::Response(ExtnResponse::GatekeeperPolicy(v)) = payload {
+            return Some(v);
+        }
+
+        None
+    }
+
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Response(ExtnResponse::GatekeeperPolicy(self.clone()))
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::GatekeeperPolicy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::test_extn_payload_provider;
+
+    #[test]
+    fn test_extn_request_gatekeeper_policy() {
+        let request = GatekeeperPolicyRequest {
+            app_id: "app1".into(),
+            method: "device.name".into(),
+            session_id: "session1".into(),
+        };
+        test_extn_payload_provider(request, RippleContract::GatekeeperPolicy);
+    }
+
+    #[test]
+    fn test_extn_response_gatekeeper_policy() {
+        let decision = GatekeeperPolicyDecision::Deny {
+            reason: Some("operator rule".into()),
+        };
+        test_extn_payload_provider(decision, RippleContract::GatekeeperPolicy);
+        let decision: GatekeeperPolicyDecision =
+            serde_json::from_str(r#"{"decision": "fallthrough"}"#).unwrap();
+        assert_eq!(decision, GatekeeperPolicyDecision::Fallthrough);
+    }
+}
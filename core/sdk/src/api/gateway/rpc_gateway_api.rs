@@ -15,6 +15,8 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+use std::time::{Duration, Instant};
+
 use jsonrpsee::types::ErrorObject;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -24,6 +26,7 @@ use uuid::Uuid;
 use crate::{
     api::{
         firebolt::{fb_general::ListenRequest, fb_openrpc::FireboltOpenRpcMethod},
+        gateway::rpc_response::JsonRpcApiResponseBuilder,
         observability::metrics_util::ApiStats,
     },
     extn::extn_client_message::{ExtnPayload, ExtnPayloadProvider, ExtnRequest},
@@ -32,6 +35,9 @@ use crate::{
 
 pub const RPC_V2: &str = "rpc_v2";
 
+/// Optional request member carrying the time in millis the caller is willing to wait
+pub const DEADLINE_EXTENSION_FIELD: &str = "deadlineMs";
+
 #[derive(Debug, Clone, Default)]
 pub struct CallerSession {
     pub session_id: Option<String>,
@@ -69,6 +75,15 @@ pub struct CallContext {
     pub cid: Option<String>,
     pub gateway_secure: bool,
     pub context: Vec<String>,
+    /// Instant after which the caller no longer waits for the response, serialized as the
+    /// millis left so extensions and services can honour it.
+    #[serde(
+        default,
+        rename = "deadline_ms",
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::serde_utils::optional_deadline_serde"
+    )]
+    pub deadline: Option<Instant>,
 }
 impl From<CallContext> for serde_json::Value {
     fn from(ctx: CallContext) -> Self {
@@ -82,6 +97,7 @@ impl From<CallContext> for serde_json::Value {
             "cid": ctx.cid,
             "gateway_secure": ctx.gateway_secure,
             "context": ctx.context,
+            "deadline_ms": ctx.get_remaining_budget().map(|d| d.as_millis() as u64),
         })
     }
 }
@@ -124,9 +140,31 @@ impl CallContext {
             cid,
             gateway_secure,
             context: Vec::new(),
+            deadline: None,
         }
     }
 
+    /// Sets the deadline to `budget_ms` from now, an earlier deadline is kept
+    pub fn set_deadline_budget(&mut self, budget_ms: u64) {
+        // a budget too large to represent is no deadline at all
+        let Some(deadline) = Instant::now().checked_add(Duration::from_millis(budget_ms)) else {
+            return;
+        };
+        self.deadline = Some(match self.deadline {
+            Some(current) => current.min(deadline),
+            None => deadline,
+        });
+    }
+
+    /// Time left before the deadline, zero once it has passed
+    pub fn get_remaining_budget(&self) -> Option<Duration> {
+        Some(self.deadline?.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        matches!(self.get_remaining_budget(), Some(remaining) if remaining.is_zero())
+    }
+
     pub fn get_id(&self) -> String {
         if let Some(cid) = &self.cid {
             return cid.clone();
@@ -164,6 +202,7 @@ impl crate::Mockable for CallContext {
             cid: Some("cid".to_owned()),
             gateway_secure: true,
             context: Vec::new(),
+            deadline: None,
         }
     }
 }
@@ -393,6 +432,10 @@ impl JsonRpcApiResponse {
         }
     }
 
+    pub fn builder(id: u64) -> JsonRpcApiResponseBuilder {
+        JsonRpcApiResponseBuilder::new(id)
+    }
+
     pub fn update_event_message(&mut self, request: &RpcRequest) {
         if request.is_rpc_v2() {
             self.params = self.result.take();
@@ -604,6 +647,9 @@ impl RpcRequest {
         if !base.is_jsonrpc() {
             return Err(RequestParseError {});
         }
+        let deadline_budget = parsed
+            .get(DEADLINE_EXTENSION_FIELD)
+            .and_then(|v| v.as_u64());
         let jsonrpc_req = serde_json::from_value::<JsonRpcApiRequest>(parsed)
             .map_err(|_| RequestParseError {})?;
 
@@ -620,6 +666,9 @@ impl RpcRequest {
             gateway_secure,
         );
         ctx.context = context;
+        if let Some(budget_ms) = deadline_budget {
+            ctx.set_deadline_budget(budget_ms);
+        }
         let ps = RpcRequest::prepend_ctx(jsonrpc_req.params, &ctx);
         Ok(RpcRequest::new(method, ps, ctx))
     }
@@ -763,6 +812,7 @@ mod tests {
             cid: Some("cid123".to_string()),
             gateway_secure: true,
             context: Vec::new(),
+            deadline: None,
         };
 
         let caller_session: CallerSession = ctx.into();
@@ -783,6 +833,7 @@ mod tests {
             cid: Some("cid123".to_string()),
             gateway_secure: true,
             context: Vec::new(),
+            deadline: None,
         };
 
         let app_identification: AppIdentification = ctx.into();
@@ -986,6 +1037,7 @@ mod tests {
             cid: Some("some_cid".to_string()),
             gateway_secure: true,
             context: Vec::new(),
+            deadline: None,
         };
 
         let rpc_request = RpcRequest {
@@ -1055,4 +1107,59 @@ mod tests {
         let request = serde_json::from_str::<ListenRequest>(&new.params_json).unwrap();
         assert!(!request.listen);
     }
+
+    #[test]
+    fn test_parse_deadline() {
+        let request = RpcRequest::parse(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "device.name", "deadlineMs": 60000}"#.into(),
+            "app".into(),
+            "session".into(),
+            "request".into(),
+            None,
+            true,
+            Vec::new(),
+        )
+        .unwrap();
+        let remaining = request.ctx.get_remaining_budget().unwrap();
+        assert!(remaining > Duration::from_secs(50) && remaining <= Duration::from_secs(60));
+        assert!(!request.ctx.is_expired());
+
+        // a later deadline does not extend the one sent by the app
+        let mut ctx = request.ctx.clone();
+        ctx.set_deadline_budget(120000);
+        assert_eq!(ctx.deadline, request.ctx.deadline);
+
+        ctx.deadline = Some(Instant::now());
+        assert!(ctx.is_expired());
+        assert!(CallContext::mock().get_remaining_budget().is_none());
+    }
+
+    #[test]
+    fn test_deadline_serde() {
+        let mut ctx = CallContext::mock();
+        let value = serde_json::to_value(&ctx).unwrap();
+        assert!(value.get("deadline_ms").is_none());
+        assert!(serde_json::from_value::<CallContext>(value)
+            .unwrap()
+            .deadline
+            .is_none());
+
+        ctx.set_deadline_budget(60000);
+        let value = serde_json::to_value(&ctx).unwrap();
+        let remaining_ms = value["deadline_ms"].as_u64().unwrap();
+        assert!(remaining_ms > 50000 && remaining_ms <= 60000);
+        let remaining = serde_json::from_value::<CallContext>(value)
+            .unwrap()
+            .get_remaining_budget()
+            .unwrap();
+        assert!(remaining > Duration::from_secs(50) && remaining <= Duration::from_secs(60));
+
+        // a deadline which has passed is sent as no time left
+        ctx.deadline = Some(Instant::now());
+        let value = serde_json::to_value(&ctx).unwrap();
+        assert_eq!(value["deadline_ms"], json!(0));
+        assert!(serde_json::from_value::<CallContext>(value)
+            .unwrap()
+            .is_expired());
+    }
 }
@@ -0,0 +1,254 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde_json::{json, Value};
+
+use crate::api::firebolt::fb_capabilities::{
+    CAPABILITY_APP_NOT_IN_ACTIVE_STATE, CAPABILITY_GET_ERROR, CAPABILITY_GRANT_DENIED,
+    CAPABILITY_GRANT_PROVIDER_MISSING, CAPABILITY_NOT_AVAILABLE, CAPABILITY_NOT_PERMITTED,
+    CAPABILITY_NOT_SUPPORTED, CAPABILITY_UNGRANTED, JSON_RPC_STANDARD_ERROR_INVALID_PARAMS,
+    JSON_RPC_STANDARD_ERROR_METHOD_NOT_FOUND, METHOD_BLOCKED_BY_POLICY,
+};
+
+use super::rpc_gateway_api::{JsonRpcApiError, JsonRpcApiResponse, RpcRequest};
+use crate::utils::error::RippleError;
+
+pub const JSON_RPC_STANDARD_ERROR_PARSE: i32 = -32700;
+
+pub const JSON_RPC_STANDARD_ERROR_INVALID_REQUEST: i32 = -32600;
+
+pub const JSON_RPC_STANDARD_ERROR_INTERNAL: i32 = -32603;
+
+/// Server error returned by the brokers when the endpoint serving a method cannot be reached.
+pub const JSON_RPC_SERVER_ERROR_UNAVAILABLE: i32 = -32001;
+
+/// Server error returned when a request is still pending at the deadline set by the caller.
+pub const JSON_RPC_SERVER_ERROR_DEADLINE_EXCEEDED: i32 = -32002;
+
+/// Server error returned while an app is throttled for exceeding its error budget.
+pub const JSON_RPC_SERVER_ERROR_THROTTLED: i32 = -32003;
+
+/// Builds a [JsonRpcApiResponse] for a request.
+///
+/// The id is required up front so a response can always be correlated by the caller, and
+/// errors are only built through the named constructors so the code always matches the
+/// Firebolt or JSON-RPC definition.
+#[derive(Debug, Clone)]
+pub struct JsonRpcApiResponseBuilder {
+    id: u64,
+    method: Option<String>,
+    params: Option<Value>,
+}
+
+impl JsonRpcApiResponseBuilder {
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            method: None,
+            params: None,
+        }
+    }
+
+    pub fn for_request(request: &RpcRequest) -> Self {
+        Self::new(request.ctx.call_id)
+    }
+
+    pub fn with_method(mut self, method: String) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    pub fn with_params(mut self, params: Option<Value>) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn result(self, result: Value) -> JsonRpcApiResponse {
+        JsonRpcApiResponse {
+            jsonrpc: "2.0".to_owned(),
+            id: Some(self.id),
+            result: Some(result),
+            error: None,
+            method: self.method,
+            params: self.params,
+        }
+    }
+
+    fn error(self, code: i32, message: String) -> JsonRpcApiResponse {
+        JsonRpcApiResponse {
+            jsonrpc: "2.0".to_owned(),
+            id: Some(self.id),
+            result: None,
+            error: Some(json!({"code": code, "message": message})),
+            method: self.method,
+            params: self.params,
+        }
+    }
+
+    pub fn parse_error(self, message: String) -> JsonRpcApiResponse {
+        self.error(JSON_RPC_STANDARD_ERROR_PARSE, message)
+    }
+
+    pub fn invalid_request(self, message: String) -> JsonRpcApiResponse {
+        self.error(JSON_RPC_STANDARD_ERROR_INVALID_REQUEST, message)
+    }
+
+    pub fn method_not_found(self, message: String) -> JsonRpcApiResponse {
+        self.error(JSON_RPC_STANDARD_ERROR_METHOD_NOT_FOUND, message)
+    }
+
+    pub fn invalid_params(self, message: String) -> JsonRpcApiResponse {
+        self.error(JSON_RPC_STANDARD_ERROR_INVALID_PARAMS, message)
+    }
+
+    pub fn internal_error(self, message: String) -> JsonRpcApiResponse {
+        self.error(JSON_RPC_STANDARD_ERROR_INTERNAL, message)
+    }
+
+    pub fn service_unavailable(self, message: String) -> JsonRpcApiResponse {
+        self.error(JSON_RPC_SERVER_ERROR_UNAVAILABLE, message)
+    }
+
+    pub fn deadline_exceeded(self, message: String) -> JsonRpcApiResponse {
+        self.error(JSON_RPC_SERVER_ERROR_DEADLINE_EXCEEDED, message)
+    }
+
+    pub fn capability_not_available(self, message: String) -> JsonRpcApiResponse {
+        self.error(CAPABILITY_NOT_AVAILABLE, message)
+    }
+
+    pub fn capability_not_supported(self, message: String) -> JsonRpcApiResponse {
+        self.error(CAPABILITY_NOT_SUPPORTED, message)
+    }
+
+    pub fn capability_get_error(self, message: String) -> JsonRpcApiResponse {
+        self.error(CAPABILITY_GET_ERROR, message)
+    }
+
+    pub fn capability_not_permitted(self, message: String) -> JsonRpcApiResponse {
+        self.error(CAPABILITY_NOT_PERMITTED, message)
+    }
+
+    pub fn capability_grant_denied(self, message: String) -> JsonRpcApiResponse {
+        self.error(CAPABILITY_GRANT_DENIED, message)
+    }
+
+    pub fn capability_ungranted(self, message: String) -> JsonRpcApiResponse {
+        self.error(CAPABILITY_UNGRANTED, message)
+    }
+
+    pub fn app_not_in_active_state(self, message: String) -> JsonRpcApiResponse {
+        self.error(CAPABILITY_APP_NOT_IN_ACTIVE_STATE, message)
+    }
+
+    pub fn grant_provider_missing(self, message: String) -> JsonRpcApiResponse {
+        self.error(CAPABILITY_GRANT_PROVIDER_MISSING, message)
+    }
+
+    pub fn blocked_by_policy(self, message: String) -> JsonRpcApiResponse {
+        self.error(METHOD_BLOCKED_BY_POLICY, message)
+    }
+
+    /// Maps an error raised while brokering the request to its code, only input errors are
+    /// reported as invalid params.
+    pub fn ripple_error(self, error: &RippleError) -> JsonRpcApiResponse {
+        let message = format!("Error with {:?}", error);
+        match error {
+            RippleError::MissingInput | RippleError::InvalidInput => self.invalid_params(message),
+            RippleError::TimeoutError => self.deadline_exceeded(message),
+            RippleError::Permission(_)
+            | RippleError::InvalidAccess
+            | RippleError::ApiAuthenticationFailed => self.capability_not_permitted(message),
+            RippleError::NotAvailable
+            | RippleError::ServiceNotReady
+            | RippleError::ServiceError
+            | RippleError::SenderMissing
+            | RippleError::SendFailure
+            | RippleError::ClientMissing
+            | RippleError::NoResponse
+            | RippleError::BrokerError(_) => self.service_unavailable(message),
+            _ => self.internal_error(message),
+        }
+    }
+
+    /// Carries over an error which already has a code, eg. one returned by an endpoint.
+    pub fn from_error(self, error: &JsonRpcApiError) -> JsonRpcApiResponse {
+        self.error(error.code, error.message.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Mockable;
+
+    #[test]
+    fn test_response_builder() {
+        let mut request = RpcRequest::mock();
+        request.ctx.call_id = 7;
+
+        let response = JsonRpcApiResponseBuilder::for_request(&request).result(json!(true));
+        assert!(response.is_response());
+        assert_eq!(response.id, Some(7));
+        assert_eq!(response.result, Some(json!(true)));
+
+        let response = JsonRpcApiResponseBuilder::new(8).service_unavailable("down".into());
+        assert!(response.is_error());
+        assert_eq!(response.id, Some(8));
+        assert_eq!(
+            response.error,
+            Some(json!({"code": JSON_RPC_SERVER_ERROR_UNAVAILABLE, "message": "down"}))
+        );
+
+        let response = JsonRpcApiResponseBuilder::new(9)
+            .with_method("device.name".into())
+            .capability_not_available("not available".into());
+        assert_eq!(response.method, Some("device.name".into()));
+        assert_eq!(response.error.unwrap()["code"], json!(-50300));
+    }
+
+    #[test]
+    fn test_ripple_error_codes() {
+        let code = |error: RippleError| {
+            JsonRpcApiResponseBuilder::new(1)
+                .ripple_error(&error)
+                .error
+                .unwrap()["code"]
+                .clone()
+        };
+        assert_eq!(
+            code(RippleError::InvalidInput),
+            json!(JSON_RPC_STANDARD_ERROR_INVALID_PARAMS)
+        );
+        assert_eq!(
+            code(RippleError::TimeoutError),
+            json!(JSON_RPC_SERVER_ERROR_DEADLINE_EXCEEDED)
+        );
+        assert_eq!(
+            code(RippleError::ServiceError),
+            json!(JSON_RPC_SERVER_ERROR_UNAVAILABLE)
+        );
+        assert_eq!(
+            code(RippleError::InvalidAccess),
+            json!(CAPABILITY_NOT_PERMITTED)
+        );
+        assert_eq!(
+            code(RippleError::ParseError),
+            json!(JSON_RPC_STANDARD_ERROR_INTERNAL)
+        );
+    }
+}
@@ -17,7 +17,7 @@
 
 use super::{
     apps::AppManifest,
-    device_manifest::{AppLibraryEntry, AppManifestLoad, BootState},
+    device_manifest::{AppLibraryEntry, AppManifestLoad, BootState, SessionPriority},
 };
 use log::{error, warn};
 use serde::{Deserialize, Serialize};
@@ -35,11 +35,28 @@ impl std::fmt::Debug for AppLibraryState {
     }
 }
 
+pub const APP_LIBRARY_EVENT_APP_ADDED: &str = "ripple.onAppAdded";
+pub const APP_LIBRARY_EVENT_APP_REMOVED: &str = "ripple.onAppRemoved";
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct DefaultLibrary {
     pub default_library: Vec<AppLibraryEntry>,
 }
 
+/// App ids which changed when a new copy of the app library was applied
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppLibraryDelta {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub updated: Vec<String>,
+}
+
+impl AppLibraryDelta {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
 pub struct AppLibrary {}
 
 impl AppLibraryState {
@@ -55,6 +72,37 @@ impl AppLibraryState {
         self.default_apps.clone()
     }
 
+    /// Replaces the library with a newer copy and rebuilds the provider relations
+    pub fn apply_update(&mut self, apps: Vec<AppLibraryEntry>) -> AppLibraryDelta {
+        let mut delta = AppLibraryDelta::default();
+        for app in apps.iter() {
+            match self.default_apps.iter().find(|a| a.app_id == app.app_id) {
+                Some(existing) if existing != app => delta.updated.push(app.app_id.clone()),
+                Some(_) => {}
+                None => delta.added.push(app.app_id.clone()),
+            }
+        }
+        for app in self.default_apps.iter() {
+            if !apps.iter().any(|a| a.app_id == app.app_id) {
+                delta.removed.push(app.app_id.clone());
+            }
+        }
+        if !delta.is_empty() {
+            self.providers = AppLibrary::generate_provider_relation_map(&apps);
+            self.default_apps = apps;
+        }
+        delta
+    }
+
+    /// Apps which are not in the library get the normal priority
+    pub fn get_session_priority(&self, app_id: &str) -> SessionPriority {
+        self.default_apps
+            .iter()
+            .find(|a| a.app_id == app_id)
+            .map(|a| a.session_priority)
+            .unwrap_or_default()
+    }
+
     pub fn get_default_app(&self) -> Option<AppLibraryEntry> {
         if let Some(default_app) = self
             .default_apps
@@ -123,11 +171,13 @@ mod tests {
             AppLibraryEntry {
                 app_id: "app1".to_string(),
                 boot_state: BootState::Foreground,
+                session_priority: SessionPriority::Normal,
                 manifest: AppManifestLoad::Embedded(AppManifest::default()),
             },
             AppLibraryEntry {
                 app_id: "app2".to_string(),
                 boot_state: BootState::Unloaded,
+                session_priority: SessionPriority::Normal,
                 manifest: AppManifestLoad::Embedded(AppManifest::default()),
             },
         ]
@@ -161,11 +211,27 @@ mod tests {
             Some(AppLibraryEntry {
                 app_id: "app1".to_string(),
                 boot_state: BootState::Foreground,
+                session_priority: SessionPriority::Normal,
                 manifest: AppManifestLoad::Embedded(AppManifest::default()),
             })
         );
     }
 
+    #[test]
+    fn test_get_session_priority() {
+        let mut default_apps = get_default_apps();
+        default_apps[0].session_priority = SessionPriority::Critical;
+        let app_library_state = AppLibraryState::new(default_apps);
+        assert_eq!(
+            app_library_state.get_session_priority("app1"),
+            SessionPriority::Critical
+        );
+        assert_eq!(
+            app_library_state.get_session_priority("unknown"),
+            SessionPriority::Normal
+        );
+    }
+
     #[test]
     fn test_get_provider() {
         let default_apps = get_default_apps();
@@ -210,4 +276,30 @@ mod tests {
 
         assert_eq!(AppLibrary::get_manifest(&app_library_state, "app3"), None);
     }
+
+    #[test]
+    fn test_apply_update() {
+        let mut app_library_state = AppLibraryState::new(get_default_apps());
+        let mut apps = get_default_apps();
+        apps.remove(0);
+        apps[0].boot_state = BootState::Foreground;
+        apps.push(AppLibraryEntry {
+            app_id: "app3".to_string(),
+            boot_state: BootState::Unloaded,
+            session_priority: SessionPriority::Normal,
+            manifest: AppManifestLoad::Embedded(AppManifest::default()),
+        });
+
+        let delta = app_library_state.apply_update(apps.clone());
+        assert_eq!(
+            delta,
+            AppLibraryDelta {
+                added: vec!["app3".to_string()],
+                removed: vec!["app1".to_string()],
+                updated: vec!["app2".to_string()],
+            }
+        );
+        assert_eq!(app_library_state.get_all_apps(), apps);
+        assert!(app_library_state.apply_update(apps).is_empty());
+    }
 }
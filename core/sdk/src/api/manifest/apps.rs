@@ -76,6 +76,8 @@ pub struct AppManifest {
     pub h: u32,
     pub capabilities: AppCapabilities,
     pub properties: Option<AppProperties>,
+    #[serde(default)]
+    pub version: Option<String>,
 }
 
 impl AppManifest {
@@ -111,6 +113,7 @@ impl Default for AppManifest {
                 },
             },
             properties: None,
+            version: None,
         }
     }
 }
@@ -181,6 +184,7 @@ mod tests {
                 provided: Capability::default(),
             },
             properties: None,
+            version: None,
         };
 
         assert!(app_manifest.requires_capability("capability1"));
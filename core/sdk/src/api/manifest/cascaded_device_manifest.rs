@@ -20,7 +20,6 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use std::{
     collections::{HashMap, HashSet},
-    fs,
     hash::{Hash, Hasher},
     path::Path,
 };
@@ -41,13 +40,28 @@ use crate::{
 
 use super::{
     device_manifest::{
-        ApplicationDefaultsConfiguration, ApplicationsConfiguration, CapabilityConfiguration,
-        CaptionStyle, DataGovernanceConfig, DataGovernancePolicy, DataGovernanceSettingTag,
-        DefaultValues, DeviceManifest, DistributionConfiguration, IdSalt, IntentValidation,
-        InternetMonitoringConfiguration, LifecycleConfiguration, PrivacySettingsStorageType,
-        RippleConfiguration, RippleFeatures, VoiceGuidance, WsConfiguration,
+        ActivationConfiguration, AdConfigConfiguration, AdminConfiguration,
+        AppLibraryRefreshConfiguration, AppUsageConfiguration, ApplicationDefaultsConfiguration,
+        ApplicationsConfiguration, AudioFocusConfiguration, CapabilityConfiguration,
+        CapabilityUsageConfiguration, CaptionStyle, ComplianceConfiguration,
+        ConnectionLimitsConfiguration, DataGovernanceConfig, DataGovernancePolicy,
+        DataGovernanceSettingTag, DbusBridgeConfiguration, DeadlineConfiguration, DefaultValues,
+        DeviceManifest, DiagnosticsConfiguration, DistributionConfiguration, DrmConfiguration,
+        EntitlementsSyncConfiguration, ErrorBudgetConfiguration, EventTransform,
+        GatekeeperPolicyConfiguration, HeartbeatConfiguration, HttpBridgeConfiguration, IdSalt,
+        InactivityConfiguration, InputConfiguration, IntentValidation,
+        InternetMonitoringConfiguration, JqSandboxConfiguration, LifecycleConfiguration,
+        MethodOverridesConfiguration, MetricsCategoryConsent, MetricsEnrichmentConfiguration,
+        NotificationPolicyConfiguration, PendingRequestConfiguration, PrivacySettingsStorageType,
+        RegionConfiguration, RequestJournalConfiguration, RippleConfiguration, RippleFeatures,
+        RoutingConfiguration, RuntimeTopologyConfiguration, ServiceAuthConfiguration,
+        ServiceCapabilityPolicy, ServiceConflictConfiguration, ServiceHttpConfiguration,
+        ServiceLauncherConfiguration, ServiceLivenessConfiguration,
+        ServiceRequestTimeoutConfiguration, ServiceTakeoverPolicy, ShutdownConfiguration,
+        TenantsConfiguration, VoiceGuidance, WatchHistoryUploadConfiguration, WsConfiguration,
     },
     exclusory::{AppAuthorizationRules, ExclusoryImpl},
+    manifest_source::ManifestSource,
     remote_feature::FeatureFlag,
     MergeConfig,
 };
@@ -81,7 +95,7 @@ impl CascadedDeviceManifest {
     pub fn load(path: String) -> Result<(String, CascadedDeviceManifest), RippleError> {
         info!("Trying to load device manifest cascaded from path={}", path);
         if let Some(p) = Path::new(&path).to_str() {
-            if let Ok(contents) = fs::read_to_string(p) {
+            if let Ok(contents) = ManifestSource::load_to_string(p) {
                 info!("Device manifest found in {}", path);
                 return Self::load_from_content(contents);
             }
@@ -123,6 +137,47 @@ pub struct CascadedRippleConfiguration {
     pub partner_exclusion_refresh_timeout: Option<u32>,
     pub metrics_logging_percentage: Option<u32>,
     pub internet_monitoring_configuration: Option<InternetMonitoringConfiguration>,
+    pub service_launcher: Option<ServiceLauncherConfiguration>,
+    pub service_takeover_policies: Option<HashMap<String, ServiceTakeoverPolicy>>,
+    pub admin: Option<AdminConfiguration>,
+    pub dbus_bridge: Option<DbusBridgeConfiguration>,
+    pub app_library_refresh: Option<AppLibraryRefreshConfiguration>,
+    pub entitlements_sync: Option<EntitlementsSyncConfiguration>,
+    pub watch_history_upload: Option<WatchHistoryUploadConfiguration>,
+    pub heartbeat: Option<HeartbeatConfiguration>,
+    pub diagnostics: Option<DiagnosticsConfiguration>,
+    pub http_bridge: Option<HttpBridgeConfiguration>,
+    pub method_overrides: Option<MethodOverridesConfiguration>,
+    pub capability_usage: Option<CapabilityUsageConfiguration>,
+    pub activation: Option<ActivationConfiguration>,
+    pub inactivity: Option<InactivityConfiguration>,
+    pub input: Option<InputConfiguration>,
+    pub audio_focus: Option<AudioFocusConfiguration>,
+    pub drm: Option<DrmConfiguration>,
+    pub compliance: Option<ComplianceConfiguration>,
+    pub notification_policy: Option<NotificationPolicyConfiguration>,
+    pub pending_requests: Option<PendingRequestConfiguration>,
+    pub runtime_topology: Option<RuntimeTopologyConfiguration>,
+    pub deadlines: Option<DeadlineConfiguration>,
+    pub shutdown: Option<ShutdownConfiguration>,
+    pub jq_sandbox: Option<JqSandboxConfiguration>,
+    pub event_transforms: Option<Vec<EventTransform>>,
+    pub app_usage: Option<AppUsageConfiguration>,
+    pub metrics_enrichment: Option<MetricsEnrichmentConfiguration>,
+    pub error_budget: Option<ErrorBudgetConfiguration>,
+    pub service_liveness: Option<ServiceLivenessConfiguration>,
+    pub ad_config: Option<AdConfigConfiguration>,
+    pub service_request_timeouts: Option<ServiceRequestTimeoutConfiguration>,
+    pub gatekeeper_policy: Option<GatekeeperPolicyConfiguration>,
+    pub service_http: Option<ServiceHttpConfiguration>,
+    pub tenants: Option<TenantsConfiguration>,
+    pub connection_limits: Option<ConnectionLimitsConfiguration>,
+    pub region: Option<RegionConfiguration>,
+    pub request_journal: Option<RequestJournalConfiguration>,
+    pub service_auth: Option<ServiceAuthConfiguration>,
+    pub service_capabilities: Option<ServiceCapabilityPolicy>,
+    pub service_conflicts: Option<ServiceConflictConfiguration>,
+    pub routing: Option<RoutingConfiguration>,
 }
 
 impl MergeConfig<CascadedRippleConfiguration> for RippleConfiguration {
@@ -197,6 +252,130 @@ impl MergeConfig<CascadedRippleConfiguration> for RippleConfiguration {
         if let Some(cas_internet_monitering_conf) = cascaded.internet_monitoring_configuration {
             self.internet_monitoring_configuration = cas_internet_monitering_conf;
         }
+        if let Some(cas_service_launcher) = cascaded.service_launcher {
+            self.service_launcher = cas_service_launcher;
+        }
+        if let Some(cas_service_takeover_policies) = cascaded.service_takeover_policies {
+            self.service_takeover_policies
+                .extend(cas_service_takeover_policies);
+        }
+        if let Some(cas_admin) = cascaded.admin {
+            self.admin = cas_admin;
+        }
+        if let Some(cas_dbus_bridge) = cascaded.dbus_bridge {
+            self.dbus_bridge = cas_dbus_bridge;
+        }
+        if let Some(cas_app_library_refresh) = cascaded.app_library_refresh {
+            self.app_library_refresh = cas_app_library_refresh;
+        }
+        if let Some(cas_entitlements_sync) = cascaded.entitlements_sync {
+            self.entitlements_sync = cas_entitlements_sync;
+        }
+        if let Some(cas_watch_history_upload) = cascaded.watch_history_upload {
+            self.watch_history_upload = cas_watch_history_upload;
+        }
+        if let Some(cas_heartbeat) = cascaded.heartbeat {
+            self.heartbeat = cas_heartbeat;
+        }
+        if let Some(cas_diagnostics) = cascaded.diagnostics {
+            self.diagnostics = cas_diagnostics;
+        }
+        if let Some(cas_http_bridge) = cascaded.http_bridge {
+            self.http_bridge = cas_http_bridge;
+        }
+        if let Some(cas_method_overrides) = cascaded.method_overrides {
+            self.method_overrides = cas_method_overrides;
+        }
+        if let Some(cas_capability_usage) = cascaded.capability_usage {
+            self.capability_usage = cas_capability_usage;
+        }
+        if let Some(cas_activation) = cascaded.activation {
+            self.activation = cas_activation;
+        }
+        if let Some(cas_inactivity) = cascaded.inactivity {
+            self.inactivity = cas_inactivity;
+        }
+        if let Some(cas_input) = cascaded.input {
+            self.input = cas_input;
+        }
+        if let Some(cas_audio_focus) = cascaded.audio_focus {
+            self.audio_focus = cas_audio_focus;
+        }
+        if let Some(cas_drm) = cascaded.drm {
+            self.drm = cas_drm;
+        }
+        if let Some(cas_compliance) = cascaded.compliance {
+            self.compliance = cas_compliance;
+        }
+        if let Some(cas_notification_policy) = cascaded.notification_policy {
+            self.notification_policy = cas_notification_policy;
+        }
+        if let Some(cas_pending_requests) = cascaded.pending_requests {
+            self.pending_requests = cas_pending_requests;
+        }
+        if let Some(cas_runtime_topology) = cascaded.runtime_topology {
+            self.runtime_topology = cas_runtime_topology;
+        }
+        if let Some(cas_deadlines) = cascaded.deadlines {
+            self.deadlines = cas_deadlines;
+        }
+        if let Some(cas_shutdown) = cascaded.shutdown {
+            self.shutdown = cas_shutdown;
+        }
+        if let Some(cas_jq_sandbox) = cascaded.jq_sandbox {
+            self.jq_sandbox = cas_jq_sandbox;
+        }
+        if let Some(cas_event_transforms) = cascaded.event_transforms {
+            self.event_transforms.extend(cas_event_transforms);
+        }
+        if let Some(cas_app_usage) = cascaded.app_usage {
+            self.app_usage = cas_app_usage;
+        }
+        if let Some(cas_metrics_enrichment) = cascaded.metrics_enrichment {
+            self.metrics_enrichment = cas_metrics_enrichment;
+        }
+        if let Some(cas_error_budget) = cascaded.error_budget {
+            self.error_budget = cas_error_budget;
+        }
+        if let Some(cas_service_liveness) = cascaded.service_liveness {
+            self.service_liveness = cas_service_liveness;
+        }
+        if let Some(cas_ad_config) = cascaded.ad_config {
+            self.ad_config = cas_ad_config;
+        }
+        if let Some(cas_service_request_timeouts) = cascaded.service_request_timeouts {
+            self.service_request_timeouts = cas_service_request_timeouts;
+        }
+        if let Some(cas_gatekeeper_policy) = cascaded.gatekeeper_policy {
+            self.gatekeeper_policy = cas_gatekeeper_policy;
+        }
+        if let Some(cas_service_http) = cascaded.service_http {
+            self.service_http = cas_service_http;
+        }
+        if let Some(cas_tenants) = cascaded.tenants {
+            self.tenants = cas_tenants;
+        }
+        if let Some(cas_connection_limits) = cascaded.connection_limits {
+            self.connection_limits = cas_connection_limits;
+        }
+        if let Some(cas_region) = cascaded.region {
+            self.region = cas_region;
+        }
+        if let Some(cas_request_journal) = cascaded.request_journal {
+            self.request_journal = cas_request_journal;
+        }
+        if let Some(cas_service_auth) = cascaded.service_auth {
+            self.service_auth = cas_service_auth;
+        }
+        if let Some(cas_service_capabilities) = cascaded.service_capabilities {
+            self.service_capabilities = cas_service_capabilities;
+        }
+        if let Some(cas_service_conflicts) = cascaded.service_conflicts {
+            self.service_conflicts = cas_service_conflicts;
+        }
+        if let Some(cas_routing) = cascaded.routing {
+            self.routing = cas_routing;
+        }
     }
 }
 
@@ -380,6 +559,7 @@ pub struct CascadedRippleFeatures {
     pub privacy_settings_storage_type: Option<PrivacySettingsStorageType>,
     pub intent_validation: Option<IntentValidation>,
     pub cloud_permissions: Option<bool>,
+    pub developer_mode: Option<bool>,
 }
 
 impl MergeConfig<CascadedRippleFeatures> for RippleFeatures {
@@ -393,6 +573,9 @@ impl MergeConfig<CascadedRippleFeatures> for RippleFeatures {
         if let Some(cas_cloud_permission) = cascaded.cloud_permissions {
             self.cloud_permissions = cas_cloud_permission
         }
+        if let Some(cas_developer_mode) = cascaded.developer_mode {
+            self.developer_mode = cas_developer_mode
+        }
     }
 }
 
@@ -841,10 +1024,14 @@ impl MergeConfig<CascadedAppAuthorizationRules> for AppAuthorizationRules {
 #[derive(Deserialize, Debug, Clone)]
 pub struct CascadedDataGovernanceConfig {
     pub policies: Option<Vec<CascadedDataGovernancePolicy>>,
+    pub metrics_consent: Option<Vec<MetricsCategoryConsent>>,
 }
 
 impl MergeConfig<CascadedDataGovernanceConfig> for DataGovernanceConfig {
     fn merge_config(&mut self, other: CascadedDataGovernanceConfig) {
+        if let Some(metrics_consent) = other.metrics_consent {
+            self.metrics_consent = metrics_consent;
+        }
         if let Some(other_policies) = other.policies {
             for cascaded_policy in other_policies {
                 // Try to find a matching existing policy based on a unique identifier
@@ -1205,7 +1392,8 @@ mod tests {
                 privacy_settings_storage_type: PrivacySettingsStorageType::Local,
                 intent_validation: IntentValidation::Fail,
                 cloud_permissions: true,
-                thunder_plugin_status_check_at_broker_start_up: true
+                thunder_plugin_status_check_at_broker_start_up: true,
+                developer_mode: false,
             }
         );
     }
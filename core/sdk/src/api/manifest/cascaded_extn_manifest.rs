@@ -14,7 +14,9 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 //
-use super::extn_manifest::{ExtnManifest, ExtnManifestEntry, ExtnResolutionEntry, ExtnSymbol};
+use super::extn_manifest::{
+    ExtnManifest, ExtnManifestEntry, ExtnResolutionEntry, ExtnRoute, ExtnSymbol,
+};
 use super::MergeConfig;
 use crate::utils::error::RippleError;
 use log::{info, warn};
@@ -35,6 +37,9 @@ pub struct CascadedExtnManifest {
     pub rules_path: Option<Vec<String>>,
     pub extn_sdks: Option<Vec<String>>,
     pub provider_registrations: Option<Vec<String>>,
+    pub extn_routes: Option<Vec<ExtnRoute>>,
+    pub extn_routes_enforced: Option<bool>,
+    pub backlog_warning_threshold: Option<usize>,
 }
 impl MergeConfig<CascadedExtnManifest> for ExtnManifest {
     fn merge_config(&mut self, cascaded: CascadedExtnManifest) {
@@ -84,6 +89,15 @@ impl MergeConfig<CascadedExtnManifest> for ExtnManifest {
             self.provider_registrations.extend(cas_provider_reg);
             self.provider_registrations.sort();
         }
+        if let Some(cas_extn_routes) = cascaded.extn_routes {
+            self.extn_routes.extend(cas_extn_routes);
+        }
+        if let Some(cas_extn_routes_enforced) = cascaded.extn_routes_enforced {
+            self.extn_routes_enforced = Some(cas_extn_routes_enforced);
+        }
+        if let Some(cas_backlog_warning_threshold) = cascaded.backlog_warning_threshold {
+            self.backlog_warning_threshold = Some(cas_backlog_warning_threshold);
+        }
     }
 }
 
@@ -131,6 +145,7 @@ impl ExtnManifestEntry {
                                 .collect()
                         })
                         .filter(|c: &std::collections::HashMap<String, String>| !c.is_empty()),
+                    contract_versions: s.contract_versions.unwrap_or_default(),
                 })
                 .collect(),
             resolution: cascaded.resolution,
@@ -170,6 +185,7 @@ impl MergeConfig<CascadedExtnManifestEntry> for ExtnManifestEntry {
                                 .filter_map(|(k, v)| v.map(|vv| (k, vv)))
                                 .collect()
                         }),
+                        contract_versions: cascaded_symbol.contract_versions.unwrap_or_default(),
                     });
                 }
             }
@@ -203,6 +219,7 @@ pub struct CascadedExtnSymbol {
     pub uses: Option<Vec<String>>,
     pub fulfills: Option<Vec<String>>,
     pub config: Option<HashMap<String, Option<String>>>,
+    pub contract_versions: Option<HashMap<String, Vec<u32>>>,
 }
 impl MergeConfig<CascadedExtnSymbol> for ExtnSymbol {
     fn merge_config(&mut self, cascaded: CascadedExtnSymbol) {
@@ -217,6 +234,9 @@ impl MergeConfig<CascadedExtnSymbol> for ExtnSymbol {
             self.fulfills.extend(fulfills);
             self.fulfills.dedup(); // Remove duplicates if needed
         }
+        if let Some(contract_versions) = cascaded.contract_versions {
+            self.contract_versions.extend(contract_versions);
+        }
         if let Some(cascaded_config) = cascaded.config {
             match &mut self.config {
                 Some(existing_config) => {
@@ -0,0 +1,376 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::api::{
+    distributor::distributor_privacy::DataEventType, storage_property::StorageProperty,
+};
+
+use super::device_manifest::{
+    ComplianceProfile, DataGovernancePolicy, DataGovernanceSettingTag, DefaultValues,
+    RippleConfiguration,
+};
+
+pub const FIELD_DATA_GOVERNANCE: &str = "dataGovernance";
+pub const FIELD_DIAGNOSTICS_RECENT_RECORDS: &str = "diagnosticsRecentRecords";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceDeviation {
+    pub field: String,
+    pub preset: Value,
+    pub configured: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceReport {
+    pub profile: Option<ComplianceProfile>,
+    pub deviations: Vec<ComplianceDeviation>,
+}
+
+/// Single configuration value preset by a compliance profile
+enum PresetField {
+    PrivacyDefault(StorageProperty, bool),
+    DataGovernance(Vec<DataGovernancePolicy>),
+    DiagnosticsRecentRecords(usize),
+}
+
+impl PresetField {
+    fn name(&self) -> String {
+        match self {
+            PresetField::PrivacyDefault(property, _) => property.as_data().key.to_owned(),
+            PresetField::DataGovernance(_) => FIELD_DATA_GOVERNANCE.to_owned(),
+            PresetField::DiagnosticsRecentRecords(_) => FIELD_DIAGNOSTICS_RECENT_RECORDS.to_owned(),
+        }
+    }
+
+    fn preset(&self) -> Value {
+        match self {
+            PresetField::PrivacyDefault(_, value) => json!(value),
+            PresetField::DataGovernance(policies) => json!(policies),
+            PresetField::DiagnosticsRecentRecords(records) => json!(records),
+        }
+    }
+
+    fn configured(&self, config: &RippleConfiguration) -> Value {
+        match self {
+            PresetField::PrivacyDefault(property, _) => json!(config
+                .default_values
+                .clone()
+                .get_privacy_default_mut(property)
+                .copied()),
+            PresetField::DataGovernance(_) => json!(config.data_governance.policies),
+            PresetField::DiagnosticsRecentRecords(_) => {
+                json!(config.diagnostics.recent_records)
+            }
+        }
+    }
+
+    fn apply(self, config: &mut RippleConfiguration) {
+        match self {
+            PresetField::PrivacyDefault(property, value) => {
+                if let Some(v) = config.default_values.get_privacy_default_mut(&property) {
+                    *v = value;
+                }
+            }
+            PresetField::DataGovernance(policies) => {
+                // policies of the manifest take precedence over the preset for their data type
+                let configured = &mut config.data_governance.policies;
+                for policy in policies {
+                    if !configured.iter().any(|p| p.data_type == policy.data_type) {
+                        configured.push(policy);
+                    }
+                }
+            }
+            PresetField::DiagnosticsRecentRecords(records) => {
+                config.diagnostics.recent_records = records
+            }
+        }
+    }
+}
+
+impl ComplianceProfile {
+    fn get_privacy_defaults(&self) -> Vec<(StorageProperty, bool)> {
+        use StorageProperty::*;
+        // GDPR requires an opt in for every setting, CCPA only for selling the data
+        let opt_out = matches!(self, ComplianceProfile::Ccpa);
+        vec![
+            (AllowAcrCollection, false),
+            (AllowCameraAnalytics, false),
+            (AllowAppContentAdTargeting, false),
+            (AllowPrimaryBrowseAdTargeting, false),
+            (AllowPrimaryContentAdTargeting, false),
+            (AllowBusinessAnalytics, opt_out),
+            (AllowProductAnalytics, opt_out),
+            (AllowRemoteDiagnostics, opt_out),
+            (AllowPersonalization, opt_out),
+            (AllowUnentitledPersonalization, opt_out),
+            (AllowResumePoints, opt_out),
+            (AllowUnentitledResumePoints, opt_out),
+            (AllowWatchHistory, opt_out),
+        ]
+    }
+
+    fn get_data_governance_policies(&self) -> Vec<DataGovernancePolicy> {
+        // GDPR drops the data without consent, CCPA forwards it tagged as not for sale
+        let drop_on_all_tags = matches!(self, ComplianceProfile::Gdpr);
+        let policy = |data_type, settings: Vec<(StorageProperty, &str)>| {
+            DataGovernancePolicy::new(
+                data_type,
+                settings
+                    .into_iter()
+                    .map(|(setting, tag)| {
+                        DataGovernanceSettingTag::new(
+                            setting,
+                            false,
+                            HashSet::from([tag.to_owned()]),
+                        )
+                    })
+                    .collect(),
+                drop_on_all_tags,
+            )
+        };
+        vec![
+            policy(
+                DataEventType::Watched,
+                vec![
+                    (
+                        StorageProperty::AllowPersonalization,
+                        "dataPlatform:personalization",
+                    ),
+                    (
+                        StorageProperty::AllowWatchHistory,
+                        "dataPlatform:watchHistory",
+                    ),
+                ],
+            ),
+            policy(
+                DataEventType::BusinessIntelligence,
+                vec![(
+                    StorageProperty::AllowBusinessAnalytics,
+                    "dataPlatform:businessAnalytics",
+                )],
+            ),
+            policy(
+                DataEventType::RemoteDiagnostics,
+                vec![(
+                    StorageProperty::AllowRemoteDiagnostics,
+                    "dataPlatform:remoteDiagnostics",
+                )],
+            ),
+        ]
+    }
+
+    fn get_preset(&self) -> Vec<PresetField> {
+        let mut preset: Vec<PresetField> = self
+            .get_privacy_defaults()
+            .into_iter()
+            .map(|(property, value)| PresetField::PrivacyDefault(property, value))
+            .collect();
+        preset.push(PresetField::DataGovernance(
+            self.get_data_governance_policies(),
+        ));
+        // GDPR keeps no diagnostic records on the device for the remote bundle
+        preset.push(PresetField::DiagnosticsRecentRecords(match self {
+            ComplianceProfile::Gdpr => 0,
+            ComplianceProfile::Ccpa => 100,
+        }));
+        preset
+    }
+}
+
+impl DefaultValues {
+    pub fn get_privacy_default_mut(&mut self, property: &StorageProperty) -> Option<&mut bool> {
+        let value = match property {
+            StorageProperty::AllowAcrCollection => &mut self.allow_acr_collection,
+            StorageProperty::AllowAppContentAdTargeting => &mut self.allow_app_content_ad_targeting,
+            StorageProperty::AllowBusinessAnalytics => &mut self.allow_business_analytics,
+            StorageProperty::AllowCameraAnalytics => &mut self.allow_camera_analytics,
+            StorageProperty::AllowPersonalization => &mut self.allow_personalization,
+            StorageProperty::AllowPrimaryBrowseAdTargeting => {
+                &mut self.allow_primary_browse_ad_targeting
+            }
+            StorageProperty::AllowPrimaryContentAdTargeting => {
+                &mut self.allow_primary_content_ad_targeting
+            }
+            StorageProperty::AllowProductAnalytics => &mut self.allow_product_analytics,
+            StorageProperty::AllowRemoteDiagnostics => &mut self.allow_remote_diagnostics,
+            StorageProperty::AllowResumePoints => &mut self.allow_resume_points,
+            StorageProperty::AllowUnentitledPersonalization => {
+                &mut self.allow_unentitled_personalization
+            }
+            StorageProperty::AllowUnentitledResumePoints => {
+                &mut self.allow_unentitled_resume_points
+            }
+            StorageProperty::AllowWatchHistory => &mut self.allow_watch_history,
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+impl RippleConfiguration {
+    /// Profile selected in the manifest, either explicitly or for the country code of the
+    /// device. The fixed region of the manifest is used as the country code as the profile is
+    /// applied before any other region source is available.
+    fn get_compliance_profile(&self) -> Option<ComplianceProfile> {
+        let country_code = self
+            .region
+            .fixed
+            .country_code
+            .as_deref()
+            .unwrap_or(&self.default_values.country_code);
+        self.compliance.get_profile(country_code)
+    }
+
+    /// Applies the preset of the compliance profile, fields listed as exceptions keep the
+    /// value from the manifest
+    pub fn apply_compliance_profile(&mut self) {
+        let profile = match self.get_compliance_profile() {
+            Some(profile) => profile,
+            None => return,
+        };
+        let exceptions = self.compliance.exceptions.clone();
+        for field in profile.get_preset() {
+            if !exceptions.contains(&field.name()) {
+                field.apply(self);
+            }
+        }
+    }
+
+    /// Reports the active compliance profile along with every field which deviates from it
+    pub fn get_compliance_report(&self) -> ComplianceReport {
+        let profile = match self.get_compliance_profile() {
+            Some(profile) => profile,
+            None => return ComplianceReport::default(),
+        };
+        let deviations = profile
+            .get_preset()
+            .into_iter()
+            .filter_map(|field| {
+                let preset = field.preset();
+                let configured = field.configured(self);
+                (preset != configured).then(|| ComplianceDeviation {
+                    field: field.name(),
+                    preset,
+                    configured,
+                })
+            })
+            .collect();
+        ComplianceReport {
+            profile: Some(profile),
+            deviations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::manifest::device_manifest::ComplianceConfiguration;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_apply_compliance_profile() {
+        let mut config = RippleConfiguration::default();
+        config.default_values.allow_personalization = true;
+        config.default_values.allow_watch_history = true;
+        config.compliance = ComplianceConfiguration {
+            profile: Some(ComplianceProfile::Gdpr),
+            exceptions: vec!["allowWatchHistory".to_owned()],
+            ..Default::default()
+        };
+        config.apply_compliance_profile();
+
+        assert!(!config.default_values.allow_personalization);
+        assert!(!config.default_values.allow_business_analytics);
+        assert_eq!(config.diagnostics.recent_records, 0);
+        assert_eq!(config.data_governance.policies.len(), 3);
+
+        let report = config.get_compliance_report();
+        assert_eq!(report.profile, Some(ComplianceProfile::Gdpr));
+        assert_eq!(
+            report.deviations,
+            vec![ComplianceDeviation {
+                field: "allowWatchHistory".to_owned(),
+                preset: json!(false),
+                configured: json!(true),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_compliance_profile_keeps_manifest_policies() {
+        let mut config = RippleConfiguration::default();
+        config.data_governance.policies = vec![DataGovernancePolicy::new(
+            DataEventType::Watched,
+            vec![],
+            false,
+        )];
+        config.compliance.profile = Some(ComplianceProfile::Gdpr);
+        config.apply_compliance_profile();
+
+        assert_eq!(config.data_governance.policies.len(), 3);
+        let watched = config
+            .data_governance
+            .get_policy(DataEventType::Watched)
+            .unwrap();
+        assert!(watched.setting_tags.is_empty());
+        assert!(!watched.drop_on_all_tags);
+        let report = config.get_compliance_report();
+        assert!(report
+            .deviations
+            .iter()
+            .any(|d| d.field == FIELD_DATA_GOVERNANCE));
+    }
+
+    #[test]
+    fn test_compliance_profile_by_region() {
+        let mut config = RippleConfiguration::default();
+        config.compliance.regions = HashMap::from([
+            ("GB".to_owned(), ComplianceProfile::Gdpr),
+            ("US".to_owned(), ComplianceProfile::Ccpa),
+        ]);
+        assert_eq!(
+            config.get_compliance_report().profile,
+            Some(ComplianceProfile::Ccpa)
+        );
+
+        config.region.fixed.country_code = Some("gb".to_owned());
+        config.apply_compliance_profile();
+        assert_eq!(config.diagnostics.recent_records, 0);
+        assert_eq!(
+            config.get_compliance_report().profile,
+            Some(ComplianceProfile::Gdpr)
+        );
+
+        config.region.fixed.country_code = Some("FR".to_owned());
+        assert_eq!(config.get_compliance_report().profile, None);
+    }
+
+    #[test]
+    fn test_compliance_report_without_profile() {
+        let mut config = RippleConfiguration::default();
+        config.apply_compliance_profile();
+        assert_eq!(config.get_compliance_report(), ComplianceReport::default());
+    }
+}
@@ -20,21 +20,27 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{
     collections::{HashMap, HashSet},
-    fs,
     path::Path,
+    time::Duration,
 };
 
 use crate::{
     api::{
         device::device_user_grants_data::{GrantExclusionFilter, GrantPolicies},
         distributor::distributor_privacy::DataEventType,
-        firebolt::fb_capabilities::FireboltPermission,
+        firebolt::{
+            fb_capabilities::FireboltPermission, fb_keyboard::KEYBOARD_PROVIDER_CAPABILITY,
+            provider::ACK_CHALLENGE_CAPABILITY,
+        },
         storage_property::StorageProperty,
     },
     utils::error::RippleError,
 };
 
-use super::{apps::AppManifest, exclusory::ExclusoryImpl};
+use super::{
+    apps::AppManifest, exclusory::ExclusoryImpl, manifest_source::ManifestSource,
+    remote_feature::FeatureFlag,
+};
 pub const PARTNER_EXCLUSION_REFRESH_TIMEOUT: u32 = 12 * 60 * 60; // 12 hours
 pub const METRICS_LOGGING_PERCENTAGE_DEFAULT: u32 = 10;
 
@@ -70,6 +76,88 @@ pub struct RippleConfiguration {
     pub metrics_logging_percentage: u32,
     #[serde(default)]
     pub internet_monitoring_configuration: InternetMonitoringConfiguration,
+    #[serde(default)]
+    pub service_launcher: ServiceLauncherConfiguration,
+    #[serde(default)]
+    pub service_takeover_policies: HashMap<String, ServiceTakeoverPolicy>,
+    #[serde(default)]
+    pub admin: AdminConfiguration,
+    #[serde(default)]
+    pub dbus_bridge: DbusBridgeConfiguration,
+    #[serde(default)]
+    pub app_library_refresh: AppLibraryRefreshConfiguration,
+    #[serde(default)]
+    pub entitlements_sync: EntitlementsSyncConfiguration,
+    #[serde(default)]
+    pub watch_history_upload: WatchHistoryUploadConfiguration,
+    #[serde(default)]
+    pub heartbeat: HeartbeatConfiguration,
+    #[serde(default)]
+    pub diagnostics: DiagnosticsConfiguration,
+    #[serde(default)]
+    pub http_bridge: HttpBridgeConfiguration,
+    #[serde(default)]
+    pub method_overrides: MethodOverridesConfiguration,
+    #[serde(default)]
+    pub capability_usage: CapabilityUsageConfiguration,
+    #[serde(default)]
+    pub activation: ActivationConfiguration,
+    #[serde(default)]
+    pub inactivity: InactivityConfiguration,
+    #[serde(default)]
+    pub input: InputConfiguration,
+    #[serde(default)]
+    pub audio_focus: AudioFocusConfiguration,
+    #[serde(default)]
+    pub drm: DrmConfiguration,
+    #[serde(default)]
+    pub compliance: ComplianceConfiguration,
+    #[serde(default)]
+    pub notification_policy: NotificationPolicyConfiguration,
+    #[serde(default)]
+    pub pending_requests: PendingRequestConfiguration,
+    #[serde(default)]
+    pub runtime_topology: RuntimeTopologyConfiguration,
+    #[serde(default)]
+    pub deadlines: DeadlineConfiguration,
+    #[serde(default)]
+    pub shutdown: ShutdownConfiguration,
+    #[serde(default)]
+    pub jq_sandbox: JqSandboxConfiguration,
+    #[serde(default)]
+    pub event_transforms: Vec<EventTransform>,
+    #[serde(default)]
+    pub app_usage: AppUsageConfiguration,
+    #[serde(default)]
+    pub metrics_enrichment: MetricsEnrichmentConfiguration,
+    #[serde(default)]
+    pub error_budget: ErrorBudgetConfiguration,
+    #[serde(default)]
+    pub service_liveness: ServiceLivenessConfiguration,
+    #[serde(default)]
+    pub ad_config: AdConfigConfiguration,
+    #[serde(default)]
+    pub service_request_timeouts: ServiceRequestTimeoutConfiguration,
+    #[serde(default)]
+    pub gatekeeper_policy: GatekeeperPolicyConfiguration,
+    #[serde(default)]
+    pub service_http: ServiceHttpConfiguration,
+    #[serde(default)]
+    pub tenants: TenantsConfiguration,
+    #[serde(default)]
+    pub connection_limits: ConnectionLimitsConfiguration,
+    #[serde(default)]
+    pub region: RegionConfiguration,
+    #[serde(default)]
+    pub request_journal: RequestJournalConfiguration,
+    #[serde(default)]
+    pub service_auth: ServiceAuthConfiguration,
+    #[serde(default)]
+    pub service_capabilities: ServiceCapabilityPolicy,
+    #[serde(default)]
+    pub service_conflicts: ServiceConflictConfiguration,
+    #[serde(default)]
+    pub routing: RoutingConfiguration,
 }
 
 fn partner_exclusion_refresh_timeout_default() -> u32 {
@@ -203,16 +291,187 @@ pub struct ApplicationsConfiguration {
     pub distributor_app_aliases: HashMap<String, String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// Admission policy for app connections from a browser `Origin`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WsOriginPolicy {
+    /// Exact origin like `https://apps.example.com` or a `*.example.com` domain suffix
+    pub origin: String,
+    pub allow: bool,
+}
+
+impl WsOriginPolicy {
+    pub fn matches(&self, origin: &str) -> bool {
+        match self.origin.strip_prefix('*') {
+            Some(suffix) => origin.ends_with(suffix),
+            None => self.origin.eq(origin),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct WsConfiguration {
     pub enabled: bool,
     pub gateway: String,
+    /// More addresses the listener binds to, IPv6 link-local addresses take the interface
+    /// as scope like `[fe80::1%eth0]:3473`. Unlike the `gateway`, an additional address
+    /// which cannot be bound is skipped.
+    #[serde(default)]
+    pub additional_gateways: Vec<String>,
+    /// `FileDescriptorName` of a listening socket passed through systemd socket activation,
+    /// the inherited socket is used instead of binding the gateway addresses
+    #[serde(default)]
+    pub socket_activation_name: Option<String>,
+    /// Subprotocols accepted in `Sec-WebSocket-Protocol`, in order of preference
+    #[serde(default = "ws_subprotocols_default")]
+    pub subprotocols: Vec<String>,
+    /// Origin policies, the first matching policy decides the connection
+    #[serde(default)]
+    pub origin_policies: Vec<WsOriginPolicy>,
+    /// Rejects origins which dont match any policy. Connections without an `Origin`
+    /// header are not from a browser and are always accepted.
+    #[serde(default)]
+    pub reject_unknown_origins: bool,
+    /// Ping keepalive and idle timeouts for app connections
+    #[serde(default)]
+    pub keepalive: WsKeepaliveConfiguration,
+    /// Terminates TLS on the listener, which then only accepts `wss://` connections
+    #[serde(default)]
+    pub tls: Option<WsTlsConfiguration>,
+}
+
+/// PEM certificate chain and private key of a `wss://` listener. Client certificates are
+/// requested and verified against `client_ca_path` when it is set, their fingerprints can be
+/// used in the service allowlist of [ServiceAuthConfiguration].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WsTlsConfiguration {
+    pub cert_path: String,
+    pub key_path: String,
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+}
+
+impl Default for WsConfiguration {
+    fn default() -> Self {
+        WsConfiguration {
+            enabled: false,
+            gateway: String::default(),
+            additional_gateways: Vec::new(),
+            socket_activation_name: None,
+            subprotocols: ws_subprotocols_default(),
+            origin_policies: Vec::new(),
+            reject_unknown_origins: false,
+            keepalive: WsKeepaliveConfiguration::default(),
+            tls: None,
+        }
+    }
+}
+
+/// Lists the gateway followed by the additional gateways a listener binds to
+fn get_gateways(gateway: &str, additional_gateways: &[String]) -> Vec<String> {
+    let mut gateways = vec![gateway.to_owned()];
+    gateways.extend(additional_gateways.iter().cloned());
+    gateways
+}
+
+impl WsConfiguration {
+    pub fn get_gateways(&self) -> Vec<String> {
+        get_gateways(&self.gateway, &self.additional_gateways)
+    }
+
+    /// Picks the accepted subprotocol for the `Sec-WebSocket-Protocol` header of the client,
+    /// the order of the client is used as preference.
+    pub fn negotiate_subprotocol(&self, requested: &str) -> Option<String> {
+        requested
+            .split(',')
+            .map(|p| p.trim())
+            .find(|p| self.subprotocols.iter().any(|s| s.eq(p)))
+            .map(|p| p.to_owned())
+    }
+
+    pub fn is_origin_allowed(&self, origin: Option<&str>) -> bool {
+        let origin = match origin {
+            Some(origin) => origin,
+            None => return true,
+        };
+        match self.origin_policies.iter().find(|p| p.matches(origin)) {
+            Some(policy) => policy.allow,
+            None => !self.reject_unknown_origins,
+        }
+    }
+}
+
+/// Keepalive for app connections, so half-open sockets left behind by apps on flaky
+/// networks dont keep holding their sessions.
+///
+/// A ping is sent every `ping_interval_ms`, the connection is declared dead when
+/// `max_missed_pongs` pings in a row go unanswered. Apps can also be closed after a period
+/// without any Firebolt request, with the idle timeout of the first app class listing the
+/// app or the default `idle_timeout_ms`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WsKeepaliveConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "keepalive_ping_interval_ms_default")]
+    pub ping_interval_ms: u64,
+    #[serde(default = "keepalive_max_missed_pongs_default")]
+    pub max_missed_pongs: u32,
+    #[serde(default)]
+    pub idle_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub app_classes: Vec<WsKeepaliveAppClass>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WsKeepaliveAppClass {
+    pub name: String,
+    pub app_ids: Vec<String>,
+    /// Apps of the class are never closed for being idle when not set
+    #[serde(default)]
+    pub idle_timeout_ms: Option<u64>,
+}
+
+fn keepalive_ping_interval_ms_default() -> u64 {
+    15000
+}
+
+fn keepalive_max_missed_pongs_default() -> u32 {
+    3
+}
+
+impl Default for WsKeepaliveConfiguration {
+    fn default() -> Self {
+        WsKeepaliveConfiguration {
+            enabled: false,
+            ping_interval_ms: keepalive_ping_interval_ms_default(),
+            max_missed_pongs: keepalive_max_missed_pongs_default(),
+            idle_timeout_ms: None,
+            app_classes: Vec::new(),
+        }
+    }
+}
+
+impl WsKeepaliveConfiguration {
+    pub fn get_idle_timeout_ms(&self, app_id: &str) -> Option<u64> {
+        match self
+            .app_classes
+            .iter()
+            .find(|c| c.app_ids.iter().any(|a| a.eq(app_id)))
+        {
+            Some(app_class) => app_class.idle_timeout_ms,
+            None => self.idle_timeout_ms,
+        }
+    }
+}
+
+fn ws_subprotocols_default() -> Vec<String> {
+    vec!["jsonrpc".into()]
 }
 
 pub fn ws_configuration_default() -> WsConfiguration {
     WsConfiguration {
         enabled: true,
         gateway: "127.0.0.1:3473".into(),
+        ..Default::default()
     }
 }
 
@@ -220,6 +479,7 @@ pub fn ws_configuration_internal_default() -> WsConfiguration {
     WsConfiguration {
         enabled: true,
         gateway: "127.0.0.1:3474".into(),
+        ..Default::default()
     }
 }
 
@@ -281,6 +541,25 @@ pub struct AppLibraryEntry {
     pub app_id: String,
     pub manifest: AppManifestLoad,
     pub boot_state: BootState,
+    #[serde(default, skip_serializing_if = "SessionPriority::is_normal")]
+    pub session_priority: SessionPriority,
+}
+
+/// Priority class of the sessions of an app. Critical apps like the launcher and settings
+/// can preempt idle sessions of lower priority when the session cap is reached.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionPriority {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+impl SessionPriority {
+    pub fn is_normal(&self) -> bool {
+        *self == SessionPriority::Normal
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -380,7 +659,10 @@ fn country_postal_code_default() -> HashMap<String, String> {
 }
 
 pub fn data_governance_default() -> DataGovernanceConfig {
-    DataGovernanceConfig { policies: vec![] }
+    DataGovernanceConfig {
+        policies: vec![],
+        metrics_consent: vec![],
+    }
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -548,6 +830,10 @@ pub struct RippleFeatures {
     pub cloud_permissions: bool,
     #[serde(default = "default_thunder_plugin_status_check_at_broker_start_up")]
     pub thunder_plugin_status_check_at_broker_start_up: bool,
+    /// Lets calls with ungranted capabilities go through with a warning. Only honored in
+    /// local_dev and pre_prod builds.
+    #[serde(default)]
+    pub developer_mode: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -564,6 +850,8 @@ fn default_saved_dir() -> String {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DataGovernanceConfig {
     pub policies: Vec<DataGovernancePolicy>,
+    #[serde(default)]
+    pub metrics_consent: Vec<MetricsCategoryConsent>,
 }
 
 impl DataGovernanceConfig {
@@ -573,6 +861,22 @@ impl DataGovernanceConfig {
             .find(|p| p.data_type == data_type)
             .cloned()
     }
+
+    pub fn get_metrics_consent(&self, event: &str) -> Option<&MetricsCategoryConsent> {
+        self.metrics_consent
+            .iter()
+            .find(|c| c.events.iter().any(|e| e.eq(event)))
+    }
+}
+
+/// Category of telemetry events which is only emitted while the user consents to it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MetricsCategoryConsent {
+    pub category: String,
+    /// Names of the events in the telemetry schema registry
+    pub events: Vec<String>,
+    /// Privacy settings which all need to be allowed for the category to be emitted
+    pub settings: Vec<StorageProperty>,
 }
 
 impl Default for RippleFeatures {
@@ -583,6 +887,7 @@ impl Default for RippleFeatures {
             cloud_permissions: default_cloud_permissions(),
             thunder_plugin_status_check_at_broker_start_up:
                 default_thunder_plugin_status_check_at_broker_start_up(),
+            developer_mode: false,
         }
     }
 }
@@ -664,113 +969,2028 @@ impl Default for InternetMonitoringConfiguration {
     }
 }
 
-impl Default for RippleConfiguration {
-    fn default() -> Self {
-        Self {
-            ws_configuration: Default::default(),
-            internal_ws_configuration: Default::default(),
-            platform_parameters: Value::Null,
-            distribution_id_salt: None,
-            form_factor: Default::default(),
-            default_values: DefaultValues::default(),
-            model_friendly_names: Default::default(),
-            distributor_experience_id: Default::default(),
-            distributor_services: None,
-            exclusory: None,
-            features: Default::default(),
-            internal_app_id: None,
-            saved_dir: default_saved_dir(),
-            data_governance: data_governance_default(),
-            partner_exclusion_refresh_timeout: partner_exclusion_refresh_timeout_default(),
-            metrics_logging_percentage: metrics_logging_percentage_default(),
-            internet_monitoring_configuration: Default::default(),
-            log_signal_log_level: log_signal_default_level(),
-        }
-    }
+/// Roles for the admin API, each role includes the access of the roles before it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum AdminRole {
+    ReadOnly,
+    Operator,
+    Developer,
 }
 
-impl DeviceManifest {
-    pub fn load(path: String) -> Result<(String, DeviceManifest), RippleError> {
-        info!("Trying to load device manifest from path={}", path);
-        if let Some(p) = Path::new(&path).to_str() {
-            if let Ok(contents) = fs::read_to_string(p) {
-                info!("Loaded device manifest from {}", path);
-                return Self::load_from_content(contents);
-            }
-        }
-        info!("No device manifest found in {}", path);
-        Err(RippleError::MissingInput)
-    }
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct AdminToken {
+    pub token: String,
+    pub role: AdminRole,
+}
 
-    pub fn load_from_content(contents: String) -> Result<(String, DeviceManifest), RippleError> {
-        match serde_json::from_str::<DeviceManifest>(&contents) {
-            Ok(manifest) => Ok((contents, manifest)),
-            Err(err) => {
-                warn!("{:?} could not load device manifest", err);
-                Err(RippleError::InvalidInput)
-            }
-        }
-    }
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct AdminConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub tokens: Vec<AdminToken>,
+    /// Overrides the role required by an admin method
+    #[serde(default)]
+    pub method_roles: HashMap<String, AdminRole>,
+    /// Firebolt version manifest which the payloads of `ripple.injectEvent` are validated
+    /// against when Ripple is built with `openrpc_validation`
+    #[serde(default)]
+    pub event_schema_path: Option<String>,
+}
 
-    pub fn get_web_socket_enabled(&self) -> bool {
-        self.configuration.ws_configuration.enabled
-    }
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum DbusBusType {
+    #[default]
+    System,
+    Session,
+}
 
-    pub fn get_internal_ws_enabled(&self) -> bool {
-        self.configuration.internal_ws_configuration.enabled
-    }
+/// Maps a Firebolt event onto a DBus signal member
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DbusSignalMapping {
+    pub event: String,
+    pub signal: String,
+}
 
-    pub fn get_ws_gateway_host(&self) -> String {
-        self.configuration.ws_configuration.gateway.clone()
-    }
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DbusBridgeConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub bus: DbusBusType,
+    #[serde(default = "dbus_object_path_default")]
+    pub object_path: String,
+    #[serde(default = "dbus_interface_default")]
+    pub interface: String,
+    #[serde(default)]
+    pub mappings: Vec<DbusSignalMapping>,
+}
 
-    pub fn get_internal_gateway_host(&self) -> String {
-        self.configuration.internal_ws_configuration.gateway.clone()
-    }
+fn dbus_object_path_default() -> String {
+    "/com/rdk/Ripple".into()
+}
 
-    pub fn get_internal_app_id(&self) -> Option<String> {
-        self.configuration.internal_app_id.clone()
-    }
+fn dbus_interface_default() -> String {
+    "com.rdk.Ripple.Events".into()
+}
 
-    pub fn get_form_factor(&self) -> String {
-        self.configuration.form_factor.clone()
+impl Default for DbusBridgeConfiguration {
+    fn default() -> Self {
+        DbusBridgeConfiguration {
+            enabled: false,
+            bus: DbusBusType::default(),
+            object_path: dbus_object_path_default(),
+            interface: dbus_interface_default(),
+            mappings: Vec::new(),
+        }
     }
+}
 
-    /// Get path to the app library file
-    pub fn get_app_library_path(&self) -> String {
-        self.applications.distribution.library.clone()
+impl DbusBridgeConfiguration {
+    pub fn get_signal(&self, event: &str) -> Option<String> {
+        self.mappings
+            .iter()
+            .find(|m| m.event.eq(event))
+            .map(|m| m.signal.clone())
     }
+}
 
-    pub fn get_lifecycle_policy(&self) -> LifecyclePolicy {
-        LifecyclePolicy {
-            app_ready_timeout_ms: self.lifecycle.app_ready_timeout_ms,
-            app_finished_timeout_ms: self.lifecycle.app_finished_timeout_ms,
-        }
-    }
+/// Where the app library is sourced from when the refresh is enabled
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AppLibrarySourceType {
+    /// Only the app library file on the device
+    Local,
+    /// The distributor endpoint, the local file is used until a fresh copy is available
+    #[default]
+    Cloud,
+    /// The local file with the apps from the distributor endpoint layered on top
+    Hybrid,
+}
 
-    pub fn get_retention_policy(&self) -> RetentionPolicy {
-        RetentionPolicy {
-            max_retained: self.lifecycle.max_loaded_apps,
-            min_available_mem_kb: self.lifecycle.min_available_memory_kb,
-            always_retained: self.lifecycle.prioritized.clone(),
-        }
-    }
+/// Refreshes the app library at runtime from a distributor endpoint. A saved copy older
+/// than `max_age_seconds` is not used to boot.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct AppLibraryRefreshConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub source: AppLibrarySourceType,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default = "app_library_refresh_interval_default")]
+    pub interval_seconds: u64,
+    #[serde(default)]
+    pub max_age_seconds: Option<u64>,
+}
 
-    pub fn get_supported_caps(&self) -> Vec<FireboltPermission> {
-        let supported = self.clone().capabilities.supported;
-        let role_based_support = self.configuration.default_values.role_based_support;
-        FireboltPermission::from_vec_string(supported, role_based_support)
-    }
+fn app_library_refresh_interval_default() -> u64 {
+    3600
+}
 
-    pub fn get_caps_requiring_grant(&self) -> Vec<String> {
-        if let Some(policies) = self.clone().capabilities.grant_policies {
-            return policies.into_keys().collect();
+impl Default for AppLibraryRefreshConfiguration {
+    fn default() -> Self {
+        AppLibraryRefreshConfiguration {
+            enabled: false,
+            source: AppLibrarySourceType::default(),
+            url: String::default(),
+            interval_seconds: app_library_refresh_interval_default(),
+            max_age_seconds: None,
         }
-        Vec::new()
-    }
-
-    pub fn get_grant_policies(&self) -> Option<HashMap<String, GrantPolicies>> {
-        self.clone().capabilities.grant_policies
+    }
+}
+
+/// Synchronizes the account entitlements from the distributor on a schedule
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct EntitlementsSyncConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "entitlements_sync_interval_default")]
+    pub interval_seconds: u64,
+}
+
+fn entitlements_sync_interval_default() -> u64 {
+    900
+}
+
+impl Default for EntitlementsSyncConfiguration {
+    fn default() -> Self {
+        EntitlementsSyncConfiguration {
+            enabled: false,
+            interval_seconds: entitlements_sync_interval_default(),
+        }
+    }
+}
+
+/// Batches `discovery.watched` and `discovery.watchNext` writes before pushing them upstream
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct WatchHistoryUploadConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default = "watch_history_batch_size_default")]
+    pub batch_size: usize,
+    #[serde(default = "watch_history_flush_interval_default")]
+    pub flush_interval_ms: u64,
+    /// Items kept while the endpoint is unreachable, the oldest are dropped beyond it
+    #[serde(default = "watch_history_max_queued_items_default")]
+    pub max_queued_items: usize,
+}
+
+fn watch_history_batch_size_default() -> usize {
+    20
+}
+
+fn watch_history_flush_interval_default() -> u64 {
+    30000
+}
+
+fn watch_history_max_queued_items_default() -> usize {
+    500
+}
+
+impl Default for WatchHistoryUploadConfiguration {
+    fn default() -> Self {
+        WatchHistoryUploadConfiguration {
+            enabled: false,
+            url: String::default(),
+            batch_size: watch_history_batch_size_default(),
+            flush_interval_ms: watch_history_flush_interval_default(),
+            max_queued_items: watch_history_max_queued_items_default(),
+        }
+    }
+}
+
+/// Fields which can be included in the periodic device health event
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DeviceHealthField {
+    Uptime,
+    Memory,
+    BrokerHealth,
+    Reconnects,
+    ErrorRate,
+    ExtnUsage,
+    AppUsage,
+    ServiceConnections,
+}
+
+/// Periodic device health telemetry sent through the metrics pipeline
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct HeartbeatConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "heartbeat_interval_default")]
+    pub interval_seconds: u64,
+    #[serde(default = "heartbeat_fields_default")]
+    pub fields: Vec<DeviceHealthField>,
+}
+
+fn heartbeat_interval_default() -> u64 {
+    300
+}
+
+fn heartbeat_fields_default() -> Vec<DeviceHealthField> {
+    vec![
+        DeviceHealthField::Uptime,
+        DeviceHealthField::Memory,
+        DeviceHealthField::BrokerHealth,
+        DeviceHealthField::Reconnects,
+        DeviceHealthField::ErrorRate,
+    ]
+}
+
+impl Default for HeartbeatConfiguration {
+    fn default() -> Self {
+        HeartbeatConfiguration {
+            enabled: false,
+            interval_seconds: heartbeat_interval_default(),
+            fields: heartbeat_fields_default(),
+        }
+    }
+}
+
+impl HeartbeatConfiguration {
+    pub fn has_field(&self, field: DeviceHealthField) -> bool {
+        self.fields.contains(&field)
+    }
+}
+
+/// Limits for the diagnostic records apps push through `diagnostics.log`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DiagnosticsConfiguration {
+    #[serde(default = "diagnostics_max_records_per_minute_default")]
+    pub max_records_per_minute: u32,
+    #[serde(default = "diagnostics_max_message_length_default")]
+    pub max_message_length: usize,
+    /// Number of recent records kept for the remote diagnostics bundle
+    #[serde(default = "diagnostics_recent_records_default")]
+    pub recent_records: usize,
+}
+
+fn diagnostics_max_records_per_minute_default() -> u32 {
+    60
+}
+
+fn diagnostics_max_message_length_default() -> usize {
+    1024
+}
+
+fn diagnostics_recent_records_default() -> usize {
+    100
+}
+
+impl Default for DiagnosticsConfiguration {
+    fn default() -> Self {
+        DiagnosticsConfiguration {
+            max_records_per_minute: diagnostics_max_records_per_minute_default(),
+            max_message_length: diagnostics_max_message_length_default(),
+            recent_records: diagnostics_recent_records_default(),
+        }
+    }
+}
+
+/// HTTP endpoint which accepts single JSON-RPC requests for tooling which cant use websockets.
+/// Requests need an admin token with at least `min_role` and bodies larger than
+/// `max_body_bytes` are refused.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct HttpBridgeConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "http_bridge_gateway_default")]
+    pub gateway: String,
+    #[serde(default)]
+    pub additional_gateways: Vec<String>,
+    #[serde(default = "http_bridge_min_role_default")]
+    pub min_role: AdminRole,
+    #[serde(default = "http_bridge_timeout_default")]
+    pub timeout_ms: u64,
+    #[serde(default = "http_bridge_max_body_bytes_default")]
+    pub max_body_bytes: usize,
+}
+
+impl HttpBridgeConfiguration {
+    pub fn get_gateways(&self) -> Vec<String> {
+        get_gateways(&self.gateway, &self.additional_gateways)
+    }
+}
+
+fn http_bridge_gateway_default() -> String {
+    "127.0.0.1:3475".into()
+}
+
+fn http_bridge_min_role_default() -> AdminRole {
+    AdminRole::Developer
+}
+
+fn http_bridge_timeout_default() -> u64 {
+    10000
+}
+
+fn http_bridge_max_body_bytes_default() -> usize {
+    1048576
+}
+
+impl Default for HttpBridgeConfiguration {
+    fn default() -> Self {
+        HttpBridgeConfiguration {
+            enabled: false,
+            gateway: http_bridge_gateway_default(),
+            additional_gateways: Vec::new(),
+            min_role: http_bridge_min_role_default(),
+            timeout_ms: http_bridge_timeout_default(),
+            max_body_bytes: http_bridge_max_body_bytes_default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MethodOverrideAction {
+    /// Skips the capability availability checks for the method, permissions and grants still
+    /// apply
+    Allow,
+    /// Blocks the method for the app
+    Deny,
+}
+
+/// Operator override for a method called by a specific app, `*` matches every app.
+/// Overrides with a `flag` are only active while the feature flag is on.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct MethodOverride {
+    pub app_id: String,
+    pub method: String,
+    pub action: MethodOverrideAction,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub flag: Option<FeatureFlag>,
+}
+
+impl MethodOverride {
+    pub fn matches(&self, app_id: &str, method: &str) -> bool {
+        (self.app_id.eq("*") || self.app_id.eq(app_id)) && self.method.eq_ignore_ascii_case(method)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct MethodOverridesConfiguration {
+    #[serde(default)]
+    pub overrides: Vec<MethodOverride>,
+    #[serde(default = "method_overrides_reload_interval_default")]
+    pub reload_interval_seconds: u64,
+}
+
+fn method_overrides_reload_interval_default() -> u64 {
+    300
+}
+
+impl Default for MethodOverridesConfiguration {
+    fn default() -> Self {
+        MethodOverridesConfiguration {
+            overrides: Vec::new(),
+            reload_interval_seconds: method_overrides_reload_interval_default(),
+        }
+    }
+}
+
+/// Optional reaper which revokes app grants that were not used for `unused_grant_ttl_seconds`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct CapabilityUsageConfiguration {
+    #[serde(default)]
+    pub reaper_enabled: bool,
+    #[serde(default = "unused_grant_ttl_default")]
+    pub unused_grant_ttl_seconds: u64,
+    #[serde(default = "reaper_interval_default")]
+    pub reaper_interval_seconds: u64,
+}
+
+fn unused_grant_ttl_default() -> u64 {
+    30 * 24 * 60 * 60
+}
+
+fn reaper_interval_default() -> u64 {
+    60 * 60
+}
+
+impl Default for CapabilityUsageConfiguration {
+    fn default() -> Self {
+        CapabilityUsageConfiguration {
+            reaper_enabled: false,
+            unused_grant_ttl_seconds: unused_grant_ttl_default(),
+            reaper_interval_seconds: reaper_interval_default(),
+        }
+    }
+}
+
+/// Drives the device through activation on power-on, retrying each step with an exponential
+/// backoff bounded by `max_backoff_ms`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ActivationConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "activation_max_retries_default")]
+    pub max_retries: u32,
+    #[serde(default = "activation_initial_backoff_default")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "activation_max_backoff_default")]
+    pub max_backoff_ms: u64,
+}
+
+fn activation_max_retries_default() -> u32 {
+    5
+}
+
+fn activation_initial_backoff_default() -> u64 {
+    1000
+}
+
+fn activation_max_backoff_default() -> u64 {
+    60000
+}
+
+impl Default for ActivationConfiguration {
+    fn default() -> Self {
+        ActivationConfiguration {
+            enabled: false,
+            max_retries: activation_max_retries_default(),
+            initial_backoff_ms: activation_initial_backoff_default(),
+            max_backoff_ms: activation_max_backoff_default(),
+        }
+    }
+}
+
+/// Idle detection based on the last user input or app lifecycle activity. Background apps
+/// are suspended once the device was idle for `suspend_background_after_seconds`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct InactivityConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "idle_threshold_default")]
+    pub idle_threshold_seconds: u64,
+    #[serde(default)]
+    pub suspend_background_after_seconds: Option<u64>,
+}
+
+fn idle_threshold_default() -> u64 {
+    10 * 60
+}
+
+impl Default for InactivityConfiguration {
+    fn default() -> Self {
+        InactivityConfiguration {
+            enabled: false,
+            idle_threshold_seconds: idle_threshold_default(),
+            suspend_background_after_seconds: None,
+        }
+    }
+}
+
+/// Translates the platform key codes reported by the device extension into Firebolt key
+/// names, so custom remotes can be supported by configuration. Keys listed in
+/// `restricted_keys` are only forwarded to apps with the manage role of `input:keys`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct InputConfiguration {
+    #[serde(default)]
+    pub key_map: HashMap<String, String>,
+    #[serde(default)]
+    pub forward_unmapped: bool,
+    #[serde(default)]
+    pub restricted_keys: Vec<String>,
+}
+
+impl InputConfiguration {
+    /// Returns the Firebolt key name for the platform key code, unmapped codes are passed
+    /// through as is only when `forward_unmapped` is set.
+    pub fn get_key_name(&self, code: &str) -> Option<String> {
+        match self.key_map.get(code) {
+            Some(key) => Some(key.clone()),
+            None if self.forward_unmapped => Some(code.to_owned()),
+            None => None,
+        }
+    }
+
+    pub fn is_restricted(&self, key: &str) -> bool {
+        self.restricted_keys.iter().any(|k| k.eq(key))
+    }
+}
+
+/// Audio focus policy, apps in `priority_apps` (e.g. the voice assistant) can take focus
+/// without being in the foreground and can not be preempted by other apps.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct AudioFocusConfiguration {
+    #[serde(default)]
+    pub priority_apps: Vec<String>,
+    #[serde(default = "default_require_foreground")]
+    pub require_foreground: bool,
+}
+
+fn default_require_foreground() -> bool {
+    true
+}
+
+impl Default for AudioFocusConfiguration {
+    fn default() -> Self {
+        AudioFocusConfiguration {
+            priority_apps: Vec::new(),
+            require_foreground: default_require_foreground(),
+        }
+    }
+}
+
+impl AudioFocusConfiguration {
+    pub fn is_priority_app(&self, app_id: &str) -> bool {
+        self.priority_apps.iter().any(|a| a.eq(app_id))
+    }
+}
+
+/// License request settings per DRM system, keyed by the system name reported in
+/// `device.drms` (e.g. `widevine`, `playready`).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DrmConfiguration {
+    #[serde(default)]
+    pub systems: HashMap<String, DrmSystemConfiguration>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DrmSystemConfiguration {
+    /// Replaces the license server url requested by the app
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// Headers added to the license requests for the `proxy_url`, e.g. entitlement headers
+    /// of the distributor. Without a proxy they are only added for the `allowed_hosts`.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// License server hosts which may receive the `headers` when no proxy is configured
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Passes the license request to the distributor broker hook for further decoration
+    #[serde(default)]
+    pub hook: bool,
+    /// Headers which only the distributor sets, e.g. the ones added by the hook. They are
+    /// removed from the request of the app like the configured `headers`.
+    #[serde(default)]
+    pub strip_headers: Vec<String>,
+}
+
+impl DrmSystemConfiguration {
+    pub fn is_allowed_host(&self, host: &str) -> bool {
+        self.allowed_hosts
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+
+    pub fn is_reserved_header(&self, name: &str) -> bool {
+        self.headers
+            .keys()
+            .chain(self.strip_headers.iter())
+            .any(|reserved| reserved.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComplianceProfile {
+    Gdpr,
+    Ccpa,
+}
+
+/// Regional compliance profile which presets the privacy defaults, the data governance
+/// policies and the diagnostics retention. `profile` selects the profile explicitly, otherwise
+/// it is looked up in `regions` by the country code of the device. Data governance policies
+/// configured in the manifest take precedence over the preset for their data type, fields
+/// listed in `exceptions` keep the value of the manifest. Both are reported as deviations
+/// from the profile.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ComplianceConfiguration {
+    #[serde(default)]
+    pub profile: Option<ComplianceProfile>,
+    /// Profile by country code
+    #[serde(default)]
+    pub regions: HashMap<String, ComplianceProfile>,
+    #[serde(default)]
+    pub exceptions: Vec<String>,
+}
+
+impl ComplianceConfiguration {
+    pub fn get_profile(&self, country_code: &str) -> Option<ComplianceProfile> {
+        self.profile.or_else(|| {
+            self.regions
+                .iter()
+                .find(|(region, _)| region.eq_ignore_ascii_case(country_code))
+                .map(|(_, profile)| *profile)
+        })
+    }
+}
+
+/// Do not disturb policy for provider driven overlays. Requests for the
+/// `deferred_capabilities` are held back while do not disturb is active and invoked once it
+/// ends. Requests beyond `max_deferred_requests` are rejected and requests still waiting after
+/// `max_deferred_ms` fail.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct NotificationPolicyConfiguration {
+    #[serde(default = "default_deferred_capabilities")]
+    pub deferred_capabilities: Vec<String>,
+    #[serde(default = "default_max_deferred_requests")]
+    pub max_deferred_requests: usize,
+    #[serde(default = "default_max_deferred_ms")]
+    pub max_deferred_ms: u64,
+}
+
+fn default_deferred_capabilities() -> Vec<String> {
+    vec![
+        KEYBOARD_PROVIDER_CAPABILITY.to_owned(),
+        ACK_CHALLENGE_CAPABILITY.to_owned(),
+    ]
+}
+
+fn default_max_deferred_requests() -> usize {
+    5
+}
+
+fn default_max_deferred_ms() -> u64 {
+    300_000
+}
+
+impl Default for NotificationPolicyConfiguration {
+    fn default() -> Self {
+        NotificationPolicyConfiguration {
+            deferred_capabilities: default_deferred_capabilities(),
+            max_deferred_requests: default_max_deferred_requests(),
+            max_deferred_ms: default_max_deferred_ms(),
+        }
+    }
+}
+
+impl NotificationPolicyConfiguration {
+    pub fn is_deferred(&self, capability: &str) -> bool {
+        self.deferred_capabilities.iter().any(|c| c.eq(capability))
+    }
+}
+
+/// Methods which wait on a provider app or on the user, they are never failed as stuck
+const PENDING_REQUEST_EXEMPT_METHODS: &[&str] = &[
+    "keyboard.*",
+    "pinchallenge.*",
+    "acknowledgechallenge.*",
+    "content.entity",
+    "content.purchases",
+    "usergrants.request",
+];
+
+/// Opt-in tracking of the in-flight app requests. A request still pending after
+/// `stuck_multiplier` times `request_timeout_ms`, or after its entry in
+/// `method_thresholds_ms`, is considered stuck, it is failed and its correlation entries are
+/// freed. Methods waiting on a provider or on the user prompts are exempt, more can be listed
+/// in `excluded_methods`. Methods can be given as `module.*`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct PendingRequestConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_pending_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+    #[serde(default = "default_stuck_multiplier")]
+    pub stuck_multiplier: u32,
+    #[serde(default = "default_stuck_check_interval_seconds")]
+    pub check_interval_seconds: u64,
+    #[serde(default)]
+    pub excluded_methods: Vec<String>,
+    #[serde(default)]
+    pub method_thresholds_ms: HashMap<String, u64>,
+}
+
+fn default_pending_request_timeout_ms() -> u64 {
+    10000
+}
+
+fn default_stuck_multiplier() -> u32 {
+    6
+}
+
+fn default_stuck_check_interval_seconds() -> u64 {
+    30
+}
+
+impl Default for PendingRequestConfiguration {
+    fn default() -> Self {
+        PendingRequestConfiguration {
+            enabled: false,
+            request_timeout_ms: default_pending_request_timeout_ms(),
+            stuck_multiplier: default_stuck_multiplier(),
+            check_interval_seconds: default_stuck_check_interval_seconds(),
+            excluded_methods: Vec::new(),
+            method_thresholds_ms: HashMap::new(),
+        }
+    }
+}
+
+impl PendingRequestConfiguration {
+    fn matches_method(pattern: &str, method: &str) -> bool {
+        match pattern.strip_suffix('*') {
+            Some(prefix) => method
+                .get(..prefix.len())
+                .is_some_and(|m| m.eq_ignore_ascii_case(prefix)),
+            None => pattern.eq_ignore_ascii_case(method),
+        }
+    }
+
+    /// Time after which a request for the method is stuck, `None` for the exempt methods.
+    /// The most specific entry of `method_thresholds_ms` wins over the default threshold.
+    pub fn get_stuck_threshold(&self, method: &str) -> Option<Duration> {
+        if self.is_excluded(method) {
+            return None;
+        }
+        let threshold_ms = self
+            .method_thresholds_ms
+            .iter()
+            .filter(|(pattern, _)| Self::matches_method(pattern, method))
+            .max_by_key(|(pattern, _)| (!pattern.ends_with('*'), pattern.len()))
+            .map(|(_, threshold_ms)| *threshold_ms)
+            .unwrap_or_else(|| {
+                self.request_timeout_ms
+                    .saturating_mul(self.stuck_multiplier as u64)
+            });
+        Some(Duration::from_millis(threshold_ms))
+    }
+
+    pub fn is_excluded(&self, method: &str) -> bool {
+        PENDING_REQUEST_EXEMPT_METHODS
+            .iter()
+            .copied()
+            .chain(self.excluded_methods.iter().map(String::as_str))
+            .any(|m| Self::matches_method(m, method))
+    }
+}
+
+/// Moves parts of the gateway onto dedicated tokio runtimes, so a busy broker cannot starve
+/// the app websocket reads on SoCs with few cores. Work without a configured runtime stays on
+/// the main runtime.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct RuntimeTopologyConfiguration {
+    /// App websocket connections
+    #[serde(default)]
+    pub websocket: Option<DedicatedRuntimeConfiguration>,
+    /// Endpoint brokers and their connections
+    #[serde(default)]
+    pub broker: Option<DedicatedRuntimeConfiguration>,
+    /// Broker output forwarder which applies the response and event transforms
+    #[serde(default)]
+    pub transform: Option<DedicatedRuntimeConfiguration>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DedicatedRuntimeConfiguration {
+    #[serde(default = "default_runtime_worker_threads")]
+    pub worker_threads: usize,
+}
+
+fn default_runtime_worker_threads() -> usize {
+    1
+}
+
+impl Default for DedicatedRuntimeConfiguration {
+    fn default() -> Self {
+        DedicatedRuntimeConfiguration {
+            worker_threads: default_runtime_worker_threads(),
+        }
+    }
+}
+
+/// Deadlines for app requests, a tighter deadline sent by the app in `deadlineMs` wins. An
+/// expired request is failed instead of being forwarded, brokers shrink their downstream
+/// timeouts to the remaining time and requests still waiting on a broker are failed once the
+/// deadline passed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DeadlineConfiguration {
+    #[serde(default)]
+    pub default_deadline_ms: Option<u64>,
+    #[serde(default)]
+    pub method_deadlines_ms: HashMap<String, u64>,
+}
+
+impl DeadlineConfiguration {
+    pub fn get_deadline_ms(&self, method: &str) -> Option<u64> {
+        self.method_deadlines_ms
+            .iter()
+            .find(|(m, _)| m.eq_ignore_ascii_case(method))
+            .map(|(_, deadline)| *deadline)
+            .or(self.default_deadline_ms)
+    }
+}
+
+/// Bounds the graceful shutdown triggered by SIGTERM or `ripple.shutdown`. In-flight requests
+/// get up to `drain_timeout_ms` to complete, and the process exits once `deadline_ms` passed
+/// even if the sequence is not done.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ShutdownConfiguration {
+    #[serde(default = "default_shutdown_deadline_ms")]
+    pub deadline_ms: u64,
+    #[serde(default = "default_shutdown_drain_timeout_ms")]
+    pub drain_timeout_ms: u64,
+}
+
+fn default_shutdown_deadline_ms() -> u64 {
+    10000
+}
+
+fn default_shutdown_drain_timeout_ms() -> u64 {
+    3000
+}
+
+impl Default for ShutdownConfiguration {
+    fn default() -> Self {
+        ShutdownConfiguration {
+            deadline_ms: default_shutdown_deadline_ms(),
+            drain_timeout_ms: default_shutdown_drain_timeout_ms(),
+        }
+    }
+}
+
+/// Execution limits for a jq filter. The nesting bounds the values going into and coming
+/// out of the filter, the filter depth bounds the nesting of the filter itself, its brackets,
+/// conditionals and definitions, and is checked before the filter runs. Recursion at runtime
+/// is only bounded by the runtime.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JqLimits {
+    #[serde(default = "default_jq_max_runtime_ms")]
+    pub max_runtime_ms: u64,
+    #[serde(default = "default_jq_max_output_bytes")]
+    pub max_output_bytes: usize,
+    #[serde(default = "default_jq_max_nesting", alias = "max_depth")]
+    pub max_nesting: usize,
+    #[serde(default = "default_jq_max_filter_depth")]
+    pub max_filter_depth: usize,
+}
+
+fn default_jq_max_runtime_ms() -> u64 {
+    50
+}
+
+fn default_jq_max_output_bytes() -> usize {
+    65536
+}
+
+fn default_jq_max_nesting() -> usize {
+    32
+}
+
+fn default_jq_max_filter_depth() -> usize {
+    16
+}
+
+impl Default for JqLimits {
+    fn default() -> Self {
+        JqLimits {
+            max_runtime_ms: default_jq_max_runtime_ms(),
+            max_output_bytes: default_jq_max_output_bytes(),
+            max_nesting: default_jq_max_nesting(),
+            max_filter_depth: default_jq_max_filter_depth(),
+        }
+    }
+}
+
+/// Sandbox for the jq filters of the rules. Trusted rules can be given their own limits in
+/// `rule_limits` keyed by the rule name, and a rule tripping the limits
+/// `quarantine_threshold` times is quarantined until released through the admin API, a
+/// threshold of 0 never quarantines. Filters run on a pool of `max_workers` threads,
+/// evaluations fail while every worker is busy.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct JqSandboxConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub limits: JqLimits,
+    #[serde(default)]
+    pub rule_limits: HashMap<String, JqLimits>,
+    #[serde(default = "default_jq_quarantine_threshold")]
+    pub quarantine_threshold: u32,
+    #[serde(default = "default_jq_max_workers")]
+    pub max_workers: usize,
+}
+
+fn default_jq_quarantine_threshold() -> u32 {
+    3
+}
+
+fn default_jq_max_workers() -> usize {
+    2
+}
+
+impl Default for JqSandboxConfiguration {
+    fn default() -> Self {
+        JqSandboxConfiguration {
+            enabled: false,
+            limits: JqLimits::default(),
+            rule_limits: HashMap::new(),
+            quarantine_threshold: default_jq_quarantine_threshold(),
+            max_workers: default_jq_max_workers(),
+        }
+    }
+}
+
+impl JqSandboxConfiguration {
+    pub fn get_limits(&self, rule: &str) -> JqLimits {
+        self.rule_limits
+            .iter()
+            .find(|(r, _)| r.eq_ignore_ascii_case(rule))
+            .map(|(_, limits)| limits.clone())
+            .unwrap_or_else(|| self.limits.clone())
+    }
+}
+
+/// Reshapes the payload of an app event for the subscribers on the given SDK versions, so
+/// older SDKs keep getting the shape they expect from a single event producer. A version
+/// matches itself and its patch versions, `1.2` matches `1.2.5`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct EventTransform {
+    pub event: String,
+    pub sdk_versions: Vec<String>,
+    /// jq filter applied to the event payload
+    pub filter: String,
+}
+
+impl EventTransform {
+    pub fn matches(&self, event: &str, sdk_version: &str) -> bool {
+        self.event.eq_ignore_ascii_case(event)
+            && self.sdk_versions.iter().any(|v| {
+                sdk_version.eq(v)
+                    || sdk_version.starts_with(&format!("{}.", v.trim_end_matches('.')))
+            })
+    }
+}
+
+/// Lifecycle statistics kept per app for the launcher UI and device health telemetry. Only
+/// the `max_apps` most recently used apps are kept.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct AppUsageConfiguration {
+    #[serde(default = "default_app_usage_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_app_usage_max_apps")]
+    pub max_apps: usize,
+}
+
+fn default_app_usage_enabled() -> bool {
+    true
+}
+
+fn default_app_usage_max_apps() -> usize {
+    50
+}
+
+impl Default for AppUsageConfiguration {
+    fn default() -> Self {
+        AppUsageConfiguration {
+            enabled: default_app_usage_enabled(),
+            max_apps: default_app_usage_max_apps(),
+        }
+    }
+}
+
+/// Server side values which can be added to the app reported metrics events
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MetricsEnrichmentSource {
+    AppId,
+    /// Version of the app from the app library
+    AppVersion,
+    AppSessionId,
+    RippleSessionId,
+    RippleVersion,
+    /// Form factor of the device
+    DeviceClass,
+}
+
+/// Adds server side context to the metrics events reported by apps so the values are
+/// consistent regardless of what the app reports. `fields` maps the param name which is
+/// set on the event to the source of its value.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct MetricsEnrichmentConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_metrics_enrichment_methods")]
+    pub methods: Vec<String>,
+    #[serde(default = "default_metrics_enrichment_fields")]
+    pub fields: HashMap<String, MetricsEnrichmentSource>,
+}
+
+fn default_metrics_enrichment_methods() -> Vec<String> {
+    [
+        "metrics.page",
+        "metrics.action",
+        "metrics.error",
+        "metrics.startContent",
+        "metrics.stopContent",
+        "metrics.mediaLoadStart",
+        "metrics.mediaPlay",
+        "metrics.mediaPlaying",
+        "metrics.mediaPause",
+        "metrics.mediaWaiting",
+        "metrics.mediaProgress",
+        "metrics.mediaSeeking",
+        "metrics.mediaSeeked",
+        "metrics.mediaRateChange",
+        "metrics.mediaRenditionChange",
+        "metrics.mediaEnded",
+    ]
+    .iter()
+    .map(|m| m.to_string())
+    .collect()
+}
+
+fn default_metrics_enrichment_fields() -> HashMap<String, MetricsEnrichmentSource> {
+    HashMap::from([
+        ("appVersion".to_owned(), MetricsEnrichmentSource::AppVersion),
+        (
+            "appSessionId".to_owned(),
+            MetricsEnrichmentSource::AppSessionId,
+        ),
+        (
+            "deviceClass".to_owned(),
+            MetricsEnrichmentSource::DeviceClass,
+        ),
+    ])
+}
+
+impl Default for MetricsEnrichmentConfiguration {
+    fn default() -> Self {
+        MetricsEnrichmentConfiguration {
+            enabled: false,
+            methods: default_metrics_enrichment_methods(),
+            fields: default_metrics_enrichment_fields(),
+        }
+    }
+}
+
+impl MetricsEnrichmentConfiguration {
+    pub fn is_enriched(&self, method: &str) -> bool {
+        self.enabled && self.methods.iter().any(|m| m.eq_ignore_ascii_case(method))
+    }
+}
+
+/// Error budget of each app. An app with more than `client_error_budget` denied or invalid
+/// calls within `window_seconds` is throttled, starting at `throttle_ms` and doubling for
+/// every consecutive window over budget up to `max_throttle_ms`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ErrorBudgetConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_error_budget_window_seconds")]
+    pub window_seconds: u64,
+    #[serde(default = "default_client_error_budget")]
+    pub client_error_budget: u64,
+    #[serde(default = "default_error_budget_throttle_ms")]
+    pub throttle_ms: u64,
+    #[serde(default = "default_error_budget_max_throttle_ms")]
+    pub max_throttle_ms: u64,
+}
+
+fn default_error_budget_window_seconds() -> u64 {
+    60
+}
+
+fn default_client_error_budget() -> u64 {
+    30
+}
+
+fn default_error_budget_throttle_ms() -> u64 {
+    1000
+}
+
+fn default_error_budget_max_throttle_ms() -> u64 {
+    60000
+}
+
+impl Default for ErrorBudgetConfiguration {
+    fn default() -> Self {
+        ErrorBudgetConfiguration {
+            enabled: false,
+            window_seconds: default_error_budget_window_seconds(),
+            client_error_budget: default_client_error_budget(),
+            throttle_ms: default_error_budget_throttle_ms(),
+            max_throttle_ms: default_error_budget_max_throttle_ms(),
+        }
+    }
+}
+
+/// Liveness of connected services. Ripple sends a heartbeat to every service each
+/// `interval_ms` and a service which has not sent anything for `timeout_ms` is treated as
+/// dead, its rules are removed and its connection is closed. The timeout has to be longer
+/// than the interval.
+///
+/// Requests left unanswered by a dropped connection are held for `resync_window_ms`, a
+/// service which reconnects and resyncs within the window gets them replayed, otherwise they
+/// fail as unavailable.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ServiceLivenessConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_service_heartbeat_interval_ms")]
+    pub interval_ms: u64,
+    #[serde(default = "default_service_heartbeat_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_service_resync_window_ms")]
+    pub resync_window_ms: u64,
+}
+
+fn default_service_heartbeat_interval_ms() -> u64 {
+    5000
+}
+
+fn default_service_heartbeat_timeout_ms() -> u64 {
+    15000
+}
+
+fn default_service_resync_window_ms() -> u64 {
+    10000
+}
+
+impl ServiceLivenessConfiguration {
+    /// A timeout which is not longer than the interval would evict services between two
+    /// heartbeats, it is raised to twice the interval.
+    pub fn get_timeout_ms(&self) -> u64 {
+        if self.timeout_ms > self.interval_ms {
+            self.timeout_ms
+        } else {
+            self.interval_ms.max(1) * 2
+        }
+    }
+}
+
+impl Default for ServiceLivenessConfiguration {
+    fn default() -> Self {
+        ServiceLivenessConfiguration {
+            enabled: false,
+            interval_ms: default_service_heartbeat_interval_ms(),
+            timeout_ms: default_service_heartbeat_timeout_ms(),
+            resync_window_ms: default_service_resync_window_ms(),
+        }
+    }
+}
+
+/// Caching of the ad framework configuration served by the distributor. Cached entries are
+/// scoped to the account, app, environment and privacy settings of the request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct AdConfigConfiguration {
+    #[serde(default = "default_ad_config_cache_ttl_seconds")]
+    pub cache_ttl_seconds: u64,
+    #[serde(default = "default_ad_config_cache_size")]
+    pub cache_size: usize,
+}
+
+fn default_ad_config_cache_ttl_seconds() -> u64 {
+    3600
+}
+
+fn default_ad_config_cache_size() -> usize {
+    32
+}
+
+impl Default for AdConfigConfiguration {
+    fn default() -> Self {
+        AdConfigConfiguration {
+            cache_ttl_seconds: default_ad_config_cache_ttl_seconds(),
+            cache_size: default_ad_config_cache_size(),
+        }
+    }
+}
+
+/// Timeouts for requests forwarded to services by the ServiceBroker. `endpoint_timeouts_ms` is
+/// keyed by ServiceId and `method_timeouts_ms` overrides both for a single method. A request
+/// without a response in time fails with a deadline exceeded error.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ServiceRequestTimeoutConfiguration {
+    #[serde(default = "default_service_request_timeout_ms")]
+    pub default_timeout_ms: u64,
+    #[serde(default)]
+    pub endpoint_timeouts_ms: HashMap<String, u64>,
+    #[serde(default)]
+    pub method_timeouts_ms: HashMap<String, u64>,
+}
+
+fn default_service_request_timeout_ms() -> u64 {
+    30000
+}
+
+impl Default for ServiceRequestTimeoutConfiguration {
+    fn default() -> Self {
+        ServiceRequestTimeoutConfiguration {
+            default_timeout_ms: default_service_request_timeout_ms(),
+            endpoint_timeouts_ms: HashMap::new(),
+            method_timeouts_ms: HashMap::new(),
+        }
+    }
+}
+
+impl ServiceRequestTimeoutConfiguration {
+    pub fn get_timeout(&self, service_id: &str, method: &str) -> Duration {
+        let timeout_ms = self
+            .method_timeouts_ms
+            .iter()
+            .find(|(m, _)| m.eq_ignore_ascii_case(method))
+            .map(|(_, timeout)| *timeout)
+            .or_else(|| self.endpoint_timeouts_ms.get(service_id).cloned())
+            .unwrap_or(self.default_timeout_ms);
+        Duration::from_millis(timeout_ms)
+    }
+}
+
+/// Methods whose authorization is delegated first to the extension fulfilling the
+/// gatekeeper policy contract. A decision not received within `timeout_ms` falls through to
+/// the regular checks, unless `fail_closed` is set and the call is denied instead.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct GatekeeperPolicyConfiguration {
+    #[serde(default)]
+    pub methods: Vec<String>,
+    #[serde(default = "default_gatekeeper_policy_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub fail_closed: bool,
+}
+
+fn default_gatekeeper_policy_timeout_ms() -> u64 {
+    200
+}
+
+impl Default for GatekeeperPolicyConfiguration {
+    fn default() -> Self {
+        GatekeeperPolicyConfiguration {
+            methods: Vec::new(),
+            timeout_ms: default_gatekeeper_policy_timeout_ms(),
+            fail_closed: false,
+        }
+    }
+}
+
+impl GatekeeperPolicyConfiguration {
+    /// Matches the method case insensitively, `module.*` entries match a whole module
+    pub fn is_policy_method(&self, method: &str) -> bool {
+        let method = method.to_lowercase();
+        self.methods.iter().any(|m| {
+            let m = m.to_lowercase();
+            match m.strip_suffix('*') {
+                Some(prefix) => method.starts_with(prefix),
+                None => m.eq(&method),
+            }
+        })
+    }
+}
+
+/// HTTP transport for services which cant open a websocket, for example behind middleboxes
+/// which block the upgrade. Services register with a POST and receive calls from Ripple
+/// Main by long-polling or as server-sent events, waiting up to `poll_timeout_ms` per poll.
+/// Connections which neither poll nor stream for `idle_timeout_ms` are unregistered, and
+/// messages larger than `max_message_bytes` are refused.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ServiceHttpConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "service_http_gateway_default")]
+    pub gateway: String,
+    #[serde(default = "service_http_poll_timeout_default")]
+    pub poll_timeout_ms: u64,
+    #[serde(default = "service_http_idle_timeout_default")]
+    pub idle_timeout_ms: u64,
+    #[serde(default = "service_http_max_message_bytes_default")]
+    pub max_message_bytes: usize,
+}
+
+fn service_http_gateway_default() -> String {
+    "127.0.0.1:3476".into()
+}
+
+fn service_http_poll_timeout_default() -> u64 {
+    25000
+}
+
+fn service_http_idle_timeout_default() -> u64 {
+    90000
+}
+
+fn service_http_max_message_bytes_default() -> usize {
+    1048576
+}
+
+impl Default for ServiceHttpConfiguration {
+    fn default() -> Self {
+        ServiceHttpConfiguration {
+            enabled: false,
+            gateway: service_http_gateway_default(),
+            poll_timeout_ms: service_http_poll_timeout_default(),
+            idle_timeout_ms: service_http_idle_timeout_default(),
+            max_message_bytes: service_http_max_message_bytes_default(),
+        }
+    }
+}
+
+/// Tenants hosting services on the device, for example different vendors. A service belongs
+/// to the first tenant whose `service_prefix` starts its ServiceId.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct TenantsConfiguration {
+    #[serde(default)]
+    pub tenants: Vec<TenantConfiguration>,
+}
+
+impl TenantsConfiguration {
+    pub fn get_tenant(&self, service_id: &str) -> Option<&TenantConfiguration> {
+        self.tenants
+            .iter()
+            .find(|t| service_id.starts_with(&t.service_prefix))
+    }
+}
+
+/// `max_services` limits the services of the tenant connected at the same time and
+/// `method_namespaces` limits the modules its services can serve, any module when empty.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct TenantConfiguration {
+    pub id: String,
+    pub service_prefix: String,
+    #[serde(default)]
+    pub max_services: Option<usize>,
+    #[serde(default)]
+    pub method_namespaces: Vec<String>,
+}
+
+impl TenantConfiguration {
+    pub fn is_method_allowed(&self, method: &str) -> bool {
+        if self.method_namespaces.is_empty() {
+            return true;
+        }
+        let namespace = method.split('.').next().unwrap_or_default();
+        self.method_namespaces
+            .iter()
+            .any(|n| n.eq_ignore_ascii_case(namespace))
+    }
+}
+
+/// Caps on concurrent app connections which protect memory on low end devices from
+/// connection storms. `app_limits` overrides `max_connections_per_app` for specific apps,
+/// no cap applies when a limit is not set.
+///
+/// With `preemption` a critical app which hits the session cap evicts the session of lowest
+/// priority which has been idle for at least `preemption_idle_ms`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ConnectionLimitsConfiguration {
+    #[serde(default)]
+    pub max_sessions: Option<usize>,
+    #[serde(default)]
+    pub max_connections_per_app: Option<usize>,
+    #[serde(default)]
+    pub app_limits: HashMap<String, usize>,
+    #[serde(default)]
+    pub preemption: bool,
+    #[serde(default = "preemption_idle_ms_default")]
+    pub preemption_idle_ms: u64,
+}
+
+fn preemption_idle_ms_default() -> u64 {
+    30000
+}
+
+impl Default for ConnectionLimitsConfiguration {
+    fn default() -> Self {
+        ConnectionLimitsConfiguration {
+            max_sessions: None,
+            max_connections_per_app: None,
+            app_limits: HashMap::new(),
+            preemption: false,
+            preemption_idle_ms: preemption_idle_ms_default(),
+        }
+    }
+}
+
+impl ConnectionLimitsConfiguration {
+    pub fn get_app_limit(&self, app_id: &str) -> Option<usize> {
+        self.app_limits
+            .get(app_id)
+            .cloned()
+            .or(self.max_connections_per_app)
+    }
+}
+
+/// Source of the country code, locale and time zone reported to apps
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionSourceType {
+    /// Values from the platform through the regular firebolt routing
+    Platform,
+    /// Fixed values from the device manifest
+    Manifest,
+    /// Values from the remote configuration keys
+    Remote,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum RegionField {
+    CountryCode,
+    Locale,
+    TimeZone,
+}
+
+impl RegionField {
+    pub const ALL: [RegionField; 3] = [
+        RegionField::CountryCode,
+        RegionField::Locale,
+        RegionField::TimeZone,
+    ];
+
+    pub fn get_method(&self) -> &'static str {
+        match self {
+            RegionField::CountryCode => "localization.countryCode",
+            RegionField::Locale => "localization.locale",
+            RegionField::TimeZone => "localization.timeZone",
+        }
+    }
+
+    pub fn get_event(&self) -> &'static str {
+        match self {
+            RegionField::CountryCode => "localization.onCountryCodeChanged",
+            RegionField::Locale => "localization.onLocaleChanged",
+            RegionField::TimeZone => "localization.onTimeZoneChanged",
+        }
+    }
+
+    pub fn from_method(method: &str) -> Option<RegionField> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.get_method().eq_ignore_ascii_case(method))
+    }
+
+    pub fn from_event(event: &str) -> Option<RegionField> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.get_event().eq_ignore_ascii_case(event))
+    }
+}
+
+/// Region values, the remote configuration uses the same fields for the RFC keys
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionValues {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+}
+
+/// Lets one image ship to many regions. The sources are in order of precedence, the first
+/// source with a value for a field wins.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct RegionConfiguration {
+    #[serde(default = "region_sources_default")]
+    pub sources: Vec<RegionSourceType>,
+    #[serde(default)]
+    pub fixed: RegionValues,
+    #[serde(default)]
+    pub remote_keys: RegionValues,
+    #[serde(default = "region_reload_interval_default")]
+    pub reload_interval_seconds: u64,
+}
+
+fn region_sources_default() -> Vec<RegionSourceType> {
+    vec![RegionSourceType::Platform]
+}
+
+fn region_reload_interval_default() -> u64 {
+    300
+}
+
+impl RegionValues {
+    pub fn get(&self, field: RegionField) -> Option<String> {
+        match field {
+            RegionField::CountryCode => self.country_code.clone(),
+            RegionField::Locale => self.locale.clone(),
+            RegionField::TimeZone => self.time_zone.clone(),
+        }
+    }
+
+    pub fn set(&mut self, field: RegionField, value: Option<String>) {
+        match field {
+            RegionField::CountryCode => self.country_code = value,
+            RegionField::Locale => self.locale = value,
+            RegionField::TimeZone => self.time_zone = value,
+        }
+    }
+}
+
+impl Default for RegionConfiguration {
+    fn default() -> Self {
+        RegionConfiguration {
+            sources: region_sources_default(),
+            fixed: RegionValues::default(),
+            remote_keys: RegionValues::default(),
+            reload_interval_seconds: region_reload_interval_default(),
+        }
+    }
+}
+
+/// Rolling journal of the last `max_entries` Firebolt request envelopes, kept in the saved dir
+/// without payloads and written every `flush_interval_ms`. When Ripple exited without
+/// shutting down, the last `crash_tail_size` entries go out with the crash breadcrumb
+/// telemetry on the next start.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct RequestJournalConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "request_journal_max_entries_default")]
+    pub max_entries: usize,
+    #[serde(default = "request_journal_flush_interval_ms_default")]
+    pub flush_interval_ms: u64,
+    #[serde(default = "request_journal_crash_tail_size_default")]
+    pub crash_tail_size: usize,
+}
+
+fn request_journal_max_entries_default() -> usize {
+    200
+}
+
+fn request_journal_flush_interval_ms_default() -> u64 {
+    5000
+}
+
+fn request_journal_crash_tail_size_default() -> usize {
+    20
+}
+
+impl Default for RequestJournalConfiguration {
+    fn default() -> Self {
+        RequestJournalConfiguration {
+            enabled: false,
+            max_entries: request_journal_max_entries_default(),
+            flush_interval_ms: request_journal_flush_interval_ms_default(),
+            crash_tail_size: request_journal_crash_tail_size_default(),
+        }
+    }
+}
+
+/// Credentials accepted from a service, bearer tokens presented in the `Authorization` header
+/// of the connection and SHA-256 fingerprints of the TLS client certificate.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ServiceCredentials {
+    #[serde(default)]
+    pub tokens: Vec<String>,
+    #[serde(default)]
+    pub cert_fingerprints: Vec<String>,
+}
+
+/// Authentication of service connections. When enabled only the ServiceIds in `allowlist`
+/// can connect, presenting one of the credentials listed for them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ServiceAuthConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub allowlist: HashMap<String, ServiceCredentials>,
+}
+
+/// Capabilities each service may provide and the Firebolt methods each capability covers.
+/// Methods are matched case insensitively and `module.*` covers every method of the module.
+/// The policy is enforced by default, a service without an entry is granted nothing. With
+/// `enforce` turned off every registration is accepted as declared.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ServiceCapabilityPolicy {
+    #[serde(default = "service_capability_enforce_default")]
+    pub enforce: bool,
+    #[serde(default)]
+    pub services: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub capability_methods: HashMap<String, Vec<String>>,
+}
+
+fn service_capability_enforce_default() -> bool {
+    true
+}
+
+impl Default for ServiceCapabilityPolicy {
+    fn default() -> Self {
+        Self {
+            enforce: service_capability_enforce_default(),
+            services: HashMap::new(),
+            capability_methods: HashMap::new(),
+        }
+    }
+}
+
+impl ServiceCapabilityPolicy {
+    /// Splits the requested capabilities into the ones granted to the service and the rejected ones
+    pub fn grant_capabilities(
+        &self,
+        service_id: &str,
+        requested: &[String],
+    ) -> (Vec<String>, Vec<String>) {
+        if !self.enforce {
+            return (requested.to_vec(), Vec::new());
+        }
+        let allowed = self.services.get(service_id);
+        requested
+            .iter()
+            .cloned()
+            .partition(|cap| allowed.is_some_and(|caps| caps.contains(cap)))
+    }
+
+    pub fn is_method_covered(&self, capabilities: &[String], method: &str) -> bool {
+        if !self.enforce {
+            return true;
+        }
+        let method = method.to_lowercase();
+        capabilities
+            .iter()
+            .filter_map(|cap| self.capability_methods.get(cap))
+            .flatten()
+            .any(|pattern| {
+                let pattern = pattern.to_lowercase();
+                match pattern.strip_suffix('*') {
+                    Some(prefix) => method.starts_with(prefix),
+                    None => pattern.eq(&method),
+                }
+            })
+    }
+}
+
+/// Decides which service serves a method registered by two services
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MethodConflictPolicy {
+    /// The service which registered the method first keeps it
+    #[default]
+    Reject,
+    /// The service which registered the method last takes it over
+    LastWriterWins,
+    /// The service ranked higher in the priorities takes it over
+    PriorityOrdered,
+}
+
+/// Resolution of methods registered by more than one service. Services missing from
+/// `priorities`, which lists ServiceIds from the highest priority, rank last and a service
+/// never takes a method over from a service of the same rank.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ServiceConflictConfiguration {
+    #[serde(default)]
+    pub policy: MethodConflictPolicy,
+    #[serde(default)]
+    pub priorities: Vec<String>,
+}
+
+impl ServiceConflictConfiguration {
+    /// Checks if the challenger takes over a method registered by the owner
+    pub fn is_won_by(&self, challenger: &str, owner: &str) -> bool {
+        match self.policy {
+            MethodConflictPolicy::Reject => false,
+            MethodConflictPolicy::LastWriterWins => true,
+            MethodConflictPolicy::PriorityOrdered => {
+                let rank = |service_id: &str| {
+                    self.priorities
+                        .iter()
+                        .position(|p| p.eq(service_id))
+                        .unwrap_or(usize::MAX)
+                };
+                rank(challenger) < rank(owner)
+            }
+        }
+    }
+}
+
+/// Kind of target which can serve a Firebolt method
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum RouteTarget {
+    /// Method statically registered with the rpc router
+    Handler,
+    /// Rule of the device, brokered to its endpoint
+    Broker,
+    /// Method registered at runtime by an extension
+    Extension,
+    /// Method registered at runtime by a Ripple service
+    Service,
+}
+
+/// The rules of the device and the handlers of Ripple Main come before the methods registered
+/// at runtime, so an extension or a service can not take over a method the device serves
+const DEFAULT_ROUTE_PRECEDENCE: [RouteTarget; 4] = [
+    RouteTarget::Broker,
+    RouteTarget::Handler,
+    RouteTarget::Service,
+    RouteTarget::Extension,
+];
+
+/// Order in which the targets serving a method are tried, from the highest precedence.
+/// Targets missing from `precedence` follow the listed ones in the default order.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct RoutingConfiguration {
+    #[serde(default)]
+    pub precedence: Vec<RouteTarget>,
+}
+
+impl RoutingConfiguration {
+    pub fn get_precedence(&self) -> Vec<RouteTarget> {
+        let mut precedence: Vec<RouteTarget> = Vec::new();
+        for target in self
+            .precedence
+            .iter()
+            .chain(DEFAULT_ROUTE_PRECEDENCE.iter())
+        {
+            if !precedence.contains(target) {
+                precedence.push(*target);
+            }
+        }
+        precedence
+    }
+}
+
+/// Decides what happens when a second connection claims an already connected ServiceId.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ServiceTakeoverPolicy {
+    /// The second connection is rejected and the first one is kept until it closes or is
+    /// found dead by the liveness monitor
+    #[default]
+    Reject,
+    /// The second connection takes over if it connects with `force=true`
+    AllowForced,
+}
+
+/// Companion services which are launched and supervised by Ripple Main.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ServiceLauncherConfiguration {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub services: Vec<CompanionServiceConfiguration>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct CompanionServiceConfiguration {
+    /// ServiceId the binary uses in its service handshake
+    pub service_id: String,
+    pub path: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default = "companion_service_initial_backoff_ms_default")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "companion_service_max_backoff_ms_default")]
+    pub max_backoff_ms: u64,
+    /// Stop restarting after this many consecutive failures, restart forever if not set
+    #[serde(default)]
+    pub max_restarts: Option<u32>,
+    #[serde(default = "companion_service_registration_timeout_ms_default")]
+    pub registration_timeout_ms: u64,
+    /// Launch the service inside an OCI container instead of as a plain process
+    #[serde(default)]
+    pub oci: Option<OciLaunchConfiguration>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct OciLaunchConfiguration {
+    /// OCI runtime binary invoked with `run --bundle`, crun by default
+    #[serde(default = "oci_runtime_default")]
+    pub runtime: String,
+    /// Bundle directory containing the base config.json and rootfs
+    pub bundle: String,
+    /// Directory where Ripple writes the generated runtime bundle
+    #[serde(default = "oci_state_dir_default")]
+    pub state_dir: String,
+    /// Host paths bind mounted at the same location inside the container
+    #[serde(default)]
+    pub bind_mounts: Vec<String>,
+    /// Share the host network namespace so the service can reach the Ripple service socket
+    #[serde(default = "oci_host_network_default")]
+    pub host_network: bool,
+    #[serde(default)]
+    pub memory_limit_bytes: Option<i64>,
+    #[serde(default)]
+    pub cpu_shares: Option<u64>,
+    #[serde(default)]
+    pub pids_limit: Option<i64>,
+}
+
+fn oci_runtime_default() -> String {
+    "crun".to_string()
+}
+
+fn oci_state_dir_default() -> String {
+    "/tmp/ripple/oci".to_string()
+}
+
+fn oci_host_network_default() -> bool {
+    true
+}
+
+fn companion_service_initial_backoff_ms_default() -> u64 {
+    1000
+}
+
+fn companion_service_max_backoff_ms_default() -> u64 {
+    30000
+}
+
+fn companion_service_registration_timeout_ms_default() -> u64 {
+    10000
+}
+
+impl Default for RippleConfiguration {
+    fn default() -> Self {
+        Self {
+            ws_configuration: Default::default(),
+            internal_ws_configuration: Default::default(),
+            platform_parameters: Value::Null,
+            distribution_id_salt: None,
+            form_factor: Default::default(),
+            default_values: DefaultValues::default(),
+            model_friendly_names: Default::default(),
+            distributor_experience_id: Default::default(),
+            distributor_services: None,
+            exclusory: None,
+            features: Default::default(),
+            internal_app_id: None,
+            saved_dir: default_saved_dir(),
+            data_governance: data_governance_default(),
+            partner_exclusion_refresh_timeout: partner_exclusion_refresh_timeout_default(),
+            metrics_logging_percentage: metrics_logging_percentage_default(),
+            internet_monitoring_configuration: Default::default(),
+            service_launcher: Default::default(),
+            service_takeover_policies: Default::default(),
+            admin: Default::default(),
+            dbus_bridge: Default::default(),
+            app_library_refresh: Default::default(),
+            entitlements_sync: Default::default(),
+            watch_history_upload: Default::default(),
+            heartbeat: Default::default(),
+            diagnostics: Default::default(),
+            http_bridge: Default::default(),
+            method_overrides: Default::default(),
+            capability_usage: Default::default(),
+            activation: Default::default(),
+            inactivity: Default::default(),
+            input: Default::default(),
+            audio_focus: Default::default(),
+            drm: Default::default(),
+            compliance: Default::default(),
+            notification_policy: Default::default(),
+            pending_requests: Default::default(),
+            runtime_topology: Default::default(),
+            deadlines: Default::default(),
+            shutdown: Default::default(),
+            jq_sandbox: Default::default(),
+            event_transforms: Default::default(),
+            app_usage: Default::default(),
+            metrics_enrichment: Default::default(),
+            error_budget: Default::default(),
+            service_liveness: Default::default(),
+            ad_config: Default::default(),
+            service_request_timeouts: Default::default(),
+            gatekeeper_policy: Default::default(),
+            service_http: Default::default(),
+            tenants: Default::default(),
+            connection_limits: Default::default(),
+            region: Default::default(),
+            request_journal: Default::default(),
+            service_auth: Default::default(),
+            service_capabilities: Default::default(),
+            service_conflicts: Default::default(),
+            routing: Default::default(),
+            log_signal_log_level: log_signal_default_level(),
+        }
+    }
+}
+
+impl DeviceManifest {
+    pub fn load(path: String) -> Result<(String, DeviceManifest), RippleError> {
+        info!("Trying to load device manifest from path={}", path);
+        if let Some(p) = Path::new(&path).to_str() {
+            if let Ok(contents) = ManifestSource::load_to_string(p) {
+                info!("Loaded device manifest from {}", path);
+                return Self::load_from_content(contents);
+            }
+        }
+        info!("No device manifest found in {}", path);
+        Err(RippleError::MissingInput)
+    }
+
+    pub fn load_from_content(contents: String) -> Result<(String, DeviceManifest), RippleError> {
+        match serde_json::from_str::<DeviceManifest>(&contents) {
+            Ok(manifest) => Ok((contents, manifest)),
+            Err(err) => {
+                warn!("{:?} could not load device manifest", err);
+                Err(RippleError::InvalidInput)
+            }
+        }
+    }
+
+    pub fn get_web_socket_enabled(&self) -> bool {
+        self.configuration.ws_configuration.enabled
+    }
+
+    pub fn get_internal_ws_enabled(&self) -> bool {
+        self.configuration.internal_ws_configuration.enabled
+    }
+
+    pub fn get_ws_gateway_host(&self) -> String {
+        self.configuration.ws_configuration.gateway.clone()
+    }
+
+    pub fn get_internal_gateway_host(&self) -> String {
+        self.configuration.internal_ws_configuration.gateway.clone()
+    }
+
+    pub fn get_internal_app_id(&self) -> Option<String> {
+        self.configuration.internal_app_id.clone()
+    }
+
+    pub fn get_form_factor(&self) -> String {
+        self.configuration.form_factor.clone()
+    }
+
+    /// Get path to the app library file
+    pub fn get_app_library_path(&self) -> String {
+        self.applications.distribution.library.clone()
+    }
+
+    pub fn get_lifecycle_policy(&self) -> LifecyclePolicy {
+        LifecyclePolicy {
+            app_ready_timeout_ms: self.lifecycle.app_ready_timeout_ms,
+            app_finished_timeout_ms: self.lifecycle.app_finished_timeout_ms,
+        }
+    }
+
+    pub fn get_retention_policy(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            max_retained: self.lifecycle.max_loaded_apps,
+            min_available_mem_kb: self.lifecycle.min_available_memory_kb,
+            always_retained: self.lifecycle.prioritized.clone(),
+        }
+    }
+
+    pub fn get_supported_caps(&self) -> Vec<FireboltPermission> {
+        let supported = self.clone().capabilities.supported;
+        let role_based_support = self.configuration.default_values.role_based_support;
+        FireboltPermission::from_vec_string(supported, role_based_support)
+    }
+
+    pub fn get_caps_requiring_grant(&self) -> Vec<String> {
+        if let Some(policies) = self.clone().capabilities.grant_policies {
+            return policies.into_keys().collect();
+        }
+        Vec::new()
+    }
+
+    pub fn get_grant_policies(&self) -> Option<HashMap<String, GrantPolicies>> {
+        self.clone().capabilities.grant_policies
     }
 
     pub fn get_grant_exclusion_filters(&self) -> Vec<GrantExclusionFilter> {
@@ -802,6 +3022,179 @@ impl DeviceManifest {
             .internet_monitoring_configuration
             .default_monitoring_interval_seconds
     }
+
+    pub fn get_service_launcher_configuration(&self) -> ServiceLauncherConfiguration {
+        self.configuration.service_launcher.clone()
+    }
+
+    pub fn get_admin_configuration(&self) -> AdminConfiguration {
+        self.configuration.admin.clone()
+    }
+
+    pub fn get_dbus_bridge_configuration(&self) -> DbusBridgeConfiguration {
+        self.configuration.dbus_bridge.clone()
+    }
+
+    pub fn get_app_library_refresh_configuration(&self) -> AppLibraryRefreshConfiguration {
+        self.configuration.app_library_refresh.clone()
+    }
+
+    pub fn get_entitlements_sync_configuration(&self) -> EntitlementsSyncConfiguration {
+        self.configuration.entitlements_sync.clone()
+    }
+
+    pub fn get_watch_history_upload_configuration(&self) -> WatchHistoryUploadConfiguration {
+        self.configuration.watch_history_upload.clone()
+    }
+
+    pub fn get_heartbeat_configuration(&self) -> HeartbeatConfiguration {
+        self.configuration.heartbeat.clone()
+    }
+
+    pub fn get_diagnostics_configuration(&self) -> DiagnosticsConfiguration {
+        self.configuration.diagnostics.clone()
+    }
+
+    pub fn get_http_bridge_configuration(&self) -> HttpBridgeConfiguration {
+        self.configuration.http_bridge.clone()
+    }
+
+    pub fn get_method_overrides_configuration(&self) -> MethodOverridesConfiguration {
+        self.configuration.method_overrides.clone()
+    }
+
+    pub fn get_capability_usage_configuration(&self) -> CapabilityUsageConfiguration {
+        self.configuration.capability_usage.clone()
+    }
+
+    pub fn get_activation_configuration(&self) -> ActivationConfiguration {
+        self.configuration.activation.clone()
+    }
+
+    pub fn get_inactivity_configuration(&self) -> InactivityConfiguration {
+        self.configuration.inactivity.clone()
+    }
+
+    pub fn get_input_configuration(&self) -> InputConfiguration {
+        self.configuration.input.clone()
+    }
+
+    pub fn get_audio_focus_configuration(&self) -> AudioFocusConfiguration {
+        self.configuration.audio_focus.clone()
+    }
+
+    pub fn get_drm_configuration(&self) -> DrmConfiguration {
+        self.configuration.drm.clone()
+    }
+
+    pub fn get_compliance_configuration(&self) -> ComplianceConfiguration {
+        self.configuration.compliance.clone()
+    }
+
+    pub fn get_notification_policy_configuration(&self) -> NotificationPolicyConfiguration {
+        self.configuration.notification_policy.clone()
+    }
+
+    pub fn get_pending_request_configuration(&self) -> PendingRequestConfiguration {
+        self.configuration.pending_requests.clone()
+    }
+
+    pub fn get_runtime_topology_configuration(&self) -> RuntimeTopologyConfiguration {
+        self.configuration.runtime_topology.clone()
+    }
+
+    pub fn get_deadline_configuration(&self) -> DeadlineConfiguration {
+        self.configuration.deadlines.clone()
+    }
+
+    pub fn get_shutdown_configuration(&self) -> ShutdownConfiguration {
+        self.configuration.shutdown.clone()
+    }
+
+    pub fn get_jq_sandbox_configuration(&self) -> JqSandboxConfiguration {
+        self.configuration.jq_sandbox.clone()
+    }
+
+    /// Returns the payload filter for the event on the subscriber's SDK version
+    pub fn get_event_transform(&self, event: &str, sdk_version: &str) -> Option<String> {
+        self.configuration
+            .event_transforms
+            .iter()
+            .find(|t| t.matches(event, sdk_version))
+            .map(|t| t.filter.clone())
+    }
+
+    pub fn get_app_usage_configuration(&self) -> AppUsageConfiguration {
+        self.configuration.app_usage.clone()
+    }
+
+    pub fn get_metrics_enrichment_configuration(&self) -> MetricsEnrichmentConfiguration {
+        self.configuration.metrics_enrichment.clone()
+    }
+
+    pub fn get_error_budget_configuration(&self) -> ErrorBudgetConfiguration {
+        self.configuration.error_budget.clone()
+    }
+
+    pub fn get_service_liveness_configuration(&self) -> ServiceLivenessConfiguration {
+        self.configuration.service_liveness.clone()
+    }
+
+    pub fn get_ad_config_configuration(&self) -> AdConfigConfiguration {
+        self.configuration.ad_config.clone()
+    }
+
+    pub fn get_service_request_timeout_configuration(&self) -> ServiceRequestTimeoutConfiguration {
+        self.configuration.service_request_timeouts.clone()
+    }
+
+    pub fn get_gatekeeper_policy_configuration(&self) -> GatekeeperPolicyConfiguration {
+        self.configuration.gatekeeper_policy.clone()
+    }
+
+    pub fn get_service_http_configuration(&self) -> ServiceHttpConfiguration {
+        self.configuration.service_http.clone()
+    }
+
+    pub fn get_tenants_configuration(&self) -> TenantsConfiguration {
+        self.configuration.tenants.clone()
+    }
+
+    pub fn get_connection_limits(&self) -> ConnectionLimitsConfiguration {
+        self.configuration.connection_limits.clone()
+    }
+
+    pub fn get_region_configuration(&self) -> RegionConfiguration {
+        self.configuration.region.clone()
+    }
+
+    pub fn get_request_journal_configuration(&self) -> RequestJournalConfiguration {
+        self.configuration.request_journal.clone()
+    }
+
+    pub fn get_service_auth_configuration(&self) -> ServiceAuthConfiguration {
+        self.configuration.service_auth.clone()
+    }
+
+    pub fn get_service_capability_policy(&self) -> ServiceCapabilityPolicy {
+        self.configuration.service_capabilities.clone()
+    }
+
+    pub fn get_service_conflict_configuration(&self) -> ServiceConflictConfiguration {
+        self.configuration.service_conflicts.clone()
+    }
+
+    pub fn get_routing_configuration(&self) -> RoutingConfiguration {
+        self.configuration.routing.clone()
+    }
+
+    pub fn get_service_takeover_policy(&self, service_id: &str) -> ServiceTakeoverPolicy {
+        self.configuration
+            .service_takeover_policies
+            .get(service_id)
+            .cloned()
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
@@ -822,10 +3215,12 @@ pub(crate) mod tests {
                     ws_configuration: WsConfiguration {
                         enabled: true,
                         gateway: "127.0.0.1:3473".to_string(),
+                        ..Default::default()
                     },
                     internal_ws_configuration: WsConfiguration {
                         enabled: true,
                         gateway: "127.0.0.1:3474".to_string(),
+                        ..Default::default()
                     },
                     platform_parameters: {
                         let mut params = HashMap::new();
@@ -898,17 +3293,60 @@ pub(crate) mod tests {
                         intent_validation: IntentValidation::Fail,
                         cloud_permissions: true,
                         thunder_plugin_status_check_at_broker_start_up: true,
+                        developer_mode: false,
                     },
                     internal_app_id: Some("test".to_string()),
                     saved_dir: "/opt/persistent/ripple".to_string(),
                     data_governance: DataGovernanceConfig {
                         policies: Vec::new(),
+                        metrics_consent: Vec::new(),
                     },
                     partner_exclusion_refresh_timeout: 43200,
                     metrics_logging_percentage: 10,
                     internet_monitoring_configuration: InternetMonitoringConfiguration {
                         default_monitoring_interval_seconds: 180,
                     },
+                    service_launcher: ServiceLauncherConfiguration::default(),
+                    service_takeover_policies: HashMap::new(),
+                    admin: AdminConfiguration::default(),
+                    dbus_bridge: DbusBridgeConfiguration::default(),
+                    app_library_refresh: AppLibraryRefreshConfiguration::default(),
+                    entitlements_sync: EntitlementsSyncConfiguration::default(),
+                    watch_history_upload: WatchHistoryUploadConfiguration::default(),
+                    heartbeat: HeartbeatConfiguration::default(),
+                    diagnostics: DiagnosticsConfiguration::default(),
+                    http_bridge: HttpBridgeConfiguration::default(),
+                    method_overrides: MethodOverridesConfiguration::default(),
+                    capability_usage: CapabilityUsageConfiguration::default(),
+                    activation: ActivationConfiguration::default(),
+                    inactivity: InactivityConfiguration::default(),
+                    input: InputConfiguration::default(),
+                    audio_focus: AudioFocusConfiguration::default(),
+                    drm: DrmConfiguration::default(),
+                    compliance: ComplianceConfiguration::default(),
+                    notification_policy: NotificationPolicyConfiguration::default(),
+                    pending_requests: PendingRequestConfiguration::default(),
+                    runtime_topology: RuntimeTopologyConfiguration::default(),
+                    deadlines: DeadlineConfiguration::default(),
+                    shutdown: ShutdownConfiguration::default(),
+                    jq_sandbox: JqSandboxConfiguration::default(),
+                    event_transforms: Vec::new(),
+                    app_usage: AppUsageConfiguration::default(),
+                    metrics_enrichment: MetricsEnrichmentConfiguration::default(),
+                    error_budget: ErrorBudgetConfiguration::default(),
+                    service_liveness: ServiceLivenessConfiguration::default(),
+                    ad_config: AdConfigConfiguration::default(),
+                    service_request_timeouts: ServiceRequestTimeoutConfiguration::default(),
+                    gatekeeper_policy: GatekeeperPolicyConfiguration::default(),
+                    service_http: ServiceHttpConfiguration::default(),
+                    tenants: TenantsConfiguration::default(),
+                    connection_limits: ConnectionLimitsConfiguration::default(),
+                    region: RegionConfiguration::default(),
+                    request_journal: RequestJournalConfiguration::default(),
+                    service_auth: ServiceAuthConfiguration::default(),
+                    service_capabilities: ServiceCapabilityPolicy::default(),
+                    service_conflicts: ServiceConflictConfiguration::default(),
+                    routing: RoutingConfiguration::default(),
                 },
                 capabilities: CapabilityConfiguration {
                     supported: vec!["main[manage]".to_string(), "test".to_string()],
@@ -1058,7 +3496,8 @@ pub(crate) mod tests {
                 privacy_settings_storage_type: PrivacySettingsStorageType::Local,
                 intent_validation: IntentValidation::Fail,
                 cloud_permissions: true,
-                thunder_plugin_status_check_at_broker_start_up: true
+                thunder_plugin_status_check_at_broker_start_up: true,
+                developer_mode: false,
             }
         );
     }
@@ -1226,6 +3665,53 @@ pub(crate) mod tests {
         assert!(default_values.lifecycle_transition_validate);
     }
 
+    #[test]
+    fn test_ws_admission() {
+        let config = WsConfiguration {
+            subprotocols: vec!["jsonrpc".into(), "firebolt".into()],
+            origin_policies: vec![
+                WsOriginPolicy {
+                    origin: "https://blocked.example.com".into(),
+                    allow: false,
+                },
+                WsOriginPolicy {
+                    origin: "*.example.com".into(),
+                    allow: true,
+                },
+            ],
+            reject_unknown_origins: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.negotiate_subprotocol("mqtt, firebolt, jsonrpc"),
+            Some("firebolt".into())
+        );
+        assert_eq!(config.negotiate_subprotocol("mqtt"), None);
+
+        assert!(config.is_origin_allowed(None));
+        assert!(config.is_origin_allowed(Some("https://apps.example.com")));
+        assert!(!config.is_origin_allowed(Some("https://blocked.example.com")));
+        assert!(!config.is_origin_allowed(Some("https://other.com")));
+    }
+
+    #[test]
+    fn test_ws_keepalive() {
+        let config: WsKeepaliveConfiguration = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "idle_timeout_ms": 600000,
+            "app_classes": [
+                {"name": "player", "app_ids": ["player1"]},
+                {"name": "widget", "app_ids": ["widget1"], "idle_timeout_ms": 60000}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(config.ping_interval_ms, 15000);
+        assert_eq!(config.max_missed_pongs, 3);
+        assert_eq!(config.get_idle_timeout_ms("player1"), None);
+        assert_eq!(config.get_idle_timeout_ms("widget1"), Some(60000));
+        assert_eq!(config.get_idle_timeout_ms("app1"), Some(600000));
+    }
+
     #[test]
     fn test_accessibility_audio_desc_settings_default_value() {
         let manifest = DeviceManifest::mock();
@@ -1236,4 +3722,254 @@ pub(crate) mod tests {
                 .accessibility_audio_description_settings
         );
     }
+
+    #[test]
+    fn test_input_key_name() {
+        let mut config: InputConfiguration = serde_json::from_str(
+            r#"{"key_map": {"0x1c": "enter", "0x3e": "settings"}, "restricted_keys": ["settings"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.get_key_name("0x1c"), Some("enter".into()));
+        assert_eq!(config.get_key_name("0x99"), None);
+        assert!(config.is_restricted("settings"));
+        assert!(!config.is_restricted("enter"));
+
+        config.forward_unmapped = true;
+        assert_eq!(config.get_key_name("0x99"), Some("0x99".into()));
+    }
+
+    #[test]
+    fn test_get_event_transform() {
+        let mut manifest = DeviceManifest::mock();
+        manifest.configuration.event_transforms = vec![EventTransform {
+            event: "device.onNameChanged".into(),
+            sdk_versions: vec!["1.2".into()],
+            filter: "{ value: . }".into(),
+        }];
+        assert!(manifest
+            .get_event_transform("device.onnamechanged", "1.2.5")
+            .is_some());
+        assert!(manifest
+            .get_event_transform("device.onNameChanged", "1.2")
+            .is_some());
+        assert!(manifest
+            .get_event_transform("device.onNameChanged", "1.20.0")
+            .is_none());
+        assert!(manifest
+            .get_event_transform("device.onModelChanged", "1.2.5")
+            .is_none());
+    }
+
+    #[test]
+    fn test_service_request_timeout() {
+        let config: ServiceRequestTimeoutConfiguration = serde_json::from_str(
+            r#"{"endpoint_timeouts_ms": {"ripple:channel:test:svc": 5000}, "method_timeouts_ms": {"svc.slow": 60000}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.get_timeout("ripple:channel:test:svc", "svc.Slow"),
+            Duration::from_millis(60000)
+        );
+        assert_eq!(
+            config.get_timeout("ripple:channel:test:svc", "svc.fast"),
+            Duration::from_millis(5000)
+        );
+        assert_eq!(
+            config.get_timeout("ripple:channel:other:svc", "other.fast"),
+            Duration::from_millis(30000)
+        );
+    }
+
+    #[test]
+    fn test_pending_request_threshold() {
+        let config: PendingRequestConfiguration = serde_json::from_str(
+            r#"{"excluded_methods": ["discovery.launch"], "method_thresholds_ms": {"device.*": 5000, "device.Model": 2000}}"#,
+        )
+        .unwrap();
+        assert!(!config.enabled);
+        assert_eq!(
+            config.get_stuck_threshold("device.model"),
+            Some(Duration::from_millis(2000))
+        );
+        assert_eq!(
+            config.get_stuck_threshold("device.name"),
+            Some(Duration::from_millis(5000))
+        );
+        assert_eq!(
+            config.get_stuck_threshold("localization.language"),
+            Some(Duration::from_millis(60000))
+        );
+        assert!(config.get_stuck_threshold("Discovery.launch").is_none());
+        assert!(config.get_stuck_threshold("keyboard.standard").is_none());
+        assert!(config.get_stuck_threshold("usergrants.request").is_none());
+    }
+
+    #[test]
+    fn test_is_policy_method() {
+        let config = GatekeeperPolicyConfiguration {
+            methods: vec!["Device.Name".into(), "advertising.*".into()],
+            ..Default::default()
+        };
+        assert!(config.is_policy_method("device.name"));
+        assert!(config.is_policy_method("advertising.policy"));
+        assert!(!config.is_policy_method("device.model"));
+        assert!(!GatekeeperPolicyConfiguration::default().is_policy_method("device.name"));
+    }
+
+    #[test]
+    fn test_get_tenant() {
+        let config: TenantsConfiguration = serde_json::from_str(
+            r#"{"tenants": [{"id": "vendor_a", "service_prefix": "ripple:channel:vendora:", "max_services": 2, "method_namespaces": ["VendorA"]}]}"#,
+        )
+        .unwrap();
+        let tenant = config.get_tenant("ripple:channel:vendora:player").unwrap();
+        assert_eq!(tenant.id, "vendor_a");
+        assert!(tenant.is_method_allowed("vendora.play"));
+        assert!(!tenant.is_method_allowed("device.name"));
+        assert!(config
+            .get_tenant("ripple:channel:gateway:service1")
+            .is_none());
+    }
+
+    #[test]
+    fn test_get_app_limit() {
+        let config: ConnectionLimitsConfiguration = serde_json::from_str(
+            r#"{"max_sessions": 16, "max_connections_per_app": 2, "app_limits": {"refui": 4}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.max_sessions, Some(16));
+        assert_eq!(config.get_app_limit("refui"), Some(4));
+        assert_eq!(config.get_app_limit("app1"), Some(2));
+        assert_eq!(
+            ConnectionLimitsConfiguration::default().get_app_limit("app1"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_service_liveness_timeout() {
+        let mut config = ServiceLivenessConfiguration::default();
+        assert_eq!(config.get_timeout_ms(), 15000);
+        config.timeout_ms = 5000;
+        assert_eq!(config.get_timeout_ms(), 10000);
+    }
+
+    #[test]
+    fn test_region_configuration() {
+        let config: RegionConfiguration = serde_json::from_str(
+            r#"{"sources": ["remote", "manifest", "platform"], "fixed": {"countryCode": "GB", "timeZone": "Europe/London"}, "remote_keys": {"countryCode": "ripple_region_country"}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.sources[0], RegionSourceType::Remote);
+        assert_eq!(
+            config.fixed.get(RegionField::CountryCode),
+            Some("GB".into())
+        );
+        assert_eq!(config.fixed.get(RegionField::Locale), None);
+        assert_eq!(
+            RegionField::from_method("localization.timeZone"),
+            Some(RegionField::TimeZone)
+        );
+        assert_eq!(RegionField::from_method("localization.language"), None);
+        assert_eq!(
+            RegionConfiguration::default().sources,
+            vec![RegionSourceType::Platform]
+        );
+    }
+
+    #[test]
+    fn test_request_journal_configuration() {
+        let config: RequestJournalConfiguration =
+            serde_json::from_str(r#"{"enabled": true, "max_entries": 50}"#).unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.max_entries, 50);
+        assert_eq!(config.crash_tail_size, 20);
+        assert!(!RequestJournalConfiguration::default().enabled);
+    }
+
+    #[test]
+    fn test_service_capability_policy() {
+        let policy: ServiceCapabilityPolicy = serde_json::from_value(serde_json::json!({
+            "enforce": true,
+            "services": { "xvp": ["xrn:firebolt:capability:discovery:watched"] },
+            "capability_methods": {
+                "xrn:firebolt:capability:discovery:watched": ["discovery.watched", "content.*"]
+            }
+        }))
+        .unwrap();
+        let (granted, rejected) = policy.grant_capabilities(
+            "xvp",
+            &[
+                "xrn:firebolt:capability:discovery:watched".to_owned(),
+                "xrn:firebolt:capability:device:info".to_owned(),
+            ],
+        );
+        assert_eq!(granted, vec!["xrn:firebolt:capability:discovery:watched"]);
+        assert_eq!(rejected, vec!["xrn:firebolt:capability:device:info"]);
+        assert!(policy.is_method_covered(&granted, "Discovery.watched"));
+        assert!(policy.is_method_covered(&granted, "content.providers"));
+        assert!(!policy.is_method_covered(&granted, "device.name"));
+        assert!(policy.grant_capabilities("other", &granted).0.is_empty());
+
+        let default = ServiceCapabilityPolicy::default();
+        assert!(default.enforce);
+        assert!(default.grant_capabilities("xvp", &granted).0.is_empty());
+        assert!(!default.is_method_covered(&[], "device.name"));
+
+        let open = ServiceCapabilityPolicy {
+            enforce: false,
+            ..Default::default()
+        };
+        assert!(open.is_method_covered(&[], "device.name"));
+    }
+
+    #[test]
+    fn test_service_conflict_configuration() {
+        let mut config: ServiceConflictConfiguration = serde_json::from_value(serde_json::json!({
+            "policy": "priorityOrdered",
+            "priorities": ["media", "xvp"]
+        }))
+        .unwrap();
+        assert!(config.is_won_by("media", "xvp"));
+        assert!(!config.is_won_by("xvp", "media"));
+        assert!(config.is_won_by("xvp", "other"));
+        assert!(!config.is_won_by("other", "unknown"));
+
+        config.policy = MethodConflictPolicy::LastWriterWins;
+        assert!(config.is_won_by("other", "media"));
+        assert!(!ServiceConflictConfiguration::default().is_won_by("media", "xvp"));
+    }
+
+    #[test]
+    fn test_routing_configuration() {
+        let config: RoutingConfiguration = serde_json::from_value(serde_json::json!({
+            "precedence": ["handler", "broker", "handler"]
+        }))
+        .unwrap();
+        assert_eq!(
+            config.get_precedence(),
+            vec![
+                RouteTarget::Handler,
+                RouteTarget::Broker,
+                RouteTarget::Service,
+                RouteTarget::Extension
+            ]
+        );
+        assert_eq!(
+            RoutingConfiguration::default().get_precedence(),
+            DEFAULT_ROUTE_PRECEDENCE.to_vec()
+        );
+    }
+
+    #[test]
+    fn test_ws_tls_configuration() {
+        let config: WsConfiguration = serde_json::from_str(
+            r#"{"enabled": true, "gateway": "0.0.0.0:3474", "tls": {"cert_path": "/etc/ripple/cert.pem", "key_path": "/etc/ripple/key.pem"}}"#,
+        )
+        .unwrap();
+        let tls = config.tls.unwrap();
+        assert_eq!(tls.cert_path, "/etc/ripple/cert.pem");
+        assert!(tls.client_ca_path.is_none());
+        assert!(ws_configuration_default().tls.is_none());
+    }
 }
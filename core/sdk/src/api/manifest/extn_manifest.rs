@@ -20,7 +20,33 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::{fs, path::Path};
 
-use crate::{extn::extn_id::ExtnId, utils::error::RippleError};
+use crate::{
+    api::status_update::ExtnContractStatus,
+    extn::{client::extn_usage::DEFAULT_BACKLOG_WARNING_THRESHOLD, extn_id::ExtnId},
+    utils::error::RippleError,
+};
+
+/// Contract version assumed when neither side declares one.
+pub const DEFAULT_CONTRACT_VERSION: u32 = 1;
+
+/// Contract versions supported by Ripple Main for contracts which evolved past the
+/// [DEFAULT_CONTRACT_VERSION].
+/// - `config` version 2 serves the typed and versioned [crate::api::config::ConfigSection]s
+/// - `telemetry_events_listener` version 2 carries the versioned telemetry event schemas
+pub const CORE_CONTRACT_VERSIONS: &[(&str, &[u32])] =
+    &[("config", &[1, 2]), ("telemetry_events_listener", &[1, 2])];
+
+/// Query parameter of the service handshake in which an extension declares the contract
+/// versions it supports, e.g. `contractVersions=config:1;2,device_info:1`.
+pub const CONTRACT_VERSIONS_QUERY: &str = "contractVersions";
+
+pub fn get_core_contract_versions(contract: &str) -> Vec<u32> {
+    CORE_CONTRACT_VERSIONS
+        .iter()
+        .find(|(c, _)| c.eq(&contract))
+        .map(|(_, versions)| versions.to_vec())
+        .unwrap_or_else(|| vec![DEFAULT_CONTRACT_VERSION])
+}
 
 /// Contains the default path for the manifest
 /// file extension type based on platform
@@ -41,6 +67,17 @@ pub struct ExtnManifest {
     pub extn_sdks: Vec<String>,
     #[serde(default = "default_providers")]
     pub provider_registrations: Vec<String>,
+    /// Routes permitted between extensions. Once routes are listed every extension to
+    /// extension message which does not match a route is denied, so extensions can only talk
+    /// to each other through the routes listed here.
+    #[serde(default)]
+    pub extn_routes: Vec<ExtnRoute>,
+    /// Overrides whether the routes are enforced, by default they are enforced once any route
+    /// is listed. Devices which list no routes keep forwarding messages between extensions,
+    /// `true` denies them all until routes are added.
+    #[serde(default)]
+    pub extn_routes_enforced: Option<bool>,
+    pub backlog_warning_threshold: Option<usize>,
 }
 
 /// Some unit tests which use defaults are failing because we need default providers for unit testing
@@ -57,6 +94,9 @@ impl Default for ExtnManifest {
             rules_path: Vec::new(),
             extn_sdks: Vec::new(),
             provider_registrations: default_providers(),
+            extn_routes: Vec::new(),
+            extn_routes_enforced: None,
+            backlog_warning_threshold: None,
         }
     }
 }
@@ -76,6 +116,29 @@ pub fn default_providers() -> Vec<String> {
     value.iter().map(|x| x.to_string()).collect()
 }
 
+/// Permits an extension to send messages for the listed contracts to another extension
+/// through Ripple Main. `*` matches any extension or contract.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ExtnRoute {
+    pub from: String,
+    pub to: String,
+    #[serde(default = "default_route_contracts")]
+    pub contracts: Vec<String>,
+}
+
+fn default_route_contracts() -> Vec<String> {
+    vec!["*".to_owned()]
+}
+
+impl ExtnRoute {
+    pub fn permits(&self, from: &str, to: &str, contract: &str) -> bool {
+        (self.from.eq("*") || self.from.eq(from))
+            && (self.to.eq("*") || self.to.eq(to))
+            && self.contracts.iter().any(|c| c.eq("*") || c.eq(contract))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct ExtnResolutionEntry {
@@ -100,9 +163,84 @@ pub struct ExtnSymbol {
     pub uses: Vec<String>,
     pub fulfills: Vec<String>,
     pub config: Option<HashMap<String, String>>,
+    /// Contract versions supported by the extension, keyed by contract. Contracts which are
+    /// not listed are assumed to support [DEFAULT_CONTRACT_VERSION].
+    #[serde(default)]
+    pub contract_versions: HashMap<String, Vec<u32>>,
 }
 
 impl ExtnSymbol {
+    pub fn get_contract_versions(&self, contract: &str) -> Vec<u32> {
+        self.contract_versions
+            .get(contract)
+            .cloned()
+            .unwrap_or_else(|| vec![DEFAULT_CONTRACT_VERSION])
+    }
+
+    /// Encodes the declared contract versions for the [CONTRACT_VERSIONS_QUERY] of the
+    /// service handshake, `None` when the extension declares no versions.
+    pub fn encode_contract_versions(&self) -> Option<String> {
+        if self.contract_versions.is_empty() {
+            return None;
+        }
+        let mut contracts: Vec<String> = self
+            .contract_versions
+            .iter()
+            .map(|(contract, versions)| {
+                let versions: Vec<String> = versions.iter().map(|v| v.to_string()).collect();
+                format!("{}:{}", contract, versions.join(";"))
+            })
+            .collect();
+        contracts.sort();
+        Some(contracts.join(","))
+    }
+
+    /// Decodes the contract versions declared by an extension in the service handshake.
+    pub fn decode_contract_versions(
+        declaration: &str,
+    ) -> Result<HashMap<String, Vec<u32>>, RippleError> {
+        let mut contract_versions = HashMap::new();
+        for entry in declaration.split(',').filter(|e| !e.is_empty()) {
+            let (contract, versions) = entry.split_once(':').ok_or(RippleError::ParseError)?;
+            let versions = versions
+                .split(';')
+                .map(|v| v.parse::<u32>().map_err(|_| RippleError::ParseError))
+                .collect::<Result<Vec<u32>, RippleError>>()?;
+            if contract.is_empty() || versions.is_empty() {
+                return Err(RippleError::ParseError);
+            }
+            contract_versions.insert(contract.to_owned(), versions);
+        }
+        Ok(contract_versions)
+    }
+
+    /// Selects the highest version supported by both the extension and Ripple Main for
+    /// every contract the extension uses or fulfills.
+    pub fn negotiate_contract_versions(&self) -> Vec<ExtnContractStatus> {
+        let mut contracts: Vec<&String> = self.fulfills.iter().chain(self.uses.iter()).collect();
+        contracts.sort();
+        contracts.dedup();
+        contracts
+            .into_iter()
+            .map(|contract| {
+                let extn_versions = self.get_contract_versions(contract);
+                let core_versions = get_core_contract_versions(contract);
+                let negotiated_version = extn_versions
+                    .iter()
+                    .filter(|v| core_versions.contains(v))
+                    .max()
+                    .cloned();
+                ExtnContractStatus {
+                    extn_id: self.id.clone(),
+                    contract: contract.clone(),
+                    negotiated_version,
+                    extn_versions,
+                    core_versions,
+                }
+            })
+            .collect()
+    }
+
     pub fn get_launcher_capability(&self) -> Option<ExtnId> {
         if let Ok(cap) = ExtnId::try_from(self.id.clone()) {
             if cap.is_launcher_channel() {
@@ -208,6 +346,16 @@ impl ExtnManifest {
         self.timeout.unwrap_or(10000)
     }
 
+    pub fn get_backlog_warning_threshold(&self) -> usize {
+        self.backlog_warning_threshold
+            .unwrap_or(DEFAULT_BACKLOG_WARNING_THRESHOLD)
+    }
+
+    pub fn is_extn_routes_enforced(&self) -> bool {
+        self.extn_routes_enforced
+            .unwrap_or(!self.extn_routes.is_empty())
+    }
+
     pub fn has_rpc_override_method(&self, method: &str) -> Option<String> {
         self.rpc_overrides.get(method).cloned()
     }
@@ -223,10 +371,18 @@ impl ExtnManifest {
     pub fn get_extn_symbol(&self, id: &str) -> Option<ExtnSymbol> {
         self.get_all_extns().into_iter().find(|extn| extn.id.eq(id))
     }
+
+    pub fn negotiate_contract_versions(&self) -> Vec<ExtnContractStatus> {
+        self.get_all_extns()
+            .iter()
+            .flat_map(|extn| extn.negotiate_contract_versions())
+            .collect()
+    }
 }
 #[cfg(test)]
 pub(crate) mod tests {
     use crate::extn::extn_id::{ExtnClassId, ExtnType};
+    use crate::framework::ripple_contract::RippleContract;
 
     use super::*;
 
@@ -249,6 +405,9 @@ pub(crate) mod tests {
                 rules_path: Vec::new(),
                 extn_sdks: Vec::new(),
                 provider_registrations: Vec::new(),
+                extn_routes: Vec::new(),
+                extn_routes_enforced: None,
+                backlog_warning_threshold: None,
             }
         }
     }
@@ -281,6 +440,7 @@ pub(crate) mod tests {
             uses: vec![],
             fulfills: vec![],
             config: None,
+            contract_versions: HashMap::new(),
         };
         let extn_manifest_entry = ExtnManifestEntry {
             path: "relative/path".to_string(),
@@ -293,6 +453,73 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn test_negotiate_contract_versions() {
+        let symbol = ExtnSymbol {
+            id: "ripple:channel:device:thunder".to_string(),
+            uses: vec!["config".to_string()],
+            fulfills: vec!["device_info".to_string(), "config".to_string()],
+            config: None,
+            contract_versions: HashMap::from([
+                ("device_info".to_string(), vec![1, 2]),
+                ("config".to_string(), vec![2, 3]),
+            ]),
+        };
+        let matrix = symbol.negotiate_contract_versions();
+        assert_eq!(matrix.len(), 2);
+
+        let config = matrix.iter().find(|s| s.contract.eq("config")).unwrap();
+        assert_eq!(config.negotiated_version, Some(2));
+
+        let device_info = matrix
+            .iter()
+            .find(|s| s.contract.eq("device_info"))
+            .unwrap();
+        assert_eq!(
+            device_info.negotiated_version,
+            Some(DEFAULT_CONTRACT_VERSION)
+        );
+        assert!(device_info.get_error().is_none());
+
+        let symbol = ExtnSymbol {
+            contract_versions: HashMap::from([("config".to_string(), vec![3])]),
+            ..symbol
+        };
+        let matrix = symbol.negotiate_contract_versions();
+        let config = matrix.iter().find(|s| s.contract.eq("config")).unwrap();
+        assert!(!config.is_compatible());
+        assert!(config.get_error().unwrap().contains("config"));
+    }
+
+    #[test]
+    fn test_core_contract_versions() {
+        for (contract, versions) in CORE_CONTRACT_VERSIONS {
+            assert!(RippleContract::try_from(format!("\"{}\"", contract)).is_ok());
+            assert!(versions.contains(&DEFAULT_CONTRACT_VERSION));
+        }
+    }
+
+    #[test]
+    fn test_contract_versions_declaration() {
+        let symbol = ExtnSymbol {
+            id: "ripple:channel:device:thunder".to_string(),
+            contract_versions: HashMap::from([
+                ("device_info".to_string(), vec![1]),
+                ("config".to_string(), vec![1, 2]),
+            ]),
+            ..Default::default()
+        };
+        let declaration = symbol.encode_contract_versions().unwrap();
+        assert_eq!(declaration, "config:1;2,device_info:1");
+        assert_eq!(
+            ExtnSymbol::decode_contract_versions(&declaration).unwrap(),
+            symbol.contract_versions
+        );
+        assert!(ExtnSymbol::default().encode_contract_versions().is_none());
+        assert!(ExtnSymbol::decode_contract_versions("config:x").is_err());
+        assert!(ExtnSymbol::decode_contract_versions("config").is_err());
+    }
+
     #[test]
     fn test_load_from_content_valid() {
         let contents = r#"
@@ -333,6 +560,7 @@ pub(crate) mod tests {
             uses: vec![],
             fulfills: vec![],
             config: None,
+            contract_versions: HashMap::new(),
         };
 
         let capability = symbol.get_launcher_capability();
@@ -354,6 +582,7 @@ pub(crate) mod tests {
             uses: vec![],
             fulfills: vec![],
             config: None,
+            contract_versions: HashMap::new(),
         };
 
         let capability = symbol.get_distributor_capability();
@@ -376,6 +605,7 @@ pub(crate) mod tests {
             uses: vec![],
             fulfills: vec![],
             config: None,
+            contract_versions: HashMap::new(),
         };
         let extn_manifest_entry = ExtnManifestEntry {
             path: "relative/path".to_string(),
@@ -400,6 +630,7 @@ pub(crate) mod tests {
             uses: vec![],
             fulfills: vec![],
             config: None,
+            contract_versions: HashMap::new(),
         };
         let extn_manifest_entry = ExtnManifestEntry {
             path: "relative/path".to_string(),
@@ -424,6 +655,7 @@ pub(crate) mod tests {
             uses: vec!["config".to_string()],
             fulfills: vec!["test".to_string()],
             config: None,
+            contract_versions: HashMap::new(),
         };
         let extn_manifest_entry = ExtnManifestEntry {
             path: "relative/path".to_string(),
@@ -446,4 +678,24 @@ pub(crate) mod tests {
         manifest.timeout = None;
         assert_eq!(manifest.get_timeout(), 10000);
     }
+
+    #[test]
+    fn test_is_extn_routes_enforced() {
+        let mut manifest = ExtnManifest::mock();
+        assert!(!manifest.is_extn_routes_enforced());
+
+        manifest.extn_routes = vec![ExtnRoute {
+            from: "ripple:extn:jsonrpsee:custom".to_string(),
+            to: "ripple:channel:device:thunder".to_string(),
+            contracts: vec!["device_info".to_string()],
+        }];
+        assert!(manifest.is_extn_routes_enforced());
+
+        manifest.extn_routes_enforced = Some(false);
+        assert!(!manifest.is_extn_routes_enforced());
+
+        manifest.extn_routes = Vec::new();
+        manifest.extn_routes_enforced = Some(true);
+        assert!(manifest.is_extn_routes_enforced());
+    }
 }
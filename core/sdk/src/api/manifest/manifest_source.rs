@@ -0,0 +1,188 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use log::{error, info};
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::utils::error::RippleError;
+
+use super::cascaded_device_manifest::merge_json_objects;
+
+/// Key used by manifests and rule sets to pull in other files
+pub const INCLUDE_KEY: &str = "include";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ManifestFormat {
+    /// Format is derived from the file extension, anything unknown is treated as json
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .as_deref()
+        {
+            Some("yaml") | Some("yml") => ManifestFormat::Yaml,
+            Some("toml") => ManifestFormat::Toml,
+            _ => ManifestFormat::Json,
+        }
+    }
+
+    pub fn parse(&self, contents: &str) -> Result<Value, RippleError> {
+        let result = match self {
+            ManifestFormat::Json => {
+                serde_json::from_str::<Value>(contents).map_err(|e| e.to_string())
+            }
+            ManifestFormat::Yaml => {
+                serde_yaml::from_str::<Value>(contents).map_err(|e| e.to_string())
+            }
+            ManifestFormat::Toml => toml::from_str::<Value>(contents).map_err(|e| e.to_string()),
+        };
+        result.map_err(|e| {
+            error!("could not parse {:?} manifest e={}", self, e);
+            RippleError::ParseError
+        })
+    }
+}
+
+/// Loads json, yaml or toml manifests into a single json document.
+///
+/// A document can list other files under `include`, either a single path or an array of paths
+/// relative to the including file. Included files are merged in order and the including file
+/// is merged last so its values win. Include cycles are rejected.
+pub struct ManifestSource;
+
+impl ManifestSource {
+    pub fn load(path: &str) -> Result<Value, RippleError> {
+        let mut stack = Vec::new();
+        Self::load_with_stack(Path::new(path), &mut stack)
+    }
+
+    /// Loads the manifest and returns it as a json string, for loaders which parse content
+    pub fn load_to_string(path: &str) -> Result<String, RippleError> {
+        let value = Self::load(path)?;
+        serde_json::to_string(&value).map_err(|_| RippleError::ParseError)
+    }
+
+    fn load_with_stack(path: &Path, stack: &mut Vec<PathBuf>) -> Result<Value, RippleError> {
+        let canonical = fs::canonicalize(path).map_err(|_| RippleError::MissingInput)?;
+        if stack.contains(&canonical) {
+            error!(
+                "include cycle detected path={} stack={:?}",
+                canonical.display(),
+                stack
+            );
+            return Err(RippleError::InvalidInput);
+        }
+        let contents = fs::read_to_string(&canonical).map_err(|_| RippleError::MissingInput)?;
+        let mut value = ManifestFormat::from_path(&canonical).parse(&contents)?;
+        let includes = match value.as_object_mut().and_then(|o| o.remove(INCLUDE_KEY)) {
+            None => return Ok(value),
+            Some(Value::String(include)) => vec![include],
+            Some(Value::Array(includes)) => includes
+                .into_iter()
+                .filter_map(|i| i.as_str().map(String::from))
+                .collect(),
+            Some(_) => {
+                error!("invalid include in {}", canonical.display());
+                return Err(RippleError::InvalidInput);
+            }
+        };
+
+        stack.push(canonical.clone());
+        let base = canonical.parent().unwrap_or(Path::new("/")).to_path_buf();
+        let mut merged = Map::new();
+        for include in includes {
+            let include_path = base.join(&include);
+            info!(
+                "including {} from {}",
+                include_path.display(),
+                canonical.display()
+            );
+            match Self::load_with_stack(&include_path, stack)? {
+                Value::Object(included) => merge_json_objects(&mut merged, &included),
+                _ => {
+                    error!(
+                        "included manifest {} is not an object",
+                        include_path.display()
+                    );
+                    return Err(RippleError::InvalidInput);
+                }
+            }
+        }
+        stack.pop();
+
+        if let Value::Object(own) = value {
+            merge_json_objects(&mut merged, &own);
+        }
+        Ok(Value::Object(merged))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_includes() {
+        let dir = std::env::temp_dir().join("ripple_manifest_source_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("rules.yaml"),
+            "include:\n  - device.toml\n  - advertising.json\nrules:\n  device.name:\n    alias: override\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("device.toml"),
+            "[rules.\"device.name\"]\nalias = \"org.rdk.System.getFriendlyName\"\n[rules.\"device.model\"]\nalias = \"org.rdk.System.getDeviceInfo\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("advertising.json"),
+            r#"{"rules": {"advertising.policy": {"alias": "static"}}}"#,
+        )
+        .unwrap();
+
+        let value = ManifestSource::load(dir.join("rules.yaml").to_str().unwrap()).unwrap();
+        assert!(value.get(INCLUDE_KEY).is_none());
+        let rules = value.get("rules").unwrap();
+        assert_eq!(rules["device.name"]["alias"], "override");
+        assert_eq!(
+            rules["device.model"]["alias"],
+            "org.rdk.System.getDeviceInfo"
+        );
+        assert_eq!(rules["advertising.policy"]["alias"], "static");
+
+        // a file including itself through another file is rejected
+        fs::write(dir.join("advertising.json"), r#"{"include": "rules.yaml"}"#).unwrap();
+        assert_eq!(
+            ManifestSource::load(dir.join("rules.yaml").to_str().unwrap()),
+            Err(RippleError::InvalidInput)
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
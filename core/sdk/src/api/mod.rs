@@ -23,6 +23,8 @@ pub mod config;
 pub mod context;
 pub mod default_storage_properties;
 pub mod device;
+pub mod extn_method;
+pub mod gatekeeper_policy;
 pub mod manifest;
 pub mod ripple_cache;
 pub mod session;
@@ -37,9 +39,13 @@ pub mod wifi;
 pub mod gateway {
     pub mod rpc_error;
     pub mod rpc_gateway_api;
+    pub mod rpc_response;
 }
 
 pub mod distributor {
+    pub mod distributor_activation;
+    pub mod distributor_advertising;
+    pub mod distributor_entitlements;
     pub mod distributor_permissions;
     pub mod distributor_privacy;
     pub mod distributor_usergrants;
@@ -47,14 +53,18 @@ pub mod distributor {
 
 pub mod firebolt {
     pub mod fb_advertising;
+    pub mod fb_audio_focus;
     pub mod fb_capabilities;
+    pub mod fb_diagnostics;
     pub mod fb_discovery;
     pub mod fb_general;
     pub mod fb_keyboard;
     pub mod fb_lifecycle;
     pub mod fb_lifecycle_management;
     pub mod fb_localization;
+    pub mod fb_media_session;
     pub mod fb_metrics;
+    pub mod fb_notification_policy;
     pub mod fb_openrpc;
     pub mod fb_parameters;
     pub mod fb_pin;
@@ -65,6 +75,7 @@ pub mod firebolt {
 }
 
 pub mod observability {
+    pub mod device_health;
     pub mod log_signal;
     pub mod metrics_util;
     pub mod operational_metrics;
@@ -0,0 +1,28 @@
+use std::fs;
+
+/// Resident memory of the current process in kB, read from `/proc/self/status`
+pub fn get_memory_rss_kb() -> Option<u64> {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| parse_vm_rss(&status))
+}
+
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|value| value.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\tripple\nVmPeak:\t  204800 kB\nVmRSS:\t   51200 kB\nThreads:\t8\n";
+        assert_eq!(parse_vm_rss(status), Some(51200));
+        assert_eq!(parse_vm_rss("Name:\tripple\n"), None);
+    }
+}
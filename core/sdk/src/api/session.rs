@@ -195,6 +195,7 @@ pub enum EventAdjective {
     Input,
     VoiceGuidance,
     Audio,
+    RemoteKey,
 }
 
 impl ContractAdjective for EventAdjective {
@@ -35,6 +35,8 @@ pub enum SettingKey {
     DeviceName,
     PowerSaving,
     LegacyMiniGuide,
+    HighContrast,
+    Magnification,
 }
 
 impl std::fmt::Display for SettingKey {
@@ -68,6 +70,8 @@ impl SettingKey {
             SettingKey::DeviceName => "device:name",
             SettingKey::PowerSaving => "",
             SettingKey::LegacyMiniGuide => "",
+            SettingKey::HighContrast => "accessibility:highcontrast",
+            SettingKey::Magnification => "accessibility:magnification",
         }
     }
 }
@@ -168,6 +172,17 @@ mod tests {
         assert_eq!(setting_key.use_capability(), "accessibility:voiceguidance");
     }
 
+    #[test]
+    fn test_accessibility_setting_key_from_str() {
+        let setting_key = SettingKey::from_str("\"Magnification\"").unwrap();
+        assert_eq!(setting_key, SettingKey::Magnification);
+        assert_eq!(setting_key.use_capability(), "accessibility:magnification");
+        assert_eq!(
+            SettingKey::HighContrast.use_capability(),
+            "accessibility:highcontrast"
+        );
+    }
+
     #[test]
     fn test_settings_request_param_get_alias() {
         let alias_map = Some(
@@ -189,6 +204,7 @@ mod tests {
                 cid: Some("test_cid".to_string()),
                 gateway_secure: true,
                 context: Vec::new(),
+                deadline: None,
             },
             vec![SettingKey::VoiceGuidanceEnabled, SettingKey::ClosedCaptions],
             alias_map,
@@ -216,6 +232,7 @@ mod tests {
                 cid: Some("test_cid".to_string()),
                 gateway_secure: true,
                 context: Vec::new(),
+                deadline: None,
             },
             keys: vec![SettingKey::VoiceGuidanceEnabled, SettingKey::ClosedCaptions],
             alias_map: Some(HashMap::new()),
@@ -46,6 +46,33 @@ impl ExtnPayloadProvider for ExtnStatus {
     }
 }
 
+/// Outcome of the contract version negotiation between Ripple Main and an extension.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnContractStatus {
+    pub extn_id: String,
+    pub contract: String,
+    pub negotiated_version: Option<u32>,
+    pub extn_versions: Vec<u32>,
+    pub core_versions: Vec<u32>,
+}
+
+impl ExtnContractStatus {
+    pub fn is_compatible(&self) -> bool {
+        self.negotiated_version.is_some()
+    }
+
+    pub fn get_error(&self) -> Option<String> {
+        if self.is_compatible() {
+            return None;
+        }
+        Some(format!(
+            "Extension {} is incompatible for contract {}: extension supports versions {:?} but Ripple supports {:?}. Update the extension or the contract versions it declares",
+            self.extn_id, self.contract, self.extn_versions, self.core_versions
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
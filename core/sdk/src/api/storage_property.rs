@@ -34,6 +34,8 @@ pub const NAMESPACE_DEVICE_NAME: &str = "DeviceName";
 pub const NAMESPACE_LOCALIZATION: &str = "Localization";
 pub const NAMESPACE_ADVERTISING: &str = "Advertising";
 pub const NAMESPACE_AUDIO_DESCRIPTION: &str = "AudioDescription";
+pub const NAMESPACE_ACCESSIBILITY: &str = "Accessibility";
+pub const NAMESPACE_NOTIFICATIONS: &str = "Notifications";
 
 pub const KEY_ENABLED: &str = "enabled";
 pub const KEY_FONT_FAMILY: &str = "fontFamily";
@@ -73,6 +75,11 @@ pub const KEY_VOICE_GUIDANCE_SPEED: &str = "speed";
 pub const KEY_PARTNER_EXCLUSIONS: &str = "partnerExclusions";
 pub const KEY_SKIP_RESTRICTION: &str = "skipRestriction";
 pub const KEY_AUDIO_DESCRIPTION_ENABLED: &str = "audioDescriptionEnabled";
+pub const KEY_HIGH_CONTRAST_ENABLED: &str = "highContrastEnabled";
+pub const KEY_MAGNIFICATION_ENABLED: &str = "magnificationEnabled";
+pub const KEY_MAGNIFICATION_SCALE: &str = "magnificationScale";
+pub const KEY_DO_NOT_DISTURB_ENABLED: &str = "doNotDisturbEnabled";
+pub const KEY_DO_NOT_DISTURB_WINDOWS: &str = "doNotDisturbWindows";
 pub const KEY_PREFERRED_AUDIO_LANGUAGES: &str = "preferredAudioLanguages";
 
 pub const EVENT_CLOSED_CAPTIONS_SETTINGS_CHANGED: &str =
@@ -125,6 +132,10 @@ pub const EVENT_PREFERRED_AUDIO_LANGUAGES: &str = "Localization.onPreferredAudio
 pub const EVENT_CC_PREFERRED_LANGUAGES: &str = "ClosedCaptions.onPreferredLanguagesChanged";
 pub const EVENT_AUDIO_DESCRIPTION_SETTINGS_CHANGED: &str =
     "Accessibility.onAudioDescriptionSettingsChanged";
+pub const EVENT_HIGH_CONTRAST_SETTINGS_CHANGED: &str =
+    "accessibility.onHighContrastSettingsChanged";
+pub const EVENT_MAGNIFICATION_SETTINGS_CHANGED: &str =
+    "accessibility.onMagnificationSettingsChanged";
 pub const EVENT_TIMEZONE_CHANGED: &str = "localization.onTimeZoneChanged";
 
 const PROPERTY_DATA_CLOSED_CAPTIONS_FONT_FAMILY: PropertyData = PropertyData {
@@ -374,6 +385,38 @@ const PROPERTY_AUDIO_DESCRIPTION_ENABLED: PropertyData = PropertyData {
     event_names: Some(&[EVENT_AUDIO_DESCRIPTION_SETTINGS_CHANGED]),
 };
 
+const PROPERTY_HIGH_CONTRAST_ENABLED: PropertyData = PropertyData {
+    key: KEY_HIGH_CONTRAST_ENABLED,
+    namespace: NAMESPACE_ACCESSIBILITY,
+    event_names: Some(&[EVENT_HIGH_CONTRAST_SETTINGS_CHANGED]),
+};
+
+const PROPERTY_MAGNIFICATION_ENABLED: PropertyData = PropertyData {
+    key: KEY_MAGNIFICATION_ENABLED,
+    namespace: NAMESPACE_ACCESSIBILITY,
+    event_names: Some(&[EVENT_MAGNIFICATION_SETTINGS_CHANGED]),
+};
+
+const PROPERTY_MAGNIFICATION_SCALE: PropertyData = PropertyData {
+    key: KEY_MAGNIFICATION_SCALE,
+    namespace: NAMESPACE_ACCESSIBILITY,
+    event_names: Some(&[EVENT_MAGNIFICATION_SETTINGS_CHANGED]),
+};
+
+// the do not disturb status event is emitted by the notification policy once the windows
+// are evaluated, so the properties have no events of their own
+const PROPERTY_DO_NOT_DISTURB_ENABLED: PropertyData = PropertyData {
+    key: KEY_DO_NOT_DISTURB_ENABLED,
+    namespace: NAMESPACE_NOTIFICATIONS,
+    event_names: None,
+};
+
+const PROPERTY_DO_NOT_DISTURB_WINDOWS: PropertyData = PropertyData {
+    key: KEY_DO_NOT_DISTURB_WINDOWS,
+    namespace: NAMESPACE_NOTIFICATIONS,
+    event_names: None,
+};
+
 const PROPERTY_CC_PREFERRED_LANGUAGES: PropertyData = PropertyData {
     key: KEY_PREFERRED_AUDIO_LANGUAGES,
     namespace: NAMESPACE_CLOSED_CAPTIONS,
@@ -434,6 +477,11 @@ pub enum StorageProperty {
     SkipRestriction,
     AudioDescriptionEnabled,
     CCPreferredLanguages,
+    HighContrastEnabled,
+    MagnificationEnabled,
+    MagnificationScale,
+    DoNotDisturbEnabled,
+    DoNotDisturbWindows,
 }
 
 impl TryFrom<PrivacySetting> for StorageProperty {
@@ -519,6 +567,11 @@ impl StorageProperty {
             StorageProperty::SkipRestriction => PROPERTY_DATA_SKIP_RESTRICTION,
             StorageProperty::AudioDescriptionEnabled => PROPERTY_AUDIO_DESCRIPTION_ENABLED,
             StorageProperty::CCPreferredLanguages => PROPERTY_CC_PREFERRED_LANGUAGES,
+            StorageProperty::HighContrastEnabled => PROPERTY_HIGH_CONTRAST_ENABLED,
+            StorageProperty::MagnificationEnabled => PROPERTY_MAGNIFICATION_ENABLED,
+            StorageProperty::MagnificationScale => PROPERTY_MAGNIFICATION_SCALE,
+            StorageProperty::DoNotDisturbEnabled => PROPERTY_DO_NOT_DISTURB_ENABLED,
+            StorageProperty::DoNotDisturbWindows => PROPERTY_DO_NOT_DISTURB_WINDOWS,
         }
     }
 
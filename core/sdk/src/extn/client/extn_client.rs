@@ -15,6 +15,7 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+#[cfg(feature = "service_client")]
 use chrono::Utc;
 use log::warn;
 #[cfg(not(test))]
@@ -22,10 +23,15 @@ use log::{debug, error, info, trace};
 use std::{
     collections::HashMap,
     ops::ControlFlow,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
 };
+#[cfg(feature = "service_client")]
 use tokio_tungstenite::tungstenite::Message;
 
+#[cfg(feature = "service_client")]
 use futures_util::{SinkExt, StreamExt};
 use tokio::sync::{
     mpsc::{self, Sender as MSender},
@@ -34,24 +40,32 @@ use tokio::sync::{
 #[cfg(test)]
 use {println as info, println as trace, println as debug, println as error};
 
+#[cfg(feature = "service_client")]
+use crate::utils::ws_utils::WebSocketUtils;
 use crate::{
     api::{
-        context::{ActivationStatus, RippleContext, RippleContextUpdateRequest},
+        config::{Config, ConfigResponse, ConfigSectionRequest, ConfigSectionValue},
+        context::{
+            ActivationStatus, ContextApplyResult, ContextSnapshot, ContextSnapshotRequest,
+            RippleContext, RippleContextUpdateRequest,
+        },
         device::device_request::{InternetConnectionStatus, TimeZone},
-        gateway::rpc_gateway_api::ApiMessage,
-        manifest::extn_manifest::ExtnSymbol,
+        gateway::rpc_gateway_api::{ApiMessage, ClientContext},
+        manifest::extn_manifest::{ExtnRoute, ExtnSymbol},
+        observability::log_signal::LogSignal,
     },
     extn::{
         extn_client_message::{ExtnMessage, ExtnPayloadProvider, ExtnResponse},
         extn_id::ExtnId,
     },
     framework::{ripple_contract::RippleContract, RippleResponse},
-    utils::{error::RippleError, extn_utils::ExtnStackSize, ws_utils::WebSocketUtils},
+    utils::{error::RippleError, expiring_map::ExpiringMap, extn_utils::ExtnStackSize},
 };
 
 use super::{
     extn_processor::{ExtnEventProcessor, ExtnRequestProcessor},
     extn_sender::ExtnSender,
+    extn_usage::{ExtnUsage, ExtnUsageTracker},
 };
 
 #[cfg(any(test, feature = "mock"))]
@@ -74,10 +88,13 @@ pub struct ExtnClient {
     sender: ExtnSender,
     extn_sender_map: Arc<RwLock<HashMap<String, MSender<ApiMessage>>>>,
     contract_map: Arc<RwLock<HashMap<String, String>>>,
-    response_processors: Arc<RwLock<HashMap<String, OSender<ExtnMessage>>>>,
+    response_processors: Arc<RwLock<ExpiringMap<String, OSender<ExtnMessage>>>>,
     request_processors: Arc<RwLock<HashMap<String, MSender<ExtnMessage>>>>,
     event_processors: Arc<RwLock<HashMap<String, Vec<MSender<ExtnMessage>>>>>,
     ripple_context: Arc<RwLock<RippleContext>>,
+    extn_routes: Arc<RwLock<Vec<ExtnRoute>>>,
+    extn_routes_enforced: Arc<AtomicBool>,
+    extn_usage: Arc<RwLock<ExtnUsageTracker>>,
 }
 
 fn add_stream_processor<P>(id: String, context: P, map: Arc<RwLock<HashMap<String, P>>>) {
@@ -94,7 +111,11 @@ fn add_vec_stream_processor<P>(id: String, context: P, map: Arc<RwLock<HashMap<S
     }
 }
 
-fn add_single_processor<P>(id: String, processor: Option<P>, map: Arc<RwLock<HashMap<String, P>>>) {
+fn add_single_processor<P>(
+    id: String,
+    processor: Option<P>,
+    map: Arc<RwLock<ExpiringMap<String, P>>>,
+) {
     if let Some(processor) = processor {
         let mut processor_state = map.write().unwrap();
         processor_state.insert(id, processor);
@@ -120,10 +141,13 @@ impl ExtnClient {
             sender: ExtnSender::new_main(),
             extn_sender_map: Arc::new(RwLock::new(HashMap::new())),
             contract_map: Arc::new(RwLock::new(HashMap::new())),
-            response_processors: Arc::new(RwLock::new(HashMap::new())),
+            response_processors: Arc::new(RwLock::new(ExpiringMap::default())),
             request_processors: Arc::new(RwLock::new(HashMap::new())),
             event_processors: Arc::new(RwLock::new(HashMap::new())),
             ripple_context: Arc::new(RwLock::new(RippleContext::default())),
+            extn_routes: Arc::new(RwLock::new(Vec::new())),
+            extn_routes_enforced: Arc::new(AtomicBool::new(false)),
+            extn_usage: Arc::new(RwLock::new(ExtnUsageTracker::default())),
         }
     }
 
@@ -133,10 +157,13 @@ impl ExtnClient {
             sender: ExtnSender::new_extn(tx, symbol),
             extn_sender_map: Arc::new(RwLock::new(HashMap::new())),
             contract_map: Arc::new(RwLock::new(HashMap::new())),
-            response_processors: Arc::new(RwLock::new(HashMap::new())),
+            response_processors: Arc::new(RwLock::new(ExpiringMap::default())),
             request_processors: Arc::new(RwLock::new(HashMap::new())),
             event_processors: Arc::new(RwLock::new(HashMap::new())),
             ripple_context: Arc::new(RwLock::new(RippleContext::default())),
+            extn_routes: Arc::new(RwLock::new(Vec::new())),
+            extn_routes_enforced: Arc::new(AtomicBool::new(false)),
+            extn_usage: Arc::new(RwLock::new(ExtnUsageTracker::default())),
         };
 
         (client, tr)
@@ -238,6 +265,7 @@ impl ExtnClient {
             let mut sender_map = self.extn_sender_map.write().unwrap();
             sender_map.remove(&id);
         }
+        self.extn_usage.write().unwrap().on_extn_removed(&id);
 
         {
             let mut contract_map = self.contract_map.write().unwrap();
@@ -247,6 +275,16 @@ impl ExtnClient {
         }
     }
 
+    /// Used by `Main` to list the extensions which are currently connected
+    pub fn get_connected_extn_ids(&self) -> Vec<String> {
+        self.extn_sender_map
+            .read()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
     pub fn get_other_senders(&self) -> Vec<MSender<ApiMessage>> {
         self.extn_sender_map
             .read()
@@ -259,19 +297,23 @@ impl ExtnClient {
     }
 
     /// Called once per client initialization this is a blocking method. Use a spawned thread to call this method
+    #[cfg(feature = "service_client")]
     pub async fn initialize(&self, mut tr: mpsc::Receiver<ApiMessage>) {
         debug!("Starting initialize");
         let base_path = std::env::var("RIPPLE_SERVICE_HANDSHAKE_PATH")
             .unwrap_or_else(|_| "127.0.0.1:3474".to_string());
         let path = tokio_tungstenite::tungstenite::http::Uri::builder()
-            .scheme("ws")
+            .scheme(WebSocketUtils::get_service_scheme().as_str())
             .authority(base_path.as_str())
             .path_and_query(format!("/?service_handshake={}", self.sender.get_cap()))
             .build()
             .unwrap();
 
         let path = path.to_string();
-        if let Ok((mut ws_tx, mut ws_rx)) = WebSocketUtils::get_ws_stream(&path, None).await {
+        let config = WebSocketUtils::get_service_config();
+        if let Ok((mut ws_tx, mut ws_rx)) =
+            WebSocketUtils::get_secure_ws_stream(&path, config).await
+        {
             tokio::pin! {
                 let read_pin = ws_rx.next();
             }
@@ -313,6 +355,15 @@ impl ExtnClient {
 
     pub fn handle_message(&self, message: ExtnMessage) -> ControlFlow<()> {
         trace!("IEC recv: {:#?}", message);
+        if self.sender.get_cap().is_main() {
+            // responses carry the original requestor so they are correlated by the request id
+            let mut extn_usage = self.extn_usage.write().unwrap();
+            if message.payload.is_response() {
+                extn_usage.on_response(&message.id);
+            } else if !message.requestor.is_main() {
+                extn_usage.on_message_received(&message.requestor.to_string());
+            }
+        }
         if message.payload.is_response() {
             Self::handle_single(message, self.response_processors.clone());
         } else if message.payload.is_event() {
@@ -322,9 +373,11 @@ impl ExtnClient {
                         && !message.target_id.as_ref().unwrap().is_main()
             // But it is not for main. So main has to fwd it.
             {
-                if let Some(sender) = self
-                    .get_extn_sender_with_extn_id(&message.target_id.as_ref().unwrap().to_string())
-                {
+                let target_id = message.target_id.as_ref().unwrap().to_string();
+                if !self.is_route_permitted(&message, &target_id) {
+                    return ControlFlow::Continue(());
+                }
+                if let Some(sender) = self.get_extn_sender_with_extn_id(&target_id) {
                     let send_response: Result<(), RippleError> =
                         self.sender.respond(message, Some(sender));
                     trace!("fwding event result: {:?}", send_response);
@@ -333,6 +386,7 @@ impl ExtnClient {
                     self.handle_no_processor_error(message);
                 }
             } else {
+                let mut message = message;
                 if !is_main {
                     if let Some(context) = RippleContext::is_ripple_context(&message.payload) {
                         trace!(
@@ -340,13 +394,26 @@ impl ExtnClient {
                             self.sender.get_cap(),
                             message
                         );
-                        {
+                        let result = {
                             let mut ripple_context = self.ripple_context.write().unwrap();
-                            ripple_context.deep_copy(context);
+                            ripple_context.apply(context.clone())
+                        };
+                        match result {
+                            ContextApplyResult::Stale => {
+                                debug!("Ignoring stale context version={}", context.version);
+                                return ControlFlow::Continue(());
+                            }
+                            ContextApplyResult::Gap => {
+                                self.refresh_context(context);
+                                return ControlFlow::Continue(());
+                            }
+                            ContextApplyResult::Applied => {}
                         }
                         if !self.has_event_listener(&message.target.as_clear_string()) {
                             return ControlFlow::Continue(());
                         }
+                        // listeners always receive the full context
+                        message.payload = self.get_context_event(&context).get_extn_payload();
                     }
                 }
                 Self::handle_vec_stream(message, self.event_processors.clone());
@@ -359,12 +426,22 @@ impl ExtnClient {
                     RippleContextUpdateRequest::is_ripple_context_update(&message.payload)
                 {
                     self.context_update(request);
+                } else if let Some(request) =
+                    ContextSnapshotRequest::get_from_payload(message.payload.clone())
+                {
+                    self.respond_context_snapshot(message, request);
                 }
                 // if its a request coming as an extn provider the extension is calling on itself.
                 // for eg an extension has a RPC Method provider and also a channel to process the
                 // requests this below impl will take care of sending the data back to the Extension
                 else if let Some(extn_id) = target_contract.is_extn_provider() {
-                    if let Some(s) = self.get_extn_sender_with_extn_id(&extn_id) {
+                    if !self.is_route_permitted(&message, &extn_id) {
+                        self.handle_error_response(message, RippleError::InvalidAccess);
+                    } else if let Some(s) = self.get_extn_sender_with_extn_id(&extn_id) {
+                        self.extn_usage
+                            .write()
+                            .unwrap()
+                            .on_request_sent(&extn_id, &message.id);
                         let new_message = message.clone();
                         tokio::spawn(async move {
                             if let Err(e) = s.send(new_message.into()).await {
@@ -380,6 +457,17 @@ impl ExtnClient {
                 else if let Some(sender) =
                     self.get_extn_sender_with_contract(target_contract.clone())
                 {
+                    let extn_id = self
+                        .get_extn_id_with_contract(&target_contract)
+                        .unwrap_or_default();
+                    if !self.is_route_permitted(&message, &extn_id) {
+                        self.handle_error_response(message, RippleError::InvalidAccess);
+                        return ControlFlow::Continue(());
+                    }
+                    self.extn_usage
+                        .write()
+                        .unwrap()
+                        .on_request_sent(&extn_id, &message.id);
                     let new_message = message.clone();
 
                     tokio::spawn(async move {
@@ -422,7 +510,8 @@ impl ExtnClient {
 
         if propagate {
             trace!("Formed Context update event: {:?}", message);
-            let c_message: ApiMessage = message.clone().into();
+            // other clients only receive the changed member along with the version
+            let c_message: ApiMessage = new_context.get_delta_event_message().into();
             {
                 let senders = self.get_other_senders();
                 for sender in senders {
@@ -445,8 +534,16 @@ impl ExtnClient {
     }
 
     fn handle_no_processor_error(&self, message: ExtnMessage) {
+        self.handle_error_response(message, RippleError::ProcessorError)
+    }
+
+    fn handle_error_response(&self, message: ExtnMessage, error: RippleError) {
+        self.handle_response(message, ExtnResponse::Error(error))
+    }
+
+    fn handle_response(&self, message: ExtnMessage, response: ExtnResponse) {
         let req_sender = self.get_extn_sender_with_extn_id(&message.requestor.to_string());
-        if let Ok(resp) = message.get_response(ExtnResponse::Error(RippleError::ProcessorError)) {
+        if let Ok(resp) = message.get_response(response) {
             if message.requestor.is_main() {
                 self.handle_message(resp);
             } else if self.sender.respond(resp, req_sender).is_err() {
@@ -455,9 +552,65 @@ impl ExtnClient {
         }
     }
 
+    fn respond_context_snapshot(&self, message: ExtnMessage, request: ContextSnapshotRequest) {
+        let context = self.ripple_context.read().unwrap().clone();
+        if context.is_at_least(request.min_version) {
+            self.handle_response(message, ExtnResponse::Context(context));
+        } else {
+            error!(
+                "Context version={} requested by {} is newer than the current version={}",
+                request.min_version, message.requestor, context.version
+            );
+            self.handle_error_response(message, RippleError::NotAvailable);
+        }
+    }
+
+    /// Full context carrying the update type of the received context
+    fn get_context_event(&self, received: &RippleContext) -> RippleContext {
+        let mut context = self.ripple_context.read().unwrap().clone();
+        context.update_type = received.update_type.clone();
+        context.delta = false;
+        context
+    }
+
+    /// Requests a snapshot from main after a missed context update and notifies the
+    /// listeners once the local context has caught up.
+    fn refresh_context(&self, received: RippleContext) {
+        debug!(
+            "Context version={} skips the local version={}, requesting a snapshot",
+            received.version,
+            self.get_context_version()
+        );
+        let mut client = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = client.request_context_snapshot(received.version).await {
+                error!("Unable to refresh the context {:?}", e);
+                return;
+            }
+            let message = client.get_context_event(&received).get_event_message();
+            if client.has_event_listener(&message.target.as_clear_string()) {
+                Self::handle_vec_stream(message, client.event_processors.clone());
+            }
+        });
+    }
+
+    async fn request_context_snapshot(
+        &mut self,
+        min_version: u64,
+    ) -> Result<RippleContext, RippleError> {
+        let response = self.request(ContextSnapshotRequest { min_version }).await?;
+        match response.payload.extract::<ContextSnapshot>() {
+            Some(ContextSnapshot(context)) => {
+                self.ripple_context.write().unwrap().apply(context.clone());
+                Ok(context)
+            }
+            None => Err(RippleError::NotAvailable),
+        }
+    }
+
     fn handle_single(
         msg: ExtnMessage,
-        processor: Arc<RwLock<HashMap<String, OSender<ExtnMessage>>>>,
+        processor: Arc<RwLock<ExpiringMap<String, OSender<ExtnMessage>>>>,
     ) {
         let id_c = msg.id.clone();
         let processor_result = {
@@ -582,21 +735,88 @@ impl ExtnClient {
         &self,
         contract: RippleContract,
     ) -> Option<MSender<ApiMessage>> {
-        let contract_str: String = contract.as_clear_string();
-        let id = {
-            self.contract_map
-                .read()
-                .unwrap()
-                .get(&contract_str)
-                .cloned()
-        };
-        if let Some(extn_id) = id {
+        if let Some(extn_id) = self.get_extn_id_with_contract(&contract) {
             return self.get_extn_sender_with_extn_id(&extn_id);
         }
 
         None
     }
 
+    fn get_extn_id_with_contract(&self, contract: &RippleContract) -> Option<String> {
+        let contract_str: String = contract.as_clear_string();
+        self.contract_map
+            .read()
+            .unwrap()
+            .get(&contract_str)
+            .cloned()
+    }
+
+    /// Used by `Main` to configure the backlog size above which an extension is reported as
+    /// falling behind.
+    pub fn set_backlog_warning_threshold(&self, threshold: usize) {
+        self.extn_usage
+            .write()
+            .unwrap()
+            .set_backlog_warning_threshold(threshold);
+    }
+
+    /// Returns the channel usage of the extensions which `Main` forwarded requests to.
+    pub fn get_extn_usage(&self) -> Vec<ExtnUsage> {
+        self.extn_usage.read().unwrap().get_usage()
+    }
+
+    /// Used by `Main` to enforce the inter extension routing table from the extn manifest.
+    pub fn set_extn_routes(&self, routes: Vec<ExtnRoute>) {
+        *self.extn_routes.write().unwrap() = routes;
+    }
+
+    /// Used by `Main` to enforce the routes once the extn manifest lists any, or when it sets
+    /// `extn_routes_enforced`. Routes are not enforced until then.
+    pub fn set_extn_routes_enforced(&self, enforced: bool) {
+        self.extn_routes_enforced.store(enforced, Ordering::Relaxed);
+    }
+
+    /// Checks whether `Main` may forward a message from the requesting extension to the target
+    /// extension. While routes are enforced messages without a matching route are denied and
+    /// audited.
+    fn is_route_permitted(&self, message: &ExtnMessage, target_id: &str) -> bool {
+        let routes = self.extn_routes.read().unwrap();
+        let from = message.requestor.to_string();
+        if message.requestor.is_main() || from.eq(target_id) {
+            return true;
+        }
+        let contract = message.target.as_clear_string();
+        if !self.extn_routes_enforced.load(Ordering::Relaxed) {
+            debug!(
+                "extn routes are not enforced, forwarding {} from {} to {}",
+                contract, from, target_id
+            );
+            return true;
+        }
+        if routes
+            .iter()
+            .any(|r| r.permits(&from, target_id, &contract))
+        {
+            return true;
+        }
+        LogSignal::new(
+            "extn_route_denied".to_string(),
+            format!(
+                "{} is not permitted to send {} to {}",
+                from, contract, target_id
+            ),
+            ClientContext {
+                session_id: message.id.clone(),
+                app_id: from.clone(),
+                gateway_secure: false,
+            },
+        )
+        .with_diagnostic_context_item("contract", &contract)
+        .with_diagnostic_context_item("target", target_id)
+        .emit_error();
+        false
+    }
+
     pub fn get_extn_sender_with_extn_id(&self, id: &str) -> Option<MSender<ApiMessage>> {
         return self.extn_sender_map.read().unwrap().get(id).cloned();
     }
@@ -673,6 +893,9 @@ impl ExtnClient {
     /// As part of the send process it adds a callback to asynchronously respond back to the caller when the response does get
     /// received.
     ///
+    /// In `Main` the request is routed by [ExtnClient::handle_message], which tracks it as
+    /// pending on the extension it is forwarded to.
+    ///
     /// # Arguments
     /// `payload` - impl [ExtnPayloadProvider]
     pub async fn request(
@@ -764,8 +987,16 @@ impl ExtnClient {
         let (tx, rx) = oneshot::channel();
         add_single_processor(id.clone(), Some(tx), self.response_processors.clone());
 
-        let other_sender = self.get_extn_sender_with_contract(payload.get_contract());
+        let contract = payload.get_contract();
+        let other_sender = self.get_extn_sender_with_contract(contract.clone());
         if other_sender.is_some() {
+            // sent directly, so it is tracked here instead of in handle_message
+            if let Some(extn_id) = self.get_extn_id_with_contract(&contract) {
+                self.extn_usage
+                    .write()
+                    .unwrap()
+                    .on_request_sent(&extn_id, &id);
+            }
             match self.sender.send_request(id, payload, other_sender) {
                 Ok(_) => {
                     trace!("Main internal request sent successfully");
@@ -928,7 +1159,8 @@ impl ExtnClient {
 
     /// Request method which accepts a impl [ExtnPayloadProvider] and uses the capability provided by the trait to send the request.
     /// This method doesnt provide a response it just provides a result after a successful send. Useful for transient requests from
-    /// protocols which do not need a single point of request and response.
+    /// protocols which do not need a single point of request and response. As no response
+    /// is awaited the request is not kept as pending on the extension it is forwarded to.
     ///
     /// # Arguments
     /// `payload` - impl [ExtnPayloadProvider]
@@ -940,6 +1172,7 @@ impl ExtnClient {
         let msg = self.sender.get_message(id.clone(), payload.clone());
         if self.sender.get_cap().is_main() {
             self.handle_message(msg);
+            self.extn_usage.write().unwrap().forget_request(&id);
         } else {
             let other_sender = self.get_extn_sender_with_contract(payload.get_contract());
             self.sender
@@ -978,6 +1211,19 @@ impl ExtnClient {
         None
     }
 
+    /// Requests a typed config section from `Main`. Sections built against a different schema
+    /// are refused by `Main` so the extension never decodes a payload it doesn't understand.
+    pub async fn request_config_section<T: ConfigSectionValue>(
+        &mut self,
+        subscribe: bool,
+    ) -> Result<T, RippleError> {
+        let request = Config::Section(ConfigSectionRequest::new::<T>(subscribe));
+        match self.request_and_flatten(request).await?.payload.extract() {
+            Some(ExtnResponse::Config(ConfigResponse::Section(section))) => section.get_value(),
+            _ => Err(RippleError::InvalidOutput),
+        }
+    }
+
     pub fn get_string_array_config(&self, key: &str) -> Option<Vec<String>> {
         if let Some(s) = self.sender.get_config(key) {
             if let Ok(v) = serde_json::from_str(s.as_str()) {
@@ -1046,6 +1292,34 @@ impl ExtnClient {
         let ripple_context = self.ripple_context.read().unwrap();
         ripple_context.features.clone()
     }
+
+    pub fn get_context_version(&self) -> u64 {
+        self.ripple_context.read().unwrap().version
+    }
+
+    /// Returns the context at or newer than the given version. Extensions which are behind
+    /// request a snapshot from main, so actions tied to an account change do not run on
+    /// stale state.
+    pub async fn get_context_at_least(
+        &mut self,
+        min_version: u64,
+    ) -> Result<RippleContext, RippleError> {
+        {
+            let ripple_context = self.ripple_context.read().unwrap();
+            if ripple_context.is_at_least(min_version) {
+                return Ok(ripple_context.clone());
+            }
+        }
+        if self.sender.get_cap().is_main() {
+            return Err(RippleError::NotAvailable);
+        }
+        let context = self.request_context_snapshot(min_version).await?;
+        if context.is_at_least(min_version) {
+            Ok(context)
+        } else {
+            Err(RippleError::NotAvailable)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1072,7 +1346,10 @@ pub mod tests {
         },
         utils::{
             logger::init_logger,
-            mock_utils::{get_mock_extn_client, queue_mock_response, MockEvent, MockRequest},
+            mock_utils::{
+                get_mock_extn_client, get_mock_message, queue_mock_response, MockEvent,
+                MockRequest, PayloadType,
+            },
         },
     };
     use core::panic;
@@ -1268,6 +1545,7 @@ pub mod tests {
                 uses: Vec::new(),
                 fulfills: Vec::new(),
                 config: None,
+                contract_versions: HashMap::new(),
             },
             s,
         );
@@ -1296,6 +1574,7 @@ pub mod tests {
                 uses: Vec::new(),
                 fulfills: Vec::new(),
                 config: None,
+                contract_versions: HashMap::new(),
             },
             s,
         );
@@ -1306,6 +1585,34 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_extn_route_policy() {
+        let extn_client = ExtnClient::mock();
+        let mut message = get_mock_message(PayloadType::Request);
+        message.requestor = ExtnId::try_from("ripple:extn:jsonrpsee:custom".to_string()).unwrap();
+        let target = "ripple:channel:device:thunder";
+
+        // everything is forwarded until routes are enforced
+        assert!(extn_client.is_route_permitted(&message, target));
+
+        extn_client.set_extn_routes_enforced(true);
+        assert!(!extn_client.is_route_permitted(&message, target));
+
+        extn_client.set_extn_routes(vec![ExtnRoute {
+            from: "ripple:extn:jsonrpsee:custom".to_string(),
+            to: target.to_string(),
+            contracts: vec!["device_info".to_string()],
+        }]);
+        assert!(!extn_client.is_route_permitted(&message, target));
+
+        message.target = RippleContract::DeviceInfo;
+        assert!(extn_client.is_route_permitted(&message, target));
+        assert!(!extn_client.is_route_permitted(&message, "ripple:channel:distributor:general"));
+
+        extn_client.set_extn_routes_enforced(false);
+        assert!(extn_client.is_route_permitted(&message, "ripple:channel:distributor:general"));
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_get_extn_sender_with_extn_contract() {
         let extn_client = ExtnClient::mock();
@@ -1323,6 +1630,7 @@ pub mod tests {
                 uses: Vec::new(),
                 fulfills: vec!["account.session".to_string()],
                 config: None,
+                contract_versions: HashMap::new(),
             },
             s,
         );
@@ -1354,6 +1662,7 @@ pub mod tests {
                 uses: Vec::new(),
                 fulfills: vec![RippleContract::Session(SessionAdjective::Account).as_clear_string()],
                 config: None,
+                contract_versions: HashMap::new(),
             },
             s,
         );
@@ -1517,6 +1826,7 @@ pub mod tests {
                 uses: Vec::new(),
                 fulfills: vec![RippleContract::DeviceInfo.as_clear_string()],
                 config: Some(HashMap::new()),
+                contract_versions: HashMap::new(),
             },
             tx,
         );
@@ -1584,6 +1894,7 @@ pub mod tests {
                 uses: vec![RippleContract::Config.as_clear_string()],
                 fulfills: vec![RippleContract::DeviceInfo.as_clear_string()],
                 config: Some(HashMap::new()),
+                contract_versions: HashMap::new(),
             },
             tx,
         );
@@ -1718,6 +2029,20 @@ pub mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_get_context_at_least() {
+        let mut main_client = ExtnClient::new_main();
+        main_client.context_update(RippleContextUpdateRequest::Activation(true));
+        assert_eq!(main_client.get_context_version(), 1);
+
+        let context = main_client.get_context_at_least(1).await.unwrap();
+        assert_eq!(context.activation_status, Some(ActivationStatus::Activated));
+        assert!(matches!(
+            main_client.get_context_at_least(2).await,
+            Err(RippleError::NotAvailable)
+        ));
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_handle_no_processor_error() {
         let mut extn_client = ExtnClient::new_main();
@@ -1736,6 +2061,7 @@ pub mod tests {
                 uses: vec!["account.session".to_string()],
                 fulfills: vec!["account.session".to_string()],
                 config: None,
+                contract_versions: HashMap::new(),
             },
             tx,
         );
@@ -2025,6 +2351,7 @@ pub mod tests {
                 uses: vec!["config".to_string()],
                 fulfills: vec!["permissions".to_string()],
                 config: None,
+                contract_versions: HashMap::new(),
             },
             tx,
         );
@@ -2068,6 +2395,7 @@ pub mod tests {
             uses: Vec::new(),
             fulfills: Vec::new(),
             config: Some(config),
+            contract_versions: HashMap::new(),
         });
         let result = extn_client.get_stack_size();
 
@@ -2117,6 +2445,7 @@ pub mod tests {
             uses: Vec::new(),
             fulfills: Vec::new(),
             config,
+            contract_versions: HashMap::new(),
         });
         assert_eq!(extn_client.get_bool_config("key"), expected_value);
     }
@@ -2134,6 +2463,7 @@ pub mod tests {
             uses: Vec::new(),
             fulfills: Vec::new(),
             config,
+            contract_versions: HashMap::new(),
         });
         assert_eq!(extn_client.get_uint_config("key"), expected_value);
     }
@@ -2161,6 +2491,7 @@ pub mod tests {
                     uses: permitted,
                     fulfills,
                     config: None,
+                    contract_versions: HashMap::new(),
                 },
                 tx,
             );
@@ -2201,6 +2532,7 @@ pub mod tests {
             uses: permitted,
             fulfills,
             config: None,
+            contract_versions: HashMap::new(),
         });
         let cp = extn_client.check_contract_permitted(RippleContract::DeviceInfo);
         assert_eq!(cp, exp_resp, "{}", error_msg);
@@ -2241,6 +2573,7 @@ pub mod tests {
             uses: Vec::new(),
             fulfills,
             config: None,
+            contract_versions: HashMap::new(),
         });
         let cp = extn_client.check_contract_fulfillment(RippleContract::DeviceInfo);
         assert_eq!(cp, exp_resp, "{}", error_msg);
@@ -0,0 +1,433 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression, Crc};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::error::RippleError;
+
+/// Extn symbol config key through which an extension opts in to compressed frames
+pub const EXTN_FRAME_COMPRESSION_CONFIG: &str = "frame_compression";
+/// Extn symbol config key for the largest frame the extension accepts
+pub const EXTN_MAX_FRAME_SIZE_CONFIG: &str = "max_frame_size";
+/// Largest frame Ripple Main sends or accepts
+pub const MAX_FRAME_SIZE: usize = 64 * 1024;
+/// Frames are never made smaller than this to keep the framing overhead sensible
+pub const MIN_FRAME_SIZE: usize = 1024;
+/// Messages below this size are sent as is even when compression is negotiated
+pub const COMPRESSION_THRESHOLD: usize = 4 * 1024;
+/// Partially received messages are dropped after this duration
+pub const FRAME_REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(30);
+/// Largest message reassembled from frames, after decompression
+pub const MAX_FRAMED_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+/// Most frames a single message is split into, enough for the largest message in the
+/// smallest frames
+pub const MAX_FRAMES_PER_MESSAGE: u32 =
+    (MAX_FRAMED_MESSAGE_SIZE / MIN_FRAME_SIZE * 4 / 3 + 1) as u32;
+/// Most base64 data buffered for a single message
+const MAX_BUFFERED_FRAME_DATA: usize = MAX_FRAMED_MESSAGE_SIZE / 3 * 4 + 4;
+/// Most messages reassembled at the same time, the oldest is dropped for a new one
+pub const MAX_PENDING_MESSAGES: usize = 8;
+/// Most base64 data buffered across the messages being reassembled, the oldest messages are
+/// dropped to stay below it
+const MAX_PENDING_FRAME_DATA: usize = 2 * MAX_BUFFERED_FRAME_DATA;
+
+const FRAME_PREFIX: &str = "{\"extnFrame\"";
+
+/// Framing negotiated with an extension for messages on the extension channel.
+///
+/// Large payloads are optionally deflate compressed, base64 encoded and split into frames
+/// no larger than `max_frame_size`. Every frame carries the crc32 of the original message
+/// so the receiver can verify the reassembled payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtnFraming {
+    pub compression: bool,
+    pub max_frame_size: usize,
+}
+
+impl ExtnFraming {
+    /// Negotiates framing from the extn symbol config. Extensions which do not declare
+    /// either key keep receiving unframed messages.
+    pub fn negotiate(config: Option<&HashMap<String, String>>) -> Option<ExtnFraming> {
+        let config = config?;
+        let compression = config
+            .get(EXTN_FRAME_COMPRESSION_CONFIG)
+            .map(|c| c.eq("true"));
+        let max_frame_size = config
+            .get(EXTN_MAX_FRAME_SIZE_CONFIG)
+            .and_then(|s| s.parse::<usize>().ok());
+        if compression.is_none() && max_frame_size.is_none() {
+            return None;
+        }
+        Some(ExtnFraming {
+            compression: compression.unwrap_or(false),
+            max_frame_size: max_frame_size
+                .unwrap_or(MAX_FRAME_SIZE)
+                .clamp(MIN_FRAME_SIZE, MAX_FRAME_SIZE),
+        })
+    }
+
+    /// Encodes a message into one or more text frames. Messages which fit in a single frame
+    /// and are below the compression threshold are returned unchanged.
+    pub fn encode(&self, message: String) -> Vec<String> {
+        let compress = self.compression && message.len() > COMPRESSION_THRESHOLD;
+        if !compress && message.len() <= self.max_frame_size {
+            return vec![message];
+        }
+        let mut crc = Crc::new();
+        crc.update(message.as_bytes());
+        let crc = crc.sum();
+
+        let bytes = if compress {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            match encoder
+                .write_all(message.as_bytes())
+                .and_then(|_| encoder.finish())
+            {
+                Ok(bytes) => bytes,
+                Err(_) => return vec![message],
+            }
+        } else {
+            message.into_bytes()
+        };
+        let data = STANDARD.encode(bytes);
+
+        let frame_id = uuid::Uuid::new_v4().to_string();
+        // base64 output is ascii so the chunks are always valid strings
+        let chunks: Vec<&[u8]> = data.as_bytes().chunks(self.max_frame_size).collect();
+        let total = chunks.len() as u32;
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(seq, chunk)| {
+                ExtnFrameMessage {
+                    extn_frame: ExtnFrame {
+                        frame_id: frame_id.clone(),
+                        seq: seq as u32,
+                        total,
+                        compressed: compress,
+                        crc,
+                        data: String::from_utf8_lossy(chunk).into_owned(),
+                    },
+                }
+                .into()
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnFrame {
+    pub frame_id: String,
+    pub seq: u32,
+    pub total: u32,
+    pub compressed: bool,
+    pub crc: u32,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnFrameMessage {
+    pub extn_frame: ExtnFrame,
+}
+
+impl From<ExtnFrameMessage> for String {
+    fn from(value: ExtnFrameMessage) -> Self {
+        serde_json::to_string(&value).unwrap()
+    }
+}
+
+#[derive(Debug)]
+struct PendingMessage {
+    frames: Vec<Option<String>>,
+    received: u32,
+    buffered: usize,
+    started: Instant,
+}
+
+/// Reassembles framed messages received on the extension channel. Partial messages are
+/// bounded in number, in total size and in age.
+#[derive(Debug, Default)]
+pub struct ExtnFrameAssembler {
+    pending: HashMap<String, PendingMessage>,
+}
+
+impl ExtnFrameAssembler {
+    pub fn is_frame(message: &str) -> bool {
+        message.starts_with(FRAME_PREFIX)
+    }
+
+    /// Accepts a received text message. Unframed messages are returned as is, frames are held
+    /// until the message is complete and then returned after the integrity check.
+    pub fn accept(&mut self, message: String) -> Result<Option<String>, RippleError> {
+        if !Self::is_frame(&message) {
+            return Ok(Some(message));
+        }
+        let frame = serde_json::from_str::<ExtnFrameMessage>(&message)
+            .map_err(|_| RippleError::ParseError)?
+            .extn_frame;
+        if frame.total == 0
+            || frame.total > MAX_FRAMES_PER_MESSAGE
+            || frame.seq >= frame.total
+            || frame.data.len() > MAX_FRAME_SIZE
+        {
+            return Err(RippleError::InvalidInput);
+        }
+
+        self.pending
+            .retain(|_, p| p.started.elapsed() < FRAME_REASSEMBLY_TIMEOUT);
+        if !self.pending.contains_key(&frame.frame_id) && self.pending.len() >= MAX_PENDING_MESSAGES
+        {
+            self.evict_oldest(&frame.frame_id);
+        }
+        let pending = self
+            .pending
+            .entry(frame.frame_id.clone())
+            .or_insert_with(|| PendingMessage {
+                frames: vec![None; frame.total as usize],
+                received: 0,
+                buffered: 0,
+                started: Instant::now(),
+            });
+        if pending.frames.len() != frame.total as usize {
+            self.pending.remove(&frame.frame_id);
+            return Err(RippleError::InvalidInput);
+        }
+        let slot = &mut pending.frames[frame.seq as usize];
+        if slot.is_none() {
+            pending.buffered += frame.data.len();
+            *slot = Some(frame.data);
+            pending.received += 1;
+        }
+        if pending.buffered > MAX_BUFFERED_FRAME_DATA {
+            self.pending.remove(&frame.frame_id);
+            return Err(RippleError::InvalidInput);
+        }
+        if pending.received < frame.total {
+            while self.pending.values().map(|p| p.buffered).sum::<usize>() > MAX_PENDING_FRAME_DATA
+                && self.evict_oldest(&frame.frame_id)
+            {}
+            return Ok(None);
+        }
+
+        let pending = self.pending.remove(&frame.frame_id).unwrap();
+        let data: String = pending.frames.into_iter().flatten().collect();
+        let bytes = STANDARD.decode(data).map_err(|_| RippleError::ParseError)?;
+        let bytes = if frame.compressed {
+            // one byte over the limit is enough to tell an oversized message apart
+            let mut decoded = Vec::new();
+            DeflateDecoder::new(bytes.as_slice())
+                .take(MAX_FRAMED_MESSAGE_SIZE as u64 + 1)
+                .read_to_end(&mut decoded)
+                .map_err(|_| RippleError::ParseError)?;
+            if decoded.len() > MAX_FRAMED_MESSAGE_SIZE {
+                return Err(RippleError::InvalidInput);
+            }
+            decoded
+        } else {
+            bytes
+        };
+
+        let mut crc = Crc::new();
+        crc.update(&bytes);
+        if crc.sum() != frame.crc {
+            return Err(RippleError::InvalidInput);
+        }
+        String::from_utf8(bytes)
+            .map(Some)
+            .map_err(|_| RippleError::ParseError)
+    }
+
+    /// Drops the oldest partial message other than `keep`, returns false when there is none
+    fn evict_oldest(&mut self, keep: &str) -> bool {
+        let oldest = self
+            .pending
+            .iter()
+            .filter(|(frame_id, _)| frame_id.as_str().ne(keep))
+            .min_by_key(|(_, p)| p.started)
+            .map(|(frame_id, _)| frame_id.clone());
+        match oldest {
+            Some(frame_id) => {
+                self.pending.remove(&frame_id);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_framing() -> ExtnFraming {
+        ExtnFraming::negotiate(Some(&HashMap::from([
+            (
+                EXTN_FRAME_COMPRESSION_CONFIG.to_string(),
+                "true".to_string(),
+            ),
+            (EXTN_MAX_FRAME_SIZE_CONFIG.to_string(), "10".to_string()),
+        ])))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert!(ExtnFraming::negotiate(None).is_none());
+        assert!(ExtnFraming::negotiate(Some(&HashMap::new())).is_none());
+        let framing = get_framing();
+        assert!(framing.compression);
+        assert_eq!(framing.max_frame_size, MIN_FRAME_SIZE);
+    }
+
+    #[test]
+    fn test_encode_and_reassemble() {
+        let framing = get_framing();
+        let small = "{\"jsonrpc\":\"2.0\"}".to_string();
+        assert_eq!(framing.encode(small.clone()), vec![small]);
+
+        let catalog: Vec<String> = (0..4096_u64)
+            .map(|i| format!("{:x}", i * 2654435761 % 1000003))
+            .collect();
+        let large = serde_json::json!({ "catalog": catalog }).to_string();
+        let mut frames = framing.encode(large.clone());
+        assert!(frames.len() > 1);
+        assert!(frames.iter().all(|f| ExtnFrameAssembler::is_frame(f)));
+
+        // frames can arrive out of order
+        frames.reverse();
+        let mut assembler = ExtnFrameAssembler::default();
+        let last = frames.pop().unwrap();
+        for frame in frames {
+            assert_eq!(assembler.accept(frame).unwrap(), None);
+        }
+        assert_eq!(assembler.accept(last).unwrap(), Some(large));
+    }
+
+    #[test]
+    fn test_integrity_check() {
+        let framing = ExtnFraming {
+            compression: false,
+            max_frame_size: MIN_FRAME_SIZE,
+        };
+        let frames = framing.encode("x".repeat(2 * MIN_FRAME_SIZE));
+        let last = frames.len() - 1;
+        let mut assembler = ExtnFrameAssembler::default();
+        for (i, frame) in frames.into_iter().enumerate() {
+            let mut message: ExtnFrameMessage = serde_json::from_str(&frame).unwrap();
+            if i == 0 {
+                // corrupt the payload while keeping it valid base64
+                message.extn_frame.data.replace_range(0..1, "f");
+            }
+            let result = assembler.accept(message.into());
+            if i < last {
+                assert_eq!(result.unwrap(), None);
+            } else {
+                assert_eq!(result.unwrap_err(), RippleError::InvalidInput);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pending_limits() {
+        let frame = |frame_id: String, data: String| -> String {
+            ExtnFrameMessage {
+                extn_frame: ExtnFrame {
+                    frame_id,
+                    seq: 0,
+                    total: 2,
+                    compressed: false,
+                    crc: 0,
+                    data,
+                },
+            }
+            .into()
+        };
+        let mut assembler = ExtnFrameAssembler::default();
+        for i in 0..=MAX_PENDING_MESSAGES {
+            let result = assembler.accept(frame(format!("frame{}", i), "eA==".into()));
+            assert_eq!(result.unwrap(), None);
+        }
+        // the oldest partial message made room for the newest
+        assert_eq!(assembler.pending.len(), MAX_PENDING_MESSAGES);
+        assert!(!assembler.pending.contains_key("frame0"));
+
+        for p in assembler.pending.values_mut() {
+            p.buffered = MAX_BUFFERED_FRAME_DATA / 2;
+        }
+        assert_eq!(
+            assembler
+                .accept(frame("next".into(), "eA==".into()))
+                .unwrap(),
+            None
+        );
+        let buffered: usize = assembler.pending.values().map(|p| p.buffered).sum();
+        assert!(buffered <= MAX_PENDING_FRAME_DATA);
+        assert!(assembler.pending.contains_key("next"));
+    }
+
+    #[test]
+    fn test_reassembly_limits() {
+        let frame = |total: u32, seq: u32, compressed: bool, data: String| -> String {
+            ExtnFrameMessage {
+                extn_frame: ExtnFrame {
+                    frame_id: "frame1".into(),
+                    seq,
+                    total,
+                    compressed,
+                    crc: 0,
+                    data,
+                },
+            }
+            .into()
+        };
+        let mut assembler = ExtnFrameAssembler::default();
+        assert_eq!(
+            assembler
+                .accept(frame(u32::MAX, 0, false, "eA==".into()))
+                .unwrap_err(),
+            RippleError::InvalidInput
+        );
+        assert_eq!(
+            assembler
+                .accept(frame(2, 0, false, "e".repeat(MAX_FRAME_SIZE + 1)))
+                .unwrap_err(),
+            RippleError::InvalidInput
+        );
+
+        // a small compressed payload which inflates past the message limit
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        encoder
+            .write_all(&vec![b'x'; MAX_FRAMED_MESSAGE_SIZE + 1])
+            .unwrap();
+        let bomb = STANDARD.encode(encoder.finish().unwrap());
+        assert!(bomb.len() <= MAX_FRAME_SIZE);
+        assert_eq!(
+            assembler.accept(frame(1, 0, true, bomb)).unwrap_err(),
+            RippleError::InvalidInput
+        );
+    }
+}
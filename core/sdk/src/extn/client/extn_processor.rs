@@ -337,6 +337,7 @@ pub mod tests {
     use chrono::Utc;
     use log::info;
     use rstest::rstest;
+    use std::collections::HashMap;
     use uuid::Uuid;
 
     #[derive(Debug, Clone)]
@@ -720,6 +721,7 @@ pub mod tests {
                 uses: vec!["uses".to_string()],
                 fulfills: Vec::new(),
                 config: None,
+                contract_versions: HashMap::new(),
             },
             mock_sender.tx.unwrap(),
         );
@@ -0,0 +1,203 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Backlog size above which an extension is reported as falling behind
+pub const DEFAULT_BACKLOG_WARNING_THRESHOLD: usize = 64;
+/// Requests without a response are dropped from the backlog after this duration
+pub const PENDING_REQUEST_EXPIRY: Duration = Duration::from_secs(60);
+
+/// Channel usage of a single extension as seen by Ripple Main
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnUsage {
+    pub extn_id: String,
+    pub requests_sent: u64,
+    pub messages_received: u64,
+    pub responses: u64,
+    pub expired_requests: u64,
+    pub pending_requests: usize,
+    pub avg_latency_ms: u64,
+    pub max_latency_ms: u64,
+    #[serde(skip)]
+    total_latency_ms: u64,
+    #[serde(skip)]
+    backlog_warned: bool,
+}
+
+#[derive(Debug)]
+struct PendingRequest {
+    extn_id: String,
+    sent: Instant,
+}
+
+/// Tracks throughput, pending requests and round trip latencies for the requests Ripple Main
+/// forwards to extensions.
+#[derive(Debug)]
+pub struct ExtnUsageTracker {
+    usage: HashMap<String, ExtnUsage>,
+    pending: HashMap<String, PendingRequest>,
+    backlog_warning_threshold: usize,
+}
+
+impl Default for ExtnUsageTracker {
+    fn default() -> Self {
+        Self {
+            usage: HashMap::new(),
+            pending: HashMap::new(),
+            backlog_warning_threshold: DEFAULT_BACKLOG_WARNING_THRESHOLD,
+        }
+    }
+}
+
+impl ExtnUsageTracker {
+    pub fn set_backlog_warning_threshold(&mut self, threshold: usize) {
+        self.backlog_warning_threshold = threshold;
+    }
+
+    fn get_usage_mut(&mut self, extn_id: &str) -> &mut ExtnUsage {
+        self.usage
+            .entry(extn_id.to_owned())
+            .or_insert_with(|| ExtnUsage {
+                extn_id: extn_id.to_owned(),
+                ..Default::default()
+            })
+    }
+
+    pub fn on_request_sent(&mut self, extn_id: &str, request_id: &str) {
+        self.expire_pending(PENDING_REQUEST_EXPIRY);
+        self.pending.insert(
+            request_id.to_owned(),
+            PendingRequest {
+                extn_id: extn_id.to_owned(),
+                sent: Instant::now(),
+            },
+        );
+        let threshold = self.backlog_warning_threshold;
+        let usage = self.get_usage_mut(extn_id);
+        usage.requests_sent += 1;
+        usage.pending_requests += 1;
+        if usage.pending_requests > threshold && !usage.backlog_warned {
+            usage.backlog_warned = true;
+            warn!(
+                "Extension {} is falling behind pending_requests={} threshold={}",
+                extn_id, usage.pending_requests, threshold
+            );
+        }
+    }
+
+    pub fn on_message_received(&mut self, extn_id: &str) {
+        self.get_usage_mut(extn_id).messages_received += 1;
+    }
+
+    pub fn on_response(&mut self, request_id: &str) {
+        if let Some(pending) = self.pending.remove(request_id) {
+            let latency_ms = pending.sent.elapsed().as_millis() as u64;
+            let threshold = self.backlog_warning_threshold;
+            let usage = self.get_usage_mut(&pending.extn_id);
+            usage.responses += 1;
+            usage.pending_requests = usage.pending_requests.saturating_sub(1);
+            usage.total_latency_ms += latency_ms;
+            usage.avg_latency_ms = usage.total_latency_ms / usage.responses;
+            usage.max_latency_ms = usage.max_latency_ms.max(latency_ms);
+            if usage.pending_requests <= threshold / 2 {
+                usage.backlog_warned = false;
+            }
+        }
+    }
+
+    /// Stops waiting for the response of a request which does not get one, it still counts as
+    /// sent but not as pending nor expired
+    pub fn forget_request(&mut self, request_id: &str) {
+        if let Some(pending) = self.pending.remove(request_id) {
+            let usage = self.get_usage_mut(&pending.extn_id);
+            usage.pending_requests = usage.pending_requests.saturating_sub(1);
+        }
+    }
+
+    /// Drops the pending requests of an extension, used when the extension disconnects
+    pub fn on_extn_removed(&mut self, extn_id: &str) {
+        self.pending.retain(|_, p| p.extn_id.ne(extn_id));
+        if let Some(usage) = self.usage.get_mut(extn_id) {
+            usage.pending_requests = 0;
+            usage.backlog_warned = false;
+        }
+    }
+
+    fn expire_pending(&mut self, expiry: Duration) {
+        let expired: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| p.sent.elapsed() >= expiry)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in expired {
+            if let Some(pending) = self.pending.remove(&id) {
+                let usage = self.get_usage_mut(&pending.extn_id);
+                usage.expired_requests += 1;
+                usage.pending_requests = usage.pending_requests.saturating_sub(1);
+            }
+        }
+    }
+
+    pub fn get_usage(&self) -> Vec<ExtnUsage> {
+        let mut usage: Vec<ExtnUsage> = self.usage.values().cloned().collect();
+        usage.sort_by(|a, b| a.extn_id.cmp(&b.extn_id));
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extn_usage() {
+        let mut tracker = ExtnUsageTracker::default();
+        tracker.set_backlog_warning_threshold(1);
+        tracker.on_request_sent("ripple:channel:device:thunder", "1");
+        tracker.on_request_sent("ripple:channel:device:thunder", "2");
+        tracker.on_message_received("ripple:channel:device:thunder");
+        tracker.on_response("1");
+        tracker.on_response("unknown");
+
+        let usage = tracker.get_usage();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].requests_sent, 2);
+        assert_eq!(usage[0].responses, 1);
+        assert_eq!(usage[0].messages_received, 1);
+        assert_eq!(usage[0].pending_requests, 1);
+
+        tracker.on_request_sent("ripple:channel:device:thunder", "3");
+        tracker.forget_request("3");
+        let usage = tracker.get_usage();
+        assert_eq!(usage[0].requests_sent, 3);
+        assert_eq!(usage[0].pending_requests, 1);
+
+        tracker.expire_pending(Duration::ZERO);
+        let usage = tracker.get_usage();
+        assert_eq!(usage[0].pending_requests, 0);
+        assert_eq!(usage[0].expired_requests, 1);
+    }
+}
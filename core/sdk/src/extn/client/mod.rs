@@ -16,6 +16,8 @@
 //
 
 pub mod extn_client;
+pub mod extn_framing;
 pub mod extn_processor;
 pub mod extn_sender;
+pub mod extn_usage;
 pub mod wait_for_service_processor;
@@ -32,26 +32,35 @@ use crate::{
         apps::AppEventRequest,
         caps::CapsRequest,
         config::{Config, ConfigResponse},
-        context::{RippleContext, RippleContextUpdateRequest},
+        context::{ContextSnapshotRequest, RippleContext, RippleContextUpdateRequest},
         device::{
-            device_events::DeviceEventRequest,
+            device_events::{DeviceEventRequest, UserActivity},
+            device_input::RawKeyEvent,
             device_peristence::StorageData,
+            device_provisioning::{ProvisioningRequest, ProvisioningResponse},
             device_request::{DeviceRequest, NetworkResponse, TimeZone},
+            device_update::{SystemUpdateRequest, UpdateStatus},
         },
         distributor::{
+            distributor_activation::ActivationRequest,
+            distributor_entitlements::{EntitlementsRequest, EntitlementsResponse},
             distributor_permissions::{PermissionRequest, PermissionResponse},
             distributor_privacy::{PrivacyCloudRequest, PrivacySettingsStoreRequest},
             distributor_usergrants::UserGrantsCloudStoreRequest,
         },
+        extn_method::{ExtnMethodRegistrationResult, ExtnMethodRequest},
         firebolt::{
+            fb_advertising::{AdConfigRequestParams, AdConfigResponse},
+            fb_discovery::DiscoveryRequest,
             fb_keyboard::{KeyboardSessionRequest, KeyboardSessionResponse},
             fb_lifecycle_management::LifecycleManagementRequest,
             fb_pin::{PinChallengeRequestWithContext, PinChallengeResponse},
             fb_telemetry::{OperationalMetricRequest, TelemetryPayload},
         },
+        gatekeeper_policy::{GatekeeperPolicyDecision, GatekeeperPolicyRequest},
         gateway::rpc_gateway_api::{ApiMessage, ApiProtocol, JsonRpcApiResponse, RpcRequest},
         manifest::device_manifest::AppLibraryEntry,
-        session::{AccountSessionRequest, AccountSessionResponse},
+        session::{AccountSessionRequest, AccountSessionResponse, ProvisionRequest},
         settings::{SettingValue, SettingsRequest},
         status_update::ExtnStatus,
         storage_property::StorageManagerRequest,
@@ -453,6 +462,12 @@ pub enum ExtnRequest {
     PinChallenge(PinChallengeRequestWithContext),
     Keyboard(KeyboardSessionRequest),
     Permission(PermissionRequest),
+    Entitlements(EntitlementsRequest),
+    AdConfig(AdConfigRequestParams),
+    Activation(ActivationRequest),
+    Provisioning(ProvisioningRequest),
+    SystemUpdate(SystemUpdateRequest),
+    Discovery(DiscoveryRequest),
     AccountSession(AccountSessionRequest),
     PrivacySettings(PrivacyCloudRequest),
     StorageManager(StorageManagerRequest),
@@ -463,6 +478,9 @@ pub enum ExtnRequest {
     AuthorizedInfo(CapsRequest),
     OperationalMetricsRequest(OperationalMetricRequest),
     Context(RippleContextUpdateRequest),
+    ContextSnapshot(ContextSnapshotRequest),
+    GatekeeperPolicy(GatekeeperPolicyRequest),
+    ExtnMethod(ExtnMethodRequest),
 }
 
 impl ExtnPayloadProvider for ExtnRequest {
@@ -499,12 +517,20 @@ pub enum ExtnResponse {
     Keyboard(KeyboardSessionResponse),
     AccountSession(AccountSessionResponse),
     Permission(PermissionResponse),
+    Entitlements(EntitlementsResponse),
+    AdConfig(AdConfigResponse),
+    Provision(ProvisionRequest),
+    Provisioning(ProvisioningResponse),
+    UpdateStatus(UpdateStatus),
     StorageData(StorageData),
     NetworkResponse(NetworkResponse),
     TimezoneWithOffset(String, i64),
     DefaultApp(AppLibraryEntry),
     Settings(HashMap<String, SettingValue>),
     BoolMap(HashMap<String, bool>),
+    Context(RippleContext),
+    GatekeeperPolicy(GatekeeperPolicyDecision),
+    ExtnMethod(ExtnMethodRegistrationResult),
 }
 
 impl ExtnPayloadProvider for ExtnResponse {
@@ -535,6 +561,9 @@ pub enum ExtnEvent {
     OperationalMetrics(TelemetryPayload),
     Context(RippleContext),
     TimeZone(TimeZone),
+    UpdateStatus(UpdateStatus),
+    UserActivity(UserActivity),
+    RawKey(RawKeyEvent),
 }
 
 impl ExtnPayloadProvider for ExtnEvent {
@@ -21,14 +21,75 @@ use std::{
     fs::{self, OpenOptions},
     io::Write,
     path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
 
 use crate::utils::error::RippleError;
 
+/// Serialized value of a [FileStore] waiting to be written to disk. Snapshots written out of
+/// order never overwrite a newer one.
+#[derive(Debug, Clone)]
+pub struct FileStoreSnapshot {
+    path: String,
+    value: String,
+    generation: u64,
+    written: Arc<Mutex<u64>>,
+}
+
+impl FileStoreSnapshot {
+    pub fn write(self) {
+        let mut written = self.written.lock().unwrap();
+        if *written < self.generation {
+            write_file(&self.path, self.value);
+            *written = self.generation;
+        }
+    }
+
+    /// Writes the snapshot on a blocking thread of the runtime, or right away outside of one
+    pub fn persist(self) {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || self.write());
+            }
+            Err(_) => self.write(),
+        }
+    }
+}
+
+fn write_file(path: &str, value: String) {
+    // Create the folder if it doesnt exist
+    let p = Path::new(path);
+    if let Some(parent) = p.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .open(path)
+    {
+        Ok(mut file) => {
+            if let Err(e) = file.write_all(value.as_bytes()) {
+                warn!("Failed to write file store for {:?} {}", e, path);
+            }
+        }
+        Err(e) => {
+            warn!("Failed to open file store for {} {:?}", path, e);
+        }
+    }
+}
+
+/// Clones of a store share the generations of its snapshots, so whichever clone took the
+/// last snapshot has its value on disk.
 #[derive(Debug, Clone)]
 pub struct FileStore<S> {
     pub value: S,
     path: String,
+    generation: Arc<AtomicU64>,
+    written: Arc<Mutex<u64>>,
 }
 
 impl<S> FileStore<S>
@@ -39,30 +100,13 @@ where
         FileStore {
             value,
             path: Path::new(&path).to_str().unwrap().into(),
+            generation: Arc::new(AtomicU64::new(0)),
+            written: Arc::new(Mutex::new(0)),
         }
     }
 
     fn write_to_disk(&self, value: String) {
-        // Create the folder if it doesnt exist
-        let p = Path::new(&self.path);
-        if let Some(parent) = p.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
-        match OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(self.path.clone())
-        {
-            Ok(mut file) => {
-                if let Err(e) = file.write_all(value.as_bytes()) {
-                    warn!("Failed to write file store for {:?} {}", e, self.path);
-                }
-            }
-            Err(e) => {
-                warn!("Failed to open file store for {} {:?}", self.path, e);
-            }
-        }
+        write_file(&self.path, value);
     }
 
     pub fn sync(&mut self) {
@@ -70,6 +114,17 @@ where
         self.write_to_disk(new_value_string);
     }
 
+    /// Serializes the value, the snapshot is written to disk with [FileStoreSnapshot::persist]
+    /// once the lock held on the store is released
+    pub fn snapshot(&mut self) -> FileStoreSnapshot {
+        FileStoreSnapshot {
+            path: self.path.clone(),
+            value: serde_json::to_string(&self.value).unwrap(),
+            generation: self.generation.fetch_add(1, Ordering::Relaxed) + 1,
+            written: self.written.clone(),
+        }
+    }
+
     fn load_from_content(contents: String) -> Result<S, RippleError> {
         match serde_json::from_str::<S>(&contents) {
             Ok(s) => Ok(s),
@@ -84,7 +139,7 @@ where
         if let Ok(contents) = fs::read_to_string(&path) {
             if let Ok(s) = Self::load_from_content(contents.clone()) {
                 debug!("valid filestore content {} from {}", contents, path);
-                Ok(FileStore { value: s, path })
+                Ok(FileStore::new(path, s))
             } else {
                 Err(RippleError::InvalidAccess)
             }
@@ -94,3 +149,34 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persist_from_clones() {
+        let dir = std::env::temp_dir().join("ripple_file_store_clones_test");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("store.json").to_str().unwrap().to_owned();
+        let mut store = FileStore::new(path.clone(), 1);
+        let mut clone = store.clone();
+
+        let first = store.snapshot();
+        clone.value = 2;
+        let second = clone.snapshot();
+        first.write();
+        second.write();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2");
+
+        // an older snapshot of the other clone does not overwrite the newer one
+        store.value = 3;
+        let third = store.snapshot();
+        clone.value = 4;
+        let fourth = clone.snapshot();
+        fourth.write();
+        third.write();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "4");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -73,6 +73,32 @@ pub enum RippleContract {
     Browser,
     /// Provides list of permitted capabilities for a given application.
     Permissions,
+    /// Provided by an operator extension taking part in the gatekeeper decision for the
+    /// methods configured in the device manifest.
+    /// Used by [crate::api::gatekeeper_policy::GatekeeperPolicyRequest]
+    GatekeeperPolicy,
+    /// Provided by Ripple Main for extensions which serve Firebolt methods registered at runtime.
+    /// Used by [crate::api::extn_method::ExtnMethodRequest]
+    ExtnMethodRegistration,
+    /// Provided by the distributor to synchronize the entitlements of the account.
+    /// Used by [crate::api::distributor::distributor_entitlements::EntitlementsRequest]
+    Entitlements,
+    /// Provided by the distributor, either an extension or a service, for the account
+    /// scoped ad framework configuration.
+    /// Used by [crate::api::firebolt::fb_advertising::AdConfigRequestParams]
+    Advertising,
+    /// Provided by the distributor to provision the device during activation.
+    /// Used by [crate::api::distributor::distributor_activation::ActivationRequest]
+    Activation,
+    /// Provided by the device to install certificates and rotate the identity keys held in
+    /// the secure element. Used by [crate::api::device::device_provisioning::ProvisioningRequest]
+    SecureElement,
+    /// Provided by the device for the status of the platform updater. Status changes are
+    /// sent to Main as [crate::api::device::device_update::UpdateStatus] events.
+    SystemUpdate,
+    /// Provided by the distributor to store the content access info reported by apps.
+    /// Used by [crate::api::firebolt::fb_discovery::DiscoveryRequest]
+    Discovery,
     /// Alternate protocol mechanism to connect to Ripple.
     RemoteAccessory,
     /// Provides options for triggering the Keyboard provider UI.
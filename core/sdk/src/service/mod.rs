@@ -14,6 +14,10 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 //
+#[cfg(feature = "service_client")]
 pub mod service_client;
+#[cfg(feature = "service_client")]
+pub mod service_http_client;
 pub mod service_message;
+#[cfg(feature = "service_client")]
 pub mod service_rpc_router;
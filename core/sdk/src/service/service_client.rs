@@ -15,23 +15,33 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use crate::api::gateway::rpc_gateway_api::CallContext;
 use crate::api::{
+    firebolt::fb_capabilities::DenyReason,
     gateway::rpc_gateway_api::{ApiMessage, ApiProtocol},
-    manifest::extn_manifest::ExtnSymbol,
+    manifest::extn_manifest::{ExtnSymbol, CONTRACT_VERSIONS_QUERY},
 };
+use crate::extn::client::extn_framing::{ExtnFrameAssembler, ExtnFraming};
 use crate::extn::extn_id::ExtnId;
 use crate::extn::{client::extn_client::ExtnClient, extn_client_message::ExtnMessage};
 use crate::processor::rpc_router::RouterState;
-use crate::service::service_message::{Id, JsonRpcMessage};
-use crate::service::service_rpc_router::route_service_message;
+use crate::service::service_http_client::HttpServiceTransport;
+use crate::service::service_message::{
+    Id, JsonRpcMessage, ServiceEvent, ServiceRegistrationParams, ServiceRegistrationResult,
+    ServiceRegistrationUpdate, ServiceResyncParams, ServiceResyncResult, SERVICE_REGISTER_METHOD,
+    SERVICE_RESYNC_METHOD, SERVICE_UPDATE_REGISTRATION_METHOD,
+};
+use crate::service::service_rpc_router::{route_service_message, ServiceInFlightRequests};
 use crate::utils::extn_utils::ExtnStackSize;
 #[cfg(any(test, feature = "mock"))]
 use crate::utils::mock_utils::get_next_mock_service_response;
-use crate::utils::{error::RippleError, ws_utils::WebSocketUtils};
+use crate::utils::{error::RippleError, expiring_map::ExpiringMap, ws_utils::WebSocketUtils};
+use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use jsonrpsee::core::server::rpc_module::Methods;
 use log::{debug, error, info, trace, warn};
@@ -42,18 +52,63 @@ use tokio_tungstenite::tungstenite::Message;
 use uuid::Uuid;
 
 use super::service_message::ServiceMessage;
+
+const RECONNECT_BACKOFF_MIN_MS: u64 = 500;
+const RECONNECT_BACKOFF_MAX_MS: u64 = 30_000;
+
+/// Timeout of Firebolt calls made by a service when the caller does not set one
+pub const DEFAULT_FIREBOLT_CALL_TIMEOUT_MS: u64 = 5000;
+
+/// Hooks of a service into the lifecycle of its connection with Ripple Main. A client built
+/// with a connection handler reconnects whenever the websocket drops.
+#[async_trait]
+pub trait ServiceConnectionHandler: std::fmt::Debug + Send + Sync {
+    /// Whether requests left unanswered by the previous connection are replayed on the new
+    /// connection, otherwise Ripple Main fails them as unavailable.
+    fn replay_unanswered_calls(&self) -> bool {
+        true
+    }
+
+    /// Called once the client reconnected and resynced with Ripple Main, services refresh
+    /// their own state here, like events which the listeners may have missed.
+    async fn on_reconnected(&self, client: ServiceClient, result: ServiceResyncResult);
+
+    /// Called when Ripple Main refused the credentials of the service with a 401. The client
+    /// stops connecting, the same credentials would be refused again.
+    async fn on_unauthorized(&self) {}
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct ServiceClient {
     pub service_sender: Option<MSender<ServiceMessage>>,
     pub service_router: Arc<RwLock<RouterState>>,
-    response_processors: Arc<RwLock<HashMap<String, OSender<ServiceMessage>>>>,
+    response_processors: Arc<RwLock<ExpiringMap<String, OSender<ServiceMessage>>>>,
     pub extn_client: Option<ExtnClient>,
     // TBD: Remove this field after implementing service.register API call.
     pub service_id: Option<ExtnId>,
+    framing: Option<ExtnFraming>,
+    frame_assembler: Arc<RwLock<ExtnFrameAssembler>>,
+    in_flight: ServiceInFlightRequests,
+    connection_handler: Option<Arc<dyn ServiceConnectionHandler>>,
+    /// Contract versions declared to Ripple Main in the service handshake
+    contract_versions: Option<String>,
+}
+
+/// Channel pair used by a service hosted inside the Ripple Main process. Messages
+/// travel over these channels in the same wire format as the service websocket,
+/// so handler code stays the same whether it is deployed in or out of process.
+#[derive(Debug)]
+pub struct InProcessServiceChannel {
+    /// Messages sent by Ripple Main to the service
+    pub inbound: mpsc::Receiver<Message>,
+    /// Messages sent by the service to Ripple Main
+    pub outbound: MSender<Message>,
 }
 
 pub struct ServiceClientBuilder {
     extn_symbol: Option<ExtnSymbol>,
+    max_in_flight_requests: Option<usize>,
+    connection_handler: Option<Arc<dyn ServiceConnectionHandler>>,
 }
 
 impl Default for ServiceClientBuilder {
@@ -64,7 +119,11 @@ impl Default for ServiceClientBuilder {
 
 impl ServiceClientBuilder {
     pub fn new() -> Self {
-        Self { extn_symbol: None }
+        Self {
+            extn_symbol: None,
+            max_in_flight_requests: None,
+            connection_handler: None,
+        }
     }
 
     pub fn with_extension(mut self, symbol: ExtnSymbol) -> Self {
@@ -72,6 +131,20 @@ impl ServiceClientBuilder {
         self
     }
 
+    /// Overrides the number of requests of a method from Ripple Main handled concurrently,
+    /// which is otherwise read from the extn symbol config.
+    pub fn with_max_in_flight_requests(mut self, max_in_flight_requests: usize) -> Self {
+        self.max_in_flight_requests = Some(max_in_flight_requests);
+        self
+    }
+
+    /// Reconnects the websocket when it drops and resyncs the unanswered requests, the
+    /// handler is notified once the service is connected again.
+    pub fn with_connection_handler(mut self, handler: Arc<dyn ServiceConnectionHandler>) -> Self {
+        self.connection_handler = Some(handler);
+        self
+    }
+
     pub fn build(
         self,
     ) -> (
@@ -81,6 +154,12 @@ impl ServiceClientBuilder {
     ) {
         let service_router = Arc::new(RwLock::new(RouterState::new()));
         let (service_sender, service_tr) = mpsc::channel::<ServiceMessage>(32);
+        let in_flight = match self.max_in_flight_requests {
+            Some(max_in_flight_requests) => ServiceInFlightRequests::new(max_in_flight_requests),
+            None => ServiceInFlightRequests::from_config(
+                self.extn_symbol.as_ref().and_then(|s| s.config.as_ref()),
+            ),
+        };
 
         if let Some(symbol) = self.extn_symbol {
             let (extn_client, ext_tr) = ExtnClient::new_extn(symbol.clone());
@@ -90,7 +169,12 @@ impl ServiceClientBuilder {
                     service_router,
                     extn_client: Some(extn_client),
                     service_id: Some(ExtnId::try_from(symbol.id.clone()).unwrap()),
-                    response_processors: Arc::new(RwLock::new(HashMap::new())),
+                    response_processors: Arc::new(RwLock::new(ExpiringMap::default())),
+                    framing: ExtnFraming::negotiate(symbol.config.as_ref()),
+                    frame_assembler: Arc::new(RwLock::new(ExtnFrameAssembler::default())),
+                    in_flight,
+                    connection_handler: self.connection_handler.clone(),
+                    contract_versions: symbol.encode_contract_versions(),
                 },
                 Some(ext_tr),
                 Some(service_tr),
@@ -102,7 +186,12 @@ impl ServiceClientBuilder {
                     service_router,
                     extn_client: None,
                     service_id: None,
-                    response_processors: Arc::new(RwLock::new(HashMap::new())),
+                    response_processors: Arc::new(RwLock::new(ExpiringMap::default())),
+                    framing: None,
+                    frame_assembler: Arc::new(RwLock::new(ExtnFrameAssembler::default())),
+                    in_flight,
+                    connection_handler: self.connection_handler.clone(),
+                    contract_versions: None,
                 },
                 None,
                 Some(service_tr),
@@ -136,10 +225,17 @@ impl ServiceClient {
         let service_id = self.service_id.clone().unwrap();
         let base_path = std::env::var("RIPPLE_SERVICE_HANDSHAKE_PATH")
             .unwrap_or_else(|_| "127.0.0.1:3474".to_string());
+        let mut query = format!("/?service_handshake={}", service_id);
+        if let Some(contract_versions) = &self.contract_versions {
+            query.push_str(&format!(
+                "&{}={}",
+                CONTRACT_VERSIONS_QUERY, contract_versions
+            ));
+        }
         let path = tokio_tungstenite::tungstenite::http::Uri::builder()
-            .scheme("ws")
+            .scheme(WebSocketUtils::get_service_scheme().as_str())
             .authority(base_path.as_str())
-            .path_and_query(format!("/?service_handshake={}", service_id))
+            .path_and_query(query)
             .build()
             .unwrap()
             .to_string();
@@ -152,60 +248,34 @@ impl ServiceClient {
             }
         };
 
-        if let Ok((mut ws_tx, mut ws_rx)) = WebSocketUtils::get_ws_stream(&path, None).await {
-            let handle_ws_message = |msg: Message| {
-                if let Message::Text(message) = msg.clone() {
-                    // Service message
-                    if let Ok(sm) = serde_json::from_str::<ServiceMessage>(&message) {
-                        match sm.message {
-                            JsonRpcMessage::Request(ref _json_rpc_request) => {
-                                if let Some(sender) = &self.service_sender {
-                                    route_service_message(
-                                        sender,
-                                        &self.service_router.read().unwrap(),
-                                        sm.clone(),
-                                    )
-                                    .unwrap_or_else(|e| {
-                                        error!("Error handling service message: {:?}", e);
-                                    })
-                                } else {
-                                    error!("Service sender is not available");
-                                }
-                            }
-                            JsonRpcMessage::Notification(_json_rpc_notification) => todo!(),
-                            JsonRpcMessage::Success(ref json_rpc_success) => {
-                                debug!(
-                                    "Received Service Success: {:?} context {:?}",
-                                    json_rpc_success,
-                                    sm.context.clone().unwrap()
-                                );
-                                self.send_service_response(sm.clone());
-                            }
-                            JsonRpcMessage::Error(ref json_rpc_error) => {
-                                error!("Received Service Error: {:?}", json_rpc_error);
-                                let mut service_message = sm.clone();
-                                service_message.message =
-                                    JsonRpcMessage::Error(json_rpc_error.clone());
-                                self.send_service_response(service_message.clone());
-                            }
-                        }
-
-                    // Extension message
-                    } else if let Ok(extn_message) = ExtnMessage::try_from(message) {
-                        if let Some(extn_client) = &self.extn_client {
-                            extn_client.handle_message(extn_message);
-                        } else {
-                            warn!("Received extension message but no extn_client present");
+        let mut reconnected = false;
+        let mut backoff_ms = RECONNECT_BACKOFF_MIN_MS;
+        loop {
+            if reconnected {
+                let delay = Self::get_reconnect_delay(backoff_ms);
+                debug!("Service Websocket reconnecting in {:?}", delay);
+                tokio::time::sleep(delay).await;
+                backoff_ms = backoff_ms.saturating_mul(2).min(RECONNECT_BACKOFF_MAX_MS);
+            }
+            let config = WebSocketUtils::get_service_config();
+            let (mut ws_tx, mut ws_rx) =
+                match WebSocketUtils::get_secure_ws_stream(&path, config).await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        error!("Service Websocket connection failed {:?}", e);
+                        if self.is_unauthorized(&e).await || self.connection_handler.is_none() {
+                            break;
                         }
-                    };
-                } else if let Message::Close(_) = msg {
-                    info!("Received Close message, exiting initialize");
-                    return false;
-                } else {
-                    warn!("Received unexpected message: {:?}", msg);
-                }
-                true
-            };
+                        reconnected = true;
+                        continue;
+                    }
+                };
+            let connected_at = tokio::time::Instant::now();
+            if reconnected {
+                // frames of a message cut off by the drop never complete
+                *self.frame_assembler.write().unwrap() = ExtnFrameAssembler::default();
+                self.start_resync();
+            }
             tokio::pin! {
                 let read_pin = ws_rx.next();
             }
@@ -215,7 +285,7 @@ impl ServiceClient {
                     Some(value) = &mut read_pin => {
                         match value {
                             Ok(msg) => {
-                                if !handle_ws_message(msg) {
+                                if !self.handle_inbound_message(msg) {
                                      break;
                                 }
                             }
@@ -232,7 +302,9 @@ impl ServiceClient {
                         }
                     }, if outbound_extn_rx.is_some() => {
                         trace!("IEC send: {:?}", request.jsonrpc_msg);
-                        let _feed = ws_tx.feed(Message::Text(request.jsonrpc_msg)).await;
+                        for frame in self.get_frames(request.jsonrpc_msg) {
+                            let _feed = ws_tx.feed(Message::Text(frame)).await;
+                        }
                         let _flush = ws_tx.flush().await;
                     }
                     Some(request) = outbound_service_rx.recv() => {
@@ -242,10 +314,239 @@ impl ServiceClient {
                     }
                 }
             }
+            if self.connection_handler.is_none() {
+                break;
+            }
+            info!("Service Websocket dropped, reconnecting");
+            // a connection which stayed up starts over from the shortest delay, one which the
+            // server keeps closing right away keeps backing off
+            if connected_at.elapsed() >= Duration::from_millis(RECONNECT_BACKOFF_MAX_MS) {
+                backoff_ms = RECONNECT_BACKOFF_MIN_MS;
+            }
+            reconnected = true;
         }
         debug!("Initialize Ended Abruptly");
     }
 
+    /// Delay before the next reconnect attempt, the backoff with up to half of it added as
+    /// jitter so services dropped together do not reconnect together
+    fn get_reconnect_delay(backoff_ms: u64) -> Duration {
+        let jitter_ms = (Uuid::new_v4().as_u128() as u64) % (backoff_ms / 2 + 1);
+        Duration::from_millis(backoff_ms.saturating_add(jitter_ms))
+    }
+
+    /// Initializes the service client against an in-process Ripple Main connection
+    /// instead of the websocket handshake endpoint.
+    pub async fn initialize_in_process(
+        &self,
+        mut outbound_extn_rx: Option<mpsc::Receiver<ApiMessage>>,
+        outbound_service_rx: Option<mpsc::Receiver<ServiceMessage>>,
+        channel: InProcessServiceChannel,
+    ) {
+        debug!("Starting Service Client in-process initialize");
+        let mut outbound_service_rx = match outbound_service_rx {
+            Some(rx) => rx,
+            None => {
+                error!("No service receiver provided to ServiceClient::initialize_in_process");
+                return;
+            }
+        };
+        let InProcessServiceChannel {
+            mut inbound,
+            outbound,
+        } = channel;
+
+        loop {
+            tokio::select! {
+                value = inbound.recv() => {
+                    match value {
+                        Some(msg) => {
+                            if !self.handle_inbound_message(msg) {
+                                break;
+                            }
+                        }
+                        None => {
+                            error!("In-process service channel closed by Ripple Main");
+                            break;
+                        }
+                    }
+                },
+                Some(request) = async {
+                    match outbound_extn_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => None,
+                    }
+                }, if outbound_extn_rx.is_some() => {
+                    trace!("IEC send: {:?}", request.jsonrpc_msg);
+                    if let Err(e) = outbound.send(Message::Text(request.jsonrpc_msg)).await {
+                        error!("Failed to send in-process extn message: {:?}", e);
+                    }
+                }
+                Some(request) = outbound_service_rx.recv() => {
+                    trace!("Service Message send: {:?}", request);
+                    if let Err(e) = outbound.send(Message::Text(request.into())).await {
+                        error!("Failed to send in-process service message: {:?}", e);
+                    }
+                }
+            }
+        }
+        debug!("In-process initialize ended");
+    }
+
+    /// Initializes the service client over the HTTP transport, for services which cant
+    /// upgrade to a websocket.
+    pub async fn initialize_http(
+        &self,
+        outbound_extn_rx: Option<mpsc::Receiver<ApiMessage>>,
+        outbound_service_rx: Option<mpsc::Receiver<ServiceMessage>>,
+        transport: HttpServiceTransport,
+    ) {
+        debug!("Starting Service Client HTTP initialize");
+        let service_id = self.service_id.clone().unwrap();
+        match transport.connect(&service_id.to_string()).await {
+            Ok(channel) => {
+                self.initialize_in_process(outbound_extn_rx, outbound_service_rx, channel)
+                    .await
+            }
+            Err(e) => {
+                error!("Failed to connect service over HTTP: {:?}", e);
+                self.is_unauthorized(&e).await;
+            }
+        }
+    }
+
+    /// Returns true and tells the connection handler when Ripple Main refused the credentials,
+    /// which the handshakes report as [DenyReason::Unpermitted]
+    async fn is_unauthorized(&self, error: &RippleError) -> bool {
+        if !matches!(error, RippleError::Permission(DenyReason::Unpermitted)) {
+            return false;
+        }
+        error!("Service credentials refused by Ripple Main, not connecting again");
+        if let Some(handler) = &self.connection_handler {
+            handler.on_unauthorized().await;
+        }
+        true
+    }
+
+    /// Handles a message received from Ripple Main over the websocket, the HTTP transport
+    /// or the in-process channel. Returns false when the connection should be closed.
+    fn handle_inbound_message(&self, msg: Message) -> bool {
+        if let Message::Text(message) = msg.clone() {
+            let message = match self.frame_assembler.write().unwrap().accept(message) {
+                Ok(Some(message)) => message,
+                Ok(None) => return true,
+                Err(e) => {
+                    error!("Dropping framed message from Ripple Main: {:?}", e);
+                    return true;
+                }
+            };
+            // Service message
+            if let Ok(sm) = serde_json::from_str::<ServiceMessage>(&message) {
+                match sm.message {
+                    JsonRpcMessage::Request(ref _json_rpc_request) => {
+                        if let Some(sender) = &self.service_sender {
+                            route_service_message(
+                                sender,
+                                &self.service_router.read().unwrap(),
+                                &self.in_flight,
+                                sm.clone(),
+                            )
+                            .unwrap_or_else(|e| {
+                                error!("Error handling service message: {:?}", e);
+                            })
+                        } else {
+                            error!("Service sender is not available");
+                        }
+                    }
+                    JsonRpcMessage::Notification(ref json_rpc_notification) => {
+                        if sm.is_heartbeat() {
+                            // answer the liveness check from Ripple Main
+                            if let Some(sender) = &self.service_sender {
+                                if let Err(e) = sender.try_send(ServiceMessage::new_heartbeat()) {
+                                    error!("Failed to answer heartbeat: {:?}", e);
+                                }
+                            }
+                        } else if sm.is_unregister() {
+                            info!(
+                                "Unregistered by Ripple Main {:?}",
+                                json_rpc_notification.params
+                            );
+                            return false;
+                        } else {
+                            debug!(
+                                "Ignoring service notification {}",
+                                json_rpc_notification.method
+                            );
+                        }
+                    }
+                    JsonRpcMessage::Success(ref json_rpc_success) => {
+                        debug!(
+                            "Received Service Success: {:?} context {:?}",
+                            json_rpc_success,
+                            sm.context.clone().unwrap()
+                        );
+                        self.send_service_response(sm.clone());
+                    }
+                    JsonRpcMessage::Error(ref json_rpc_error) => {
+                        error!("Received Service Error: {:?}", json_rpc_error);
+                        let mut service_message = sm.clone();
+                        service_message.message = JsonRpcMessage::Error(json_rpc_error.clone());
+                        self.send_service_response(service_message.clone());
+                    }
+                }
+
+            // Extension message
+            } else if let Ok(extn_message) = ExtnMessage::try_from(message) {
+                if let Some(extn_client) = &self.extn_client {
+                    extn_client.handle_message(extn_message);
+                } else {
+                    warn!("Received extension message but no extn_client present");
+                }
+            };
+        } else if let Message::Close(_) = msg {
+            info!("Received Close message, exiting initialize");
+            return false;
+        } else {
+            warn!("Received unexpected message: {:?}", msg);
+        }
+        true
+    }
+
+    /// Resyncs with Ripple Main after a reconnect. The resync request goes out over the new
+    /// connection, so it runs on its own task while the connection loop serves it.
+    fn start_resync(&self) {
+        let handler = match &self.connection_handler {
+            Some(handler) => handler.clone(),
+            None => return,
+        };
+        let mut client = self.clone();
+        tokio::spawn(async move {
+            let params = ServiceResyncParams {
+                replay: handler.replay_unanswered_calls(),
+            };
+            let result = client
+                .call_firebolt(SERVICE_RESYNC_METHOD, serde_json::to_value(params).ok())
+                .await
+                .and_then(|r| serde_json::from_value(r).map_err(|_| RippleError::ParseError));
+            match result {
+                Ok(result) => {
+                    info!("Service resynced after reconnect {:?}", result);
+                    handler.on_reconnected(client, result).await;
+                }
+                Err(e) => error!("Service resync failed after reconnect: {:?}", e),
+            }
+        });
+    }
+
+    /// Splits an outbound extn message into frames when framing was negotiated through the
+    /// extn symbol config.
+    fn get_frames(&self, message: String) -> Vec<String> {
+        match &self.framing {
+            Some(framing) => framing.encode(message),
+            None => vec![message],
+        }
+    }
+
     fn send_service_response(&self, sm: ServiceMessage) {
         if let Some(context) = &sm.context {
             if let Some(Value::String(id)) = context
@@ -277,6 +578,9 @@ impl ServiceClient {
     pub fn get_service_router(&self) -> Arc<RwLock<RouterState>> {
         self.service_router.clone()
     }
+    pub fn get_in_flight_requests(&self) -> ServiceInFlightRequests {
+        self.in_flight.clone()
+    }
     pub fn get_stack_size(&self) -> Option<ExtnStackSize> {
         self.extn_client.as_ref().and_then(|ec| ec.get_stack_size())
     }
@@ -359,6 +663,104 @@ impl ServiceClient {
         }
     }
 
+    /// Calls a Firebolt method of Ripple Main, like `device.id`. The request goes upstream
+    /// over the service connection into the Firebolt router and the response is correlated
+    /// by request id. Ripple Main only honors calls from the services it allows.
+    pub async fn call_firebolt(
+        &mut self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Value, RippleError> {
+        self.call_firebolt_with_timeout(method, params, DEFAULT_FIREBOLT_CALL_TIMEOUT_MS)
+            .await
+    }
+
+    pub async fn call_firebolt_with_timeout(
+        &mut self,
+        method: &str,
+        params: Option<Value>,
+        timeout_in_msecs: u64,
+    ) -> Result<Value, RippleError> {
+        let service_id = match &self.service_id {
+            Some(service_id) => service_id.to_string(),
+            None => {
+                error!("Firebolt call {} without a service id", method);
+                return Err(RippleError::ServiceError);
+            }
+        };
+        let ctx = Self::get_default_service_call_context(method.to_owned());
+        let response = self
+            .request_with_timeout_main(
+                method.to_owned(),
+                params,
+                &ctx,
+                timeout_in_msecs,
+                service_id,
+            )
+            .await?;
+        Self::get_firebolt_result(method, response)
+    }
+
+    /// Registers the capabilities and Firebolt methods of the service with Ripple Main, the
+    /// result lists what the capability policy of the device refused
+    pub async fn register(
+        &mut self,
+        registration: ServiceRegistrationParams,
+    ) -> Result<ServiceRegistrationResult, RippleError> {
+        let result = self
+            .call_firebolt(
+                SERVICE_REGISTER_METHOD,
+                serde_json::to_value(registration).ok(),
+            )
+            .await?;
+        serde_json::from_value(result).map_err(|_| RippleError::ParseError)
+    }
+
+    /// Adds and removes Firebolt methods of the registered service while it stays connected,
+    /// the result lists every method now served and the added ones which were refused
+    pub async fn update_registration(
+        &mut self,
+        update: ServiceRegistrationUpdate,
+    ) -> Result<ServiceRegistrationResult, RippleError> {
+        let result = self
+            .call_firebolt(
+                SERVICE_UPDATE_REGISTRATION_METHOD,
+                serde_json::to_value(update).ok(),
+            )
+            .await?;
+        serde_json::from_value(result).map_err(|_| RippleError::ParseError)
+    }
+
+    /// Emits a Firebolt event to the apps listening to it through Ripple Main
+    pub fn emit_event(&self, event: ServiceEvent) -> Result<(), RippleError> {
+        match &self.service_sender {
+            Some(sender) => sender
+                .try_send(ServiceMessage::new_event(event))
+                .map_err(|e| {
+                    error!("Error sending service event: {:?}", e);
+                    RippleError::SendFailure
+                }),
+            None => {
+                error!("Service sender is not available");
+                Err(RippleError::ServiceError)
+            }
+        }
+    }
+
+    fn get_firebolt_result(method: &str, response: ServiceMessage) -> Result<Value, RippleError> {
+        match response.message {
+            JsonRpcMessage::Success(success) => Ok(success.result),
+            JsonRpcMessage::Error(e) => {
+                error!(
+                    "Firebolt call {} failed code={} message={}",
+                    method, e.error.code, e.error.message
+                );
+                Err(RippleError::ServiceError)
+            }
+            _ => Err(RippleError::InvalidOutput),
+        }
+    }
+
     fn get_default_service_call_context(method: String) -> CallContext {
         CallContext::new(
             Uuid::new_v4().to_string(),
@@ -412,7 +814,11 @@ impl ServiceClient {
     }
 }
 
-fn add_single_processor<P>(id: String, processor: Option<P>, map: Arc<RwLock<HashMap<String, P>>>) {
+fn add_single_processor<P>(
+    id: String,
+    processor: Option<P>,
+    map: Arc<RwLock<ExpiringMap<String, P>>>,
+) {
     if let Some(processor) = processor {
         let mut processor_state = map.write().unwrap();
         processor_state.insert(id, processor);
@@ -440,7 +846,7 @@ pub mod tests {
         fn mock_with_params(
             service_sender: Option<Sender<ServiceMessage>>,
             service_rpc_router: Arc<RwLock<RouterState>>,
-            response_processors: Arc<RwLock<HashMap<String, oneshot::Sender<ServiceMessage>>>>,
+            response_processors: Arc<RwLock<ExpiringMap<String, oneshot::Sender<ServiceMessage>>>>,
             extn_client: Option<ExtnClient>,
         ) -> ServiceClient
         where
@@ -459,14 +865,19 @@ pub mod tests {
                 service_id: Some(
                     ExtnId::try_from("ripple:channel:gateway:service1".to_string()).unwrap(),
                 ),
-                response_processors: Arc::new(RwLock::new(HashMap::new())),
+                response_processors: Arc::new(RwLock::new(ExpiringMap::default())),
+                framing: None,
+                frame_assembler: Arc::new(RwLock::new(ExtnFrameAssembler::default())),
+                in_flight: ServiceInFlightRequests::default(),
+                connection_handler: None,
+                contract_versions: None,
             }
         }
 
         fn mock_with_params(
             _service_sender: Option<Sender<ServiceMessage>>,
             _service_rpc_router: Arc<RwLock<RouterState>>,
-            _response_processors: Arc<RwLock<HashMap<String, oneshot::Sender<ServiceMessage>>>>,
+            _response_processors: Arc<RwLock<ExpiringMap<String, oneshot::Sender<ServiceMessage>>>>,
             _extn_client: Option<ExtnClient>,
         ) -> ServiceClient
         where
@@ -504,4 +915,80 @@ pub mod tests {
         println!("result: {:?}", result);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_get_firebolt_result() {
+        let result = ServiceClient::get_firebolt_result(
+            "device.id",
+            ServiceMessage::new_success(json!("device1"), Id::Number(1)),
+        );
+        assert_eq!(result.unwrap(), json!("device1"));
+
+        let result = ServiceClient::get_firebolt_result(
+            "device.id",
+            ServiceMessage::new_error(-40400, "Not permitted".into(), None, Id::Number(1)),
+        );
+        assert!(matches!(result, Err(RippleError::ServiceError)));
+    }
+
+    #[derive(Debug, Default)]
+    struct UnauthorizedHandler {
+        refused: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl ServiceConnectionHandler for UnauthorizedHandler {
+        async fn on_reconnected(&self, _client: ServiceClient, _result: ServiceResyncResult) {}
+
+        async fn on_unauthorized(&self) {
+            self.refused
+                .store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_initialize_stops_on_unauthorized() {
+        use tokio_tungstenite::tungstenite::{handshake::server, http::StatusCode};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        std::env::set_var(
+            "RIPPLE_SERVICE_HANDSHAKE_PATH",
+            listener.local_addr().unwrap().to_string(),
+        );
+        let server = tokio::spawn(async move {
+            let mut handshakes = 0;
+            while let Ok(Ok((stream, _))) =
+                tokio::time::timeout(Duration::from_millis(500), listener.accept()).await
+            {
+                handshakes += 1;
+                let refuse = |_: &server::Request, _: server::Response| {
+                    let mut response =
+                        server::ErrorResponse::new(Some(r#"{"error":"unauthorized"}"#.into()));
+                    *response.status_mut() = StatusCode::UNAUTHORIZED;
+                    Err(response)
+                };
+                let _ = tokio_tungstenite::accept_hdr_async(stream, refuse).await;
+            }
+            handshakes
+        });
+
+        let handler = Arc::new(UnauthorizedHandler::default());
+        let (_tx, rx) = mpsc::channel::<ServiceMessage>(1);
+        let mut client = ServiceClient::mock();
+        client.connection_handler = Some(handler.clone());
+        tokio::time::timeout(Duration::from_secs(5), client.initialize(None, Some(rx)))
+            .await
+            .expect("initialize keeps reconnecting after a 401");
+        assert!(handler.refused.load(std::sync::atomic::Ordering::SeqCst));
+        assert_eq!(server.await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_get_reconnect_delay() {
+        for backoff_ms in [RECONNECT_BACKOFF_MIN_MS, RECONNECT_BACKOFF_MAX_MS] {
+            let delay = ServiceClient::get_reconnect_delay(backoff_ms);
+            assert!(delay >= Duration::from_millis(backoff_ms));
+            assert!(delay <= Duration::from_millis(backoff_ms + backoff_ms / 2));
+        }
+    }
 }
@@ -0,0 +1,360 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use hyper::{
+    body::HttpBody,
+    client::HttpConnector,
+    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    Body, Client, Method, Request, StatusCode,
+};
+use log::{debug, error, info};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::service_client::InProcessServiceChannel;
+use crate::{api::firebolt::fb_capabilities::DenyReason, utils::error::RippleError};
+
+const SERVICE_HTTP_EVENT_STREAM: &str = "text/event-stream";
+/// Largest response or pending event read from Ripple Main, same as its default limit for
+/// the messages of a service
+pub const SERVICE_HTTP_MAX_MESSAGE_BYTES: usize = 1048576;
+
+/// How a service connected over HTTP receives the messages from Ripple Main
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HttpServiceReceiveMode {
+    #[default]
+    LongPoll,
+    ServerSentEvents,
+}
+
+/// HTTP transport for services in environments where middleboxes block the websocket
+/// upgrade. The transport exposes the same channel pair as an in-process connection, so
+/// the service client handles messages identically on every transport.
+#[derive(Debug, Clone)]
+pub struct HttpServiceTransport {
+    base_url: String,
+    mode: HttpServiceReceiveMode,
+    client: Client<HttpConnector>,
+    /// Bearer token presented when the service registers
+    auth_token: Option<String>,
+    max_message_bytes: usize,
+}
+
+impl HttpServiceTransport {
+    pub fn new(base_url: String, mode: HttpServiceReceiveMode) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            mode,
+            client: Client::new(),
+            auth_token: None,
+            max_message_bytes: SERVICE_HTTP_MAX_MESSAGE_BYTES,
+        }
+    }
+
+    pub fn with_auth_token(mut self, auth_token: String) -> Self {
+        self.auth_token = Some(auth_token);
+        self
+    }
+
+    pub fn with_max_message_bytes(mut self, max_message_bytes: usize) -> Self {
+        self.max_message_bytes = max_message_bytes;
+        self
+    }
+
+    /// Uses the `RIPPLE_SERVICE_HTTP_PATH` authority, same as the websocket handshake path,
+    /// and the `RIPPLE_SERVICE_TOKEN` bearer token
+    pub fn from_env(mode: HttpServiceReceiveMode) -> Self {
+        let authority = std::env::var("RIPPLE_SERVICE_HTTP_PATH")
+            .unwrap_or_else(|_| "127.0.0.1:3476".to_string());
+        let transport = Self::new(format!("http://{}", authority), mode);
+        match std::env::var("RIPPLE_SERVICE_TOKEN") {
+            Ok(token) => transport.with_auth_token(token),
+            Err(_) => transport,
+        }
+    }
+
+    /// Registers the service with Ripple Main and starts the tasks which move messages
+    /// between the returned channels and the HTTP endpoints.
+    pub async fn connect(&self, service_id: &str) -> Result<InProcessServiceChannel, RippleError> {
+        let connection_id = self.register(service_id).await?;
+        info!(
+            "Service {} connected over HTTP connection_id={}",
+            service_id, connection_id
+        );
+        let messages_url = format!("{}/services/{}/messages", self.base_url, connection_id);
+
+        // Messages from Ripple Main to the service
+        let (inbound_tx, inbound_rx) = mpsc::channel::<Message>(32);
+        // Messages from the service to Ripple Main
+        let (outbound_tx, mut outbound_rx) = mpsc::channel::<Message>(32);
+
+        let sender = self.clone();
+        let url = messages_url.clone();
+        let connection_url = format!("{}/services/{}", self.base_url, connection_id);
+        tokio::spawn(async move {
+            while let Some(msg) = outbound_rx.recv().await {
+                match msg {
+                    Message::Text(text) => {
+                        if let Err(e) = sender.send_request(Method::POST, &url, text).await {
+                            error!("Failed to send service message over HTTP: {:?}", e);
+                        }
+                    }
+                    Message::Close(_) => break,
+                    _ => {}
+                }
+            }
+            // the service client is gone, unregister instead of waiting for the liveness check
+            let _ = sender
+                .send_request(Method::DELETE, &connection_url, String::new())
+                .await;
+        });
+
+        let receiver = self.clone();
+        tokio::spawn(async move {
+            let result = match receiver.mode {
+                HttpServiceReceiveMode::LongPoll => {
+                    receiver.long_poll(&messages_url, &inbound_tx).await
+                }
+                HttpServiceReceiveMode::ServerSentEvents => {
+                    receiver.receive_events(&messages_url, &inbound_tx).await
+                }
+            };
+            if let Err(e) = result {
+                error!("Service HTTP receive ended: {:?}", e);
+            }
+            let _ = inbound_tx.send(Message::Close(None)).await;
+        });
+
+        Ok(InProcessServiceChannel {
+            inbound: inbound_rx,
+            outbound: outbound_tx,
+        })
+    }
+
+    async fn register(&self, service_id: &str) -> Result<String, RippleError> {
+        let url = format!("{}/services?serviceId={}", self.base_url, service_id);
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(url)
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(token) = &self.auth_token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let request = request
+            .body(Body::empty())
+            .map_err(|_| RippleError::InvalidInput)?;
+        let (status, body) = self.send(request).await?;
+        if status == StatusCode::UNAUTHORIZED {
+            error!("Service HTTP registration refused: {}", body);
+            return Err(RippleError::Permission(DenyReason::Unpermitted));
+        }
+        if status != StatusCode::OK {
+            error!("Service HTTP registration rejected with {}", status);
+            return Err(RippleError::ServiceError);
+        }
+        serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|v| {
+                v.get("connectionId")
+                    .and_then(Value::as_str)
+                    .map(String::from)
+            })
+            .ok_or(RippleError::ParseError)
+    }
+
+    async fn long_poll(
+        &self,
+        url: &str,
+        inbound_tx: &mpsc::Sender<Message>,
+    ) -> Result<(), RippleError> {
+        loop {
+            let (status, body) = self.send_request(Method::GET, url, String::new()).await?;
+            match status {
+                StatusCode::OK => {
+                    let messages: Vec<Value> =
+                        serde_json::from_str(&body).map_err(|_| RippleError::ParseError)?;
+                    for message in messages {
+                        let text = match message {
+                            Value::String(text) => text,
+                            value => value.to_string(),
+                        };
+                        if inbound_tx.send(Message::Text(text)).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+                StatusCode::NO_CONTENT => debug!("Service HTTP poll timed out, polling again"),
+                _ => {
+                    info!("Service HTTP connection closed with {}", status);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn receive_events(
+        &self,
+        url: &str,
+        inbound_tx: &mpsc::Sender<Message>,
+    ) -> Result<(), RippleError> {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(url)
+            .header(ACCEPT, SERVICE_HTTP_EVENT_STREAM)
+            .body(Body::empty())
+            .map_err(|_| RippleError::InvalidInput)?;
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(|e| RippleError::BrokerError(e.to_string()))?;
+        if response.status() != StatusCode::OK {
+            info!(
+                "Service HTTP event stream refused with {}",
+                response.status()
+            );
+            return Ok(());
+        }
+        let mut body = response.into_body();
+        let mut buffer = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(|e| RippleError::BrokerError(e.to_string()))?;
+            buffer.extend_from_slice(&chunk);
+            for event in take_events(&mut buffer) {
+                if inbound_tx.send(Message::Text(event)).await.is_err() {
+                    return Ok(());
+                }
+            }
+            if buffer.len() > self.max_message_bytes {
+                return Err(RippleError::BrokerError(format!(
+                    "Service HTTP event exceeds {} bytes",
+                    self.max_message_bytes
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    async fn send_request(
+        &self,
+        method: Method,
+        url: &str,
+        body: String,
+    ) -> Result<(StatusCode, String), RippleError> {
+        let request = Request::builder()
+            .method(method)
+            .uri(url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .map_err(|_| RippleError::InvalidInput)?;
+        self.send(request).await
+    }
+
+    async fn send(&self, request: Request<Body>) -> Result<(StatusCode, String), RippleError> {
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(|e| RippleError::BrokerError(e.to_string()))?;
+        let status = response.status();
+        let body = read_body(response.into_body(), self.max_message_bytes).await?;
+        Ok((status, body))
+    }
+}
+
+/// Reads the body up to the limit, so a misbehaving peer cannot make the service buffer
+/// an unbounded response
+async fn read_body(mut body: Body, limit: usize) -> Result<String, RippleError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| RippleError::BrokerError(e.to_string()))?;
+        if bytes.len() + chunk.len() > limit {
+            return Err(RippleError::BrokerError(format!(
+                "Service HTTP response exceeds {} bytes",
+                limit
+            )));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+/// Removes the complete server-sent events from the buffer and returns their data. Only
+/// complete events are decoded, so a character split across chunks is kept intact.
+fn take_events(buffer: &mut Vec<u8>) -> Vec<String> {
+    let mut events = Vec::new();
+    while let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+        let bytes: Vec<u8> = buffer.drain(..end + 2).collect();
+        let event = String::from_utf8_lossy(&bytes);
+        let data: Vec<&str> = event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|data| data.strip_prefix(' ').unwrap_or(data))
+            .collect();
+        if !data.is_empty() {
+            events.push(data.join("\n"));
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_events() {
+        let mut buffer =
+            b"data: {\"id\":1}\n\n: keep-alive\n\ndata: a\ndata: b\n\ndata: par".to_vec();
+        assert_eq!(
+            take_events(&mut buffer),
+            vec!["{\"id\":1}".to_string(), "a\nb".to_string()]
+        );
+        assert_eq!(buffer, b"data: par");
+        buffer.extend_from_slice(b"tial\n\n");
+        assert_eq!(take_events(&mut buffer), vec!["partial".to_string()]);
+        assert!(buffer.is_empty());
+
+        // a character split across chunks is decoded once the event is complete
+        let bytes = "data: caf\u{e9}\n\n".as_bytes();
+        let split = bytes.len() - 3;
+        buffer.extend_from_slice(&bytes[..split]);
+        assert!(take_events(&mut buffer).is_empty());
+        buffer.extend_from_slice(&bytes[split..]);
+        assert_eq!(take_events(&mut buffer), vec!["caf\u{e9}".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_read_body() {
+        assert_eq!(read_body(Body::from("{}"), 2).await, Ok("{}".to_string()));
+        assert!(read_body(Body::from("{\"id\":1}"), 2).await.is_err());
+    }
+
+    #[test]
+    fn test_new() {
+        let transport = HttpServiceTransport::new(
+            "http://127.0.0.1:3476/".into(),
+            HttpServiceReceiveMode::ServerSentEvents,
+        );
+        assert_eq!(transport.base_url, "http://127.0.0.1:3476");
+        assert_eq!(transport.mode, HttpServiceReceiveMode::ServerSentEvents);
+        assert_eq!(transport.max_message_bytes, SERVICE_HTTP_MAX_MESSAGE_BYTES);
+        let transport = transport.with_max_message_bytes(1024);
+        assert_eq!(transport.max_message_bytes, 1024);
+    }
+}
@@ -15,23 +15,162 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
 use crate::{
-    api::gateway::rpc_gateway_api::{CallContext, RpcRequest},
-    log::{debug, error, trace},
+    api::gateway::{
+        rpc_gateway_api::{CallContext, RpcRequest},
+        rpc_response::JSON_RPC_SERVER_ERROR_THROTTLED,
+    },
+    log::{debug, error, trace, warn},
     processor::rpc_router::{RouterState, RpcRouter},
-    service::service_message::{JsonRpcMessage, ServiceMessage},
+    service::service_message::{JsonRpcMessage, ServiceMessage, ServiceRequestId},
     utils::error::RippleError,
 };
 use tokio::sync::mpsc::Sender as MSender;
 
+/// Extn symbol config key for the number of requests of a method a service handles
+/// concurrently
+pub const SERVICE_MAX_IN_FLIGHT_CONFIG: &str = "max_in_flight_requests";
+pub const DEFAULT_MAX_IN_FLIGHT_REQUESTS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TrackResult {
+    Tracked,
+    Duplicate,
+    Overloaded,
+}
+
+#[derive(Debug, Default)]
+struct InFlight {
+    requests: HashMap<ServiceRequestId, String>,
+    method_counts: HashMap<String, usize>,
+}
+
+/// Requests from Ripple Main which a service connection is handling.
+///
+/// Every request is resolved on its own task so a slow handler does not stall the other
+/// methods of the service. Each method has at most `max_in_flight` requests in flight,
+/// requests beyond the limit are rejected right away so a slow method can not hold back the
+/// others.
+#[derive(Debug, Clone)]
+pub struct ServiceInFlightRequests {
+    max_in_flight: usize,
+    in_flight: Arc<RwLock<InFlight>>,
+}
+
+impl Default for ServiceInFlightRequests {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_IN_FLIGHT_REQUESTS)
+    }
+}
+
+impl ServiceInFlightRequests {
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            max_in_flight: max_in_flight.max(1),
+            in_flight: Arc::new(RwLock::new(InFlight::default())),
+        }
+    }
+
+    pub fn from_config(config: Option<&HashMap<String, String>>) -> Self {
+        Self::new(
+            config
+                .and_then(|c| c.get(SERVICE_MAX_IN_FLIGHT_CONFIG))
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(DEFAULT_MAX_IN_FLIGHT_REQUESTS),
+        )
+    }
+
+    /// Tracks the request unless one with the same id is in flight or the method is at its
+    /// limit
+    fn track(&self, request_id: ServiceRequestId, method: &str) -> TrackResult {
+        let mut in_flight = self.in_flight.write().unwrap();
+        if in_flight.requests.contains_key(&request_id) {
+            return TrackResult::Duplicate;
+        }
+        let method = method.to_lowercase();
+        let count = in_flight.method_counts.entry(method.clone()).or_default();
+        if *count >= self.max_in_flight {
+            return TrackResult::Overloaded;
+        }
+        *count += 1;
+        in_flight.requests.insert(request_id, method);
+        TrackResult::Tracked
+    }
+
+    fn complete(&self, request_id: &ServiceRequestId) {
+        let mut in_flight = self.in_flight.write().unwrap();
+        if let Some(method) = in_flight.requests.remove(request_id) {
+            if let Some(count) = in_flight.method_counts.get_mut(&method) {
+                *count -= 1;
+                if *count == 0 {
+                    in_flight.method_counts.remove(&method);
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.in_flight.read().unwrap().requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Methods of the requests in flight, for diagnosing slow handlers
+    pub fn get_methods(&self) -> Vec<String> {
+        self.in_flight
+            .read()
+            .unwrap()
+            .requests
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
 pub fn route_service_message(
     sender: &MSender<ServiceMessage>,
     state: &RouterState,
+    in_flight: &ServiceInFlightRequests,
     sm: ServiceMessage,
 ) -> Result<(), RippleError> {
     trace!("Received Service Message: {:#?}", sm);
     match sm.message {
         JsonRpcMessage::Request(json_rpc_request) => {
+            let request_id = ServiceRequestId::from(&json_rpc_request.id);
+            let rejection = match in_flight.track(request_id.clone(), &json_rpc_request.method) {
+                TrackResult::Tracked => None,
+                TrackResult::Duplicate => Some((
+                    -32600,
+                    format!("Request {} is already in flight", request_id),
+                )),
+                TrackResult::Overloaded => Some((
+                    JSON_RPC_SERVER_ERROR_THROTTLED as i64,
+                    format!(
+                        "Too many requests for {} in flight",
+                        json_rpc_request.method
+                    ),
+                )),
+            };
+            if let Some((code, message)) = rejection {
+                warn!(
+                    "Rejecting service request {} for {}: {}",
+                    request_id, json_rpc_request.method, message
+                );
+                let mut sm_resp =
+                    ServiceMessage::new_error(code, message, None, json_rpc_request.id.clone());
+                sm_resp.set_context(sm.context.clone());
+                return sender.try_send(sm_resp).map_err(|e| {
+                    error!("Error sending service error response: {:?}", e);
+                    RippleError::InvalidInput
+                });
+            }
             let ctx = sm.context.as_ref().map_or_else(CallContext::default, |v| {
                 serde_json::from_value(v.clone()).unwrap_or_default()
             });
@@ -43,9 +182,11 @@ pub fn route_service_message(
 
             let sender = sender.clone();
             let state_clone = state.clone();
+            let in_flight = in_flight.clone();
             tokio::spawn(async move {
                 let router_state = state_clone.clone();
                 let resp = RpcRouter::resolve_route(req.clone(), &router_state).await;
+                in_flight.complete(&request_id);
 
                 match resp {
                     Ok(msg) => {
@@ -58,10 +199,11 @@ pub fn route_service_message(
                             message: msg,
                             context: sm.context.clone(),
                         };
-                        let _ = sender.try_send(sm_resp).map_err(|e| {
+                        // responses of concurrent handlers can fill the channel, wait for room
+                        // instead of dropping them
+                        if let Err(e) = sender.send(sm_resp).await {
                             error!("Error sending service response: {:?}", e);
-                            RippleError::InvalidInput
-                        });
+                        }
                     }
                     Err(e) => {
                         error!("Error resolving service route: {:?}", e);
@@ -71,10 +213,9 @@ pub fn route_service_message(
                             None,
                             json_rpc_request.id.clone(),
                         );
-                        let _ = sender.try_send(sm_resp).map_err(|e| {
+                        if let Err(e) = sender.send(sm_resp).await {
                             error!("Error sending service error response: {:?}", e);
-                            RippleError::InvalidInput
-                        });
+                        }
                     }
                 }
             });
@@ -87,3 +228,46 @@ pub fn route_service_message(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::service_message::Id;
+
+    #[test]
+    fn test_in_flight_requests() {
+        let in_flight = ServiceInFlightRequests::from_config(Some(&HashMap::from([(
+            SERVICE_MAX_IN_FLIGHT_CONFIG.to_string(),
+            "1".to_string(),
+        )])));
+        let first = ServiceRequestId::from(&Id::Number(1));
+        assert_eq!(
+            in_flight.track(first.clone(), "module.slow"),
+            TrackResult::Tracked
+        );
+        assert_eq!(
+            in_flight.track(first.clone(), "module.slow"),
+            TrackResult::Duplicate
+        );
+        // the slow method is at its limit, other methods are not held back
+        assert_eq!(
+            in_flight.track(ServiceRequestId::from(&Id::Number(2)), "Module.Slow"),
+            TrackResult::Overloaded
+        );
+        assert_eq!(
+            in_flight.track(
+                ServiceRequestId::from(&Id::String("1".into())),
+                "module.fast"
+            ),
+            TrackResult::Tracked
+        );
+        assert_eq!(in_flight.len(), 2);
+
+        in_flight.complete(&first);
+        assert_eq!(in_flight.get_methods(), vec!["module.fast".to_string()]);
+        assert_eq!(
+            in_flight.track(ServiceRequestId::from(&Id::Number(2)), "module.slow"),
+            TrackResult::Tracked
+        );
+    }
+}
@@ -73,6 +73,12 @@ impl std::fmt::Display for RippleError {
     }
 }
 
+impl From<ssda_protocol::service_message::ServiceMessageParseError> for RippleError {
+    fn from(_: ssda_protocol::service_message::ServiceMessageParseError) -> Self {
+        RippleError::ParseError
+    }
+}
+
 #[cfg(feature = "rpc")]
 impl From<RippleError> for jsonrpsee::core::Error {
     fn from(value: RippleError) -> Self {
@@ -0,0 +1,271 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    hash::Hash,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Expired entries are purged at most this often while inserting
+const PURGE_INTERVAL: Duration = Duration::from_secs(1);
+pub const DEFAULT_CORRELATION_TTL: Duration = Duration::from_secs(300);
+pub const DEFAULT_CORRELATION_CAPACITY: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    Expired,
+    Capacity,
+}
+
+pub type EvictionCallback<K, V> = Arc<dyn Fn(&K, V, EvictionReason) + Send + Sync>;
+
+struct ExpiringEntry<V> {
+    value: V,
+    inserted: Instant,
+    expires: bool,
+}
+
+/// Map for correlating requests and responses which never grows unbounded when responses
+/// do not arrive. Entries expire after the ttl, and the oldest expiring entry is evicted
+/// when the map is at capacity. Entries inserted with [ExpiringMap::insert_persistent],
+/// like subscriptions, are only removed explicitly.
+///
+/// Evicted values are dropped after the eviction callback, so pending oneshot senders
+/// resolve their receivers with an error instead of leaking.
+pub struct ExpiringMap<K, V> {
+    entries: HashMap<K, ExpiringEntry<V>>,
+    ttl: Duration,
+    capacity: usize,
+    last_purge: Instant,
+    on_evict: Option<EvictionCallback<K, V>>,
+}
+
+impl<K, V> std::fmt::Debug for ExpiringMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExpiringMap")
+            .field("len", &self.entries.len())
+            .field("ttl", &self.ttl)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+impl<K: Eq + Hash + Clone, V> Default for ExpiringMap<K, V> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CORRELATION_TTL, DEFAULT_CORRELATION_CAPACITY)
+    }
+}
+
+impl<K: Eq + Hash + Clone, V> ExpiringMap<K, V> {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            capacity,
+            last_purge: Instant::now(),
+            on_evict: None,
+        }
+    }
+
+    pub fn with_eviction_callback(mut self, on_evict: EvictionCallback<K, V>) -> Self {
+        self.on_evict = Some(on_evict);
+        self
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_entry(key, value, true)
+    }
+
+    pub fn insert_persistent(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_entry(key, value, false)
+    }
+
+    fn insert_entry(&mut self, key: K, value: V, expires: bool) -> Option<V> {
+        let now = Instant::now();
+        if now.duration_since(self.last_purge) >= PURGE_INTERVAL {
+            self.purge_expired_at(now);
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            self.evict_oldest();
+        }
+        self.entries
+            .insert(
+                key,
+                ExpiringEntry {
+                    value,
+                    inserted: now,
+                    expires,
+                },
+            )
+            .map(|e| e.value)
+    }
+
+    fn is_expired(&self, entry: &ExpiringEntry<V>, now: Instant) -> bool {
+        entry.expires && now.saturating_duration_since(entry.inserted) > self.ttl
+    }
+
+    fn evict(&mut self, key: &K, reason: EvictionReason) {
+        if let Some(entry) = self.entries.remove(key) {
+            if let Some(on_evict) = &self.on_evict {
+                on_evict(key, entry.value, reason);
+            }
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .filter(|(_, e)| e.expires)
+            .min_by_key(|(_, e)| e.inserted)
+            .map(|(k, _)| k.clone());
+        if let Some(key) = oldest {
+            self.evict(&key, EvictionReason::Capacity);
+        }
+    }
+
+    fn purge_expired_at(&mut self, now: Instant) -> usize {
+        self.last_purge = now;
+        let expired: Vec<K> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| self.is_expired(e, now))
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in expired.iter() {
+            self.evict(key, EvictionReason::Expired);
+        }
+        expired.len()
+    }
+
+    /// Evicts the expired entries and returns how many were evicted
+    pub fn purge_expired(&mut self) -> usize {
+        self.purge_expired_at(Instant::now())
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries
+            .get(key)
+            .filter(|e| !self.is_expired(e, Instant::now()))
+            .map(|e| &e.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = Instant::now();
+        let ttl = self.ttl;
+        self.entries
+            .get_mut(key)
+            .filter(|e| !(e.expires && now.saturating_duration_since(e.inserted) > ttl))
+            .map(|e| &mut e.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.remove(key).map(|e| e.value)
+    }
+
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.entries.retain(|k, e| f(k, &mut e.value));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(k, e)| (k, &e.value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.keys()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.values().map(|e| &e.value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_expiring_map() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let evicted_c = evicted.clone();
+        let mut map =
+            ExpiringMap::new(Duration::from_millis(20), 2).with_eviction_callback(Arc::new(
+                move |k: &u64, _v: String, reason| evicted_c.lock().unwrap().push((*k, reason)),
+            ));
+        map.insert(1, "one".to_owned());
+        map.insert_persistent(2, "subscription".to_owned());
+        assert_eq!(map.get(&1).unwrap(), "one");
+
+        // at capacity the oldest expiring entry is evicted
+        map.insert(3, "three".to_owned());
+        assert!(!map.contains_key(&1));
+        assert_eq!(
+            evicted.lock().unwrap().pop(),
+            Some((1, EvictionReason::Capacity))
+        );
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(map.get(&3).is_none());
+        assert_eq!(map.purge_expired(), 1);
+        assert_eq!(
+            evicted.lock().unwrap().pop(),
+            Some((3, EvictionReason::Expired))
+        );
+        assert_eq!(map.get(&2).unwrap(), "subscription");
+        assert_eq!(map.remove(&2).unwrap(), "subscription");
+        assert!(map.is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Process wide counter for the ids of requests sent by Ripple. Ids taken from the caller,
+/// like the JSON-RPC id of an app or the context of a service, are only unique within the
+/// caller and collide across sessions, so brokers and the service path allocate their own
+/// and map the response back to the original id.
+pub static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the next id, unique for the lifetime of the process
+pub fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Ordering::SeqCst)
+}
+
+/// Returns a request id prefixed with the session or service it is scoped to
+pub fn next_scoped_id(scope: &str) -> String {
+    format!("{}-{}", scope, next_id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_scoped_id() {
+        let first = next_id();
+        assert!(next_id() > first);
+        let scoped = next_scoped_id("session1");
+        assert!(scoped.starts_with("session1-"));
+        assert_ne!(scoped, next_scoped_id("session1"));
+    }
+}
@@ -17,11 +17,17 @@
 
 pub mod channel_utils;
 pub mod error;
+pub mod expiring_map;
 pub mod extn_utils;
+pub mod id_allocator;
 pub mod logger;
 pub mod mock_utils;
 pub mod rpc_utils;
+pub mod schema_utils;
 pub mod serde_utils;
 pub mod test_utils;
 pub mod time_utils;
+#[cfg(feature = "service_client")]
+pub mod tls_utils;
+#[cfg(feature = "service_client")]
 pub mod ws_utils;
@@ -0,0 +1,395 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{cell::RefCell, collections::HashMap};
+
+use serde::de::{
+    value::Error, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer,
+    MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+
+/// Options and collections nested deeper than this are traced empty, which ends the trace
+/// of recursive types
+const MAX_TRACE_DEPTH: usize = 16;
+/// Upper bound of the passes spent on visiting the variants of the enums of a type
+const MAX_TRACE_PASSES: usize = 256;
+
+/// Generates the schema of a type from its `Deserialize` implementation, as the containers,
+/// fields, variants and primitive types the type reads in order. Every variant of every enum
+/// is visited, each pass picks the next variant of the innermost enum which still has
+/// unvisited variants.
+pub fn get_schema<T: DeserializeOwned>() -> Vec<String> {
+    let mut schema = Vec::new();
+    let mut choices: HashMap<&'static str, usize> = HashMap::new();
+    for _ in 0..MAX_TRACE_PASSES {
+        let state = TraceState {
+            trace: RefCell::default(),
+            enums: RefCell::default(),
+            choices: &choices,
+        };
+        // custom deserializers may refuse the traced values, the schema read until then is kept
+        let _ = T::deserialize(Tracer {
+            state: &state,
+            depth: 0,
+        });
+        schema.extend(state.trace.into_inner());
+        let next = state
+            .enums
+            .into_inner()
+            .into_iter()
+            .rev()
+            .find(|(name, variants)| choices.get(name).copied().unwrap_or(0) + 1 < *variants);
+        match next {
+            Some((name, _)) => *choices.entry(name).or_default() += 1,
+            None => break,
+        }
+    }
+    schema
+}
+
+struct TraceState<'c> {
+    trace: RefCell<Vec<String>>,
+    /// Enums reached in this pass along with their number of variants, in the order reached
+    enums: RefCell<Vec<(&'static str, usize)>>,
+    /// Variant picked for each enum in this pass
+    choices: &'c HashMap<&'static str, usize>,
+}
+
+#[derive(Clone, Copy)]
+struct Tracer<'a> {
+    state: &'a TraceState<'a>,
+    depth: usize,
+}
+
+impl<'a> Tracer<'a> {
+    fn push(&self, entry: impl Into<String>) {
+        self.state.trace.borrow_mut().push(entry.into());
+    }
+
+    fn nested(self) -> Self {
+        Self {
+            state: self.state,
+            depth: self.depth + 1,
+        }
+    }
+
+    fn is_deep(&self) -> bool {
+        self.depth >= MAX_TRACE_DEPTH
+    }
+
+    fn pick_variant(&self, name: &'static str, variants: &'static [&'static str]) -> usize {
+        let mut enums = self.state.enums.borrow_mut();
+        if !enums.iter().any(|(n, _)| n.eq(&name)) {
+            enums.push((name, variants.len()));
+        }
+        let choice = self.state.choices.get(name).copied().unwrap_or(0);
+        choice.min(variants.len().saturating_sub(1))
+    }
+}
+
+macro_rules! trace_primitives {
+    ($($method:ident($name:literal) => $visit:ident($($value:expr)?)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.push($name);
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for Tracer<'a> {
+    type Error = Error;
+
+    trace_primitives! {
+        deserialize_bool("bool") => visit_bool(false),
+        deserialize_i8("i8") => visit_i8(0),
+        deserialize_i16("i16") => visit_i16(0),
+        deserialize_i32("i32") => visit_i32(0),
+        deserialize_i64("i64") => visit_i64(0),
+        deserialize_u8("u8") => visit_u8(0),
+        deserialize_u16("u16") => visit_u16(0),
+        deserialize_u32("u32") => visit_u32(0),
+        deserialize_u64("u64") => visit_u64(0),
+        deserialize_f32("f32") => visit_f32(0.0),
+        deserialize_f64("f64") => visit_f64(0.0),
+        deserialize_char("char") => visit_char('0'),
+        deserialize_str("str") => visit_str(""),
+        deserialize_string("string") => visit_string(String::new()),
+        deserialize_bytes("bytes") => visit_bytes(&[]),
+        deserialize_byte_buf("bytes") => visit_byte_buf(Vec::new()),
+        deserialize_unit("unit") => visit_unit(),
+        deserialize_identifier("identifier") => visit_str(""),
+        deserialize_any("any") => visit_unit(),
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.push("option");
+        if self.is_deep() {
+            return visitor.visit_none();
+        }
+        visitor.visit_some(self.nested())
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.push(format!("unit {}", name));
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.push(format!("newtype {}", name));
+        visitor.visit_newtype_struct(self.nested())
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.push("seq");
+        let remaining = if self.is_deep() { 0 } else { 1 };
+        visitor.visit_seq(SeqTrace {
+            tracer: self.nested(),
+            remaining,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.push(format!("tuple {}", len));
+        visitor.visit_seq(SeqTrace {
+            tracer: self.nested(),
+            remaining: len,
+        })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.push(format!("tuple {} {}", name, len));
+        visitor.visit_seq(SeqTrace {
+            tracer: self.nested(),
+            remaining: len,
+        })
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.push("map");
+        let remaining = if self.is_deep() { 0 } else { 1 };
+        visitor.visit_map(MapTrace {
+            tracer: self.nested(),
+            remaining,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.push(format!("struct {} {:?}", name, fields));
+        visitor.visit_map(StructTrace {
+            tracer: self.nested(),
+            fields: fields.iter(),
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.push(format!("enum {} {:?}", name, variants));
+        let Some(variant) = variants.get(self.pick_variant(name, variants)) else {
+            return Err(serde::de::Error::custom("enum without variants"));
+        };
+        self.push(format!("variant {}", variant));
+        visitor.visit_enum(EnumTrace {
+            tracer: self.nested(),
+            variant,
+        })
+    }
+}
+
+struct SeqTrace<'a> {
+    tracer: Tracer<'a>,
+    remaining: usize,
+}
+
+impl<'de, 'a> SeqAccess<'de> for SeqTrace<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(self.tracer).map(Some)
+    }
+}
+
+struct MapTrace<'a> {
+    tracer: Tracer<'a>,
+    remaining: usize,
+}
+
+impl<'de, 'a> MapAccess<'de> for MapTrace<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(self.tracer).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(self.tracer)
+    }
+}
+
+struct StructTrace<'a> {
+    tracer: Tracer<'a>,
+    fields: std::slice::Iter<'static, &'static str>,
+}
+
+impl<'de, 'a> MapAccess<'de> for StructTrace<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.fields.next() {
+            Some(field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(self.tracer)
+    }
+}
+
+struct EnumTrace<'a> {
+    tracer: Tracer<'a>,
+    variant: &'static str,
+}
+
+impl<'de, 'a> EnumAccess<'de> for EnumTrace<'a> {
+    type Error = Error;
+    type Variant = Tracer<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Tracer<'a>), Error> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self.tracer))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for Tracer<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_struct("", fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    enum Shape {
+        Point,
+        Circle { radius: f64 },
+        Polygon(Vec<(i32, i32)>),
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Drawing {
+        name: String,
+        shapes: Vec<Shape>,
+        layers: Option<Box<Drawing>>,
+    }
+
+    #[test]
+    fn test_get_schema() {
+        let schema = get_schema::<Drawing>();
+        assert_eq!(
+            schema[..4],
+            [
+                "struct Drawing [\"name\", \"shapes\", \"layers\"]",
+                "string",
+                "seq",
+                "enum Shape [\"Point\", \"Circle\", \"Polygon\"]"
+            ]
+        );
+        for entry in [
+            "variant Point",
+            "variant Circle",
+            "struct  [\"radius\"]",
+            "f64",
+            "variant Polygon",
+            "tuple 2",
+            "i32",
+        ] {
+            assert!(schema.iter().any(|e| e.eq(entry)), "{} not traced", entry);
+        }
+        assert_eq!(schema, get_schema::<Drawing>());
+    }
+}
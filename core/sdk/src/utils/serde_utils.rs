@@ -211,6 +211,35 @@ pub mod optional_date_time_str_serde {
     }
 }
 
+/// Deadline carried as the millis left before it, an [std::time::Instant] only has a meaning
+/// in the process which created it. The receiver rebuilds the deadline from its own clock.
+pub mod optional_deadline_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::{Duration, Instant};
+
+    pub fn serialize<S>(deadline: &Option<Instant>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match deadline {
+            Some(deadline) => serializer.serialize_u64(
+                deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_millis() as u64,
+            ),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Instant>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let remaining_ms: Option<u64> = Option::deserialize(deserializer)?;
+        Ok(remaining_ms.and_then(|ms| Instant::now().checked_add(Duration::from_millis(ms))))
+    }
+}
+
 pub mod timezone_serde {
     use super::{pattern_matches, Patterns};
     use serde::{Deserialize, Deserializer, Serializer};
@@ -0,0 +1,365 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use log::error;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+};
+
+use super::error::RippleError;
+use crate::api::manifest::device_manifest::WsTlsConfiguration;
+
+#[cfg(feature = "tls")]
+use {
+    rustls::{
+        client::{ServerCertVerified, ServerCertVerifier},
+        server::AllowAnyAnonymousOrAuthenticatedClient,
+        Certificate, ClientConfig, OwnedTrustAnchor, PrivateKey, RootCertStore, ServerConfig,
+        ServerName,
+    },
+    sha2::{Digest, Sha256},
+    std::{fmt::Write, fs::File, io::BufReader, sync::Arc, time::SystemTime},
+    tokio_rustls::TlsAcceptor,
+    tokio_tungstenite::Connector,
+};
+
+/// TLS settings of a `wss://` client connection. The server certificate is checked against
+/// `pinned_fingerprints` when any are given, otherwise against `ca_cert_path` or the public
+/// web roots. A client certificate is presented when both its certificate and key are set.
+#[derive(Debug, Clone, Default)]
+pub struct TlsClientConfig {
+    pub ca_cert_path: Option<String>,
+    /// SHA-256 fingerprints of the accepted server certificates, hex with optional colons
+    pub pinned_fingerprints: Vec<String>,
+    pub client_cert_path: Option<String>,
+    pub client_key_path: Option<String>,
+}
+
+impl TlsClientConfig {
+    /// Reads the `RIPPLE_SERVICE_TLS_CA`, `RIPPLE_SERVICE_TLS_PINS` (comma separated),
+    /// `RIPPLE_SERVICE_TLS_CERT` and `RIPPLE_SERVICE_TLS_KEY` variables
+    pub fn from_env() -> Self {
+        Self {
+            ca_cert_path: std::env::var("RIPPLE_SERVICE_TLS_CA").ok(),
+            pinned_fingerprints: std::env::var("RIPPLE_SERVICE_TLS_PINS")
+                .map(|pins| {
+                    pins.split(',')
+                        .map(|p| p.trim().to_owned())
+                        .filter(|p| !p.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            client_cert_path: std::env::var("RIPPLE_SERVICE_TLS_CERT").ok(),
+            client_key_path: std::env::var("RIPPLE_SERVICE_TLS_KEY").ok(),
+        }
+    }
+}
+
+/// Stream accepted by a websocket listener, wrapped in TLS when the listener terminates TLS
+pub enum ServerStream {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<tokio_rustls::server::TlsStream<TcpStream>>),
+}
+
+impl ServerStream {
+    /// Fingerprint of the certificate presented by the client, if any
+    pub fn get_peer_cert_fingerprint(&self) -> Option<String> {
+        match self {
+            ServerStream::Plain(_) => None,
+            #[cfg(feature = "tls")]
+            ServerStream::Tls(stream) => stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| TlsUtils::get_cert_fingerprint(&cert.0)),
+        }
+    }
+}
+
+impl AsyncRead for ServerStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ServerStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "tls")]
+            ServerStream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for ServerStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            ServerStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            ServerStream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ServerStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            ServerStream::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ServerStream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            ServerStream::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Wraps the connections accepted by a listener in TLS when the listener has a
+/// [WsTlsConfiguration]
+#[derive(Clone, Default)]
+pub struct ServerTlsAcceptor {
+    #[cfg(feature = "tls")]
+    acceptor: Option<TlsAcceptor>,
+}
+
+impl ServerTlsAcceptor {
+    #[cfg(feature = "tls")]
+    pub fn new(config: Option<&WsTlsConfiguration>) -> Result<Self, RippleError> {
+        let acceptor = match config {
+            Some(config) => Some(TlsUtils::get_acceptor(config)?),
+            None => None,
+        };
+        Ok(Self { acceptor })
+    }
+
+    #[cfg(not(feature = "tls"))]
+    pub fn new(config: Option<&WsTlsConfiguration>) -> Result<Self, RippleError> {
+        if config.is_some() {
+            error!("TLS listeners need ripple_sdk to be built with the tls feature");
+            return Err(RippleError::InvalidInput);
+        }
+        Ok(Self {})
+    }
+
+    pub async fn accept(&self, stream: TcpStream) -> Result<ServerStream, RippleError> {
+        #[cfg(feature = "tls")]
+        if let Some(acceptor) = &self.acceptor {
+            return match acceptor.accept(stream).await {
+                Ok(stream) => Ok(ServerStream::Tls(Box::new(stream))),
+                Err(e) => {
+                    error!("TLS handshake failed: {}", e);
+                    Err(RippleError::ApiAuthenticationFailed)
+                }
+            };
+        }
+        Ok(ServerStream::Plain(stream))
+    }
+}
+
+/// Accepts only server certificates with a pinned fingerprint, used for self signed
+/// certificates of on device and container to host connections
+#[cfg(feature = "tls")]
+struct PinnedCertVerifier {
+    fingerprints: Vec<String>,
+}
+
+#[cfg(feature = "tls")]
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let fingerprint = TlsUtils::get_cert_fingerprint(&end_entity.0);
+        if self.fingerprints.contains(&fingerprint) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "Server certificate {} is not pinned",
+                fingerprint
+            )))
+        }
+    }
+}
+
+pub struct TlsUtils;
+
+impl TlsUtils {
+    /// Lowercase hex without separators, so a fingerprint copied as `AB:CD:...` from openssl
+    /// matches the one computed from the presented certificate
+    pub fn normalize_fingerprint(fingerprint: &str) -> String {
+        fingerprint.replace(':', "").to_lowercase()
+    }
+}
+
+#[cfg(feature = "tls")]
+impl TlsUtils {
+    /// Lowercase hex SHA-256 of a DER certificate, the form used for pins and allowlists
+    pub fn get_cert_fingerprint(der: &[u8]) -> String {
+        Sha256::digest(der)
+            .iter()
+            .fold(String::new(), |mut fingerprint, b| {
+                let _ = write!(fingerprint, "{:02x}", b);
+                fingerprint
+            })
+    }
+
+    fn load_certs(path: &str) -> Result<Vec<Certificate>, RippleError> {
+        let file = File::open(path).map_err(|e| {
+            error!("Unable to open certificate {}: {}", path, e);
+            RippleError::MissingInput
+        })?;
+        let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+            .map_err(|_| RippleError::ParseError)?;
+        if certs.is_empty() {
+            error!("No certificate found in {}", path);
+            return Err(RippleError::InvalidInput);
+        }
+        Ok(certs.into_iter().map(Certificate).collect())
+    }
+
+    fn load_private_key(path: &str) -> Result<PrivateKey, RippleError> {
+        let file = File::open(path).map_err(|e| {
+            error!("Unable to open private key {}: {}", path, e);
+            RippleError::MissingInput
+        })?;
+        rustls_pemfile::read_all(&mut BufReader::new(file))
+            .map_err(|_| RippleError::ParseError)?
+            .into_iter()
+            .find_map(|item| match item {
+                rustls_pemfile::Item::PKCS8Key(key)
+                | rustls_pemfile::Item::RSAKey(key)
+                | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                error!("No private key found in {}", path);
+                RippleError::InvalidInput
+            })
+    }
+
+    fn load_roots(path: &str) -> Result<RootCertStore, RippleError> {
+        let mut roots = RootCertStore::empty();
+        for cert in Self::load_certs(path)? {
+            roots.add(&cert).map_err(|_| RippleError::InvalidInput)?;
+        }
+        Ok(roots)
+    }
+
+    /// Acceptor for a listener terminating TLS. Client certificates are requested, and
+    /// verified against `client_ca_path`, only when it is configured.
+    pub fn get_acceptor(config: &WsTlsConfiguration) -> Result<TlsAcceptor, RippleError> {
+        let certs = Self::load_certs(&config.cert_path)?;
+        let key = Self::load_private_key(&config.key_path)?;
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let builder = match &config.client_ca_path {
+            Some(path) => builder.with_client_cert_verifier(
+                AllowAnyAnonymousOrAuthenticatedClient::new(Self::load_roots(path)?).boxed(),
+            ),
+            None => builder.with_no_client_auth(),
+        };
+        let server_config = builder.with_single_cert(certs, key).map_err(|e| {
+            error!("Invalid TLS certificate or key: {}", e);
+            RippleError::InvalidInput
+        })?;
+        Ok(TlsAcceptor::from(Arc::new(server_config)))
+    }
+
+    pub fn get_connector(config: &TlsClientConfig) -> Result<Connector, RippleError> {
+        let client_auth = match (&config.client_cert_path, &config.client_key_path) {
+            (Some(cert_path), Some(key_path)) => Some((
+                Self::load_certs(cert_path)?,
+                Self::load_private_key(key_path)?,
+            )),
+            _ => None,
+        };
+        let builder = ClientConfig::builder().with_safe_defaults();
+        // the verifier and root certificate builders are different types, each one is
+        // completed to a ClientConfig on its own
+        let client_config = if !config.pinned_fingerprints.is_empty() {
+            let builder = builder.with_custom_certificate_verifier(Arc::new(PinnedCertVerifier {
+                fingerprints: config
+                    .pinned_fingerprints
+                    .iter()
+                    .map(|f| Self::normalize_fingerprint(f))
+                    .collect(),
+            }));
+            match client_auth {
+                Some((certs, key)) => builder.with_client_auth_cert(certs, key),
+                None => Ok(builder.with_no_client_auth()),
+            }
+        } else {
+            let roots = match &config.ca_cert_path {
+                Some(path) => Self::load_roots(path)?,
+                None => {
+                    let mut roots = RootCertStore::empty();
+                    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+                        OwnedTrustAnchor::from_subject_spki_name_constraints(
+                            ta.subject,
+                            ta.spki,
+                            ta.name_constraints,
+                        )
+                    }));
+                    roots
+                }
+            };
+            let builder = builder.with_root_certificates(roots);
+            match client_auth {
+                Some((certs, key)) => builder.with_client_auth_cert(certs, key),
+                None => Ok(builder.with_no_client_auth()),
+            }
+        }
+        .map_err(|e| {
+            error!("Invalid TLS client certificate or key: {}", e);
+            RippleError::InvalidInput
+        })?;
+        Ok(Connector::Rustls(Arc::new(client_config)))
+    }
+}
+
+#[cfg(all(test, feature = "tls"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cert_fingerprint() {
+        assert_eq!(
+            TlsUtils::get_cert_fingerprint(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(TlsUtils::normalize_fingerprint("AB:cd:01"), "abcd01");
+    }
+}
@@ -15,15 +15,32 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use std::time::Duration;
+use std::{future::Future, time::Duration};
 
 use futures::stream::{SplitSink, SplitStream};
 use futures_util::StreamExt;
 use log::{error, info};
-use tokio::net::TcpStream;
-use tokio_tungstenite::{client_async, tungstenite::Message, WebSocketStream};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_tungstenite::{
+    client_async,
+    tungstenite::{
+        self,
+        client::IntoClientRequest,
+        handshake::client::{Request, Response},
+        http::HeaderValue,
+        http::StatusCode,
+        Message,
+    },
+    WebSocketStream,
+};
+#[cfg(feature = "tls")]
+use tokio_tungstenite::{client_async_tls_with_config, MaybeTlsStream};
 
-use super::error::RippleError;
+use super::{error::RippleError, tls_utils::TlsClientConfig};
+use crate::api::firebolt::fb_capabilities::DenyReason;
 
 const DEFAULT_RETRY_INTERVAL: u64 = 100;
 const LOG_RETRY_INTERVAL: i32 = 10;
@@ -32,12 +49,18 @@ pub struct WebSocketConfig {
     pub alias: Option<String>,
     pub retry: Option<u64>,
     pub fail_after: Option<i32>,
+    /// Bearer token sent in the `Authorization` header of the handshake
+    pub auth_token: Option<String>,
+    /// TLS settings used for `wss://` endpoints
+    pub tls: Option<TlsClientConfig>,
 }
 
 pub struct WebSocketConfigBuilder {
     alias: Option<String>,
     retry: Option<u64>,
     fail_after: Option<i32>,
+    auth_token: Option<String>,
+    tls: Option<TlsClientConfig>,
 }
 
 impl Default for WebSocketConfigBuilder {
@@ -46,6 +69,8 @@ impl Default for WebSocketConfigBuilder {
             alias: None,
             retry: Some(DEFAULT_RETRY_INTERVAL),
             fail_after: None,
+            auth_token: None,
+            tls: None,
         }
     }
 }
@@ -66,11 +91,23 @@ impl WebSocketConfigBuilder {
         self
     }
 
+    pub fn auth_token(mut self, auth_token: String) -> Self {
+        self.auth_token = Some(auth_token);
+        self
+    }
+
+    pub fn tls(mut self, tls: TlsClientConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
     pub fn build(self) -> WebSocketConfig {
         WebSocketConfig {
             alias: self.alias,
             retry: self.retry,
             fail_after: self.fail_after,
+            auth_token: self.auth_token,
+            tls: self.tls,
         }
     }
 }
@@ -78,6 +115,25 @@ impl WebSocketConfigBuilder {
 pub struct WebSocketUtils;
 
 impl WebSocketUtils {
+    /// Handshake config of a service connecting to Ripple Main, which presents the bearer token
+    /// from `RIPPLE_SERVICE_TOKEN` when it is set and takes the TLS settings for `wss://` from
+    /// the environment, see [TlsClientConfig::from_env]
+    pub fn get_service_config() -> Option<WebSocketConfig> {
+        let mut builder = WebSocketConfigBuilder::default()
+            .retry(DEFAULT_RETRY_INTERVAL)
+            .tls(TlsClientConfig::from_env());
+        if let Ok(token) = std::env::var("RIPPLE_SERVICE_TOKEN") {
+            builder = builder.auth_token(token);
+        }
+        Some(builder.build())
+    }
+
+    /// Scheme of the service handshake path, `wss` when `RIPPLE_SERVICE_HANDSHAKE_SCHEME` asks
+    /// for TLS
+    pub fn get_service_scheme() -> String {
+        std::env::var("RIPPLE_SERVICE_HANDSHAKE_SCHEME").unwrap_or_else(|_| "ws".to_string())
+    }
+
     /// Attempts to establish a WebSocket connection to the given endpoint.
     ///
     /// # Parameters
@@ -114,21 +170,81 @@ impl WebSocketUtils {
         ),
         RippleError,
     > {
+        let (config, url_path, tcp_port) = Self::prepare(endpoint, inital_config, false)?;
+        Self::connect(config, url_path, tcp_port, |request, stream| {
+            client_async(request, stream)
+        })
+        .await
+    }
+
+    /// Same as [WebSocketUtils::get_ws_stream] and also connects to `wss://` endpoints, which
+    /// can be remote. The TLS settings are taken from [WebSocketConfig::tls].
+    #[cfg(feature = "tls")]
+    pub async fn get_secure_ws_stream(
+        endpoint: &str,
+        inital_config: Option<WebSocketConfig>,
+    ) -> Result<
+        (
+            SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
+            SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+        ),
+        RippleError,
+    > {
+        let (config, url_path, tcp_port) = Self::prepare(endpoint, inital_config, true)?;
+        let connector = if url_path.starts_with("wss://") {
+            let tls = config.tls.clone().unwrap_or_default();
+            Some(super::tls_utils::TlsUtils::get_connector(&tls)?)
+        } else {
+            None
+        };
+        Self::connect(config, url_path, tcp_port, move |request, stream| {
+            client_async_tls_with_config(request, stream, None, connector.clone())
+        })
+        .await
+    }
+
+    /// Without the `tls` feature only plain `ws://` endpoints can be reached, `wss://`
+    /// endpoints are refused
+    #[cfg(not(feature = "tls"))]
+    pub async fn get_secure_ws_stream(
+        endpoint: &str,
+        inital_config: Option<WebSocketConfig>,
+    ) -> Result<
+        (
+            SplitSink<WebSocketStream<TcpStream>, Message>,
+            SplitStream<WebSocketStream<TcpStream>>,
+        ),
+        RippleError,
+    > {
+        if endpoint.starts_with("wss://") {
+            error!("wss:// endpoints need ripple_sdk to be built with the tls feature");
+            return Err(RippleError::InvalidInput);
+        }
+        Self::get_ws_stream(endpoint, inital_config).await
+    }
+
+    fn prepare(
+        endpoint: &str,
+        inital_config: Option<WebSocketConfig>,
+        allow_tls: bool,
+    ) -> Result<(WebSocketConfig, String, String), RippleError> {
         info!("Broker Endpoint url {}", endpoint);
         let config = inital_config.unwrap_or_else(|| {
             WebSocketConfigBuilder::default()
                 .retry(DEFAULT_RETRY_INTERVAL)
                 .build()
         });
-        let retry_every = config.retry.unwrap_or(DEFAULT_RETRY_INTERVAL);
         let url_path = if let Some(ref a) = config.alias {
             format!("{}{}", endpoint, a)
         } else {
             endpoint.to_owned()
         };
         if cfg!(not(feature = "local_dev")) {
-            // Only support local ws connections
-            if !url_path.starts_with("ws://127.0.0.1") && !url_path.starts_with("ws://localhost") {
+            // Only support local ws connections, remote connections need TLS
+            let allowed = url_path.starts_with("ws://127.0.0.1")
+                || url_path.starts_with("ws://localhost")
+                || (allow_tls && url_path.starts_with("wss://"));
+            if !allowed {
                 return Err(RippleError::InvalidInput);
             }
         }
@@ -139,35 +255,85 @@ impl WebSocketUtils {
         let tcp_port = Self::extract_tcp_port(endpoint)?;
 
         info!("Url host str {}", url.host_str().unwrap());
+        Ok((config, url_path, tcp_port))
+    }
 
+    async fn connect<S, F, Fut>(
+        config: WebSocketConfig,
+        url_path: String,
+        tcp_port: String,
+        client: F,
+    ) -> Result<
+        (
+            SplitSink<WebSocketStream<S>, Message>,
+            SplitStream<WebSocketStream<S>>,
+        ),
+        RippleError,
+    >
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+        F: Fn(Request, TcpStream) -> Fut,
+        Fut: Future<Output = Result<(WebSocketStream<S>, Response), tungstenite::Error>>,
+    {
+        let retry_every = config.retry.unwrap_or(DEFAULT_RETRY_INTERVAL);
         let timeout_duration = config.fail_after.map(|f| Duration::from_secs(f as u64));
         if let Some(duration) = timeout_duration {
             tokio::time::timeout(duration, async {
-                Self::handshake(config, retry_every, url_path, tcp_port).await
+                Self::handshake(config, retry_every, url_path, tcp_port, client).await
             })
             .await
             .map_err(|_| RippleError::NotAvailable)?
         } else {
-            Self::handshake(config, retry_every, url_path, tcp_port).await
+            Self::handshake(config, retry_every, url_path, tcp_port, client).await
         }
     }
 
-    async fn connect_tcp_port(
+    async fn connect_tcp_port<S, F, Fut>(
         tcp_port: &str,
         url_path: &str,
+        auth_token: Option<&str>,
+        client: &F,
     ) -> Result<
         (
-            SplitSink<WebSocketStream<TcpStream>, Message>,
-            SplitStream<WebSocketStream<TcpStream>>,
+            SplitSink<WebSocketStream<S>, Message>,
+            SplitStream<WebSocketStream<S>>,
         ),
         RippleError,
-    > {
+    >
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+        F: Fn(Request, TcpStream) -> Fut,
+        Fut: Future<Output = Result<(WebSocketStream<S>, Response), tungstenite::Error>>,
+    {
         match TcpStream::connect(&tcp_port).await {
             Ok(v) => {
                 // Setup handshake for websocket with the tcp port
                 // Some WS servers lock on to the Port but not setup handshake till they are fully setup
-                if let Ok((stream, _)) = client_async(url_path, v).await {
-                    return Ok(stream.split());
+                let mut request = url_path
+                    .into_client_request()
+                    .map_err(|_| RippleError::InvalidInput)?;
+                if let Some(token) = auth_token {
+                    if let Ok(value) = HeaderValue::from_str(&format!("Bearer {}", token)) {
+                        request.headers_mut().insert("Authorization", value);
+                    }
+                }
+                match client(request, v).await {
+                    Ok((stream, _)) => return Ok(stream.split()),
+                    Err(tungstenite::Error::Http(response))
+                        if response.status() == StatusCode::UNAUTHORIZED =>
+                    {
+                        // retrying does not help, the credentials are refused
+                        error!(
+                            "Websocket handshake with {} refused: {}",
+                            url_path,
+                            String::from_utf8_lossy(response.body().as_deref().unwrap_or_default())
+                        );
+                        return Err(RippleError::Permission(DenyReason::Unpermitted));
+                    }
+                    Err(tungstenite::Error::Tls(e)) => {
+                        error!("TLS handshake with {} failed: {}", url_path, e);
+                    }
+                    Err(_) => {}
                 }
             }
             Err(e) => {
@@ -190,21 +356,34 @@ impl WebSocketUtils {
         }
     }
 
-    async fn handshake(
+    async fn handshake<S, F, Fut>(
         config: WebSocketConfig,
         retry_every: u64,
         url_path: String,
         tcp_port: String,
+        client: F,
     ) -> Result<
         (
-            SplitSink<WebSocketStream<TcpStream>, Message>,
-            SplitStream<WebSocketStream<TcpStream>>,
+            SplitSink<WebSocketStream<S>, Message>,
+            SplitStream<WebSocketStream<S>>,
         ),
         RippleError,
-    > {
+    >
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+        F: Fn(Request, TcpStream) -> Fut,
+        Fut: Future<Output = Result<(WebSocketStream<S>, Response), tungstenite::Error>>,
+    {
         let mut index: i32 = 0;
         loop {
-            match Self::connect_tcp_port(&tcp_port, &url_path).await {
+            match Self::connect_tcp_port(
+                &tcp_port,
+                &url_path,
+                config.auth_token.as_deref(),
+                &client,
+            )
+            .await
+            {
                 Ok(v) => {
                     info!("Websocket TCP Connection with {} succeeded", url_path);
                     break Ok(v);
@@ -263,6 +442,8 @@ mod tests {
             alias: None,
             retry: Some(100),
             fail_after: Some(5),
+            auth_token: None,
+            tls: None,
         };
         let result = WebSocketUtils::get_ws_stream("invalid_url", Some(config)).await;
         assert!(matches!(result, Err(RippleError::InvalidInput)));
@@ -0,0 +1,13 @@
+export type Handler = (params: unknown) => unknown | Promise<unknown>;
+
+export class RippleServiceError extends Error {
+  code: number;
+}
+
+export class RippleService {
+  constructor(serviceId: string);
+  handle(method: string, handler: Handler): this;
+  register(capabilities: string[], methods?: string[]): Promise<unknown>;
+  emitEvent(event: string, result: unknown, appId?: string | null): void;
+  close(): Promise<void>;
+}
@@ -0,0 +1,183 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+'use strict';
+
+const path = require('path');
+const koffi = require('koffi');
+
+const OK = 0;
+const ERROR_FAILED = -2;
+// JSON-RPC internal error, used when a handler throws
+const INTERNAL_ERROR = -32603;
+
+let lib = null;
+
+function library() {
+  if (lib) {
+    return lib;
+  }
+  const name = process.platform === 'darwin' ? 'libripple_service_ffi.dylib' : 'libripple_service_ffi.so';
+  const native = koffi.load(process.env.RIPPLE_SERVICE_LIB || path.join(__dirname, name));
+  koffi.opaque('RippleService');
+  const handler = koffi.proto(
+    'void RippleServiceHandler(void *user_data, uint64_t request_id, const char *method, const char *params_json)'
+  );
+  lib = {
+    handler,
+    connect: native.func(
+      'RippleService *ripple_service_connect(const char *service_id, RippleServiceHandler *handler, void *user_data)'
+    ),
+    register: native.func(
+      'int32_t ripple_service_register(RippleService *service, const char *registration_json, _Out_ void **result_json)'
+    ),
+    respond: native.func(
+      'int32_t ripple_service_respond(RippleService *service, uint64_t request_id, const char *result_json)'
+    ),
+    respondError: native.func(
+      'int32_t ripple_service_respond_error(RippleService *service, uint64_t request_id, int32_t code, const char *message)'
+    ),
+    emitEvent: native.func(
+      'int32_t ripple_service_emit_event(RippleService *service, const char *event, const char *result_json, const char *app_id)'
+    ),
+    freeString: native.func('void ripple_service_free_string(void *value)'),
+    disconnect: native.func('void ripple_service_disconnect(RippleService *service)'),
+  };
+  return lib;
+}
+
+/** A call into the service library failed, `code` is one of the RIPPLE_SERVICE_ codes */
+class RippleServiceError extends Error {
+  constructor(call, code) {
+    super(`${call} failed with ${code}`);
+    this.code = code;
+  }
+}
+
+function check(call, code) {
+  if (code !== OK) {
+    throw new RippleServiceError(call, code);
+  }
+}
+
+/**
+ * Connection of a service with Ripple Main, kept until `close`.
+ *
+ * Handlers receive the params of the request, or null, and return the JSON result or a
+ * promise of it. A thrown error is answered as an internal error with its message.
+ */
+class RippleService {
+  constructor(serviceId) {
+    this.lib = library();
+    this.handlers = new Map();
+    this.pending = new Set();
+    this.closed = false;
+    // Registered for the lifetime of the connection, the library calls it from its runtime
+    // threads and koffi runs it on the event loop
+    this.callback = koffi.register(
+      (userData, requestId, method, paramsJson) => this.onRequest(requestId, method, paramsJson),
+      koffi.pointer(this.lib.handler)
+    );
+    this.service = this.lib.connect(serviceId, this.callback, null);
+    if (!this.service) {
+      koffi.unregister(this.callback);
+      throw new Error(`invalid service id ${serviceId}`);
+    }
+  }
+
+  /** Adds the handler of `method`. Methods are served once registered. */
+  handle(method, handler) {
+    this.handlers.set(method, handler);
+    return this;
+  }
+
+  /** Registers the capabilities and the handled methods, resolves with the registration result */
+  register(capabilities, methods = [...this.handlers.keys()]) {
+    const service = this.connected();
+    const registration = JSON.stringify({ capabilities, methods });
+    const result = [null];
+    // Waits for Ripple Main on a worker thread, the event loop keeps serving requests
+    return new Promise((resolve, reject) => {
+      this.lib.register.async(service, registration, result, (err, code) => {
+        try {
+          if (err) {
+            reject(err);
+          } else if (code !== OK) {
+            reject(new RippleServiceError('ripple_service_register', code));
+          } else {
+            resolve(result[0] ? JSON.parse(koffi.decode(result[0], 'char', -1)) : null);
+          }
+        } finally {
+          this.lib.freeString(result[0]);
+        }
+      });
+    });
+  }
+
+  /** Emits `event` to the listening apps, or only to `appId` */
+  emitEvent(event, result, appId = null) {
+    check('ripple_service_emit_event', this.lib.emitEvent(this.connected(), event, JSON.stringify(result), appId));
+  }
+
+  /** Waits for the running handlers and disconnects, requests not answered yet are failed */
+  async close() {
+    if (this.closed) {
+      return;
+    }
+    this.closed = true;
+    await Promise.allSettled([...this.pending]);
+    this.lib.disconnect(this.service);
+    koffi.unregister(this.callback);
+    this.service = null;
+  }
+
+  connected() {
+    if (this.closed) {
+      throw new RippleServiceError('closed service', ERROR_FAILED);
+    }
+    return this.service;
+  }
+
+  onRequest(requestId, method, paramsJson) {
+    if (this.closed) {
+      // The library fails the request once disconnected
+      return;
+    }
+    const params = paramsJson ? JSON.parse(paramsJson) : null;
+    const served = this.serve(requestId, method, params);
+    this.pending.add(served);
+    served.finally(() => this.pending.delete(served));
+  }
+
+  async serve(requestId, method, params) {
+    const handler = this.handlers.get(method);
+    let result;
+    try {
+      if (!handler) {
+        throw new Error(`no handler for ${method}`);
+      }
+      const value = await handler(params);
+      result = JSON.stringify(value === undefined ? null : value);
+    } catch (e) {
+      this.lib.respondError(this.service, requestId, INTERNAL_ERROR, String(e && e.message ? e.message : e));
+      return;
+    }
+    // close() waits for the pending handlers, so the service is still connected here
+    this.lib.respond(this.service, requestId, result);
+  }
+}
+
+module.exports = { RippleService, RippleServiceError };
@@ -0,0 +1,22 @@
+#!/bin/sh
+#
+# Builds ripple_service_ffi and packs the Python wheel and the Node package with the library,
+# into bindings/dist. Run from anywhere in the workspace.
+set -e
+
+BINDINGS=$(cd "$(dirname "$0")" && pwd)
+WORKSPACE=$(cd "$BINDINGS/../../.." && pwd)
+case "$(uname)" in
+    Darwin) LIB=libripple_service_ffi.dylib ;;
+    *) LIB=libripple_service_ffi.so ;;
+esac
+
+cargo build --release -p ripple_service_ffi --manifest-path "$WORKSPACE/Cargo.toml"
+TARGET_DIR=${CARGO_TARGET_DIR:-$WORKSPACE/target}
+
+mkdir -p "$BINDINGS/dist"
+cp "$TARGET_DIR/release/$LIB" "$BINDINGS/python/ripple_service/$LIB"
+cp "$TARGET_DIR/release/$LIB" "$BINDINGS/node/$LIB"
+
+python3 -m pip wheel --no-deps -w "$BINDINGS/dist" "$BINDINGS/python"
+(cd "$BINDINGS/node" && npm pack --pack-destination "$BINDINGS/dist")
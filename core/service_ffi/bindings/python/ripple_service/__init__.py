@@ -0,0 +1,212 @@
+# Copyright 2023 Comcast Cable Communications Management, LLC
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+# http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+# SPDX-License-Identifier: Apache-2.0
+#
+"""Serve Firebolt methods from a Python service through Ripple Main.
+
+    service = RippleService("ripple:channel:device:audio")
+
+    @service.handle("audio.volume")
+    def volume(params):
+        return {"volume": 50}
+
+    service.register(["xrn:firebolt:capability:device:audio"])
+    service.emit_event("audio.onVolumeChanged", {"volume": 50})
+"""
+
+import ctypes
+import json
+import os
+import sys
+import threading
+from concurrent.futures import ThreadPoolExecutor
+
+__all__ = ["RippleService", "RippleServiceError"]
+
+OK = 0
+ERROR_INVALID_ARGUMENT = -1
+ERROR_FAILED = -2
+ERROR_UNKNOWN_REQUEST = -3
+
+# JSON-RPC internal error, used when a handler raises
+INTERNAL_ERROR = -32603
+
+_HANDLER = ctypes.CFUNCTYPE(
+    None, ctypes.c_void_p, ctypes.c_uint64, ctypes.c_char_p, ctypes.c_char_p
+)
+
+
+def _load():
+    name = "libripple_service_ffi.dylib" if sys.platform == "darwin" else "libripple_service_ffi.so"
+    path = os.environ.get("RIPPLE_SERVICE_LIB") or os.path.join(os.path.dirname(__file__), name)
+    lib = ctypes.CDLL(path)
+    lib.ripple_service_connect.argtypes = [ctypes.c_char_p, _HANDLER, ctypes.c_void_p]
+    lib.ripple_service_connect.restype = ctypes.c_void_p
+    lib.ripple_service_register.argtypes = [
+        ctypes.c_void_p,
+        ctypes.c_char_p,
+        ctypes.POINTER(ctypes.c_void_p),
+    ]
+    lib.ripple_service_register.restype = ctypes.c_int32
+    lib.ripple_service_respond.argtypes = [ctypes.c_void_p, ctypes.c_uint64, ctypes.c_char_p]
+    lib.ripple_service_respond.restype = ctypes.c_int32
+    lib.ripple_service_respond_error.argtypes = [
+        ctypes.c_void_p,
+        ctypes.c_uint64,
+        ctypes.c_int32,
+        ctypes.c_char_p,
+    ]
+    lib.ripple_service_respond_error.restype = ctypes.c_int32
+    lib.ripple_service_emit_event.argtypes = [
+        ctypes.c_void_p,
+        ctypes.c_char_p,
+        ctypes.c_char_p,
+        ctypes.c_char_p,
+    ]
+    lib.ripple_service_emit_event.restype = ctypes.c_int32
+    lib.ripple_service_free_string.argtypes = [ctypes.c_void_p]
+    lib.ripple_service_free_string.restype = None
+    lib.ripple_service_disconnect.argtypes = [ctypes.c_void_p]
+    lib.ripple_service_disconnect.restype = None
+    return lib
+
+
+_lib = None
+
+
+def _library():
+    global _lib
+    if _lib is None:
+        _lib = _load()
+    return _lib
+
+
+class RippleServiceError(Exception):
+    """A call into the service library failed, `code` is one of the RIPPLE_SERVICE_ codes"""
+
+    def __init__(self, call, code):
+        super().__init__("{} failed with {}".format(call, code))
+        self.code = code
+
+
+def _check(call, code):
+    if code != OK:
+        raise RippleServiceError(call, code)
+
+
+class RippleService:
+    """Connection of a service with Ripple Main, kept until `close`.
+
+    Handlers run on a thread pool of `max_workers` threads, they receive the params of the
+    request, or None, and return the JSON result. An exception is answered as an internal
+    error with its message.
+    """
+
+    def __init__(self, service_id, max_workers=4):
+        self._lib = _library()
+        self._handlers = {}
+        self._closed = False
+        self._lock = threading.Lock()
+        self._executor = ThreadPoolExecutor(max_workers=max_workers)
+        # Kept for the lifetime of the connection, the library calls it until close
+        self._callback = _HANDLER(self._on_request)
+        self._service = self._lib.ripple_service_connect(
+            service_id.encode(), self._callback, None
+        )
+        if not self._service:
+            raise ValueError("invalid service id {}".format(service_id))
+
+    def handle(self, method, handler=None):
+        """Adds the handler of `method`, usable as a decorator. Methods are served once registered."""
+
+        def add(handler):
+            with self._lock:
+                self._handlers[method] = handler
+            return handler
+
+        return add(handler) if handler is not None else add
+
+    def register(self, capabilities, methods=None):
+        """Registers the capabilities and the handled methods, returns the registration result"""
+        with self._lock:
+            methods = list(self._handlers) if methods is None else list(methods)
+        registration = json.dumps({"capabilities": list(capabilities), "methods": methods})
+        result = ctypes.c_void_p()
+        code = self._lib.ripple_service_register(
+            self._handle(), registration.encode(), ctypes.byref(result)
+        )
+        try:
+            _check("ripple_service_register", code)
+            return json.loads(ctypes.string_at(result.value).decode()) if result.value else None
+        finally:
+            self._lib.ripple_service_free_string(result)
+
+    def emit_event(self, event, result, app_id=None):
+        """Emits `event` to the listening apps, or only to `app_id`"""
+        code = self._lib.ripple_service_emit_event(
+            self._handle(),
+            event.encode(),
+            json.dumps(result).encode(),
+            app_id.encode() if app_id is not None else None,
+        )
+        _check("ripple_service_emit_event", code)
+
+    def close(self):
+        """Waits for the running handlers and disconnects, requests not answered yet are failed"""
+        with self._lock:
+            if self._closed:
+                return
+            self._closed = True
+        self._executor.shutdown(wait=True)
+        self._lib.ripple_service_disconnect(self._service)
+        self._service = None
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *args):
+        self.close()
+
+    def _handle(self):
+        if self._closed:
+            raise RippleServiceError("closed service", ERROR_FAILED)
+        return self._service
+
+    def _on_request(self, _user_data, request_id, method, params_json):
+        # Called on a runtime thread of the library, the strings are only valid during the call
+        method = method.decode()
+        params = json.loads(params_json.decode()) if params_json else None
+        try:
+            self._executor.submit(self._serve, request_id, method, params)
+        except RuntimeError:
+            # Closing, the library fails the request once disconnected
+            pass
+
+    def _serve(self, request_id, method, params):
+        with self._lock:
+            handler = self._handlers.get(method)
+        if handler is None:
+            self._respond_error(request_id, INTERNAL_ERROR, "no handler for {}".format(method))
+            return
+        try:
+            result = json.dumps(handler(params))
+        except Exception as e:
+            self._respond_error(request_id, INTERNAL_ERROR, str(e))
+            return
+        self._lib.ripple_service_respond(self._service, request_id, result.encode())
+
+    def _respond_error(self, request_id, code, message):
+        # The pool is drained before disconnecting, so the handle is still open here
+        self._lib.ripple_service_respond_error(self._service, request_id, code, message.encode())
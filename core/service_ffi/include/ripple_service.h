@@ -0,0 +1,102 @@
+#ifndef RIPPLE_SERVICE_H
+#define RIPPLE_SERVICE_H
+
+/* Generated with cbindgen from core/service_ffi, build with --features headers to update. */
+
+#include <stdint.h>
+
+#define RIPPLE_SERVICE_OK 0
+
+/*
+ A required pointer was null, or a string was not valid UTF-8 or JSON
+ */
+#define RIPPLE_SERVICE_ERROR_INVALID_ARGUMENT -1
+
+/*
+ Ripple Main refused the call or the service is not connected
+ */
+#define RIPPLE_SERVICE_ERROR_FAILED -2
+
+/*
+ The request was answered already or never handed to the handler
+ */
+#define RIPPLE_SERVICE_ERROR_UNKNOWN_REQUEST -3
+
+/*
+ Connection of a native service with Ripple Main, created by `ripple_service_connect`
+ */
+typedef struct RippleService RippleService;
+
+/*
+ Called for every request of a registered method. `method` and `params_json` are only valid
+ during the call, `params_json` is null for requests without params. The handler answers
+ later with `ripple_service_respond` or `ripple_service_respond_error` from any thread, it
+ is called on a runtime thread and should return quickly.
+ */
+typedef void (*RippleServiceHandler)(void *user_data, uint64_t request_id, const char *method, const char *params_json);
+
+/*
+ Connects the service with Ripple Main in the background and returns its handle, or null
+ when `service_id` is not a valid ServiceId like `ripple:channel:device:daemon`. The
+ connection is retried until `ripple_service_disconnect`.
+
+ # Safety
+ `service_id` must be a valid C string. `user_data` is passed to `handler` as is and must
+ stay valid until `ripple_service_disconnect` returns.
+ */
+struct RippleService *ripple_service_connect(const char *service_id, RippleServiceHandler handler, void *user_data);
+
+/*
+ Registers the capabilities and methods of the service, `registration_json` is like
+ `{"capabilities":["xrn:firebolt:capability:..."],"methods":["module.method"]}`. When
+ `result_json` is not null it receives the registration result, which the caller frees
+ with `ripple_service_free_string`.
+
+ # Safety
+ `service` must come from `ripple_service_connect`, the strings must be valid C strings.
+ */
+int32_t ripple_service_register(struct RippleService *service, const char *registration_json, char **result_json);
+
+/*
+ Answers a request handed to the handler with its result
+
+ # Safety
+ `service` must come from `ripple_service_connect`, `result_json` must be a valid C string.
+ */
+int32_t ripple_service_respond(struct RippleService *service, uint64_t request_id, const char *result_json);
+
+/*
+ Answers a request handed to the handler with a JSON-RPC error
+
+ # Safety
+ `service` must come from `ripple_service_connect`, `message` must be a valid C string.
+ */
+int32_t ripple_service_respond_error(struct RippleService *service, uint64_t request_id, int32_t code, const char *message);
+
+/*
+ Emits a Firebolt event like `module.onSomethingChanged` to the listening apps, or only to
+ `app_id` when it is not null
+
+ # Safety
+ `service` must come from `ripple_service_connect`, the strings must be valid C strings.
+ */
+int32_t ripple_service_emit_event(struct RippleService *service, const char *event, const char *result_json, const char *app_id);
+
+/*
+ Frees a string returned by this library
+
+ # Safety
+ `value` must be null or a string returned by this library which was not freed yet.
+ */
+void ripple_service_free_string(char *value);
+
+/*
+ Closes the connection and frees the service. The handler is not called anymore once this
+ returns, requests it did not answer are failed. Must not be called from the handler.
+
+ # Safety
+ `service` must be null or come from `ripple_service_connect` and not be used afterwards.
+ */
+void ripple_service_disconnect(struct RippleService *service);
+
+#endif /* RIPPLE_SERVICE_H */
@@ -0,0 +1,577 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::{c_char, c_void, CStr, CString},
+    future::Future,
+    ptr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc as std_mpsc, Arc, Condvar, Mutex,
+    },
+};
+
+use jsonrpsee::{
+    core::{server::rpc_module::Methods, RpcResult},
+    types::Params,
+    RpcModule,
+};
+use ripple_sdk::{
+    api::manifest::extn_manifest::ExtnSymbol,
+    async_trait::async_trait,
+    extn::extn_id::ExtnId,
+    log::{error, warn},
+    serde_json::{self, Value},
+    service::{
+        service_client::{ServiceClient, ServiceConnectionHandler},
+        service_message::{ServiceEvent, ServiceRegistrationParams, ServiceResyncResult},
+    },
+    tokio::{
+        runtime::{Builder, Handle, Runtime, RuntimeFlavor},
+        sync::oneshot,
+        task::block_in_place,
+    },
+    utils::rpc_utils::{rpc_err, rpc_error_with_code},
+};
+
+pub const RIPPLE_SERVICE_OK: i32 = 0;
+/// A required pointer was null, or a string was not valid UTF-8 or JSON
+pub const RIPPLE_SERVICE_ERROR_INVALID_ARGUMENT: i32 = -1;
+/// Ripple Main refused the call or the service is not connected
+pub const RIPPLE_SERVICE_ERROR_FAILED: i32 = -2;
+/// The request was answered already or never handed to the handler
+pub const RIPPLE_SERVICE_ERROR_UNKNOWN_REQUEST: i32 = -3;
+
+/// Called for every request of a registered method. `method` and `params_json` are only valid
+/// during the call, `params_json` is null for requests without params. The handler answers
+/// later with `ripple_service_respond` or `ripple_service_respond_error` from any thread, it
+/// is called on a runtime thread and should return quickly.
+pub type RippleServiceHandler = Option<
+    extern "C" fn(
+        user_data: *mut c_void,
+        request_id: u64,
+        method: *const c_char,
+        params_json: *const c_char,
+    ),
+>;
+
+#[derive(Clone, Copy)]
+struct FfiHandler {
+    handler: RippleServiceHandler,
+    user_data: *mut c_void,
+}
+
+// The daemon hands over user_data for the calls of its handler from any thread
+unsafe impl Send for FfiHandler {}
+unsafe impl Sync for FfiHandler {}
+
+enum FfiResponse {
+    Result(Value),
+    Error(i32, String),
+}
+
+/// The handler of the daemon and the number of its calls which did not return yet
+#[derive(Default)]
+struct FfiCalls {
+    handler: Option<FfiHandler>,
+    running: usize,
+}
+
+/// Hands requests to the handler of the daemon and correlates its answers
+#[derive(Default)]
+struct FfiDispatcher {
+    calls: Mutex<FfiCalls>,
+    idle: Condvar,
+    next_request_id: AtomicU64,
+    pending: Mutex<HashMap<u64, oneshot::Sender<FfiResponse>>>,
+}
+
+impl FfiDispatcher {
+    fn new(handler: RippleServiceHandler, user_data: *mut c_void) -> Self {
+        Self {
+            calls: Mutex::new(FfiCalls {
+                handler: Some(FfiHandler { handler, user_data }),
+                running: 0,
+            }),
+            ..Default::default()
+        }
+    }
+
+    async fn dispatch(&self, method: &str, params: Option<Value>) -> RpcResult<Value> {
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(request_id, tx);
+        if !self.invoke(request_id, method, params) {
+            self.pending.lock().unwrap().remove(&request_id);
+            return Err(rpc_err("Service handler is not available"));
+        }
+        match rx.await {
+            Ok(FfiResponse::Result(result)) => Ok(result),
+            Ok(FfiResponse::Error(code, message)) => Err(rpc_error_with_code::<()>(message, code)),
+            Err(_) => Err(rpc_err("Service disconnected before responding")),
+        }
+    }
+
+    /// Calls the handler unless the service disconnected. No lock is held during the call, so
+    /// the handler can call back into the library, `detach` waits for the running calls.
+    fn invoke(&self, request_id: u64, method: &str, params: Option<Value>) -> bool {
+        let (Ok(method), Ok(params)) = (
+            CString::new(method),
+            params.map(|p| CString::new(p.to_string())).transpose(),
+        ) else {
+            return false;
+        };
+        let (handler, user_data) = {
+            let mut calls = self.calls.lock().unwrap();
+            let Some(FfiHandler {
+                handler: Some(handler),
+                user_data,
+            }) = calls.handler
+            else {
+                return false;
+            };
+            calls.running += 1;
+            (handler, user_data)
+        };
+        handler(
+            user_data,
+            request_id,
+            method.as_ptr(),
+            params.as_ref().map_or(ptr::null(), |p| p.as_ptr()),
+        );
+        let mut calls = self.calls.lock().unwrap();
+        calls.running -= 1;
+        if calls.running == 0 {
+            self.idle.notify_all();
+        }
+        true
+    }
+
+    fn respond(&self, request_id: u64, response: FfiResponse) -> bool {
+        match self.pending.lock().unwrap().remove(&request_id) {
+            Some(tx) => tx.send(response).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Stops calling the handler, waits for its running calls and fails the requests it did
+    /// not answer
+    fn detach(&self) {
+        let mut calls = self.calls.lock().unwrap();
+        calls.handler = None;
+        drop(self.idle.wait_while(calls, |c| c.running > 0).unwrap());
+        self.pending.lock().unwrap().clear();
+    }
+}
+
+struct FfiMethodContext {
+    method: String,
+    dispatcher: Arc<FfiDispatcher>,
+}
+
+async fn call_handler(params: Params<'static>, context: Arc<FfiMethodContext>) -> RpcResult<Value> {
+    // the first param is the call context added by the service client
+    let params = params
+        .parse::<Vec<Value>>()
+        .ok()
+        .and_then(|p| p.into_iter().nth(1));
+    context.dispatcher.dispatch(&context.method, params).await
+}
+
+/// Registers the last registration again once the client reconnected
+#[derive(Debug, Default)]
+struct FfiConnectionHandler {
+    registration: Arc<Mutex<Option<ServiceRegistrationParams>>>,
+}
+
+#[async_trait]
+impl ServiceConnectionHandler for FfiConnectionHandler {
+    async fn on_reconnected(&self, mut client: ServiceClient, _result: ServiceResyncResult) {
+        let registration = self.registration.lock().unwrap().clone();
+        if let Some(registration) = registration {
+            if let Err(e) = client.register(registration).await {
+                error!("Failed to register the service again: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Connection of a native service with Ripple Main, created by `ripple_service_connect`
+pub struct RippleService {
+    runtime: Runtime,
+    client: ServiceClient,
+    dispatcher: Arc<FfiDispatcher>,
+    registration: Arc<Mutex<Option<ServiceRegistrationParams>>>,
+    methods: Mutex<HashSet<String>>,
+}
+
+impl RippleService {
+    /// Runs a call of the client on the runtime and waits for it, this works from the
+    /// threads of the daemon and from its handler alike. The handler is called on a worker
+    /// of the runtime, which hands its other tasks to a new worker while it waits.
+    fn run<F>(&self, future: F) -> Option<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (tx, rx) = std_mpsc::channel();
+        self.runtime.spawn(async move {
+            let _ = tx.send(future.await);
+        });
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                block_in_place(|| rx.recv().ok())
+            }
+            _ => rx.recv().ok(),
+        }
+    }
+
+    /// Routes the requests of the methods which are not routed yet to the handler
+    fn add_methods(&self, methods: &[String]) {
+        let mut registered = self.methods.lock().unwrap();
+        let mut all = Methods::new();
+        for method in methods {
+            if !registered.insert(method.to_owned()) {
+                continue;
+            }
+            let mut module = RpcModule::new(FfiMethodContext {
+                method: method.to_owned(),
+                dispatcher: self.dispatcher.clone(),
+            });
+            // the router keeps methods for the lifetime of the service
+            let name: &'static str = Box::leak(method.clone().into_boxed_str());
+            if let Err(e) = module.register_async_method(name, call_handler) {
+                error!("Failed to route {}: {:?}", method, e);
+                continue;
+            }
+            if let Err(e) = all.merge(module) {
+                error!("Failed to route {}: {:?}", method, e);
+            }
+        }
+        if let Err(e) = self.client.clone().set_service_rpc_route(all) {
+            error!("Failed to update the service routes: {:?}", e);
+        }
+    }
+}
+
+unsafe fn get_str<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+    CStr::from_ptr(value).to_str().ok()
+}
+
+unsafe fn get_json(value: *const c_char) -> Option<Value> {
+    get_str(value).and_then(|v| serde_json::from_str(v).ok())
+}
+
+/// Connects the service with Ripple Main in the background and returns its handle, or null
+/// when `service_id` is not a valid ServiceId like `ripple:channel:device:daemon`. The
+/// connection is retried until `ripple_service_disconnect`.
+///
+/// # Safety
+/// `service_id` must be a valid C string. `user_data` is passed to `handler` as is and must
+/// stay valid until `ripple_service_disconnect` returns.
+#[no_mangle]
+pub unsafe extern "C" fn ripple_service_connect(
+    service_id: *const c_char,
+    handler: RippleServiceHandler,
+    user_data: *mut c_void,
+) -> *mut RippleService {
+    let Some(service_id) = get_str(service_id).filter(|_| handler.is_some()) else {
+        return ptr::null_mut();
+    };
+    if ExtnId::try_from(service_id.to_owned()).is_err() {
+        error!("Invalid service id {}", service_id);
+        return ptr::null_mut();
+    }
+    let runtime = match Builder::new_multi_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            error!("Failed to start the service runtime: {:?}", e);
+            return ptr::null_mut();
+        }
+    };
+    let connection_handler = FfiConnectionHandler::default();
+    let registration = connection_handler.registration.clone();
+    let (client, ext_tr, service_tr) = ServiceClient::builder()
+        .with_extension(ExtnSymbol {
+            id: service_id.to_owned(),
+            uses: Vec::new(),
+            fulfills: Vec::new(),
+            config: None,
+            contract_versions: HashMap::new(),
+        })
+        .with_connection_handler(Arc::new(connection_handler))
+        .build();
+    let client_c = client.clone();
+    runtime.spawn(async move { client_c.initialize(ext_tr, service_tr).await });
+    Box::into_raw(Box::new(RippleService {
+        runtime,
+        client,
+        dispatcher: Arc::new(FfiDispatcher::new(handler, user_data)),
+        registration,
+        methods: Mutex::new(HashSet::new()),
+    }))
+}
+
+/// Registers the capabilities and methods of the service, `registration_json` is like
+/// `{"capabilities":["xrn:firebolt:capability:..."],"methods":["module.method"]}`. When
+/// `result_json` is not null it receives the registration result, which the caller frees
+/// with `ripple_service_free_string`.
+///
+/// # Safety
+/// `service` must come from `ripple_service_connect`, the strings must be valid C strings.
+#[no_mangle]
+pub unsafe extern "C" fn ripple_service_register(
+    service: *mut RippleService,
+    registration_json: *const c_char,
+    result_json: *mut *mut c_char,
+) -> i32 {
+    let (Some(service), Some(registration)) = (
+        service.as_ref(),
+        get_json(registration_json)
+            .and_then(|r| serde_json::from_value::<ServiceRegistrationParams>(r).ok()),
+    ) else {
+        return RIPPLE_SERVICE_ERROR_INVALID_ARGUMENT;
+    };
+    service.add_methods(&registration.methods);
+    *service.registration.lock().unwrap() = Some(registration.clone());
+    let mut client = service.client.clone();
+    match service.run(async move { client.register(registration).await }) {
+        Some(Ok(result)) => {
+            if !result_json.is_null() {
+                let result = serde_json::to_string(&result).unwrap_or_default();
+                *result_json = CString::new(result).map_or(ptr::null_mut(), CString::into_raw);
+            }
+            RIPPLE_SERVICE_OK
+        }
+        Some(Err(e)) => {
+            error!("Service registration failed: {:?}", e);
+            RIPPLE_SERVICE_ERROR_FAILED
+        }
+        None => RIPPLE_SERVICE_ERROR_FAILED,
+    }
+}
+
+/// Answers a request handed to the handler with its result
+///
+/// # Safety
+/// `service` must come from `ripple_service_connect`, `result_json` must be a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn ripple_service_respond(
+    service: *mut RippleService,
+    request_id: u64,
+    result_json: *const c_char,
+) -> i32 {
+    let (Some(service), Some(result)) = (service.as_ref(), get_json(result_json)) else {
+        return RIPPLE_SERVICE_ERROR_INVALID_ARGUMENT;
+    };
+    if service
+        .dispatcher
+        .respond(request_id, FfiResponse::Result(result))
+    {
+        RIPPLE_SERVICE_OK
+    } else {
+        RIPPLE_SERVICE_ERROR_UNKNOWN_REQUEST
+    }
+}
+
+/// Answers a request handed to the handler with a JSON-RPC error
+///
+/// # Safety
+/// `service` must come from `ripple_service_connect`, `message` must be a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn ripple_service_respond_error(
+    service: *mut RippleService,
+    request_id: u64,
+    code: i32,
+    message: *const c_char,
+) -> i32 {
+    let (Some(service), Some(message)) = (service.as_ref(), get_str(message)) else {
+        return RIPPLE_SERVICE_ERROR_INVALID_ARGUMENT;
+    };
+    if service
+        .dispatcher
+        .respond(request_id, FfiResponse::Error(code, message.to_owned()))
+    {
+        RIPPLE_SERVICE_OK
+    } else {
+        RIPPLE_SERVICE_ERROR_UNKNOWN_REQUEST
+    }
+}
+
+/// Emits a Firebolt event like `module.onSomethingChanged` to the listening apps, or only to
+/// `app_id` when it is not null
+///
+/// # Safety
+/// `service` must come from `ripple_service_connect`, the strings must be valid C strings.
+#[no_mangle]
+pub unsafe extern "C" fn ripple_service_emit_event(
+    service: *mut RippleService,
+    event: *const c_char,
+    result_json: *const c_char,
+    app_id: *const c_char,
+) -> i32 {
+    let (Some(service), Some(event), Some(result)) =
+        (service.as_ref(), get_str(event), get_json(result_json))
+    else {
+        return RIPPLE_SERVICE_ERROR_INVALID_ARGUMENT;
+    };
+    let event = ServiceEvent {
+        event: event.to_owned(),
+        result,
+        context: None,
+        app_id: get_str(app_id).map(str::to_owned),
+    };
+    match service.client.emit_event(event) {
+        Ok(_) => RIPPLE_SERVICE_OK,
+        Err(e) => {
+            warn!("Failed to emit service event: {:?}", e);
+            RIPPLE_SERVICE_ERROR_FAILED
+        }
+    }
+}
+
+/// Frees a string returned by this library
+///
+/// # Safety
+/// `value` must be null or a string returned by this library which was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn ripple_service_free_string(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+/// Closes the connection and frees the service. The handler is not called anymore once this
+/// returns, requests it did not answer are failed. Must not be called from the handler.
+///
+/// # Safety
+/// `service` must be null or come from `ripple_service_connect` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ripple_service_disconnect(service: *mut RippleService) {
+    if service.is_null() {
+        return;
+    }
+    let service = Box::from_raw(service);
+    service.dispatcher.detach();
+    let RippleService { runtime, .. } = *service;
+    runtime.shutdown_background();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{serde_json::json, tokio};
+    use std::time::Duration;
+
+    static CALLS: Mutex<Vec<(u64, String, Option<String>)>> = Mutex::new(Vec::new());
+
+    extern "C" fn record_call(
+        _user_data: *mut c_void,
+        request_id: u64,
+        method: *const c_char,
+        params_json: *const c_char,
+    ) {
+        unsafe {
+            CALLS.lock().unwrap().push((
+                request_id,
+                get_str(method).unwrap().to_owned(),
+                get_str(params_json).map(str::to_owned),
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_to_handler() {
+        let dispatcher = Arc::new(FfiDispatcher::new(Some(record_call), ptr::null_mut()));
+        let dispatcher_c = dispatcher.clone();
+        let call = tokio::spawn(async move {
+            dispatcher_c
+                .dispatch("module.method", Some(json!({"a": 1})))
+                .await
+        });
+        let request_id = loop {
+            if let Some(c) = CALLS.lock().unwrap().first().cloned() {
+                assert_eq!(c.1, "module.method");
+                assert_eq!(c.2, Some(r#"{"a":1}"#.to_owned()));
+                break c.0;
+            }
+            tokio::task::yield_now().await;
+        };
+        assert!(dispatcher.respond(request_id, FfiResponse::Result(json!(true))));
+        assert_eq!(call.await.unwrap().unwrap(), json!(true));
+        // a request is answered once
+        assert!(!dispatcher.respond(request_id, FfiResponse::Result(json!(true))));
+
+        // once detached the handler is not called and requests fail right away
+        dispatcher.detach();
+        assert!(dispatcher.dispatch("module.method", None).await.is_err());
+        assert_eq!(CALLS.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_run_from_runtime_worker() {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let (client, _, _) = ServiceClient::builder().build();
+        let service = Arc::new(RippleService {
+            runtime,
+            client,
+            dispatcher: Arc::default(),
+            registration: Arc::default(),
+            methods: Mutex::default(),
+        });
+        let service_c = service.clone();
+        let (tx, rx) = std_mpsc::channel();
+        // like the handler, which waits for a call of the client on the only worker
+        service.runtime.spawn(async move {
+            let result = service_c.run(async { 1 });
+            drop(service_c);
+            let _ = tx.send(result);
+        });
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        unsafe {
+            let id = CString::new("ripple:channel:device:daemon").unwrap();
+            assert!(ripple_service_connect(id.as_ptr(), None, ptr::null_mut()).is_null());
+            let id = CString::new("not a service id").unwrap();
+            assert!(
+                ripple_service_connect(id.as_ptr(), Some(record_call), ptr::null_mut()).is_null()
+            );
+            assert!(
+                ripple_service_connect(ptr::null(), Some(record_call), ptr::null_mut()).is_null()
+            );
+            assert_eq!(
+                ripple_service_register(ptr::null_mut(), ptr::null(), ptr::null_mut()),
+                RIPPLE_SERVICE_ERROR_INVALID_ARGUMENT
+            );
+            assert_eq!(
+                ripple_service_respond(ptr::null_mut(), 1, ptr::null()),
+                RIPPLE_SERVICE_ERROR_INVALID_ARGUMENT
+            );
+            ripple_service_disconnect(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+};
+use serde_json::Value;
+
+use crate::service_message::{Id, JsonRpcMessage, ServiceMessage, ServiceMessageParseError};
+
+/// Message received by a [ServiceProtocolClient], responses are matched with the request
+/// which the client sent for them.
+#[derive(Debug, Clone)]
+pub enum ServiceProtocolEvent {
+    /// Success or error for a request of this client, along with the method it called
+    Response {
+        method: String,
+        message: ServiceMessage,
+    },
+    /// Response for an id which this client is not waiting for
+    Unmatched(ServiceMessage),
+    Request(ServiceMessage),
+    Notification(ServiceMessage),
+}
+
+/// Client side of the service protocol without a transport. It allocates the request ids
+/// and correlates the responses, the caller moves the text over the websocket, HTTP or
+/// whatever channel it has, like a browser websocket in the web inspector.
+#[derive(Debug, Default)]
+pub struct ServiceProtocolClient {
+    next_id: i64,
+    pending: BTreeMap<i64, String>,
+}
+
+impl ServiceProtocolClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a request with the next id and remembers it until the response is accepted
+    pub fn request(&mut self, method: &str, params: Option<Value>) -> ServiceMessage {
+        self.next_id += 1;
+        self.pending.insert(self.next_id, method.to_string());
+        ServiceMessage::new_request(method.to_string(), params, Id::Number(self.next_id))
+    }
+
+    /// Parses a message received from the transport and matches responses with the
+    /// pending requests
+    pub fn accept(&mut self, text: &str) -> Result<ServiceProtocolEvent, ServiceMessageParseError> {
+        let message = ServiceMessage::try_from(text)?;
+        let id = match &message.message {
+            JsonRpcMessage::Request(_) => return Ok(ServiceProtocolEvent::Request(message)),
+            JsonRpcMessage::Notification(_) => {
+                return Ok(ServiceProtocolEvent::Notification(message))
+            }
+            JsonRpcMessage::Success(success) => success.id.get_number(),
+            JsonRpcMessage::Error(error) => error.id.get_number(),
+        };
+        match id.and_then(|id| self.pending.remove(&id)) {
+            Some(method) => Ok(ServiceProtocolEvent::Response { method, message }),
+            None => Ok(ServiceProtocolEvent::Unmatched(message)),
+        }
+    }
+
+    /// Number of requests still waiting for a response
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_protocol_client_correlates_responses() {
+        let mut client = ServiceProtocolClient::new();
+        let request = client.request("device.id", None);
+        assert_eq!(client.pending_count(), 1);
+
+        let response: String = ServiceMessage::new_success(
+            json!("device1"),
+            Id::Number(request.get_request_id() as i64),
+        )
+        .into();
+        match client.accept(&response).unwrap() {
+            ServiceProtocolEvent::Response { method, .. } => assert_eq!(method, "device.id"),
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert_eq!(client.pending_count(), 0);
+
+        // the same response again is not waited for anymore
+        assert!(matches!(
+            client.accept(&response).unwrap(),
+            ServiceProtocolEvent::Unmatched(_)
+        ));
+        let heartbeat: String = ServiceMessage::new_heartbeat().into();
+        assert!(matches!(
+            client.accept(&heartbeat).unwrap(),
+            ServiceProtocolEvent::Notification(_)
+        ));
+        assert!(client.accept("not json").is_err());
+    }
+}
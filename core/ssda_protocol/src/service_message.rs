@@ -0,0 +1,472 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Notification exchanged between Ripple and a connected service to prove liveness. Ripple
+/// sends it periodically and a service answers with the same notification.
+pub const SERVICE_HEARTBEAT_METHOD: &str = "service.heartbeat";
+/// Request sent by a service to stop receiving requests before it disconnects. Ripple Main
+/// sends it as a notification with the shutdown reason, asking the service to disconnect.
+pub const SERVICE_UNREGISTER_METHOD: &str = "service.unregister";
+/// Notification sent by a service to emit a Firebolt event to the app listeners
+pub const SERVICE_EMIT_EVENT_METHOD: &str = "service.emitEvent";
+/// Request sent by a service once it reconnected, Ripple Main replays or fails the requests
+/// which the previous connection left unanswered
+pub const SERVICE_RESYNC_METHOD: &str = "service.resync";
+/// Request sent by a service to declare the capabilities it provides and the Firebolt methods
+/// it serves
+pub const SERVICE_REGISTER_METHOD: &str = "service.register";
+/// Request sent by a registered service to start or stop serving Firebolt methods without
+/// registering again
+pub const SERVICE_UPDATE_REGISTRATION_METHOD: &str = "service.updateRegistration";
+/// Notification sent by Ripple Main to a service which did not get or lost methods because
+/// another service registered them
+pub const SERVICE_REGISTRATION_CONFLICT_METHOD: &str = "service.registrationConflict";
+
+/// Error of a text which is not a valid service message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceMessageParseError;
+
+impl core::fmt::Display for ServiceMessageParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "ServiceMessageParseError")
+    }
+}
+
+/// Firebolt event originated by a service, like `xvp.onSomethingChanged`. The event goes
+/// to the listeners of every app unless `app_id` is set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceEvent {
+    pub event: String,
+    pub result: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+}
+
+/// Parameters of the resync request, `replay` asks Ripple Main to send the unanswered requests
+/// again instead of failing them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceResyncParams {
+    pub replay: bool,
+}
+
+/// Number of unanswered requests which were replayed on the new connection or failed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceResyncResult {
+    pub replayed: usize,
+    pub failed: usize,
+}
+
+/// Capabilities provided by a service and the Firebolt methods it serves for them. A new
+/// registration replaces the methods of the previous one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceRegistrationParams {
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub methods: Vec<String>,
+}
+
+/// Capabilities granted and methods installed for a registration, along with the ones the
+/// capability policy of the device refused
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceRegistrationResult {
+    pub capabilities: Vec<String>,
+    pub methods: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rejected_capabilities: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rejected_methods: Vec<String>,
+}
+
+/// Methods a registered service starts or stops serving, like when a hardware feature appears
+/// or disappears. Added methods are checked against the capabilities granted by the last
+/// registration, the other methods of the service are kept.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceRegistrationUpdate {
+    #[serde(default)]
+    pub added: Vec<String>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+}
+
+/// Methods of a service which were not installed, or were taken over, under the method
+/// conflict policy of the device. Each method maps to the ServiceId serving it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceRegistrationConflict {
+    pub methods: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Id {
+    Number(i64),
+    String(String),
+    Null,
+}
+impl Id {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Id::Null)
+    }
+    pub fn get_number(&self) -> Option<i64> {
+        if let Id::Number(n) = self {
+            Some(*n)
+        } else {
+            None
+        }
+    }
+}
+
+/// Identifies a request from Ripple Main while a service is handling it. Numeric and
+/// string ids stay distinct, `1` and `"1"` are different requests.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServiceRequestId(String);
+
+impl From<&Id> for ServiceRequestId {
+    fn from(id: &Id) -> Self {
+        ServiceRequestId(serde_json::to_string(id).unwrap_or_default())
+    }
+}
+
+impl core::fmt::Display for ServiceRequestId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcRequest {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    pub id: Id,
+}
+// implment fmt for JsonRpcRequest
+impl core::fmt::Display for JsonRpcRequest {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "JsonRpcRequest {{ jsonrpc: {}, method: {}, params: {:?}, id: {:?} }}",
+            self.jsonrpc, self.method, self.params, self.id
+        )
+    }
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcSuccess {
+    pub jsonrpc: String,
+    pub result: Value,
+    pub id: Id,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcErrorDetails {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcError {
+    pub jsonrpc: String,
+    pub error: JsonRpcErrorDetails,
+    pub id: Id,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum JsonRpcMessage {
+    Request(JsonRpcRequest),
+    Notification(JsonRpcNotification),
+    Success(JsonRpcSuccess),
+    Error(JsonRpcError),
+}
+
+// convert JsonRpcMessage to String
+impl From<JsonRpcMessage> for String {
+    fn from(val: JsonRpcMessage) -> Self {
+        serde_json::to_string(&val).unwrap()
+    }
+}
+
+// set the id for JsonRpcMessage
+impl JsonRpcMessage {
+    pub fn set_id(&mut self, id: Id) {
+        match self {
+            JsonRpcMessage::Request(req) => req.id = id,
+            JsonRpcMessage::Notification(_) => {}
+            JsonRpcMessage::Success(success) => success.id = id,
+            JsonRpcMessage::Error(err) => err.id = id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceMessage {
+    // #[serde(flatten)] Enable this once we stop supporting ExtnMessage
+    pub message: JsonRpcMessage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<Value>,
+}
+
+// implement fmt for ServiceMessage
+impl core::fmt::Display for ServiceMessage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "ServiceMessage {{ message: {:?}, context: {:?} }}",
+            self.message, self.context
+        )
+    }
+}
+
+// Custom deserializer for ServiceMessage is required due to the flattern field
+// this helps to distinguish between request and notification messages
+// Enable this once we stop supporting ExtnMessage
+/*
+impl<'de> Deserialize<'de> for ServiceMessage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // parse the input into a map
+        let mut map: Map<String, Value> = Deserialize::deserialize(deserializer)?;
+
+        // extract the context if it exists
+        let context = map.remove("context");
+
+        let json_value = Value::Object(map.clone());
+
+        // extract the message type
+        let message = if map.contains_key("result") {
+            serde_json::from_value::<JsonRpcSuccess>(json_value).map(JsonRpcMessage::Success)
+        } else if map.contains_key("error") {
+            serde_json::from_value::<JsonRpcError>(json_value).map(JsonRpcMessage::Error)
+        } else if map.contains_key("id") {
+            serde_json::from_value::<JsonRpcRequest>(json_value).map(JsonRpcMessage::Request)
+        } else {
+            serde_json::from_value::<JsonRpcNotification>(json_value)
+                .map(JsonRpcMessage::Notification)
+        }
+        .map_err(|_| serde::de::Error::custom("Failed to parse JsonRpcMessage"))?;
+
+        Ok(ServiceMessage { message, context })
+    }
+}
+*/
+impl TryFrom<&str> for ServiceMessage {
+    type Error = ServiceMessageParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        serde_json::from_str(value).map_err(|_| ServiceMessageParseError)
+    }
+}
+
+impl From<ServiceMessage> for String {
+    fn from(val: ServiceMessage) -> Self {
+        serde_json::to_string(&val).unwrap()
+    }
+}
+
+impl ServiceMessage {
+    pub fn new_request(method: String, params: Option<Value>, id: Id) -> Self {
+        ServiceMessage {
+            message: JsonRpcMessage::Request(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method,
+                params,
+                id,
+            }),
+            context: None,
+        }
+    }
+
+    pub fn new_notification(method: String, params: Option<Value>) -> Self {
+        ServiceMessage {
+            message: JsonRpcMessage::Notification(JsonRpcNotification {
+                jsonrpc: "2.0".to_string(),
+                method,
+                params,
+            }),
+            context: None,
+        }
+    }
+
+    pub fn new_success(result: Value, id: Id) -> Self {
+        ServiceMessage {
+            message: JsonRpcMessage::Success(JsonRpcSuccess {
+                jsonrpc: "2.0".to_string(),
+                result,
+                id,
+            }),
+            context: None,
+        }
+    }
+
+    pub fn new_error(code: i64, message: String, data: Option<Value>, id: Id) -> Self {
+        ServiceMessage {
+            message: JsonRpcMessage::Error(JsonRpcError {
+                jsonrpc: "2.0".to_string(),
+                error: JsonRpcErrorDetails {
+                    code,
+                    message,
+                    data,
+                },
+                id,
+            }),
+            context: None,
+        }
+    }
+
+    pub fn new_heartbeat() -> Self {
+        Self::new_notification(SERVICE_HEARTBEAT_METHOD.to_string(), None)
+    }
+
+    pub fn new_event(event: ServiceEvent) -> Self {
+        Self::new_notification(
+            SERVICE_EMIT_EVENT_METHOD.to_string(),
+            serde_json::to_value(event).ok(),
+        )
+    }
+
+    pub fn new_registration_conflict(conflict: ServiceRegistrationConflict) -> Self {
+        Self::new_notification(
+            SERVICE_REGISTRATION_CONFLICT_METHOD.to_string(),
+            serde_json::to_value(conflict).ok(),
+        )
+    }
+
+    pub fn new_unregister(reason: String) -> Self {
+        Self::new_notification(
+            SERVICE_UNREGISTER_METHOD.to_string(),
+            Some(serde_json::json!({ "reason": reason })),
+        )
+    }
+
+    pub fn get_event(&self) -> Option<ServiceEvent> {
+        match &self.message {
+            JsonRpcMessage::Notification(n) if n.method == SERVICE_EMIT_EVENT_METHOD => n
+                .params
+                .clone()
+                .and_then(|p| serde_json::from_value(p).ok()),
+            _ => None,
+        }
+    }
+
+    pub fn is_heartbeat(&self) -> bool {
+        matches!(&self.message, JsonRpcMessage::Notification(n) if n.method == SERVICE_HEARTBEAT_METHOD)
+    }
+
+    pub fn is_unregister(&self) -> bool {
+        matches!(&self.message, JsonRpcMessage::Notification(n) if n.method == SERVICE_UNREGISTER_METHOD)
+    }
+
+    pub fn set_context(&mut self, context: Option<Value>) {
+        self.context = context;
+    }
+
+    // get the request id from the message
+    pub fn get_request_id(&self) -> u64 {
+        match &self.message {
+            JsonRpcMessage::Request(req) => {
+                if let Id::Number(id) = req.id {
+                    id as u64
+                } else {
+                    0
+                }
+            }
+            JsonRpcMessage::Notification(_) => 0,
+            JsonRpcMessage::Success(success) => {
+                if let Id::Number(id) = success.id {
+                    id as u64
+                } else {
+                    0
+                }
+            }
+            JsonRpcMessage::Error(err) => {
+                if let Id::Number(id) = err.id {
+                    id as u64
+                } else {
+                    0
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_service_event() {
+        let event = ServiceEvent {
+            event: "xvp.onSomethingChanged".into(),
+            result: json!({"changed": true}),
+            context: None,
+            app_id: Some("app1".into()),
+        };
+        let message: String = ServiceMessage::new_event(event.clone()).into();
+        let sm = ServiceMessage::try_from(message.as_str()).unwrap();
+        assert_eq!(sm.get_event(), Some(event));
+        assert!(ServiceMessage::new_heartbeat().get_event().is_none());
+    }
+
+    // runs for every feature set of the crate, including the alloc only build
+    #[test]
+    fn test_service_message_round_trip() {
+        let messages = [
+            ServiceMessage::new_request("device.id".into(), None, Id::Number(1)),
+            ServiceMessage::new_success(json!("device1"), Id::String("a".into())),
+            ServiceMessage::new_error(-32601, "Method not found".into(), None, Id::Number(2)),
+            ServiceMessage::new_unregister("shutdown".into()),
+            ServiceMessage::new_registration_conflict(ServiceRegistrationConflict {
+                methods: BTreeMap::from([("player.play".into(), "service1".into())]),
+            }),
+        ];
+        for message in messages {
+            let text: String = message.clone().into();
+            let parsed = ServiceMessage::try_from(text.as_str()).unwrap();
+            assert_eq!(parsed.get_request_id(), message.get_request_id());
+            assert_eq!(String::from(parsed), text);
+        }
+        assert_eq!(
+            ServiceMessage::try_from("{").unwrap_err(),
+            ServiceMessageParseError
+        );
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    protocol_client::{ServiceProtocolClient, ServiceProtocolEvent},
+    service_message::{Id, JsonRpcMessage, ServiceMessage},
+};
+
+/// Kind of a [WasmServiceMessage], JavaScript only gets C-style enums from wasm-bindgen
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceMessageKind {
+    Request,
+    Notification,
+    Success,
+    Error,
+}
+
+fn parse_json(text: Option<String>) -> Result<Option<Value>, JsError> {
+    text.map(|t| serde_json::from_str(&t))
+        .transpose()
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Service message for browser based tools, JSON values cross the boundary as text
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct WasmServiceMessage {
+    inner: ServiceMessage,
+}
+
+#[wasm_bindgen]
+impl WasmServiceMessage {
+    pub fn parse(text: &str) -> Result<WasmServiceMessage, JsError> {
+        ServiceMessage::try_from(text)
+            .map(|inner| WasmServiceMessage { inner })
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    pub fn request(
+        method: String,
+        params: Option<String>,
+        id: i32,
+    ) -> Result<WasmServiceMessage, JsError> {
+        Ok(WasmServiceMessage {
+            inner: ServiceMessage::new_request(method, parse_json(params)?, Id::Number(id.into())),
+        })
+    }
+
+    pub fn notification(
+        method: String,
+        params: Option<String>,
+    ) -> Result<WasmServiceMessage, JsError> {
+        Ok(WasmServiceMessage {
+            inner: ServiceMessage::new_notification(method, parse_json(params)?),
+        })
+    }
+
+    pub fn heartbeat() -> WasmServiceMessage {
+        WasmServiceMessage {
+            inner: ServiceMessage::new_heartbeat(),
+        }
+    }
+
+    pub fn kind(&self) -> ServiceMessageKind {
+        match &self.inner.message {
+            JsonRpcMessage::Request(_) => ServiceMessageKind::Request,
+            JsonRpcMessage::Notification(_) => ServiceMessageKind::Notification,
+            JsonRpcMessage::Success(_) => ServiceMessageKind::Success,
+            JsonRpcMessage::Error(_) => ServiceMessageKind::Error,
+        }
+    }
+
+    /// Method of a request or notification
+    pub fn method(&self) -> Option<String> {
+        match &self.inner.message {
+            JsonRpcMessage::Request(r) => Some(r.method.clone()),
+            JsonRpcMessage::Notification(n) => Some(n.method.clone()),
+            _ => None,
+        }
+    }
+
+    /// Params of a request or notification, or the result of a success, as JSON text
+    pub fn payload(&self) -> Option<String> {
+        let value = match &self.inner.message {
+            JsonRpcMessage::Request(r) => r.params.as_ref(),
+            JsonRpcMessage::Notification(n) => n.params.as_ref(),
+            JsonRpcMessage::Success(s) => Some(&s.result),
+            JsonRpcMessage::Error(e) => e.error.data.as_ref(),
+        };
+        value.map(|v| v.to_string())
+    }
+
+    pub fn error_code(&self) -> Option<f64> {
+        match &self.inner.message {
+            JsonRpcMessage::Error(e) => Some(e.error.code as f64),
+            _ => None,
+        }
+    }
+
+    pub fn error_message(&self) -> Option<String> {
+        match &self.inner.message {
+            JsonRpcMessage::Error(e) => Some(e.error.message.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        self.inner.clone().into()
+    }
+}
+
+/// Thin client for browser based tools, the page owns the websocket and passes the text of
+/// every message through this client to correlate the responses
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct WasmServiceClient {
+    inner: ServiceProtocolClient,
+}
+
+#[wasm_bindgen]
+impl WasmServiceClient {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmServiceClient {
+        WasmServiceClient::default()
+    }
+
+    /// Returns the text of the request to send over the websocket
+    pub fn request(&mut self, method: &str, params: Option<String>) -> Result<String, JsError> {
+        Ok(self.inner.request(method, parse_json(params)?).into())
+    }
+
+    /// Parses a received message, responses carry the method of the request they answer
+    pub fn accept(&mut self, text: &str) -> Result<WasmServiceEvent, JsError> {
+        let event = self
+            .inner
+            .accept(text)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(match event {
+            ServiceProtocolEvent::Response { method, message } => WasmServiceEvent {
+                method: Some(method),
+                message: WasmServiceMessage { inner: message },
+            },
+            ServiceProtocolEvent::Unmatched(message)
+            | ServiceProtocolEvent::Request(message)
+            | ServiceProtocolEvent::Notification(message) => WasmServiceEvent {
+                method: None,
+                message: WasmServiceMessage { inner: message },
+            },
+        })
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.inner.pending_count()
+    }
+}
+
+/// Message accepted by a [WasmServiceClient], `request_method` is set for the responses of
+/// its own requests
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct WasmServiceEvent {
+    method: Option<String>,
+    message: WasmServiceMessage,
+}
+
+#[wasm_bindgen]
+impl WasmServiceEvent {
+    pub fn request_method(&self) -> Option<String> {
+        self.method.clone()
+    }
+
+    pub fn message(&self) -> WasmServiceMessage {
+        self.message.clone()
+    }
+}
@@ -100,6 +100,8 @@ pub enum LoadMockDataError {
     MockDataNotValidJson,
     MockDataNotArray,
     MockDataError(MockDataError),
+    ProfileNotValidJson(PathBuf),
+    ProfileNotFound(String),
 }
 
 impl Display for LoadMockDataError {
@@ -115,6 +117,15 @@ impl Display for LoadMockDataError {
             Self::MockDataError(err) => {
                 format!("Failed to parse message in mock data. Error: {err:?}")
             }
+            Self::ProfileNotValidJson(path) => {
+                format!(
+                    "The device profile is not valid JSON. File: {}",
+                    path.display()
+                )
+            }
+            Self::ProfileNotFound(name) => {
+                format!("The device profile was not found. Name: {name}")
+            }
         };
 
         f.write_str(msg.as_str())
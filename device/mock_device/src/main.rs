@@ -3,6 +3,7 @@ pub mod mock_config;
 pub mod mock_data;
 pub mod mock_device_controller;
 pub mod mock_device_ffi;
+pub mod mock_profile;
 pub mod mock_server;
 pub mod mock_web_socket_server;
 
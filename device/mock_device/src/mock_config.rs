@@ -21,6 +21,8 @@ use serde::Deserialize;
 pub struct MockConfig {
     pub activate_all_plugins: bool,
     pub stats_file: String,
+    pub profiles_dir: Option<String>,
+    pub device_profile: Option<String>,
 }
 
 impl Default for MockConfig {
@@ -28,6 +30,8 @@ impl Default for MockConfig {
         Self {
             activate_all_plugins: true,
             stats_file: "stats.json".to_string(),
+            profiles_dir: None,
+            device_profile: None,
         }
     }
 }
@@ -21,7 +21,8 @@ use crate::{
     mock_data::MockData,
     mock_data::MockDeviceState,
     mock_server::{
-        AddRequestResponseResponse, EmitEventParams, EmitEventResponse, RemoveRequestResponse,
+        AddRequestResponseResponse, EmitEventParams, EmitEventResponse, GetProfilesResponse,
+        RemoveRequestResponse, SetProfileParams, SetProfileResponse,
     },
 };
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
@@ -79,6 +80,16 @@ pub trait MockDeviceController {
         ctx: CallContext,
         req: MockData,
     ) -> RpcResult<ExtnProviderResponse>;
+
+    #[method(name = "mockdevice.getProfiles")]
+    async fn get_profiles(&self, ctx: CallContext) -> RpcResult<ExtnProviderResponse>;
+
+    #[method(name = "mockdevice.setProfile")]
+    async fn set_profile(
+        &self,
+        ctx: CallContext,
+        req: SetProfileParams,
+    ) -> RpcResult<ExtnProviderResponse>;
 }
 
 pub struct MockDeviceController {
@@ -158,4 +169,33 @@ impl MockDeviceControllerServer for MockDeviceController {
             value: serde_json::to_value(EmitEventResponse { success: true }).unwrap(),
         })
     }
+
+    async fn get_profiles(&self, _ctx: CallContext) -> RpcResult<ExtnProviderResponse> {
+        Ok(ExtnProviderResponse {
+            value: serde_json::to_value(GetProfilesResponse {
+                profiles: self.state.server.get_profiles(),
+                active: self.state.server.get_active_profile(),
+            })
+            .unwrap(),
+        })
+    }
+
+    async fn set_profile(
+        &self,
+        _ctx: CallContext,
+        req: SetProfileParams,
+    ) -> RpcResult<ExtnProviderResponse> {
+        if self.state.server.apply_profile(&req.name).is_err() {
+            return Err(rpc_err(MockDeviceControllerError::RequestFailed(
+                RippleError::InvalidInput,
+            )));
+        }
+        Ok(ExtnProviderResponse {
+            value: serde_json::to_value(SetProfileResponse {
+                success: true,
+                error: None,
+            })
+            .unwrap(),
+        })
+    }
 }
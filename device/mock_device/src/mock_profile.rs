@@ -0,0 +1,190 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
+
+use ripple_sdk::log::{debug, error};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    errors::LoadMockDataError,
+    mock_data::{MockData, ParamResponse},
+};
+
+const HDR_FLAGS: &[(&str, u32)] = &[
+    ("hdr10", 0x01),
+    ("hlg", 0x02),
+    ("dolbyVision", 0x04),
+    ("technicolor", 0x08),
+    ("hdr10plus", 0x10),
+];
+
+/// A virtual device configuration layered over the base mock data.
+///
+/// The capability fields are turned into the platform responses Ripple reads for them, any
+/// entries in `mock_data` are applied last so a profile can override or extend them.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceProfile {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub hdr: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+    #[serde(default)]
+    pub audio_formats: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<DeviceProfileRegion>,
+    #[serde(default, skip_serializing)]
+    pub mock_data: MockData,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceProfileRegion {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+}
+
+fn result_response(result: serde_json::Value) -> Vec<ParamResponse> {
+    vec![ParamResponse {
+        params: None,
+        result: Some(result),
+        error: None,
+        events: None,
+    }]
+}
+
+impl DeviceProfile {
+    pub fn get_mock_data(&self) -> MockData {
+        let mut mock_data = MockData::new();
+        if !self.hdr.is_empty() {
+            let capabilities = HDR_FLAGS
+                .iter()
+                .filter(|(name, _)| self.hdr.iter().any(|h| h.eq_ignore_ascii_case(name)))
+                .fold(0, |acc, (_, flag)| acc | flag);
+            mock_data.insert(
+                "org.rdk.DisplaySettings.1.getTVHDRCapabilities".into(),
+                result_response(json!({"capabilities": capabilities, "success": true})),
+            );
+        }
+        if let Some(resolution) = &self.resolution {
+            mock_data.insert(
+                "org.rdk.DisplaySettings.1.getCurrentResolution".into(),
+                result_response(json!({"resolution": resolution, "success": true})),
+            );
+        }
+        if !self.audio_formats.is_empty() {
+            mock_data.insert(
+                "org.rdk.DisplaySettings.1.getAudioFormat".into(),
+                result_response(
+                    json!({"supportedAudioFormat": self.audio_formats, "success": true}),
+                ),
+            );
+        }
+        if let Some(region) = &self.region {
+            if let Some(time_zone) = &region.time_zone {
+                mock_data.insert(
+                    "org.rdk.System.1.getTimeZoneDST".into(),
+                    result_response(json!({"timeZone": time_zone, "success": true})),
+                );
+            }
+        }
+        mock_data.extend(self.mock_data.clone());
+        mock_data
+    }
+}
+
+/// Loads every `.json` file in the directory as a [DeviceProfile], keyed by profile name.
+pub fn load_device_profiles(
+    dir: &Path,
+) -> Result<HashMap<String, DeviceProfile>, LoadMockDataError> {
+    if !dir.is_dir() {
+        return Err(LoadMockDataError::PathDoesNotExist(dir.to_path_buf()));
+    }
+    let entries = std::fs::read_dir(dir).map_err(|e| {
+        error!("Failed to read profiles dir {e:?}");
+        LoadMockDataError::FileOpenFailed(dir.to_path_buf())
+    })?;
+
+    let mut profiles = HashMap::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() || path.extension().is_none_or(|e| e != "json") {
+            continue;
+        }
+        let file = File::open(&path).map_err(|e| {
+            error!("Failed to open device profile {e:?}");
+            LoadMockDataError::FileOpenFailed(path.clone())
+        })?;
+        let profile: DeviceProfile = serde_json::from_reader(BufReader::new(file))
+            .map_err(|_| LoadMockDataError::ProfileNotValidJson(path.clone()))?;
+        debug!("loaded device profile {} from {:?}", profile.name, path);
+        profiles.insert(profile.name.clone(), profile);
+    }
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_mock_data() {
+        let profile: DeviceProfile = serde_json::from_value(json!({
+            "name": "uhd-eu",
+            "hdr": ["hdr10", "dolbyVision"],
+            "resolution": "2160p",
+            "audioFormats": ["PCM", "DOLBY AC4"],
+            "region": {"timeZone": "Europe/London"},
+            "mockData": {
+                "org.rdk.DisplaySettings.1.getCurrentResolution": [
+                    {"result": {"resolution": "1080p", "success": true}}
+                ]
+            }
+        }))
+        .unwrap();
+
+        let mock_data = profile.get_mock_data();
+        let hdr = mock_data
+            .get("org.rdk.DisplaySettings.1.getTVHDRCapabilities")
+            .unwrap();
+        assert_eq!(hdr[0].result.as_ref().unwrap()["capabilities"], json!(5));
+        let time_zone = mock_data.get("org.rdk.System.1.getTimeZoneDST").unwrap();
+        assert_eq!(
+            time_zone[0].result.as_ref().unwrap()["timeZone"],
+            json!("Europe/London")
+        );
+        // explicit mock data wins over the generated responses
+        let resolution = mock_data
+            .get("org.rdk.DisplaySettings.1.getCurrentResolution")
+            .unwrap();
+        assert_eq!(
+            resolution[0].result.as_ref().unwrap()["resolution"],
+            json!("1080p")
+        );
+        let audio = mock_data
+            .get("org.rdk.DisplaySettings.1.getAudioFormat")
+            .unwrap();
+        assert_eq!(
+            audio[0].result.as_ref().unwrap()["supportedAudioFormat"],
+            json!(["PCM", "DOLBY AC4"])
+        );
+    }
+}
@@ -20,7 +20,7 @@ use std::fmt::Display;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::mock_data::MockData;
+use crate::{mock_data::MockData, mock_profile::DeviceProfile};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum PayloadTypeError {
@@ -139,6 +139,23 @@ pub struct EmitEventResponse {
     pub success: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetProfileParams {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SetProfileResponse {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetProfilesResponse {
+    pub profiles: Vec<DeviceProfile>,
+    pub active: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,9 +42,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use crate::{
-    errors::MockServerWebSocketError,
+    errors::{LoadMockDataError, MockServerWebSocketError},
     mock_config::MockConfig,
     mock_data::{MockData, MockDataError, ParamResponse, ResponseSink},
+    mock_profile::DeviceProfile,
     utils::is_value_jsonrpc,
 };
 
@@ -107,6 +108,9 @@ type WSConnection = Arc<Mutex<HashMap<String, SplitSink<WebSocketStream<TcpStrea
 #[derive(Debug)]
 pub struct MockWebSocketServer {
     mock_data_v2: Arc<RwLock<MockData>>,
+    initial_mock_data: MockData,
+    profiles: HashMap<String, DeviceProfile>,
+    active_profile: RwLock<Option<String>>,
     listener: TcpListener,
     conn_path: String,
     conn_headers: HeaderMap,
@@ -194,6 +198,10 @@ impl MockWebSocketServer {
             .port();
         let (stats_tx, stats_rx) = tokio::sync::mpsc::channel(10);
         tokio::spawn(StatsCollector::new(stats_rx, config.clone().stats_file).start());
+        let initial_mock_data: MockData = mock_data_v2
+            .into_iter()
+            .map(|(k, v)| (k.to_lowercase(), v))
+            .collect();
 
         Ok(Self {
             listener,
@@ -203,16 +211,47 @@ impl MockWebSocketServer {
             conn_query_params: server_config.query_params.unwrap_or_default(),
             connected_peer_sinks: Arc::new(Mutex::new(HashMap::new())),
             config,
-            mock_data_v2: Arc::new(RwLock::new(
-                mock_data_v2
-                    .into_iter()
-                    .map(|(k, v)| (k.to_lowercase(), v))
-                    .collect(),
-            )),
+            mock_data_v2: Arc::new(RwLock::new(initial_mock_data.clone())),
+            initial_mock_data,
+            profiles: HashMap::new(),
+            active_profile: RwLock::new(None),
             stats_channel: stats_tx,
         })
     }
 
+    pub fn with_profiles(mut self, profiles: HashMap<String, DeviceProfile>) -> Self {
+        self.profiles = profiles;
+        self
+    }
+
+    pub fn get_profiles(&self) -> Vec<DeviceProfile> {
+        self.profiles.values().cloned().collect()
+    }
+
+    pub fn get_active_profile(&self) -> Option<String> {
+        self.active_profile.read().unwrap().clone()
+    }
+
+    /// Resets the mock data to the initial mock data file and layers the profile on top.
+    /// Any mocks added at runtime are dropped so each profile starts from the same baseline.
+    pub fn apply_profile(&self, name: &str) -> Result<(), LoadMockDataError> {
+        let profile = self
+            .profiles
+            .get(name)
+            .ok_or_else(|| LoadMockDataError::ProfileNotFound(name.to_owned()))?;
+        let mut mock_data = self.initial_mock_data.clone();
+        mock_data.extend(
+            profile
+                .get_mock_data()
+                .into_iter()
+                .map(|(k, v)| (k.to_lowercase(), v)),
+        );
+        *self.mock_data_v2.write().unwrap() = mock_data;
+        *self.active_profile.write().unwrap() = Some(name.to_owned());
+        info!("Applied mock device profile {}", name);
+        Ok(())
+    }
+
     pub fn port(&self) -> u16 {
         self.port
     }
@@ -677,4 +716,36 @@ mod tests {
         );
         assert_eq!(&response, &expected);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_apply_profile() {
+        let mock_data = get_mock_data(json!({
+            "org.rdk.System.1.getSystemVersions": [{"result": {"success": true}}]
+        }));
+        let profile: DeviceProfile = serde_json::from_value(json!({
+            "name": "hd",
+            "resolution": "1080p"
+        }))
+        .unwrap();
+        let server =
+            MockWebSocketServer::new(mock_data, WsServerParameters::new(), MockConfig::default())
+                .await
+                .expect("Unable to start server")
+                .with_profiles(HashMap::from([("hd".to_owned(), profile)]));
+
+        let runtime_mock = get_mock_data(json!({
+            "org.rdk.System.1.getTimeZoneDST": [{"result": {"timeZone": "UTC"}}]
+        }));
+        server.add_request_response_v2(runtime_mock).await.unwrap();
+
+        assert!(server.apply_profile("sd").is_err());
+        assert!(server.get_active_profile().is_none());
+        assert!(server.apply_profile("hd").is_ok());
+        assert_eq!(server.get_active_profile(), Some("hd".to_owned()));
+
+        let mock_data = server.mock_data_v2.read().unwrap();
+        assert!(mock_data.contains_key("org.rdk.system.1.getsystemversions"));
+        assert!(mock_data.contains_key("org.rdk.displaysettings.1.getcurrentresolution"));
+        assert!(!mock_data.contains_key("org.rdk.system.1.gettimezonedst"));
+    }
 }
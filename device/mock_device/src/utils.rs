@@ -15,7 +15,7 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use std::{fs::File, io::BufReader, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, fs::File, io::BufReader, path::PathBuf, sync::Arc};
 
 use ripple_sdk::{
     api::config::Config,
@@ -31,6 +31,7 @@ use crate::{
     errors::{BootFailedError, LoadMockDataError, MockDeviceError},
     mock_config::MockConfig,
     mock_data::MockData,
+    mock_profile::{load_device_profiles, DeviceProfile},
     mock_web_socket_server::{MockWebSocketServer, WsServerParameters},
 };
 
@@ -52,12 +53,18 @@ pub async fn boot_ws_server(
 
     let mut server_config = WsServerParameters::new();
     let mock_data_v2 = load_mock_data_v2(client.clone()).await?;
+    let profiles = load_profiles(client.clone(), &config).await?;
+    let device_profile = config.device_profile.clone();
     server_config
         .port(gateway.port().unwrap_or(0))
         .path(gateway.path());
     let ws_server = MockWebSocketServer::new(mock_data_v2, server_config, config)
         .await
-        .map_err(BootFailedError::ServerStartFailed)?;
+        .map_err(BootFailedError::ServerStartFailed)?
+        .with_profiles(profiles);
+    if let Some(name) = device_profile {
+        ws_server.apply_profile(&name)?;
+    }
 
     let ws_server = Arc::new(ws_server);
     let server = ws_server.clone();
@@ -94,10 +101,19 @@ fn is_valid_host(host: Option<Host<&str>>) -> bool {
     }
 }
 
-async fn find_mock_device_data_file(mut client: ExtnClient) -> Result<PathBuf, MockDeviceError> {
+async fn find_mock_device_data_file(client: ExtnClient) -> Result<PathBuf, MockDeviceError> {
     let file = client
         .get_config("mock_data_file")
         .unwrap_or("mock-device.json".to_owned());
+    resolve_saved_dir_path(client, file).await
+}
+
+/// Relative paths in the extension config are resolved against the `saved_dir` from the
+/// device manifest.
+async fn resolve_saved_dir_path(
+    mut client: ExtnClient,
+    file: String,
+) -> Result<PathBuf, MockDeviceError> {
     let path = PathBuf::from(file);
 
     debug!(
@@ -142,9 +158,23 @@ pub fn load_config(client: &ExtnClient) -> MockConfig {
     if let Some(c) = client.get_config("activate_all_plugins") {
         config.activate_all_plugins = c.parse::<bool>().unwrap_or(false);
     }
+    config.profiles_dir = client.get_config("profiles_dir");
+    config.device_profile = client.get_config("device_profile");
     config
 }
 
+pub async fn load_profiles(
+    client: ExtnClient,
+    config: &MockConfig,
+) -> Result<HashMap<String, DeviceProfile>, MockDeviceError> {
+    let Some(dir) = config.profiles_dir.clone() else {
+        return Ok(HashMap::new());
+    };
+    let path = resolve_saved_dir_path(client, dir).await?;
+    debug!("profiles path={:?}", path);
+    Ok(load_device_profiles(&path)?)
+}
+
 pub async fn load_mock_data_v2(client: ExtnClient) -> Result<MockData, MockDeviceError> {
     let path = find_mock_device_data_file(client).await?;
     debug!("path={:?}", path);
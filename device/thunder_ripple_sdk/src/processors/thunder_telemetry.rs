@@ -44,6 +44,8 @@ use crate::{
 pub struct ThunderTelemetryEvent {
     event_name: String,
     event_value: String,
+    /// Version of the event in the telemetry schema registry
+    schema_version: u32,
 }
 impl From<ThunderTelemetryEvent> for DeviceChannelParams {
     fn from(event: ThunderTelemetryEvent) -> Self {
@@ -77,10 +79,11 @@ fn render_event_data(event: &TelemetryPayload) -> Result<String, RippleError> {
     serialize(event)
 }
 
-fn telemetry_event(event_name: &str, event_payload: String) -> DeviceChannelParams {
+fn telemetry_event(event: &TelemetryPayload, event_payload: String) -> DeviceChannelParams {
     ThunderTelemetryEvent {
-        event_name: String::from(event_name),
+        event_name: String::from(get_event_name(event)),
         event_value: event_payload,
+        schema_version: event.get_schema().version,
     }
     .into()
 }
@@ -97,6 +100,15 @@ fn get_event_name(event: &TelemetryPayload) -> &'static str {
         TelemetryPayload::InternalInitialize(_) => "app_internal_initialize_split",
         TelemetryPayload::FireboltInteraction(_) => "app_firebolt_split",
         TelemetryPayload::FireboltEvent(_) => "app_firebolt_event_split",
+        TelemetryPayload::DeviceHealth(_) => "ripple_device_health_split",
+        TelemetryPayload::BootMilestone(_) => "ripple_boot_milestone_split",
+        TelemetryPayload::AppDiagnostic(_) => "app_diagnostic_split",
+        TelemetryPayload::RelaxedCapabilityCall(_) => "app_relaxed_capability_split",
+        TelemetryPayload::ErrorBudgetExceeded(_) => "app_error_budget_exceeded_split",
+        TelemetryPayload::StoreRepair(_) => "ripple_store_repair_split",
+        TelemetryPayload::ServiceConnectionEvent(_) => "ripple_service_connection_split",
+        TelemetryPayload::ConnectionRejected(_) => "ripple_connection_rejected_split",
+        TelemetryPayload::CrashBreadcrumb(_) => "ripple_crash_breadcrumb_split",
     }
 }
 
@@ -165,7 +177,7 @@ impl ExtnEventProcessor for ThunderTelemetryProcessor {
                 .get_thunder_client()
                 .call(DeviceCallRequest {
                     method: ThunderPlugin::Telemetry.unversioned_method("logApplicationEvent"),
-                    params: Some(telemetry_event(get_event_name(&extracted_message), data)),
+                    params: Some(telemetry_event(&extracted_message, data)),
                 })
                 .await;
         }